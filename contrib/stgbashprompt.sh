@@ -1,5 +1,8 @@
 # modify PS1 to your preference and include this file in your bashrc
 # or copy to /etc/bash_completions.d.
+#
+# See also `stg completion prompt` for a prompt function showing the
+# patch position and conflicts.
 
 if [ "$PS1" ]; then
 	function __prompt_git()
@@ -28,6 +28,21 @@ pub(crate) fn keep_arg() -> Arg {
         .action(clap::ArgAction::SetTrue)
 }
 
+/// The `--force-dirty` option.
+pub(crate) fn force_dirty_arg() -> Arg {
+    Arg::new("force-dirty")
+        .long("force-dirty")
+        .help("Proceed with local changes to files not touched by the patches")
+        .long_help(
+            "Proceed despite local changes in the index or work tree as long as \
+             none of the changed files are touched by the patches being pushed or \
+             popped. Unlike '--keep', the files with local changes are checked \
+             against the files touched by the patches before any changes are made.",
+        )
+        .action(clap::ArgAction::SetTrue)
+        .conflicts_with("keep")
+}
+
 /// The `--merged` option checking for already-merged patches before pushes.
 pub(crate) fn merged_arg() -> Arg {
     Arg::new("merged")
@@ -73,11 +88,23 @@ pub(crate) fn committer_date_is_author_date_arg() -> clap::Arg {
         .help("Use author date as committer date")
         .long_help(
             "Instead of using the current time as the committer date, use the author \
-             date of the commit as the committer date.",
+             date of the commit as the committer date. This takes precedence over \
+             the `stgit.keepcommitterdate` configuration variable.",
         )
         .action(clap::ArgAction::SetTrue)
 }
 
+/// The `--label` option for selecting patches having a label.
+pub(crate) fn label_arg() -> Arg {
+    Arg::new("label")
+        .long("label")
+        .help("Select patches labeled <label>")
+        .num_args(1)
+        .value_name("label")
+        .value_hint(clap::ValueHint::Other)
+        .value_parser(parse_label)
+}
+
 /// The `--diff-opt`/`-O` option for pass-through to subordinate `git` processes.
 pub(crate) fn diff_opts_arg() -> Arg {
     Arg::new("git-diff-opt")
@@ -98,6 +125,103 @@ pub(crate) fn diff_opts_arg() -> Arg {
         .value_hint(clap::ValueHint::Other)
 }
 
+/// The `--find-renames`/`-M` option for detecting renames in diffs.
+pub(crate) fn find_renames_arg() -> Arg {
+    Arg::new("find-renames")
+        .long("find-renames")
+        .short('M')
+        .help("Detect renames, optionally with similarity threshold <n>")
+        .long_help(
+            "Detect renames in the generated diff. If <n> is specified, it is a \
+             threshold on the similarity index, as with the '--find-renames' option \
+             of git-diff(1).",
+        )
+        .num_args(0..=1)
+        .default_missing_value("")
+        .require_equals(true)
+        .value_name("n")
+        .value_hint(clap::ValueHint::Other)
+}
+
+/// The `--find-copies`/`-C` option for detecting copies in diffs.
+pub(crate) fn find_copies_arg() -> Arg {
+    Arg::new("find-copies")
+        .long("find-copies")
+        .short('C')
+        .help("Detect copies and renames, optionally with threshold <n>")
+        .long_help(
+            "Detect copies as well as renames in the generated diff. If <n> is \
+             specified, it has the same meaning as for '--find-renames'.",
+        )
+        .num_args(0..=1)
+        .default_missing_value("")
+        .require_equals(true)
+        .value_name("n")
+        .value_hint(clap::ValueHint::Other)
+}
+
+/// The `--word-diff` option for showing word-level changes in diffs.
+pub(crate) fn word_diff_arg() -> Arg {
+    Arg::new("word-diff")
+        .long("word-diff")
+        .help("Show a word diff, using <mode> to delimit changed words")
+        .long_help(
+            "Show a word diff, using <mode> to delimit changed words. The mode is \
+             one of \"color\", \"plain\" (the default), \"porcelain\", or \"none\". \
+             Words are delimited by whitespace unless the `diff.wordRegex` \
+             configuration variable is set. See the '--word-diff' option of \
+             git-diff(1).",
+        )
+        .num_args(0..=1)
+        .default_missing_value("")
+        .require_equals(true)
+        .value_name("mode")
+        .value_parser(["color", "plain", "porcelain", "none", ""])
+        .hide_possible_values(true)
+}
+
+/// The `--color-moved` option for coloring moved lines in diffs.
+pub(crate) fn color_moved_arg() -> Arg {
+    Arg::new("color-moved")
+        .long("color-moved")
+        .help("Color moved lines differently, using <mode>")
+        .long_help(
+            "Color moved lines of code differently from added and removed lines. \
+             The mode is one of \"no\", \"default\", \"plain\", \"blocks\", \
+             \"zebra\", or \"dimmed-zebra\". Without this option, the `diff.colorMoved` \
+             configuration variable is honored. See the '--color-moved' option of \
+             git-diff(1).",
+        )
+        .num_args(0..=1)
+        .default_missing_value("")
+        .require_equals(true)
+        .value_name("mode")
+        .value_parser([
+            "no",
+            "default",
+            "plain",
+            "blocks",
+            "zebra",
+            "dimmed-zebra",
+            "",
+        ])
+        .hide_possible_values(true)
+}
+
+/// The `--stash-discarded` option for use with `--hard`.
+pub(crate) fn stash_discarded_arg() -> Arg {
+    Arg::new("stash-discarded")
+        .long("stash-discarded")
+        .help("Stash the changes discarded by --hard")
+        .long_help(
+            "Save the index and worktree changes discarded by '--hard' as a new git \
+             stash entry before discarding them. The discarded changes may then be \
+             recovered with `git stash apply`.",
+        )
+        .requires("hard")
+        .action(clap::ArgAction::SetTrue)
+}
+
 /// Get a `&str` from a `clap::ArgMatches` instance for the given `id`.
 ///
 /// This function may be cleaner than calling `ArgMatches::get_one::<String>()` directly
@@ -116,14 +240,30 @@ pub(crate) fn parse_usize(s: &str) -> anyhow::Result<usize> {
         .map_err(|_| anyhow::anyhow!("'{s}' is not a positive integer"))
 }
 
+/// Validate patch label.
+pub(crate) fn parse_label(label: &str) -> anyhow::Result<String> {
+    if label.is_empty() {
+        Err(anyhow::anyhow!("label may not be empty"))
+    } else if let Some(c) = label
+        .chars()
+        .find(|c| !(c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '/')))
+    {
+        Err(anyhow::anyhow!("invalid character `{c}` in label"))
+    } else {
+        Ok(label.to_string())
+    }
+}
+
 /// Compose aggregate set of git diff options from various sources.
 ///
 /// These options are meant to be passed to various subordinate `git` commands that take
 /// diff options.
 ///
 /// The base set of options come from `stgit.diff-opts` in the config. Additional
-/// options from `--diff-opt`/`-O` command line options are appended. And StGit
-/// command-specific policies for displaying the full object id (`--full-index`) and
+/// options from `--diff-opt`/`-O` command line options are appended, followed by any
+/// `--find-renames`/`-M`, `--find-copies`/`-C`, `--word-diff`, and `--color-moved`
+/// options. And StGit command-specific
+/// policies for displaying the full object id (`--full-index`) and
 /// including binary diffs (`--binary`) are tacked on at the end.
 ///
 /// The returned `Vec<String>` is appropriate for inserting directly into the command
@@ -148,6 +288,16 @@ pub(crate) fn get_diff_opts(
         opts.extend(values.cloned());
     }
 
+    for id in ["find-renames", "find-copies", "word-diff", "color-moved"] {
+        if let Some(value) = matches.try_get_one::<String>(id).ok().flatten() {
+            if value.is_empty() {
+                opts.push(format!("--{id}"));
+            } else {
+                opts.push(format!("--{id}={value}"));
+            }
+        }
+    }
+
     if force_full_index {
         opts.push(String::from("--full-index"));
     }
@@ -167,3 +317,45 @@ pub(crate) fn resolve_allow_push_conflicts(
         .map(|s| s == "allow")
         .unwrap_or_else(|| config.boolean("stgit.push.allow-conflicts").unwrap_or(true))
 }
+
+/// Resolve the stack log annotation for a command.
+///
+/// The annotation is taken from the `--annotate` option or, failing that, the
+/// `stgit.<command>.annotate` configuration variable. The `%(patchname)s`,
+/// `%(branch)s`, `%(date)s`, and `%(version)s` placeholders in the annotation are
+/// replaced with the patch name, the branch name, the committer date, and the StGit
+/// version, respectively. Unknown placeholders are left as-is.
+pub(crate) fn resolve_annotation(
+    repo: &gix::Repository,
+    matches: &clap::ArgMatches,
+    command: &str,
+    patchname: &str,
+    branch_name: &str,
+) -> anyhow::Result<Option<String>> {
+    use std::{borrow::Cow, collections::HashMap};
+
+    use crate::ext::RepositoryExtended;
+
+    let annotation = if let Some(annotation) = get_one_str(matches, "annotate") {
+        annotation.to_string()
+    } else if let Some(annotation) = repo
+        .config_snapshot()
+        .string(format!("stgit.{command}.annotate").as_str())
+    {
+        annotation.to_str_lossy().into_owned()
+    } else {
+        return Ok(None);
+    };
+
+    let date = repo
+        .get_committer()?
+        .time
+        .format(gix::date::time::format::SHORT);
+    let mut replacements: HashMap<&str, Cow<'_, bstr::BStr>> = HashMap::new();
+    replacements.insert("patchname", Cow::Borrowed(patchname.into()));
+    replacements.insert("branch", Cow::Borrowed(branch_name.into()));
+    replacements.insert("date", Cow::Owned(date.into()));
+    replacements.insert("version", Cow::Borrowed(env!("CARGO_PKG_VERSION").into()));
+    let annotation = crate::templates::specialize_template(&annotation, &replacements);
+    Ok(Some(annotation.to_str_lossy().into_owned()))
+}
@@ -0,0 +1,469 @@
+// SPDX-License-Identifier: GPL-2.0-only
+
+//! `stg absorb` implementation.
+
+use std::{collections::HashMap, path::PathBuf};
+
+use anyhow::{anyhow, Result};
+use bstr::{BStr, BString, ByteSlice, ByteVec};
+use clap::{Arg, ArgMatches};
+
+use crate::{
+    color::get_color_stdout,
+    ext::{CommitExtended, RepositoryExtended},
+    patch::PatchName,
+    print_info_message,
+    stack::{InitializationPolicy, Stack, StackAccess, StackStateAccess},
+    stupid::{Stupid, StupidContext},
+};
+
+pub(super) const STGIT_COMMAND: super::StGitCommand = super::StGitCommand {
+    name: "absorb",
+    category: super::CommandCategory::PatchManipulation,
+    make,
+    run,
+};
+
+fn make() -> clap::Command {
+    clap::Command::new(STGIT_COMMAND.name)
+        .about("Absorb work tree changes into the patches that introduced them")
+        .long_about(
+            "Refresh each hunk of the work tree changes into the applied patch that \
+             last modified the hunk's lines.\n\
+             \n\
+             The lines modified or removed by each hunk are blamed against the \
+             applied patches. When all of those lines were last modified by the same \
+             applied patch, the hunk is absorbed into that patch. Hunks that only add \
+             lines are absorbed into the applied patch that last modified the lines \
+             surrounding the addition, provided there is only one such patch. Other \
+             hunks are left in the work tree, as are changes to new, deleted, or \
+             binary files and changes of file modes.\n\
+             \n\
+             All of the absorbed hunks are refreshed into their patches in a single \
+             transaction, which may be undone with `stg undo`. The index must be \
+             clean.",
+        )
+        .arg(
+            Arg::new("pathspecs")
+                .help("Only absorb changes to files matching path")
+                .value_name("path")
+                .num_args(1..)
+                .value_parser(clap::value_parser!(PathBuf)),
+        )
+        .arg(
+            Arg::new("dry-run")
+                .long("dry-run")
+                .short('n')
+                .help("Only show which patch each hunk would be absorbed into")
+                .action(clap::ArgAction::SetTrue),
+        )
+}
+
+fn run(matches: &ArgMatches) -> Result<()> {
+    let repo = gix::Repository::open()?;
+    let stack = Stack::current(&repo, InitializationPolicy::AllowUninitialized)?;
+    let stupid = repo.stupid();
+
+    stack.check_head_top_mismatch()?;
+    if stack.applied().is_empty() {
+        return Err(super::Error::NoAppliedPatches.into());
+    }
+
+    let statuses = stupid.statuses(None)?;
+    statuses.check_conflicts()?;
+    statuses.check_index_clean()?;
+
+    let diff = stupid.diff_files_patch(matches.get_many::<PathBuf>("pathspecs"), 0)?;
+    let mut files = parse_diff(diff.as_ref())?;
+
+    let patch_by_commit: HashMap<gix::ObjectId, &PatchName> = stack
+        .applied()
+        .iter()
+        .map(|pn| (stack.get_patch_commit_id(pn), pn))
+        .collect();
+    let work_dir = repo
+        .work_dir()
+        .ok_or_else(|| anyhow!("cannot absorb changes in a bare repository"))?;
+
+    for file in files.iter_mut().filter(|file| file.absorbable) {
+        let path = work_dir.join(file.path.to_path()?);
+        let blame = stupid.blame(
+            stack.base().id,
+            stack.top().id,
+            &path,
+            std::iter::empty::<&str>(),
+        )?;
+        for hunk in &mut file.hunks {
+            let owners: Vec<Option<&PatchName>> = hunk
+                .blamed_lines()
+                .filter_map(|line_num| blame.get(line_num - 1))
+                .map(|(commit_id, _)| patch_by_commit.get(commit_id).copied())
+                .collect();
+            // Lines added next to lines of the stack base may still be absorbed.
+            if hunk.old_count > 0 && owners.contains(&None) {
+                continue;
+            }
+            let mut owners = owners.into_iter().flatten();
+            if let Some(first_patchname) = owners.next() {
+                if owners.all(|pn| pn == first_patchname) {
+                    hunk.target = Some(first_patchname.clone());
+                }
+            }
+        }
+    }
+
+    let mut num_absorbed = 0;
+    let mut num_left = 0;
+    for file in &files {
+        for hunk in &file.hunks {
+            if hunk.target.is_some() {
+                num_absorbed += 1;
+            } else {
+                num_left += 1;
+            }
+            if matches.get_flag("dry-run") {
+                println!(
+                    "{}:{}: {}",
+                    file.path,
+                    hunk.old_start,
+                    hunk.target
+                        .as_ref()
+                        .map_or("(not absorbed)", |pn| pn.as_ref())
+                );
+            }
+        }
+    }
+
+    if matches.get_flag("dry-run") {
+        return Ok(());
+    } else if num_absorbed == 0 {
+        return Err(anyhow!("no hunks could be absorbed"));
+    }
+
+    let head_tree_id = stack.get_branch_head().tree_id()?.detach();
+    let mut new_commits: Vec<(PatchName, gix::ObjectId)> = Vec::new();
+    for patchname in stack.applied() {
+        let patch_diff = render_diff(&files, |target| target == Some(patchname));
+        if patch_diff.is_empty() {
+            continue;
+        }
+        let patch_commit = stack.get_patch_commit(patchname);
+        let tree_id = absorb_tree(
+            &stupid,
+            head_tree_id,
+            patch_commit.tree_id()?.detach(),
+            patch_diff.as_ref(),
+        )?
+        .ok_or_else(|| anyhow!("cannot absorb hunks into patch `{patchname}`"))?;
+        let author = patch_commit.author_strict()?;
+        let committer = repo.get_rewrite_committer(Some(patch_commit), author.time, false)?;
+        let commit_id = repo.commit_ex(
+            &author,
+            &committer,
+            &patch_commit.message_ex(),
+            tree_id,
+            patch_commit.parent_ids().map(|id| id.detach()),
+        )?;
+        new_commits.push((patchname.clone(), commit_id));
+    }
+
+    let first_pos = stack
+        .applied()
+        .iter()
+        .position(|pn| pn == &new_commits[0].0)
+        .expect("absorbed patches are applied");
+    let to_repush = stack.applied()[first_pos..].to_vec();
+
+    let stack = stack
+        .setup_transaction()
+        .with_output_stream(get_color_stdout(matches))
+        .transact(|trans| {
+            trans.pop_patches(|pn| to_repush.contains(pn))?;
+            for (patchname, commit_id) in &new_commits {
+                trans.update_patch(patchname, *commit_id)?;
+            }
+            trans.push_patches(&to_repush, false)?;
+            Ok(())
+        })
+        .execute("absorb")?;
+
+    // The absorbed changes are now part of the stack. Leave the remaining changes in
+    // the work tree.
+    stupid.read_tree(stack.get_branch_head().tree_id()?.detach())?;
+    stupid.update_index_refresh()?;
+
+    print_info_message(
+        matches,
+        &format!(
+            "absorbed {num_absorbed} hunk{} into {} patch{}{}",
+            if num_absorbed == 1 { "" } else { "s" },
+            new_commits.len(),
+            if new_commits.len() == 1 { "" } else { "es" },
+            if num_left > 0 {
+                format!(
+                    "; {num_left} hunk{} left in the work tree",
+                    if num_left == 1 { "" } else { "s" }
+                )
+            } else {
+                String::new()
+            }
+        ),
+    );
+
+    Ok(())
+}
+
+/// Make the tree of a patch with absorbed hunks.
+///
+/// The hunks of the given zero-context diff apply to the stack's head tree. They are
+/// carried over to the patch's tree with a three-way merge. `None` is returned if the
+/// hunks do not apply cleanly to the patch's tree.
+fn absorb_tree(
+    stupid: &StupidContext,
+    head_tree_id: gix::ObjectId,
+    patch_tree_id: gix::ObjectId,
+    diff: &BStr,
+) -> Result<Option<gix::ObjectId>> {
+    let absorbed_head_tree_id = stupid.with_temp_index(|stupid_temp| {
+        stupid_temp.read_tree(head_tree_id)?;
+        stupid_temp.apply_zero_context_to_index(diff)?;
+        stupid_temp.write_tree()
+    })?;
+    stupid.with_temp_index(|stupid_temp| {
+        stupid_temp.read_tree(patch_tree_id)?;
+        if stupid_temp.apply_treediff_to_index(head_tree_id, absorbed_head_tree_id, true)? {
+            Ok(Some(stupid_temp.write_tree()?))
+        } else {
+            Ok(None)
+        }
+    })
+}
+
+/// Changes to a file in a zero-context diff.
+struct FileDiff {
+    /// Path of the file, relative to the root of the work tree.
+    path: BString,
+
+    /// Lines of the file's diff header.
+    header: Vec<BString>,
+
+    /// Whether the file's hunks may be absorbed.
+    absorbable: bool,
+
+    hunks: Vec<Hunk>,
+}
+
+/// Hunk of a zero-context diff.
+struct Hunk {
+    old_start: usize,
+    old_count: usize,
+    new_count: usize,
+
+    /// Text following the hunk's line ranges in its header line.
+    header_suffix: BString,
+
+    /// Removed and added lines of the hunk.
+    lines: Vec<BString>,
+
+    /// Patch the hunk is to be absorbed into.
+    target: Option<PatchName>,
+}
+
+impl Hunk {
+    /// Get the (one-based) line numbers to blame for the hunk.
+    ///
+    /// The removed lines are blamed. For hunks that only add lines, the lines
+    /// surrounding the addition are blamed.
+    fn blamed_lines(&self) -> impl Iterator<Item = usize> {
+        if self.old_count > 0 {
+            self.old_start..self.old_start + self.old_count
+        } else {
+            self.old_start.max(1)..self.old_start + 2
+        }
+    }
+}
+
+/// Parse the output of `git diff-files -p -U0`.
+fn parse_diff(diff: &BStr) -> Result<Vec<FileDiff>> {
+    let mut files: Vec<FileDiff> = Vec::new();
+    for line in diff.lines() {
+        if line.starts_with(b"diff --git ") {
+            files.push(FileDiff {
+                path: BString::default(),
+                header: vec![line.into()],
+                absorbable: true,
+                hunks: Vec::new(),
+            });
+            continue;
+        }
+        let file = files
+            .last_mut()
+            .ok_or_else(|| anyhow!("unexpected diff output `{}`", line.as_bstr()))?;
+        if let Some(header_line) = line.strip_prefix(b"@@ -") {
+            file.hunks.push(
+                parse_hunk_header(header_line)
+                    .ok_or_else(|| anyhow!("malformed hunk header `{}`", line.as_bstr()))?,
+            );
+        } else if let Some(hunk) = file.hunks.last_mut() {
+            hunk.lines.push(line.into());
+        } else {
+            if let Some(path) = line.strip_prefix(b"+++ b/") {
+                file.path = path.into();
+            } else if line.starts_with(b"+++ ")
+                || line.starts_with(b"new file mode ")
+                || line.starts_with(b"deleted file mode ")
+                || line.starts_with(b"old mode ")
+                || line.starts_with(b"Binary files ")
+            {
+                file.absorbable = false;
+            }
+            file.header.push(line.into());
+        }
+    }
+    for file in &mut files {
+        if file.path.is_empty() {
+            file.absorbable = false;
+        }
+    }
+    Ok(files)
+}
+
+/// Parse hunk header line, sans its leading `@@ -`.
+fn parse_hunk_header(line: &[u8]) -> Option<Hunk> {
+    let (ranges, header_suffix) = line.split_once_str(" @@")?;
+    let (old_range, new_range) = ranges.split_once_str(" +")?;
+    let parse_range = |range: &[u8]| -> Option<(usize, usize)> {
+        let range = range.to_str().ok()?;
+        if let Some((start, count)) = range.split_once(',') {
+            Some((start.parse().ok()?, count.parse().ok()?))
+        } else {
+            Some((range.parse().ok()?, 1))
+        }
+    };
+    let (old_start, old_count) = parse_range(old_range)?;
+    let (_, new_count) = parse_range(new_range)?;
+    Some(Hunk {
+        old_start,
+        old_count,
+        new_count,
+        header_suffix: header_suffix.into(),
+        lines: Vec::new(),
+        target: None,
+    })
+}
+
+/// Render a zero-context diff of the hunks whose target satisfies the predicate.
+///
+/// The new line ranges of the rendered hunks are adjusted to only account for the
+/// other rendered hunks.
+fn render_diff<F>(files: &[FileDiff], predicate: F) -> BString
+where
+    F: Fn(Option<&PatchName>) -> bool,
+{
+    let mut diff = BString::default();
+    for file in files.iter().filter(|file| file.absorbable) {
+        let mut hunks = file
+            .hunks
+            .iter()
+            .filter(|hunk| predicate(hunk.target.as_ref()))
+            .peekable();
+        if hunks.peek().is_none() {
+            continue;
+        }
+        for line in &file.header {
+            diff.push_str(line);
+            diff.push(b'\n');
+        }
+        let mut offset: isize = 0;
+        for hunk in hunks {
+            let new_start = if hunk.old_count == 0 {
+                hunk.old_start + 1
+            } else {
+                hunk.old_start
+            } as isize
+                + offset
+                - if hunk.new_count == 0 { 1 } else { 0 };
+            offset += hunk.new_count as isize - hunk.old_count as isize;
+            diff.push_str(format!(
+                "@@ -{},{} +{new_start},{} @@",
+                hunk.old_start, hunk.old_count, hunk.new_count
+            ));
+            diff.push_str(&hunk.header_suffix);
+            diff.push(b'\n');
+            for line in &hunk.lines {
+                diff.push_str(line);
+                diff.push(b'\n');
+            }
+        }
+    }
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIFF: &str = "\
+diff --git a/a.txt b/a.txt
+index 1111111..2222222 100644
+--- a/a.txt
++++ b/a.txt
+@@ -2 +2 @@ one
+-2
++two
+@@ -5,0 +6,2 @@ five
++five-a
++five-b
+@@ -9,2 +10,0 @@ eight
+-9
+-10
+diff --git a/new.bin b/new.bin
+new file mode 100644
+index 0000000..3333333
+Binary files /dev/null and b/new.bin differ
+";
+
+    #[test]
+    fn parse_zero_context_diff() {
+        let files = parse_diff(DIFF.into()).unwrap();
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0].path, "a.txt");
+        assert!(files[0].absorbable);
+        assert_eq!(files[0].header.len(), 4);
+        let ranges: Vec<(usize, usize, usize)> = files[0]
+            .hunks
+            .iter()
+            .map(|hunk| (hunk.old_start, hunk.old_count, hunk.new_count))
+            .collect();
+        assert_eq!(ranges, [(2, 1, 1), (5, 0, 2), (9, 2, 0)]);
+        let blamed: Vec<Vec<usize>> = files[0]
+            .hunks
+            .iter()
+            .map(|hunk| hunk.blamed_lines().collect())
+            .collect();
+        assert_eq!(blamed, [vec![2], vec![5, 6], vec![9, 10]]);
+        assert!(!files[1].absorbable);
+    }
+
+    #[test]
+    fn render_subset_of_hunks() {
+        let mut files = parse_diff(DIFF.into()).unwrap();
+        let target = PatchName::make("p1", false, None);
+        files[0].hunks[0].target = Some(target.clone());
+        files[0].hunks[2].target = Some(target.clone());
+        let diff = render_diff(&files, |pn| pn == Some(&target));
+        assert_eq!(
+            diff,
+            "diff --git a/a.txt b/a.txt\n\
+             index 1111111..2222222 100644\n\
+             --- a/a.txt\n\
+             +++ b/a.txt\n\
+             @@ -2,1 +2,1 @@ one\n\
+             -2\n\
+             +two\n\
+             @@ -9,2 +8,0 @@ eight\n\
+             -9\n\
+             -10\n"
+        );
+    }
+}
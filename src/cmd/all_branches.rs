@@ -0,0 +1,210 @@
+// SPDX-License-Identifier: GPL-2.0-only
+
+//! `stg all-branches` implementation.
+
+use std::{ffi::OsString, io::Write};
+
+use anyhow::{anyhow, Context, Result};
+use clap::{Arg, ArgMatches};
+use termcolor::WriteColor;
+
+use crate::{
+    color::get_color_stdout,
+    ext::RepositoryExtended,
+    print_info_message,
+    stack::{InitializationPolicy, Stack},
+    stupid::Stupid,
+    wrap::{Branch, PartialRefName},
+};
+
+pub(super) const STGIT_COMMAND: super::StGitCommand = super::StGitCommand {
+    name: "all-branches",
+    category: super::CommandCategory::StackManipulation,
+    make,
+    run,
+};
+
+fn make() -> clap::Command {
+    clap::Command::new(STGIT_COMMAND.name)
+        .about("Run a command on every StGit branch")
+        .long_about(
+            "Run a StGit command on every branch initialized with a StGit stack.\n\
+             \n\
+             Each initialized branch is checked-out in turn and the given StGit \
+             command is run on it, e.g. `stg all-branches series --count` or `stg \
+             all-branches pull`. The command is run from the top-level of the work \
+             tree. Once all branches have been visited, the originally checked-out \
+             branch is checked-out again and a summary of which branches the command \
+             succeeded or failed on is printed.\n\
+             \n\
+             The index and work tree must be clean before running this command. If \
+             the command leaves the index or work tree unclean on a branch, e.g. due \
+             to merge conflicts, no further branches are visited so that the \
+             situation may be resolved on that branch.",
+        )
+        .override_usage(super::make_usage(
+            "stg all-branches",
+            &["[OPTIONS] <command> [args]..."],
+        ))
+        .arg(
+            Arg::new("command")
+                .help("StGit command and arguments to run on each branch")
+                .value_name("command")
+                .required(true)
+                .num_args(1..)
+                .trailing_var_arg(true)
+                .allow_hyphen_values(true)
+                .value_parser(clap::value_parser!(OsString)),
+        )
+}
+
+/// Outcome of running the command on a single branch.
+enum Outcome {
+    Succeeded,
+    Failed(Option<i32>),
+    NotRun,
+}
+
+fn run(matches: &ArgMatches) -> Result<()> {
+    let repo = gix::Repository::open()?;
+    let stupid = repo.stupid();
+    let command_args: Vec<&OsString> = matches
+        .get_many::<OsString>("command")
+        .expect("command is required")
+        .collect();
+    let work_dir = repo
+        .work_dir()
+        .ok_or_else(|| anyhow!("cannot run on all branches in a bare repository"))?
+        .to_owned();
+    let exec_path = std::env::current_exe().context("finding stg executable")?;
+
+    let statuses = stupid.statuses(None)?;
+    statuses.check_index_and_worktree_clean()?;
+    statuses.check_conflicts()?;
+
+    let mut branchnames: Vec<PartialRefName> = Vec::new();
+    for local_branch in repo.references()?.local_branches()?.filter_map(Result::ok) {
+        let local_branch = Branch::wrap(local_branch);
+        if let Ok(branchname) = local_branch.get_branch_partial_name() {
+            if Stack::from_branch_name(&repo, &branchname, InitializationPolicy::RequireInitialized)
+                .is_ok()
+            {
+                branchnames.push(branchname);
+            }
+        }
+    }
+    branchnames.sort();
+
+    if branchnames.is_empty() {
+        return Err(anyhow!("no initialized branches found"));
+    }
+
+    let original_branchname = repo
+        .get_current_branch()
+        .ok()
+        .and_then(|branch| branch.get_branch_partial_name().ok());
+
+    let mut outcomes: Vec<(&PartialRefName, Outcome)> = Vec::with_capacity(branchnames.len());
+    let mut stopped = false;
+
+    for branchname in &branchnames {
+        if stopped {
+            outcomes.push((branchname, Outcome::NotRun));
+            continue;
+        }
+
+        if Some(branchname) != original_branchname.as_ref() {
+            if let Err(e) = stupid.checkout(branchname.as_ref()) {
+                print_info_message(
+                    matches,
+                    &format!("Could not check-out `{branchname}`: {e:#}"),
+                );
+                outcomes.push((branchname, Outcome::NotRun));
+                continue;
+            }
+        }
+
+        print_info_message(matches, &format!("Running on `{branchname}`"));
+        let status = std::process::Command::new(&exec_path)
+            .args(command_args.iter())
+            .current_dir(&work_dir)
+            .status()
+            .with_context(|| format!("running `{}`", exec_path.to_string_lossy()))?;
+
+        outcomes.push((
+            branchname,
+            if status.success() {
+                Outcome::Succeeded
+            } else {
+                Outcome::Failed(status.code())
+            },
+        ));
+
+        let statuses = stupid.statuses(None)?;
+        if statuses.check_index_and_worktree_clean().is_err() || statuses.check_conflicts().is_err()
+        {
+            print_info_message(
+                matches,
+                &format!("Index or work tree not clean on `{branchname}`; stopping"),
+            );
+            stopped = true;
+        }
+    }
+
+    if !stopped {
+        if let Some(original_branchname) = original_branchname.as_ref() {
+            let current_branchname = repo
+                .get_current_branch()
+                .ok()
+                .and_then(|branch| branch.get_branch_partial_name().ok());
+            if current_branchname.as_ref() != Some(original_branchname) {
+                stupid.checkout(original_branchname.as_ref())?;
+            }
+        }
+    }
+
+    let mut stdout = get_color_stdout(matches);
+    let mut color_spec = termcolor::ColorSpec::new();
+    let branchname_width = branchnames
+        .iter()
+        .map(|name| name.as_ref().len())
+        .max()
+        .expect("branchnames is not empty");
+    let mut num_failed = 0;
+    for (branchname, outcome) in &outcomes {
+        write!(stdout, "{branchname:branchname_width$}  ")?;
+        match outcome {
+            Outcome::Succeeded => {
+                stdout.set_color(color_spec.set_fg(Some(termcolor::Color::Green)))?;
+                write!(stdout, "ok")?;
+            }
+            Outcome::Failed(code) => {
+                num_failed += 1;
+                stdout.set_color(color_spec.set_fg(Some(termcolor::Color::Red)))?;
+                if let Some(code) = code {
+                    write!(stdout, "failed ({code})")?;
+                } else {
+                    write!(stdout, "failed")?;
+                }
+            }
+            Outcome::NotRun => {
+                num_failed += 1;
+                stdout.set_color(color_spec.set_fg(Some(termcolor::Color::Yellow)))?;
+                write!(stdout, "not run")?;
+            }
+        }
+        color_spec.clear();
+        stdout.set_color(&color_spec)?;
+        writeln!(stdout)?;
+    }
+
+    if num_failed == 0 {
+        Ok(())
+    } else {
+        let plural = if outcomes.len() == 1 { "" } else { "es" };
+        Err(anyhow!(
+            "command did not succeed on {num_failed} of {} branch{plural}",
+            outcomes.len()
+        ))
+    }
+}
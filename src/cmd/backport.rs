@@ -0,0 +1,158 @@
+// SPDX-License-Identifier: GPL-2.0-only
+
+//! `stg backport` implementation.
+
+use anyhow::{anyhow, Result};
+use clap::{Arg, ArgMatches};
+
+use crate::{
+    branchloc::BranchLocator,
+    color::get_color_stdout,
+    ext::{CommitExtended, RepositoryExtended},
+    patch::{patchrange, PatchName, PatchRange, RangeConstraint},
+    stack::{InitializationPolicy, Stack, StackAccess, StackStateAccess},
+    stupid::Stupid,
+    wrap::Message,
+};
+
+pub(super) const STGIT_COMMAND: super::StGitCommand = super::StGitCommand {
+    name: "backport",
+    category: super::CommandCategory::StackManipulation,
+    make,
+    run,
+};
+
+fn make() -> clap::Command {
+    clap::Command::new(STGIT_COMMAND.name)
+        .about("Pick patches onto another branch's stack")
+        .long_about(
+            "Pick one or more patches from the current branch onto the stack of \
+             another StGit branch, e.g. to backport fixes to a release branch.\n\
+             \n\
+             The patches are applied, in the order given, on top of the target \
+             branch's topmost applied patch. The target branch does not need to be \
+             checked-out; the patches are merged without using the index or work \
+             tree. If any patch does not apply cleanly to the target branch, no \
+             patches are backported.\n\
+             \n\
+             Each backported patch keeps its name, unless the name is already used on \
+             the target branch, in which case a unique name is derived. A \
+             \"Backported-from\" trailer recording the original patch's commit is \
+             appended to each backported patch's message.",
+        )
+        .override_usage(super::make_usage(
+            "stg backport",
+            &["[OPTIONS] --to <branch> <patch>..."],
+        ))
+        .arg(
+            Arg::new("patchranges")
+                .help("Patches to backport")
+                .value_name("patch")
+                .num_args(1..)
+                .required(true)
+                .allow_hyphen_values(true)
+                .value_parser(clap::value_parser!(PatchRange)),
+        )
+        .arg(
+            Arg::new("to")
+                .long("to")
+                .help("Backport patches to <branch>")
+                .value_name("branch")
+                .required(true)
+                .value_parser(clap::value_parser!(BranchLocator)),
+        )
+}
+
+fn run(matches: &ArgMatches) -> Result<()> {
+    let repo = gix::Repository::open()?;
+    let stack = Stack::current(&repo, InitializationPolicy::AllowUninitialized)?;
+    let target_stack = Stack::from_branch_locator(
+        &repo,
+        matches.get_one::<BranchLocator>("to"),
+        InitializationPolicy::RequireInitialized,
+    )?;
+
+    if target_stack.get_branch_refname() == stack.get_branch_refname() {
+        return Err(anyhow!("cannot backport to the current branch"));
+    }
+    if target_stack.is_protected(&repo.config_snapshot()) {
+        return Err(anyhow!(
+            "backport not permitted: branch `{}` is protected",
+            target_stack.get_branch_name()
+        ));
+    }
+    target_stack.check_head_top_mismatch()?;
+
+    let patchnames = patchrange::resolve_names(
+        &stack,
+        matches
+            .get_many::<PatchRange>("patchranges")
+            .expect("clap will ensure at least one range is provided"),
+        RangeConstraint::All,
+    )?;
+
+    let source_branch_name = stack.get_branch_name().to_string();
+    let target_branch_name = target_stack.get_branch_name().to_string();
+    let stupid = repo.stupid();
+    let committer = repo.get_committer()?;
+
+    target_stack
+        .setup_transaction()
+        .with_output_stream(get_color_stdout(matches))
+        .transact(|trans| {
+            for patchname in &patchnames {
+                let patch_commit = stack.get_patch_commit(patchname);
+                let base_tree_id = patch_commit.get_parent_commit()?.tree_id()?.detach();
+                let patch_tree_id = patch_commit.tree_id()?.detach();
+                let top = trans.top().clone();
+                let top_tree_id = top.tree_id()?.detach();
+
+                let tree_id = stupid
+                    .with_temp_index(|stupid_temp| {
+                        stupid_temp.read_tree(top_tree_id)?;
+                        if stupid_temp.apply_treediff_to_index(base_tree_id, patch_tree_id, true)? {
+                            Ok(Some(stupid_temp.write_tree()?))
+                        } else {
+                            Ok(None)
+                        }
+                    })?
+                    .ok_or_else(|| {
+                        anyhow!(
+                            "patch `{patchname}` does not apply cleanly to \
+                             `{target_branch_name}`"
+                        )
+                    })?;
+
+                let message = patch_commit.message_ex();
+                let message = message.decode()?;
+                let origin = format!(
+                    "{} ({patchname} from {source_branch_name})",
+                    patch_commit.id
+                );
+                let message = stupid.interpret_trailers(
+                    message.as_bytes(),
+                    [("Backported-from", origin.as_str())],
+                )?;
+                let message = String::from_utf8(message)
+                    .map_err(|_| anyhow!("could not decode message after adding trailers"))?;
+
+                let commit_id = repo.commit_ex(
+                    &patch_commit.author_strict()?,
+                    committer,
+                    &Message::from(message),
+                    tree_id,
+                    [top.id],
+                )?;
+
+                let new_patchname = {
+                    let disallow: Vec<&PatchName> = trans.all_patches().collect();
+                    patchname.clone().uniquify(&[], &disallow)
+                };
+                trans.new_applied(&new_patchname, commit_id)?;
+            }
+            Ok(())
+        })
+        .execute(&format!("backport from {source_branch_name}"))?;
+
+    Ok(())
+}
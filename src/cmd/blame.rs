@@ -0,0 +1,112 @@
+// SPDX-License-Identifier: GPL-2.0-only
+
+//! `stg blame` implementation.
+
+use std::{collections::HashMap, io::Write, path::PathBuf};
+
+use anyhow::Result;
+use clap::{Arg, ArgMatches};
+
+use crate::{
+    argset,
+    branchloc::BranchLocator,
+    ext::RepositoryExtended,
+    patch::PatchName,
+    stack::{InitializationPolicy, Stack, StackAccess, StackStateAccess},
+    stupid::Stupid,
+};
+
+pub(super) const STGIT_COMMAND: super::StGitCommand = super::StGitCommand {
+    name: "blame",
+    category: super::CommandCategory::StackInspection,
+    make,
+    run,
+};
+
+fn make() -> clap::Command {
+    clap::Command::new(STGIT_COMMAND.name)
+        .about("Show which patch last modified each line of a file")
+        .long_about(
+            "Show which applied patch last modified each line of a file.\n\
+             \n\
+             Each line of the file, as of the top of the stack, is printed prefixed \
+             with the name of the applied patch that last modified the line. Lines \
+             that were not modified by any applied patch are prefixed with '{base}'.\n\
+             \n\
+             Changes in the worktree and index are not considered.",
+        )
+        .arg(
+            Arg::new("file")
+                .help("File to blame")
+                .value_name("file")
+                .required(true)
+                .value_hint(clap::ValueHint::FilePath)
+                .value_parser(clap::value_parser!(PathBuf)),
+        )
+        .arg(argset::branch_arg())
+        .arg(
+            Arg::new("line-range")
+                .short('L')
+                .help("Only show lines in <range>")
+                .long_help(
+                    "Only show lines in <range>. The range is specified as for the \
+                     '-L' option of git-blame(1), e.g. '10,20' or ':funcname'. This \
+                     option may be specified multiple times.",
+                )
+                .value_name("range")
+                .action(clap::ArgAction::Append)
+                .allow_hyphen_values(true),
+        )
+}
+
+fn run(matches: &ArgMatches) -> Result<()> {
+    let repo = gix::Repository::open()?;
+    let stack = Stack::from_branch_locator(
+        &repo,
+        matches.get_one::<BranchLocator>("branch"),
+        InitializationPolicy::AllowUninitialized,
+    )?;
+
+    let path = matches
+        .get_one::<PathBuf>("file")
+        .expect("required argument");
+
+    let mut opts = Vec::new();
+    if let Some(ranges) = matches.get_many::<String>("line-range") {
+        for range in ranges {
+            opts.push(format!("-L{range}"));
+        }
+    }
+
+    let lines = repo
+        .stupid()
+        .blame(stack.base().id, stack.get_branch_head().id, path, opts)?;
+
+    let patch_ids: HashMap<gix::ObjectId, &PatchName> = stack
+        .applied()
+        .iter()
+        .map(|pn| (stack.get_patch_commit_id(pn), pn))
+        .collect();
+
+    let width = stack
+        .applied()
+        .iter()
+        .map(|pn| pn.len())
+        .max()
+        .unwrap_or_default()
+        .max("{base}".len());
+
+    let mut stdout = std::io::stdout().lock();
+    for (commit_id, content) in lines {
+        let label = if let Some(patchname) = patch_ids.get(&commit_id) {
+            patchname.to_string()
+        } else {
+            "{base}".to_string()
+        };
+        write!(stdout, "{label:width$} ")?;
+        stdout.write_all(&content)?;
+        stdout.write_all(b"\n")?;
+    }
+
+    Ok(())
+}
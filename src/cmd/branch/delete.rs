@@ -7,6 +7,7 @@ use anyhow::{anyhow, Result};
 use crate::{
     branchloc::BranchLocator,
     ext::RepositoryExtended,
+    patch::PatchName,
     stack::{InitializationPolicy, Stack, StackStateAccess},
 };
 
@@ -14,14 +15,22 @@ pub(super) fn command() -> clap::Command {
     clap::Command::new("--delete")
         .override_usage(super::super::make_usage(
             "stg branch --delete",
-            &["[--force] <branch>"],
+            &["[--force] [--archive] <branch>"],
         ))
         .about("Delete a branch")
         .long_about(
             "Delete a branch.\n\
              \n\
              The branch will not be deleted if there are any patches remaining unless \
-             the '--force' option is provided.\n\
+             the '--force' or '--archive' option is provided. The patches whose \
+             commits would become unreachable, i.e. that are not reachable from any \
+             other branch, tag, or reference, are listed before the branch is \
+             deleted or when its deletion is refused.\n\
+             \n\
+             With '--archive', a backup of the branch's stack is kept in \
+             'refs/stgit-backup/<branch>/<n>' such that the unreachable patches may \
+             be restored with 'stg recover' after a new branch with the same name \
+             is created.\n\
              \n\
              A protected branch may not be deleted; it must be unprotected first.",
         )
@@ -38,6 +47,12 @@ pub(super) fn command() -> clap::Command {
                 .help("Force deletion even if branch has patches")
                 .action(clap::ArgAction::SetTrue),
         )
+        .arg(
+            clap::Arg::new("archive")
+                .long("archive")
+                .help("Keep a backup of patches that would become unreachable")
+                .action(clap::ArgAction::SetTrue),
+        )
 }
 
 pub(super) fn dispatch(repo: &gix::Repository, matches: &clap::ArgMatches) -> Result<()> {
@@ -50,7 +65,7 @@ pub(super) fn dispatch(repo: &gix::Repository, matches: &clap::ArgMatches) -> Re
     let current_branchname = current_branch
         .as_ref()
         .and_then(|branch| branch.get_branch_partial_name().ok());
-    if Some(target_branchname) == current_branchname {
+    if Some(&target_branchname) == current_branchname.as_ref() {
         return Err(anyhow!("cannot delete the current branch"));
     }
 
@@ -61,14 +76,55 @@ pub(super) fn dispatch(repo: &gix::Repository, matches: &clap::ArgMatches) -> Re
     ) {
         if stack.is_protected(&repo.config_snapshot()) {
             return Err(anyhow!("delete not permitted: this branch is protected"));
-        } else if !matches.get_flag("force") && stack.all_patches().count() > 0 {
+        }
+        let unreachable = stack.unreachable_patches()?;
+        print_inventory(&stack, &unreachable)?;
+        if matches.get_flag("archive") && !unreachable.is_empty() {
+            let number = stack.deinitialize_archived("branch --delete", &unreachable)?;
+            eprintln!(
+                "info: patches archived in backup `{number}`; restore them with `stg recover` \
+                 after recreating branch `{target_branchname}`"
+            );
+        } else if !matches.get_flag("force")
+            && !matches.get_flag("archive")
+            && stack.all_patches().count() > 0
+        {
             return Err(anyhow!(
-                "delete not permitted: the series still contains patches (override with --force)"
+                "delete not permitted: the series still contains patches \
+                 (override with --force or --archive)"
             ));
+        } else {
+            stack.deinitialize()?;
         }
-        stack.deinitialize()?;
     }
 
     target_branch.delete()?;
     Ok(())
 }
+
+/// Print the patches that would become unreachable to stderr.
+fn print_inventory(stack: &Stack, patchnames: &[PatchName]) -> Result<()> {
+    if patchnames.is_empty() {
+        return Ok(());
+    }
+    let plural = if patchnames.len() == 1 { "" } else { "es" };
+    eprintln!(
+        "The following {} patch{plural} would become unreachable:",
+        patchnames.len()
+    );
+    for patchname in patchnames {
+        let sigil = if stack.is_applied(patchname) {
+            '+'
+        } else if stack.is_hidden(patchname) {
+            '!'
+        } else {
+            '-'
+        };
+        eprintln!(
+            "  {sigil} {patchname}  {}  {}",
+            stack.get_patch_commit_id(patchname).to_hex_with_len(7),
+            super::super::squash::patch_subject(stack, patchname)?,
+        );
+    }
+    Ok(())
+}
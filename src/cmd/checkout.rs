@@ -0,0 +1,128 @@
+// SPDX-License-Identifier: GPL-2.0-only
+
+//! `stg checkout` implementation.
+
+use std::str::FromStr;
+
+use anyhow::{anyhow, Context, Result};
+use clap::{Arg, ArgMatches};
+
+use crate::{
+    ext::RepositoryExtended,
+    patch::PatchLocator,
+    stack::{InitializationPolicy, Stack, StackAccess, StackStateAccess},
+    stupid::Stupid,
+    wrap::PartialRefName,
+};
+
+pub(super) const STGIT_COMMAND: super::StGitCommand = super::StGitCommand {
+    name: "checkout",
+    category: super::CommandCategory::PatchInspection,
+    make,
+    run,
+};
+
+/// File, relative to the git dir, recording the branch to return to.
+const RETURN_BRANCH_FILENAME: &str = "STGIT_CHECKOUT_BRANCH";
+
+fn make() -> clap::Command {
+    clap::Command::new(STGIT_COMMAND.name)
+        .about("Inspect a patch's tree with a detached HEAD")
+        .long_about(
+            "Check out a patch's commit with a detached HEAD, without changing the \
+             stack.\n\
+             \n\
+             This allows the tree as of any patch in the series to be built, \
+             tested, or otherwise inspected without popping the patches above it. \
+             The index and work tree must be clean. Other patches may be checked \
+             out while HEAD remains detached.\n\
+             \n\
+             Use 'stg checkout -' to return to the branch. Since HEAD is detached, \
+             commands that operate on the stack are not available until returning \
+             to the branch.",
+        )
+        .override_usage(super::make_usage("stg checkout", &["<patch>", "-"]))
+        .arg(
+            Arg::new("patch")
+                .help("Patch to check out, or '-' to return to the branch")
+                .required(true)
+                .allow_hyphen_values(true),
+        )
+}
+
+fn run(matches: &ArgMatches) -> Result<()> {
+    let repo = gix::Repository::open()?;
+    let stupid = repo.stupid();
+    let return_path = repo.git_dir().join(RETURN_BRANCH_FILENAME);
+    let return_branchname = read_return_branch(&repo, &return_path)?;
+
+    repo.check_repository_state()?;
+    let statuses = stupid.statuses(None)?;
+    statuses.check_conflicts()?;
+    statuses.check_index_clean()?;
+    statuses.check_worktree_clean()?;
+
+    let patch_arg = matches
+        .get_one::<String>("patch")
+        .expect("required argument");
+
+    if patch_arg == "-" {
+        let return_branchname = return_branchname
+            .ok_or_else(|| anyhow!("no patch is checked out; nothing to return from"))?;
+        stupid.checkout(return_branchname.as_ref())?;
+        std::fs::remove_file(&return_path)?;
+        return Ok(());
+    }
+
+    let patch_loc = PatchLocator::from_str(patch_arg)?;
+    let stack = match return_branchname.as_ref() {
+        Some(branchname) => {
+            Stack::from_branch_name(&repo, branchname, InitializationPolicy::RequireInitialized)?
+        }
+        None => {
+            let stack = Stack::current(&repo, InitializationPolicy::RequireInitialized)?;
+            stack.check_head_top_mismatch()?;
+            stack
+        }
+    };
+    let patchname = patch_loc.resolve_name(&stack)?;
+    let commit_id = stack.get_patch_commit_id(&patchname);
+
+    stupid.checkout_detached(commit_id)?;
+    std::fs::write(&return_path, format!("{}\n", stack.get_branch_name()))?;
+    Ok(())
+}
+
+/// Read the branch to return to from the return branch file.
+///
+/// The recorded branch is only valid while HEAD remains detached at one of the recorded
+/// stack's patches. A stale file, e.g. left behind after HEAD was moved by git
+/// directly, is removed and no return branch is reported.
+fn read_return_branch(
+    repo: &gix::Repository,
+    return_path: &std::path::Path,
+) -> Result<Option<PartialRefName>> {
+    let branchname = match std::fs::read_to_string(return_path) {
+        Ok(content) => PartialRefName::from_str(content.trim())?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e).context("reading checkout return branch"),
+    };
+
+    let head = repo.head()?;
+    let is_valid = head.is_detached()
+        && head.id().map_or(false, |head_id| {
+            Stack::from_branch_name(repo, &branchname, InitializationPolicy::RequireInitialized)
+                .map_or(false, |stack| {
+                    stack
+                        .all_patches()
+                        .any(|patchname| stack.get_patch_commit_id(patchname) == head_id)
+                })
+        });
+
+    if is_valid {
+        Ok(Some(branchname))
+    } else {
+        std::fs::remove_file(return_path).context("removing stale checkout return branch")?;
+        Ok(None)
+    }
+}
@@ -2,14 +2,19 @@
 
 //! `stg clean` implementation.
 
-use anyhow::Result;
+use std::{collections::BTreeSet, io::Write};
+
+use anyhow::{anyhow, Result};
+use bstr::ByteSlice;
 use clap::{Arg, ArgMatches};
+use is_terminal::IsTerminal;
 
 use crate::{
     color::get_color_stdout,
     ext::{CommitExtended, RepositoryExtended},
     patch::PatchName,
-    stack::{InitializationPolicy, Stack, StackStateAccess},
+    print_info_message, print_warning_message,
+    stack::{InitializationPolicy, RawStackState, Stack, StackAccess, StackStateAccess},
     stupid::Stupid,
 };
 
@@ -26,7 +31,23 @@ fn make() -> clap::Command {
         .long_about(
             "Delete the empty patches from the entire series by default, \
              or only empty patches from the applied or unapplied patches. \
-             A patch is considered empty if its tree is the same as its parent.",
+             A patch is considered empty if its tree is the same as its parent.\n\
+             \n\
+             With '--orphans', the stack log is cleaned instead. The stack log keeps \
+             every commit a patch ever had reachable, which makes long-lived \
+             repositories grow without bound. Stack log entries older than the number \
+             of days configured with \"stgit.log.expire\", 90 by default, are dropped \
+             from the log. Patch commits only referenced by the dropped entries become \
+             orphans which are subsequently pruned by `git gc`. With '--dry-run', the \
+             orphaned commits are only listed.\n\
+             \n\
+             Commits referenced by any other reference, such as branches, tags, or the \
+             backups made by destructive operations, are not orphaned.\n\
+             \n\
+             With '--expired-hidden', hidden patches that have been hidden for longer \
+             than the number of days configured with \"stgit.hidden.expire\" are \
+             handled according to \"stgit.hidden.expire-action\": they are either \
+             reported with a warning or, when prompting, deleted upon confirmation.",
         )
         .arg(
             Arg::new("applied")
@@ -42,10 +63,45 @@ fn make() -> clap::Command {
                 .help("Delete empty unapplied patches")
                 .action(clap::ArgAction::SetTrue),
         )
+        .arg(
+            Arg::new("orphans")
+                .long("orphans")
+                .help("Drop expired stack log entries and their orphaned commits")
+                .action(clap::ArgAction::SetTrue)
+                .conflicts_with_all(["applied", "unapplied"]),
+        )
+        .arg(
+            Arg::new("expired-hidden")
+                .long("expired-hidden")
+                .help("Warn about or prompt to delete expired hidden patches")
+                .action(clap::ArgAction::SetTrue)
+                .conflicts_with_all(["applied", "unapplied", "orphans"]),
+        )
+        .arg(
+            Arg::new("dry-run")
+                .long("dry-run")
+                .short('n')
+                .help("Only list the commits that would be orphaned")
+                .requires("orphans")
+                .action(clap::ArgAction::SetTrue),
+        )
 }
 
+/// Default number of days after which stack log entries expire.
+const DEFAULT_LOG_EXPIRE_DAYS: i64 = 90;
+
 fn run(matches: &ArgMatches) -> Result<()> {
     let repo = gix::Repository::open()?;
+
+    if matches.get_flag("orphans") {
+        let stack = Stack::current(&repo, InitializationPolicy::RequireInitialized)?;
+        return clean_orphans(stack, matches);
+    } else if matches.get_flag("expired-hidden") {
+        let stack = Stack::current(&repo, InitializationPolicy::RequireInitialized)?;
+        stack.check_head_top_mismatch()?;
+        return clean_expired_hidden(stack, matches);
+    }
+
     let stack = Stack::current(&repo, InitializationPolicy::AllowUninitialized)?;
     stack.check_head_top_mismatch()?;
     repo.check_repository_state()?;
@@ -85,6 +141,8 @@ fn run(matches: &ArgMatches) -> Result<()> {
     }
 
     if !to_delete.is_empty() {
+        stack.backup("clean", &to_delete)?;
+
         stack
             .setup_transaction()
             .allow_conflicts(true)
@@ -100,3 +158,191 @@ fn run(matches: &ArgMatches) -> Result<()> {
 
     Ok(())
 }
+
+/// Warn about, or prompt to delete, hidden patches that have expired.
+///
+/// Hidden patches expire once they have been hidden for more than the number of days
+/// configured by `stgit.hidden.expire`. Depending on `stgit.hidden.expire-action`,
+/// expired patches are either reported with a warning (the default) or, when stdin is
+/// a terminal, the user is prompted to delete each expired patch.
+fn clean_expired_hidden(stack: Stack, matches: &ArgMatches) -> Result<()> {
+    let config = stack.repo.config_snapshot();
+    let expire_days = match config.integer("stgit.hidden.expire") {
+        Some(days) if days > 0 => days,
+        _ => {
+            print_info_message(matches, "hidden patches never expire");
+            return Ok(());
+        }
+    };
+    let prompt = match config
+        .string("stgit.hidden.expire-action")
+        .map(|value| value.to_str_lossy().to_string())
+        .as_deref()
+    {
+        None | Some("warn") => false,
+        Some("prompt") => std::io::stdin().is_terminal(),
+        Some(action) => {
+            return Err(anyhow!(
+                "invalid `stgit.hidden.expire-action` value `{action}`; \
+                 expected `warn` or `prompt`"
+            ))
+        }
+    };
+
+    let now = gix::date::Time::now_local_or_utc();
+    let mut expired: Vec<(PatchName, i64)> = Vec::new();
+    for (patchname, since) in stack.hidden_since()? {
+        let days = (now.seconds - since.seconds) / (24 * 60 * 60);
+        if days > expire_days {
+            expired.push((patchname, days));
+        }
+    }
+
+    if !prompt {
+        for (patchname, days) in &expired {
+            print_warning_message(
+                matches,
+                &format!("hidden patch `{patchname}` expired; hidden for {days} days"),
+            );
+        }
+        return Ok(());
+    }
+
+    let mut to_delete: Vec<PatchName> = Vec::new();
+    let mut stderr = std::io::stderr();
+    for (patchname, days) in expired {
+        write!(
+            stderr,
+            "Delete hidden patch `{patchname}` (hidden for {days} days)? [y/N] "
+        )?;
+        stderr.flush()?;
+        let mut answer = String::new();
+        std::io::stdin().read_line(&mut answer)?;
+        if matches!(answer.trim(), "y" | "Y" | "yes") {
+            to_delete.push(patchname);
+        }
+    }
+
+    if !to_delete.is_empty() {
+        stack
+            .setup_transaction()
+            .with_output_stream(get_color_stdout(matches))
+            .transact(|trans| {
+                trans.delete_patches(|pn| to_delete.contains(pn))?;
+                Ok(())
+            })
+            .execute("delete expired hidden patches")?;
+    }
+    Ok(())
+}
+
+/// Drop expired stack log entries, orphaning the commits only they reference.
+fn clean_orphans(mut stack: Stack, matches: &ArgMatches) -> Result<()> {
+    let repo = stack.repo;
+    let expire_days = repo
+        .config_snapshot()
+        .integer("stgit.log.expire")
+        .unwrap_or(DEFAULT_LOG_EXPIRE_DAYS);
+    if expire_days <= 0 {
+        print_info_message(matches, "stack log entries never expire");
+        return Ok(());
+    }
+    let cutoff = gix::date::Time::now_local_or_utc().seconds - expire_days * 24 * 60 * 60;
+
+    let log = stack.state_log()?;
+    let mut keep = 1;
+    while keep < log.len() && log[keep].time()?.seconds >= cutoff {
+        keep += 1;
+    }
+    if keep == log.len() {
+        print_info_message(
+            matches,
+            &format!("no stack log entries older than {expire_days} days"),
+        );
+        return Ok(());
+    }
+
+    let orphan_ids = find_orphans(&stack, &log[..keep], &log[keep..])?;
+
+    if matches.get_flag("dry-run") {
+        for commit_id in &orphan_ids {
+            let commit = repo.find_commit(*commit_id)?;
+            println!(
+                "{} {}",
+                commit_id.to_hex_with_len(7),
+                commit.decode()?.message_summary()
+            );
+        }
+        return Ok(());
+    }
+
+    let num_dropped = log.len() - keep;
+    stack.truncate_state_log(&log, keep, "clean orphans")?;
+    repo.stupid()
+        .reflog_expire_unreachable(stack.get_stack_refname())?;
+    print_info_message(
+        matches,
+        &format!(
+            "Dropped {num_dropped} stack log entr{} older than {expire_days} days, \
+             orphaning {} commit{}; run `git gc` to prune them",
+            if num_dropped == 1 { "y" } else { "ies" },
+            orphan_ids.len(),
+            if orphan_ids.len() == 1 { "" } else { "s" },
+        ),
+    );
+    Ok(())
+}
+
+/// Find the patch commits referenced only by the dropped stack log entries.
+///
+/// Patch commits that are reachable from any kept log entry or from any reference other
+/// than the stack state reference are not orphans.
+fn find_orphans(
+    stack: &Stack,
+    kept: &[gix::Commit],
+    dropped: &[gix::Commit],
+) -> Result<Vec<gix::ObjectId>> {
+    let repo = stack.repo;
+    let state_ids = |commit: &gix::Commit| -> Result<Vec<gix::ObjectId>> {
+        let state = RawStackState::from_tree(commit.tree()?)?;
+        let mut ids = vec![state.head];
+        ids.extend(state.patches.values().map(|patch_state| patch_state.oid));
+        Ok(ids)
+    };
+
+    let mut candidate_ids = indexmap::IndexSet::new();
+    for commit in dropped {
+        candidate_ids.extend(state_ids(commit)?);
+    }
+
+    let mut exclude_ids = BTreeSet::new();
+    for commit in kept {
+        exclude_ids.extend(state_ids(commit)?);
+    }
+    for reference in repo.references()?.all()?.filter_map(Result::ok) {
+        if reference.name().as_bstr() == stack.get_stack_refname().as_bytes() {
+            continue;
+        }
+        if let Ok(commit) = reference
+            .into_fully_peeled_id()
+            .map_err(anyhow::Error::from)
+            .and_then(|id| Ok(id.object()?.try_into_commit()?))
+        {
+            exclude_ids.insert(commit.id);
+        }
+    }
+
+    let candidate_ids: Vec<gix::ObjectId> = candidate_ids
+        .into_iter()
+        .filter(|id| repo.find_object(*id).is_ok())
+        .collect();
+    let unreachable_ids: BTreeSet<gix::ObjectId> = repo
+        .stupid()
+        .rev_list_excluding(candidate_ids.iter().copied(), exclude_ids)?
+        .into_iter()
+        .collect();
+    Ok(candidate_ids
+        .into_iter()
+        .filter(|id| unreachable_ids.contains(id))
+        .collect())
+}
@@ -0,0 +1,168 @@
+// SPDX-License-Identifier: GPL-2.0-only
+
+//! `stg clone` implementation.
+
+use std::{
+    ffi::OsString,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, Result};
+use bstr::ByteSlice;
+use clap::{Arg, ArgMatches};
+
+use crate::stupid::{Stupid, StupidContext};
+
+pub(super) const STGIT_COMMAND: super::StGitCommand = super::StGitCommand {
+    name: "clone",
+    category: super::CommandCategory::Administration,
+    make,
+    run,
+};
+
+/// Refspecs for fetching the stack state and patch references of all branches.
+const STACK_REFSPECS: [&str; 2] = [
+    "+refs/stacks/*:refs/stacks/*",
+    "+refs/patches/*:refs/patches/*",
+];
+
+const REMOTE_NAME: &str = "origin";
+
+fn make() -> clap::Command {
+    clap::Command::new(STGIT_COMMAND.name)
+        .about("Clone a repository along with its stacks")
+        .long_about(
+            "Clone a repository into a new directory along with the StGit stacks \
+             of the repository's branches.\n\
+             \n\
+             In addition to what `git clone` fetches, the stack state references \
+             ('refs/stacks/*') and patch references ('refs/patches/*') are fetched \
+             from the cloned repository. Fetch refspecs for these references are \
+             added to the 'origin' remote's configuration such that subsequent \
+             fetches from 'origin' keep the stacks up to date with the cloned \
+             repository. Note that such fetches overwrite the local state of any \
+             stack that also exists in the cloned repository.\n\
+             \n\
+             A local branch, tracking the corresponding remote branch, is created \
+             for each cloned stack whose branch does not otherwise exist in the new \
+             repository.\n\
+             \n\
+             When <directory> is not specified, the directory name is derived from \
+             <repository> in the same way as `git clone`.",
+        )
+        .override_usage(super::make_usage(
+            "stg clone",
+            &["[OPTIONS] <repository> [<directory>]"],
+        ))
+        .arg(
+            Arg::new("repository")
+                .help("Repository to clone")
+                .value_name("repository")
+                .required(true)
+                .value_hint(clap::ValueHint::Url)
+                .value_parser(clap::value_parser!(OsString)),
+        )
+        .arg(
+            Arg::new("directory")
+                .help("Directory to clone into")
+                .value_name("directory")
+                .value_hint(clap::ValueHint::DirPath)
+                .value_parser(clap::value_parser!(PathBuf)),
+        )
+        .arg(
+            Arg::new("branch")
+                .long("branch")
+                .short('b')
+                .help("Check out <branch> instead of the remote's HEAD")
+                .value_name("branch"),
+        )
+}
+
+fn run(matches: &ArgMatches) -> Result<()> {
+    let repository = matches
+        .get_one::<OsString>("repository")
+        .expect("required argument");
+    let directory = if let Some(directory) = matches.get_one::<PathBuf>("directory") {
+        directory.clone()
+    } else {
+        default_directory(repository)?
+    };
+
+    StupidContext::default().clone_repository(
+        repository,
+        &directory,
+        matches.get_one::<String>("branch").map(String::as_str),
+    )?;
+
+    let repo = gix::open(&directory)?;
+    let stupid = repo.stupid();
+
+    stupid.fetch_refspecs(REMOTE_NAME, STACK_REFSPECS)?;
+    for refspec in STACK_REFSPECS {
+        stupid.config_add(&format!("remote.{REMOTE_NAME}.fetch"), refspec)?;
+    }
+
+    let mut stack_branches = Vec::new();
+    for reference in repo
+        .references()?
+        .prefixed("refs/stacks/")?
+        .filter_map(Result::ok)
+    {
+        if let Some(branch_name) = reference
+            .name()
+            .as_bstr()
+            .to_str()
+            .ok()
+            .and_then(|refname| refname.strip_prefix("refs/stacks/"))
+        {
+            stack_branches.push(branch_name.to_string());
+        }
+    }
+
+    for branch_name in &stack_branches {
+        let local_refname = format!("refs/heads/{branch_name}");
+        if repo.try_find_reference(local_refname.as_str())?.is_some() {
+            continue;
+        }
+        let remote_refname = format!("refs/remotes/{REMOTE_NAME}/{branch_name}");
+        let remote_branch_id =
+            if let Some(reference) = repo.try_find_reference(remote_refname.as_str())? {
+                reference.into_fully_peeled_id()?.detach()
+            } else {
+                continue;
+            };
+        repo.reference(
+            local_refname.as_str(),
+            remote_branch_id,
+            gix::refs::transaction::PreviousValue::MustNotExist,
+            format!("clone: from {remote_refname}"),
+        )?;
+        stupid.config_add(&format!("branch.{branch_name}.remote"), REMOTE_NAME)?;
+        stupid.config_add(&format!("branch.{branch_name}.merge"), &local_refname)?;
+    }
+
+    Ok(())
+}
+
+/// Derive the clone directory name from the repository, like `git clone` does.
+///
+/// E.g. "host:path/to/repo.git" and "/path/to/repo/.git" both yield "repo".
+fn default_directory(repository: &OsString) -> Result<PathBuf> {
+    let repository = repository.to_string_lossy();
+    let trimmed = repository.trim_end_matches('/');
+    let trimmed = trimmed.strip_suffix("/.git").unwrap_or(trimmed);
+    let trimmed = trimmed.trim_end_matches('/');
+    let name = trimmed.rsplit(['/', ':']).next().unwrap_or(trimmed);
+    let name = name
+        .strip_suffix(".git")
+        .or_else(|| name.strip_suffix(".bundle"))
+        .unwrap_or(name);
+    if name.is_empty() {
+        Err(anyhow!(
+            "cannot determine directory name from `{repository}`; please specify \
+             <directory>"
+        ))
+    } else {
+        Ok(Path::new(name).to_path_buf())
+    }
+}
@@ -6,6 +6,7 @@ mod bash;
 mod fish;
 mod list;
 mod man;
+mod prompt;
 mod shstream;
 mod zsh;
 
@@ -26,7 +27,8 @@ fn make() -> clap::Command {
         .long_about(
             "Support completions for bash, fish, and zsh. Also provides 'stg \
              completion list' command for dynamically introspecting StGit's \
-             commands and aliases.",
+             commands and aliases, and 'stg completion prompt' for generating a shell \
+             prompt snippet.",
         )
         .subcommand_required(true)
         .subcommand(bash::command())
@@ -34,6 +36,7 @@ fn make() -> clap::Command {
         .subcommand(zsh::command())
         .subcommand(list::command())
         .subcommand(man::command())
+        .subcommand(prompt::command())
         .arg(
             clap::Arg::new("output")
                 .long("output")
@@ -53,6 +56,7 @@ fn run(matches: &clap::ArgMatches) -> Result<()> {
         Some(("zsh", sub_matches)) => zsh::dispatch(sub_matches),
         Some(("list", sub_matches)) => list::dispatch(sub_matches),
         Some(("man", sub_matches)) => man::dispatch(sub_matches),
+        Some(("prompt", sub_matches)) => prompt::dispatch(sub_matches),
         _ => panic!("valid subcommand is required"),
     }
 }
@@ -0,0 +1,63 @@
+// SPDX-License-Identifier: GPL-2.0-only
+
+//! `stg completion prompt` implementation.
+
+use std::path::PathBuf;
+
+use anyhow::Result;
+
+pub(super) fn command() -> clap::Command {
+    clap::Command::new("prompt")
+        .about("Generate shell prompt snippet")
+        .long_about(
+            "Generate a snippet of shell code defining a '__stg_ps1' function for use \
+             in bash or zsh prompts.\n\
+             \n\
+             The function prints the output of 'stg series --prompt', i.e. the \
+             topmost patch name, its position in the series, and whether there are \
+             unresolved conflicts. Nothing is printed outside of a StGit stack. An \
+             optional printf-style format may be passed to the function; the default \
+             format is \" (%s)\".\n\
+             \n\
+             For example, for bash:\n\
+             \n\
+             \x20   eval \"$(stg completion prompt)\"\n\
+             \x20   PS1='\\w$(__stg_ps1)\\$ '\n\
+             \n\
+             Or for zsh:\n\
+             \n\
+             \x20   eval \"$(stg completion prompt)\"\n\
+             \x20   setopt PROMPT_SUBST\n\
+             \x20   PROMPT='%~$(__stg_ps1 \" [%s]\")%# '",
+        )
+        .arg(
+            clap::Arg::new("output")
+                .long("output")
+                .short('o')
+                .help("Output prompt snippet to <path>")
+                .value_name("path")
+                .value_hint(clap::ValueHint::FilePath)
+                .value_parser(clap::value_parser!(PathBuf)),
+        )
+}
+
+pub(super) fn dispatch(matches: &clap::ArgMatches) -> Result<()> {
+    let mut stream = super::get_output_stream(matches)?;
+    stream.write_all(SNIPPET.as_bytes())?;
+    Ok(())
+}
+
+const SNIPPET: &str = r#"# StGit prompt support for bash and zsh.
+#
+# Add $(__stg_ps1) to PS1 (bash) or PROMPT (zsh, with PROMPT_SUBST set) to show the
+# topmost patch, its position in the series, and a conflict flag. An optional
+# printf-style format may be given, e.g. $(__stg_ps1 " [%s]").
+__stg_ps1 ()
+{
+    local stg_prompt_info
+    stg_prompt_info="$(stg series --prompt 2>/dev/null)" || return 0
+    if [ -n "$stg_prompt_info" ]; then
+        printf -- "${1:- (%s)}" "$stg_prompt_info"
+    fi
+}
+"#;
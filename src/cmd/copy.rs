@@ -0,0 +1,95 @@
+// SPDX-License-Identifier: GPL-2.0-only
+
+//! `stg copy` implementation.
+
+use anyhow::{anyhow, Result};
+use clap::{Arg, ArgMatches};
+
+use crate::{
+    color::get_color_stdout,
+    ext::{CommitExtended, RepositoryExtended},
+    patch::{PatchLocator, PatchName},
+    stack::{InitializationPolicy, Stack, StackStateAccess},
+};
+
+pub(super) const STGIT_COMMAND: super::StGitCommand = super::StGitCommand {
+    name: "copy",
+    category: super::CommandCategory::PatchManipulation,
+    make,
+    run,
+};
+
+fn make() -> clap::Command {
+    clap::Command::new(STGIT_COMMAND.name)
+        .about("Duplicate a patch")
+        .long_about(
+            "Duplicate a patch under a new name.\n\
+             \n\
+             The new patch has the same author, message, and diff as the original \
+             patch and is added as the first unapplied patch. If <new-patch> is not \
+             given, the new patch's name is derived from the original patch's name.\n\
+             \n\
+             This is useful, for example, for preparing a variant of a patch to be \
+             backported to another branch.",
+        )
+        .override_usage(super::make_usage(
+            "stg copy",
+            &["[OPTIONS] <patch> [new-patch]"],
+        ))
+        .arg(
+            Arg::new("patch")
+                .help("Patch to copy")
+                .required(true)
+                .allow_hyphen_values(true)
+                .value_parser(clap::value_parser!(PatchLocator)),
+        )
+        .arg(
+            Arg::new("new-patch")
+                .help("Name of the new patch")
+                .allow_hyphen_values(true)
+                .value_parser(clap::value_parser!(PatchName)),
+        )
+}
+
+fn run(matches: &ArgMatches) -> Result<()> {
+    let repo = gix::Repository::open()?;
+    let stack = Stack::current(&repo, InitializationPolicy::AllowUninitialized)?;
+
+    let patchname = matches
+        .get_one::<PatchLocator>("patch")
+        .expect("required argument")
+        .resolve_name(&stack)?;
+
+    let new_patchname = if let Some(new_patchname) = matches.get_one::<PatchName>("new-patch") {
+        if let Some(colliding_name) = stack.collides(new_patchname) {
+            return if stack.has_patch(new_patchname) {
+                Err(anyhow!("patch `{new_patchname}` already exists"))
+            } else {
+                Err(anyhow!(
+                    "new name `{new_patchname}` collides with `{colliding_name}`"
+                ))
+            };
+        }
+        new_patchname.clone()
+    } else {
+        let disallow: Vec<&PatchName> = stack.all_patches().collect();
+        PatchName::make(&format!("{patchname}-copy"), false, None).uniquify(&[], &disallow)
+    };
+
+    let patch_commit = stack.get_patch_commit(&patchname);
+    let commit_id = repo.commit_ex(
+        &patch_commit.author_strict()?,
+        repo.get_committer()?,
+        &patch_commit.message_ex(),
+        patch_commit.tree_id()?.detach(),
+        [patch_commit.get_parent_commit()?.id],
+    )?;
+
+    stack
+        .setup_transaction()
+        .with_output_stream(get_color_stdout(matches))
+        .transact(|trans| trans.new_unapplied(&new_patchname, commit_id, 0))
+        .execute(&format!("copy {patchname} {new_patchname}"))?;
+
+    Ok(())
+}
@@ -0,0 +1,257 @@
+// SPDX-License-Identifier: GPL-2.0-only
+
+//! `stg cover` implementation.
+
+use std::{collections::BTreeMap, ffi::OsString, io::Write, path::PathBuf};
+
+use anyhow::{anyhow, Context, Result};
+use bstr::{BString, ByteSlice, ByteVec};
+use clap::{Arg, ArgMatches};
+
+use crate::{
+    argset,
+    branchloc::BranchLocator,
+    ext::{CommitExtended, RepositoryExtended},
+    patch::edit as patchedit,
+    stack::{InitializationPolicy, Stack, StackAccess, StackState, StackStateAccess},
+    stupid::Stupid,
+};
+
+pub(super) const STGIT_COMMAND: super::StGitCommand = super::StGitCommand {
+    name: "cover",
+    category: super::CommandCategory::StackInspection,
+    make,
+    run,
+};
+
+pub(super) const SUBJECT_PLACEHOLDER: &str = "*** SUBJECT HERE ***";
+pub(super) const BLURB_PLACEHOLDER: &str = "*** BLURB HERE ***";
+
+fn make() -> clap::Command {
+    clap::Command::new(STGIT_COMMAND.name)
+        .about("Generate a cover letter for the applied patches")
+        .long_about(
+            "Generate a cover letter for the applied patches of the stack.\n\
+             \n\
+             The cover letter consists of a subject line and a blurb followed by a \
+             shortlog of the applied patches, grouped by author, and a diffstat of \
+             the combined changes of the applied patches. When '--interdiff' is \
+             specified, the diff between the given revision and the top of the \
+             stack is also included, e.g. to show the changes since a previously \
+             submitted version of the series. The revision may also name a stack \
+             state from the stack log, in which case the top of the stack as \
+             recorded in that state is used.\n\
+             \n\
+             The subject and blurb are kept in the 'branch.<branch>.stgit.cover' \
+             configuration variable such that they persist across invocations. Use \
+             '--edit' to interactively edit the stored subject and blurb. \
+             Placeholders are used when no subject and blurb are stored.\n\
+             \n\
+             The cover letter is not tied to any particular transport. It may be \
+             used with `stg email format` or `stg email send`, or as the \
+             description of a pull or merge request.",
+        )
+        .arg(argset::branch_arg())
+        .arg(
+            Arg::new("output")
+                .long("output")
+                .short('o')
+                .help("Write cover letter to <file> instead of stdout")
+                .value_name("file")
+                .value_hint(clap::ValueHint::FilePath)
+                .value_parser(clap::value_parser!(PathBuf)),
+        )
+        .arg(
+            Arg::new("edit")
+                .long("edit")
+                .short('e')
+                .help("Edit and store the subject and blurb")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("interdiff")
+                .long("interdiff")
+                .help("Include the diff between <rev> and the stack top")
+                .value_name("rev"),
+        )
+}
+
+/// Get the tree to compute the interdiff against.
+///
+/// A stack state revision, e.g. `refs/stacks/<branch>~1`, resolves to the tree of the
+/// top of the stack as it was recorded in that state.
+fn interdiff_tree_id(repo: &gix::Repository, rev: &str) -> Result<gix::ObjectId> {
+    let object = repo
+        .rev_parse_single_ex(rev)?
+        .object()?
+        .peel_tags_to_end()?;
+    if object.kind == gix::object::Kind::Commit {
+        let commit = object.into_commit();
+        if let Ok(state) = StackState::from_commit(repo, &commit) {
+            Ok(state.top().tree_id()?.detach())
+        } else {
+            Ok(commit.tree_id()?.detach())
+        }
+    } else {
+        Ok(object
+            .peel_to_tree()
+            .with_context(|| format!("getting tree of `{rev}`"))?
+            .id)
+    }
+}
+
+fn run(matches: &ArgMatches) -> Result<()> {
+    let repo = gix::Repository::open()?;
+    let stack = Stack::from_branch_locator(
+        &repo,
+        matches.get_one::<BranchLocator>("branch"),
+        InitializationPolicy::AllowUninitialized,
+    )?;
+    let stupid = repo.stupid();
+    let config = repo.config_snapshot();
+    let branch_name = stack.get_branch_name().to_string();
+
+    if stack.applied().is_empty() {
+        return Err(anyhow!("no patches applied"));
+    }
+
+    let mut shortlog: BTreeMap<BString, Vec<BString>> = BTreeMap::new();
+    for patchname in stack.applied() {
+        let patch_commit = stack.get_patch_commit(patchname);
+        let author = patch_commit.author_strict()?;
+        let message = patch_commit.message_raw()?;
+        let subject = message.lines().next().unwrap_or_default();
+        shortlog
+            .entry(author.name)
+            .or_default()
+            .push(BString::from(subject));
+    }
+
+    let mut generated = BString::default();
+    for (author_name, subjects) in &shortlog {
+        generated.push_str(author_name);
+        generated.push_str(format!(" ({}):\n", subjects.len()));
+        for subject in subjects {
+            generated.push_str("  ");
+            generated.push_str(subject);
+            generated.push_str("\n");
+        }
+        generated.push_str("\n");
+    }
+
+    let base_tree_id = stack.base().tree_id()?.detach();
+    let top_tree_id = stack.top().tree_id()?.detach();
+    let diff = stupid.diff_tree_patch(
+        base_tree_id,
+        top_tree_id,
+        <Option<Vec<OsString>>>::None,
+        false,
+        ["--full-index"],
+    )?;
+    generated.push_str(stupid.diffstat(diff.as_bstr())?);
+
+    if let Some(rev) = matches.get_one::<String>("interdiff") {
+        let interdiff_tree_id = interdiff_tree_id(&repo, rev)?;
+        generated.push_str(format!("\nInterdiff against {rev}:\n"));
+        generated.push_str(stupid.diff_tree_patch(
+            interdiff_tree_id,
+            top_tree_id,
+            <Option<Vec<OsString>>>::None,
+            false,
+            <Vec<OsString>>::new(),
+        )?);
+    }
+
+    let cover_key = format!("branch.{branch_name}.stgit.cover");
+    let mut description = config
+        .string(cover_key.as_str())
+        .map(|value| value.to_str_lossy().to_string())
+        .unwrap_or_default();
+
+    if matches.get_flag("edit") {
+        description = edit_description(&description, &generated, &config)?;
+        store_description(&repo, &branch_name, &description)?;
+    }
+
+    let mut cover = BString::default();
+    if description.trim().is_empty() {
+        cover.push_str(format!("{SUBJECT_PLACEHOLDER}\n\n{BLURB_PLACEHOLDER}\n"));
+    } else {
+        cover.push_str(description.trim_end());
+        cover.push_str("\n");
+    }
+    cover.push_str("\n");
+    cover.push_str(generated);
+
+    if let Some(path) = matches.get_one::<PathBuf>("output") {
+        std::fs::write(path, cover)
+            .with_context(|| format!("writing cover letter to `{}`", path.display()))?;
+    } else {
+        std::io::stdout().write_all(&cover)?;
+    }
+
+    Ok(())
+}
+
+/// Store the cover letter subject and blurb of the given branch.
+pub(super) fn store_description(
+    repo: &gix::Repository,
+    branch_name: &str,
+    description: &str,
+) -> Result<()> {
+    let mut local_config_file = repo.local_config_file()?;
+    let subsection = format!("{branch_name}.stgit");
+    local_config_file.set_raw_value(
+        "branch",
+        Some(subsection.as_str().into()),
+        "cover",
+        description,
+    )?;
+    repo.write_local_config(local_config_file)
+}
+
+/// Interactively edit the cover letter subject and blurb.
+///
+/// The generated portion of the cover letter is included as commented reference.
+fn edit_description(
+    description: &str,
+    generated: &[u8],
+    config: &gix::config::Snapshot,
+) -> Result<String> {
+    let filename = ".stgit-cover.txt";
+    let mut template = BString::default();
+    if description.trim().is_empty() {
+        template.push_str(format!("{SUBJECT_PLACEHOLDER}\n\n{BLURB_PLACEHOLDER}\n"));
+    } else {
+        template.push_str(description.trim_end());
+        template.push_str("\n");
+    }
+    template.push_str(
+        "\n\
+         # Please enter the cover letter subject on the first line followed by the\n\
+         # blurb. Lines starting with '#' will be ignored. The shortlog and diffstat\n\
+         # below are for reference and are generated anew for each cover letter.\n\
+         #\n",
+    );
+    for line in generated.lines() {
+        if line.is_empty() {
+            template.push_str("#\n");
+        } else {
+            template.push_str("# ");
+            template.push_str(line);
+            template.push_str("\n");
+        }
+    }
+    std::fs::write(filename, &template)?;
+
+    let buf = patchedit::call_editor(filename, config)?;
+    let buf = buf
+        .to_str()
+        .map_err(|_| anyhow!("`{filename}` is not valid UTF-8"))?;
+    let description = buf
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .collect::<Vec<_>>()
+        .join("\n");
+    Ok(description.trim().to_string())
+}
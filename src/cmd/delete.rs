@@ -110,6 +110,8 @@ fn run(matches: &ArgMatches) -> Result<()> {
         return Ok(());
     }
 
+    stack.backup("delete", &patches)?;
+
     stack
         .setup_transaction()
         .use_index_and_worktree(opt_branch.is_none() && !spill_flag)
@@ -12,7 +12,7 @@ use crate::{
     ext::RepositoryExtended,
     patch::{RangeRevisionSpec, StGitBoundaryRevisions},
     stack::Stack,
-    stupid::Stupid,
+    stupid::{DiffSummary, Stupid},
 };
 
 pub(super) const STGIT_COMMAND: super::StGitCommand = super::StGitCommand {
@@ -26,7 +26,7 @@ fn make() -> clap::Command {
     clap::Command::new(STGIT_COMMAND.name)
         .about("Show a diff")
         .long_about(
-            "Show the diff (default) or diffstat between the current working copy \
+            "Show the diff (default), diffstat, or numstat between the current working copy \
              or a tree-ish object and another tree-ish object (defaulting to HEAD). \
              File names can also be given to restrict the diff output. The \
              tree-ish object has the format accepted by the 'stg id' command.",
@@ -61,6 +61,22 @@ fn make() -> clap::Command {
                 .help("Show the stat instead of the diff")
                 .action(clap::ArgAction::SetTrue),
         )
+        .arg(
+            Arg::new("numstat")
+                .long("numstat")
+                .help("Show the numbers of added and deleted lines per file")
+                .long_help(
+                    "Instead of the diff, show the numbers of added and deleted lines \
+                     in decimal notation and the pathname of each changed file, as \
+                     with the '--numstat' option of git-diff(1).",
+                )
+                .action(clap::ArgAction::SetTrue)
+                .conflicts_with("stat"),
+        )
+        .arg(argset::find_renames_arg())
+        .arg(argset::find_copies_arg())
+        .arg(argset::word_diff_arg())
+        .arg(argset::color_moved_arg())
         .arg(argset::diff_opts_arg())
 }
 
@@ -78,10 +94,18 @@ fn run(matches: &ArgMatches) -> Result<()> {
         "HEAD".to_string()
     };
 
+    let summary = if matches.get_flag("stat") {
+        Some(DiffSummary::Stat)
+    } else if matches.get_flag("numstat") {
+        Some(DiffSummary::NumStat)
+    } else {
+        None
+    };
+
     repo.stupid().diff(
         &revspec,
         matches.get_many::<PathBuf>("pathspecs"),
-        matches.get_flag("stat"),
+        summary,
         crate::color::use_color(matches),
         argset::get_diff_opts(matches, &repo.config_snapshot(), false, false),
     )
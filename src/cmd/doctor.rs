@@ -0,0 +1,312 @@
+// SPDX-License-Identifier: GPL-2.0-only
+
+//! `stg doctor` implementation.
+
+use std::collections::BTreeSet;
+
+use anyhow::{anyhow, Result};
+use clap::{Arg, ArgMatches};
+
+use crate::{
+    argset,
+    branchloc::BranchLocator,
+    ext::RepositoryExtended,
+    patch::PatchName,
+    print_info_message,
+    stack::{state_refname_from_branch_name, RawStackState, StackState},
+};
+
+pub(super) const STGIT_COMMAND: super::StGitCommand = super::StGitCommand {
+    name: "doctor",
+    category: super::CommandCategory::Administration,
+    make,
+    run,
+};
+
+fn make() -> clap::Command {
+    clap::Command::new(STGIT_COMMAND.name)
+        .about("Check the integrity of the stack")
+        .long_about(
+            "Check the integrity of the stack and optionally fix the problems found.\n\
+             \n\
+             The following stack invariants are checked:\n\
+             \n\
+             - The stack state reference can be read and parsed. When it cannot, the \
+             most recent readable state from the stack log is restored.\n\
+             \n\
+             - The commit of every patch exists. Patches with missing commits are \
+             removed from the stack.\n\
+             \n\
+             - Each patch is listed exactly once as applied, unapplied, or hidden. \
+             Duplicate and dangling entries are removed and unlisted patches become \
+             unapplied.\n\
+             \n\
+             - Each applied patch is a child of the previous applied patch. Applied \
+             patches following a break in the chain become unapplied.\n\
+             \n\
+             - The stack head matches the topmost applied patch.\n\
+             \n\
+             - The branch head matches the stack head. This is fixed by running `stg \
+             repair`, which is only possible for the current branch.\n\
+             \n\
+             Without '--fix', the problems and their fixes are only reported and the \
+             command fails if any problems are found. With '--fix', the fixes are \
+             recorded as a new stack state which may be undone with `stg undo`.",
+        )
+        .arg(argset::branch_arg())
+        .arg(
+            Arg::new("fix")
+                .long("fix")
+                .help("Fix the problems found")
+                .action(clap::ArgAction::SetTrue),
+        )
+}
+
+/// A violated stack invariant along with a description of its fix, if any.
+struct Problem {
+    description: String,
+    fix: Option<String>,
+}
+
+impl Problem {
+    fn new(description: String, fix: &str) -> Self {
+        Self {
+            description,
+            fix: Some(fix.to_string()),
+        }
+    }
+}
+
+fn run(matches: &ArgMatches) -> Result<()> {
+    let repo = gix::Repository::open()?;
+    let opt_branch = matches.get_one::<BranchLocator>("branch");
+    let branch = if let Some(branch_loc) = opt_branch {
+        branch_loc.resolve(&repo)?
+    } else {
+        repo.get_current_branch()?
+    };
+    let branch_name = branch.get_branch_name()?.to_string();
+    let branch_head_id = branch.get_commit()?.id;
+    let is_current_branch = repo.get_current_branch().ok().map_or(false, |current| {
+        current.get_branch_name().ok() == Some(&branch_name)
+    });
+    let stack_refname = state_refname_from_branch_name(&branch_name);
+    let fix_flag = matches.get_flag("fix");
+
+    let state_commit_id = repo
+        .try_find_reference(stack_refname.as_str())?
+        .ok_or_else(|| anyhow!("StGit stack not initialized for branch `{branch_name}`"))?
+        .into_fully_peeled_id()
+        .map_err(|e| anyhow!("cannot resolve `{stack_refname}`: {e}"))?
+        .detach();
+
+    let mut problems: Vec<Problem> = Vec::new();
+
+    let mut raw = match read_raw_state(&repo, state_commit_id) {
+        Ok(mut raw) => {
+            // The state can only be recorded as the previous state of the fixed state
+            // if all of its patch commits exist.
+            let state_commit = repo.find_commit(state_commit_id)?;
+            if StackState::from_commit(&repo, &state_commit).is_ok() {
+                raw.prev = Some(state_commit_id);
+            }
+            raw
+        }
+        Err(e) => {
+            let (log_commit_id, mut raw) =
+                find_readable_state(&repo, state_commit_id).ok_or_else(|| {
+                    anyhow!(
+                        "stack state `{stack_refname}` is unreadable ({e:#}) and no readable \
+                         state was found in the stack log"
+                    )
+                })?;
+            problems.push(Problem::new(
+                format!("stack state `{stack_refname}` is unreadable: {e:#}"),
+                &format!("restore the stack state from log entry `{log_commit_id}`"),
+            ));
+            raw.prev = None;
+            raw
+        }
+    };
+
+    if repo.find_commit(raw.head).is_err() {
+        problems.push(Problem::new(
+            format!("stack head commit `{}` is missing", raw.head),
+            "use the branch head as the stack head",
+        ));
+        raw.head = branch_head_id;
+    }
+
+    let missing: Vec<PatchName> = raw
+        .patches
+        .iter()
+        .filter(|(_, patch)| repo.find_commit(patch.oid).is_err())
+        .map(|(patchname, _)| patchname.clone())
+        .collect();
+    for patchname in missing {
+        let oid = raw.patches.remove(&patchname).expect("patch exists").oid;
+        problems.push(Problem::new(
+            format!("commit `{oid}` of patch `{patchname}` is missing"),
+            &format!("remove `{patchname}` from the stack"),
+        ));
+        for list in [&mut raw.applied, &mut raw.unapplied, &mut raw.hidden] {
+            list.retain(|pn| pn != &patchname);
+        }
+    }
+
+    let mut listed: BTreeSet<PatchName> = BTreeSet::new();
+    for list in [&mut raw.applied, &mut raw.unapplied, &mut raw.hidden] {
+        list.retain(|patchname| {
+            if !raw.patches.contains_key(patchname) {
+                problems.push(Problem::new(
+                    format!("patch `{patchname}` is listed without a recorded commit"),
+                    &format!("remove `{patchname}` from the stack"),
+                ));
+                false
+            } else if !listed.insert(patchname.clone()) {
+                problems.push(Problem::new(
+                    format!("patch `{patchname}` is listed more than once"),
+                    "remove the duplicate entry",
+                ));
+                false
+            } else {
+                true
+            }
+        });
+    }
+    for patchname in raw.patches.keys() {
+        if !listed.contains(patchname) {
+            problems.push(Problem::new(
+                format!("patch `{patchname}` is not listed in the stack"),
+                &format!("make `{patchname}` unapplied"),
+            ));
+            raw.unapplied.push(patchname.clone());
+        }
+    }
+
+    let mut chain_break = None;
+    for (i, pair) in raw.applied.windows(2).enumerate() {
+        let parent_id = repo
+            .find_commit(raw.patches[&pair[1]].oid)?
+            .parent_ids()
+            .next()
+            .map(|id| id.detach());
+        if parent_id != Some(raw.patches[&pair[0]].oid) {
+            problems.push(Problem::new(
+                format!(
+                    "applied patch `{}` is not a child of `{}`",
+                    pair[1], pair[0]
+                ),
+                &format!("unapply the patches from `{}` onward", pair[1]),
+            ));
+            chain_break = Some(i + 1);
+            break;
+        }
+    }
+    if let Some(pos) = chain_break {
+        let unapplied: Vec<PatchName> = raw.applied.split_off(pos);
+        raw.unapplied.splice(0..0, unapplied);
+    }
+
+    if let Some(top_patchname) = raw.applied.last() {
+        let top_id = raw.patches[top_patchname].oid;
+        if raw.head != top_id {
+            problems.push(Problem::new(
+                format!("stack head does not match topmost patch `{top_patchname}`"),
+                &format!("use `{top_patchname}` as the stack head"),
+            ));
+            raw.head = top_id;
+        }
+    }
+
+    let has_state_problems = !problems.is_empty();
+
+    let needs_repair = raw.head != branch_head_id;
+    if needs_repair {
+        problems.push(Problem {
+            description: "branch head does not match stack head".to_string(),
+            fix: if is_current_branch {
+                Some("run `stg repair`".to_string())
+            } else {
+                None
+            },
+        });
+    }
+
+    if problems.is_empty() {
+        print_info_message(matches, "no problems found");
+        return Ok(());
+    }
+
+    for problem in &problems {
+        println!("problem: {}", problem.description);
+        if let Some(fix) = problem.fix.as_ref() {
+            println!("    fix: {fix}");
+        } else {
+            println!("    fix: none; check out `{branch_name}` and run `stg repair`");
+        }
+    }
+
+    if !fix_flag {
+        return Err(anyhow!(
+            "found {} problem{}; use `stg doctor --fix` to apply the fixes",
+            problems.len(),
+            if problems.len() == 1 { "" } else { "s" }
+        ));
+    }
+
+    if has_state_problems {
+        let state = StackState::from_raw_state(&repo, raw)?;
+        state.commit(&repo, Some(&stack_refname), "doctor")?;
+    }
+
+    if needs_repair && is_current_branch {
+        (super::repair::STGIT_COMMAND.run)(matches)?;
+    }
+
+    let unfixed = problems
+        .iter()
+        .filter(|problem| problem.fix.is_none())
+        .count();
+    if unfixed > 0 {
+        Err(anyhow!(
+            "{unfixed} problem{} could not be fixed",
+            if unfixed == 1 { "" } else { "s" }
+        ))
+    } else {
+        print_info_message(
+            matches,
+            &format!(
+                "fixed {} problem{}",
+                problems.len(),
+                if problems.len() == 1 { "" } else { "s" }
+            ),
+        );
+        Ok(())
+    }
+}
+
+/// Read the raw stack state recorded in the given stack state commit.
+fn read_raw_state(repo: &gix::Repository, commit_id: gix::ObjectId) -> Result<RawStackState> {
+    RawStackState::from_tree(repo.find_commit(commit_id)?.tree()?)
+}
+
+/// Find the most recent readable stack state in the stack log.
+///
+/// The log is walked by following first parents from the given stack state commit.
+fn find_readable_state(
+    repo: &gix::Repository,
+    state_commit_id: gix::ObjectId,
+) -> Option<(gix::ObjectId, RawStackState)> {
+    let mut next_id = Some(state_commit_id);
+    while let Some(commit_id) = next_id {
+        let commit = repo.find_commit(commit_id).ok()?;
+        if commit_id != state_commit_id {
+            if let Ok(raw) = read_raw_state(repo, commit_id) {
+                return Some((commit_id, raw));
+            }
+        }
+        next_id = commit.parent_ids().next().map(|id| id.detach());
+    }
+    None
+}
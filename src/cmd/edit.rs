@@ -2,14 +2,23 @@
 
 //! `stg edit` implementation.
 
-use anyhow::{Context, Result};
+use std::{
+    collections::BTreeMap,
+    io::Write,
+    process::{Command, Stdio},
+};
+
+use anyhow::{anyhow, Context, Result};
+use bstr::ByteSlice;
 use clap::{Arg, ArgMatches, ValueHint};
 
 use crate::{
+    argset,
     color::get_color_stdout,
-    ext::RepositoryExtended,
-    patch::{patchedit, PatchLocator, SingleRevisionSpec},
+    ext::{CommitExtended, RepositoryExtended},
+    patch::{patchedit, patchrange, PatchName, PatchRange, RangeConstraint, SingleRevisionSpec},
     stack::{InitializationPolicy, Stack, StackStateAccess},
+    wrap::Message,
 };
 
 pub(super) const STGIT_COMMAND: super::StGitCommand = super::StGitCommand {
@@ -38,14 +47,66 @@ fn make() -> clap::Command {
              message). The StGit attempts to apply the modified diff to the patch's \
              parent tree. If the updated diff does not apply, no changes are made to \
              the patch and the edited patch is saved to a file which may be corrected \
-             and then fed-back into `stg edit --file`.",
+             and then fed-back into `stg edit --file`.\n\
+             \n\
+             Headers following the \"Patch\", \"Author\", and \"Date\" headers in an \
+             edited patch description, such as \"Message-Id\" or \"Change-Id\", are \
+             preserved as trailers of the patch's message.\n\
+             \n\
+             The '--exec' option rewrites the messages of one or more patches by \
+             piping each patch's message through the given shell command, similar to \
+             the '--msg-filter' option of `git filter-branch`. The command's output \
+             becomes the patch's new message. The name of the patch is available to \
+             the command in the STGIT_PATCH environment variable. Patch ranges may be \
+             given with '--exec', e.g. `stg edit --exec <command> ..` rewrites the \
+             messages of all applied patches.\n\
+             \n\
+             When more than one patch is given, e.g. as a patch range, or with \
+             '--all', the messages of all the selected patches are edited together in \
+             a single editor session. Each patch's message is preceded by a delimiter \
+             line naming the patch. Only patches whose messages are changed are \
+             updated. Trailer options, such as '--ack' or '--trailer', may instead be \
+             given to add the trailers to each selected patch's message without \
+             invoking the editor.",
         )
+        .override_usage(super::make_usage(
+            "stg edit",
+            &[
+                "[OPTIONS] [patch]",
+                "[--all | <patch>...]",
+                "--exec <command> [--all | <patch>...]",
+            ],
+        ))
         .arg(
             Arg::new("patch")
                 .help("Patch to edit")
-                .allow_hyphen_values(true)
-                .value_parser(clap::value_parser!(PatchLocator))
+                // .allow_hyphen_values() breaks parsing of options following the patch
+                .num_args(1..)
+                .value_parser(clap::value_parser!(PatchRange))
                 .value_hint(ValueHint::Other),
+        )
+        .arg(
+            Arg::new("all")
+                .long("all")
+                .short('a')
+                .help("Edit the messages of all unhidden patches")
+                .action(clap::ArgAction::SetTrue)
+                .conflicts_with("patch"),
+        )
+        .arg(
+            Arg::new("exec")
+                .long("exec")
+                .help("Rewrite patch messages by piping them through <command>")
+                .value_name("command")
+                .value_hint(ValueHint::CommandString)
+                .conflicts_with_all([
+                    "edit",
+                    "diff",
+                    "message",
+                    "file",
+                    "save-template",
+                    "set-tree",
+                ]),
         );
     patchedit::add_args(app, true, true).arg(
         Arg::new("set-tree")
@@ -71,8 +132,53 @@ fn run(matches: &ArgMatches) -> Result<()> {
     let stack = Stack::current(&repo, InitializationPolicy::AllowUninitialized)?;
     stack.check_head_top_mismatch()?;
 
-    let patchname = if let Some(patch_loc) = matches.get_one::<PatchLocator>("patch") {
-        patch_loc.resolve_name(&stack)?
+    let patch_specs: Vec<&PatchRange> = matches
+        .get_many::<PatchRange>("patch")
+        .map_or_else(Vec::new, Iterator::collect);
+    let opt_exec = argset::get_one_str(matches, "exec");
+    let is_multiple = matches.get_flag("all")
+        || patch_specs.len() > 1
+        || patch_specs
+            .first()
+            .map_or(false, |spec| !matches!(spec, PatchRange::Single(_)));
+
+    if opt_exec.is_some() || is_multiple {
+        let patchnames = if matches.get_flag("all") {
+            let patchnames: Vec<PatchName> = stack
+                .applied()
+                .iter()
+                .chain(stack.unapplied())
+                .cloned()
+                .collect();
+            if patchnames.is_empty() {
+                return Err(anyhow!("no patches to edit"));
+            }
+            patchnames
+        } else if !patch_specs.is_empty() {
+            patchrange::resolve_names(&stack, patch_specs, RangeConstraint::All)?
+        } else if let Some(top_patchname) = stack.applied().last() {
+            vec![top_patchname.clone()]
+        } else {
+            return Err(super::Error::NoAppliedPatches.into());
+        };
+        return if let Some(command) = opt_exec {
+            exec_message_filter(stack, matches, command, &patchnames)
+        } else if TRAILER_ARGS
+            .iter()
+            .any(|&id| matches.value_source(id) == Some(clap::parser::ValueSource::CommandLine))
+        {
+            add_message_trailers(stack, matches, &patchnames)
+        } else {
+            edit_messages(stack, matches, &patchnames)
+        };
+    }
+
+    let patchname = if let Some(PatchRange::Single(patch_loc)) = patch_specs.first() {
+        patch_loc
+            .expand_abbreviation(&stack)?
+            .as_ref()
+            .unwrap_or(patch_loc)
+            .resolve_name(&stack)?
     } else if let Some(top_patchname) = stack.applied().last() {
         top_patchname.clone()
     } else {
@@ -145,3 +251,301 @@ fn run(matches: &ArgMatches) -> Result<()> {
         }
     }
 }
+
+/// Rewrite the messages of the given patches by piping each through `command`.
+fn exec_message_filter(
+    stack: Stack,
+    matches: &ArgMatches,
+    command: &str,
+    patchnames: &[PatchName],
+) -> Result<()> {
+    let mut new_messages: BTreeMap<PatchName, String> = BTreeMap::new();
+    for patchname in patchnames {
+        let message = stack.get_patch_commit(patchname).message_ex();
+        let message = message.decode()?;
+        let new_message = run_message_filter(stack.repo, command, patchname, &message)?;
+        if new_message != message {
+            new_messages.insert(patchname.clone(), new_message);
+        }
+    }
+
+    rewrite_messages(stack, matches, &new_messages, "edit: exec")
+}
+
+/// Add the trailers given on the command line to the messages of the given patches.
+fn add_message_trailers(
+    stack: Stack,
+    matches: &ArgMatches,
+    patchnames: &[PatchName],
+) -> Result<()> {
+    for id in SINGLE_PATCH_ARGS
+        .iter()
+        .filter(|&id| !TRAILER_ARGS.contains(id))
+    {
+        if matches.value_source(id) == Some(clap::parser::ValueSource::CommandLine) {
+            return Err(anyhow!(
+                "`--{id}` cannot be used when editing multiple patches"
+            ));
+        }
+    }
+
+    let committer = stack.repo.get_committer()?;
+    let mut new_messages: BTreeMap<PatchName, String> = BTreeMap::new();
+    for patchname in patchnames {
+        let message = stack.get_patch_commit(patchname).message_ex();
+        let old_message = message.decode()?.to_string();
+        let new_message = patchedit::add_trailers(stack.repo, message, matches, committer, None)?;
+        let new_message = new_message.decode()?.to_string();
+        if new_message != old_message {
+            new_messages.insert(patchname.clone(), new_message);
+        }
+    }
+
+    rewrite_messages(stack, matches, &new_messages, "edit: trailers")
+}
+
+/// Edit the messages of the given patches together in a single editor session.
+fn edit_messages(stack: Stack, matches: &ArgMatches, patchnames: &[PatchName]) -> Result<()> {
+    for id in SINGLE_PATCH_ARGS {
+        if matches.value_source(id) == Some(clap::parser::ValueSource::CommandLine) {
+            return Err(anyhow!(
+                "`--{id}` cannot be used when editing multiple patches"
+            ));
+        }
+    }
+
+    let mut old_messages: BTreeMap<&PatchName, String> = BTreeMap::new();
+    let mut template = String::new();
+    for patchname in patchnames {
+        let message = stack.get_patch_commit(patchname).message_ex();
+        let message = message.decode()?;
+        let message = normalize_message(&message);
+        template.push_str(&format!(
+            "{MESSAGE_DELIMITER_START}{patchname}{MESSAGE_DELIMITER_END}\n"
+        ));
+        template.push_str(&message);
+        template.push('\n');
+        old_messages.insert(patchname, message);
+    }
+    template.push_str(BULK_EDIT_HELP_LINES);
+
+    let filename = ".stgit-edit-messages.txt";
+    std::fs::write(filename, template)?;
+    let buf = patchedit::call_editor(filename, &stack.repo.config_snapshot())?;
+    let buf = buf
+        .to_str()
+        .map_err(|_| anyhow!("`{filename}` is not valid UTF-8"))?;
+
+    let mut edited: Vec<(&str, String)> = Vec::new();
+    for line in buf.lines() {
+        if let Some(name) = line
+            .strip_prefix(MESSAGE_DELIMITER_START)
+            .and_then(|rest| rest.strip_suffix(MESSAGE_DELIMITER_END))
+        {
+            edited.push((name, String::new()));
+        } else if line.starts_with('#') {
+            continue;
+        } else if let Some((_, message)) = edited.last_mut() {
+            message.push_str(line);
+            message.push('\n');
+        } else if !line.trim().is_empty() {
+            return Err(anyhow!(
+                "text found before the first patch delimiter line in `{filename}`"
+            ));
+        }
+    }
+
+    let mut new_messages: BTreeMap<PatchName, String> = BTreeMap::new();
+    let mut seen: Vec<&str> = Vec::with_capacity(edited.len());
+    for (name, message) in &edited {
+        let (patchname, old_message) = old_messages
+            .iter()
+            .find(|(pn, _)| AsRef::<str>::as_ref(*pn) == *name)
+            .ok_or_else(|| anyhow!("unexpected patch `{name}` in `{filename}`"))?;
+        if seen.contains(name) {
+            return Err(anyhow!("patch `{name}` appears more than once"));
+        }
+        seen.push(name);
+        let message = normalize_message(message);
+        if message.is_empty() {
+            return Err(anyhow!("empty message for patch `{name}`"));
+        }
+        if &message != old_message {
+            new_messages.insert((*patchname).clone(), message);
+        }
+    }
+    for patchname in patchnames {
+        if !seen.contains(&AsRef::<str>::as_ref(patchname)) {
+            return Err(anyhow!("message for patch `{patchname}` is missing"));
+        }
+    }
+
+    rewrite_messages(stack, matches, &new_messages, "edit: messages")
+}
+
+/// Options that only apply when editing a single patch.
+const SINGLE_PATCH_ARGS: [&str; 18] = [
+    "edit",
+    "diff",
+    "message",
+    "file",
+    "save-template",
+    "set-tree",
+    "no-verify",
+    "signoff",
+    "ack",
+    "review",
+    "sign-by",
+    "ack-by",
+    "review-by",
+    "trailer",
+    "author",
+    "authname",
+    "authemail",
+    "authdate",
+];
+
+/// Trailer options which may be applied to multiple patches at once.
+const TRAILER_ARGS: [&str; 7] = [
+    "signoff",
+    "ack",
+    "review",
+    "sign-by",
+    "ack-by",
+    "review-by",
+    "trailer",
+];
+
+const MESSAGE_DELIMITER_START: &str = "=== patch: ";
+const MESSAGE_DELIMITER_END: &str = " ===";
+
+const BULK_EDIT_HELP_LINES: &str = "\
+# Edit the messages of the patches above. Each message follows a delimiter
+# line naming its patch; do not modify or remove these lines.
+# Lines starting with '#' are ignored.
+";
+
+/// Strip leading and trailing blank lines and trailing whitespace from a message.
+///
+/// A message consisting only of whitespace is normalized to the empty string.
+fn normalize_message(message: &str) -> String {
+    let mut normalized = String::with_capacity(message.len());
+    for line in message.trim_matches('\n').lines() {
+        normalized.push_str(line.trim_end());
+        normalized.push('\n');
+    }
+    let normalized = normalized.trim_matches('\n');
+    if normalized.is_empty() {
+        String::new()
+    } else {
+        normalized.to_string() + "\n"
+    }
+}
+
+/// Replace the messages of patches with the given new messages.
+///
+/// Applied patches above the lowest rewritten applied patch are recreated on top of
+/// their rewritten parents. The patches' trees are unchanged.
+fn rewrite_messages(
+    stack: Stack,
+    matches: &ArgMatches,
+    new_messages: &BTreeMap<PatchName, String>,
+    reflog_msg: &str,
+) -> Result<()> {
+    if new_messages.is_empty() {
+        return Ok(());
+    }
+
+    stack
+        .setup_transaction()
+        .use_index_and_worktree(true)
+        .with_output_stream(get_color_stdout(matches))
+        .transact(|trans| {
+            let mut rewritten_parent_id: Option<gix::ObjectId> = None;
+            let all_patchnames: Vec<PatchName> = trans.all_patches().cloned().collect();
+            for patchname in &all_patchnames {
+                let is_applied = trans.applied().contains(patchname);
+                let new_message = new_messages.get(patchname);
+                if new_message.is_none() && (!is_applied || rewritten_parent_id.is_none()) {
+                    continue;
+                }
+                let commit_id = {
+                    let commit = trans.get_patch_commit(patchname);
+                    let parent_id = match rewritten_parent_id {
+                        Some(parent_id) if is_applied => parent_id,
+                        _ => commit.get_parent_commit()?.id,
+                    };
+                    let message = new_message.map_or_else(
+                        || commit.message_ex(),
+                        |message| Message::from(message.as_str()),
+                    );
+                    let author = commit.author_strict()?;
+                    let committer =
+                        trans
+                            .repo()
+                            .get_rewrite_committer(Some(commit), author.time, false)?;
+                    trans.repo().commit_ex(
+                        &author,
+                        &committer,
+                        &message,
+                        commit.tree_id()?.detach(),
+                        [parent_id],
+                    )?
+                };
+                if is_applied {
+                    rewritten_parent_id = Some(commit_id);
+                }
+                trans.update_patch(patchname, commit_id)?;
+            }
+            Ok(())
+        })
+        .execute(reflog_msg)?;
+
+    Ok(())
+}
+
+/// Pipe a patch's message through the message filter command.
+fn run_message_filter(
+    repo: &gix::Repository,
+    command: &str,
+    patchname: &PatchName,
+    message: &str,
+) -> Result<String> {
+    let work_dir = repo
+        .work_dir()
+        .ok_or_else(|| anyhow!("cannot run message filter in a bare repository"))?;
+    let mut child = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .current_dir(work_dir)
+        .env("STGIT_PATCH", patchname.to_string())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .map_err(|e| anyhow!("could not run message filter `{command}`: {e}"))?;
+    // The filter may exit without reading all of its input, in which case its exit
+    // status determines the outcome.
+    match child
+        .stdin
+        .take()
+        .expect("stdin is piped")
+        .write_all(message.as_bytes())
+    {
+        Err(e) if e.kind() != std::io::ErrorKind::BrokenPipe => return Err(e.into()),
+        _ => {}
+    }
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "message filter `{command}` failed for patch `{patchname}`"
+        ));
+    }
+    let new_message = String::from_utf8(output.stdout)
+        .map_err(|_| anyhow!("message filter output for patch `{patchname}` is not UTF-8"))?;
+    if new_message.trim().is_empty() {
+        return Err(anyhow!(
+            "message filter produced an empty message for patch `{patchname}`"
+        ));
+    }
+    Ok(new_message)
+}
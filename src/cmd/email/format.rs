@@ -87,7 +87,12 @@ pub(super) fn command() -> clap::Command {
         .args(format_options())
         .next_help_heading("Message Options")
         .args(message_options())
-    // DIFF OPTIONS ???
+        .next_help_heading("Diff Options")
+        .args(diff_options())
+}
+
+fn diff_options() -> Vec<Arg> {
+    vec![argset::find_renames_arg(), argset::find_copies_arg()]
 }
 
 fn format_options() -> Vec<Arg> {
@@ -151,9 +156,18 @@ fn format_options() -> Vec<Arg> {
             .long("rfc")
             .help("Use [RFC PATCH] instead of [PATCH]")
             .long_help(
-                "Alias for '--subject-prefix=\"RFC PATCH\"'. RFC means \"Request For \
-                 Comments\"; use this when sending an experimental patch for \
-                 discussion rather than application.",
+                "Prepend \"RFC\" to the subject prefix, producing \"RFC PATCH\" by \
+                 default. RFC means \"Request For Comments\"; use this when sending an \
+                 experimental patch for discussion rather than application.",
+            )
+            .action(clap::ArgAction::SetTrue),
+        Arg::new("resend")
+            .long("resend")
+            .help("Use [RESEND PATCH] instead of [PATCH]")
+            .long_help(
+                "Prepend \"RESEND\" to the subject prefix, producing \"RESEND PATCH\" \
+                 by default. Use this when sending unchanged patches again. When \
+                 combined with '--rfc', the prefix becomes \"RESEND RFC PATCH\".",
             )
             .action(clap::ArgAction::SetTrue),
         Arg::new("subject-prefix")
@@ -162,7 +176,12 @@ fn format_options() -> Vec<Arg> {
             .long_help(
                 "Instead of the standard `[PATCH]` prefix in the subject line, instead \
                  use `[<prefix>]`. This allows for useful naming of a patch series, \
-                 and can be combined with the '--numbered' option.",
+                 and can be combined with the '--numbered' option.\n\
+                 \n\
+                 The default prefix may be configured with the `stgit.mail.prefix` \
+                 configuration variable, e.g. \"PATCH net-next\". The '--rfc', \
+                 '--resend', and '--reroll-count' options add to the configured or \
+                 given prefix.",
             )
             .value_name("prefix")
             .num_args(1),
@@ -479,11 +498,16 @@ pub(super) fn dispatch(matches: &clap::ArgMatches) -> Result<()> {
     // passed-through directly to `git format-patch`.
     let mut dummy_command = clap::Command::new("dummy")
         .args(format_options())
-        .args(message_options());
+        .args(message_options())
+        .args(diff_options());
     dummy_command.build();
 
     for arg in dummy_command.get_arguments() {
         let arg_id = arg.get_id().as_str();
+        if matches!(arg_id, "rfc" | "resend" | "subject-prefix") {
+            // The subject prefix is composed below.
+            continue;
+        }
         if matches!(
             matches.value_source(arg_id),
             Some(clap::parser::ValueSource::CommandLine)
@@ -495,7 +519,9 @@ pub(super) fn dispatch(matches: &clap::ArgMatches) -> Result<()> {
                 let values = matches.get_many::<String>(arg_id).unwrap();
                 assert!(indices.len() == values.len());
                 indices.into_iter().zip(values).for_each(|(index, value)| {
-                    if arg_id == "thread" && value.is_empty() {
+                    if matches!(arg_id, "thread" | "find-renames" | "find-copies")
+                        && value.is_empty()
+                    {
                         format_args.push((index, format!("--{long}")));
                     } else {
                         format_args.push((index, format!("--{long}={value}")));
@@ -511,6 +537,12 @@ pub(super) fn dispatch(matches: &clap::ArgMatches) -> Result<()> {
 
     let mut format_args = format_args.drain(..).map(|(_, s)| s).collect::<Vec<_>>();
 
+    if !matches.get_flag("keep-subject") {
+        if let Some(prefix) = super::subject_prefix(matches, &repo.config_snapshot(), None) {
+            format_args.push(format!("--subject-prefix={prefix}"));
+        }
+    }
+
     if let Some(values) = matches.get_many::<String>("git-format-patch-opt") {
         format_args.extend(values.cloned());
     }
@@ -6,6 +6,7 @@ mod format;
 mod send;
 
 use anyhow::Result;
+use bstr::ByteSlice;
 
 pub(super) const STGIT_COMMAND: super::StGitCommand = super::StGitCommand {
     name: "email",
@@ -36,6 +37,57 @@ fn make() -> clap::Command {
         .subcommand(send::command())
 }
 
+/// Compose the subject prefix for patch emails, e.g. "RFC PATCH net-next v2".
+///
+/// The base prefix is taken from the `--subject-prefix` option, the
+/// `stgit.mail.prefix` configuration variable, or git's `format.subjectPrefix`, in
+/// that order, and defaults to "PATCH". "RFC" and then "RESEND" are prepended for the
+/// `--rfc` and `--resend` options, and " v<n>" is appended for `reroll_count`.
+///
+/// `None` is returned if none of these options or configuration variables are set,
+/// i.e. when the default "PATCH" prefix would be used unchanged.
+pub(super) fn subject_prefix(
+    matches: &clap::ArgMatches,
+    config: &gix::config::Snapshot,
+    reroll_count: Option<&str>,
+) -> Option<String> {
+    let rfc = matches.try_get_one::<bool>("rfc").ok().flatten() == Some(&true);
+    let resend = matches.try_get_one::<bool>("resend").ok().flatten() == Some(&true);
+    let base = matches
+        .try_get_one::<String>("subject-prefix")
+        .ok()
+        .flatten()
+        .cloned()
+        .or_else(|| {
+            config
+                .string("stgit.mail.prefix")
+                .map(|prefix| prefix.to_str_lossy().to_string())
+        });
+
+    if base.is_none() && !rfc && !resend && reroll_count.is_none() {
+        return None;
+    }
+
+    let mut prefix = base
+        .or_else(|| {
+            config
+                .string("format.subjectprefix")
+                .map(|prefix| prefix.to_str_lossy().to_string())
+        })
+        .unwrap_or_else(|| "PATCH".to_string());
+    if rfc {
+        prefix.insert_str(0, "RFC ");
+    }
+    if resend {
+        prefix.insert_str(0, "RESEND ");
+    }
+    if let Some(reroll_count) = reroll_count {
+        prefix.push_str(" v");
+        prefix.push_str(reroll_count);
+    }
+    Some(prefix)
+}
+
 fn run(matches: &clap::ArgMatches) -> Result<()> {
     match matches.subcommand() {
         Some(("format", sub_matches)) => format::dispatch(sub_matches),
@@ -359,6 +359,10 @@ fn format_options() -> Vec<Arg> {
             .long("rfc")
             .help("Use [RFC PATCH] instead of [PATCH]")
             .action(clap::ArgAction::SetTrue),
+        Arg::new("resend")
+            .long("resend")
+            .help("Use [RESEND PATCH] instead of [PATCH]")
+            .action(clap::ArgAction::SetTrue),
         Arg::new("subject-prefix")
             .long("subject-prefix")
             .help("Use [<prefix>] instead of [PATCH]")
@@ -381,11 +385,13 @@ pub(super) fn dispatch(matches: &clap::ArgMatches) -> Result<()> {
     )?;
 
     let source_args = matches.get_many::<String>("patchranges-or-paths");
+    let mut sources_are_files = false;
     let sources = if let Some(patchranges_or_paths) = source_args {
         let patchranges_or_paths = patchranges_or_paths.collect::<Vec<_>>();
         if patchranges_or_paths.iter().all(|s| Path::new(s).is_dir())
             || patchranges_or_paths.iter().all(|s| Path::new(s).is_file())
         {
+            sources_are_files = true;
             patchranges_or_paths
                 .iter()
                 .map(ToString::to_string)
@@ -453,6 +459,10 @@ pub(super) fn dispatch(matches: &clap::ArgMatches) -> Result<()> {
 
     for arg in dummy_command.get_arguments() {
         let arg_id = arg.get_id().as_str();
+        if matches!(arg_id, "rfc" | "resend" | "subject-prefix") {
+            // The subject prefix is composed below.
+            continue;
+        }
         if matches!(
             matches.value_source(arg_id),
             Some(clap::parser::ValueSource::CommandLine)
@@ -476,6 +486,14 @@ pub(super) fn dispatch(matches: &clap::ArgMatches) -> Result<()> {
 
     let mut send_args = send_args.drain(..).map(|(_, s)| s).collect::<Vec<_>>();
 
+    // The subject prefix only applies when the patches are formatted by git
+    // send-email, as opposed to already formatted email files being sent.
+    if !sources_are_files {
+        if let Some(prefix) = super::subject_prefix(matches, &repo.config_snapshot(), None) {
+            send_args.push(format!("--subject-prefix={prefix}"));
+        }
+    }
+
     if let Some(values) = matches.get_many::<String>("git-send-email-opt") {
         send_args.extend(values.cloned());
     }
@@ -10,16 +10,16 @@ use std::{
     path::{Path, PathBuf},
 };
 
-use anyhow::{Context, Result};
-use bstr::BStr;
+use anyhow::{anyhow, Context, Result};
+use bstr::{BStr, ByteSlice};
 use clap::Arg;
 
 use crate::{
     argset,
     branchloc::BranchLocator,
     ext::{CommitExtended, RepositoryExtended},
-    patch::{patchrange, PatchRange, RangeConstraint},
-    stack::{InitializationPolicy, Stack, StackAccess, StackStateAccess},
+    patch::{patchrange, PatchName, PatchRange, RangeConstraint},
+    stack::{InitializationPolicy, Stack, StackAccess, StackState, StackStateAccess},
     stupid::Stupid,
 };
 
@@ -35,7 +35,13 @@ fn make() -> clap::Command {
         .about("Export patches to a directory")
         .long_about(
             "Export a range of patches to a given directory in unified diff format. \
-             All applied patches are exported by default.\n\
+             All applied patches are exported by default. With '--all', the \
+             unapplied and hidden patches are exported as well.\n\
+             \n\
+             Unapplied and hidden patches are marked with \"# unapplied\" and \
+             \"# hidden\" comments in the exported series file. `stg import --series` \
+             recognizes these markers, allowing a whole stack to be round-tripped \
+             through the exported directory.\n\
              \n\
              Patches are exported to 'patches-<branch>' by default. The '--dir' option \
              may be used to specify a different output directory.\n\
@@ -45,6 +51,7 @@ fn make() -> clap::Command {
              or \"$(prefix)/share/stgit/templates\". The following variables are \
              supported in the template file:\n\
              \n    %(description)s - patch description\
+             \n    %(subjectprefix)s - the subject prefix, e.g. \"[PATCH v2] \", if any\
              \n    %(shortdescr)s  - the first line of the patch description\
              \n    %(longdescr)s   - the rest of the patch description, after the first line\
              \n    %(diffstat)s    - the diff statistics\
@@ -52,7 +59,17 @@ fn make() -> clap::Command {
              \n    %(authemail)s   - author email\
              \n    %(authdate)s    - patch creation date (ISO-8601 format)\
              \n    %(commname)s    - committer name\
-             \n    %(commemail)s   - committer email",
+             \n    %(commemail)s   - committer email\n\
+             \n\
+             A subject prefix is only used when one of the '--subject-prefix', \
+             '--rfc', '--resend', or '--reroll-count' options is given, or when the \
+             `stgit.mail.prefix` configuration variable is set. The prefix is composed \
+             in the same way as by `stg email format`.\n\
+             \n\
+             When re-rolling a series, '--range-diff' may be used to also write a \
+             range-diff (see git-range-diff(1)) between a previous version of the \
+             series and the exported patches to 'range-diff.txt' in the output \
+             directory.",
         )
         .arg(
             Arg::new("patchranges")
@@ -66,9 +83,19 @@ fn make() -> clap::Command {
                 .value_name("patch")
                 .num_args(1..)
                 .allow_hyphen_values(true)
-                .value_parser(clap::value_parser!(PatchRange)),
+                .value_parser(clap::value_parser!(PatchRange))
+                .conflicts_with("label"),
+        )
+        .arg(
+            Arg::new("all")
+                .long("all")
+                .short('a')
+                .help("Export all patches, including unapplied and hidden patches")
+                .action(clap::ArgAction::SetTrue)
+                .conflicts_with_all(["patchranges", "label"]),
         )
         .arg(argset::branch_arg())
+        .arg(argset::label_arg().help("Export the applied and unapplied patches labeled <label>"))
         .arg(
             Arg::new("dir")
                 .long("dir")
@@ -118,6 +145,55 @@ fn make() -> clap::Command {
                 .conflicts_with("dir")
                 .action(clap::ArgAction::SetTrue),
         )
+        .arg(
+            Arg::new("subject-prefix")
+                .long("subject-prefix")
+                .help("Use [<prefix>] as subject prefix")
+                .value_name("prefix")
+                .num_args(1),
+        )
+        .arg(
+            Arg::new("rfc")
+                .long("rfc")
+                .help("Prepend \"RFC\" to the subject prefix")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("resend")
+                .long("resend")
+                .help("Prepend \"RESEND\" to the subject prefix")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("reroll-count")
+                .long("reroll-count")
+                .short('v')
+                .help("Mark the series as the <n>th reroll")
+                .value_name("n")
+                .num_args(1),
+        )
+        .arg(
+            Arg::new("range-diff")
+                .long("range-diff")
+                .help("Write a range-diff against <snapshot> to the output directory")
+                .long_help(
+                    "Write a range-diff against <snapshot> to 'range-diff.txt' in the \
+                     output directory.\n\
+                     \n\
+                     The snapshot may be a stack state, such as a commit id listed by \
+                     `stg log`, in which case the previous version of the series is \
+                     that state's applied patches. Any other commit, such as a tag \
+                     made when the previous version was exported, is taken as the tip \
+                     of the previous version, which is assumed to start at the current \
+                     stack base.\n\
+                     \n\
+                     The exported patches must be consecutive applied patches.",
+                )
+                .value_name("snapshot")
+                .conflicts_with("stdout"),
+        )
+        .arg(argset::find_renames_arg())
+        .arg(argset::find_copies_arg())
         .arg(argset::diff_opts_arg())
 }
 
@@ -147,6 +223,20 @@ fn run(matches: &clap::ArgMatches) -> Result<()> {
             range_specs,
             RangeConstraint::VisibleWithAppliedBoundary,
         )?
+    } else if let Some(label) = matches.get_one::<String>("label") {
+        let patches: Vec<PatchName> = stack
+            .applied()
+            .iter()
+            .chain(stack.unapplied().iter())
+            .filter(|pn| stack.get_patch(pn).labels.contains(label))
+            .cloned()
+            .collect();
+        if patches.is_empty() {
+            return Err(anyhow!("no patches labeled `{label}`"));
+        }
+        patches
+    } else if matches.get_flag("all") {
+        stack.all_patches().cloned().collect()
     } else {
         stack.applied().to_vec()
     };
@@ -155,6 +245,12 @@ fn run(matches: &clap::ArgMatches) -> Result<()> {
         return Err(super::Error::NoAppliedPatches.into());
     }
 
+    let range_diff_ranges = if let Some(snapshot) = argset::get_one_str(matches, "range-diff") {
+        Some(get_range_diff_ranges(&repo, &stack, &patches, snapshot)?)
+    } else {
+        None
+    };
+
     let default_output_dir;
     let output_dir = if let Some(dir) = matches.get_one::<PathBuf>("dir").map(PathBuf::as_path) {
         dir
@@ -176,7 +272,22 @@ fn run(matches: &clap::ArgMatches) -> Result<()> {
     let numbered_flag = matches.get_flag("numbered");
     let num_width = std::cmp::max(patches.len().to_string().len(), 2);
 
-    let diff_opts = argset::get_diff_opts(matches, &repo.config_snapshot(), false, true);
+    let config = repo.config_snapshot();
+    let mut diff_opts = Vec::new();
+    // Unlike git-diff(1), the git-diff-tree(1) plumbing used to generate the patches
+    // does not honor `diff.renames`, so it is applied here. Any explicit diff options
+    // come later and thus take precedence.
+    if let Some(renames) = config.string("diff.renames") {
+        match renames.to_str_lossy().to_lowercase().as_str() {
+            "copies" | "copy" => diff_opts.push(String::from("--find-copies")),
+            _ => {
+                if config.boolean("diff.renames").unwrap_or(false) {
+                    diff_opts.push(String::from("--find-renames"));
+                }
+            }
+        }
+    }
+    diff_opts.extend(argset::get_diff_opts(matches, &config, false, true));
 
     let template = if let Some(template_file) = matches.get_one::<PathBuf>("template") {
         Cow::Owned(std::fs::read_to_string(template_file)?)
@@ -190,6 +301,16 @@ fn run(matches: &clap::ArgMatches) -> Result<()> {
 
     let need_diffstat = template.contains("%(diffstat)");
 
+    let subject_prefix = super::email::subject_prefix(
+        matches,
+        &config,
+        matches
+            .get_one::<String>("reroll-count")
+            .map(String::as_str),
+    )
+    .map(|prefix| format!("[{prefix}] "))
+    .unwrap_or_default();
+
     let stdout_flag = matches.get_flag("stdout");
     let mut series = format!(
         "# This series applies on Git commit {}\n",
@@ -209,6 +330,11 @@ fn run(matches: &clap::ArgMatches) -> Result<()> {
         };
 
         series.push_str(&patchfile_name);
+        if stack.is_hidden(patchname) {
+            series.push_str(" # hidden");
+        } else if !stack.is_applied(patchname) {
+            series.push_str(" # unapplied");
+        }
         series.push('\n');
 
         let patch_commit = stack.get_patch_commit(patchname);
@@ -227,6 +353,10 @@ fn run(matches: &clap::ArgMatches) -> Result<()> {
         };
         replacements.insert("description", Cow::Borrowed(description.into()));
         replacements.insert("shortdescr", Cow::Borrowed(shortdescr.into()));
+        replacements.insert(
+            "subjectprefix",
+            Cow::Borrowed(subject_prefix.as_str().into()),
+        );
         replacements.insert("longdescr", Cow::Borrowed(longdescr.into()));
         let author = patch_commit.author()?;
         replacements.insert("authname", Cow::Borrowed(author.name));
@@ -300,5 +430,64 @@ fn run(matches: &clap::ArgMatches) -> Result<()> {
             .with_context(|| format!("writing {series_path:?}"))?;
     }
 
+    if let Some((old_range, new_range)) = range_diff_ranges {
+        let range_diff_path = output_dir.join("range-diff.txt");
+        std::fs::write(&range_diff_path, stupid.range_diff(old_range, new_range)?)
+            .with_context(|| format!("writing {range_diff_path:?}"))?;
+    }
+
     Ok(())
 }
+
+/// Base and top commit ids of a range of commits.
+type CommitRange = (gix::ObjectId, gix::ObjectId);
+
+/// Get the old and new commit ranges to compare with `git range-diff`.
+///
+/// The old range is determined from the given snapshot and the new range spans the
+/// exported patches.
+fn get_range_diff_ranges(
+    repo: &gix::Repository,
+    stack: &Stack,
+    patches: &[PatchName],
+    snapshot: &str,
+) -> Result<(CommitRange, CommitRange)> {
+    let first_pos = stack
+        .applied()
+        .iter()
+        .position(|pn| pn == &patches[0])
+        .filter(|&pos| stack.applied().get(pos..pos + patches.len()) == Some(patches))
+        .ok_or_else(|| {
+            anyhow!(
+                "`--range-diff` requires the exported patches to be consecutive applied patches"
+            )
+        })?;
+    let new_base = if first_pos == 0 {
+        stack.base().id
+    } else {
+        stack.get_patch_commit_id(&stack.applied()[first_pos - 1])
+    };
+    let new_top = stack.get_patch_commit_id(&patches[patches.len() - 1]);
+
+    let snapshot_commit = repo
+        .rev_parse_single_ex(snapshot)?
+        .object()?
+        .peel_tags_to_end()?
+        .try_into_commit()
+        .map_err(|_| anyhow!("range-diff snapshot `{snapshot}` is not a commit"))?;
+    let old_range = if let Ok(state) = StackState::from_commit(repo, &snapshot_commit) {
+        let old_base = if let Some(first_patchname) = state.applied().first() {
+            state
+                .get_patch_commit(first_patchname)
+                .get_parent_commit()?
+                .id
+        } else {
+            state.head().id
+        };
+        (old_base, state.top().id)
+    } else {
+        (stack.base().id, snapshot_commit.id)
+    };
+
+    Ok((old_range, (new_base, new_top)))
+}
@@ -31,7 +31,17 @@ fn make() -> clap::Command {
              the diff statistics for the given patch. Note that this command \
              does not show the files modified in the working tree and not yet \
              included in the patch by a 'refresh' command. Use the 'diff' or \
-             'status' commands to show these files.",
+             'status' commands to show these files.\n\
+             \n\
+             With '--find-renames', renamed files are detected and shown as, for \
+             example, 'R old -> new'.\n\
+             \n\
+             With '--format=json', the files are output as a JSON array for use by \
+             scripts. Each element is an object with the file's \"status\" letter, \
+             \"path\", and its \"old_mode\" and \"new_mode\", which are null for \
+             added and deleted files, respectively. Renamed files, which are detected \
+             with '--find-renames', additionally have an \"old_path\" and a \
+             \"similarity\" percentage.",
         )
         .arg(
             Arg::new("stgit-revision")
@@ -55,6 +65,85 @@ fn make() -> clap::Command {
                 .action(clap::ArgAction::SetTrue)
                 .conflicts_with("stat"),
         )
+        .arg(
+            Arg::new("find-renames")
+                .long("find-renames")
+                .short('M')
+                .help("Detect renamed files")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("format")
+                .long("format")
+                .help("Output files in <format>")
+                .long_help(
+                    "Output files in <format>. The schema of the 'json' format is \
+                     printed by 'stg schema files'.",
+                )
+                .value_name("format")
+                .value_parser(["text", "json"])
+                .default_value("text")
+                .conflicts_with_all(["stat", "bare"]),
+        )
+}
+
+/// Schema of the `--format json` output, for `stg schema`.
+pub(super) const JSON_SCHEMA: super::schema::JsonSchema = super::schema::JsonSchema {
+    command: "files",
+    version: 1,
+    schema: json_schema,
+};
+
+fn json_schema() -> serde_json::Value {
+    let mode = |description: &str| {
+        serde_json::json!({
+            "description": description,
+            "type": ["string", "null"],
+            "pattern": "^[0-7]{6}$",
+        })
+    };
+    serde_json::json!({
+        "type": "array",
+        "items": {
+            "type": "object",
+            "properties": {
+                "status": {
+                    "description": "Status letter as with git-diff-tree(1), e.g. 'A', 'M', or 'R'",
+                    "type": "string",
+                },
+                "path": {
+                    "description": "Path of the file in the patch",
+                    "type": "string",
+                },
+                "old_path": {
+                    "description": "Path of the file before a rename",
+                    "type": "string",
+                },
+                "similarity": {
+                    "description": "Similarity index percentage of a rename",
+                    "type": "integer",
+                    "minimum": 0,
+                    "maximum": 100,
+                },
+                "old_mode": mode("Octal mode of the file before the patch, if it existed"),
+                "new_mode": mode("Octal mode of the file after the patch, if it exists"),
+            },
+            "required": ["status", "path", "old_mode", "new_mode"],
+            "additionalProperties": false,
+        },
+    })
+}
+
+#[derive(serde::Serialize)]
+struct FileEntry {
+    status: String,
+    path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    old_path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    similarity: Option<u32>,
+    old_mode: Option<String>,
+    new_mode: Option<String>,
 }
 
 fn run(matches: &ArgMatches) -> Result<()> {
@@ -69,16 +158,68 @@ fn run(matches: &ArgMatches) -> Result<()> {
         Rc::new(repo.head_commit()?)
     };
     let parent = commit.get_parent_commit()?;
+
+    if matches
+        .get_one::<String>("format")
+        .map_or(false, |format| format == "json")
+    {
+        let entries: Vec<FileEntry> = repo
+            .stupid()
+            .diff_tree_raw(
+                parent.tree_id()?.detach(),
+                commit.tree_id()?.detach(),
+                matches.get_flag("find-renames"),
+            )?
+            .into_iter()
+            .map(|entry| FileEntry {
+                status: entry.status.to_string(),
+                path: entry.path.to_str_lossy().into_owned(),
+                old_path: entry
+                    .old_path
+                    .map(|old_path| old_path.to_str_lossy().into_owned()),
+                similarity: entry.similarity,
+                old_mode: entry.old_mode,
+                new_mode: entry.new_mode,
+            })
+            .collect();
+        let mut output = serde_json::to_vec_pretty(&entries)?;
+        output.push(b'\n');
+        std::io::stdout().write_all(&output)?;
+        return Ok(());
+    }
+
     let mut output = repo.stupid().diff_tree_files_status(
         parent.tree_id()?.detach(),
         commit.tree_id()?.detach(),
         matches.get_flag("stat"),
         matches.get_flag("bare"),
+        matches.get_flag("find-renames"),
         crate::color::use_color(matches),
     )?;
 
     let stdout = std::io::stdout();
     let mut stdout = stdout.lock();
+
+    if matches.get_flag("find-renames") && !matches.get_flag("stat") && !matches.get_flag("bare") {
+        for line in output.lines() {
+            let mut fields = line.splitn_str(3, b"\t");
+            if let (Some(status), Some(old_path), Some(new_path)) =
+                (fields.next(), fields.next(), fields.next())
+            {
+                // Renames are shown without their similarity score.
+                stdout.write_all(&status[..1])?;
+                stdout.write_all(b" ")?;
+                stdout.write_all(old_path)?;
+                stdout.write_all(b" -> ")?;
+                stdout.write_all(new_path)?;
+            } else {
+                stdout.write_all(&line.replacen(b"\t", b" ", 1))?;
+            }
+            stdout.write_all(b"\n")?;
+        }
+        return Ok(());
+    }
+
     for line in output.split_inclusive_mut(|b| *b == b'\t') {
         // Replace tab separator with space between status and filename.
         // This is done for compatibility with StGit <2.0.
@@ -14,7 +14,7 @@ use crate::{
     argset,
     color::get_color_stdout,
     ext::RepositoryExtended,
-    patch::{patchrange, PatchName, PatchRange, RangeConstraint},
+    patch::{patchrange, PatchLocator, PatchName, PatchRange, RangeConstraint},
     stack::{InitializationPolicy, Stack, StackStateAccess},
     stupid::Stupid,
 };
@@ -36,7 +36,11 @@ fn make() -> clap::Command {
              to be floated may currently be either applied or unapplied. The necessary \
              pop and push operations will be performed to float the named patches. \
              Patches not specified will remain applied or unapplied as they were prior \
-             to the float operation.",
+             to the float operation.\n\
+             \n\
+             The '--before' and '--after' options may be used to float the patches to \
+             a position immediately below or above another applied patch instead of \
+             to the top.",
         )
         .override_usage(super::make_usage(
             "stg float",
@@ -56,6 +60,15 @@ fn make() -> clap::Command {
             Arg::new("noapply")
                 .long("noapply")
                 .help("Reorder patches without reapplying any patches")
+                .long_help(
+                    "Reorder patches without reapplying any patches.\n\
+                     \n\
+                     The floated patches become the first unapplied patches, in the \
+                     order specified, such that they will be the next patches pushed. \
+                     Any floated patches that are currently applied are popped, which \
+                     requires a clean worktree unless '--keep' is used. Floating only \
+                     unapplied patches never touches the index or worktree.",
+                )
                 .action(clap::ArgAction::SetTrue),
         )
         .arg(
@@ -67,6 +80,36 @@ fn make() -> clap::Command {
                 .value_hint(clap::ValueHint::FilePath)
                 .value_parser(clap::value_parser!(PathBuf)),
         )
+        .arg(
+            Arg::new("before")
+                .long("before")
+                .help("Float patches below <target> patch")
+                .long_help(
+                    "Float patches below <target> patch.\n\
+                     \n\
+                     Specified patches are placed immediately below the applied \
+                     <target> patch instead of at the top of the stack.",
+                )
+                .value_name("target")
+                .allow_hyphen_values(true)
+                .value_parser(clap::value_parser!(PatchLocator))
+                .conflicts_with_all(["after", "noapply"]),
+        )
+        .arg(
+            Arg::new("after")
+                .long("after")
+                .help("Float patches above <target> patch")
+                .long_help(
+                    "Float patches above <target> patch.\n\
+                     \n\
+                     Specified patches are placed immediately above the applied \
+                     <target> patch instead of at the top of the stack.",
+                )
+                .value_name("target")
+                .allow_hyphen_values(true)
+                .value_parser(clap::value_parser!(PatchLocator))
+                .conflicts_with("noapply"),
+        )
         .arg(argset::keep_arg())
         .arg(argset::committer_date_is_author_date_arg())
 }
@@ -98,6 +141,16 @@ fn run(matches: &ArgMatches) -> Result<()> {
         return Err(anyhow!("no patches to float"));
     }
 
+    let opt_target = if let Some(locator) = matches.get_one::<PatchLocator>("before") {
+        let target = super::sink::resolve_target(&stack, locator, &patches, "float", "below")?;
+        Some((target, 0))
+    } else if let Some(locator) = matches.get_one::<PatchLocator>("after") {
+        let target = super::sink::resolve_target(&stack, locator, &patches, "float", "above")?;
+        Some((target, 1))
+    } else {
+        None
+    };
+
     if !keep_flag && (!noapply_flag || patches.iter().any(|pn| stack.is_applied(pn))) {
         statuses.check_index_and_worktree_clean()?;
     }
@@ -116,13 +169,22 @@ fn run(matches: &ArgMatches) -> Result<()> {
             .collect();
         (applied, unapplied)
     } else {
-        let applied: Vec<PatchName> = stack
+        let mut applied: Vec<PatchName> = stack
             .applied()
             .iter()
             .filter(|pn| !patches.contains(pn))
-            .chain(patches.iter())
             .cloned()
             .collect();
+        let target_pos = if let Some((target_patch, offset)) = &opt_target {
+            applied
+                .iter()
+                .position(|pn| pn == target_patch)
+                .expect("already validated that target is applied")
+                + offset
+        } else {
+            applied.len()
+        };
+        applied.splice(target_pos..target_pos, patches.iter().cloned());
         let unapplied: Vec<PatchName> = stack
             .unapplied()
             .iter()
@@ -32,7 +32,22 @@ fn make() -> clap::Command {
              With the '--threeway' option, the diff is applied onto the bottom of the \
              current patch and a three-way merge is performed with the current top. \
              With the '--base' option, the diff is applied onto the specified base and \
-             a three-way merge is performed with the current top.",
+             a three-way merge is performed with the current top.\n\
+             \n\
+             With the '--apply-3way' option, the diff is applied onto the current top \
+             and, only if it does not apply cleanly, a three-way merge is attempted \
+             using the blob ids recorded in the diff's index lines, as with `git apply \
+             --3way`. Unlike '--threeway', which always merges against the bottom of \
+             the current patch, this requires the blobs named by the diff to exist in \
+             the repository. Any conflicts are left in the index and work tree to be \
+             resolved before refreshing the current patch.\n\
+             \n\
+             Binary changes are applied from the binary data included in the diff. \
+             Binary changes without binary data may still be applied when the blobs \
+             named by the diff's index line exist in the repository.\n\
+             \n\
+             With the '--reject' option, the hunks that apply cleanly are applied and \
+             the rejected hunks are left in corresponding \".rej\" files.",
         )
         .arg(
             Arg::new("file")
@@ -77,6 +92,21 @@ fn make() -> clap::Command {
                 .help("Leave rejected hunks in \".rej\" files")
                 .action(clap::ArgAction::SetTrue),
         )
+        .arg(
+            Arg::new("apply-3way")
+                .long("apply-3way")
+                .short('3')
+                .help("Fall back to three-way merge using blob ids recorded in the diff")
+                .long_help(
+                    "Fall back to a three-way merge, as with `git apply --3way`, if the \
+                     diff does not apply cleanly. The merge uses the blobs that the diff \
+                     records it applies to, which must be available locally. This differs \
+                     from '--threeway', which merges against the bottom of the current \
+                     patch.",
+                )
+                .action(clap::ArgAction::SetTrue)
+                .conflicts_with_all(["three-way", "base", "reject"]),
+        )
 }
 
 fn run(matches: &clap::ArgMatches) -> Result<()> {
@@ -111,6 +141,8 @@ fn run(matches: &clap::ArgMatches) -> Result<()> {
 
     stupid.update_index_refresh()?;
 
+    let diff = super::import::expand_binary_index(&repo, diff.as_ref())?;
+
     let base_commit = if matches.get_flag("three-way") {
         Some(Rc::new(stack.top().get_parent_commit()?))
     } else if let Some(base_spec) = matches.get_one::<SingleRevisionSpec>("base") {
@@ -143,13 +175,22 @@ fn run(matches: &clap::ArgMatches) -> Result<()> {
             Err(super::Error::CausedConflicts("merge conflicts".to_string()).into())
         }
     } else {
-        stupid.apply_to_worktree_and_index(
-            diff.as_ref(),
-            reject_flag,
-            false,
-            strip_level,
-            None,
-            context_lines,
-        )
+        let threeway_flag = matches.get_flag("apply-3way");
+        stupid
+            .apply_to_worktree_and_index(
+                diff.as_ref(),
+                reject_flag,
+                threeway_flag,
+                strip_level,
+                None,
+                context_lines,
+            )
+            .or_else(|e| {
+                if threeway_flag && stupid.statuses(None)?.check_conflicts().is_err() {
+                    Err(super::Error::CausedConflicts("merge conflicts".to_string()).into())
+                } else {
+                    Err(e)
+                }
+            })
     }
 }
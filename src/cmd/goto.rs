@@ -25,6 +25,7 @@ fn make() -> clap::Command {
     clap::Command::new(STGIT_COMMAND.name)
         .about("Go to patch by pushing or popping as necessary")
         .arg(argset::keep_arg())
+        .arg(argset::force_dirty_arg())
         .arg(argset::merged_arg())
         .arg(argset::committer_date_is_author_date_arg())
         .arg(argset::push_conflicts_arg())
@@ -52,16 +53,33 @@ fn run(matches: &ArgMatches) -> Result<()> {
     let statuses = stupid.statuses(None)?;
     statuses.check_conflicts()?;
     stack.check_head_top_mismatch()?;
-    if !keep_flag {
-        statuses.check_index_and_worktree_clean()?;
-    }
 
-    let patchname = matches
+    let patch_loc = matches
         .get_one::<PatchLocator>("patch")
-        .expect("required argument")
+        .expect("required argument");
+    let patchname = patch_loc
+        .expand_abbreviation(&stack)?
+        .as_ref()
+        .unwrap_or(patch_loc)
         .resolve_name(&stack)?
         .constrain(&stack, LocationConstraint::Visible)?;
 
+    if matches.get_flag("force-dirty") {
+        let touched = if let Some(pos) = stack.applied().iter().position(|pn| pn == &patchname) {
+            &stack.applied()[pos + 1..]
+        } else {
+            let pos = stack
+                .unapplied()
+                .iter()
+                .position(|pn| pn == &patchname)
+                .expect("patch is visible and not applied");
+            &stack.unapplied()[..=pos]
+        };
+        super::pop::check_touched_paths_clean(&stack, touched)?;
+    } else if !keep_flag {
+        statuses.check_index_and_worktree_clean()?;
+    }
+
     stack
         .setup_transaction()
         .use_index_and_worktree(true)
@@ -0,0 +1,254 @@
+// SPDX-License-Identifier: GPL-2.0-only
+
+//! `stg grep` implementation.
+
+use std::{ffi::OsString, io::Write};
+
+use anyhow::{Context, Result};
+use bstr::ByteSlice;
+use clap::{Arg, ArgMatches};
+use termcolor::WriteColor;
+
+use crate::{
+    argset,
+    branchloc::BranchLocator,
+    color::get_color_stdout,
+    ext::{CommitExtended, RepositoryExtended},
+    patch::{patchrange, PatchName, PatchRange, RangeConstraint},
+    stack::{InitializationPolicy, Stack, StackStateAccess},
+    stupid::Stupid,
+};
+
+pub(super) const STGIT_COMMAND: super::StGitCommand = super::StGitCommand {
+    name: "grep",
+    category: super::CommandCategory::StackInspection,
+    make,
+    run,
+};
+
+fn make() -> clap::Command {
+    clap::Command::new(STGIT_COMMAND.name)
+        .about("Search patch messages and diffs")
+        .long_about(
+            "Search the messages and diffs of patches for lines matching a pattern.\n\
+             \n\
+             For each patch with a match, the patch name is printed followed by the \
+             matching lines of the patch's message and the diff hunks with matching \
+             added or removed lines. Context lines of the diff are not searched.\n\
+             \n\
+             Patterns are regular expressions. When multiple patterns are given, \
+             lines matching any of the patterns are matched.\n\
+             \n\
+             By default, all applied and unapplied patches are searched. A subset of \
+             patches may be searched by specifying patch names or patch ranges of the \
+             form '[begin-patch]..[end-patch]'.",
+        )
+        .override_usage(super::make_usage(
+            "stg grep",
+            &["[OPTIONS] -e <pattern>... [patch]..."],
+        ))
+        .arg(
+            Arg::new("patchranges")
+                .help("Patches to search")
+                .value_name("patch")
+                .num_args(1..)
+                .allow_hyphen_values(true)
+                .value_parser(clap::value_parser!(PatchRange)),
+        )
+        .arg(argset::branch_arg())
+        .arg(
+            Arg::new("regexp")
+                .long("regexp")
+                .short('e')
+                .help("Search for lines matching <pattern>")
+                .value_name("pattern")
+                .required(true)
+                .action(clap::ArgAction::Append)
+                .allow_hyphen_values(true),
+        )
+        .arg(
+            Arg::new("ignore-case")
+                .long("ignore-case")
+                .short('i')
+                .help("Ignore case differences when matching")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("patches-only")
+                .long("patches-only")
+                .short('l')
+                .help("Only print the names of matching patches")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("all")
+                .long("all")
+                .short('a')
+                .help("Also search hidden patches")
+                .action(clap::ArgAction::SetTrue)
+                .conflicts_with("patchranges"),
+        )
+}
+
+fn run(matches: &ArgMatches) -> Result<()> {
+    let repo = gix::Repository::open()?;
+    let stack = Stack::from_branch_locator(
+        &repo,
+        matches.get_one::<BranchLocator>("branch"),
+        InitializationPolicy::AllowUninitialized,
+    )?;
+
+    let patterns: Vec<&String> = matches
+        .get_many::<String>("regexp")
+        .expect("required argument")
+        .collect();
+    let pattern = patterns
+        .iter()
+        .map(|pattern| format!("(?:{pattern})"))
+        .collect::<Vec<_>>()
+        .join("|");
+    let regex = regex::bytes::RegexBuilder::new(&pattern)
+        .case_insensitive(matches.get_flag("ignore-case"))
+        .build()
+        .context("invalid pattern")?;
+
+    let patchnames: Vec<PatchName> =
+        if let Some(range_specs) = matches.get_many::<PatchRange>("patchranges") {
+            patchrange::resolve_names(&stack, range_specs, RangeConstraint::All)?
+        } else if matches.get_flag("all") {
+            stack.all_patches().cloned().collect()
+        } else {
+            stack
+                .applied()
+                .iter()
+                .chain(stack.unapplied().iter())
+                .cloned()
+                .collect()
+        };
+
+    let patches_only = matches.get_flag("patches-only");
+    let stupid = repo.stupid();
+    let mut stdout = get_color_stdout(matches);
+    let mut color_spec = termcolor::ColorSpec::new();
+    let mut is_first = true;
+
+    for patchname in &patchnames {
+        let patch_commit = stack.get_patch_commit(patchname);
+        let parent_commit = patch_commit.get_parent_commit()?;
+        let message = patch_commit.message_raw()?;
+        let diff = stupid.diff_tree_patch(
+            parent_commit.tree_id()?.detach(),
+            patch_commit.tree_id()?.detach(),
+            <Option<Vec<OsString>>>::None,
+            false,
+            ["--full-index"],
+        )?;
+
+        let message_lines: Vec<&[u8]> = message
+            .lines()
+            .filter(|line| regex.is_match(line))
+            .collect();
+        let diff_matches = grep_diff(&diff, &regex);
+
+        if message_lines.is_empty() && diff_matches.is_empty() {
+            continue;
+        }
+
+        if !patches_only && !is_first {
+            writeln!(stdout)?;
+        }
+        is_first = false;
+
+        stdout.set_color(color_spec.set_fg(Some(termcolor::Color::Yellow)))?;
+        write!(stdout, "{patchname}")?;
+        color_spec.clear();
+        stdout.set_color(&color_spec)?;
+        writeln!(stdout)?;
+
+        if patches_only {
+            continue;
+        }
+
+        for line in message_lines {
+            stdout.write_all(b"    ")?;
+            stdout.write_all(line)?;
+            stdout.write_all(b"\n")?;
+        }
+
+        for section in diff_matches {
+            stdout.write_all(section)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Find diff hunks with added or removed lines matching `regex`.
+///
+/// Each matching hunk is preceded by its file's diff header. The returned slices
+/// include line terminators.
+fn grep_diff<'a>(diff: &'a [u8], regex: &regex::bytes::Regex) -> Vec<&'a [u8]> {
+    let mut sections = Vec::new();
+    let mut file_header: Option<&[u8]> = None;
+    let mut hunk_start: Option<usize> = None;
+    let mut hunk_matched = false;
+    let mut file_start = 0;
+    let mut pos = 0;
+
+    let finish_hunk = |sections: &mut Vec<&'a [u8]>,
+                       file_header: &mut Option<&'a [u8]>,
+                       hunk_start: Option<usize>,
+                       hunk_matched: bool,
+                       end: usize| {
+        if let (Some(start), true) = (hunk_start, hunk_matched) {
+            if let Some(header) = file_header.take() {
+                sections.push(header);
+            }
+            sections.push(&diff[start..end]);
+        }
+    };
+
+    for line in diff.lines_with_terminator() {
+        if line.starts_with(b"diff --git ") {
+            finish_hunk(
+                &mut sections,
+                &mut file_header,
+                hunk_start,
+                hunk_matched,
+                pos,
+            );
+            hunk_start = None;
+            hunk_matched = false;
+            file_header = None;
+            file_start = pos;
+        } else if line.starts_with(b"@@") {
+            finish_hunk(
+                &mut sections,
+                &mut file_header,
+                hunk_start,
+                hunk_matched,
+                pos,
+            );
+            if hunk_start.is_none() {
+                file_header = Some(&diff[file_start..pos]);
+            }
+            hunk_start = Some(pos);
+            hunk_matched = false;
+        } else if hunk_start.is_some()
+            && (line.starts_with(b"+") || line.starts_with(b"-"))
+            && regex.is_match(line[1..].trim_end_with(|c| c == '\n' || c == '\r'))
+        {
+            hunk_matched = true;
+        }
+        pos += line.len();
+    }
+    finish_hunk(
+        &mut sections,
+        &mut file_header,
+        hunk_start,
+        hunk_matched,
+        pos,
+    );
+
+    sections
+}
@@ -27,7 +27,13 @@ fn make() -> clap::Command {
         .long_about(
             "Hide patches in the series.\n\
              \n\
-             Hidden patches are no longer shown in the plain 'series' output.",
+             Hidden patches are no longer shown in the plain 'series' output.\n\
+             \n\
+             Patches that remain hidden for longer than the number of days set by \
+             the \"stgit.hidden.expire\" configuration variable are considered \
+             expired. Depending on \"stgit.hidden.expire-action\", `stg clean \
+             --expired-hidden` either warns about expired hidden patches or prompts \
+             to delete them.",
         )
         .arg(
             Arg::new("patchranges")
@@ -2,7 +2,10 @@
 
 //! `stg id` implementation.
 
+use std::io::{BufRead, Write};
+
 use anyhow::Result;
+use bstr::ByteSlice;
 use clap::{Arg, ArgMatches};
 
 use crate::{
@@ -30,16 +33,38 @@ fn make() -> clap::Command {
              patches may be specified in the form '[<branch>:]<patch>' or \
              '[<branch>:]{base}' for the base of a stack. If no branch is \
              specified, the current branch is used by default. The parent \
-             of a patch may be specified with '[<branch>:]<patch>^'.",
+             of a patch may be specified with '[<branch>:]<patch>^'.\n\
+             \n\
+             Multiple revisions may be given, in which case the hash of each \
+             revision is printed on its own line. With '--batch', revisions are \
+             instead read from standard input, one per line, and the hash of each is \
+             printed as soon as it is resolved. A revision that cannot be resolved \
+             is reported as '<revision> missing' and does not stop the processing of \
+             later revisions. This allows scripts to resolve many revisions without \
+             running a separate process for each.",
         )
         .arg(argset::branch_arg())
         .arg(
             Arg::new("stgit-revision")
                 .value_name("revision")
+                .num_args(1..)
                 .allow_hyphen_values(true)
                 .value_parser(clap::value_parser!(SingleRevisionSpec))
                 .help("StGit revision"),
         )
+        .arg(
+            Arg::new("batch")
+                .long("batch")
+                .help("Read revisions from standard input")
+                .action(clap::ArgAction::SetTrue)
+                .conflicts_with("stgit-revision"),
+        )
+        .arg(
+            Arg::new("null")
+                .short('z')
+                .help("Use NUL instead of newline to terminate input and output lines")
+                .action(clap::ArgAction::SetTrue),
+        )
 }
 
 fn run(matches: &ArgMatches) -> Result<()> {
@@ -50,12 +75,46 @@ fn run(matches: &ArgMatches) -> Result<()> {
         InitializationPolicy::AllowUninitialized,
     )?;
 
-    let oid = matches
-        .get_one::<SingleRevisionSpec>("stgit-revision")
-        .map(|spec| spec.resolve_object(&repo, &stack).map(|object| object.id))
-        .transpose()?
-        .unwrap_or_else(|| stack.get_branch_head().id);
+    let terminator = if matches.get_flag("null") {
+        b'\0'
+    } else {
+        b'\n'
+    };
+    let mut stdout = std::io::stdout().lock();
+
+    if matches.get_flag("batch") {
+        for spec in std::io::stdin().lock().split(terminator) {
+            let spec = spec?;
+            if spec.is_empty() {
+                continue;
+            }
+            let oid = spec
+                .to_str()
+                .ok()
+                .and_then(|spec| spec.parse::<SingleRevisionSpec>().ok())
+                .and_then(|spec| spec.resolve_object(&repo, &stack).ok())
+                .map(|object| object.id);
+            if let Some(oid) = oid {
+                write!(stdout, "{oid}")?;
+            } else {
+                stdout.write_all(&spec)?;
+                stdout.write_all(b" missing")?;
+            }
+            stdout.write_all(&[terminator])?;
+            stdout.flush()?;
+        }
+    } else if let Some(specs) = matches.get_many::<SingleRevisionSpec>("stgit-revision") {
+        let oids = specs
+            .map(|spec| spec.resolve_object(&repo, &stack).map(|object| object.id))
+            .collect::<Result<Vec<_>>>()?;
+        for oid in oids {
+            write!(stdout, "{oid}")?;
+            stdout.write_all(&[terminator])?;
+        }
+    } else {
+        write!(stdout, "{}", stack.get_branch_head().id)?;
+        stdout.write_all(&[terminator])?;
+    }
 
-    println!("{oid}");
     Ok(())
 }
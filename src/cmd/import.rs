@@ -44,6 +44,22 @@ fn make() -> clap::Command {
              allows the patches source to be fetched from a url instead of from a \
              local file.\n\
              \n\
+             Patches marked with a \"# unapplied\" or \"# hidden\" comment in a series \
+             file, as written by `stg export`, are imported as unapplied or hidden \
+             patches, respectively.\n\
+             \n\
+             When the source is a directory of `git format-patch` output, the \
+             numbered patch files are imported in numeric order. If the directory \
+             contains a cover letter (0000-cover-letter.patch), its subject and blurb \
+             are stored as the branch's cover letter description for use by `stg \
+             cover`. Unedited cover letter skeletons are ignored.\n\
+             \n\
+             Binary changes are applied from the binary data included in the patch, \
+             as written by `stg export` and `git diff --binary`. Binary changes \
+             without binary data, i.e. \"Binary files ... differ\", may still be \
+             applied when the blobs named by the patch's abbreviated index line exist \
+             in the repository.\n\
+             \n\
              If a patch does not apply cleanly, the failed diff is written to a \
              .stgit-failed.patch file and an empty patch is added to the stack.\n\
              \n\
@@ -54,6 +70,7 @@ fn make() -> clap::Command {
             if cfg!(feature = "import-url") {
                 &[
                     "[OPTIONS] <diff-path>",
+                    "[OPTIONS] <format-patch-dir>",
                     "[OPTIONS] -m [<mail-path>|<Maildir-path>]",
                     "[OPTIONS] -M [<mbox-path>]",
                     "[OPTIONS] -S [<series-path>]",
@@ -65,6 +82,7 @@ fn make() -> clap::Command {
             } else {
                 &[
                     "[OPTIONS] <diff-path>",
+                    "[OPTIONS] <format-patch-dir>",
                     "[OPTIONS] -m [<mail-path>|<Maildir-path>]",
                     "[OPTIONS] -M [<mbox-path>]",
                     "[OPTIONS] -S [<series-path>]",
@@ -247,6 +265,8 @@ fn run(matches: &clap::ArgMatches) -> Result<()> {
         import_series(stack, matches, source_path.as_deref())
     } else if matches.get_flag("mail") || matches.get_flag("mbox") {
         import_mail(stack, matches, source_path.as_deref())
+    } else if let Some(source_dir) = source_path.as_deref().filter(|path| path.is_dir()) {
+        import_format_patch_dir(stack, matches, source_dir)
     } else {
         import_file(stack, matches, source_path.as_deref(), None)?;
         Ok(())
@@ -365,12 +385,14 @@ fn import_series(
     };
 
     let mut stack = stack;
+    let mut to_unapply: Vec<PatchName> = Vec::new();
+    let mut to_hide: Vec<PatchName> = Vec::new();
 
     for line in series.lines() {
-        let line = line
-            .find_char('#')
-            .map_or(line, |pos| &line[..pos])
-            .trim_with(|c| c.is_ascii_whitespace());
+        let (line, comment) = line.find_char('#').map_or((line, b"".as_bstr()), |pos| {
+            (&line[..pos], line[pos + 1..].as_bstr())
+        });
+        let line = line.trim_with(|c| c.is_ascii_whitespace());
         if line.is_empty() {
             continue;
         }
@@ -404,8 +426,36 @@ fn import_series(
             None
         };
 
+        let old_top = stack.applied().last().cloned();
         stack = import_file(stack, matches, Some(patch_path.as_path()), strip_level)?;
+        if let Some(new_top) = stack
+            .applied()
+            .last()
+            .filter(|pn| Some(*pn) != old_top.as_ref())
+        {
+            match comment.trim_with(|c| c.is_ascii_whitespace()).as_bytes() {
+                b"unapplied" => to_unapply.push(new_top.clone()),
+                b"hidden" => to_hide.push(new_top.clone()),
+                _ => {}
+            }
+        }
     }
+
+    if to_unapply.is_empty() && to_hide.is_empty() {
+        return Ok(());
+    }
+
+    stack
+        .setup_transaction()
+        .with_output_stream(get_color_stdout(matches))
+        .allow_conflicts(false)
+        .transact(|trans| {
+            let incidental =
+                trans.pop_patches(|pn| to_unapply.contains(pn) || to_hide.contains(pn))?;
+            trans.push_patches(&incidental, false)?;
+            trans.hide_patches(&to_hide)
+        })
+        .execute("import: restore unapplied and hidden patches")?;
     Ok(())
 }
 
@@ -454,6 +504,142 @@ fn import_mail(stack: Stack, matches: &clap::ArgMatches, source_path: Option<&Pa
     Ok(())
 }
 
+/// Import the patches of a directory of `git format-patch` output.
+///
+/// The numbered patch files are imported in numeric order. A cover letter, numbered
+/// zero, is not imported as a patch; instead its subject and blurb are stored as the
+/// branch's cover letter description.
+fn import_format_patch_dir(
+    stack: Stack,
+    matches: &clap::ArgMatches,
+    source_dir: &Path,
+) -> Result<()> {
+    let mut patch_paths: Vec<(usize, PathBuf)> = Vec::new();
+    for entry in source_dir.read_dir()? {
+        let entry = entry?;
+        if !entry.file_type()?.is_file() {
+            continue;
+        }
+        if let Some(number) = entry.file_name().to_str().and_then(format_patch_number) {
+            patch_paths.push((number, entry.path()));
+        }
+    }
+    patch_paths.sort();
+
+    let cover_path = if patch_paths
+        .first()
+        .map_or(false, |(number, _)| *number == 0)
+    {
+        Some(patch_paths.remove(0).1)
+    } else {
+        None
+    };
+
+    if patch_paths.is_empty() {
+        return Err(anyhow!(
+            "no format-patch files found in `{}`",
+            source_dir.display()
+        ));
+    }
+
+    let message_id = use_message_id(matches, &stack.repo.config_snapshot());
+    let stupid = stack.repo.stupid();
+    let cover_description = if let Some(cover_path) = cover_path {
+        let (mailinfo, message, _) =
+            stupid.mailinfo(Some(std::fs::File::open(cover_path)?), false)?;
+        let subject = Headers::parse_mailinfo(mailinfo.as_bstr())
+            .and_then(|headers| headers.subject)
+            .unwrap_or_default();
+        cover_description(&subject, &message.to_str_lossy())
+    } else {
+        None
+    };
+
+    let mut stack = stack;
+    for (_, patch_path) in patch_paths {
+        let patch_file = std::fs::File::open(patch_path)?;
+        let (mailinfo, message, diff) = stupid.mailinfo(Some(patch_file), message_id)?;
+        let headers = Headers::parse_mailinfo(mailinfo.as_bstr()).unwrap_or_default();
+        stack = create_patch(
+            stack,
+            matches,
+            None,
+            headers,
+            message.as_bstr(),
+            diff.as_bstr(),
+            None,
+        )?;
+    }
+
+    if let Some(description) = cover_description {
+        super::cover::store_description(stack.repo, stack.get_branch_name(), &description)?;
+    }
+
+    Ok(())
+}
+
+/// Get the number of a `git format-patch` output file from its file name.
+///
+/// File names have the form `[v<n>-]<number>-<subject>.patch` where the number has at
+/// least four digits.
+fn format_patch_number(file_name: &str) -> Option<usize> {
+    let name = file_name.strip_suffix(".patch")?;
+    let name = match name.split_once('-') {
+        Some((reroll, rest))
+            if reroll.len() > 1
+                && reroll.starts_with('v')
+                && reroll[1..].bytes().all(|b| b.is_ascii_digit()) =>
+        {
+            rest
+        }
+        _ => name,
+    };
+    let (number, _) = name.split_once('-')?;
+    if number.len() >= 4 && number.bytes().all(|b| b.is_ascii_digit()) {
+        number.parse().ok()
+    } else {
+        None
+    }
+}
+
+/// Get the cover letter description from a `git format-patch` cover letter.
+///
+/// The blurb is the part of the cover letter body preceding the shortlog. `None` is
+/// returned for unedited cover letter skeletons.
+fn cover_description(subject: &str, body: &str) -> Option<String> {
+    let blurb: Vec<&str> = body
+        .lines()
+        .take_while(|line| !is_shortlog_header(line))
+        .collect();
+    let blurb = blurb.join("\n");
+    let blurb = blurb.trim();
+    let subject = subject.trim();
+    let is_placeholder = |s: &str| {
+        s.is_empty()
+            || s == super::cover::SUBJECT_PLACEHOLDER
+            || s == super::cover::BLURB_PLACEHOLDER
+    };
+    if is_placeholder(subject) && is_placeholder(blurb) {
+        None
+    } else if blurb.is_empty() {
+        Some(subject.to_string())
+    } else {
+        Some(format!("{subject}\n\n{blurb}"))
+    }
+}
+
+/// Determine whether a cover letter line is a shortlog author line, e.g. `Name (2):`.
+fn is_shortlog_header(line: &str) -> bool {
+    line.strip_suffix("):")
+        .and_then(|line| line.rsplit_once(" ("))
+        .map_or(false, |(name, count)| {
+            !name.is_empty()
+                && !name.starts_with(char::is_whitespace)
+                && !count.is_empty()
+                && count.bytes().all(|b| b.is_ascii_digit())
+        })
+}
+
 fn read_gz(source_file: std::fs::File, content: &mut Vec<u8>) -> Result<()> {
     flate2::read::GzDecoder::new(source_file).read_to_end(content)?;
     Ok(())
@@ -611,8 +797,9 @@ fn create_patch<'repo>(
         stack.get_branch_head().tree_id()?.detach()
     } else {
         let stupid = stack.repo.stupid();
+        let diff = expand_binary_index(stack.repo, diff)?;
         stupid.apply_to_worktree_and_index(
-            diff,
+            diff.as_bstr(),
             matches.get_flag("reject"),
             matches.get_flag("3way"),
             strip_level,
@@ -668,6 +855,65 @@ fn stripname(name: &str) -> &str {
         .unwrap_or(name)
 }
 
+/// Expand abbreviated index lines of binary diffs lacking binary data.
+///
+/// `git apply` is only able to apply a "Binary files ... differ" diff when the diff's
+/// index line has full object ids and the postimage blob exists in the repository.
+/// Abbreviated ids, as found in diffs made without `--full-index`, are expanded if
+/// they resolve to blobs in the repository. Other diffs are left untouched.
+pub(super) fn expand_binary_index(repo: &gix::Repository, diff: &BStr) -> Result<BString> {
+    let mut expanded = BString::from(Vec::with_capacity(diff.len()));
+    let mut index_line: Option<(usize, usize)> = None;
+    for line in diff.lines_with_terminator() {
+        if line.starts_with(b"diff --git ") {
+            index_line = None;
+        } else if line.starts_with(b"index ") {
+            index_line = Some((expanded.len(), expanded.len() + line.len()));
+        } else if line.starts_with(b"Binary files ") {
+            if let Some((start, end)) = index_line.take() {
+                if let Some(full_line) = expand_index_line(repo, &expanded[start..end]) {
+                    expanded.splice(start..end, Vec::from(full_line));
+                }
+            }
+        }
+        expanded.push_str(line);
+    }
+    Ok(expanded)
+}
+
+/// Expand the object ids of a diff's index line to full ids.
+///
+/// Returns `None` if the line is malformed or any abbreviated id does not resolve to a
+/// blob in the repository.
+fn expand_index_line(repo: &gix::Repository, line: &[u8]) -> Option<BString> {
+    let content = line.strip_prefix(b"index ")?.trim_end();
+    let (ids, mode) = if let Some((ids, mode)) = content.split_once_str(" ") {
+        (ids, Some(mode))
+    } else {
+        (content, None)
+    };
+    let (old_id, new_id) = ids.split_once_str("..")?;
+    let expand_id = |abbrev: &[u8]| -> Option<gix::ObjectId> {
+        if !abbrev.is_empty() && abbrev.iter().all(|&c| c == b'0') {
+            Some(repo.object_hash().null())
+        } else {
+            let id = repo.rev_parse_single(abbrev.as_bstr()).ok()?;
+            (id.object().ok()?.kind == gix::object::Kind::Blob).then_some(id.detach())
+        }
+    };
+    let mut full_line = BString::from(format!(
+        "index {}..{}",
+        expand_id(old_id)?,
+        expand_id(new_id)?
+    ));
+    if let Some(mode) = mode {
+        full_line.push(b' ');
+        full_line.push_str(mode);
+    }
+    full_line.push(b'\n');
+    Some(full_line)
+}
+
 fn split_patch(content: Vec<u8>) -> Result<(BString, BString)> {
     let mut content = content;
     let mut pos = 0;
@@ -695,7 +941,7 @@ fn split_patch(content: Vec<u8>) -> Result<(BString, BString)> {
 mod test {
     use bstr::B;
 
-    use super::{split_patch, stripname};
+    use super::{cover_description, format_patch_number, split_patch, stripname};
 
     #[test]
     fn patch_without_message() {
@@ -799,6 +1045,31 @@ mod test {
         let name = String::from("01-patch-name.patch.diff");
         assert_eq!(stripname(&name), "patch-name.patch");
     }
+
+    #[test]
+    fn format_patch_numbers() {
+        assert_eq!(format_patch_number("0000-cover-letter.patch"), Some(0));
+        assert_eq!(format_patch_number("0012-add-feature.patch"), Some(12));
+        assert_eq!(format_patch_number("v2-0003-fix-bug.patch"), Some(3));
+        assert_eq!(format_patch_number("01-short-number.patch"), None);
+        assert_eq!(format_patch_number("0001-add-feature.diff"), None);
+        assert_eq!(format_patch_number("series"), None);
+    }
+
+    #[test]
+    fn cover_letter_description() {
+        let body = "The blurb\nspans lines.\n\nA U Thor (2):\n  add a\n  add b\n\n a | 1 +\n";
+        assert_eq!(
+            cover_description("The subject", body).as_deref(),
+            Some("The subject\n\nThe blurb\nspans lines.")
+        );
+        let skeleton = "*** BLURB HERE ***\n\nA U Thor (1):\n  add a\n";
+        assert_eq!(cover_description("*** SUBJECT HERE ***", skeleton), None);
+        assert_eq!(
+            cover_description("Only subject", "A U Thor (1):\n  add a\n").as_deref(),
+            Some("Only subject")
+        );
+    }
 }
 
 #[derive(Default, Debug)]
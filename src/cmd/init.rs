@@ -2,14 +2,24 @@
 
 //! `stg init` implementation.
 
-use anyhow::Result;
-use clap::ArgMatches;
+use std::{
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use anyhow::{anyhow, Context, Result};
+use bstr::ByteSlice;
+use clap::{Arg, ArgMatches};
 
 use crate::{
     argset,
     branchloc::BranchLocator,
+    color::get_color_stdout,
     ext::RepositoryExtended,
-    stack::{InitializationPolicy, Stack},
+    patch::PatchName,
+    stack::{InitializationPolicy, Stack, StackAccess},
+    stupid::Stupid,
+    wrap::{Message, PartialRefName},
 };
 
 pub(super) const STGIT_COMMAND: super::StGitCommand = super::StGitCommand {
@@ -40,17 +50,291 @@ fn make() -> clap::Command {
              StGit stack.\n\
              \n\
              StGit stack metadata can be deinitialized from a branch using `stg branch \
-             --cleanup`. See 'stg branch' for more details.",
+             --cleanup`. See 'stg branch' for more details.\n\
+             \n\
+             A stack template directory may be used to pre-seed the new stack. The \
+             template directory may contain any of the following:\n\
+             \n\
+             - 'series': names of empty patches to create, one per line, ordered \
+             from bottom to top. Text following '#' is ignored.\n\
+             - 'patches/<patchname>': the commit message for the named patch. The \
+             patch name is used as the message if this file does not exist.\n\
+             - 'trailers': trailer lines, e.g. \"Signed-off-by: A U Thor \
+             <author@example.com>\", added to each new patch's message.\n\
+             - 'config': a git config file. Each variable in its [stgit] section is \
+             set in the repository's config. Variables that StGit reads per branch, \
+             such as 'autostash' or 'pull-policy', are set as \
+             'branch.<branch>.stgit.<variable>'; all others are set as \
+             'stgit.<variable>'.\n\
+             \n\
+             The template is validated before the stack is initialized, and the \
+             stack is not left initialized if applying the template fails.\n\
+             \n\
+             The template directory is specified with '--template' or by the \
+             \"stgit.init.template\" configuration variable.",
         )
         .arg(argset::branch_arg())
+        .arg(
+            Arg::new("template")
+                .long("template")
+                .help("Seed the new stack from template <dir>")
+                .long_help(
+                    "Seed the new stack from the template directory <dir>. This \
+                     overrides the \"stgit.init.template\" configuration variable.",
+                )
+                .value_name("dir")
+                .value_hint(clap::ValueHint::DirPath)
+                .value_parser(clap::value_parser!(PathBuf)),
+        )
+        .arg(
+            Arg::new("no-template")
+                .long("no-template")
+                .help("Do not use the configured stack template")
+                .action(clap::ArgAction::SetTrue)
+                .conflicts_with("template"),
+        )
 }
 
+/// Configuration variables that StGit reads per branch, from the branch's
+/// `branch.<branch>.stgit` section.
+const BRANCH_CONFIG_KEYS: &[&str] = &[
+    "autostash",
+    "cover",
+    "fetchcmd",
+    "protect",
+    "public",
+    "pull-policy",
+    "pullcmd",
+    "rebasecmd",
+];
+
 fn run(matches: &ArgMatches) -> Result<()> {
     let repo = gix::Repository::open()?;
-    Stack::from_branch_locator(
+
+    let opt_template_dir = if matches.get_flag("no-template") {
+        None
+    } else if let Some(template_dir) = matches.get_one::<PathBuf>("template") {
+        Some(template_dir.clone())
+    } else {
+        repo.config_snapshot()
+            .string("stgit.init.template")
+            .and_then(|value| value.to_path().ok().map(Path::to_path_buf))
+    };
+
+    // The template is read in its entirety before initializing the stack such that an
+    // invalid template does not leave an initialized stack behind.
+    let opt_template = opt_template_dir
+        .map(|template_dir| {
+            Template::read(&template_dir)
+                .with_context(|| format!("reading template `{}`", template_dir.display()))
+                .map(|template| (template_dir, template))
+        })
+        .transpose()?;
+
+    let stack = Stack::from_branch_locator(
         &repo,
         matches.get_one::<BranchLocator>("branch"),
         InitializationPolicy::MustInitialize,
     )?;
+
+    if let Some((template_dir, template)) = opt_template {
+        let branch_name = PartialRefName::from_str(stack.get_branch_name())?;
+        if let Err(e) = template.apply(stack, matches) {
+            Stack::from_branch_name(
+                &repo,
+                &branch_name,
+                InitializationPolicy::RequireInitialized,
+            )
+            .and_then(Stack::deinitialize)
+            .context("deinitializing stack after failing to apply template")?;
+            return Err(e)
+                .with_context(|| format!("applying template `{}`", template_dir.display()));
+        }
+    }
+
     Ok(())
 }
+
+/// Contents of a stack template directory.
+struct Template {
+    /// Names and messages of the patches to create, from bottom to top.
+    patches: Vec<(PatchName, String)>,
+
+    /// Trailers added to each new patch's message.
+    trailers: Vec<(String, String)>,
+
+    /// Variables from the template config's `[stgit]` section.
+    config: Vec<(String, bstr::BString)>,
+}
+
+impl Template {
+    /// Read and validate the template in `template_dir`.
+    fn read(template_dir: &Path) -> Result<Self> {
+        if !template_dir.is_dir() {
+            return Err(anyhow!("template directory does not exist"));
+        }
+
+        let mut patches = Vec::new();
+        for patchname in read_template_series(&template_dir.join("series"))? {
+            let message_path = template_dir
+                .join("patches")
+                .join(patchname.as_ref() as &str);
+            let message = if message_path.is_file() {
+                std::fs::read_to_string(&message_path)
+                    .with_context(|| format!("reading `{}`", message_path.display()))?
+            } else {
+                format!("{patchname}\n")
+            };
+            patches.push((patchname, message));
+        }
+
+        let trailers = read_template_trailers(&template_dir.join("trailers"))?;
+
+        let mut config = Vec::new();
+        let config_path = template_dir.join("config");
+        if config_path.is_file() {
+            let template_config =
+                gix::config::File::from_path_no_includes(config_path, gix::config::Source::Local)?;
+            if let Some(sections) = template_config.sections_by_name("stgit") {
+                for section in
+                    sections.filter(|section| section.header().subsection_name().is_none())
+                {
+                    for key in section.keys() {
+                        if let Some(value) = section.value(key.as_ref()) {
+                            config.push((key.to_string(), value.into_owned()));
+                        }
+                    }
+                }
+            };
+        }
+
+        Ok(Self {
+            patches,
+            trailers,
+            config,
+        })
+    }
+
+    /// Seed a newly initialized stack with the template's patches and config.
+    ///
+    /// Variables that StGit reads per branch are set in the stack's branch section,
+    /// i.e. as `branch.<branch>.stgit.<variable>`. All other variables are set as
+    /// `stgit.<variable>` in the repository's config.
+    fn apply(self, stack: Stack, matches: &ArgMatches) -> Result<()> {
+        let repo = stack.repo;
+        let subsection = format!("{}.stgit", stack.get_branch_name());
+
+        if !self.patches.is_empty() {
+            let author = repo.get_author()?;
+            let committer = repo.get_committer()?;
+            let tree_id = stack.get_branch_head().tree_id()?.detach();
+            let mut parent_id = stack.get_branch_head().id;
+            let mut new_patches: Vec<(PatchName, gix::ObjectId)> =
+                Vec::with_capacity(self.patches.len());
+
+            for (patchname, message) in self.patches {
+                let message = if self.trailers.is_empty() {
+                    message
+                } else {
+                    let message_bytes = repo.stupid().interpret_trailers(
+                        message.as_bytes(),
+                        self.trailers
+                            .iter()
+                            .map(|(key, value)| (key.as_str(), value.as_str())),
+                    )?;
+                    String::from_utf8(message_bytes)
+                        .map_err(|_| anyhow!("could not decode message after adding trailers"))?
+                };
+                let commit_id = repo.commit_ex(
+                    author,
+                    committer,
+                    &Message::from(message),
+                    tree_id,
+                    [parent_id],
+                )?;
+                parent_id = commit_id;
+                new_patches.push((patchname, commit_id));
+            }
+
+            stack
+                .setup_transaction()
+                .with_output_stream(get_color_stdout(matches))
+                .transact(|trans| {
+                    for (patchname, commit_id) in &new_patches {
+                        trans.new_applied(patchname, *commit_id)?;
+                    }
+                    Ok(())
+                })
+                .execute("init: apply template")?;
+        }
+
+        if !self.config.is_empty() {
+            let mut local_config_file = repo.local_config_file()?;
+            for (key, value) in self.config {
+                let (section, subsection) =
+                    if BRANCH_CONFIG_KEYS.contains(&key.to_lowercase().as_str()) {
+                        ("branch", Some(subsection.as_str().into()))
+                    } else {
+                        ("stgit", None)
+                    };
+                local_config_file.set_raw_value(section, subsection, key, value.as_bstr())?;
+            }
+            repo.write_local_config(local_config_file)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Read patch names from a template's series file.
+///
+/// A missing series file is equivalent to an empty series.
+fn read_template_series(path: &Path) -> Result<Vec<PatchName>> {
+    let contents = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(e) => return Err(e).with_context(|| format!("reading `{}`", path.display())),
+    };
+
+    let mut patchnames: Vec<PatchName> = Vec::new();
+    for line in contents.lines() {
+        let name = line
+            .split_once('#')
+            .map_or(line, |(name, _comment)| name)
+            .trim();
+        if name.is_empty() {
+            continue;
+        }
+        let patchname = PatchName::from_str(name)?;
+        if let Some(colliding) = patchnames.iter().find(|pn| patchname.collides(pn)) {
+            return Err(anyhow!(
+                "patch `{patchname}` collides with `{colliding}` in template series"
+            ));
+        }
+        patchnames.push(patchname);
+    }
+    Ok(patchnames)
+}
+
+/// Read `<key>: <value>` trailer lines from a template's trailers file.
+///
+/// A missing trailers file is equivalent to having no trailers.
+fn read_template_trailers(path: &Path) -> Result<Vec<(String, String)>> {
+    let contents = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(e) => return Err(e).with_context(|| format!("reading `{}`", path.display())),
+    };
+
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            line.split_once(':')
+                .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+                .filter(|(key, _)| !key.is_empty())
+                .ok_or_else(|| anyhow!("invalid trailer line `{line}`"))
+        })
+        .collect()
+}
@@ -0,0 +1,145 @@
+// SPDX-License-Identifier: GPL-2.0-only
+
+//! `stg interdiff` implementation.
+
+use std::io::Write;
+
+use anyhow::{anyhow, Context, Result};
+use clap::{Arg, ArgMatches};
+
+use crate::{
+    argset,
+    branchloc::BranchLocator,
+    ext::RepositoryExtended,
+    patch::{patchrange, PatchName, PatchRange, RangeConstraint},
+    stack::{InitializationPolicy, Stack, StackState, StackStateAccess},
+    stupid::Stupid,
+};
+
+pub(super) const STGIT_COMMAND: super::StGitCommand = super::StGitCommand {
+    name: "interdiff",
+    category: super::CommandCategory::StackInspection,
+    make,
+    run,
+};
+
+fn make() -> clap::Command {
+    clap::Command::new(STGIT_COMMAND.name)
+        .about("Show interdiffs against a previous stack state")
+        .long_about(
+            "Show the changes to each patch, and to the stack as a whole, since a \
+             previously recorded stack state.\n\
+             \n\
+             The previous stack state may be given as any revision that resolves to \
+             a stack state commit, such as a commit id listed by `stg log`, a reflog \
+             entry of the stack state reference (e.g. 'refs/stacks/<branch>@{3}'), \
+             or a backup listed by `stg recover` (e.g. \
+             'refs/stgit-backup/<branch>/<n>').\n\
+             \n\
+             For each selected patch, the diff from the patch's previous tree to its \
+             current tree is shown. Patches are matched by name. Patches without a \
+             previous version are reported as new and, when no patches are given, \
+             previously applied patches that no longer exist are reported as \
+             removed. Unchanged patches are not shown. Finally, the diff from the \
+             previous topmost tree to the current topmost tree is shown.\n\
+             \n\
+             The applied patches are selected by default. Note that when the stack \
+             base changed between the two states, the interdiffs include the \
+             changes to the base.",
+        )
+        .arg(
+            Arg::new("state")
+                .help("Previous stack state to compare against")
+                .value_name("state")
+                .required(true),
+        )
+        .arg(
+            Arg::new("patchranges-all")
+                .help("Patches to show interdiffs for")
+                .value_name("patch")
+                .num_args(1..)
+                .allow_hyphen_values(true)
+                .value_parser(clap::value_parser!(PatchRange)),
+        )
+        .arg(argset::branch_arg())
+        .arg(argset::diff_opts_arg())
+}
+
+fn run(matches: &ArgMatches) -> Result<()> {
+    let repo = gix::Repository::open()?;
+    let stack = Stack::from_branch_locator(
+        &repo,
+        matches.get_one::<BranchLocator>("branch"),
+        InitializationPolicy::AllowUninitialized,
+    )?;
+    let stupid = repo.stupid();
+
+    let state_spec = argset::get_one_str(matches, "state").expect("required argument");
+    let state_commit = repo
+        .rev_parse_single_ex(state_spec)?
+        .object()?
+        .peel_tags_to_end()?
+        .try_into_commit()
+        .map_err(|_| anyhow!("target `{state_spec}` is not a commit"))?;
+    let prev_state = StackState::from_commit(&repo, &state_commit)
+        .with_context(|| format!("`{state_spec}` is not a stack state"))?;
+
+    let (patchnames, removed): (Vec<PatchName>, Vec<PatchName>) =
+        if let Some(range_specs) = matches.get_many::<PatchRange>("patchranges-all") {
+            (
+                patchrange::resolve_names(&stack, range_specs, RangeConstraint::All)?,
+                vec![],
+            )
+        } else {
+            (
+                stack.applied().to_vec(),
+                prev_state
+                    .applied()
+                    .iter()
+                    .filter(|pn| !stack.has_patch(pn))
+                    .cloned()
+                    .collect(),
+            )
+        };
+
+    let diff_opts = argset::get_diff_opts(matches, &repo.config_snapshot(), false, false);
+    let use_color = crate::color::use_color(matches);
+    let mut stdout = std::io::stdout();
+
+    for patchname in &patchnames {
+        if prev_state.has_patch(patchname) {
+            let prev_tree_id = prev_state.get_patch_commit(patchname).tree_id()?.detach();
+            let tree_id = stack.get_patch_commit(patchname).tree_id()?.detach();
+            if prev_tree_id != tree_id {
+                writeln!(stdout, "Interdiff for {patchname}:")?;
+                stdout.write_all(&stupid.diff_tree_patch(
+                    prev_tree_id,
+                    tree_id,
+                    None::<Vec<&str>>,
+                    use_color,
+                    &diff_opts,
+                )?)?;
+                writeln!(stdout)?;
+            }
+        } else {
+            writeln!(stdout, "New patch {patchname}\n")?;
+        }
+    }
+
+    for patchname in &removed {
+        writeln!(stdout, "Removed patch {patchname}\n")?;
+    }
+
+    let prev_top_tree_id = prev_state.top().tree_id()?.detach();
+    let top_tree_id = stack.top().tree_id()?.detach();
+    writeln!(stdout, "Interdiff for the stack:")?;
+    stdout.write_all(&stupid.diff_tree_patch(
+        prev_top_tree_id,
+        top_tree_id,
+        None::<Vec<&str>>,
+        use_color,
+        &diff_opts,
+    )?)?;
+
+    Ok(())
+}
@@ -0,0 +1,64 @@
+// SPDX-License-Identifier: GPL-2.0-only
+
+//! `stg label list` implementation.
+
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use clap::Arg;
+
+use crate::{
+    argset,
+    branchloc::BranchLocator,
+    ext::RepositoryExtended,
+    patch::{PatchLocator, PatchName},
+    stack::{InitializationPolicy, Stack, StackStateAccess},
+};
+
+pub(super) fn command() -> clap::Command {
+    clap::Command::new("list")
+        .about("List patch labels")
+        .long_about(
+            "List the labels of the given patch, one per line.\n\
+             \n\
+             When no patch is given, list all labels in use in the stack, each \
+             followed by the patches having that label in stack order.",
+        )
+        .arg(
+            Arg::new("patch")
+                .help("Patch to list labels of")
+                .value_name("patch")
+                .allow_hyphen_values(true)
+                .value_parser(clap::value_parser!(PatchLocator)),
+        )
+        .arg(argset::branch_arg())
+}
+
+pub(super) fn dispatch(matches: &clap::ArgMatches) -> Result<()> {
+    let repo = gix::Repository::open()?;
+    let stack = Stack::from_branch_locator(
+        &repo,
+        matches.get_one::<BranchLocator>("branch"),
+        InitializationPolicy::AllowUninitialized,
+    )?;
+
+    if let Some(locator) = matches.get_one::<PatchLocator>("patch") {
+        let patchname = locator.resolve_name(&stack)?;
+        for label in &stack.get_patch(&patchname).labels {
+            println!("{label}");
+        }
+    } else {
+        let mut labeled: BTreeMap<&str, Vec<&PatchName>> = BTreeMap::new();
+        for patchname in stack.all_patches() {
+            for label in &stack.get_patch(patchname).labels {
+                labeled.entry(label.as_str()).or_default().push(patchname);
+            }
+        }
+        for (label, patchnames) in labeled {
+            let patchnames: Vec<String> = patchnames.iter().map(|pn| pn.to_string()).collect();
+            println!("{label}: {}", patchnames.join(" "));
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,68 @@
+// SPDX-License-Identifier: GPL-2.0-only
+
+//! `stg label` implementation.
+
+mod add;
+mod list;
+mod remove;
+
+use anyhow::Result;
+
+use crate::patch::PatchRange;
+
+pub(super) const STGIT_COMMAND: super::StGitCommand = super::StGitCommand {
+    name: "label",
+    category: super::CommandCategory::PatchManipulation,
+    make,
+    run,
+};
+
+fn make() -> clap::Command {
+    clap::Command::new(STGIT_COMMAND.name)
+        .about("Manage patch labels")
+        .long_about(
+            "Manage labels attached to patches.\n\
+             \n\
+             Labels are used to group related patches, e.g. fixups or experimental \
+             changes, regardless of their position in the stack. Patches may then \
+             be selected by label using the '--label' option of `stg series`, `stg \
+             push`, `stg pop`, and `stg export`.\n\
+             \n\
+             Labels are recorded in the stack state and are carried along when a \
+             patch is refreshed, edited, pushed, or renamed. Labels may only contain \
+             ASCII alphanumeric characters, '-', '_', '.', and '/'.",
+        )
+        .subcommand_required(true)
+        .subcommand(add::command())
+        .subcommand(list::command())
+        .subcommand(remove::command())
+}
+
+fn run(matches: &clap::ArgMatches) -> Result<()> {
+    match matches.subcommand() {
+        Some(("add", sub_matches)) => add::dispatch(sub_matches),
+        Some(("list", sub_matches)) => list::dispatch(sub_matches),
+        Some(("remove", sub_matches)) => remove::dispatch(sub_matches),
+        _ => panic!("valid subcommand is expected"),
+    }
+}
+
+/// Argument for a single label.
+fn label_arg() -> clap::Arg {
+    clap::Arg::new("label")
+        .help("Label name")
+        .value_name("label")
+        .required(true)
+        .value_parser(crate::argset::parse_label)
+}
+
+/// Argument for the patches to add or remove a label to or from.
+fn patchranges_arg() -> clap::Arg {
+    clap::Arg::new("patchranges")
+        .help("Patches to add or remove the label")
+        .value_name("patch")
+        .num_args(1..)
+        .required(true)
+        .allow_hyphen_values(true)
+        .value_parser(clap::value_parser!(PatchRange))
+}
@@ -0,0 +1,69 @@
+// SPDX-License-Identifier: GPL-2.0-only
+
+//! `stg label remove` implementation.
+
+use anyhow::Result;
+
+use crate::{
+    argset,
+    branchloc::BranchLocator,
+    color::get_color_stdout,
+    ext::RepositoryExtended,
+    patch::{patchrange, PatchName, PatchRange, RangeConstraint},
+    stack::{InitializationPolicy, Stack, StackStateAccess},
+};
+
+pub(super) fn command() -> clap::Command {
+    clap::Command::new("remove")
+        .about("Remove a label from patches")
+        .long_about(
+            "Remove a label from one or more patches. Removing a label from a patch \
+             that does not have the label is a no-op.",
+        )
+        .arg(super::label_arg())
+        .arg(super::patchranges_arg())
+        .arg(argset::branch_arg())
+}
+
+pub(super) fn dispatch(matches: &clap::ArgMatches) -> Result<()> {
+    let repo = gix::Repository::open()?;
+    let stack = Stack::from_branch_locator(
+        &repo,
+        matches.get_one::<BranchLocator>("branch"),
+        InitializationPolicy::AllowUninitialized,
+    )?;
+    let label = matches
+        .get_one::<String>("label")
+        .expect("required argument");
+    let patches: Vec<PatchName> = patchrange::resolve_names(
+        &stack,
+        matches
+            .get_many::<PatchRange>("patchranges")
+            .expect("clap ensures at least one range is provided"),
+        RangeConstraint::All,
+    )?;
+
+    let to_unlabel: Vec<PatchName> = patches
+        .into_iter()
+        .filter(|pn| stack.get_patch(pn).labels.contains(label))
+        .collect();
+
+    if to_unlabel.is_empty() {
+        return Ok(());
+    }
+
+    stack
+        .setup_transaction()
+        .with_output_stream(get_color_stdout(matches))
+        .transact(|trans| {
+            for patchname in &to_unlabel {
+                let mut labels = trans.get_patch(patchname).labels.clone();
+                labels.remove(label);
+                trans.set_patch_labels(patchname, labels)?;
+            }
+            Ok(())
+        })
+        .execute(&format!("label remove: {label}"))?;
+
+    Ok(())
+}
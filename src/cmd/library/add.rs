@@ -0,0 +1,84 @@
+// SPDX-License-Identifier: GPL-2.0-only
+
+//! `stg library add` implementation.
+
+use anyhow::{anyhow, Result};
+use clap::Arg;
+
+use crate::{
+    argset,
+    branchloc::BranchLocator,
+    ext::RepositoryExtended,
+    patch::{PatchLocator, PatchName},
+    stack::{InitializationPolicy, Stack, StackStateAccess},
+};
+
+pub(super) fn command() -> clap::Command {
+    clap::Command::new("add")
+        .about("Store a patch in the library")
+        .long_about(
+            "Store a patch from a stack in the patch library. The library entry is \
+             named after the patch unless '--name' is specified.\n\
+             \n\
+             An existing library entry is only replaced when '--force' is specified.",
+        )
+        .arg(
+            Arg::new("patch")
+                .help("Patch to store in the library")
+                .value_name("patch")
+                .required(true)
+                .allow_hyphen_values(true)
+                .value_parser(clap::value_parser!(PatchLocator)),
+        )
+        .arg(argset::branch_arg())
+        .arg(
+            Arg::new("name")
+                .long("name")
+                .short('n')
+                .help("Use <name> for the library entry")
+                .value_name("name")
+                .value_parser(clap::value_parser!(PatchName)),
+        )
+        .arg(
+            Arg::new("force")
+                .long("force")
+                .short('f')
+                .help("Replace an existing library entry")
+                .action(clap::ArgAction::SetTrue),
+        )
+}
+
+pub(super) fn dispatch(matches: &clap::ArgMatches) -> Result<()> {
+    let repo = gix::Repository::open()?;
+    let stack = Stack::from_branch_locator(
+        &repo,
+        matches.get_one::<BranchLocator>("branch"),
+        InitializationPolicy::AllowUninitialized,
+    )?;
+
+    let patchname = matches
+        .get_one::<PatchLocator>("patch")
+        .expect("required argument")
+        .resolve_name(&stack)?;
+    let name = matches
+        .get_one::<PatchName>("name")
+        .unwrap_or(&patchname)
+        .clone();
+    let refname = super::get_library_refname(&name);
+
+    if !matches.get_flag("force") && repo.try_find_reference(refname.as_str())?.is_some() {
+        return Err(anyhow!(
+            "library entry `{name}` already exists; use `--force` to replace it"
+        ));
+    }
+
+    let patch_commit = stack.get_patch_commit(&patchname);
+    repo.reference(
+        refname.as_str(),
+        patch_commit.id,
+        gix::refs::transaction::PreviousValue::Any,
+        format!("library add: {patchname}"),
+    )?;
+
+    Ok(())
+}
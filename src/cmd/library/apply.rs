@@ -0,0 +1,88 @@
+// SPDX-License-Identifier: GPL-2.0-only
+
+//! `stg library apply` implementation.
+
+use anyhow::{anyhow, Result};
+use clap::Arg;
+
+use crate::{
+    color::get_color_stdout,
+    ext::RepositoryExtended,
+    patch::PatchName,
+    stack::{InitializationPolicy, Stack, StackStateAccess},
+    stupid::Stupid,
+};
+
+pub(super) fn command() -> clap::Command {
+    clap::Command::new("apply")
+        .about("Create a patch on the current stack from the library")
+        .long_about(
+            "Create a new patch on the current stack from a library entry. The new \
+             patch is named after the library entry unless '--name' is specified and \
+             is pushed on top of the stack unless '--noapply' is specified.",
+        )
+        .arg(
+            Arg::new("entry")
+                .help("Library entry to apply")
+                .value_name("entry")
+                .required(true)
+                .value_parser(clap::value_parser!(PatchName)),
+        )
+        .arg(
+            Arg::new("name")
+                .long("name")
+                .short('n')
+                .help("Use <name> for the new patch")
+                .value_name("name")
+                .value_parser(clap::value_parser!(PatchName)),
+        )
+        .arg(
+            Arg::new("noapply")
+                .long("noapply")
+                .help("Keep the new patch unapplied")
+                .action(clap::ArgAction::SetTrue),
+        )
+}
+
+pub(super) fn dispatch(matches: &clap::ArgMatches) -> Result<()> {
+    let repo = gix::Repository::open()?;
+    let stack = Stack::current(&repo, InitializationPolicy::AutoInitialize)?;
+
+    let entry_name = matches
+        .get_one::<PatchName>("entry")
+        .expect("required argument");
+    let entry_commit = super::find_entry(&repo, entry_name)?;
+
+    if !matches.get_flag("noapply") {
+        repo.check_repository_state()?;
+        repo.stupid()
+            .statuses(None)?
+            .check_index_and_worktree_clean()?;
+        stack.check_head_top_mismatch()?;
+    }
+
+    let patchname = if let Some(name) = matches.get_one::<PatchName>("name") {
+        if let Some(colliding_patchname) = stack.collides(name) {
+            return Err(anyhow!("patch name `{colliding_patchname}` already taken"));
+        }
+        name.clone()
+    } else {
+        let disallow: Vec<&PatchName> = stack.all_patches().collect();
+        entry_name.clone().uniquify(&[], &disallow)
+    };
+
+    stack
+        .setup_transaction()
+        .with_output_stream(get_color_stdout(matches))
+        .use_index_and_worktree(true)
+        .transact(|trans| {
+            trans.new_unapplied(&patchname, entry_commit.id, 0)?;
+            if !matches.get_flag("noapply") {
+                trans.push_patches(&[&patchname], false)?;
+            }
+            Ok(())
+        })
+        .execute(&format!("library apply: {entry_name}"))?;
+
+    Ok(())
+}
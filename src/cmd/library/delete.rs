@@ -0,0 +1,45 @@
+// SPDX-License-Identifier: GPL-2.0-only
+
+//! `stg library delete` implementation.
+
+use anyhow::Result;
+use clap::Arg;
+
+use crate::{ext::RepositoryExtended, patch::PatchName};
+
+pub(super) fn command() -> clap::Command {
+    clap::Command::new("delete")
+        .about("Delete entries from the library")
+        .long_about(
+            "Delete entries from the patch library. Patches previously created from \
+             the deleted entries are not affected.",
+        )
+        .arg(
+            Arg::new("entries")
+                .help("Library entries to delete")
+                .value_name("entry")
+                .num_args(1..)
+                .required(true)
+                .value_parser(clap::value_parser!(PatchName)),
+        )
+}
+
+pub(super) fn dispatch(matches: &clap::ArgMatches) -> Result<()> {
+    let repo = gix::Repository::open()?;
+    let names: Vec<&PatchName> = matches
+        .get_many::<PatchName>("entries")
+        .expect("required argument")
+        .collect();
+
+    // Ensure all entries exist before deleting any.
+    for name in &names {
+        super::find_entry(&repo, name)?;
+    }
+
+    for name in names {
+        repo.find_reference(super::get_library_refname(name).as_str())?
+            .delete()?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,51 @@
+// SPDX-License-Identifier: GPL-2.0-only
+
+//! `stg library list` implementation.
+
+use std::io::Write;
+
+use anyhow::Result;
+use clap::Arg;
+
+use crate::ext::{CommitExtended, RepositoryExtended};
+
+pub(super) fn command() -> clap::Command {
+    clap::Command::new("list")
+        .about("List library entries")
+        .long_about(
+            "List the entries of the patch library along with the subject of each \
+             entry's commit message.",
+        )
+        .arg(
+            Arg::new("bare")
+                .long("bare")
+                .help("Print bare entry names")
+                .long_help("Print bare entry names. This is useful for scripting.")
+                .action(clap::ArgAction::SetTrue),
+        )
+}
+
+pub(super) fn dispatch(matches: &clap::ArgMatches) -> Result<()> {
+    let repo = gix::Repository::open()?;
+    let entries = super::get_entries(&repo)?;
+    let name_width = entries
+        .iter()
+        .map(|(name, _)| name.len())
+        .max()
+        .unwrap_or_default();
+
+    let stdout = std::io::stdout();
+    let mut stdout = stdout.lock();
+    for (name, commit) in entries {
+        if matches.get_flag("bare") {
+            writeln!(stdout, "{name}")?;
+        } else {
+            let message = commit.message_ex();
+            let message = message.decode()?;
+            let subject = message.lines().next().unwrap_or_default();
+            writeln!(stdout, "{name:name_width$} # {subject}")?;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,98 @@
+// SPDX-License-Identifier: GPL-2.0-only
+
+//! `stg library` implementation.
+
+mod add;
+mod apply;
+mod delete;
+mod list;
+
+use std::str::FromStr;
+
+use anyhow::{anyhow, Result};
+use bstr::ByteSlice;
+
+use crate::patch::PatchName;
+
+pub(super) const STGIT_COMMAND: super::StGitCommand = super::StGitCommand {
+    name: "library",
+    category: super::CommandCategory::StackManipulation,
+    make,
+    run,
+};
+
+fn make() -> clap::Command {
+    clap::Command::new(STGIT_COMMAND.name)
+        .about("Manage a repository-wide library of reusable patches")
+        .long_about(
+            "Manage a repository-wide library of reusable patches.\n\
+             \n\
+             The patch library stores patches independently of any branch's stack. \
+             This is useful for local changes, such as debugging toggles or build \
+             tweaks, that are needed from time to time on many different stacks.\n\
+             \n\
+             Use `stg library add` to store a patch from a stack in the library and \
+             `stg library apply` to create a new patch on the current stack from a \
+             library entry.\n\
+             \n\
+             Each library entry is stored as a reference to the patch's commit in \
+             the `refs/library/` namespace. Library entries are thus local to the \
+             repository unless those references are explicitly pushed or fetched.",
+        )
+        .subcommand_required(true)
+        .subcommand(add::command())
+        .subcommand(apply::command())
+        .subcommand(delete::command())
+        .subcommand(list::command())
+}
+
+fn run(matches: &clap::ArgMatches) -> Result<()> {
+    match matches.subcommand() {
+        Some(("add", sub_matches)) => add::dispatch(sub_matches),
+        Some(("apply", sub_matches)) => apply::dispatch(sub_matches),
+        Some(("delete", sub_matches)) => delete::dispatch(sub_matches),
+        Some(("list", sub_matches)) => list::dispatch(sub_matches),
+        _ => panic!("valid subcommand is expected"),
+    }
+}
+
+const LIBRARY_REF_PREFIX: &str = "refs/library/";
+
+fn get_library_refname(name: &PatchName) -> String {
+    format!("{LIBRARY_REF_PREFIX}{name}")
+}
+
+/// Find the commit of the named library entry.
+fn find_entry<'repo>(repo: &'repo gix::Repository, name: &PatchName) -> Result<gix::Commit<'repo>> {
+    let reference = repo
+        .try_find_reference(get_library_refname(name).as_str())?
+        .ok_or_else(|| anyhow!("library entry `{name}` does not exist"))?;
+    Ok(reference
+        .into_fully_peeled_id()?
+        .object()?
+        .try_into_commit()?)
+}
+
+/// Get all library entries, sorted by name.
+fn get_entries(repo: &gix::Repository) -> Result<Vec<(PatchName, gix::Commit<'_>)>> {
+    let mut entries = Vec::new();
+    let references = repo.references()?;
+    for reference in references
+        .prefixed(LIBRARY_REF_PREFIX)?
+        .filter_map(Result::ok)
+    {
+        let name = reference
+            .name()
+            .as_bstr()
+            .to_str()
+            .ok()
+            .and_then(|refname| refname.strip_prefix(LIBRARY_REF_PREFIX))
+            .and_then(|name| PatchName::from_str(name).ok());
+        if let Some(name) = name {
+            let commit_id = reference.into_fully_peeled_id()?.detach();
+            entries.push((name, repo.find_object(commit_id)?.try_into_commit()?));
+        }
+    }
+    entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+    Ok(entries)
+}
@@ -2,15 +2,20 @@
 
 //! `stg log` implementation.
 
+use std::{collections::BTreeSet, io::Write};
+
 use anyhow::{anyhow, Result};
+use bstr::ByteSlice;
 use clap::{Arg, ArgMatches};
+use termcolor::WriteColor;
 
 use crate::{
     argset,
     branchloc::BranchLocator,
+    color::get_color_stdout,
     ext::RepositoryExtended,
-    patch::{patchrange, PatchRange, RangeConstraint},
-    stack::{InitializationPolicy, Stack, StackAccess},
+    patch::{patchrange, PatchName, PatchRange, RangeConstraint},
+    stack::{InitializationPolicy, RawStackState, Stack, StackAccess},
     stupid::Stupid,
 };
 
@@ -28,6 +33,21 @@ fn make() -> clap::Command {
             "Show the history of changes to the stack. If one or more patch names are \
              given, only the changes affecting those patches are shown.\n\
              \n\
+             The changes affecting a patch are found by comparing consecutive stack \
+             states. A change affects a patch when the patch is created, deleted, \
+             renamed, modified (e.g. refreshed or edited), or when it is pushed, \
+             hidden, or unhidden; popping a patch does not affect it. The history of \
+             a renamed patch includes the changes made under its previous names. \
+             Patches that are no longer in the stack, such as deleted patches, may \
+             also be given. The same changes are shown with '--graphical'.\n\
+             \n\
+             With '--diff', the stack state diff of each change is shown. When the \
+             'stgit.log.increments' configuration variable is enabled, the diff \
+             absorbed by a patch each time its content is changed in place, e.g. by \
+             'stg refresh', is recorded in the stack log. For the changes affecting \
+             the given patches, these recorded increments are shown instead of the \
+             stack state diff.\n\
+             \n\
              The 'stg undo' and 'stg redo' commands may be used to step back and forth \
              through historical stack states. The 'stg reset' command may be used to \
              reset the stack directly to a historic state.\n\
@@ -98,44 +118,256 @@ fn run(matches: &ArgMatches) -> Result<()> {
     )?;
 
     if matches.get_flag("clear") {
-        stack.clear_state_log("clear log")
+        return stack.clear_state_log("clear log");
+    }
+
+    let patchnames: Option<Vec<PatchName>> = if let Some(range_specs) =
+        matches.get_many::<PatchRange>("patchranges-all")
+    {
+        let mut patchnames = Vec::new();
+        for range in range_specs {
+            match patchrange::resolve_names(&stack, [range], RangeConstraint::All) {
+                Ok(names) => patchnames.extend(names),
+                // Patches no longer in the stack may still be found in the stack log.
+                Err(e) => patchnames.push(range.to_string().parse::<PatchName>().map_err(|_| e)?),
+            }
+        }
+        Some(patchnames)
     } else {
-        let pathspecs: Option<Vec<String>> =
-            if let Some(range_specs) = matches.get_many::<PatchRange>("patchranges-all") {
-                Some(
-                    patchrange::resolve_names(&stack, range_specs, RangeConstraint::All)?
-                        .iter()
-                        .map(|pn| format!("patches/{pn}"))
-                        .collect(),
-                )
-            } else {
-                None
-            };
-
-        let simplified_parent_id = stack
-            .repo
-            .find_reference(stack.get_stack_refname())?
-            .into_fully_peeled_id()?
-            .object()?
-            .to_commit_ref()
-            .parents()
-            .next()
-            .ok_or_else(|| anyhow!("`{}` does not have any parents", stack.get_stack_refname()))?;
-
-        let stupid = repo.stupid();
-
-        if matches.get_flag("graphical") {
-            stupid.gitk(simplified_parent_id, pathspecs)
+        None
+    };
+
+    let state_commit_id = stack
+        .repo
+        .find_reference(stack.get_stack_refname())?
+        .into_fully_peeled_id()?
+        .detach();
+    let simplified_parent_id = repo
+        .find_commit(state_commit_id)?
+        .parent_ids()
+        .next()
+        .ok_or_else(|| anyhow!("`{}` does not have any parents", stack.get_stack_refname()))?
+        .detach();
+
+    let stupid = repo.stupid();
+
+    let graphical_flag = matches.get_flag("graphical");
+    let num_commits = matches.get_one::<usize>("number").copied();
+    let use_color = crate::color::use_color(matches);
+    let full_flag = matches.get_flag("full");
+    let diff_flag = matches.get_flag("diff");
+
+    if let Some(patchnames) = patchnames {
+        let (mut entries, tracked) = track_patch_log(&repo, state_commit_id, &patchnames)?;
+        if let Some(n) = num_commits {
+            entries.truncate(n);
+        }
+        if entries.is_empty() {
+            return Ok(());
+        }
+        if graphical_flag {
+            stupid.gitk(&entries, true)
+        } else if diff_flag {
+            show_increments(&repo, matches, &entries, &tracked)
+        } else {
+            stupid.log(&entries, true, None, use_color, full_flag, diff_flag)
+        }
+    } else if graphical_flag {
+        stupid.gitk(&[simplified_parent_id], false)
+    } else {
+        stupid.log(
+            &[simplified_parent_id],
+            false,
+            num_commits,
+            use_color,
+            full_flag,
+            diff_flag,
+        )
+    }
+}
+
+/// Find the stack log entries affecting any of the given patches.
+///
+/// The stack states are walked from newest to oldest, comparing each state with its
+/// previous state. A state is selected when one of the patches is created, deleted,
+/// or renamed, or when its commit or its applied/unapplied/hidden status changes,
+/// except when it is only popped from applied to unapplied.
+/// When a patch is found to have been renamed, its previous name is also followed.
+///
+/// The simplified stack log commits of the selected states are returned, newest first.
+pub(super) fn find_patch_log_entries(
+    repo: &gix::Repository,
+    state_commit_id: gix::ObjectId,
+    patchnames: &[PatchName],
+) -> Result<Vec<gix::ObjectId>> {
+    track_patch_log(repo, state_commit_id, patchnames).map(|(entries, _)| entries)
+}
+
+/// Find the stack log entries affecting any of the given patches.
+///
+/// In addition to the entries found by [`find_patch_log_entries()`], the names of the
+/// tracked patches are returned, including any previous names of renamed patches.
+fn track_patch_log(
+    repo: &gix::Repository,
+    state_commit_id: gix::ObjectId,
+    patchnames: &[PatchName],
+) -> Result<(Vec<gix::ObjectId>, BTreeSet<PatchName>)> {
+    let mut tracked: BTreeSet<PatchName> = patchnames.iter().cloned().collect();
+    let mut seen: BTreeSet<PatchName> = BTreeSet::new();
+    let mut entries = Vec::new();
+
+    let mut commit = repo.find_commit(state_commit_id)?;
+    let mut state = RawStackState::from_tree(commit.tree()?)?;
+
+    loop {
+        // The log ends at the first state without a readable previous state.
+        let prev = state
+            .prev
+            .and_then(|prev_id| repo.find_commit(prev_id).ok())
+            .and_then(|prev_commit| {
+                let prev_state = RawStackState::from_tree(prev_commit.tree().ok()?).ok()?;
+                Some((prev_commit, prev_state))
+            });
+        let prev_state = prev.as_ref().map(|(_, prev_state)| prev_state);
+
+        let mut affected = false;
+        for patchname in tracked.clone() {
+            let location = patch_location(&state, &patchname);
+            let prev_location =
+                prev_state.and_then(|prev_state| patch_location(prev_state, &patchname));
+            if location.is_some() {
+                seen.insert(patchname.clone());
+            }
+            // Popping leaves the patch itself unchanged, so a move from applied to
+            // unapplied with the same commit does not affect it.
+            let popped = matches!(
+                (location, prev_location),
+                (Some((1, oid)), Some((0, prev_oid))) if oid == prev_oid
+            );
+            if location != prev_location && !popped {
+                affected = true;
+            }
+            if let (Some((_, oid)), None, Some(prev_state)) = (location, prev_location, prev_state)
+            {
+                for (prev_patchname, prev_patch) in &prev_state.patches {
+                    if prev_patch.oid == oid && !state.patches.contains_key(prev_patchname) {
+                        tracked.insert(prev_patchname.clone());
+                    }
+                }
+            }
+        }
+
+        if affected {
+            if let Some(simplified_id) = commit.parent_ids().next() {
+                entries.push(simplified_id.detach());
+            }
+        }
+
+        if let Some((prev_commit, prev_state)) = prev {
+            commit = prev_commit;
+            state = prev_state;
         } else {
-            let num_commits = matches.get_one::<usize>("number").copied();
-            stupid.log(
-                simplified_parent_id,
-                pathspecs,
-                num_commits,
-                crate::color::use_color(matches),
-                matches.get_flag("full"),
-                matches.get_flag("diff"),
-            )
+            break;
         }
     }
+
+    if let Some(patchname) = patchnames.iter().find(|pn| !seen.contains(*pn)) {
+        return Err(anyhow!("patch `{patchname}` not found in the stack log"));
+    }
+
+    Ok((entries, tracked))
+}
+
+/// Show stack log entries along with the increments recorded for the tracked patches.
+///
+/// Entries without recorded increments for any of the tracked patches are shown with
+/// their stack state diff instead.
+fn show_increments(
+    repo: &gix::Repository,
+    matches: &ArgMatches,
+    entries: &[gix::ObjectId],
+    tracked: &BTreeSet<PatchName>,
+) -> Result<()> {
+    let stupid = repo.stupid();
+    let use_color = crate::color::use_color(matches);
+    let mut stdout = get_color_stdout(matches);
+    let mut color_spec = termcolor::ColorSpec::new();
+
+    for (i, entry_id) in entries.iter().enumerate() {
+        let mut increments: Vec<Vec<u8>> = Vec::new();
+        if let Some(increments_entry) = repo
+            .find_commit(*entry_id)?
+            .tree()?
+            .lookup_entry_by_path("increments")?
+        {
+            for entry in increments_entry.object()?.into_tree().iter() {
+                let entry = entry?;
+                if tracked
+                    .iter()
+                    .any(|pn| AsRef::<str>::as_ref(pn).as_bytes() == entry.filename())
+                {
+                    increments.push(repo.find_object(entry.oid())?.detach().data);
+                }
+            }
+        }
+
+        if i > 0 {
+            writeln!(stdout)?;
+        }
+        stdout.flush()?;
+        if increments.is_empty() {
+            stupid.log(&[*entry_id], true, None, use_color, true, true)?;
+            continue;
+        }
+        stupid.log(&[*entry_id], true, None, use_color, true, false)?;
+        writeln!(stdout)?;
+        for increment in &increments {
+            for line in increment.lines_with_terminator() {
+                let color = if line.starts_with(b"diff ")
+                    || line.starts_with(b"index ")
+                    || line.starts_with(b"--- ")
+                    || line.starts_with(b"+++ ")
+                {
+                    color_spec.set_fg(None).set_bold(true);
+                    true
+                } else if line.starts_with(b"@@") {
+                    color_spec
+                        .set_fg(Some(termcolor::Color::Cyan))
+                        .set_bold(false);
+                    true
+                } else if line.starts_with(b"+") {
+                    color_spec
+                        .set_fg(Some(termcolor::Color::Green))
+                        .set_bold(false);
+                    true
+                } else if line.starts_with(b"-") {
+                    color_spec
+                        .set_fg(Some(termcolor::Color::Red))
+                        .set_bold(false);
+                    true
+                } else {
+                    false
+                };
+                if color {
+                    stdout.set_color(&color_spec)?;
+                    stdout.write_all(line.trim_end_with(|c| c == '\n'))?;
+                    stdout.reset()?;
+                    writeln!(stdout)?;
+                } else {
+                    stdout.write_all(line)?;
+                }
+            }
+        }
+    }
+    stdout.flush()?;
+    Ok(())
+}
+
+/// Get the location group and commit id of a patch in a raw stack state.
+fn patch_location(state: &RawStackState, patchname: &PatchName) -> Option<(usize, gix::ObjectId)> {
+    let group = [&state.applied, &state.unapplied, &state.hidden]
+        .iter()
+        .position(|list| list.contains(patchname))?;
+    let oid = state.patches.get(patchname)?.oid;
+    Some((group, oid))
 }
@@ -0,0 +1,56 @@
+// SPDX-License-Identifier: GPL-2.0-only
+
+//! `stg meta get` implementation.
+
+use anyhow::{anyhow, Result};
+use clap::Arg;
+
+use crate::{
+    argset,
+    branchloc::BranchLocator,
+    ext::RepositoryExtended,
+    patch::PatchLocator,
+    stack::{InitializationPolicy, Stack, StackStateAccess},
+};
+
+pub(super) fn command() -> clap::Command {
+    clap::Command::new("get")
+        .about("Print a metadata value of a patch")
+        .long_about(
+            "Print the value of a patch's metadata key. It is an error if the patch \
+             does not have the metadata key.",
+        )
+        .arg(
+            Arg::new("patch")
+                .help("Patch to get metadata from")
+                .value_name("patch")
+                .required(true)
+                .allow_hyphen_values(true)
+                .value_parser(clap::value_parser!(PatchLocator)),
+        )
+        .arg(super::key_arg())
+        .arg(argset::branch_arg())
+}
+
+pub(super) fn dispatch(matches: &clap::ArgMatches) -> Result<()> {
+    let repo = gix::Repository::open()?;
+    let stack = Stack::from_branch_locator(
+        &repo,
+        matches.get_one::<BranchLocator>("branch"),
+        InitializationPolicy::AllowUninitialized,
+    )?;
+    let patchname = matches
+        .get_one::<PatchLocator>("patch")
+        .expect("required argument")
+        .resolve_name(&stack)?;
+    let key = matches.get_one::<String>("key").expect("required argument");
+
+    let value = stack
+        .get_patch(&patchname)
+        .metadata
+        .get(key)
+        .ok_or_else(|| anyhow!("patch `{patchname}` does not have metadata key `{key}`"))?;
+    println!("{value}");
+
+    Ok(())
+}
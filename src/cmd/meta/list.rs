@@ -0,0 +1,63 @@
+// SPDX-License-Identifier: GPL-2.0-only
+
+//! `stg meta list` implementation.
+
+use anyhow::Result;
+use clap::Arg;
+
+use crate::{
+    argset,
+    branchloc::BranchLocator,
+    ext::RepositoryExtended,
+    patch::PatchLocator,
+    stack::{InitializationPolicy, Stack, StackStateAccess},
+};
+
+pub(super) fn command() -> clap::Command {
+    clap::Command::new("list")
+        .about("List metadata of a patch")
+        .long_about(
+            "List all metadata of a patch, sorted by key. Each metadata entry is \
+             printed as 'key=value'.",
+        )
+        .arg(
+            Arg::new("patch")
+                .help("Patch to list metadata of")
+                .value_name("patch")
+                .required(true)
+                .allow_hyphen_values(true)
+                .value_parser(clap::value_parser!(PatchLocator)),
+        )
+        .arg(argset::branch_arg())
+        .arg(
+            Arg::new("keys")
+                .long("keys")
+                .short('k')
+                .help("Only list metadata keys")
+                .action(clap::ArgAction::SetTrue),
+        )
+}
+
+pub(super) fn dispatch(matches: &clap::ArgMatches) -> Result<()> {
+    let repo = gix::Repository::open()?;
+    let stack = Stack::from_branch_locator(
+        &repo,
+        matches.get_one::<BranchLocator>("branch"),
+        InitializationPolicy::AllowUninitialized,
+    )?;
+    let patchname = matches
+        .get_one::<PatchLocator>("patch")
+        .expect("required argument")
+        .resolve_name(&stack)?;
+    let keys_only = matches.get_flag("keys");
+
+    for (key, value) in &stack.get_patch(&patchname).metadata {
+        if keys_only {
+            println!("{key}");
+        } else {
+            println!("{key}={value}");
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,75 @@
+// SPDX-License-Identifier: GPL-2.0-only
+
+//! `stg meta` implementation.
+
+mod get;
+mod list;
+mod set;
+mod unset;
+
+use anyhow::{anyhow, Result};
+
+pub(super) const STGIT_COMMAND: super::StGitCommand = super::StGitCommand {
+    name: "meta",
+    category: super::CommandCategory::PatchManipulation,
+    make,
+    run,
+};
+
+fn make() -> clap::Command {
+    clap::Command::new(STGIT_COMMAND.name)
+        .about("Manage key/value metadata of patches")
+        .long_about(
+            "Manage arbitrary key/value metadata attached to patches.\n\
+             \n\
+             Patch metadata is recorded in the stack state and is carried along \
+             when a patch is refreshed, edited, pushed, or renamed. This provides a \
+             durable place for external tools, such as issue trackers or review \
+             systems, to associate information like issue or review ids with a \
+             patch.\n\
+             \n\
+             StGit assigns each new patch a UUID, recorded as the \"uuid\" metadata \
+             value, which identifies the patch even after it is renamed.\n\
+             \n\
+             Metadata keys may only contain ASCII alphanumeric characters, '-', \
+             '_', and '.'. Values are arbitrary strings.",
+        )
+        .subcommand_required(true)
+        .subcommand(get::command())
+        .subcommand(list::command())
+        .subcommand(set::command())
+        .subcommand(unset::command())
+}
+
+fn run(matches: &clap::ArgMatches) -> Result<()> {
+    match matches.subcommand() {
+        Some(("get", sub_matches)) => get::dispatch(sub_matches),
+        Some(("list", sub_matches)) => list::dispatch(sub_matches),
+        Some(("set", sub_matches)) => set::dispatch(sub_matches),
+        Some(("unset", sub_matches)) => unset::dispatch(sub_matches),
+        _ => panic!("valid subcommand is expected"),
+    }
+}
+
+/// Argument for a metadata key.
+fn key_arg() -> clap::Arg {
+    clap::Arg::new("key")
+        .help("Metadata key")
+        .value_name("key")
+        .required(true)
+        .value_parser(parse_key)
+}
+
+/// Validate metadata key.
+fn parse_key(key: &str) -> Result<String> {
+    if key.is_empty() {
+        Err(anyhow!("metadata key may not be empty"))
+    } else if let Some(c) = key
+        .chars()
+        .find(|c| !(c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.')))
+    {
+        Err(anyhow!("invalid character `{c}` in metadata key"))
+    } else {
+        Ok(key.to_string())
+    }
+}
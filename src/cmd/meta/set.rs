@@ -0,0 +1,66 @@
+// SPDX-License-Identifier: GPL-2.0-only
+
+//! `stg meta set` implementation.
+
+use anyhow::Result;
+use clap::Arg;
+
+use crate::{
+    argset,
+    branchloc::BranchLocator,
+    color::get_color_stdout,
+    ext::RepositoryExtended,
+    patch::PatchLocator,
+    stack::{InitializationPolicy, Stack},
+};
+
+pub(super) fn command() -> clap::Command {
+    clap::Command::new("set")
+        .about("Set a metadata value of a patch")
+        .long_about(
+            "Set the value of a patch's metadata key, replacing any existing value \
+             for the key.",
+        )
+        .arg(
+            Arg::new("patch")
+                .help("Patch to set metadata of")
+                .value_name("patch")
+                .required(true)
+                .allow_hyphen_values(true)
+                .value_parser(clap::value_parser!(PatchLocator)),
+        )
+        .arg(super::key_arg())
+        .arg(
+            Arg::new("value")
+                .help("Metadata value")
+                .value_name("value")
+                .required(true)
+                .allow_hyphen_values(true),
+        )
+        .arg(argset::branch_arg())
+}
+
+pub(super) fn dispatch(matches: &clap::ArgMatches) -> Result<()> {
+    let repo = gix::Repository::open()?;
+    let stack = Stack::from_branch_locator(
+        &repo,
+        matches.get_one::<BranchLocator>("branch"),
+        InitializationPolicy::AllowUninitialized,
+    )?;
+    let patchname = matches
+        .get_one::<PatchLocator>("patch")
+        .expect("required argument")
+        .resolve_name(&stack)?;
+    let key = matches.get_one::<String>("key").expect("required argument");
+    let value = matches
+        .get_one::<String>("value")
+        .expect("required argument");
+
+    stack
+        .setup_transaction()
+        .with_output_stream(get_color_stdout(matches))
+        .transact(|trans| trans.set_patch_metadata(&patchname, key, Some(value)))
+        .execute(&format!("meta set: {patchname} {key}"))?;
+
+    Ok(())
+}
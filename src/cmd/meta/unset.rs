@@ -0,0 +1,58 @@
+// SPDX-License-Identifier: GPL-2.0-only
+
+//! `stg meta unset` implementation.
+
+use anyhow::{anyhow, Result};
+use clap::Arg;
+
+use crate::{
+    argset,
+    branchloc::BranchLocator,
+    color::get_color_stdout,
+    ext::RepositoryExtended,
+    patch::PatchLocator,
+    stack::{InitializationPolicy, Stack, StackStateAccess},
+};
+
+pub(super) fn command() -> clap::Command {
+    clap::Command::new("unset")
+        .about("Remove a metadata value from a patch")
+        .arg(
+            Arg::new("patch")
+                .help("Patch to remove metadata from")
+                .value_name("patch")
+                .required(true)
+                .allow_hyphen_values(true)
+                .value_parser(clap::value_parser!(PatchLocator)),
+        )
+        .arg(super::key_arg())
+        .arg(argset::branch_arg())
+}
+
+pub(super) fn dispatch(matches: &clap::ArgMatches) -> Result<()> {
+    let repo = gix::Repository::open()?;
+    let stack = Stack::from_branch_locator(
+        &repo,
+        matches.get_one::<BranchLocator>("branch"),
+        InitializationPolicy::AllowUninitialized,
+    )?;
+    let patchname = matches
+        .get_one::<PatchLocator>("patch")
+        .expect("required argument")
+        .resolve_name(&stack)?;
+    let key = matches.get_one::<String>("key").expect("required argument");
+
+    if !stack.get_patch(&patchname).metadata.contains_key(key) {
+        return Err(anyhow!(
+            "patch `{patchname}` does not have metadata key `{key}`"
+        ));
+    }
+
+    stack
+        .setup_transaction()
+        .with_output_stream(get_color_stdout(matches))
+        .transact(|trans| trans.set_patch_metadata(&patchname, key, None))
+        .execute(&format!("meta unset: {patchname} {key}"))?;
+
+    Ok(())
+}
@@ -7,12 +7,21 @@
 
 use clap::builder::StyledStr;
 
+pub(crate) mod absorb;
+pub(crate) mod all_branches;
+pub(crate) mod backport;
+pub(crate) mod blame;
 pub(crate) mod branch;
+pub(crate) mod checkout;
 pub(crate) mod clean;
+pub(crate) mod clone;
 pub(crate) mod commit;
 pub(crate) mod completion;
+pub(crate) mod copy;
+pub(crate) mod cover;
 pub(crate) mod delete;
 pub(crate) mod diff;
+pub(crate) mod doctor;
 pub(crate) mod edit;
 pub(crate) mod email;
 pub(crate) mod export;
@@ -20,30 +29,43 @@ pub(crate) mod files;
 pub(crate) mod float;
 pub(crate) mod fold;
 pub(crate) mod goto;
+pub(crate) mod grep;
 pub(crate) mod hide;
 pub(crate) mod id;
 pub(crate) mod import;
 pub(crate) mod init;
+pub(crate) mod interdiff;
+pub(crate) mod label;
+pub(crate) mod library;
 pub(crate) mod log;
+pub(crate) mod meta;
 pub(crate) mod new;
 pub(crate) mod next;
 pub(crate) mod patches;
 pub(crate) mod pick;
 pub(crate) mod pop;
 pub(crate) mod prev;
+pub(crate) mod publish;
 pub(crate) mod pull;
 pub(crate) mod push;
 pub(crate) mod rebase;
+pub(crate) mod recover;
 pub(crate) mod redo;
 pub(crate) mod refresh;
 pub(crate) mod rename;
 pub(crate) mod repair;
 pub(crate) mod reset;
+pub(crate) mod revert;
+pub(crate) mod schema;
 pub(crate) mod series;
+pub(crate) mod serve;
 pub(crate) mod show;
 pub(crate) mod sink;
 pub(crate) mod spill;
+pub(crate) mod split;
 pub(crate) mod squash;
+pub(crate) mod stack;
+pub(crate) mod stat;
 pub(crate) mod sync;
 pub(crate) mod top;
 pub(crate) mod uncommit;
@@ -81,12 +103,21 @@ pub(crate) struct StGitCommand {
 /// This is used in [`crate::main`] for command line argument parsing and
 /// eventual dispatch of a subcommand.
 pub(crate) const STGIT_COMMANDS: &[StGitCommand] = &[
+    absorb::STGIT_COMMAND,
+    all_branches::STGIT_COMMAND,
+    backport::STGIT_COMMAND,
+    blame::STGIT_COMMAND,
     branch::STGIT_COMMAND,
+    checkout::STGIT_COMMAND,
     clean::STGIT_COMMAND,
+    clone::STGIT_COMMAND,
     commit::STGIT_COMMAND,
     completion::STGIT_COMMAND,
+    copy::STGIT_COMMAND,
+    cover::STGIT_COMMAND,
     delete::STGIT_COMMAND,
     diff::STGIT_COMMAND,
+    doctor::STGIT_COMMAND,
     edit::STGIT_COMMAND,
     email::STGIT_COMMAND,
     export::STGIT_COMMAND,
@@ -94,30 +125,43 @@ pub(crate) const STGIT_COMMANDS: &[StGitCommand] = &[
     float::STGIT_COMMAND,
     fold::STGIT_COMMAND,
     goto::STGIT_COMMAND,
+    grep::STGIT_COMMAND,
     hide::STGIT_COMMAND,
     id::STGIT_COMMAND,
     import::STGIT_COMMAND,
     init::STGIT_COMMAND,
+    interdiff::STGIT_COMMAND,
+    label::STGIT_COMMAND,
+    library::STGIT_COMMAND,
     log::STGIT_COMMAND,
+    meta::STGIT_COMMAND,
     new::STGIT_COMMAND,
     next::STGIT_COMMAND,
     patches::STGIT_COMMAND,
     pick::STGIT_COMMAND,
     pop::STGIT_COMMAND,
     prev::STGIT_COMMAND,
+    publish::STGIT_COMMAND,
     pull::STGIT_COMMAND,
     push::STGIT_COMMAND,
     rebase::STGIT_COMMAND,
+    recover::STGIT_COMMAND,
     redo::STGIT_COMMAND,
     refresh::STGIT_COMMAND,
     rename::STGIT_COMMAND,
     repair::STGIT_COMMAND,
     reset::STGIT_COMMAND,
+    revert::STGIT_COMMAND,
+    schema::STGIT_COMMAND,
     series::STGIT_COMMAND,
+    serve::STGIT_COMMAND,
     show::STGIT_COMMAND,
     sink::STGIT_COMMAND,
     spill::STGIT_COMMAND,
+    split::STGIT_COMMAND,
     squash::STGIT_COMMAND,
+    stack::STGIT_COMMAND,
+    stat::STGIT_COMMAND,
     sync::STGIT_COMMAND,
     top::STGIT_COMMAND,
     uncommit::STGIT_COMMAND,
@@ -133,6 +177,9 @@ pub(crate) enum Error {
 
     #[error("no patches applied")]
     NoAppliedPatches,
+
+    #[error("no patches selected")]
+    NoPatchesSelected,
 }
 
 pub(crate) fn make_usage(command_name: &str, usages: &[&str]) -> clap::builder::StyledStr {
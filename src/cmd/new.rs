@@ -10,8 +10,8 @@ use clap::{Arg, ArgGroup, ArgMatches};
 use super::refresh;
 use crate::{
     color::get_color_stdout,
-    ext::{RepositoryExtended, SignatureExtended},
-    patch::{patchedit, PatchName},
+    ext::{CommitExtended, RepositoryExtended, SignatureExtended},
+    patch::{patchedit, PatchLocator, PatchName},
     stack::{InitializationPolicy, Stack, StackAccess, StackStateAccess},
     stupid::Stupid,
 };
@@ -32,6 +32,11 @@ fn make() -> clap::Command {
              stack. Uncommitted changes in the work tree are not included in the patch \
              -- that is handled by stg-refresh.\n\
              \n\
+             With '--before' or '--after', the new patch is instead created at the \
+             given position in the series. Only the applied patches above the new \
+             patch are re-pushed. If the new patch is positioned among the unapplied \
+             patches, it is left unapplied.\n\
+             \n\
              The given patch name must be unique in the stack. If no name is given, \
              one is generated from the first line of the patch's commit message.\n\
              \n\
@@ -54,6 +59,7 @@ fn make() -> clap::Command {
             &[
                 "[OPTIONS] [patchname] [-- <path>...]",
                 "[OPTIONS] [--name <patchname>] [-- <path>...]",
+                "[OPTIONS] (--before|--after) <patch> [patchname]",
             ],
         ))
         .arg(
@@ -90,6 +96,24 @@ fn make() -> clap::Command {
                 .value_parser(clap::value_parser!(PatchName))
                 .conflicts_with("patchname"),
         )
+        .arg(
+            Arg::new("before")
+                .long("before")
+                .help("Create the new patch before <patch>")
+                .value_name("patch")
+                .allow_hyphen_values(true)
+                .value_parser(clap::value_parser!(PatchLocator))
+                .conflicts_with_all(["after", "refresh", "pathspecs"]),
+        )
+        .arg(
+            Arg::new("after")
+                .long("after")
+                .help("Create the new patch after <patch>")
+                .value_name("patch")
+                .allow_hyphen_values(true)
+                .value_parser(clap::value_parser!(PatchLocator))
+                .conflicts_with_all(["refresh", "pathspecs"]),
+        )
         .next_help_heading("Refresh Options")
         .arg(
             Arg::new("refresh")
@@ -179,16 +203,34 @@ fn run(matches: &ArgMatches) -> Result<()> {
         Ok(None)
     }?;
 
+    // The target patch and whether the new patch goes before it.
+    let position = if let Some(locator) = matches.get_one::<PatchLocator>("before") {
+        Some((locator.resolve_name(&stack)?, true))
+    } else if let Some(locator) = matches.get_one::<PatchLocator>("after") {
+        Some((locator.resolve_name(&stack)?, false))
+    } else {
+        None
+    };
+
+    let parent_id = match &position {
+        Some((target, _)) if stack.is_hidden(target) => {
+            return Err(anyhow!(
+                "cannot position new patch next to hidden patch `{target}`"
+            ));
+        }
+        Some((target, true)) => stack.get_patch_commit(target).get_parent_commit()?.id,
+        Some((target, false)) => stack.get_patch_commit(target).id,
+        None => stack.get_branch_head().id,
+    };
+
     let is_refreshing = matches.get_flag("refresh") || matches.contains_id("pathspecs");
 
     let tree_id = if is_refreshing {
         refresh::assemble_refresh_tree(&stack, matches, None)?
     } else {
-        stack.get_branch_head().tree_id()?.detach()
+        repo.find_commit(parent_id)?.tree_id()?.detach()
     };
 
-    let parent_id = stack.get_branch_head().id;
-
     let (patchname, commit_id) = match patchedit::EditBuilder::default()
         .allow_autosign(true)
         .allow_diff_edit(false)
@@ -221,7 +263,32 @@ fn run(matches: &ArgMatches) -> Result<()> {
     stack
         .setup_transaction()
         .with_output_stream(get_color_stdout(matches))
-        .transact(|trans| trans.new_applied(&patchname, commit_id))
+        .transact(|trans| {
+            let (target, before) = if let Some((target, before)) = &position {
+                (target, *before)
+            } else {
+                return trans.new_applied(&patchname, commit_id);
+            };
+            if let Some(pos) = trans.applied().iter().position(|pn| pn == target) {
+                let pos = if before { pos } else { pos + 1 };
+                if pos == trans.applied().len() {
+                    return trans.new_applied(&patchname, commit_id);
+                }
+                let mut to_push: Vec<PatchName> = trans.applied()[pos..].to_vec();
+                trans.pop_patches(|pn| to_push.contains(pn))?;
+                trans.new_unapplied(&patchname, commit_id, 0)?;
+                to_push.insert(0, patchname.clone());
+                trans.push_patches(&to_push, false)
+            } else {
+                let pos = trans
+                    .unapplied()
+                    .iter()
+                    .position(|pn| pn == target)
+                    .expect("target is unapplied because hidden targets are rejected");
+                let pos = if before { pos } else { pos + 1 };
+                trans.new_unapplied(&patchname, commit_id, pos)
+            }
+        })
         .execute(&format!("new: {patchname}"))?;
     Ok(())
 }
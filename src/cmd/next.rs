@@ -2,10 +2,7 @@
 
 //! `stg next` implementation.
 
-use std::io::Write;
-
 use anyhow::{anyhow, Result};
-use termcolor::WriteColor;
 
 use crate::{
     argset,
@@ -28,10 +25,20 @@ fn make() -> clap::Command {
             "Print the name of the next patch.\n\
              \n\
              The next patch is the unapplied patch that follows the current, \
-             topmost patch. An error message will be printed if there are no \
-             unapplied patches.",
+             topmost patch. With '--number', the patch <n> patches above the \
+             topmost patch is printed instead. An error message will be printed if \
+             there are not enough unapplied patches.",
         )
         .arg(argset::branch_arg())
+        .arg(
+            clap::Arg::new("number")
+                .long("number")
+                .short('n')
+                .help("Print the patch <n> patches above the topmost patch")
+                .value_name("n")
+                .value_parser(super::prev::number_parser),
+        )
+        .arg(super::top::oid_arg())
 }
 
 fn run(matches: &clap::ArgMatches) -> Result<()> {
@@ -42,17 +49,15 @@ fn run(matches: &clap::ArgMatches) -> Result<()> {
         InitializationPolicy::AllowUninitialized,
     )?;
 
-    if let Some(patchname) = stack.unapplied().first() {
-        let mut stdout = crate::color::get_color_stdout(matches);
-        let mut color_spec = termcolor::ColorSpec::new();
-        color_spec.set_bold(true);
-        stdout.set_color(&color_spec)?;
-        write!(stdout, "{patchname}")?;
-        color_spec.clear();
-        stdout.set_color(&color_spec)?;
-        writeln!(stdout)?;
-        Ok(())
+    let n = matches.get_one::<usize>("number").copied().unwrap_or(1);
+
+    if let Some(patchname) = stack.unapplied().get(n - 1) {
+        super::top::print_patch(matches, &stack, patchname)
     } else {
-        Err(anyhow!("no unapplied patches"))
+        Err(anyhow!(if stack.unapplied().is_empty() {
+            "no unapplied patches"
+        } else {
+            "not enough unapplied patches"
+        }))
     }
 }
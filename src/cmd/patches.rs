@@ -32,7 +32,11 @@ fn make() -> clap::Command {
         .long_about(
             "Show the applied patches modifying the given paths. Without path \
              arguments, the files modified in the working tree are used as the \
-             paths.",
+             paths.\n\
+             \n\
+             With '--find-renames', patches that modified the given paths under a \
+             previous name are also shown. Renames are detected in each applied \
+             patch, from the top of the stack downward.",
         )
         .arg(
             Arg::new("pathspecs")
@@ -50,6 +54,13 @@ fn make() -> clap::Command {
                 .help("Show the diff for the given paths")
                 .action(clap::ArgAction::SetTrue),
         )
+        .arg(
+            Arg::new("find-renames")
+                .long("find-renames")
+                .short('M')
+                .help("Also match paths' names prior to being renamed")
+                .action(clap::ArgAction::SetTrue),
+        )
         .arg(argset::diff_opts_arg())
 }
 
@@ -103,6 +114,17 @@ fn run(matches: &ArgMatches) -> Result<()> {
         return Err(anyhow!("no local changes and no paths specified"));
     }
 
+    let renamed_pathspecs: Vec<PathBuf>;
+    let pathspecs: Vec<&Path> = if matches.get_flag("find-renames") {
+        renamed_pathspecs = find_renamed_paths(&stack, &pathspecs)?;
+        pathspecs
+            .into_iter()
+            .chain(renamed_pathspecs.iter().map(PathBuf::as_path))
+            .collect()
+    } else {
+        pathspecs
+    };
+
     let revs = stupid.rev_list(stack.base().id, stack.top().id, Some(&pathspecs))?;
 
     if diff_flag {
@@ -143,3 +165,58 @@ fn run(matches: &ArgMatches) -> Result<()> {
 
     Ok(())
 }
+
+/// Find prior names of the given paths from renames in the stack's applied patches.
+///
+/// The returned pathspecs are anchored at the top of the worktree.
+fn find_renamed_paths(stack: &Stack, pathspecs: &[&Path]) -> Result<Vec<PathBuf>> {
+    let repo = stack.repo;
+    let prefix = if let Some(prefix_result) = repo.prefix() {
+        Some(prefix_result.context("determining Git prefix")?)
+    } else {
+        None
+    };
+
+    // Track paths relative to the top of the worktree.
+    let mut tracked: Vec<String> = pathspecs
+        .iter()
+        .map(|path| {
+            let path = if let Some(prefix) = prefix.as_ref() {
+                prefix.join(path)
+            } else {
+                path.to_path_buf()
+            };
+            path.to_string_lossy()
+                .trim_start_matches("./")
+                .trim_end_matches('/')
+                .to_string()
+        })
+        .collect();
+    let mut renamed = Vec::new();
+
+    let stupid = repo.stupid();
+    for patchname in stack.applied().iter().rev() {
+        let patch_commit = stack.get_patch_commit(patchname);
+        let parent_commit = patch_commit.get_parent_commit()?;
+        for (old_path, new_path) in stupid.diff_tree_renames(
+            parent_commit.tree_id()?.detach(),
+            patch_commit.tree_id()?.detach(),
+        )? {
+            let old_path = old_path.to_str_lossy().to_string();
+            let new_path = new_path.to_str_lossy();
+            let is_tracked = tracked.iter().any(|path| {
+                path.is_empty()
+                    || new_path == path.as_str()
+                    || new_path
+                        .strip_prefix(path.as_str())
+                        .map_or(false, |rest| rest.starts_with('/'))
+            });
+            if is_tracked && !tracked.contains(&old_path) {
+                renamed.push(PathBuf::from(format!(":(top){old_path}")));
+                tracked.push(old_path);
+            }
+        }
+    }
+
+    Ok(renamed)
+}
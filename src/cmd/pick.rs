@@ -3,6 +3,7 @@
 //! `stg pick` implementation.
 
 use std::{
+    ffi::{OsStr, OsString},
     path::{Path, PathBuf},
     rc::Rc,
     str::FromStr,
@@ -47,7 +48,13 @@ fn make() -> clap::Command {
              option is a format string as may be supplied to the '--pretty' option of \
              'git show'. The default is \"format:%B%n(imported from commit %H)\", \
              which appends the commit hash of the picked commit to the patch's commit \
-             message.",
+             message.\n\
+             \n\
+             With the '--repo' option, commits are picked from another repository, \
+             given by URL or path, without first adding it as a remote. Each source \
+             is then a committish or a '<committish>..<committish>' range in that \
+             repository. The committishs must be ref names or full commit ids, \
+             optionally followed by '~' or '^' suffixes.",
         )
         .override_usage(super::make_usage(
             "stg pick",
@@ -56,6 +63,7 @@ fn make() -> clap::Command {
                 "[OPTIONS] [--name NAME] [--parent COMMITTISH] <source>",
                 "[OPTIONS] --fold [--file PATH]... <source>...",
                 "[OPTIONS] --update <source>...",
+                "[OPTIONS] --repo <repository> <committish>...",
             ],
         ))
         .arg(
@@ -74,6 +82,14 @@ fn make() -> clap::Command {
                 .value_name("branch")
                 .value_parser(clap::value_parser!(BranchLocator)),
         )
+        .arg(
+            Arg::new("repo")
+                .long("repo")
+                .help("Pick commits from another repository at <repository>")
+                .value_name("repository")
+                .value_parser(clap::value_parser!(OsString))
+                .conflicts_with("ref-branch"),
+        )
         .arg(
             Arg::new("revert")
                 .long("revert")
@@ -161,14 +177,24 @@ fn run(matches: &clap::ArgMatches) -> Result<()> {
         stack.check_head_top_mismatch()?;
     }
 
-    let picks = revspec::resolve(
-        &repo,
-        Some(&ref_stack),
-        matches
-            .get_many::<RangeRevisionSpec>("stgit-revision")
-            .expect("required argument"),
-        RangeConstraint::VisibleWithAppliedBoundary,
-    )?;
+    let picks = if let Some(repository) = matches.get_one::<OsString>("repo") {
+        fetch_picks(
+            &repo,
+            repository,
+            matches
+                .get_raw("stgit-revision")
+                .expect("required argument"),
+        )?
+    } else {
+        revspec::resolve(
+            &repo,
+            Some(&ref_stack),
+            matches
+                .get_many::<RangeRevisionSpec>("stgit-revision")
+                .expect("required argument"),
+            RangeConstraint::VisibleWithAppliedBoundary,
+        )?
+    };
 
     if matches.get_flag("fold") || matches.get_flag("update") {
         // Fold into current patch
@@ -195,6 +221,62 @@ fn run(matches: &clap::ArgMatches) -> Result<()> {
     }
 }
 
+/// Fetch the commits to pick from another repository.
+///
+/// Each source is either a single committish or a `<committish>..<committish>` range
+/// of commits in the other repository. Ranges are picked oldest commit first.
+fn fetch_picks<'repo, 'a>(
+    repo: &'repo gix::Repository,
+    repository: &OsStr,
+    sources: impl IntoIterator<Item = &'a OsStr>,
+) -> Result<Vec<StGitRevision<'repo>>> {
+    let stupid = repo.stupid();
+
+    // Only the base of the committish may be fetched; any `~` or `^` suffix is then
+    // resolved locally.
+    let fetch_commit = |committish: &str| -> Result<Rc<gix::Commit<'repo>>> {
+        let (base, suffix) =
+            committish.split_at(committish.find(['~', '^']).unwrap_or(committish.len()));
+        let base = if base.is_empty() { "HEAD" } else { base };
+        let oid = stupid.fetch_revision(repository, base).with_context(|| {
+            format!("fetching `{base}` from `{}`", repository.to_string_lossy())
+        })?;
+        let object = repo
+            .rev_parse_single_ex(&format!("{oid}{suffix}"))?
+            .object()?;
+        Ok(Rc::new(object.peel_tags_to_end()?.try_into_commit()?))
+    };
+
+    let mut picks = Vec::new();
+    for source in sources {
+        let source = source
+            .to_str()
+            .ok_or_else(|| anyhow!("source `{}` is not valid UTF-8", source.to_string_lossy()))?;
+        if let Some((begin, end)) = source.split_once("..") {
+            let begin = fetch_commit(begin)?;
+            let end = fetch_commit(end)?;
+            let oids = stupid.rev_list(begin.id, end.id, None::<Vec<OsString>>)?;
+            if oids.is_empty() {
+                return Err(anyhow!("no commits in range `{source}`"));
+            }
+            for oid in oids.into_iter().rev() {
+                let commit = Rc::new(repo.find_commit(oid)?);
+                picks.push(StGitRevision {
+                    patchname: None,
+                    commit,
+                });
+            }
+        } else {
+            let commit = fetch_commit(source)?;
+            picks.push(StGitRevision {
+                patchname: None,
+                commit,
+            });
+        }
+    }
+    Ok(picks)
+}
+
 fn fold_picks(stack: &Stack, matches: &clap::ArgMatches, picks: &[StGitRevision]) -> Result<()> {
     let stupid = stack.repo.stupid();
     for StGitRevision { patchname, commit } in picks {
@@ -2,7 +2,11 @@
 
 //! `stg pop` implementation.
 
-use std::iter::FromIterator;
+use std::{
+    collections::BTreeSet,
+    iter::FromIterator,
+    path::{Path, PathBuf},
+};
 
 use anyhow::{anyhow, Result};
 use clap::{Arg, ArgMatches};
@@ -10,10 +14,10 @@ use clap::{Arg, ArgMatches};
 use crate::{
     argset,
     color::get_color_stdout,
-    ext::RepositoryExtended,
+    ext::{CommitExtended, RepositoryExtended},
     patch::{patchrange, PatchName, PatchRange, RangeConstraint},
     stack::{InitializationPolicy, Stack, StackStateAccess},
-    stupid::Stupid,
+    stupid::{StatusOptions, Stupid},
 };
 
 pub(super) const STGIT_COMMAND: super::StGitCommand = super::StGitCommand {
@@ -43,6 +47,7 @@ fn make() -> clap::Command {
                 "[OPTIONS] [patch]...",
                 "[OPTIONS] --all",
                 "[OPTIONS] -n <number>",
+                "[OPTIONS] --label <label>",
             ],
         ))
         .arg(
@@ -51,7 +56,7 @@ fn make() -> clap::Command {
                 .value_name("patch")
                 .num_args(1..)
                 .value_parser(clap::value_parser!(PatchRange))
-                .conflicts_with_all(["all", "number"]),
+                .conflicts_with_all(["all", "number", "label"]),
         )
         .arg(
             Arg::new("all")
@@ -59,7 +64,7 @@ fn make() -> clap::Command {
                 .short('a')
                 .help("Pop all applied patches")
                 .action(clap::ArgAction::SetTrue)
-                .conflicts_with("number"),
+                .conflicts_with_all(["number", "label"]),
         )
         .arg(
             Arg::new("number")
@@ -75,8 +80,10 @@ fn make() -> clap::Command {
                 .num_args(1)
                 .allow_negative_numbers(true)
                 .value_name("number")
-                .value_parser(clap::value_parser!(isize)),
+                .value_parser(clap::value_parser!(isize))
+                .conflicts_with("label"),
         )
+        .arg(argset::label_arg().help("Pop the applied patches labeled <label>"))
         .arg(
             Arg::new("spill")
                 .long("spill")
@@ -85,6 +92,7 @@ fn make() -> clap::Command {
                 .action(clap::ArgAction::SetTrue),
         )
         .arg(argset::keep_arg())
+        .arg(argset::force_dirty_arg().conflicts_with("spill"))
 }
 
 fn run(matches: &ArgMatches) -> Result<()> {
@@ -122,6 +130,17 @@ fn run(matches: &ArgMatches) -> Result<()> {
             .take(num_to_take)
             .cloned()
             .collect()
+    } else if let Some(label) = matches.get_one::<String>("label") {
+        let patches: indexmap::IndexSet<PatchName> = stack
+            .applied()
+            .iter()
+            .filter(|pn| stack.get_patch(pn).labels.contains(label))
+            .cloned()
+            .collect();
+        if patches.is_empty() {
+            return Err(anyhow!("no applied patches labeled `{label}`"));
+        }
+        patches
     } else if let Some(range_specs) = matches.get_many::<PatchRange>("patchranges-applied") {
         indexmap::IndexSet::from_iter(
             patchrange::resolve_names(&stack, range_specs, RangeConstraint::Applied).map_err(
@@ -144,6 +163,7 @@ fn run(matches: &ArgMatches) -> Result<()> {
 
     let keep_flag = matches.get_flag("keep");
     let spill_flag = matches.get_flag("spill");
+    let force_dirty_flag = matches.get_flag("force-dirty");
     repo.check_repository_state()?;
 
     let stupid = repo.stupid();
@@ -151,7 +171,15 @@ fn run(matches: &ArgMatches) -> Result<()> {
 
     statuses.check_conflicts()?;
     stack.check_head_top_mismatch()?;
-    if !keep_flag && !spill_flag {
+    if force_dirty_flag {
+        // Patches above the lowest popped patch are popped and pushed again.
+        let lowest_pos = stack
+            .applied()
+            .iter()
+            .position(|pn| patches.contains(pn))
+            .expect("popped patches are applied");
+        check_touched_paths_clean(&stack, &stack.applied()[lowest_pos..])?;
+    } else if !keep_flag && !spill_flag {
         statuses.check_index_and_worktree_clean()?;
     }
 
@@ -198,3 +226,24 @@ fn run(matches: &ArgMatches) -> Result<()> {
 
     Ok(())
 }
+
+/// Check that none of the files touched by the given patches have local changes.
+///
+/// Untracked files are also considered since they may collide with files created by
+/// the patches.
+pub(super) fn check_touched_paths_clean(stack: &Stack, patchnames: &[PatchName]) -> Result<()> {
+    let stupid = stack.repo.stupid();
+    let mut paths: BTreeSet<PathBuf> = BTreeSet::new();
+    for patchname in patchnames {
+        let commit = stack.get_patch_commit(patchname);
+        let parent_tree_id = commit.get_parent_commit()?.tree_id()?.detach();
+        let diff_files = stupid.diff_tree_files(parent_tree_id, commit.tree_id()?.detach())?;
+        paths.extend(diff_files.iter().map(Path::to_path_buf));
+    }
+    let statuses = stupid.statuses(Some(
+        StatusOptions::default()
+            .include_untracked(true)
+            .recurse_untracked_dirs(true),
+    ))?;
+    statuses.check_paths_clean(paths.iter().map(PathBuf::as_path))
+}
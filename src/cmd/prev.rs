@@ -2,10 +2,7 @@
 
 //! `stg prev` implementation.
 
-use std::io::Write;
-
 use anyhow::{anyhow, Result};
-use termcolor::WriteColor;
 
 use crate::{
     argset,
@@ -28,10 +25,31 @@ fn make() -> clap::Command {
             "Print the name of the previous patch.\n\
              \n\
              The previous patch is the applied patch preceding the current, \
-             topmost patch. An error message will be printed if not enough \
-             patches are applied.",
+             topmost patch. With '--number', the patch <n> patches below the \
+             topmost patch is printed instead. An error message will be printed if \
+             not enough patches are applied.",
         )
         .arg(argset::branch_arg())
+        .arg(
+            clap::Arg::new("number")
+                .long("number")
+                .short('n')
+                .help("Print the patch <n> patches below the topmost patch")
+                .value_name("n")
+                .value_parser(number_parser),
+        )
+        .arg(super::top::oid_arg())
+}
+
+/// Parse a patch offset, which must be at least 1.
+pub(super) fn number_parser(s: &str) -> Result<usize> {
+    argset::parse_usize(s).and_then(|n| {
+        if n >= 1 {
+            Ok(n)
+        } else {
+            Err(anyhow!("offset must be at least 1"))
+        }
+    })
 }
 
 fn run(matches: &clap::ArgMatches) -> Result<()> {
@@ -42,16 +60,10 @@ fn run(matches: &clap::ArgMatches) -> Result<()> {
         InitializationPolicy::AllowUninitialized,
     )?;
 
-    if let Some(patchname) = stack.applied().iter().nth_back(1) {
-        let mut stdout = crate::color::get_color_stdout(matches);
-        let mut color_spec = termcolor::ColorSpec::new();
-        color_spec.set_bold(true);
-        stdout.set_color(&color_spec)?;
-        write!(stdout, "{patchname}")?;
-        color_spec.clear();
-        stdout.set_color(&color_spec)?;
-        writeln!(stdout)?;
-        Ok(())
+    let n = matches.get_one::<usize>("number").copied().unwrap_or(1);
+
+    if let Some(patchname) = stack.applied().iter().nth_back(n) {
+        super::top::print_patch(matches, &stack, patchname)
     } else if stack.applied().is_empty() {
         Err(super::Error::NoAppliedPatches.into())
     } else {
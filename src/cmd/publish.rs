@@ -0,0 +1,352 @@
+// SPDX-License-Identifier: GPL-2.0-only
+
+//! `stg publish` implementation.
+
+use anyhow::{anyhow, Result};
+use bstr::ByteSlice;
+use clap::{Arg, ArgGroup, ArgMatches};
+
+use crate::{
+    argset,
+    branchloc::BranchLocator,
+    ext::{CommitExtended, RepositoryExtended},
+    patch::{edit as patchedit, PatchName},
+    print_info_message,
+    stack::{InitializationPolicy, Stack, StackAccess, StackStateAccess},
+    stupid::Stupid,
+    wrap::Message,
+};
+
+pub(super) const STGIT_COMMAND: super::StGitCommand = super::StGitCommand {
+    name: "publish",
+    category: super::CommandCategory::StackManipulation,
+    make,
+    run,
+};
+
+fn make() -> clap::Command {
+    clap::Command::new(STGIT_COMMAND.name)
+        .about("Publish the stack changes to a merge-friendly branch")
+        .long_about(
+            "Publish the stack changes to a merge-friendly public branch.\n\
+             \n\
+             The public branch tracks the content of the stack without ever having \
+             its history rewritten. Collaborators may thus pull from, or merge, the \
+             public branch while the underlying stack patches continue to be \
+             refreshed, reordered, and rebased.\n\
+             \n\
+             The public branch name defaults to the value of the \
+             'branch.<branch>.stgit.public' configuration variable or, if that is not \
+             set, to '<branch>.public'.\n\
+             \n\
+             When the public branch does not exist, it is created at the current \
+             stack head. Otherwise, the public branch is updated as follows:\n\
+             \n\
+             - If the public branch head is an ancestor of the stack base, the public \
+             branch is fast-forwarded to the stack head.\n\
+             \n\
+             - If the stack was rebased since it was last published, a merge commit \
+             with the public branch head and the new stack base as parents is added \
+             to the public branch. The merge commit's tree is the tree of the stack \
+             head.\n\
+             \n\
+             - If new patches were added on top of the last published patch, each of \
+             the new patches is added to the public branch as a commit having the \
+             patch's author and message.\n\
+             \n\
+             - Any other changes to the stack, such as refreshed or reordered \
+             patches, are recorded in a single commit on the public branch that \
+             makes its tree the same as the stack head's tree.\n\
+             \n\
+             Messages for merge and update commits are taken from '--message' or \
+             are otherwise edited interactively.",
+        )
+        .override_usage(super::make_usage(
+            "stg publish",
+            &["[OPTIONS] [public-branch]"],
+        ))
+        .arg(
+            Arg::new("public-branch")
+                .help("Public branch to publish to")
+                .value_name("public-branch"),
+        )
+        .arg(argset::branch_arg())
+        .arg(
+            Arg::new("message")
+                .long("message")
+                .short('m')
+                .help("Use <message> for merge and update commits")
+                .value_name("message")
+                .num_args(1)
+                .allow_hyphen_values(true),
+        )
+        .arg(
+            Arg::new("unpublished")
+                .long("unpublished")
+                .short('u')
+                .help("Show applied patches that are not yet published")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("last")
+                .long("last")
+                .short('l')
+                .help("Show the last published patch")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("overwrite")
+                .long("overwrite")
+                .help("Overwrite the public branch with the stack head")
+                .long_help(
+                    "Overwrite the public branch with the current stack head. This \
+                     rewrites the history of the public branch and should be avoided \
+                     once others have pulled from it.",
+                )
+                .action(clap::ArgAction::SetTrue),
+        )
+        .group(ArgGroup::new("mode").args(["unpublished", "last", "overwrite"]))
+}
+
+fn run(matches: &ArgMatches) -> Result<()> {
+    let repo = gix::Repository::open()?;
+    let stack = Stack::from_branch_locator(
+        &repo,
+        matches.get_one::<BranchLocator>("branch"),
+        InitializationPolicy::RequireInitialized,
+    )?;
+    stack.check_head_top_mismatch()?;
+
+    let branch_name = stack.get_branch_name().to_string();
+    let public_branch_name = if let Some(name) = matches.get_one::<String>("public-branch") {
+        name.clone()
+    } else if let Some(name) = repo
+        .config_snapshot()
+        .string(format!("branch.{branch_name}.stgit.public").as_str())
+    {
+        name.to_str_lossy().to_string()
+    } else {
+        format!("{branch_name}.public")
+    };
+    let public_refname = format!("refs/heads/{public_branch_name}");
+    let reflog_msg = format!("publish: {branch_name}");
+
+    let stack_head = stack.get_branch_head();
+    let stack_tree_id = stack_head.tree_id()?.detach();
+
+    if matches.get_flag("overwrite") {
+        repo.reference(
+            public_refname.as_str(),
+            stack_head.id,
+            gix::refs::transaction::PreviousValue::Any,
+            reflog_msg,
+        )?;
+        print_info_message(matches, &format!("Overwrote `{public_branch_name}`"));
+        return Ok(());
+    }
+
+    let public_head_id =
+        if let Some(reference) = repo.try_find_reference(public_refname.as_str())? {
+            reference.into_fully_peeled_id()?.detach()
+        } else if matches.get_flag("unpublished") || matches.get_flag("last") {
+            return Err(anyhow!(
+                "public branch `{public_branch_name}` does not exist"
+            ));
+        } else {
+            repo.reference(
+                public_refname.as_str(),
+                stack_head.id,
+                gix::refs::transaction::PreviousValue::MustNotExist,
+                reflog_msg,
+            )?;
+            print_info_message(matches, &format!("Created `{public_branch_name}`"));
+            return Ok(());
+        };
+
+    let public_head = repo.find_commit(public_head_id)?;
+    let public_tree_id = public_head.tree_id()?.detach();
+
+    if matches.get_flag("unpublished") || matches.get_flag("last") {
+        let last_published = get_last_published(&stack, public_tree_id)?;
+        if matches.get_flag("last") {
+            if let Some(patchname) = last_published {
+                println!("{patchname}");
+            } else {
+                return Err(anyhow!("no published patches"));
+            }
+        } else {
+            let published_count = if let Some(patchname) = last_published {
+                stack
+                    .applied()
+                    .iter()
+                    .position(|pn| pn == patchname)
+                    .expect("last published patch is applied")
+                    + 1
+            } else {
+                0
+            };
+            for patchname in &stack.applied()[published_count..] {
+                println!("{patchname}");
+            }
+        }
+        return Ok(());
+    }
+
+    if public_tree_id == stack_tree_id {
+        print_info_message(
+            matches,
+            &format!("`{public_branch_name}` already up to date"),
+        );
+        return Ok(());
+    }
+
+    let stupid = repo.stupid();
+    let config = repo.config_snapshot();
+    let base_id = stack.base().id;
+    let merge_bases = stupid.merge_bases(public_head_id, base_id)?;
+
+    if merge_bases.contains(&public_head_id) {
+        repo.reference(
+            public_refname.as_str(),
+            stack_head.id,
+            gix::refs::transaction::PreviousValue::MustExistAndMatch(gix::refs::Target::Peeled(
+                public_head_id,
+            )),
+            reflog_msg,
+        )?;
+        print_info_message(matches, &format!("Fast-forwarded `{public_branch_name}`"));
+        return Ok(());
+    }
+
+    let author = repo.get_author()?;
+    let committer = repo.get_committer()?;
+
+    if !merge_bases.contains(&base_id) {
+        let message = get_message(
+            matches,
+            &config,
+            &format!(
+                "Merge {} into {public_branch_name}",
+                base_id.to_hex_with_len(7)
+            ),
+        )?;
+        let merge_id = repo.commit_ex(
+            author,
+            committer,
+            &message,
+            stack_tree_id,
+            [public_head_id, base_id],
+        )?;
+        repo.reference(
+            public_refname.as_str(),
+            merge_id,
+            gix::refs::transaction::PreviousValue::MustExistAndMatch(gix::refs::Target::Peeled(
+                public_head_id,
+            )),
+            reflog_msg,
+        )?;
+        print_info_message(
+            matches,
+            &format!("Merged the stack base into `{public_branch_name}`"),
+        );
+        return Ok(());
+    }
+
+    // New patches are detected by the public tree being the same as a patch's
+    // parent tree. If older patches were modified, new patches cannot be detected
+    // and all changes are instead published as a single update commit.
+    let mut new_public_head_id = public_head_id;
+    let mut new_public_tree_id = public_tree_id;
+    for patchname in stack.applied() {
+        let patch_commit = stack.get_patch_commit(patchname);
+        if patch_commit.get_parent_commit()?.tree_id()?.detach() == new_public_tree_id {
+            let patch_tree_id = patch_commit.tree_id()?.detach();
+            new_public_head_id = repo.commit_ex(
+                &patch_commit.author_strict()?,
+                committer,
+                &patch_commit.message_ex(),
+                patch_tree_id,
+                [new_public_head_id],
+            )?;
+            new_public_tree_id = patch_tree_id;
+            print_info_message(matches, &format!("Published new patch `{patchname}`"));
+        }
+    }
+
+    if new_public_tree_id != stack_tree_id {
+        let message = get_message(
+            matches,
+            &config,
+            &format!("Update {public_branch_name} from {branch_name}"),
+        )?;
+        new_public_head_id = repo.commit_ex(
+            author,
+            committer,
+            &message,
+            stack_tree_id,
+            [new_public_head_id],
+        )?;
+    }
+
+    repo.reference(
+        public_refname.as_str(),
+        new_public_head_id,
+        gix::refs::transaction::PreviousValue::MustExistAndMatch(gix::refs::Target::Peeled(
+            public_head_id,
+        )),
+        reflog_msg,
+    )?;
+    print_info_message(matches, &format!("Published `{public_branch_name}`"));
+
+    Ok(())
+}
+
+/// Find the topmost applied patch whose tree matches the public branch's tree.
+fn get_last_published<'a>(
+    stack: &'a Stack,
+    public_tree_id: gix::ObjectId,
+) -> Result<Option<&'a PatchName>> {
+    for patchname in stack.applied().iter().rev() {
+        if stack.get_patch_commit(patchname).tree_id()?.detach() == public_tree_id {
+            return Ok(Some(patchname));
+        }
+    }
+    Ok(None)
+}
+
+/// Get message for a merge or update commit from the command line or the editor.
+fn get_message(
+    matches: &ArgMatches,
+    config: &gix::config::Snapshot,
+    default_message: &str,
+) -> Result<Message<'static>> {
+    if let Some(message) = matches.get_one::<String>("message") {
+        return Ok(Message::from(message.clone()));
+    }
+
+    let filename = ".stgit-publish.txt";
+    std::fs::write(
+        filename,
+        format!(
+            "{default_message}\n\
+             \n\
+             # Please enter the message for the public branch commit. Lines \
+             starting\n\
+             # with '#' will be ignored. An empty message aborts publishing.\n"
+        ),
+    )?;
+    let buf = patchedit::call_editor(filename, config)?;
+    let message: String = buf
+        .to_str()
+        .map_err(|_| anyhow!("`{filename}` is not valid UTF-8"))?
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let message = message.trim();
+    if message.is_empty() {
+        Err(anyhow!("aborting publish due to empty message"))
+    } else {
+        Ok(Message::from(format!("{message}\n")))
+    }
+}
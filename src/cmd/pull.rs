@@ -12,9 +12,10 @@ use crate::{
     argset,
     color::get_color_stdout,
     ext::RepositoryExtended,
-    print_info_message,
-    stack::{InitializationPolicy, Stack, StackAccess, StackStateAccess},
-    stupid::Stupid,
+    patch::PatchName,
+    print_info_message, print_warning_message,
+    stack::{InitializationPolicy, Stack, StackAccess, StackStateAccess, TransactionError},
+    stupid::{StatusOptions, Stupid},
 };
 
 pub(super) const STGIT_COMMAND: super::StGitCommand = super::StGitCommand {
@@ -40,6 +41,20 @@ fn make() -> clap::Command {
              patches back onto the stack. Merge conflicts may occur during the final \
              push step. Those conflicts need to be resolved manually.\n\
              \n\
+             The '--reapply-policy' option determines what happens when a patch cannot \
+             be pushed back without conflicts. With \"stop\", the default, the push \
+             stops at the conflicting patch. With \"skip\", conflicting patches are \
+             left unapplied and reported, and the remaining patches are pushed. With \
+             \"hide\", conflicting patches are hidden instead of being left \
+             unapplied. The default may be set with the \"stgit.pull.reapply-policy\" \
+             configuration variable.\n\
+             \n\
+             With the \"pull\" pull-policy, the current branch's upstream branch is \
+             checked before pulling. If the upstream branch no longer contains the \
+             commit the stack was based on, e.g. because it was force-pushed, the \
+             pull is refused and rebasing the stack onto the new upstream with 'stg \
+             rebase' is suggested instead of merging the rewritten history.\n\
+             \n\
              See git-fetch(1) for the format of remote repository argument.
              ",
         )
@@ -61,6 +76,23 @@ fn make() -> clap::Command {
              stack, but become empty after the pull operation.",
         ))
         .arg(argset::push_conflicts_arg())
+        .arg(
+            Arg::new("reapply-policy")
+                .long("reapply-policy")
+                .help("What to do with patches that conflict when pushed back")
+                .long_help(
+                    "Determine what to do with patches that would conflict when \
+                     pushed back after pulling. With \"stop\", pushing stops at the \
+                     first conflicting patch, leaving the conflicts to be resolved. \
+                     With \"skip\", conflicting patches are left unapplied. With \
+                     \"hide\", conflicting patches are hidden. Patches that are \
+                     skipped or hidden are reported. Overrides the \
+                     \"stgit.pull.reapply-policy\" configuration variable.",
+                )
+                .value_name("policy")
+                .value_parser(["stop", "skip", "hide"])
+                .conflicts_with("nopush"),
+        )
 }
 
 enum PullPolicy {
@@ -93,6 +125,27 @@ impl Display for PullPolicy {
     }
 }
 
+/// Policy for patches that conflict when pushed back after pulling.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ReapplyPolicy {
+    Stop,
+    Skip,
+    Hide,
+}
+
+impl FromStr for ReapplyPolicy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "stop" => Ok(ReapplyPolicy::Stop),
+            "skip" => Ok(ReapplyPolicy::Skip),
+            "hide" => Ok(ReapplyPolicy::Hide),
+            _ => Err(anyhow!("unsupported reapply-policy `{s}`")),
+        }
+    }
+}
+
 fn run(matches: &ArgMatches) -> Result<()> {
     let repo = gix::Repository::open()?;
     let stack = Stack::current(&repo, InitializationPolicy::RequireInitialized)?;
@@ -113,6 +166,13 @@ fn run(matches: &ArgMatches) -> Result<()> {
     )?;
 
     let allow_push_conflicts = argset::resolve_allow_push_conflicts(&config, matches);
+    let reapply_policy = if let Some(policy) = argset::get_one_str(matches, "reapply-policy") {
+        ReapplyPolicy::from_str(policy)?
+    } else if let Some(policy) = config.string("stgit.pull.reapply-policy") {
+        ReapplyPolicy::from_str(&policy.to_str_lossy())?
+    } else {
+        ReapplyPolicy::Stop
+    };
 
     let parent_remote;
     let remote_name = match policy {
@@ -147,9 +207,18 @@ fn run(matches: &ArgMatches) -> Result<()> {
         return Err(anyhow!("this branch is protected; pulls are not permitted"));
     }
 
-    stupid.statuses(None)?.check_index_and_worktree_clean()?;
+    let statuses = stupid.statuses(Some(StatusOptions::default().include_branch_headers(true)))?;
+    statuses.check_index_and_worktree_clean()?;
     stack.check_head_top_mismatch()?;
 
+    let upstream = statuses.headers().branch_upstream();
+    if let (PullPolicy::Pull, Some(upstream)) = (&policy, upstream.as_ref()) {
+        if let Some(message) = check_upstream_rewritten(&repo, stack.base().id, upstream)? {
+            return Err(anyhow!(message));
+        }
+    }
+    let base_id = stack.base().id;
+
     let applied = stack.applied().to_vec();
 
     stack
@@ -177,10 +246,13 @@ fn run(matches: &ArgMatches) -> Result<()> {
             let remote_name = remote_name.unwrap();
             print_info_message(matches, &format!("Pulling from `{remote_name}`"));
             if !stupid.user_pull(&pull_cmd, &remote_name)? {
-                return Err(super::Error::CausedConflicts(
-                    "pull resulted in conflicts".to_string(),
-                )
-                .into());
+                let mut message = "pull resulted in conflicts".to_string();
+                if let Some(upstream) = upstream.as_ref() {
+                    if let Some(hint) = check_upstream_rewritten(&repo, base_id, upstream)? {
+                        message = format!("{message}\n{hint}");
+                    }
+                }
+                return Err(super::Error::CausedConflicts(message).into());
             }
             None
         }
@@ -262,13 +334,50 @@ fn run(matches: &ArgMatches) -> Result<()> {
     if !matches.get_flag("nopush") {
         stack.check_head_top_mismatch()?;
         let check_merged = matches.get_flag("merged");
-        stack
-            .setup_transaction()
-            .use_index_and_worktree(true)
-            .allow_push_conflicts(allow_push_conflicts)
-            .with_output_stream(get_color_stdout(matches))
-            .transact(|trans| trans.push_patches(&applied, check_merged))
-            .execute("pull (reapply)")?;
+        if reapply_policy == ReapplyPolicy::Stop {
+            stack
+                .setup_transaction()
+                .use_index_and_worktree(true)
+                .allow_push_conflicts(allow_push_conflicts)
+                .with_output_stream(get_color_stdout(matches))
+                .transact(|trans| trans.push_patches(&applied, check_merged))
+                .execute("pull (reapply)")?;
+        } else {
+            let mut conflicting: Vec<PatchName> = Vec::new();
+            stack
+                .setup_transaction()
+                .use_index_and_worktree(true)
+                .allow_push_conflicts(false)
+                .with_output_stream(get_color_stdout(matches))
+                .transact(|trans| {
+                    for patchname in &applied {
+                        match trans.push_patches(&[patchname], check_merged) {
+                            Ok(()) => {}
+                            Err(e) if is_disallowed_conflict(&e) => {
+                                conflicting.push(patchname.clone());
+                            }
+                            Err(e) => return Err(e),
+                        }
+                    }
+                    if reapply_policy == ReapplyPolicy::Hide && !conflicting.is_empty() {
+                        trans.hide_patches(&conflicting)?;
+                    }
+                    Ok(())
+                })
+                .execute("pull (reapply)")?;
+
+            let action = if reapply_policy == ReapplyPolicy::Hide {
+                "hidden"
+            } else {
+                "left unapplied"
+            };
+            for patchname in &conflicting {
+                print_warning_message(
+                    matches,
+                    &format!("`{patchname}` conflicts with the pulled changes; {action}"),
+                );
+            }
+        }
     }
 
     if config.boolean("stgit.keepoptimized").unwrap_or(false) {
@@ -277,3 +386,71 @@ fn run(matches: &ArgMatches) -> Result<()> {
 
     Ok(())
 }
+
+/// Detect an upstream branch that was rewritten since the stack was based on it.
+///
+/// The upstream branch's reflog is searched for the most recent upstream commit
+/// contained in the stack's base. If the current upstream no longer contains that
+/// commit, the upstream was likely force-pushed and pulling would merge the
+/// rewritten history with the old history. Returns a message suggesting to rebase
+/// the stack onto the new upstream instead.
+fn check_upstream_rewritten(
+    repo: &gix::Repository,
+    base_id: gix::ObjectId,
+    upstream: &str,
+) -> Result<Option<String>> {
+    let reference = if let Ok(reference) = repo.find_reference(upstream) {
+        reference
+    } else {
+        return Ok(None);
+    };
+    let upstream_id = reference
+        .clone()
+        .into_fully_peeled_id()?
+        .object()?
+        .peel_tags_to_end()?
+        .try_into_commit()?
+        .id;
+    let stupid = repo.stupid();
+    if stupid.is_ancestor(upstream_id, base_id)? {
+        return Ok(None);
+    }
+    let mut log_ids: Vec<gix::ObjectId> = Vec::new();
+    if let Some(lines) = reference.log_iter().rev()? {
+        for line in lines {
+            let new_oid = line?.new_oid;
+            if log_ids.last() != Some(&new_oid) {
+                log_ids.push(new_oid);
+            }
+        }
+    }
+    for log_id in log_ids {
+        if log_id != upstream_id && stupid.is_ancestor(log_id, base_id)? {
+            if stupid.is_ancestor(log_id, upstream_id)? {
+                break;
+            }
+            return Ok(Some(format!(
+                "upstream `{upstream}` no longer contains `{}` which the stack is based \
+                 on; it may have been force-pushed\n\
+                 use `stg rebase {upstream}` to rebase the stack onto `{}` instead",
+                log_id.to_hex_with_len(7),
+                upstream_id.to_hex_with_len(7),
+            )));
+        }
+    }
+    Ok(None)
+}
+
+/// Determine whether a push failed only because it would have resulted in conflicts.
+///
+/// Pushes with conflicts disallowed halt before any state is modified, so the
+/// conflicting patch may simply be left unapplied.
+fn is_disallowed_conflict(err: &anyhow::Error) -> bool {
+    matches!(
+        err.downcast_ref::<TransactionError>(),
+        Some(TransactionError::TransactionHalt {
+            conflicts: false,
+            ..
+        })
+    )
+}
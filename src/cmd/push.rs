@@ -2,14 +2,17 @@
 
 //! `stg push` implementation.
 
+use std::{fmt::Write, str::FromStr};
+
 use anyhow::{anyhow, Result};
+use bstr::ByteSlice;
 use clap::{Arg, ArgMatches};
 
 use crate::{
     argset,
     color::get_color_stdout,
     ext::RepositoryExtended,
-    patch::{patchrange, PatchName, PatchRange, RangeConstraint},
+    patch::{patchedit, patchrange, PatchLocator, PatchName, PatchRange, RangeConstraint},
     stack::{InitializationPolicy, Stack, StackStateAccess},
     stupid::Stupid,
 };
@@ -29,12 +32,40 @@ fn make() -> clap::Command {
              \n\
              By default, the first unapplied patch is pushed.\n\
              \n\
+             As an alternative to giving a range of patches, '--until' pushes the \
+             unapplied patches preceding the given patch, without pushing the given \
+             patch itself, while '--through' pushes the unapplied patches up to and \
+             including the given patch.\n\
+             \n\
              Unapplied patches may be pushed in arbitrary order, but out of \
              order pushes may result in merge conflicts. If there are conflicts \
              while pushing a patch, the conflicts are written to the work tree \
              and the push command halts. Conflicts may then be resolved using \
              the normal Git methods, or alternatively the push may be undone \
-             using 'stg undo'.",
+             using 'stg undo'.\n\
+             \n\
+             With '--reject', a conflicting patch's hunks that apply cleanly are \
+             applied to the work tree, and the remaining hunks are written \
+             to '*.rej' files instead of leaving conflict markers, as with 'git apply \
+             --reject'. The pushed patch is left empty; once the rejected hunks are \
+             applied manually, the changes may be added to the patch with 'stg \
+             refresh'.\n\
+             \n\
+             With '--test-cmd', the given command is run in the work tree after \
+             each patch is pushed, making it possible to check that each patch \
+             builds or passes tests. Pushing stops at the first patch for which \
+             the command fails, leaving that patch applied. With '--skip-failing', \
+             failing patches are popped instead and pushing continues with the \
+             remaining patches. A report of which patches passed and failed is \
+             printed after pushing. Each patch is pushed as a separate operation \
+             that may be undone with 'stg undo'.\n\
+             \n\
+             With '--interactive', the patches to push are listed in an editor along \
+             with their subjects. All unapplied patches are listed unless patches are \
+             given on the command line. The lines may be reordered to change the \
+             order in which the patches are pushed, and removing a patch's line \
+             leaves that patch unapplied. The listed patches are pushed in a single \
+             transaction.",
         )
         .override_usage(super::make_usage(
             "stg push",
@@ -42,6 +73,10 @@ fn make() -> clap::Command {
                 "[OPTIONS] [patch]...",
                 "[OPTIONS] -n <number>",
                 "[OPTIONS] --all",
+                "[OPTIONS] --until <patch>",
+                "[OPTIONS] --through <patch>",
+                "[OPTIONS] --label <label>",
+                "[OPTIONS] --interactive [patch]...",
             ],
         ))
         .arg(
@@ -50,7 +85,7 @@ fn make() -> clap::Command {
                 .value_name("patch")
                 .num_args(1..)
                 .value_parser(clap::value_parser!(PatchRange))
-                .conflicts_with_all(["all", "number"]),
+                .conflicts_with_all(["all", "number", "label", "until", "through"]),
         )
         .arg(
             Arg::new("all")
@@ -58,12 +93,13 @@ fn make() -> clap::Command {
                 .short('a')
                 .help("Push all unapplied patches")
                 .action(clap::ArgAction::SetTrue)
-                .conflicts_with("number"),
+                .conflicts_with_all(["number", "label", "until", "through"]),
         )
         .arg(
             Arg::new("number")
                 .long("number")
                 .short('n')
+                .visible_alias("count")
                 .help("Push specified number of patches")
                 .long_help(
                     "Push the specified number of patches.\n\
@@ -74,7 +110,40 @@ fn make() -> clap::Command {
                 .num_args(1)
                 .allow_negative_numbers(true)
                 .value_name("n")
-                .value_parser(clap::value_parser!(isize)),
+                .value_parser(clap::value_parser!(isize))
+                .conflicts_with_all(["label", "until", "through"]),
+        )
+        .arg(
+            argset::label_arg()
+                .help("Push the unapplied patches labeled <label>")
+                .conflicts_with_all(["until", "through"]),
+        )
+        .arg(
+            Arg::new("until")
+                .long("until")
+                .help("Push unapplied patches up to, but excluding, <patch>")
+                .value_name("patch")
+                .num_args(1)
+                .allow_hyphen_values(true)
+                .value_parser(clap::value_parser!(PatchLocator))
+                .conflicts_with("through"),
+        )
+        .arg(
+            Arg::new("through")
+                .long("through")
+                .help("Push unapplied patches up to and including <patch>")
+                .value_name("patch")
+                .num_args(1)
+                .allow_hyphen_values(true)
+                .value_parser(clap::value_parser!(PatchLocator)),
+        )
+        .arg(
+            Arg::new("interactive")
+                .long("interactive")
+                .short('i')
+                .help("Select and order the patches to push in an editor")
+                .action(clap::ArgAction::SetTrue)
+                .conflicts_with_all(["all", "number", "label", "until", "through", "noapply"]),
         )
         .arg(
             Arg::new("reverse")
@@ -86,7 +155,7 @@ fn make() -> clap::Command {
             Arg::new("noapply")
                 .long("noapply")
                 .help("Reorder patches by pushing without applying")
-                .conflicts_with_all(["all", "number"])
+                .conflicts_with_all(["all", "number", "until", "through"])
                 .requires("patchranges-unapplied")
                 .action(clap::ArgAction::SetTrue)
                 .conflicts_with_all(["set-tree", "merged"]),
@@ -110,6 +179,34 @@ fn make() -> clap::Command {
                 )
                 .action(clap::ArgAction::SetTrue),
         )
+        .arg(
+            Arg::new("reject")
+                .long("reject")
+                .help("Write conflicting hunks to *.rej files")
+                .action(clap::ArgAction::SetTrue)
+                .conflicts_with_all(["noapply", "set-tree"]),
+        )
+        .arg(
+            Arg::new("test-cmd")
+                .long("test-cmd")
+                .help("Run <command> after pushing each patch")
+                .long_help(
+                    "Run <command> with the shell in the work tree after pushing \
+                     each patch. Pushing stops at the first patch for which the \
+                     command fails.",
+                )
+                .num_args(1)
+                .value_name("command")
+                .value_hint(clap::ValueHint::CommandString)
+                .conflicts_with("noapply"),
+        )
+        .arg(
+            Arg::new("skip-failing")
+                .long("skip-failing")
+                .help("Pop patches failing the test command and continue")
+                .requires("test-cmd")
+                .action(clap::ArgAction::SetTrue),
+        )
         .arg(argset::keep_arg())
         .arg(argset::merged_arg())
         .arg(argset::committer_date_is_author_date_arg())
@@ -144,8 +241,42 @@ fn run(matches: &ArgMatches) -> Result<()> {
             )?
         } else if stack.unapplied().is_empty() {
             return Err(anyhow!("no unapplied patches"));
-        } else if matches.get_flag("all") {
+        } else if matches.get_flag("all") || matches.get_flag("interactive") {
             stack.unapplied().to_vec()
+        } else if let Some(label) = matches.get_one::<String>("label") {
+            let patches: Vec<PatchName> = stack
+                .unapplied()
+                .iter()
+                .filter(|pn| stack.get_patch(pn).labels.contains(label))
+                .cloned()
+                .collect();
+            if patches.is_empty() {
+                return Err(anyhow!("no unapplied patches labeled `{label}`"));
+            }
+            patches
+        } else if let Some((locator, inclusive)) = matches
+            .get_one::<PatchLocator>("until")
+            .map(|locator| (locator, false))
+            .or_else(|| {
+                matches
+                    .get_one::<PatchLocator>("through")
+                    .map(|locator| (locator, true))
+            })
+        {
+            let patchname = locator.resolve_name(&stack)?;
+            if stack.is_applied(&patchname) {
+                return Err(anyhow!("patch `{patchname}` is already applied"));
+            }
+            let pos = stack
+                .unapplied()
+                .iter()
+                .position(|pn| pn == &patchname)
+                .ok_or_else(|| anyhow!("patch `{patchname}` is hidden"))?;
+            let end = if inclusive { pos + 1 } else { pos };
+            if end == 0 {
+                return Ok(());
+            }
+            stack.unapplied()[..end].to_vec()
         } else if let Some(number) = opt_number {
             let num_unapplied = stack.unapplied().len();
             let num_to_take: usize = {
@@ -174,6 +305,7 @@ fn run(matches: &ArgMatches) -> Result<()> {
     let settree_flag = matches.get_flag("set-tree");
     let merged_flag = matches.get_flag("merged");
     let keep_flag = matches.get_flag("keep");
+    let reject_flag = matches.get_flag("reject");
 
     repo.check_repository_state()?;
     let statuses = stupid.statuses(None)?;
@@ -187,11 +319,59 @@ fn run(matches: &ArgMatches) -> Result<()> {
         patches.reverse();
     }
 
+    if matches.get_flag("interactive") {
+        patches = edit_instructions(&stack, &patches)?;
+    }
+
+    let committer_date_is_author_date = matches.get_flag("committer-date-is-author-date");
+
+    if let Some(test_cmd) = matches.get_one::<String>("test-cmd") {
+        let mut stack = stack;
+        let mut report: Vec<(PatchName, bool)> = Vec::new();
+        for patchname in &patches {
+            stack = stack
+                .setup_transaction()
+                .use_index_and_worktree(true)
+                .allow_push_conflicts(allow_push_conflicts)
+                .reject_push_conflicts(reject_flag)
+                .committer_date_is_author_date(committer_date_is_author_date)
+                .with_output_stream(get_color_stdout(matches))
+                .transact(|trans| {
+                    if settree_flag {
+                        trans.push_tree(patchname, true)
+                    } else {
+                        trans.push_patches(&[patchname], merged_flag)
+                    }
+                })
+                .execute("push")?;
+
+            let passed = run_test_cmd(&repo, test_cmd)?;
+            report.push((patchname.clone(), passed));
+            if !passed {
+                if matches.get_flag("skip-failing") {
+                    stack = stack
+                        .setup_transaction()
+                        .use_index_and_worktree(true)
+                        .with_output_stream(get_color_stdout(matches))
+                        .transact(|trans| {
+                            trans.pop_patches(|pn| pn == patchname)?;
+                            Ok(())
+                        })
+                        .execute(&format!("push: skip failing {patchname}"))?;
+                } else {
+                    break;
+                }
+            }
+        }
+        return finish_test_report(&report);
+    }
+
     stack
         .setup_transaction()
         .use_index_and_worktree(true)
         .allow_push_conflicts(allow_push_conflicts)
-        .committer_date_is_author_date(matches.get_flag("committer-date-is-author-date"))
+        .reject_push_conflicts(reject_flag)
+        .committer_date_is_author_date(committer_date_is_author_date)
         .with_output_stream(get_color_stdout(matches))
         .transact(|trans| {
             if settree_flag {
@@ -218,3 +398,100 @@ fn run(matches: &ArgMatches) -> Result<()> {
 
     Ok(())
 }
+
+const INTERACTIVE_HELP_LINES: &str = "\
+#
+# Commands:
+#
+#   p, push <patch> = push patch
+#
+# These lines can be reordered; the patches are pushed from top to bottom.
+#
+# Removing a patch's line leaves the patch unapplied.
+# If all lines are removed, the push is aborted.
+";
+
+/// Let the user select and reorder the patches to push.
+fn edit_instructions(stack: &Stack, patchnames: &[PatchName]) -> Result<Vec<PatchName>> {
+    let name_width = patchnames.iter().map(PatchName::len).max().unwrap_or(0);
+    let mut template = String::with_capacity(4096);
+    for patchname in patchnames {
+        let subject = super::squash::patch_subject(stack, patchname)?;
+        writeln!(template, "push {patchname:name_width$} # {subject}")?;
+    }
+    template.push_str(INTERACTIVE_HELP_LINES);
+
+    let filename = ".stgit-push-interactive.txt";
+    std::fs::write(filename, template)?;
+    let buf = patchedit::call_editor(filename, &stack.repo.config_snapshot())?;
+    let buf = buf
+        .to_str()
+        .map_err(|_| anyhow!("`{filename}` is not valid UTF-8"))?;
+
+    let mut push_patchnames: Vec<PatchName> = Vec::new();
+    for line in buf.lines() {
+        let instruction_str = line.split_once('#').map_or(line, |(s, _)| s).trim();
+        if instruction_str.is_empty() {
+            continue;
+        }
+        let (action_str, patchname_str) = instruction_str
+            .split_once(|c: char| c.is_ascii_whitespace())
+            .ok_or_else(|| anyhow!("bad instruction line: `{}`", line.trim()))?;
+        let patchname = PatchName::from_str(patchname_str.trim())?;
+        if !patchnames.contains(&patchname) {
+            return Err(anyhow!("patch `{patchname}` is not being pushed"));
+        } else if push_patchnames.contains(&patchname) {
+            return Err(anyhow!("duplicated patch name `{patchname}`"));
+        }
+        match action_str {
+            "p" | "push" => {}
+            _ => return Err(anyhow!("unknown instruction action `{action_str}`")),
+        }
+        push_patchnames.push(patchname);
+    }
+
+    if push_patchnames.is_empty() {
+        Err(anyhow!("no patches listed; push aborted"))
+    } else {
+        Ok(push_patchnames)
+    }
+}
+
+/// Run the test command with the shell in the work tree, returning whether it passed.
+fn run_test_cmd(repo: &gix::Repository, test_cmd: &str) -> Result<bool> {
+    let work_dir = repo
+        .work_dir()
+        .ok_or_else(|| anyhow!("cannot run test command in a bare repository"))?;
+    let status = std::process::Command::new("sh")
+        .arg("-c")
+        .arg(test_cmd)
+        .current_dir(work_dir)
+        .status()
+        .map_err(|e| anyhow!("could not run test command `{test_cmd}`: {e}"))?;
+    Ok(status.success())
+}
+
+/// Print the test command report and fail if any patch failed the test command.
+fn finish_test_report(report: &[(PatchName, bool)]) -> Result<()> {
+    for (patchname, passed) in report {
+        println!("{}: {patchname}", if *passed { "passed" } else { "failed" });
+    }
+    let failed: Vec<&str> = report
+        .iter()
+        .filter(|(_, passed)| !passed)
+        .map(|(patchname, _)| patchname.as_ref())
+        .collect();
+    if failed.is_empty() {
+        Ok(())
+    } else {
+        Err(anyhow!(
+            "test command failed for {}: {}",
+            if failed.len() == 1 {
+                "patch"
+            } else {
+                "patches"
+            },
+            failed.join(" ")
+        ))
+    }
+}
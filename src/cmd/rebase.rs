@@ -444,6 +444,7 @@ fn interactive_pushback(
                             trans,
                             &squash_matches,
                             &squash_patchnames,
+                            &[],
                             Some(target_patchname),
                             false,
                         )?;
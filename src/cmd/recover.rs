@@ -0,0 +1,159 @@
+// SPDX-License-Identifier: GPL-2.0-only
+
+//! `stg recover` implementation.
+
+use anyhow::{anyhow, Result};
+use clap::{Arg, ArgMatches};
+
+use crate::{
+    argset,
+    branchloc::BranchLocator,
+    color::get_color_stdout,
+    ext::RepositoryExtended,
+    patch::PatchName,
+    stack::{backup, InitializationPolicy, PatchState, Stack, StackAccess, StackStateAccess},
+};
+
+pub(super) const STGIT_COMMAND: super::StGitCommand = super::StGitCommand {
+    name: "recover",
+    category: super::CommandCategory::StackManipulation,
+    make,
+    run,
+};
+
+fn make() -> clap::Command {
+    clap::Command::new(STGIT_COMMAND.name)
+        .about("List or restore patches from automatic backups")
+        .long_about(
+            "List or restore patches from the automatic backups made before \
+             destructive operations.\n\
+             \n\
+             Before `stg delete`, `stg clean`, `stg squash`, and `stg reset --hard` \
+             discard patches, the stack state is backed-up to \
+             'refs/stgit-backup/<branch>/<n>'. Without arguments, the available \
+             backups are listed along with the operation and the patches affected \
+             by it.\n\
+             \n\
+             When a backup number is given, the patches affected by the backed-up \
+             operation, or just the given patches, are restored as unapplied \
+             patches. Restored patches that collide with existing patch names are \
+             given a unique name.\n\
+             \n\
+             Backups expire after the number of days configured by \
+             \"stgit.backup.expire\", 30 days by default. Expired backups are \
+             removed when new backups are made.",
+        )
+        .override_usage(super::make_usage(
+            "stg recover",
+            &["[OPTIONS]", "[OPTIONS] <backup> [patch]..."],
+        ))
+        .arg(
+            Arg::new("backup")
+                .help("Backup to restore patches from")
+                .value_name("backup")
+                .value_parser(argset::parse_usize),
+        )
+        .arg(
+            Arg::new("patches")
+                .help("Patches to restore")
+                .value_name("patch")
+                .num_args(1..)
+                .requires("backup")
+                .value_parser(clap::value_parser!(PatchName)),
+        )
+        .arg(argset::branch_arg())
+}
+
+fn run(matches: &ArgMatches) -> Result<()> {
+    let repo = gix::Repository::open()?;
+    let stack = Stack::from_branch_locator(
+        &repo,
+        matches.get_one::<BranchLocator>("branch"),
+        InitializationPolicy::RequireInitialized,
+    )?;
+    let backups = backup::list(&repo, stack.get_branch_name())?;
+
+    let number = if let Some(number) = matches.get_one::<usize>("backup").copied() {
+        number
+    } else {
+        for backup in &backups {
+            let patchnames: Vec<String> = backup
+                .patchnames()?
+                .iter()
+                .map(ToString::to_string)
+                .collect();
+            println!(
+                "{}  {}  {}: {}",
+                backup.number,
+                backup.time()?.format(gix::date::time::format::ISO8601),
+                backup.operation()?,
+                patchnames.join(" "),
+            );
+        }
+        return Ok(());
+    };
+
+    let backup = backups
+        .iter()
+        .find(|backup| backup.number == number)
+        .ok_or_else(|| anyhow!("backup `{number}` not found"))?;
+    let backup_state = backup.state(&repo)?;
+
+    let patchnames = if let Some(patchnames) = matches.get_many::<PatchName>("patches") {
+        patchnames.cloned().collect()
+    } else {
+        backup.patchnames()?
+    };
+
+    let mut to_restore: Vec<(PatchName, PatchName)> = Vec::new();
+    let mut disallow: Vec<PatchName> = stack.all_patches().cloned().collect();
+    for patchname in patchnames {
+        if !backup_state.has_patch(&patchname) {
+            return Err(anyhow!(
+                "patch `{patchname}` not found in backup `{number}`"
+            ));
+        }
+        let backup_commit_id = backup_state.get_patch_commit_id(&patchname);
+        if stack.has_patch(&patchname) && stack.get_patch_commit_id(&patchname) == backup_commit_id
+        {
+            continue;
+        }
+        let new_patchname = patchname.clone().uniquify(&[], &disallow);
+        disallow.push(new_patchname.clone());
+        to_restore.push((patchname, new_patchname));
+    }
+
+    if to_restore.is_empty() {
+        return Ok(());
+    }
+
+    // A restored patch keeps its UUID unless the UUID is still in use, i.e. when the
+    // original patch remains in the stack. The copy then keeps the fresh UUID that is
+    // assigned to every new patch.
+    let uuids_in_use: Vec<String> = stack
+        .all_patches()
+        .filter_map(|patchname| stack.get_patch(patchname).uuid())
+        .map(ToString::to_string)
+        .collect();
+
+    stack
+        .setup_transaction()
+        .with_output_stream(get_color_stdout(matches))
+        .transact(|trans| {
+            for (patchname, new_patchname) in &to_restore {
+                let patch = backup_state.get_patch(patchname);
+                trans.new_unapplied(new_patchname, patch.commit.id, trans.unapplied().len())?;
+                trans.set_patch_labels(new_patchname, patch.labels.clone())?;
+                for (key, value) in &patch.metadata {
+                    if key == PatchState::UUID_KEY && uuids_in_use.contains(value) {
+                        continue;
+                    }
+                    trans.set_patch_metadata(new_patchname, key, Some(value))?;
+                }
+            }
+            Ok(())
+        })
+        .execute(&format!("recover: backup {number}"))?;
+
+    Ok(())
+}
@@ -5,7 +5,7 @@
 use anyhow::Result;
 use clap::Arg;
 
-use super::undo::find_undo_state;
+use super::undo::{find_undo_state, print_undo_states, stash_discarded_changes};
 use crate::{
     argset,
     color::get_color_stdout,
@@ -28,7 +28,10 @@ fn make() -> clap::Command {
              before the undo. Consecutive redos will undo the effects of consecutive invocations \
              of 'stg undo'.\n\
              \n\
-             It is an error to redo if the last stack-modifying command was not an undo.",
+             It is an error to redo if the last stack-modifying command was not an undo.\n\
+             \n\
+             The '--list' option lists the stack states that may be redone to, \
+             numbered by the number of undos to redo.",
         )
         .arg(
             Arg::new("number")
@@ -53,13 +56,30 @@ fn make() -> clap::Command {
                 .help("Discard changes in the index and worktree")
                 .action(clap::ArgAction::SetTrue),
         )
+        .arg(argset::stash_discarded_arg())
+        .arg(
+            Arg::new("list")
+                .long("list")
+                .help("List the states that may be redone to")
+                .action(clap::ArgAction::SetTrue)
+                .conflicts_with_all(["number", "hard"]),
+        )
 }
 
 fn run(matches: &clap::ArgMatches) -> Result<()> {
     let repo = gix::Repository::open()?;
     let stack = Stack::current(&repo, InitializationPolicy::RequireInitialized)?;
+
+    if matches.get_flag("list") {
+        return print_undo_states(&stack, true);
+    }
+
     let redo_steps = matches.get_one::<usize>("number").copied().unwrap_or(1);
 
+    if matches.get_flag("stash-discarded") {
+        stash_discarded_changes(&repo, matches, "redo --hard")?;
+    }
+
     stack
         .setup_transaction()
         .use_index_and_worktree(true)
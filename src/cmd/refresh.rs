@@ -3,11 +3,13 @@
 //! `stg refresh` implementation.
 
 use std::{
+    ffi::OsString,
     path::{Path, PathBuf},
     rc::Rc,
 };
 
 use anyhow::{anyhow, Result};
+use bstr::ByteSlice;
 use clap::{Arg, ArgGroup, ArgMatches, ValueHint};
 use indexmap::IndexSet;
 
@@ -17,8 +19,9 @@ use crate::{
     ext::{CommitExtended, RepositoryExtended, SignatureExtended},
     hook::run_pre_commit_hook,
     patch::{patchedit, LocationConstraint, PatchLocator, PatchName},
+    print_info_message, print_warning_message,
     stack::{InitializationPolicy, Stack, StackAccess, StackStateAccess},
-    stupid::{Status, StatusOptions, Statuses, Stupid, StupidContext},
+    stupid::{Status, StatusEntryKind, StatusOptions, Statuses, Stupid, StupidContext},
     wrap::Message,
 };
 
@@ -44,7 +47,25 @@ fn make() -> clap::Command {
              \n\
              You may optionally list one or more files or directories \
              relative to the current working directory; if you do, only \
-             matching files will be updated.\n\
+             matching files will be updated. Paths may use git pathspec magic, \
+             e.g. ':!*.gen.c' to refresh everything except generated files. The \
+             '--exclude' option may also be used to exclude paths from the \
+             refresh. When a renamed file matches by either its old or new name, \
+             both sides of the rename are refreshed, unless \
+             \"stgit.refresh.renames\" is set to false.\n\
+             \n\
+             With '--route', the modified files are routed to patches according to \
+             the \"stgit.route.<name>\" configuration variables, where <name> is \
+             matched against the first component of each modified file's path (i.e. \
+             a top-level directory or file) and the variable's value is the name of \
+             the patch to refresh the file into. All routed patches are refreshed in \
+             a single operation. Modified files not matching any route are left in \
+             the work tree.\n\
+             \n\
+             With '--interactive', the work tree changes are presented hunk by \
+             hunk, as with `git add --patch`, and only the selected hunks are \
+             refreshed into the patch. The remaining changes are left in the work \
+             tree.\n\
              \n\
              Behind the scenes, stg refresh first creates a new \
              temporary patch with your updates, and then merges that \
@@ -52,7 +73,9 @@ fn make() -> clap::Command {
              asked to refresh a patch other than the topmost patch, \
              there can be conflicts; in that case, the temporary patch \
              will be left for you to take care of, for example with stg \
-             squash.\n\
+             squash. Alternatively, when refreshing an unapplied patch, the \
+             '--push-on-conflict' option pushes the patch and leaves the \
+             conflicting changes in the work tree to be resolved in place.\n\
              \n\
              The creation of the temporary patch is recorded in a \
              separate entry in the patch stack log; this means that one \
@@ -68,6 +91,21 @@ fn make() -> clap::Command {
                 .value_parser(clap::value_parser!(PathBuf)),
         )
         .next_help_heading("Refresh Options")
+        .arg(
+            Arg::new("exclude")
+                .long("exclude")
+                .short('x')
+                .help("Do not refresh files matching path")
+                .long_help(
+                    "Do not refresh files matching path. This option may be \
+                     specified multiple times. Equivalent to specifying \
+                     ':(exclude)<path>' as a pathspec.",
+                )
+                .value_name("path")
+                .action(clap::ArgAction::Append)
+                .value_hint(ValueHint::AnyPath)
+                .value_parser(clap::value_parser!(PathBuf)),
+        )
         .arg(
             Arg::new("update")
                 .long("update")
@@ -86,7 +124,31 @@ fn make() -> clap::Command {
                      contents of the index.",
                 )
                 .action(clap::ArgAction::SetTrue)
-                .conflicts_with_all(["pathspecs", "update", "submodules", "force"]),
+                .conflicts_with_all(["pathspecs", "exclude", "update", "submodules", "force"]),
+        )
+        .arg(
+            Arg::new("interactive")
+                .long("interactive")
+                .help("Interactively select hunks to refresh")
+                .long_help(
+                    "Interactively select the work tree hunks to refresh into the \
+                     patch, as with `git add --patch`. Hunks that are not selected \
+                     remain in the work tree.",
+                )
+                .action(clap::ArgAction::SetTrue)
+                .conflicts_with_all(["index", "update", "submodules"]),
+        )
+        .arg(
+            Arg::new("route")
+                .long("route")
+                .help("Refresh files into patches according to configured routes")
+                .long_help(
+                    "Refresh each modified file into the patch configured for it \
+                     with the \"stgit.route.<name>\" configuration variables. Files \
+                     are routed by the first component of their path.",
+                )
+                .action(clap::ArgAction::SetTrue)
+                .conflicts_with_all(["index", "interactive", "update", "patch", "edit"]),
         )
         .arg(
             Arg::new("force")
@@ -101,6 +163,20 @@ fn make() -> clap::Command {
                 .action(clap::ArgAction::SetTrue),
         )
         .arg(argset::push_conflicts_arg())
+        .arg(
+            Arg::new("push-on-conflict")
+                .long("push-on-conflict")
+                .help("Push an unapplied patch to resolve refresh conflicts in place")
+                .long_help(
+                    "When the changes cannot be merged cleanly into the unapplied \
+                     patch given with '--patch', push the patch and apply the changes \
+                     to the work tree with conflict markers, instead of saving them \
+                     in a temporary patch. Once the conflicts are resolved, the patch \
+                     may be refreshed as usual.",
+                )
+                .action(clap::ArgAction::SetTrue)
+                .requires("patch"),
+        )
         .arg(
             Arg::new("patch")
                 .long("patch")
@@ -117,6 +193,14 @@ fn make() -> clap::Command {
                 .long("annotate")
                 .short('a')
                 .help("Annotate the patch log entry with <note>")
+                .long_help(
+                    "Annotate the patch log entry with <note>. The placeholders \
+                     '%(patchname)s', '%(branch)s', '%(date)s', and '%(version)s' in \
+                     <note> are replaced with the patch name, branch name, current \
+                     date, and StGit version, respectively. When this option is not \
+                     given, the 'stgit.refresh.annotate' configuration variable is used \
+                     as the annotation, if set.",
+                )
                 .num_args(1)
                 .value_name("note")
                 .value_hint(ValueHint::Other),
@@ -125,6 +209,15 @@ fn make() -> clap::Command {
             Arg::new("submodules")
                 .long("submodules")
                 .help("Include submodules in patch content")
+                .long_help(
+                    "Include submodules in patch content.\n\
+                     \n\
+                     The commit checked-out in each modified submodule is recorded in \
+                     the patch. Changes within a submodule, including new commits in \
+                     nested submodules, can only be recorded once they are committed \
+                     in the containing submodule; a warning is printed for any such \
+                     uncommitted or untracked submodule content.",
+                )
                 .action(clap::ArgAction::SetTrue)
                 .conflicts_with("update"),
         )
@@ -160,6 +253,10 @@ fn run(matches: &ArgMatches) -> Result<()> {
 
     stack.check_head_top_mismatch()?;
 
+    if matches.get_flag("route") {
+        return route_refresh(stack, matches);
+    }
+
     let patchname = if let Some(patch_loc) = matches.get_one::<PatchLocator>("patch") {
         patch_loc
             .resolve_name(&stack)?
@@ -177,7 +274,13 @@ fn run(matches: &ArgMatches) -> Result<()> {
     )?;
 
     let mut log_msg = "refresh ".to_string();
-    let opt_annotate = matches.get_one::<String>("annotate");
+    let opt_annotate = argset::resolve_annotation(
+        &repo,
+        matches,
+        "refresh",
+        patchname.as_ref(),
+        stack.get_branch_name(),
+    )?;
 
     // Make temp patch
     let temp_commit_id = stack.repo.commit_ex(
@@ -204,7 +307,7 @@ fn run(matches: &ArgMatches) -> Result<()> {
         ))?;
 
     let mut absorb_success = false;
-    stack
+    let stack = stack
         .setup_transaction()
         .use_index_and_worktree(true)
         .with_output_stream(get_color_stdout(matches))
@@ -256,7 +359,7 @@ fn run(matches: &ArgMatches) -> Result<()> {
                 } else {
                     log_msg.push_str(patchname.as_ref());
                 }
-                if let Some(annotation) = opt_annotate {
+                if let Some(annotation) = &opt_annotate {
                     log_msg.push_str("\n\n");
                     log_msg.push_str(annotation);
                 }
@@ -314,7 +417,7 @@ fn run(matches: &ArgMatches) -> Result<()> {
                     } else {
                         log_msg.push_str(patchname.as_ref());
                     }
-                    if let Some(annotation) = opt_annotate {
+                    if let Some(annotation) = &opt_annotate {
                         log_msg.push_str("\n\n");
                         log_msg.push_str(annotation);
                     }
@@ -326,7 +429,15 @@ fn run(matches: &ArgMatches) -> Result<()> {
         })
         .execute(&log_msg)?;
 
-    if !absorb_success {
+    if !absorb_success && matches.get_flag("push-on-conflict") {
+        return push_with_conflicts(
+            stack,
+            matches,
+            &patchname,
+            &temp_patchname,
+            allow_push_conflicts,
+        );
+    } else if !absorb_success {
         println!(
             "The new changes did not apply cleanly to {}. \
              They were saved in {}.",
@@ -337,9 +448,251 @@ fn run(matches: &ArgMatches) -> Result<()> {
     Ok(())
 }
 
+/// Push an unapplied patch and apply the temp patch's changes with conflicts.
+///
+/// The temp patch is only deleted once the target patch has been pushed, such that
+/// the refreshed changes are not lost if the target patch itself cannot be pushed.
+fn push_with_conflicts(
+    stack: Stack,
+    matches: &ArgMatches,
+    patchname: &PatchName,
+    temp_patchname: &PatchName,
+    allow_push_conflicts: bool,
+) -> Result<()> {
+    let stupid = stack.repo.stupid();
+    let temp_commit = stack.get_patch_commit(temp_patchname);
+    let base_tree_id = temp_commit.get_parent_commit()?.tree_id()?.detach();
+    let temp_tree_id = temp_commit.tree_id()?.detach();
+
+    stack
+        .setup_transaction()
+        .use_index_and_worktree(true)
+        .allow_push_conflicts(allow_push_conflicts)
+        .with_output_stream(get_color_stdout(matches))
+        .transact(|trans| {
+            trans.push_patches(&[patchname], false)?;
+            trans.delete_patches(|pn| pn == temp_patchname)?;
+            Ok(())
+        })
+        .execute(&format!("refresh {patchname} (push with conflicts)"))?;
+
+    if stupid.apply_treediff_to_worktree_and_index(
+        base_tree_id,
+        temp_tree_id,
+        None::<Vec<PathBuf>>,
+    )? {
+        print_info_message(
+            matches,
+            &format!("The new changes were applied to the work tree; refresh `{patchname}`"),
+        );
+        Ok(())
+    } else {
+        Err(super::Error::CausedConflicts(format!(
+            "the new changes conflict with `{patchname}`; \
+             resolve conflicts manually then refresh"
+        ))
+        .into())
+    }
+}
+
+/// Refresh modified files into the patches configured with `stgit.route.<name>`.
+///
+/// The routed changes are carried from the stack's head tree to each target patch's
+/// tree and all target patches are updated in a single transaction.
+fn route_refresh(stack: Stack, matches: &ArgMatches) -> Result<()> {
+    let repo = stack.repo;
+    let stupid = repo.stupid();
+    let routes = get_routes(&repo.config_snapshot())?;
+    if routes.is_empty() {
+        return Err(anyhow!(
+            "no routes configured; set `stgit.route.<name>` to a patch name"
+        ));
+    }
+    if stack.applied().is_empty() {
+        return Err(super::Error::NoAppliedPatches.into());
+    }
+
+    let mut status_opts = StatusOptions::default();
+    if let Some(pathspecs) = get_pathspecs(matches) {
+        status_opts.pathspecs(pathspecs);
+    }
+    let statuses = stupid.statuses(Some(&status_opts))?;
+    let refresh_paths =
+        determine_refresh_paths(&stupid, &statuses, None, None, matches.get_flag("force"))?;
+
+    let mut routed: Vec<(PatchName, Vec<PathBuf>)> = Vec::new();
+    let mut num_unrouted = 0;
+    for path in refresh_paths {
+        let first_component = path
+            .components()
+            .next()
+            .and_then(|component| component.as_os_str().to_str());
+        let route = first_component.and_then(|first| {
+            routes
+                .iter()
+                .find(|(name, _)| name.eq_ignore_ascii_case(first))
+        });
+        if let Some((name, patchname)) = route {
+            if !stack.has_patch(patchname) {
+                return Err(anyhow!(
+                    "patch `{patchname}` routed by `stgit.route.{name}` does not exist"
+                ));
+            }
+            if let Some((_, paths)) = routed.iter_mut().find(|(pn, _)| pn == patchname) {
+                paths.push(path);
+            } else {
+                routed.push((patchname.clone(), vec![path]));
+            }
+        } else {
+            num_unrouted += 1;
+        }
+    }
+
+    if routed.is_empty() {
+        return Err(anyhow!("no modified files match the configured routes"));
+    }
+
+    let head_tree_id = stack.get_branch_head().tree_id()?.detach();
+    let mut new_commits: Vec<(PatchName, gix::ObjectId)> = Vec::with_capacity(routed.len());
+    let mut num_routed_files = 0;
+    for (patchname, paths) in &routed {
+        num_routed_files += paths.len();
+        let routed_tree_id = stupid.with_temp_index(|stupid_temp| {
+            stupid_temp.read_tree(head_tree_id)?;
+            stupid_temp.update_index(Some(paths))?;
+            stupid_temp.write_tree()
+        })?;
+        let patch_commit = stack.get_patch_commit(patchname);
+        let patch_tree_id = patch_commit.tree_id()?.detach();
+        let tree_id = stupid
+            .with_temp_index(|stupid_temp| {
+                stupid_temp.read_tree(patch_tree_id)?;
+                if stupid_temp.apply_treediff_to_index(head_tree_id, routed_tree_id, true)? {
+                    Ok(Some(stupid_temp.write_tree()?))
+                } else {
+                    Ok(None)
+                }
+            })?
+            .ok_or_else(|| {
+                anyhow!("changes routed to `{patchname}` do not apply cleanly to the patch")
+            })?;
+        let author = patch_commit.author_strict()?;
+        let committer = repo.get_rewrite_committer(Some(patch_commit), author.time, false)?;
+        let commit_id = repo.commit_ex(
+            &author,
+            &committer,
+            &patch_commit.message_ex(),
+            tree_id,
+            [patch_commit.get_parent_commit()?.id],
+        )?;
+        new_commits.push((patchname.clone(), commit_id));
+    }
+
+    let first_pos = routed
+        .iter()
+        .filter_map(|(patchname, _)| stack.applied().iter().position(|pn| pn == patchname))
+        .min();
+    let to_repush: Vec<PatchName> =
+        first_pos.map_or_else(Vec::new, |pos| stack.applied()[pos..].to_vec());
+
+    let mut log_msg = "refresh (route)".to_string();
+    let routed_names = routed
+        .iter()
+        .map(|(patchname, _)| patchname.as_ref())
+        .collect::<Vec<&str>>()
+        .join(", ");
+    if let Some(annotation) = argset::resolve_annotation(
+        repo,
+        matches,
+        "refresh",
+        &routed_names,
+        stack.get_branch_name(),
+    )? {
+        log_msg.push_str("\n\n");
+        log_msg.push_str(&annotation);
+    }
+
+    let stack = stack
+        .setup_transaction()
+        .with_output_stream(get_color_stdout(matches))
+        .transact(|trans| {
+            trans.pop_patches(|pn| to_repush.contains(pn))?;
+            for (patchname, commit_id) in &new_commits {
+                trans.update_patch(patchname, *commit_id)?;
+            }
+            trans.push_patches(&to_repush, false)?;
+            Ok(())
+        })
+        .execute(&log_msg)?;
+
+    // The routed changes are now part of the stack. Leave the unrouted changes in the
+    // work tree.
+    stupid.read_tree(stack.get_branch_head().tree_id()?.detach())?;
+    stupid.update_index_refresh()?;
+
+    print_info_message(
+        matches,
+        &format!(
+            "refreshed {num_routed_files} file{} into {} patch{}{}",
+            if num_routed_files == 1 { "" } else { "s" },
+            new_commits.len(),
+            if new_commits.len() == 1 { "" } else { "es" },
+            if num_unrouted > 0 {
+                format!(
+                    "; {num_unrouted} file{} left in the work tree",
+                    if num_unrouted == 1 { "" } else { "s" }
+                )
+            } else {
+                String::new()
+            }
+        ),
+    );
+
+    Ok(())
+}
+
+/// Get the routes configured with `stgit.route.<name>` variables.
+///
+/// Each route maps the name of a top-level directory or file to a patch name.
+fn get_routes(config: &gix::config::Snapshot) -> Result<Vec<(String, PatchName)>> {
+    let mut routes: Vec<(String, PatchName)> = Vec::new();
+    if let Some(sections) = config.plumbing().sections_by_name("stgit") {
+        for section in
+            sections.filter(|section| section.header().subsection_name() == Some("route".into()))
+        {
+            for key in section.keys() {
+                let name = key.to_str_lossy().to_string();
+                if let Some(value) = section.value(key) {
+                    let patchname = value
+                        .to_str()
+                        .ok()
+                        .and_then(|value| value.parse::<PatchName>().ok())
+                        .ok_or_else(|| {
+                            anyhow!(
+                                "invalid patch name `{}` for `stgit.route.{name}`",
+                                value.to_str_lossy()
+                            )
+                        })?;
+                    // Later configuration overrides earlier configuration.
+                    routes.retain(|(existing, _)| !existing.eq_ignore_ascii_case(&name));
+                    routes.push((name, patchname));
+                }
+            }
+        }
+    }
+    Ok(routes)
+}
+
+/// Determine the paths to be refreshed from the given status entries.
+///
+/// When `rename_statuses` are provided, the paths are extended such that both sides of
+/// any renamed entry found therein are refreshed if either side would be refreshed.
+/// These statuses should not be limited by pathspecs such that renames across the
+/// pathspecs' boundaries are detected.
 fn determine_refresh_paths(
     stupid: &StupidContext,
     statuses: &Statuses,
+    rename_statuses: Option<&Statuses>,
     patch_commit: Option<&Rc<gix::Commit>>,
     force: bool,
 ) -> Result<IndexSet<PathBuf>> {
@@ -391,10 +744,26 @@ fn determine_refresh_paths(
     }
 
     // TODO: interrogate status once and avoid allocating PathBufs
-    let refresh_paths = refresh_paths
+    let mut refresh_paths: IndexSet<PathBuf> = refresh_paths
         .iter()
         .map(|path| path.to_path_buf())
         .collect();
+
+    if let Some(rename_statuses) = rename_statuses {
+        let mut rename_paths = IndexSet::new();
+        for entry in rename_statuses.iter() {
+            if let Some(orig_path) = entry.orig_path() {
+                let path = entry.path();
+                if refresh_paths.contains(path) || refresh_paths.contains(orig_path) {
+                    rename_paths.insert(path);
+                    rename_paths.insert(orig_path);
+                }
+            }
+        }
+        rename_statuses.check_conflicts_filter(|entry| rename_paths.contains(entry.path()))?;
+        refresh_paths.extend(rename_paths.into_iter().map(Path::to_path_buf));
+    }
+
     Ok(refresh_paths)
 }
 
@@ -429,10 +798,22 @@ pub(crate) fn assemble_refresh_tree(
     limit_to_patchname: Option<&PatchName>,
 ) -> Result<gix::ObjectId> {
     let stupid = stack.repo.stupid();
-    let opt_pathspecs = matches.get_many::<PathBuf>("pathspecs");
-    let is_path_limiting = limit_to_patchname.is_some() || opt_pathspecs.is_some();
+    let opt_pathspecs = get_pathspecs(matches);
+    let has_pathspecs = opt_pathspecs.is_some();
+    let is_path_limiting = limit_to_patchname.is_some() || has_pathspecs;
     let statuses;
 
+    // `stg new` shares this function, but does not have the `--interactive` option.
+    if matches
+        .try_get_one::<bool>("interactive")
+        .ok()
+        .flatten()
+        .copied()
+        .unwrap_or(false)
+    {
+        return assemble_interactive_refresh_tree(stack, matches);
+    }
+
     let refresh_paths = if matches.get_flag("index") {
         // When refreshing from the index, no path limiting may be used.
         assert!(!is_path_limiting);
@@ -457,9 +838,33 @@ pub(crate) fn assemble_refresh_tree(
         }
         statuses = stupid.statuses(Some(&status_opts))?;
 
+        if use_submodules {
+            warn_unrecordable_submodule_content(matches, &stupid, &statuses, Path::new(""))?;
+        }
+
+        let detect_renames = stack
+            .repo
+            .config_snapshot()
+            .boolean("stgit.refresh.renames")
+            .unwrap_or(true);
+        // Renames are only detected within the pathspecs, so the complete status
+        // is needed to find renames crossing the pathspecs' boundaries.
+        let unlimited_statuses;
+        let rename_statuses = if !detect_renames || !is_path_limiting {
+            None
+        } else if has_pathspecs {
+            let mut status_opts = StatusOptions::default();
+            status_opts.include_submodules(use_submodules);
+            unlimited_statuses = stupid.statuses(Some(&status_opts))?;
+            Some(&unlimited_statuses)
+        } else {
+            Some(&statuses)
+        };
+
         determine_refresh_paths(
             &stupid,
             &statuses,
+            rename_statuses,
             maybe_patch_commit,
             matches.get_flag("force"),
         )?
@@ -479,3 +884,127 @@ pub(crate) fn assemble_refresh_tree(
 
     Ok(tree_id)
 }
+
+/// Warn about submodule content that cannot be recorded in the patch.
+///
+/// Only the commit checked-out in a submodule may be recorded. Uncommitted and
+/// untracked files in submodules, as well as new commits in nested submodules that are
+/// not yet committed in their containing submodule, are reported. Submodules are
+/// inspected recursively; `prefix` is the path of the submodule whose statuses are
+/// given, relative to the root of the work tree.
+fn warn_unrecordable_submodule_content(
+    matches: &ArgMatches,
+    stupid: &StupidContext,
+    statuses: &Statuses,
+    prefix: &Path,
+) -> Result<()> {
+    for entry in statuses.iter() {
+        let state = if let Some(state) = entry.submodule_state() {
+            state
+        } else {
+            continue;
+        };
+        let path = prefix.join(entry.path());
+        if state.commit_changed && !prefix.as_os_str().is_empty() {
+            print_warning_message(
+                matches,
+                &format!(
+                    "nested submodule `{}` has a new commit that is not committed in `{}`",
+                    path.display(),
+                    prefix.display(),
+                ),
+            );
+        }
+        if state.has_tracked_changes || state.has_untracked_changes {
+            let sub_statuses = stupid.submodule_statuses(&path)?;
+            let mut has_tracked_files = false;
+            let mut has_untracked_files = false;
+            for sub_entry in sub_statuses.iter() {
+                if matches!(sub_entry.kind(), StatusEntryKind::Untracked) {
+                    has_untracked_files = true;
+                } else if sub_entry.submodule_state().is_none() {
+                    has_tracked_files = true;
+                }
+            }
+            if has_tracked_files {
+                print_warning_message(
+                    matches,
+                    &format!(
+                        "submodule `{}` has uncommitted changes that cannot be recorded",
+                        path.display()
+                    ),
+                );
+            }
+            if has_untracked_files {
+                print_warning_message(
+                    matches,
+                    &format!(
+                        "submodule `{}` has untracked files that cannot be recorded",
+                        path.display()
+                    ),
+                );
+            }
+            warn_unrecordable_submodule_content(matches, stupid, &sub_statuses, &path)?;
+        }
+    }
+    Ok(())
+}
+
+/// Get the pathspecs limiting the refresh, including any `--exclude` paths.
+///
+/// Each excluded path is converted to an `:(exclude)` pathspec. When only excluded
+/// paths are given, git matches all other paths.
+fn get_pathspecs(matches: &ArgMatches) -> Option<Vec<OsString>> {
+    let mut pathspecs: Vec<OsString> = matches
+        .get_many::<PathBuf>("pathspecs")
+        .map_or_else(Vec::new, |paths| {
+            paths.map(|path| path.as_os_str().to_owned()).collect()
+        });
+    // `stg new` shares the refresh pathspecs, but does not have `--exclude`.
+    if let Ok(Some(excludes)) = matches.try_get_many::<PathBuf>("exclude") {
+        for path in excludes {
+            let mut pathspec = OsString::from(":(exclude)");
+            pathspec.push(path);
+            pathspecs.push(pathspec);
+        }
+    }
+    if pathspecs.is_empty() {
+        None
+    } else {
+        Some(pathspecs)
+    }
+}
+
+/// Assemble the refresh tree from interactively selected work tree hunks.
+///
+/// The hunks are selected with `git add --patch` into a temporary index based on the
+/// branch head. The default index is then reset to the resulting tree, leaving the
+/// unselected changes in the work tree.
+fn assemble_interactive_refresh_tree(stack: &Stack, matches: &ArgMatches) -> Result<gix::ObjectId> {
+    let stupid = stack.repo.stupid();
+    let mut status_opts = StatusOptions::default();
+    let opt_pathspecs = get_pathspecs(matches);
+    if let Some(pathspecs) = opt_pathspecs.as_ref() {
+        status_opts.pathspecs(pathspecs);
+    }
+    stupid.statuses(Some(&status_opts))?.check_conflicts()?;
+
+    let tree_id = stupid.with_temp_index(|stupid_temp| {
+        stupid_temp.read_tree(stack.get_branch_head().tree_id()?.detach())?;
+        stupid_temp.add_patch(opt_pathspecs)?;
+        stupid_temp.write_tree()
+    })?;
+    stupid.read_tree(tree_id)?;
+
+    let tree_id = if !matches.get_flag("no-verify")
+        && run_pre_commit_hook(stack.repo, matches.get_flag("edit"))?
+        && !stupid.diff_index_quiet(tree_id)?
+    {
+        // The hook updated files in the index
+        stupid.write_tree()?
+    } else {
+        tree_id
+    };
+
+    Ok(tree_id)
+}
@@ -2,18 +2,20 @@
 
 //! `stg repair` implementation.
 
-use std::rc::Rc;
+use std::{fmt::Write, rc::Rc};
 
 use anyhow::{anyhow, Result};
 use bstr::ByteSlice;
+use clap::Arg;
 use indexmap::{indexset, IndexSet};
 
 use crate::{
     color::get_color_stdout,
     ext::{CommitExtended, RepositoryExtended},
-    patch::PatchName,
+    patch::{patchedit, PatchName},
     print_info_message, print_warning_message,
     stack::{InitializationPolicy, Stack, StackAccess, StackStateAccess},
+    stupid::Stupid,
 };
 
 pub(super) const STGIT_COMMAND: super::StGitCommand = super::StGitCommand {
@@ -59,10 +61,109 @@ fn make() -> clap::Command {
              valid workflows where git commands are used followed by `stg repair`. For \
              example, new patches can be created by first making commits with a \
              graphical commit tool and then running `stg repair` to convert those \
-             commits into patches.",
+             commits into patches.\n\
+             \n\
+             With '--interactive', the commits made on top of the stack are listed in \
+             an editor before any patches are created. For each commit, choose whether \
+             it becomes a new patch, is absorbed into the patch preceding it, or is \
+             discarded. Changes from discarded commits are removed from the work tree \
+             and index.",
+        )
+        .arg(
+            Arg::new("interactive")
+                .long("interactive")
+                .short('i')
+                .help("Choose what becomes of each commit on top of the stack")
+                .action(clap::ArgAction::SetTrue),
         )
 }
 
+/// What becomes of a commit found on top of the stack.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Disposition {
+    Patch,
+    Absorb,
+    Discard,
+}
+
+const INTERACTIVE_HELP_LINES: &str = "\
+#
+# Commands:
+#
+#   p, patch <commit> = make the commit a new patch
+#   a, absorb <commit> = absorb the commit into the preceding patch
+#   d, discard <commit> = discard the commit and its changes
+#
+# These lines can be reordered; the commits are processed from top to bottom.
+#
+# Removing a commit's line discards the commit.
+# If all lines are removed, the repair is aborted.
+";
+
+/// Let the user choose what becomes of each commit on top of the stack.
+///
+/// Returns the commits, in the order they are to be applied, along with their
+/// dispositions.
+fn edit_instructions<'repo>(
+    repo: &gix::Repository,
+    commits: &[Rc<gix::Commit<'repo>>],
+) -> Result<Vec<(Disposition, Rc<gix::Commit<'repo>>)>> {
+    let mut template = String::with_capacity(4096);
+    for commit in commits {
+        let message = commit.message_raw()?.to_str_lossy();
+        let subject = message.lines().next().unwrap_or_default();
+        writeln!(
+            template,
+            "patch {} # {subject}",
+            commit.id.to_hex_with_len(12)
+        )?;
+    }
+    template.push_str(INTERACTIVE_HELP_LINES);
+
+    let filename = ".stgit-repair-interactive.txt";
+    std::fs::write(filename, template)?;
+    let buf = patchedit::call_editor(filename, &repo.config_snapshot())?;
+    let buf = buf
+        .to_str()
+        .map_err(|_| anyhow!("`{filename}` is not valid UTF-8"))?;
+
+    let mut instructions: Vec<(Disposition, Rc<gix::Commit>)> = Vec::new();
+    for line in buf.lines() {
+        let instruction_str = line.split_once('#').map_or(line, |(s, _)| s).trim();
+        if instruction_str.is_empty() {
+            continue;
+        }
+        let (action_str, commit_str) = instruction_str
+            .split_once(|c: char| c.is_ascii_whitespace())
+            .ok_or_else(|| anyhow!("bad instruction line: `{}`", line.trim()))?;
+        let commit_str = commit_str.trim();
+        let mut candidates = commits.iter().filter(|commit| {
+            commit_str.len() >= 4 && commit.id.to_string().starts_with(commit_str)
+        });
+        let commit = match (candidates.next(), candidates.next()) {
+            (Some(commit), None) => commit,
+            (Some(_), Some(_)) => return Err(anyhow!("ambiguous commit `{commit_str}`")),
+            (None, _) => return Err(anyhow!("commit `{commit_str}` is not being repaired")),
+        };
+        if instructions.iter().any(|(_, c)| c.id == commit.id) {
+            return Err(anyhow!("duplicated commit `{commit_str}`"));
+        }
+        let disposition = match action_str {
+            "p" | "patch" => Disposition::Patch,
+            "a" | "absorb" => Disposition::Absorb,
+            "d" | "discard" => Disposition::Discard,
+            _ => return Err(anyhow!("unknown instruction action `{action_str}`")),
+        };
+        instructions.push((disposition, commit.clone()));
+    }
+
+    if instructions.is_empty() {
+        Err(anyhow!("no commits listed; repair aborted"))
+    } else {
+        Ok(instructions)
+    }
+}
+
 fn run(matches: &clap::ArgMatches) -> Result<()> {
     let repo = gix::Repository::open()?;
     let stack = Stack::current(&repo, InitializationPolicy::RequireInitialized)?;
@@ -150,6 +251,23 @@ fn run(matches: &clap::ArgMatches) -> Result<()> {
         }
     }
 
+    // `stg doctor` runs this command, but does not have the `--interactive` option.
+    let is_interactive = matches
+        .try_get_one::<bool>("interactive")
+        .ok()
+        .flatten()
+        .copied()
+        .unwrap_or(false);
+
+    let instructions = if is_interactive && !patchify.is_empty() {
+        edit_instructions(&repo, &patchify)?
+    } else {
+        patchify
+            .into_iter()
+            .map(|commit| (Disposition::Patch, commit))
+            .collect()
+    };
+
     let mut unapplied: Vec<PatchName> = stack
         .applied()
         .iter()
@@ -181,31 +299,100 @@ fn run(matches: &clap::ArgMatches) -> Result<()> {
         .filter(|&pn| !stack.unapplied().contains(pn))
         .for_each(|pn| print_info_message(matches, &format!("`{pn}` is now unapplied")));
 
+    // Commits are rewritten, and thus the work tree may need to be updated, only when
+    // some commits are not simply made into patches in their original order.
+    let is_rewriting = instructions
+        .iter()
+        .any(|(disposition, _)| *disposition != Disposition::Patch);
+    let stupid = repo.stupid();
+    let committer = repo.get_committer()?;
+
     stack
         .setup_transaction()
-        .use_index_and_worktree(false)
+        .use_index_and_worktree(is_rewriting)
+        .allow_bad_head(true)
         .with_output_stream(get_color_stdout(matches))
         .transact(|trans| {
             trans.repair_appliedness(applied, unapplied, hidden);
 
             // Make patches of any linear sequence of commits on top of a patch.
-            if !patchify.is_empty() {
+            let patch_count = instructions
+                .iter()
+                .filter(|(disposition, _)| *disposition == Disposition::Patch)
+                .count();
+            if patch_count > 0 {
                 print_info_message(
                     matches,
                     &format!(
-                        "Creating {} new patch{}",
-                        patchify.len(),
-                        if patchify.len() == 1 { "" } else { "es" }
+                        "Creating {patch_count} new patch{}",
+                        if patch_count == 1 { "" } else { "es" }
                     ),
                 );
+            }
+
+            for (disposition, commit) in &instructions {
+                let short_id = commit.id.to_hex_with_len(12);
+                if *disposition == Disposition::Discard {
+                    print_info_message(matches, &format!("Discarding commit {short_id}"));
+                    continue;
+                }
+
+                // Apply the commit's changes on top of the current top of the stack.
+                let top = trans.top().clone();
+                let parent_tree_id = commit.get_parent_commit()?.tree_id()?.detach();
+                let commit_tree_id = commit.tree_id()?.detach();
+                let top_tree_id = top.tree_id()?.detach();
+                let tree_id = if parent_tree_id == top_tree_id {
+                    commit_tree_id
+                } else {
+                    stupid
+                        .with_temp_index(|stupid_temp| {
+                            stupid_temp.read_tree(top_tree_id)?;
+                            if stupid_temp.apply_treediff_to_index(
+                                parent_tree_id,
+                                commit_tree_id,
+                                true,
+                            )? {
+                                Ok(Some(stupid_temp.write_tree()?))
+                            } else {
+                                Ok(None)
+                            }
+                        })?
+                        .ok_or_else(|| anyhow!("commit {short_id} does not apply cleanly"))?
+                };
 
-                for commit in patchify {
+                if *disposition == Disposition::Absorb {
+                    let top_patchname = trans
+                        .applied()
+                        .last()
+                        .cloned()
+                        .ok_or_else(|| anyhow!("no patch to absorb commit {short_id} into"))?;
+                    let commit_id = repo.commit_ex(
+                        &top.author_strict()?,
+                        committer,
+                        &top.message_ex(),
+                        tree_id,
+                        [top.get_parent_commit()?.id],
+                    )?;
+                    trans.update_patch(&top_patchname, commit_id)?;
+                } else {
+                    let commit_id = if commit.parent_ids().next().unwrap() == top.id {
+                        commit.id
+                    } else {
+                        repo.commit_ex(
+                            &commit.author_strict()?,
+                            committer,
+                            &commit.message_ex(),
+                            tree_id,
+                            [top.id],
+                        )?
+                    };
                     let message = commit.message_raw()?.to_str_lossy();
                     let allow = &[];
                     let disallow: Vec<_> = trans.all_patches().collect();
                     let patchname = PatchName::make(&message, true, patchname_len_limit)
                         .uniquify(allow, &disallow);
-                    trans.new_applied(&patchname, commit.id)?;
+                    trans.new_applied(&patchname, commit_id)?;
                 }
             }
             Ok(())
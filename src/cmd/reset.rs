@@ -2,14 +2,20 @@
 
 //! `stg reset` implementation.
 
+use std::fmt::Write;
+
 use anyhow::{anyhow, Result};
+use bstr::ByteSlice;
 use clap::Arg;
 
 use crate::{
+    argset,
     color::get_color_stdout,
     ext::RepositoryExtended,
-    patch::{patchrange, PatchRange, RangeConstraint},
-    stack::{InitializationPolicy, Stack, StackState},
+    patch::{patchedit, patchrange, PatchName, PatchRange, RangeConstraint},
+    stack::{
+        InitializationPolicy, RawStackState, Stack, StackAccess, StackState, StackStateAccess,
+    },
     stupid::Stupid,
 };
 
@@ -29,17 +35,30 @@ fn make() -> clap::Command {
              \n\
              The state is specified with a commit id from the stack log, which may be \
              viewed with 'stg log'. Patch name arguments may optionally be provided to \
-             limit which patches are reset.",
+             limit which patches are reset.\n\
+             \n\
+             With '--interactive', the states recorded in the stack log are listed in \
+             an editor along with the composition of the series in each state. All \
+             state lines except the line of the state to reset to must be deleted. \
+             With '--patch', only the states affecting the given patch are listed.\n\
+             \n\
+             With '--stash-discarded', the index and worktree changes discarded by \
+             '--hard' are first saved as a git stash entry so that they may be \
+             recovered with `git stash apply`.",
         )
         .override_usage(super::make_usage(
             "stg reset",
-            &["[--hard] [<committish> [<patchname>...]]", "--hard"],
+            &[
+                "[--hard [--stash-discarded]] [<committish> [<patchname>...]]",
+                "[--hard [--stash-discarded]] --interactive [--patch <patch>]",
+                "--hard [--stash-discarded]",
+            ],
         ))
         .trailing_var_arg(true)
         .arg(
             Arg::new("committish")
                 .help("Stack state committish")
-                .required_unless_present("hard"),
+                .required_unless_present_any(["hard", "interactive"]),
         )
         .arg(
             Arg::new("patchranges-all")
@@ -55,11 +74,37 @@ fn make() -> clap::Command {
                 .help("Discard changes in the index and worktree")
                 .action(clap::ArgAction::SetTrue),
         )
+        .arg(argset::stash_discarded_arg())
+        .arg(
+            Arg::new("interactive")
+                .long("interactive")
+                .short('i')
+                .help("Choose the state to reset to in an editor")
+                .action(clap::ArgAction::SetTrue)
+                .conflicts_with_all(["committish", "patchranges-all"]),
+        )
+        .arg(
+            Arg::new("patch")
+                .long("patch")
+                .help("Only list states affecting <patch>")
+                .value_name("patch")
+                .requires("interactive")
+                .value_parser(clap::value_parser!(PatchName)),
+        )
 }
 
 fn run(matches: &clap::ArgMatches) -> Result<()> {
     let repo = gix::Repository::open()?;
-    if let Some(committish) = crate::argset::get_one_str(matches, "committish") {
+    let picked = if matches.get_flag("interactive") {
+        let stack = Stack::current(&repo, InitializationPolicy::RequireInitialized)?;
+        Some(pick_state(&stack, matches.get_one::<PatchName>("patch"))?)
+    } else {
+        None
+    };
+    if let Some(committish) = picked
+        .as_deref()
+        .or_else(|| crate::argset::get_one_str(matches, "committish"))
+    {
         let stack = Stack::current(&repo, InitializationPolicy::RequireInitialized)?;
         let commit_id = repo
             .rev_parse_single(committish)
@@ -69,6 +114,23 @@ fn run(matches: &clap::ArgMatches) -> Result<()> {
             .try_into_commit()
             .map_err(|_| anyhow!("target `{committish}` is not a commit"))?
             .id;
+        if matches.get_flag("stash-discarded") {
+            super::undo::stash_discarded_changes(&repo, matches, "reset --hard")?;
+        }
+        if matches.get_flag("hard") {
+            let reset_state = StackState::from_commit(&repo, &repo.find_commit(commit_id)?)?;
+            let discarded: Vec<PatchName> = stack
+                .all_patches()
+                .filter(|pn| {
+                    !reset_state.has_patch(pn)
+                        || reset_state.get_patch_commit_id(pn) != stack.get_patch_commit_id(pn)
+                })
+                .cloned()
+                .collect();
+            if !discarded.is_empty() {
+                stack.backup("reset --hard", &discarded)?;
+            }
+        }
         stack
             .setup_transaction()
             .use_index_and_worktree(true)
@@ -89,9 +151,103 @@ fn run(matches: &clap::ArgMatches) -> Result<()> {
             .execute("reset")?;
         Ok(())
     } else if matches.get_flag("hard") {
+        if matches.get_flag("stash-discarded") {
+            super::undo::stash_discarded_changes(&repo, matches, "reset --hard")?;
+        }
         let head_tree_id = repo.head_commit()?.tree_id()?.detach();
         repo.stupid().read_tree_checkout_hard(head_tree_id)
     } else {
         unreachable!();
     }
 }
+
+const INTERACTIVE_HELP_LINES: &str = "\
+#
+# Delete the lines of all states except the state to reset to.
+# The series of each state is shown below its line.
+#
+# If all state lines are deleted, the reset is aborted.
+";
+
+/// Let the user choose a stack state from the stack log in an editor.
+///
+/// The stack log states preceding the current state are listed, newest first. When a
+/// patch is given, only the states affecting that patch are listed. The commit id of
+/// the chosen state is returned.
+fn pick_state(stack: &Stack, patchname: Option<&PatchName>) -> Result<String> {
+    let repo = stack.repo;
+    let state_commit = repo
+        .find_reference(stack.get_stack_refname())?
+        .into_fully_peeled_id()?
+        .object()?
+        .try_into_commit()?;
+    let simplified_id = state_commit.parent_ids().next().map(|id| id.detach());
+
+    let candidates: Vec<gix::ObjectId> = if let Some(patchname) = patchname {
+        let mut entries = super::log::find_patch_log_entries(
+            repo,
+            state_commit.id,
+            std::slice::from_ref(patchname),
+        )?;
+        entries.retain(|id| Some(*id) != simplified_id);
+        entries
+    } else {
+        let mut candidates = Vec::new();
+        let mut prev_id = RawStackState::from_tree(state_commit.tree()?)?.prev;
+        while let Some(prev_commit) = prev_id.and_then(|id| repo.find_commit(id).ok()) {
+            if let Some(id) = prev_commit.parent_ids().next() {
+                candidates.push(id.detach());
+            }
+            prev_id = RawStackState::from_tree(prev_commit.tree()?)?.prev;
+        }
+        candidates
+    };
+
+    if candidates.is_empty() {
+        return Err(anyhow!("no previous stack states found"));
+    }
+
+    let mut template = String::with_capacity(4096);
+    for commit_id in &candidates {
+        let commit = repo.find_commit(*commit_id)?;
+        let message = commit.message_raw()?;
+        let summary = message.lines().next().unwrap_or_default().to_str_lossy();
+        writeln!(
+            template,
+            "{}  {}  {summary}",
+            commit.id().shorten_or_id(),
+            commit.time()?.format(gix::date::time::format::ISO8601),
+        )?;
+        let state = RawStackState::from_tree(commit.tree()?)?;
+        let top = state.applied.last();
+        for patchname in &state.applied {
+            let sigil = if Some(patchname) == top { '>' } else { '+' };
+            writeln!(template, "#   {sigil} {patchname}")?;
+        }
+        for patchname in &state.unapplied {
+            writeln!(template, "#   - {patchname}")?;
+        }
+        for patchname in &state.hidden {
+            writeln!(template, "#   ! {patchname}")?;
+        }
+    }
+    template.push_str(INTERACTIVE_HELP_LINES);
+
+    let filename = ".stgit-reset-interactive.txt";
+    std::fs::write(filename, template)?;
+    let buf = patchedit::call_editor(filename, &repo.config_snapshot())?;
+    let buf = buf
+        .to_str()
+        .map_err(|_| anyhow!("`{filename}` is not valid UTF-8"))?;
+
+    let mut selected = buf
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| line.split_whitespace().next().unwrap_or_default());
+    match (selected.next(), selected.next()) {
+        (Some(committish), None) => Ok(committish.to_string()),
+        (None, _) => Err(anyhow!("no stack state selected; reset aborted")),
+        (Some(_), Some(_)) => Err(anyhow!("more than one stack state selected")),
+    }
+}
@@ -0,0 +1,137 @@
+// SPDX-License-Identifier: GPL-2.0-only
+
+//! `stg revert` implementation.
+
+use std::path::PathBuf;
+
+use anyhow::{anyhow, Result};
+use clap::{Arg, ArgMatches};
+
+use crate::{
+    argset,
+    color::get_color_stdout,
+    ext::{CommitExtended, RepositoryExtended},
+    patch::PatchLocator,
+    stack::{InitializationPolicy, Stack, StackStateAccess},
+    stupid::Stupid,
+};
+
+pub(super) const STGIT_COMMAND: super::StGitCommand = super::StGitCommand {
+    name: "revert",
+    category: super::CommandCategory::PatchManipulation,
+    make,
+    run,
+};
+
+fn make() -> clap::Command {
+    clap::Command::new(STGIT_COMMAND.name)
+        .about("Revert a patch's changes to some files")
+        .long_about(
+            "Remove the changes to the given files from a patch.\n\
+             \n\
+             The patch is rewritten such that the files matching the given paths \
+             are the same as in the patch's parent, while the patch's changes to \
+             other files are kept intact. Any patches applied on top of the patch \
+             are pushed again, which may result in conflicts if they depend on the \
+             reverted changes.\n\
+             \n\
+             Unlike 'spill', the reverted changes are not left in the worktree; they \
+             are discarded. The patch does not need to be the topmost patch. Paths \
+             are relative to the current working directory.",
+        )
+        .override_usage(super::make_usage(
+            "stg revert",
+            &["[OPTIONS] <patch> -- <path>..."],
+        ))
+        .arg(
+            Arg::new("patch")
+                .help("Patch to revert changes from")
+                .required(true)
+                .allow_hyphen_values(true)
+                .value_parser(clap::value_parser!(PatchLocator)),
+        )
+        .arg(
+            Arg::new("pathspecs")
+                .help("Revert changes to files matching path")
+                .value_name("path")
+                .required(true)
+                .last(true)
+                .num_args(1..)
+                .value_parser(clap::value_parser!(PathBuf)),
+        )
+        .arg(argset::committer_date_is_author_date_arg())
+}
+
+fn run(matches: &ArgMatches) -> Result<()> {
+    let repo = gix::Repository::open()?;
+    let stack = Stack::current(&repo, InitializationPolicy::AllowUninitialized)?;
+    let stupid = repo.stupid();
+
+    repo.check_repository_state()?;
+    let statuses = stupid.statuses(None)?;
+    statuses.check_conflicts()?;
+    stack.check_head_top_mismatch()?;
+
+    let patchname = matches
+        .get_one::<PatchLocator>("patch")
+        .expect("required argument")
+        .resolve_name(&stack)?;
+    let pathspecs = matches
+        .get_many::<PathBuf>("pathspecs")
+        .expect("required argument");
+
+    let patch_commit = stack.get_patch_commit(&patchname);
+    let patch_tree_id = patch_commit.tree_id()?.detach();
+    let parent_tree_id = patch_commit.get_parent_commit()?.tree_id()?.detach();
+
+    let tree_id = stupid.with_temp_index(|stupid_temp| {
+        stupid_temp.read_tree(patch_tree_id)?;
+        if !stupid_temp.apply_pathlimited_treediff_to_index(
+            patch_tree_id,
+            parent_tree_id,
+            true,
+            pathspecs,
+        )? {
+            return Err(anyhow!("failed to revert changes in `{patchname}`"));
+        }
+        stupid_temp.write_tree()
+    })?;
+    if tree_id == patch_tree_id {
+        return Err(anyhow!("no changes in `{patchname}` match the given paths"));
+    }
+
+    let author = patch_commit.author_strict()?;
+    let committer = repo.get_rewrite_committer(
+        Some(patch_commit),
+        author.time,
+        matches.get_flag("committer-date-is-author-date"),
+    )?;
+    let commit_id = repo.commit_ex(
+        &author,
+        &committer,
+        &patch_commit.message_ex(),
+        tree_id,
+        patch_commit.parent_ids().map(|id| id.detach()),
+    )?;
+
+    stack
+        .setup_transaction()
+        .allow_conflicts(true)
+        .use_index_and_worktree(true)
+        .with_output_stream(get_color_stdout(matches))
+        .transact(|trans| {
+            let popped = if let Some(pos) = trans.applied().iter().position(|pn| pn == &patchname) {
+                let to_pop = trans.applied()[pos + 1..].to_vec();
+                let popped_extra = trans.pop_patches(|pn| to_pop.contains(pn))?;
+                assert!(popped_extra.is_empty());
+                to_pop
+            } else {
+                vec![]
+            };
+            trans.update_patch(&patchname, commit_id)?;
+            trans.push_patches(&popped, false)
+        })
+        .execute(&format!("revert {patchname}"))?;
+
+    Ok(())
+}
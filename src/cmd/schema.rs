@@ -0,0 +1,90 @@
+// SPDX-License-Identifier: GPL-2.0-only
+
+//! `stg schema` implementation.
+
+use std::io::Write;
+
+use anyhow::{anyhow, Result};
+use clap::{Arg, ArgMatches};
+
+pub(super) const STGIT_COMMAND: super::StGitCommand = super::StGitCommand {
+    name: "schema",
+    category: super::CommandCategory::Administration,
+    make,
+    run,
+};
+
+/// JSON schema describing a command's JSON output.
+pub(super) struct JsonSchema {
+    /// Name of the command producing the output.
+    pub command: &'static str,
+
+    /// Version of the output format, incremented whenever the output changes
+    /// incompatibly.
+    pub version: u32,
+
+    /// Function returning the command-specific part of the schema.
+    pub schema: fn() -> serde_json::Value,
+}
+
+/// Schemas of all commands with JSON output, ordered by command name.
+const JSON_SCHEMAS: &[JsonSchema] = &[super::files::JSON_SCHEMA, super::series::JSON_SCHEMA];
+
+fn make() -> clap::Command {
+    clap::Command::new(STGIT_COMMAND.name)
+        .about("Print the JSON schema of a command's output")
+        .long_about(
+            "Print the JSON schema of the JSON output of the given command, e.g. \
+             'stg files --format json' or 'stg series --json'.\n\
+             \n\
+             The schema follows the JSON Schema 2020-12 specification. The \
+             \"version\" property of the schema is incremented whenever the \
+             command's output changes in an incompatible way, allowing consumers \
+             to validate and generate code against a known output format.\n\
+             \n\
+             Without a command, the commands having JSON output are listed along \
+             with their schema versions.",
+        )
+        .arg(
+            Arg::new("command")
+                .help("Command whose output schema to print")
+                .value_parser(clap::builder::PossibleValuesParser::new(
+                    JSON_SCHEMAS.iter().map(|schema| schema.command),
+                )),
+        )
+}
+
+fn run(matches: &ArgMatches) -> Result<()> {
+    let stdout = std::io::stdout();
+    let mut stdout = stdout.lock();
+
+    let command = if let Some(command) = matches.get_one::<String>("command") {
+        command
+    } else {
+        for schema in JSON_SCHEMAS {
+            writeln!(stdout, "{} {}", schema.command, schema.version)?;
+        }
+        return Ok(());
+    };
+
+    let schema = JSON_SCHEMAS
+        .iter()
+        .find(|schema| schema.command == command)
+        .ok_or_else(|| anyhow!("command `{command}` has no JSON output"))?;
+
+    let mut document = serde_json::json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "title": format!("stg {} JSON output", schema.command),
+        "version": schema.version,
+    });
+    if let (Some(document), serde_json::Value::Object(body)) =
+        (document.as_object_mut(), (schema.schema)())
+    {
+        document.extend(body);
+    }
+
+    let mut output = serde_json::to_vec_pretty(&document)?;
+    output.push(b'\n');
+    stdout.write_all(&output)?;
+    Ok(())
+}
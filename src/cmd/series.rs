@@ -2,11 +2,13 @@
 
 //! `stg series` implementation.
 
-use std::{io::Write, str::FromStr};
+use std::{collections::HashSet, io::Write, str::FromStr};
 
 use anyhow::{anyhow, Result};
 use bstr::ByteSlice;
 use clap::{Arg, ArgGroup, ArgMatches, ValueHint};
+use is_terminal::IsTerminal;
+use regex::Regex;
 use termcolor::WriteColor;
 
 use crate::{
@@ -14,7 +16,11 @@ use crate::{
     branchloc::BranchLocator,
     ext::{CommitExtended, RepositoryExtended},
     patch::{patchrange, PatchName, PatchRange, RangeConstraint},
-    stack::{InitializationPolicy, Stack, StackAccess, StackStateAccess},
+    stack::{
+        state_refname_from_branch_name, InitializationPolicy, RawStackState, Stack, StackAccess,
+        StackStateAccess,
+    },
+    stupid::Stupid,
 };
 
 const UNPRINTABLE: &str = "???";
@@ -41,7 +47,22 @@ fn make() -> clap::Command {
              are displayed. The reversed order is more stack-like, with the base of \
              the stack appearing at the bottom of of the display.\n\
              \n\
-             Empty patches are prefixed with a '*' when the --empty option is used.",
+             Empty patches are prefixed with a '*' when the --empty option is used.\n\
+             \n\
+             The --separators option prints a separator line between the applied, \
+             unapplied, and hidden patches. The separator line may be configured with \
+             \"stgit.series.separator\". Separators are shown by default when \
+             \"stgit.series.separators\" is true and the output is a terminal.\n\
+             \n\
+             When \"stgit.hidden.expire\" is set, hidden patches that have been \
+             hidden for more than that many days are reported as expired. See \
+             \"stgit.hidden.expire-action\" for prompting to delete expired \
+             hidden patches instead.\n\
+             \n\
+             The --prompt option prints a single line with the topmost patch, its \
+             position in the series, and a conflict flag. It only reads the stack \
+             metadata, making it suitable for use in shell prompts. See `stg \
+             completion prompt` for a prompt function using it.",
         )
         .override_usage(super::make_usage(
             "stg series",
@@ -49,6 +70,7 @@ fn make() -> clap::Command {
                 "[OPTIONS] [-A] [-U] [-H]",
                 "[OPTIONS] --all",
                 "[OPTIONS] --short",
+                "--prompt [--branch <branch>]",
                 "[OPTIONS] [patch]...",
             ],
         ))
@@ -86,21 +108,39 @@ fn make() -> clap::Command {
             Arg::new("applied")
                 .long("applied")
                 .short('A')
+                .visible_alias("applied-only")
                 .help("Select the applied patches only")
+                .long_help(
+                    "Select the applied patches only. May be combined with \
+                     '--unapplied' and '--hidden' to select patches in any of \
+                     the given states.",
+                )
                 .action(clap::ArgAction::SetTrue),
         )
         .arg(
             Arg::new("unapplied")
                 .long("unapplied")
                 .short('U')
+                .visible_alias("unapplied-only")
                 .help("Select the unapplied patches only")
+                .long_help(
+                    "Select the unapplied patches only. May be combined with \
+                     '--applied' and '--hidden' to select patches in any of \
+                     the given states.",
+                )
                 .action(clap::ArgAction::SetTrue),
         )
         .arg(
             Arg::new("hidden")
                 .long("hidden")
                 .short('H')
+                .visible_alias("hidden-only")
                 .help("Select the hidden patches only")
+                .long_help(
+                    "Select the hidden patches only. May be combined with \
+                     '--applied' and '--unapplied' to select patches in any of \
+                     the given states.",
+                )
                 .action(clap::ArgAction::SetTrue),
         )
         .arg(
@@ -108,11 +148,32 @@ fn make() -> clap::Command {
                 .long("missing")
                 .short('m')
                 .help("Select patches in <branch> not present in current branch")
+                .long_help(
+                    "Select patches in <branch> that are not present in the current \
+                     branch. A patch is considered present if the current branch has a \
+                     patch with the same name or with the same changes, as determined by \
+                     git-patch-id(1).",
+                )
                 .num_args(1)
                 .value_name("branch")
                 .value_parser(clap::value_parser!(BranchLocator))
                 .value_hint(ValueHint::Other),
         )
+        .arg(argset::label_arg().help("Select patches labeled <label> only"))
+        .arg(
+            Arg::new("authored-by")
+                .long("authored-by")
+                .help("Select patches whose author matches <pattern> only")
+                .long_help(
+                    "Select only patches whose author identity, in the form \
+                     \"Name <email>\", matches the regular expression <pattern>. As with \
+                     the '--author' option of git-log(1), author identities are mapped \
+                     through the mailmap before matching.",
+                )
+                .value_name("pattern")
+                .value_parser(|s: &str| Regex::new(s).map_err(|e| e.to_string()))
+                .value_hint(ValueHint::Other),
+        )
         .next_help_heading("Display Options")
         .arg(
             Arg::new("author")
@@ -134,7 +195,97 @@ fn make() -> clap::Command {
                 .short('c')
                 .help("Display the number of selected patches and exit")
                 .action(clap::ArgAction::SetTrue)
-                .conflicts_with_all(["description", "author", "empty", "show-branch", "no-prefix"]),
+                .conflicts_with_all([
+                    "description",
+                    "author",
+                    "empty",
+                    "show-branch",
+                    "no-prefix",
+                    "stat",
+                    "separators",
+                    "count-by-state",
+                ]),
+        )
+        .arg(
+            Arg::new("json")
+                .long("json")
+                .help("Display the selected patches as JSON")
+                .long_help(
+                    "Display the selected patches as a JSON array for consumption by \
+                     other programs. Each patch's name, commit id, state, whether it \
+                     is the topmost patch, whether it is empty, author, and subject \
+                     are included. The schema of the output is printed by 'stg schema \
+                     series'.",
+                )
+                .action(clap::ArgAction::SetTrue)
+                .conflicts_with_all([
+                    "count",
+                    "description",
+                    "author",
+                    "empty",
+                    "show-branch",
+                    "no-prefix",
+                    "commit-id",
+                    "uuid",
+                    "indices",
+                    "offsets",
+                    "stat",
+                    "graph",
+                    "separators",
+                    "count-by-state",
+                ]),
+        )
+        .arg(
+            Arg::new("exit-code")
+                .long("exit-code")
+                .help("Exit with status 4 if no patches are selected")
+                .long_help(
+                    "Exit with status 4 if no patches are selected and 0 otherwise. \
+                     Combined with '--count' and the selection options, this allows \
+                     scripts to cheaply test conditions such as whether there are any \
+                     unapplied patches, e.g. 'stg series --unapplied-only --count \
+                     --exit-code >/dev/null'.",
+                )
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("prompt")
+                .long("prompt")
+                .help("Display top patch, position, and conflict flag for shell prompts")
+                .long_help(
+                    "Display a single line suitable for shell prompts: the name of the \
+                     topmost applied patch followed by its position among the applied \
+                     and unapplied patches, e.g. \"fix-foo 3/12\". When no patches are \
+                     applied, only the position is shown, e.g. \"0/12\". When the index \
+                     has unresolved conflicts, \"|CONFLICT\" is appended. Nothing is \
+                     displayed if the branch has no patches or is not initialized.\n\
+                     \n\
+                     Only the stack metadata is read; patch commits are not loaded.",
+                )
+                .action(clap::ArgAction::SetTrue)
+                .conflicts_with_all([
+                    "patchranges-all",
+                    "all",
+                    "applied",
+                    "unapplied",
+                    "hidden",
+                    "short",
+                    "missing",
+                    "label",
+                    "count",
+                    "count-by-state",
+                    "json",
+                ]),
+        )
+        .arg(
+            Arg::new("count-by-state")
+                .long("count-by-state")
+                .help("Display the number of selected patches by state")
+                .long_help(
+                    "After the selected patches, display a summary line with the \
+                     number of selected applied, unapplied, and hidden patches.",
+                )
+                .action(clap::ArgAction::SetTrue),
         )
         .arg(
             Arg::new("commit-id")
@@ -163,6 +314,20 @@ fn make() -> clap::Command {
                 .action(clap::ArgAction::SetTrue)
                 .overrides_with("commit-id"),
         )
+        .arg(
+            Arg::new("uuid")
+                .long("uuid")
+                .help("Display the UUID of each patch")
+                .long_help(
+                    "Display the UUID of each patch.\n\
+                     \n\
+                     Each patch is assigned a UUID when it is created. Unlike its name, \
+                     a patch's UUID does not change when the patch is renamed, thus \
+                     external tools may use it to follow a patch. Patches created \
+                     before UUIDs were assigned are shown with '-' in place of a UUID.",
+                )
+                .action(clap::ArgAction::SetTrue),
+        )
         .arg(
             Arg::new("description")
                 .long("description")
@@ -275,6 +440,60 @@ fn make() -> clap::Command {
                 .action(clap::ArgAction::SetTrue)
                 .overrides_with("reverse"),
         )
+        .arg(
+            Arg::new("stat")
+                .long("stat")
+                .help("Display a summary of each patch's changes")
+                .long_help(
+                    "Display a summary of each patch's changes: the number of files \
+                     changed along with the number of inserted and deleted lines.",
+                )
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("graph")
+                .long("graph")
+                .help("Display a graph of dependencies between patches")
+                .long_help(
+                    "Display a graph of the dependencies between the listed patches \
+                     to the left of the series.\n\
+                     \n\
+                     A patch depends on an earlier patch in the stack if both patches \
+                     change any of the same files, or if the earlier patch's name is \
+                     declared in the patch's \"depends\" metadata value (see \
+                     'stg meta'), separated by whitespace or commas.\n\
+                     \n\
+                     Each patch that other listed patches depend on has its own lane \
+                     in the graph, marked with '●' on the patch's own line. The lane \
+                     extends to the patch's dependents, whose lines are marked with \
+                     '├', or '└' and '┌' at the ends of the lane. Patches that are not \
+                     marked on any lane neither depend on nor are depended on by the \
+                     other listed patches, and may thus be safely reordered.\n\
+                     \n\
+                     The graph is drawn with Unicode box-drawing characters by \
+                     default. With '--graph=ascii', '*' marks a patch's own lane, \
+                     '+' marks dependents, and '|' continues the lane.",
+                )
+                .value_name("style")
+                .num_args(0..=1)
+                .require_equals(true)
+                .default_missing_value("unicode")
+                .value_parser(["unicode", "ascii"]),
+        )
+        .arg(
+            Arg::new("separators")
+                .long("separators")
+                .help("Display separators between applied, unapplied, and hidden patches")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("no-separators")
+                .long("no-separators")
+                .help("Do not display separators")
+                .hide(true)
+                .action(clap::ArgAction::SetTrue)
+                .overrides_with("separators"),
+        )
         .arg(
             Arg::new("show-branch")
                 .long("showbranch")
@@ -319,11 +538,87 @@ impl FromStr for CommitIdLength {
     }
 }
 
+/// Schema of the `--json` output, for `stg schema`.
+pub(super) const JSON_SCHEMA: super::schema::JsonSchema = super::schema::JsonSchema {
+    command: "series",
+    version: 1,
+    schema: json_schema,
+};
+
+fn json_schema() -> serde_json::Value {
+    serde_json::json!({
+        "type": "array",
+        "items": {
+            "type": "object",
+            "properties": {
+                "name": {
+                    "description": "Name of the patch",
+                    "type": "string",
+                },
+                "commit": {
+                    "description": "Full commit id of the patch",
+                    "type": "string",
+                    "pattern": "^[0-9a-f]{40}([0-9a-f]{24})?$",
+                },
+                "state": {
+                    "description": "State of the patch in the stack",
+                    "enum": ["applied", "unapplied", "hidden"],
+                },
+                "top": {
+                    "description": "Whether the patch is the topmost applied patch",
+                    "type": "boolean",
+                },
+                "empty": {
+                    "description": "Whether the patch makes no changes",
+                    "type": "boolean",
+                },
+                "author": {
+                    "description": "Author of the patch, mapped through the mailmap",
+                    "type": "object",
+                    "properties": {
+                        "name": { "type": "string" },
+                        "email": { "type": "string" },
+                    },
+                    "required": ["name", "email"],
+                    "additionalProperties": false,
+                },
+                "subject": {
+                    "description": "First line of the patch's message",
+                    "type": "string",
+                },
+            },
+            "required": ["name", "commit", "state", "top", "empty", "author", "subject"],
+            "additionalProperties": false,
+        },
+    })
+}
+
+#[derive(serde::Serialize)]
+struct JsonEntry {
+    name: String,
+    commit: String,
+    state: &'static str,
+    top: bool,
+    empty: bool,
+    author: JsonAuthor,
+    subject: String,
+}
+
+#[derive(serde::Serialize)]
+struct JsonAuthor {
+    name: String,
+    email: String,
+}
+
 fn run(matches: &ArgMatches) -> Result<()> {
     let repo = gix::Repository::open()?;
     let opt_branch = matches.get_one::<BranchLocator>("branch");
     let opt_missing = matches.get_one::<BranchLocator>("missing");
 
+    if matches.get_flag("prompt") {
+        return print_prompt(&repo, opt_branch);
+    }
+
     let (stack, ref_stack) = if let Some(ref_branch) = opt_missing {
         (
             Stack::from_branch_locator(
@@ -448,6 +743,46 @@ fn run(matches: &ArgMatches) -> Result<()> {
                 .all_patches()
                 .all(|ref_patchname| patchname != ref_patchname)
         });
+
+        if !patches.is_empty() {
+            let stupid = repo.stupid();
+            let ref_patch_ids: HashSet<gix::ObjectId> = stupid
+                .patch_ids(
+                    ref_stack
+                        .all_patches()
+                        .map(|pn| ref_stack.get_patch_commit_id(pn)),
+                )?
+                .into_values()
+                .collect();
+            let patch_ids =
+                stupid.patch_ids(patches.iter().map(|Entry { commit_id, .. }| *commit_id))?;
+            patches.retain(|Entry { commit_id, .. }| {
+                patch_ids
+                    .get(commit_id)
+                    .map_or(true, |patch_id| !ref_patch_ids.contains(patch_id))
+            });
+        }
+    }
+
+    if let Some(label) = matches.get_one::<String>("label") {
+        patches.retain(|Entry { patchname, .. }| stack.get_patch(patchname).labels.contains(label));
+    }
+
+    if let Some(pattern) = matches.get_one::<Regex>("authored-by") {
+        let mailmap = repo.mailmap();
+        patches.retain(|Entry { commit_id, .. }| {
+            repo.find_commit(*commit_id).map_or(false, |commit| {
+                commit.decode().map_or(false, |commit_ref| {
+                    let author = mailmap.resolve_cow(commit_ref.author());
+                    let ident = format!(
+                        "{} <{}>",
+                        author.name.to_str_lossy(),
+                        author.email.to_str_lossy()
+                    );
+                    pattern.is_match(&ident)
+                })
+            })
+        });
     }
 
     if matches.contains_id("short") {
@@ -473,14 +808,65 @@ fn run(matches: &ArgMatches) -> Result<()> {
         }
     }
 
+    let exit_if_empty = |is_empty: bool| -> Result<()> {
+        if is_empty && matches.get_flag("exit-code") {
+            std::io::stdout().flush()?;
+            return Err(super::Error::NoPatchesSelected.into());
+        }
+        Ok(())
+    };
+
     if matches.get_flag("count") {
         println!("{}", patches.len());
-        return Ok(());
+        return exit_if_empty(patches.is_empty());
+    }
+
+    let is_empty = patches.is_empty();
+
+    if matches.get_flag("json") {
+        if matches.get_flag("reverse") {
+            patches.reverse();
+        }
+        let mailmap = repo.mailmap();
+        let mut entries = Vec::with_capacity(patches.len());
+        for Entry {
+            patchname,
+            commit_id,
+            sigil,
+            ..
+        } in &patches
+        {
+            let commit = repo.find_commit(*commit_id)?;
+            let commit_ref = commit.decode()?;
+            let author = mailmap.resolve_cow(commit_ref.author());
+            entries.push(JsonEntry {
+                name: patchname.to_string(),
+                commit: commit_id.to_string(),
+                state: match sigil {
+                    '+' | '>' => "applied",
+                    '-' => "unapplied",
+                    _ => "hidden",
+                },
+                top: *sigil == '>',
+                empty: commit.is_no_change()?,
+                author: JsonAuthor {
+                    name: author.name.to_str_lossy().into_owned(),
+                    email: author.email.to_str_lossy().into_owned(),
+                },
+                subject: commit_ref.message_summary().to_str_lossy().into_owned(),
+            });
+        }
+        let mut output = serde_json::to_vec_pretty(&entries)?;
+        output.push(b'\n');
+        std::io::stdout().write_all(&output)?;
+        return exit_if_empty(is_empty);
     }
 
     let opt_commit_id = matches.get_one::<CommitIdLength>("commit-id");
+    let uuid_flag = matches.get_flag("uuid");
     let description_flag = matches.get_flag("description");
     let author_flag = matches.get_flag("author");
+    let stat_flag = matches.get_flag("stat");
 
     let branch_prefix = format!("{}:", &stack.get_branch_name());
     let branch_prefix = if matches.get_flag("show-branch") {
@@ -489,14 +875,21 @@ fn run(matches: &ArgMatches) -> Result<()> {
         ""
     };
 
-    let patchname_width = if opt_commit_id.is_some() || description_flag || author_flag {
-        patches
-            .iter()
-            .map(|Entry { patchname, .. }| patchname.len())
-            .max()
-            .unwrap_or(0)
+    let patchname_width =
+        if opt_commit_id.is_some() || uuid_flag || description_flag || author_flag || stat_flag {
+            patches
+                .iter()
+                .map(|Entry { patchname, .. }| patchname.len())
+                .max()
+                .unwrap_or(0)
+        } else {
+            0
+        };
+
+    let mailmap = if author_flag {
+        repo.mailmap()
     } else {
-        0
+        gix::mailmap::Snapshot::default()
     };
 
     let author_width: usize = if author_flag && description_flag {
@@ -504,12 +897,7 @@ fn run(matches: &ArgMatches) -> Result<()> {
             .iter()
             .map(|Entry { commit_id, .. }| -> usize {
                 if let Ok(commit) = repo.find_commit(*commit_id) {
-                    commit
-                        .author()
-                        .ok()
-                        .and_then(|author| author.name.to_str().ok())
-                        .unwrap_or(UNPRINTABLE)
-                        .len()
+                    get_author_name(&commit, &mailmap).chars().count()
                 } else {
                     0
                 }
@@ -524,6 +912,7 @@ fn run(matches: &ArgMatches) -> Result<()> {
     let empty_flag = matches.get_flag("empty");
     let indices_flag = matches.get_flag("indices");
     let offsets_flag = matches.get_flag("offsets");
+    let stupid = repo.stupid();
 
     let index_width = (indices_flag && !patches.is_empty())
         .then(|| patches.last().unwrap().index.to_string().len())
@@ -539,6 +928,31 @@ fn run(matches: &ArgMatches) -> Result<()> {
         })
         .unwrap_or_default();
 
+    let config = repo.config_snapshot();
+    let separator: Option<String> = if matches.get_flag("separators")
+        || (!matches.get_flag("no-separators")
+            && config.boolean("stgit.series.separators").unwrap_or(false)
+            && std::io::stdout().is_terminal())
+    {
+        Some(
+            config
+                .string("stgit.series.separator")
+                .map_or_else(|| "---".to_string(), |s| s.to_string()),
+        )
+    } else {
+        None
+    };
+
+    let counts_by_state = matches.get_flag("count-by-state").then(|| {
+        let count = |sigils: &[char]| {
+            patches
+                .iter()
+                .filter(|entry| sigils.contains(&entry.sigil))
+                .count()
+        };
+        (count(&['+', '>']), count(&['-']), count(&['!']))
+    });
+
     let mut stdout = crate::color::get_color_stdout(matches);
     let mut color_spec = termcolor::ColorSpec::new();
 
@@ -546,6 +960,24 @@ fn run(matches: &ArgMatches) -> Result<()> {
         patches.reverse();
     }
 
+    let mut graph_prefixes = if let Some(style) = matches.get_one::<String>("graph") {
+        let rows: Vec<(&PatchName, gix::ObjectId)> = patches
+            .iter()
+            .map(|entry| (&entry.patchname, entry.commit_id))
+            .collect();
+        dependency_graph(&repo, &stack, &rows, style == "ascii")?
+    } else {
+        Vec::new()
+    }
+    .into_iter();
+
+    let state_of = |sigil: char| match sigil {
+        '+' | '>' => 0,
+        '-' => 1,
+        _ => 2,
+    };
+    let mut prev_state = None;
+
     for Entry {
         patchname,
         commit_id,
@@ -554,9 +986,27 @@ fn run(matches: &ArgMatches) -> Result<()> {
         offset_from_top,
     } in patches
     {
+        if let Some(separator) = separator.as_ref() {
+            let state = state_of(sigil);
+            if prev_state.map_or(false, |prev_state| prev_state != state) {
+                stdout.set_color(color_spec.set_dimmed(true))?;
+                write!(stdout, "{separator}")?;
+                color_spec.clear();
+                stdout.set_color(&color_spec)?;
+                writeln!(stdout)?;
+            }
+            prev_state = Some(state);
+        }
+
         let commit = repo.find_commit(commit_id)?;
         let commit_ref = commit.decode()?;
 
+        if let Some(graph_prefix) = graph_prefixes.next() {
+            stdout.set_color(color_spec.set_fg(Some(termcolor::Color::Cyan)))?;
+            write!(stdout, "{graph_prefix}")?;
+            stdout.set_color(color_spec.set_fg(None))?;
+        }
+
         if empty_flag {
             if commit.is_no_change()? {
                 stdout.set_color(color_spec.set_fg(Some(termcolor::Color::Cyan)))?;
@@ -616,18 +1066,21 @@ fn run(matches: &ArgMatches) -> Result<()> {
             stdout.set_color(color_spec.set_fg(None))?;
         }
 
+        if uuid_flag {
+            let uuid = stack.get_patch(&patchname).uuid().unwrap_or("-");
+            stdout.set_color(color_spec.set_fg(Some(termcolor::Color::Yellow)))?;
+            write!(stdout, "{uuid:36} ")?;
+            stdout.set_color(color_spec.set_fg(None))?;
+        }
+
         write!(stdout, "{branch_prefix}{patchname:patchname_width$}")?;
 
         if author_flag {
             stdout.set_color(color_spec.set_fg(Some(termcolor::Color::Black)))?;
             write!(stdout, " # ")?;
             stdout.set_color(color_spec.set_fg(Some(termcolor::Color::Blue)))?;
-            if let Ok(author) = commit.author_strict() {
-                write!(stdout, "{:author_width$}", &author.name.to_str().unwrap())?;
-            } else {
-                let name = commit_ref.author().name.to_str_lossy();
-                write!(stdout, "{name:author_width$}")?;
-            }
+            let name = get_author_name(&commit, &mailmap);
+            write!(stdout, "{name:author_width$}")?;
         }
         if description_flag {
             stdout.set_color(color_spec.set_fg(Some(termcolor::Color::Black)))?;
@@ -640,10 +1093,209 @@ fn run(matches: &ArgMatches) -> Result<()> {
                 }
             }
         }
+        if stat_flag {
+            let parent_tree_id = commit.get_parent_commit()?.tree_id()?.detach();
+            let shortstat = stupid.diff_tree_shortstat(parent_tree_id, commit_ref.tree())?;
+            stdout.set_color(color_spec.set_fg(Some(termcolor::Color::Black)))?;
+            write!(stdout, " |")?;
+            stdout.set_color(color_spec.set_fg(None))?;
+            if shortstat.is_empty() {
+                write!(stdout, " no changes")?;
+            } else {
+                write!(stdout, " {shortstat}")?;
+            }
+        }
         color_spec.clear();
         stdout.set_color(&color_spec)?;
         writeln!(stdout)?;
     }
 
+    if let Some((applied, unapplied, hidden)) = counts_by_state {
+        writeln!(
+            stdout,
+            "applied: {applied}, unapplied: {unapplied}, hidden: {hidden}"
+        )?;
+    }
+
+    stdout.flush()?;
+    exit_if_empty(is_empty)
+}
+
+/// Print the one-line stack summary for `--prompt`.
+///
+/// The stack state is read directly from the stack state ref so that neither the
+/// patch commits nor the stack's consistency with the branch head are checked.
+fn print_prompt(repo: &gix::Repository, opt_branch: Option<&BranchLocator>) -> Result<()> {
+    let branch = if let Some(loc) = opt_branch {
+        loc.resolve(repo)?
+    } else if let Ok(branch) = repo.get_current_branch() {
+        branch
+    } else {
+        return Ok(());
+    };
+    let branch_name = branch.get_branch_name()?;
+    let state_refname = state_refname_from_branch_name(branch_name);
+    let state_commit = if let Some(reference) = repo.try_find_reference(state_refname.as_str())? {
+        reference
+            .into_fully_peeled_id()?
+            .object()?
+            .try_into_commit()?
+    } else {
+        return Ok(());
+    };
+    let state = RawStackState::from_tree(state_commit.tree()?)?;
+
+    let position = state.applied.len();
+    let total = position + state.unapplied.len();
+    if total == 0 {
+        return Ok(());
+    }
+
+    let mut line = if let Some(top) = state.applied.last() {
+        format!("{top} {position}/{total}")
+    } else {
+        format!("{position}/{total}")
+    };
+    if repo.open_index().map_or(false, |index| {
+        index.entries().iter().any(|entry| entry.stage() != 0)
+    }) {
+        line.push_str("|CONFLICT");
+    }
+
+    let mut stdout = std::io::stdout();
+    writeln!(stdout, "{line}")?;
     Ok(())
 }
+
+/// Render the dependency graph of the given patches, in display order.
+///
+/// Returns the graph prefix for each row. See the `--graph` option's help for how
+/// dependencies are determined and drawn.
+fn dependency_graph(
+    repo: &gix::Repository,
+    stack: &Stack,
+    rows: &[(&PatchName, gix::ObjectId)],
+    ascii: bool,
+) -> Result<Vec<String>> {
+    let stupid = repo.stupid();
+    let mut changed_files: Vec<HashSet<std::path::PathBuf>> = Vec::with_capacity(rows.len());
+    for (_, commit_id) in rows {
+        let commit = repo.find_commit(*commit_id)?;
+        let parent_tree_id = commit.get_parent_commit()?.tree_id()?.detach();
+        let files = stupid.diff_tree_files(parent_tree_id, commit.tree_id()?.detach())?;
+        changed_files.push(files.iter().map(|path| path.to_path_buf()).collect());
+    }
+
+    let declared: Vec<Vec<&str>> = rows
+        .iter()
+        .map(|(patchname, _)| {
+            stack
+                .get_patch(patchname)
+                .metadata
+                .get("depends")
+                .map(|value| {
+                    value
+                        .split(|c: char| c.is_whitespace() || c == ',')
+                        .filter(|name| !name.is_empty())
+                        .collect()
+                })
+                .unwrap_or_default()
+        })
+        .collect();
+
+    let depends_on = |row: usize, other: usize| {
+        let (patchname, _) = rows[row];
+        let (other_patchname, _) = rows[other];
+        stack.index_of(other_patchname) < stack.index_of(patchname)
+            && (!changed_files[row].is_disjoint(&changed_files[other])
+                || declared[row].contains(&other_patchname.as_ref()))
+    };
+
+    // Each lane is the owning row and its dependent rows, spanning from the first to
+    // the last of these rows.
+    let mut lanes: Vec<(usize, Vec<usize>)> = Vec::new();
+    for owner in 0..rows.len() {
+        let dependents: Vec<usize> = (0..rows.len())
+            .filter(|&row| depends_on(row, owner))
+            .collect();
+        if !dependents.is_empty() {
+            lanes.push((owner, dependents));
+        }
+    }
+
+    // Assign lanes to columns such that lanes sharing a column do not overlap.
+    let span = |(owner, dependents): &(usize, Vec<usize>)| {
+        let first = dependents.iter().copied().min().unwrap().min(*owner);
+        let last = dependents.iter().copied().max().unwrap().max(*owner);
+        (first, last)
+    };
+    let mut column_ends: Vec<usize> = Vec::new();
+    let mut columns: Vec<Vec<usize>> = Vec::new();
+    let mut lane_order: Vec<usize> = (0..lanes.len()).collect();
+    lane_order.sort_by_key(|&lane| span(&lanes[lane]));
+    for lane in lane_order {
+        let (first, last) = span(&lanes[lane]);
+        if let Some(column) = column_ends.iter().position(|&end| end < first) {
+            column_ends[column] = last;
+            columns[column].push(lane);
+        } else {
+            column_ends.push(last);
+            columns.push(vec![lane]);
+        }
+    }
+
+    let (node, pass, dependent, first_dependent, last_dependent) = if ascii {
+        ('*', '|', '+', '+', '+')
+    } else {
+        ('●', '│', '├', '┌', '└')
+    };
+
+    let mut prefixes = Vec::with_capacity(rows.len());
+    for row in 0..rows.len() {
+        let mut prefix = String::new();
+        for column in &columns {
+            let mark = column
+                .iter()
+                .map(|&lane| (&lanes[lane], span(&lanes[lane])))
+                .find(|(_, (first, last))| (*first..=*last).contains(&row))
+                .map_or(' ', |((owner, dependents), (first, last))| {
+                    if row == *owner {
+                        node
+                    } else if !dependents.contains(&row) {
+                        pass
+                    } else if row == first {
+                        first_dependent
+                    } else if row == last {
+                        last_dependent
+                    } else {
+                        dependent
+                    }
+                });
+            prefix.push(mark);
+            prefix.push(' ');
+        }
+        prefixes.push(prefix);
+    }
+    Ok(prefixes)
+}
+
+/// Get the name of a patch commit's author for display.
+///
+/// The author is mapped through the given mailmap.
+fn get_author_name(commit: &gix::Commit, mailmap: &gix::mailmap::Snapshot) -> String {
+    if let Ok(author) = commit.author_strict() {
+        mailmap
+            .resolve_cow(author.to_ref())
+            .name
+            .to_str_lossy()
+            .into_owned()
+    } else if let Ok(commit_ref) = commit.decode() {
+        mailmap
+            .resolve_cow(commit_ref.author())
+            .name
+            .to_str_lossy()
+            .into_owned()
+    } else {
+        UNPRINTABLE.to_string()
+    }
+}
@@ -0,0 +1,500 @@
+// SPDX-License-Identifier: GPL-2.0-only
+
+//! `stg serve` implementation.
+
+use std::{
+    ffi::OsString,
+    io::{BufRead, BufReader, Read, Write},
+    net::{TcpListener, TcpStream},
+    time::Duration,
+};
+
+use anyhow::{Context, Result};
+use bstr::ByteSlice;
+use clap::{Arg, ArgMatches};
+
+use crate::{
+    branchloc::BranchLocator,
+    ext::{CommitExtended, RepositoryExtended},
+    patch::PatchName,
+    stack::{InitializationPolicy, Stack, StackAccess, StackStateAccess},
+    stupid::Stupid,
+    wrap::Branch,
+};
+
+pub(super) const STGIT_COMMAND: super::StGitCommand = super::StGitCommand {
+    name: "serve",
+    category: super::CommandCategory::StackInspection,
+    make,
+    run,
+};
+
+/// Time after which a client that stalls sending its request or receiving the
+/// response is disconnected.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Maximum size of a request's request line and headers, in bytes.
+const MAX_REQUEST_HEAD_SIZE: u64 = 64 * 1024;
+
+fn make() -> clap::Command {
+    clap::Command::new(STGIT_COMMAND.name)
+        .about("Serve stack information over HTTP")
+        .long_about(
+            "Serve read-only information about the repository's StGit stacks as JSON \
+             over HTTP, e.g. for use by a dashboard or web UI.\n\
+             \n\
+             The following endpoints are provided. Where a branch may be given with \
+             the 'branch' query parameter, the current branch is used by default.\n\
+             \n\
+             /branches: list the branches initialized with a StGit stack along with \
+             their number of applied, unapplied, and hidden patches.\n\
+             \n\
+             /series?branch=<branch>: list the patches of a stack with their state, \
+             commit, author, and subject.\n\
+             \n\
+             /diff?branch=<branch>&patch=<patch>: show a patch's message and diff.\n\
+             \n\
+             /log?branch=<branch>&limit=<n>: list the stack log entries of a stack, \
+             newest first. At most <n> entries, 50 by default, are listed.\n\
+             \n\
+             Errors are reported as a JSON object with an 'error' message. The status \
+             is 400 for malformed parameters, 404 for an unknown branch, patch, or \
+             endpoint, or a branch without a StGit stack, and 500 for failures of \
+             the server itself.\n\
+             \n\
+             The server does not perform any authentication. It is thus recommended to \
+             only listen on a loopback address, e.g. `stg serve --http \
+             127.0.0.1:8080`, or to place it behind a suitable proxy. The address the \
+             server listens on is printed once the server is ready. Requests are \
+             served until the command is interrupted.",
+        )
+        .arg(
+            Arg::new("http")
+                .long("http")
+                .help("Listen for HTTP requests on <addr>")
+                .value_name("addr")
+                .required(true)
+                .num_args(1),
+        )
+}
+
+fn run(matches: &ArgMatches) -> Result<()> {
+    let repo = gix::Repository::open()?;
+    let addr = matches
+        .get_one::<String>("http")
+        .expect("required argument");
+    let listener =
+        TcpListener::bind(addr.as_str()).with_context(|| format!("listening on `{addr}`"))?;
+
+    let mut stdout = std::io::stdout();
+    writeln!(stdout, "Serving on http://{}/", listener.local_addr()?)?;
+    stdout.flush()?;
+
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => {
+                if let Err(e) = handle_connection(&repo, stream) {
+                    eprintln!("warning: {e:#}");
+                }
+            }
+            Err(e) => eprintln!("warning: accepting connection: {e}"),
+        }
+    }
+
+    Ok(())
+}
+
+/// HTTP status of a response.
+#[derive(Clone, Copy)]
+enum Status {
+    Ok,
+    BadRequest,
+    NotFound,
+    MethodNotAllowed,
+    HeaderFieldsTooLarge,
+    InternalServerError,
+}
+
+impl Status {
+    fn line(self) -> &'static str {
+        match self {
+            Status::Ok => "200 OK",
+            Status::BadRequest => "400 Bad Request",
+            Status::NotFound => "404 Not Found",
+            Status::MethodNotAllowed => "405 Method Not Allowed",
+            Status::HeaderFieldsTooLarge => "431 Request Header Fields Too Large",
+            Status::InternalServerError => "500 Internal Server Error",
+        }
+    }
+}
+
+#[derive(serde::Serialize)]
+struct ErrorResponse {
+    error: String,
+}
+
+#[derive(serde::Serialize)]
+struct BranchEntry {
+    name: String,
+    applied: usize,
+    unapplied: usize,
+    hidden: usize,
+}
+
+#[derive(serde::Serialize)]
+struct SeriesResponse {
+    branch: String,
+    base: String,
+    patches: Vec<SeriesPatch>,
+}
+
+#[derive(serde::Serialize)]
+struct SeriesPatch {
+    name: PatchName,
+    state: &'static str,
+    commit: String,
+    author: Signature,
+    subject: String,
+    empty: bool,
+}
+
+#[derive(serde::Serialize)]
+struct DiffResponse {
+    branch: String,
+    patch: PatchName,
+    commit: String,
+    message: String,
+    diff: String,
+}
+
+#[derive(serde::Serialize)]
+struct LogEntry {
+    commit: String,
+    message: String,
+    time: i64,
+}
+
+#[derive(serde::Serialize)]
+struct Signature {
+    name: String,
+    email: String,
+    /// Time in git's raw format, i.e. `<seconds> <offset>`.
+    date: String,
+}
+
+impl From<gix::actor::Signature> for Signature {
+    fn from(sig: gix::actor::Signature) -> Self {
+        Self {
+            name: sig.name.to_string(),
+            email: sig.email.to_string(),
+            date: sig.time.format(gix::date::time::format::RAW),
+        }
+    }
+}
+
+/// Error to be reported to the client with the given status.
+struct RequestError(Status, String);
+
+impl RequestError {
+    fn bad_request(e: impl std::fmt::Display) -> Self {
+        RequestError(Status::BadRequest, e.to_string())
+    }
+
+    fn not_found(e: anyhow::Error) -> Self {
+        RequestError(Status::NotFound, format!("{e:#}"))
+    }
+}
+
+/// Errors not otherwise classified are failures of the server itself.
+impl From<anyhow::Error> for RequestError {
+    fn from(e: anyhow::Error) -> Self {
+        RequestError(Status::InternalServerError, format!("{e:#}"))
+    }
+}
+
+impl From<serde_json::Error> for RequestError {
+    fn from(e: serde_json::Error) -> Self {
+        RequestError::from(anyhow::Error::from(e))
+    }
+}
+
+fn handle_connection(repo: &gix::Repository, stream: TcpStream) -> Result<()> {
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    stream.set_write_timeout(Some(TIMEOUT))?;
+
+    let mut reader = BufReader::new(&stream).take(MAX_REQUEST_HEAD_SIZE);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 || header.trim_end().is_empty() {
+            break;
+        }
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default();
+    let target = parts.next().unwrap_or_default();
+
+    let result = if reader.limit() == 0 {
+        Err(RequestError(
+            Status::HeaderFieldsTooLarge,
+            "request line and headers too large".to_string(),
+        ))
+    } else if method == "GET" {
+        let (path, query) = target.split_once('?').unwrap_or((target, ""));
+        let params = parse_query(query);
+        route(repo, path, &params)
+    } else {
+        Err(RequestError(
+            Status::MethodNotAllowed,
+            format!("method `{method}` not allowed"),
+        ))
+    };
+
+    let (status, mut body) = match result {
+        Ok(body) => (Status::Ok, body),
+        Err(RequestError(status, error)) => {
+            (status, serde_json::to_vec_pretty(&ErrorResponse { error })?)
+        }
+    };
+    body.push(b'\n');
+
+    let mut stream = &stream;
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\n\
+         Content-Type: application/json\r\n\
+         Content-Length: {}\r\n\
+         Connection: close\r\n\
+         \r\n",
+        status.line(),
+        body.len()
+    )?;
+    stream.write_all(&body)?;
+    stream.flush()?;
+
+    // Closing the connection with unread request data could reset it before the
+    // client receives the response, so a bounded amount of the remainder is drained.
+    if matches!(status, Status::HeaderFieldsTooLarge) {
+        stream.shutdown(std::net::Shutdown::Write).ok();
+        std::io::copy(
+            &mut stream.take(MAX_REQUEST_HEAD_SIZE),
+            &mut std::io::sink(),
+        )
+        .ok();
+    }
+    Ok(())
+}
+
+fn route(
+    repo: &gix::Repository,
+    path: &str,
+    params: &[(String, String)],
+) -> std::result::Result<Vec<u8>, RequestError> {
+    let param = |name: &str| {
+        params
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    };
+
+    let get_stack = || -> std::result::Result<Stack, RequestError> {
+        let branch = if let Some(branch) = param("branch") {
+            branch
+                .parse::<BranchLocator>()
+                .map_err(RequestError::bad_request)?
+                .resolve(repo)
+                .map_err(RequestError::not_found)?
+        } else {
+            repo.get_current_branch().map_err(RequestError::not_found)?
+        };
+        let stack = Stack::from_branch(repo, branch, InitializationPolicy::AllowUninitialized)?;
+        if stack.is_initialized() {
+            Ok(stack)
+        } else {
+            Err(RequestError(
+                Status::NotFound,
+                format!(
+                    "StGit stack not initialized for branch `{}`",
+                    stack.get_branch_name()
+                ),
+            ))
+        }
+    };
+
+    let body = match path.trim_end_matches('/') {
+        "/branches" => serde_json::to_vec_pretty(&branches(repo)?),
+        "/series" => serde_json::to_vec_pretty(&series(&get_stack()?)?),
+        "/diff" => {
+            let stack = get_stack()?;
+            let patchname = param("patch")
+                .ok_or_else(|| RequestError::bad_request("`patch` parameter is required"))?
+                .parse::<PatchName>()
+                .map_err(RequestError::bad_request)?;
+            if !stack.has_patch(&patchname) {
+                return Err(RequestError(
+                    Status::NotFound,
+                    format!("patch `{patchname}` does not exist"),
+                ));
+            }
+            serde_json::to_vec_pretty(&diff(&stack, &patchname)?)
+        }
+        "/log" => {
+            let limit = param("limit")
+                .map(|limit| {
+                    limit.parse::<usize>().map_err(|_| {
+                        RequestError::bad_request(format!("invalid `limit` value `{limit}`"))
+                    })
+                })
+                .transpose()?
+                .unwrap_or(50);
+            serde_json::to_vec_pretty(&log(&get_stack()?, limit)?)
+        }
+        _ => {
+            return Err(RequestError(
+                Status::NotFound,
+                format!("unknown endpoint `{path}`"),
+            ))
+        }
+    };
+
+    Ok(body?)
+}
+
+fn branches(repo: &gix::Repository) -> Result<Vec<BranchEntry>> {
+    let mut entries = Vec::new();
+    for local_branch in repo.references()?.local_branches()?.filter_map(Result::ok) {
+        let local_branch = Branch::wrap(local_branch);
+        if let Ok(branchname) = local_branch.get_branch_partial_name() {
+            if let Ok(stack) =
+                Stack::from_branch_name(repo, &branchname, InitializationPolicy::RequireInitialized)
+            {
+                entries.push(BranchEntry {
+                    name: branchname.to_string(),
+                    applied: stack.applied().len(),
+                    unapplied: stack.unapplied().len(),
+                    hidden: stack.hidden().len(),
+                });
+            }
+        }
+    }
+    entries.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(entries)
+}
+
+fn series(stack: &Stack) -> Result<SeriesResponse> {
+    let mut patches = Vec::new();
+    for (patchname, state) in stack
+        .applied()
+        .iter()
+        .map(|pn| (pn, "applied"))
+        .chain(stack.unapplied().iter().map(|pn| (pn, "unapplied")))
+        .chain(stack.hidden().iter().map(|pn| (pn, "hidden")))
+    {
+        let patch_commit = stack.get_patch_commit(patchname);
+        let subject = patch_commit
+            .message_raw()?
+            .to_str_lossy()
+            .lines()
+            .next()
+            .unwrap_or_default()
+            .to_string();
+        patches.push(SeriesPatch {
+            name: patchname.clone(),
+            state,
+            commit: patch_commit.id.to_string(),
+            author: patch_commit.author_strict()?.into(),
+            subject,
+            empty: patch_commit.is_no_change()?,
+        });
+    }
+    Ok(SeriesResponse {
+        branch: stack.get_branch_name().to_string(),
+        base: stack.base().id.to_string(),
+        patches,
+    })
+}
+
+fn diff(stack: &Stack, patchname: &PatchName) -> Result<DiffResponse> {
+    let patch_commit = stack.get_patch_commit(patchname);
+    let parent_commit = patch_commit.get_parent_commit()?;
+    let message = patch_commit.message_ex().decode()?.to_string();
+    let diff = stack.repo.stupid().diff_tree_patch(
+        parent_commit.tree_id()?.detach(),
+        patch_commit.tree_id()?.detach(),
+        <Option<Vec<OsString>>>::None,
+        false,
+        ["--no-ext-diff"],
+    )?;
+    Ok(DiffResponse {
+        branch: stack.get_branch_name().to_string(),
+        patch: patchname.clone(),
+        commit: patch_commit.id.to_string(),
+        message,
+        diff: diff.to_str_lossy().into_owned(),
+    })
+}
+
+fn log(stack: &Stack, limit: usize) -> Result<Vec<LogEntry>> {
+    let repo = stack.repo;
+    let state_commit = repo
+        .find_reference(stack.get_stack_refname())?
+        .into_fully_peeled_id()?
+        .object()?
+        .try_into_commit()?;
+    let mut entries = Vec::new();
+    let mut next_id = state_commit.parent_ids().next().map(|id| id.detach());
+    while let Some(commit_id) = next_id {
+        if entries.len() >= limit {
+            break;
+        }
+        let commit = repo.find_commit(commit_id)?;
+        entries.push(LogEntry {
+            commit: commit_id.to_string(),
+            message: commit.message_raw()?.trim_end().to_str_lossy().into_owned(),
+            time: commit.time()?.seconds,
+        });
+        next_id = commit.parent_ids().next().map(|id| id.detach());
+    }
+    Ok(entries)
+}
+
+/// Parse the query component of a request target into key/value pairs.
+fn parse_query(query: &str) -> Vec<(String, String)> {
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (percent_decode(key), percent_decode(value))
+        })
+        .collect()
+}
+
+/// Decode percent-encoded characters and `+` as space.
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => decoded.push(b' '),
+            b'%' => {
+                if let Some(byte) = bytes
+                    .get(i + 1..i + 3)
+                    .and_then(|hex| std::str::from_utf8(hex).ok())
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                {
+                    decoded.push(byte);
+                    i += 3;
+                    continue;
+                }
+                decoded.push(b'%');
+            }
+            b => decoded.push(b),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
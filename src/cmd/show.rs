@@ -2,7 +2,7 @@
 
 //! `stg show` implementation.
 
-use std::path::PathBuf;
+use std::{ffi::OsString, path::PathBuf};
 
 use anyhow::Result;
 use clap::{Arg, ArgMatches};
@@ -13,7 +13,7 @@ use crate::{
     ext::RepositoryExtended,
     patch::{RangeConstraint, RangeRevisionSpec},
     stack::{InitializationPolicy, Stack, StackAccess, StackStateAccess},
-    stupid::Stupid,
+    stupid::{DiffSummary, Stupid},
 };
 
 pub(super) const STGIT_COMMAND: super::StGitCommand = super::StGitCommand {
@@ -82,6 +82,39 @@ fn make() -> clap::Command {
                 .help("Show a diffstat summary instead of the full diff")
                 .action(clap::ArgAction::SetTrue),
         )
+        .arg(
+            Arg::new("numstat")
+                .long("numstat")
+                .help("Show added and deleted line counts instead of the full diff")
+                .long_help(
+                    "Instead of the full diff, show the numbers of added and deleted \
+                     lines in decimal notation and the pathname of each changed file, \
+                     as with the '--numstat' option of git-show(1).",
+                )
+                .action(clap::ArgAction::SetTrue)
+                .conflicts_with("stat"),
+        )
+        .arg(
+            Arg::new("patch-format")
+                .long("patch-format")
+                .help("Show patches in the given format")
+                .long_help(
+                    "Show patches in the given format. The 'git' format, the default, \
+                     is the same as 'git show'. The 'email' format renders the patches \
+                     exactly as 'stg email format' would, including the email headers, \
+                     subject prefix, and diffstat, but writes them to stdout instead \
+                     of to files. A contiguous series of patches is numbered as a \
+                     series.",
+                )
+                .value_name("format")
+                .value_parser(["git", "email"])
+                .default_value("git")
+                .conflicts_with_all(["stat", "numstat"]),
+        )
+        .arg(argset::find_renames_arg())
+        .arg(argset::find_copies_arg())
+        .arg(argset::word_diff_arg())
+        .arg(argset::color_moved_arg())
         .arg(argset::diff_opts_arg())
         .next_help_heading("Selection Options")
         .arg(
@@ -113,7 +146,13 @@ fn run(matches: &ArgMatches) -> Result<()> {
     let stack =
         Stack::from_branch_locator(&repo, opt_branch, InitializationPolicy::AllowUninitialized)?;
 
-    let stat_flag = matches.get_flag("stat");
+    let summary = if matches.get_flag("stat") {
+        Some(DiffSummary::Stat)
+    } else if matches.get_flag("numstat") {
+        Some(DiffSummary::NumStat)
+    } else {
+        None
+    };
     let applied_flag = matches.get_flag("applied");
     let unapplied_flag = matches.get_flag("unapplied");
     let hidden_flag = matches.get_flag("hidden");
@@ -140,24 +179,101 @@ fn run(matches: &ArgMatches) -> Result<()> {
         .or_else(|| matches.get_many::<RangeRevisionSpec>("patchranges"))
     {
         for spec in range_specs {
-            crate::patch::revspec::resolve(
-                &repo,
-                Some(&stack),
-                [spec],
-                RangeConstraint::AllWithAppliedBoundary,
-            )?
-            .iter()
-            .for_each(|rev| oids.push(rev.commit.id));
+            let resolve = |spec| {
+                crate::patch::revspec::resolve(
+                    &repo,
+                    Some(&stack),
+                    [spec],
+                    RangeConstraint::AllWithAppliedBoundary,
+                )
+            };
+            // Abbreviated patch names are only tried once the spec fails to resolve
+            // as-is, such that they do not shadow git revisions.
+            let revs = match resolve(spec) {
+                Ok(revs) => revs,
+                Err(e) => {
+                    if let Some(spec) = spec.expand_abbreviation(&stack)? {
+                        resolve(&spec)?
+                    } else {
+                        return Err(e);
+                    }
+                }
+            };
+            revs.iter().for_each(|rev| oids.push(rev.commit.id));
         }
     } else if !applied_flag && !unapplied_flag && !hidden_flag {
         oids.push(stack.get_branch_head().id);
     }
 
+    if matches
+        .get_one::<String>("patch-format")
+        .map_or(false, |format| format == "email")
+    {
+        return show_email(&repo, matches, &oids);
+    }
+
     repo.stupid().show(
         oids,
         matches.get_many::<PathBuf>("pathspecs"),
-        stat_flag,
+        summary,
         crate::color::use_color(matches),
         argset::get_diff_opts(matches, &repo.config_snapshot(), false, false),
     )
 }
+
+/// Show commits formatted as emails, as with `stg email format`.
+///
+/// When the commits form a contiguous series, they are formatted together such that
+/// they are numbered as a series. Otherwise each commit is formatted on its own.
+fn show_email(repo: &gix::Repository, matches: &ArgMatches, oids: &[gix::ObjectId]) -> Result<()> {
+    let config = repo.config_snapshot();
+    let mut format_args = vec!["--stdout".to_string()];
+    if let Some(prefix) = super::email::subject_prefix(matches, &config, None) {
+        format_args.push(format!("--subject-prefix={prefix}"));
+    }
+    format_args.extend(argset::get_diff_opts(matches, &config, false, false));
+
+    let ranges: Vec<Vec<String>> = if oids.len() > 1 && is_contiguous(repo, oids)? {
+        let first_parent_id = repo
+            .find_commit(oids[0])?
+            .parent_ids()
+            .next()
+            .map(|id| id.detach());
+        let last = oids[oids.len() - 1];
+        if let Some(parent_id) = first_parent_id {
+            vec![vec![format!("{parent_id}..{last}")]]
+        } else {
+            vec![vec!["--root".to_string(), last.to_string()]]
+        }
+    } else {
+        oids.iter()
+            .map(|oid| vec!["-1".to_string(), oid.to_string()])
+            .collect()
+    };
+
+    let pathspecs: Vec<&PathBuf> = matches
+        .get_many::<PathBuf>("pathspecs")
+        .map(Iterator::collect)
+        .unwrap_or_default();
+
+    for range in ranges {
+        let mut args: Vec<OsString> = format_args.iter().map(OsString::from).collect();
+        args.extend(range.iter().map(OsString::from));
+        args.push("--".into());
+        args.extend(pathspecs.iter().map(|path| path.as_os_str().to_owned()));
+        repo.stupid().format_patch(args)?;
+    }
+    Ok(())
+}
+
+/// Determine whether each commit is the only parent of the following commit.
+fn is_contiguous(repo: &gix::Repository, oids: &[gix::ObjectId]) -> Result<bool> {
+    for pair in oids.windows(2) {
+        let commit = repo.find_commit(pair[1])?;
+        let mut parent_ids = commit.parent_ids();
+        if parent_ids.next().map(|id| id.detach()) != Some(pair[0]) || parent_ids.next().is_some() {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
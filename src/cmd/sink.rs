@@ -29,10 +29,12 @@ fn make() -> clap::Command {
              \n\
              If no patch is specified on the command line, the current (topmost) patch \
              is sunk. By default, patches are sunk to the bottom of the stack, but the \
-             '--to' option may be used to place them under any applied patch.\n\
+             '--to' (or '--before') option may be used to place them under any applied \
+             patch, and the '--after' option to place them over any applied patch.\n\
              \n\
              Internally, sinking involves popping all patches to the bottom (or to the \
-             target patch if '--to' is used), then pushing the patches to sink, and \
+             target patch if '--to' or '--after' is used), then pushing the patches to \
+             sink, and \
              then, unless '--nopush' is specified, pushing back any other formerly \
              applied patches.\n\
              \n\
@@ -73,9 +75,25 @@ fn make() -> clap::Command {
                      bottom of the stack.",
                 )
                 .value_name("target")
+                .visible_alias("before")
                 .allow_hyphen_values(true)
                 .value_parser(clap::value_parser!(PatchLocator)),
         )
+        .arg(
+            Arg::new("after")
+                .long("after")
+                .help("Sink patches above <target> patch")
+                .long_help(
+                    "Sink patches above <target> patch.\n\
+                     \n\
+                     Specified patches are placed immediately above <target> instead of \
+                     at the bottom of the stack.",
+                )
+                .value_name("target")
+                .allow_hyphen_values(true)
+                .value_parser(clap::value_parser!(PatchLocator))
+                .conflicts_with("target"),
+        )
         .arg(argset::keep_arg())
         .arg(argset::committer_date_is_author_date_arg())
 }
@@ -96,20 +114,6 @@ fn run(matches: &ArgMatches) -> Result<()> {
         statuses.check_index_and_worktree_clean()?;
     }
 
-    let opt_target: Option<PatchName> = matches
-        .get_one::<PatchLocator>("target")
-        .map(|loc| loc.resolve_name(&stack))
-        .transpose()
-        .map_err(|e| anyhow!("target: {e}"))?
-        .map(|name| name.constrain(&stack, LocationConstraint::Applied))
-        .transpose()
-        .map_err(|e| match e {
-            crate::patch::name::Error::PatchNotAllowed { patchname, .. } => {
-                anyhow!("cannot sink below `{patchname}` since it is not applied")
-            }
-            _ => e.into(),
-        })?;
-
     let patches: Vec<PatchName> =
         if let Some(range_specs) = matches.get_many::<PatchRange>("patchranges") {
             patchrange::resolve_names(&stack, range_specs, RangeConstraint::All)?
@@ -119,13 +123,15 @@ fn run(matches: &ArgMatches) -> Result<()> {
             return Err(super::Error::NoAppliedPatches.into());
         };
 
-    if let Some(target_patch) = &opt_target {
-        if patches.contains(target_patch) {
-            return Err(anyhow!(
-                "target patch `{target_patch}` may not also be a patch to sink",
-            ));
-        }
-    }
+    let opt_target = if let Some(locator) = matches.get_one::<PatchLocator>("target") {
+        let target = resolve_target(&stack, locator, &patches, "sink", "below")?;
+        Some((target, 0))
+    } else if let Some(locator) = matches.get_one::<PatchLocator>("after") {
+        let target = resolve_target(&stack, locator, &patches, "sink", "above")?;
+        Some((target, 1))
+    } else {
+        None
+    };
 
     let mut remaining_unapplied: Vec<PatchName> = stack
         .unapplied()
@@ -141,11 +147,12 @@ fn run(matches: &ArgMatches) -> Result<()> {
         .cloned()
         .collect();
 
-    let target_pos = if let Some(target_patch) = &opt_target {
+    let target_pos = if let Some((target_patch, offset)) = &opt_target {
         remaining_applied
             .iter()
             .position(|pn| pn == target_patch)
             .expect("already validated that target is applied")
+            + offset
     } else {
         0
     };
@@ -180,3 +187,33 @@ fn run(matches: &ArgMatches) -> Result<()> {
 
     Ok(())
 }
+
+/// Resolve the target patch that patches are to be moved below or above.
+///
+/// The target patch must be applied and may not be one of the `patches` being moved.
+/// The `command` and `placement` are used to describe the move in error messages.
+pub(super) fn resolve_target(
+    stack: &Stack,
+    locator: &PatchLocator,
+    patches: &[PatchName],
+    command: &str,
+    placement: &str,
+) -> Result<PatchName> {
+    let target = locator
+        .resolve_name(stack)
+        .map_err(|e| anyhow!("target: {e}"))?
+        .constrain(stack, LocationConstraint::Applied)
+        .map_err(|e| match e {
+            crate::patch::name::Error::PatchNotAllowed { patchname, .. } => {
+                anyhow!("cannot {command} {placement} `{patchname}` since it is not applied")
+            }
+            _ => e.into(),
+        })?;
+    if patches.contains(&target) {
+        Err(anyhow!(
+            "target patch `{target}` may not also be a patch to {command}",
+        ))
+    } else {
+        Ok(target)
+    }
+}
@@ -4,14 +4,15 @@
 
 use std::path::PathBuf;
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use clap::{Arg, ArgMatches};
 
 use crate::{
     argset,
     color::get_color_stdout,
     ext::{CommitExtended, RepositoryExtended},
-    stack::{InitializationPolicy, Stack, StackStateAccess},
+    patch::PatchName,
+    stack::{InitializationPolicy, Stack, StackAccess, StackStateAccess},
     stupid::Stupid,
 };
 
@@ -30,13 +31,31 @@ fn make() -> clap::Command {
              but remain in the index and worktree.\n\
              \n\
              Spilling a patch may be useful for reselecting the files/hunks to be \
-             included in the patch.",
+             included in the patch.\n\
+             \n\
+             When paths are given, only the patch's changes to files matching those \
+             paths are spilled; the rest of the patch is kept intact. Paths are \
+             relative to the current working directory.\n\
+             \n\
+             With '--to-patch', the spilled changes are not left in the index and \
+             worktree. Instead, they are saved to a new unapplied patch placed \
+             immediately after the spilled patch. The new patch has the same author \
+             and message as the spilled patch.",
         )
+        .override_usage(super::make_usage("stg spill", &["[OPTIONS] [<path>...]"]))
         .arg(
             Arg::new("annotate")
                 .long("annotate")
                 .short('a')
                 .help("Annotate the patch log entry with note")
+                .long_help(
+                    "Annotate the patch log entry with note. The placeholders \
+                     '%(patchname)s', '%(branch)s', '%(date)s', and '%(version)s' in \
+                     the note are replaced with the patch name, branch name, current \
+                     date, and StGit version, respectively. When this option is not \
+                     given, the 'stgit.spill.annotate' configuration variable is used \
+                     as the annotation, if set.",
+                )
                 .num_args(1..)
                 .value_name("note"),
         )
@@ -52,6 +71,15 @@ fn make() -> clap::Command {
                 )
                 .action(clap::ArgAction::SetTrue),
         )
+        .arg(
+            Arg::new("to-patch")
+                .long("to-patch")
+                .short('t')
+                .help("Move spilled changes to new unapplied patch <name>")
+                .value_name("name")
+                .value_parser(clap::value_parser!(PatchName))
+                .conflicts_with("reset"),
+        )
         .arg(argset::committer_date_is_author_date_arg())
         .arg(
             Arg::new("pathspecs")
@@ -78,13 +106,27 @@ fn run(matches: &ArgMatches) -> Result<()> {
         .last()
         .ok_or(super::Error::NoAppliedPatches)?
         .clone();
+
+    let to_patchname = matches.get_one::<PatchName>("to-patch");
+    if let Some(to_patchname) = to_patchname {
+        if let Some(colliding_name) = stack.collides(to_patchname) {
+            return if stack.has_patch(to_patchname) {
+                Err(anyhow!("patch `{to_patchname}` already exists"))
+            } else {
+                Err(anyhow!(
+                    "new name `{to_patchname}` collides with `{colliding_name}`"
+                ))
+            };
+        }
+    }
+
     let patch_commit = stack.get_patch_commit(&patchname);
     let patch_commit_ref = patch_commit.decode()?;
     let parent = patch_commit.get_parent_commit()?;
     let parent_commit_ref = parent.decode()?;
 
     let tree_id = if let Some(pathspecs) = matches.get_many::<PathBuf>("pathspecs") {
-        stupid.with_temp_index(|stupid_temp| {
+        let tree_id = stupid.with_temp_index(|stupid_temp| {
             stupid_temp.read_tree(patch_commit_ref.tree())?;
             stupid_temp.apply_pathlimited_treediff_to_index(
                 patch_commit_ref.tree(),
@@ -93,20 +135,21 @@ fn run(matches: &ArgMatches) -> Result<()> {
                 pathspecs,
             )?;
             stupid_temp.write_tree()
-        })?
+        })?;
+        if tree_id == patch_commit_ref.tree() {
+            return Err(anyhow!("no changes in `{patchname}` match the given paths"));
+        }
+        tree_id
     } else {
         parent_commit_ref.tree()
     };
 
     let author = patch_commit.author_strict()?;
-    let default_committer = repo.get_committer()?;
-    let committer = if matches.get_flag("committer-date-is-author-date") {
-        let mut committer = default_committer.to_owned();
-        committer.time = author.time;
-        committer
-    } else {
-        default_committer.to_owned()
-    };
+    let committer = repo.get_rewrite_committer(
+        Some(patch_commit),
+        author.time,
+        matches.get_flag("committer-date-is-author-date"),
+    )?;
 
     let commit_id = repo.commit_ex(
         &author,
@@ -116,9 +159,27 @@ fn run(matches: &ArgMatches) -> Result<()> {
         patch_commit_ref.parents(),
     )?;
 
+    let to_patch_commit_id = if to_patchname.is_some() {
+        Some(repo.commit_ex(
+            &author,
+            repo.get_committer()?,
+            &patch_commit.message_ex(),
+            patch_commit_ref.tree(),
+            [commit_id],
+        )?)
+    } else {
+        None
+    };
+
     drop(patch_commit_ref);
 
-    let reflog_msg = if let Some(annotation) = matches.get_one::<String>("annotate") {
+    let reflog_msg = if let Some(annotation) = argset::resolve_annotation(
+        &repo,
+        matches,
+        "spill",
+        patchname.as_ref(),
+        stack.get_branch_name(),
+    )? {
         format!("spill {patchname}\n\n{annotation}")
     } else {
         format!("spill {patchname}")
@@ -126,9 +187,17 @@ fn run(matches: &ArgMatches) -> Result<()> {
 
     stack
         .setup_transaction()
-        .use_index_and_worktree(false)
+        .use_index_and_worktree(to_patchname.is_some())
         .with_output_stream(get_color_stdout(matches))
-        .transact(|trans| trans.update_patch(&patchname, commit_id))
+        .transact(|trans| {
+            trans.update_patch(&patchname, commit_id)?;
+            if let (Some(to_patchname), Some(to_patch_commit_id)) =
+                (to_patchname, to_patch_commit_id)
+            {
+                trans.new_unapplied(to_patchname, to_patch_commit_id, 0)?;
+            }
+            Ok(())
+        })
         .execute(&reflog_msg)?;
 
     if matches.get_flag("reset") {
@@ -0,0 +1,255 @@
+// SPDX-License-Identifier: GPL-2.0-only
+
+//! `stg split` implementation.
+
+use std::ffi::OsString;
+
+use anyhow::{anyhow, Result};
+use bstr::ByteSlice;
+use clap::{Arg, ArgMatches};
+
+use crate::{
+    color::get_color_stdout,
+    ext::{CommitExtended, RepositoryExtended},
+    patch::{patchedit, PatchLocator, PatchName},
+    stack::{InitializationPolicy, Stack, StackStateAccess},
+    stupid::Stupid,
+};
+
+pub(super) const STGIT_COMMAND: super::StGitCommand = super::StGitCommand {
+    name: "split",
+    category: super::CommandCategory::PatchManipulation,
+    make,
+    run,
+};
+
+fn make() -> clap::Command {
+    clap::Command::new(STGIT_COMMAND.name)
+        .about("Split a patch into several patches")
+        .long_about(
+            "Split a patch into two or more adjacent patches.\n\
+             \n\
+             Some of the patch's changes are split off into new patches which are \
+             placed immediately before the original patch in the series. The \
+             original patch keeps its name and the remaining changes. The changes to \
+             split off may be selected in one of three ways:\n\
+             \n\
+             With '--path', the changes to files matching each given pathspec are \
+             split off into a separate new patch. Giving '--path' multiple times \
+             thus splits the patch into multiple new patches.\n\
+             \n\
+             With '--interactive', the hunks to split off are selected interactively \
+             as with `git add --patch`. This requires the patch to be the topmost \
+             applied patch and the index and work tree to be clean.\n\
+             \n\
+             Otherwise, or with '--edit', the patch's diff is opened in an editor. \
+             The changes remaining in the edited diff are split off; removed changes \
+             stay in the original patch.\n\
+             \n\
+             The new patches have the same author and message, including any \
+             trailers, as the original patch. Their names are derived from the \
+             original patch's name.",
+        )
+        .override_usage(super::make_usage(
+            "stg split",
+            &["[OPTIONS] [patch]", "[OPTIONS] --path <path>... [patch]"],
+        ))
+        .arg(
+            Arg::new("patch")
+                .help("Patch to split, defaults to the topmost patch")
+                .allow_hyphen_values(true)
+                .value_parser(clap::value_parser!(PatchLocator)),
+        )
+        .arg(
+            Arg::new("path")
+                .long("path")
+                .short('p')
+                .help("Split off changes to files matching <path>")
+                .long_help(
+                    "Split off the changes to files matching <path> into a new patch. \
+                     This option may be given multiple times, each time splitting off \
+                     a further patch.",
+                )
+                .value_name("path")
+                .action(clap::ArgAction::Append)
+                .value_parser(clap::value_parser!(OsString)),
+        )
+        .arg(
+            Arg::new("interactive")
+                .long("interactive")
+                .short('i')
+                .help("Interactively select hunks to split off")
+                .action(clap::ArgAction::SetTrue)
+                .conflicts_with("path"),
+        )
+        .arg(
+            Arg::new("edit")
+                .long("edit")
+                .short('e')
+                .help("Edit the patch's diff to select changes to split off")
+                .action(clap::ArgAction::SetTrue)
+                .conflicts_with_all(["path", "interactive"]),
+        )
+}
+
+fn run(matches: &ArgMatches) -> Result<()> {
+    let repo = gix::Repository::open()?;
+    let stack = Stack::current(&repo, InitializationPolicy::AllowUninitialized)?;
+    let stupid = repo.stupid();
+
+    let patchname = if let Some(locator) = matches.get_one::<PatchLocator>("patch") {
+        locator.resolve_name(&stack)?
+    } else if let Some(patchname) = stack.applied().last() {
+        patchname.clone()
+    } else {
+        return Err(super::Error::NoAppliedPatches.into());
+    };
+
+    if stack.is_hidden(&patchname) {
+        return Err(anyhow!("cannot split hidden patch `{patchname}`"));
+    }
+
+    let patch_commit = stack.get_patch_commit(&patchname);
+    let parent_commit = patch_commit.get_parent_commit()?;
+    let parent_tree_id = parent_commit.tree_id()?.detach();
+    let patch_tree_id = patch_commit.tree_id()?.detach();
+
+    if parent_tree_id == patch_tree_id {
+        return Err(anyhow!("patch `{patchname}` is empty; nothing to split"));
+    }
+
+    let tree_ids: Vec<gix::ObjectId> = if let Some(pathspecs) = matches.get_many::<OsString>("path")
+    {
+        let mut tree_ids = Vec::new();
+        let mut tree_id = parent_tree_id;
+        for pathspec in pathspecs {
+            let diff = stupid.diff_tree_patch(
+                tree_id,
+                patch_tree_id,
+                Some([pathspec]),
+                false,
+                ["--binary"],
+            )?;
+            if diff.is_empty() {
+                return Err(anyhow!(
+                    "no changes to `{}` in patch `{patchname}`",
+                    pathspec.to_string_lossy()
+                ));
+            }
+            tree_id = stupid.with_temp_index(|stupid_temp| {
+                stupid_temp.read_tree(tree_id)?;
+                stupid_temp.apply_to_index(diff.as_bstr())?;
+                stupid_temp.write_tree()
+            })?;
+            tree_ids.push(tree_id);
+        }
+        tree_ids
+    } else if matches.get_flag("interactive") {
+        if stack.applied().last() != Some(&patchname) {
+            return Err(anyhow!(
+                "interactive split requires `{patchname}` to be the topmost applied patch"
+            ));
+        }
+        stack.check_head_top_mismatch()?;
+        let statuses = stupid.statuses(None)?;
+        statuses.check_index_and_worktree_clean()?;
+        statuses.check_conflicts()?;
+        vec![stupid.with_temp_index(|stupid_temp| {
+            stupid_temp.read_tree(parent_tree_id)?;
+            stupid_temp.add_patch(None::<Vec<OsString>>)?;
+            stupid_temp.write_tree()
+        })?]
+    } else {
+        let diff = stupid.diff_tree_patch(
+            parent_tree_id,
+            patch_tree_id,
+            None::<Vec<OsString>>,
+            false,
+            ["--no-ext-diff"],
+        )?;
+        let mut template = Vec::with_capacity(diff.len() + 512);
+        template.extend_from_slice(
+            format!(
+                "# Edit the diff of `{patchname}` such that only the changes to be split\n\
+                 # off into a new patch remain. Removed changes stay in `{patchname}`.\n\
+                 # Removing all changes aborts the split.\n"
+            )
+            .as_bytes(),
+        );
+        template.extend_from_slice(&diff);
+        let filename = ".stgit-split.patch";
+        std::fs::write(filename, template)?;
+        let edited = patchedit::call_editor(filename, &repo.config_snapshot())?;
+        if !edited.lines().any(|line| line.starts_with(b"diff --git ")) {
+            return Err(anyhow!("no changes selected; split aborted"));
+        }
+        vec![stupid.with_temp_index(|stupid_temp| {
+            stupid_temp.read_tree(parent_tree_id)?;
+            stupid_temp.apply_edited_to_index(edited.as_bstr())?;
+            stupid_temp.write_tree()
+        })?]
+    };
+
+    let mut prev_tree_id = parent_tree_id;
+    for tree_id in &tree_ids {
+        if *tree_id == prev_tree_id {
+            return Err(anyhow!("no changes selected; split aborted"));
+        }
+        prev_tree_id = *tree_id;
+    }
+    if prev_tree_id == patch_tree_id {
+        return Err(anyhow!(
+            "all changes selected; nothing would remain in `{patchname}`"
+        ));
+    }
+
+    let author = patch_commit.author_strict()?;
+    let committer = repo.get_committer()?;
+    let message = patch_commit.message_ex();
+
+    let mut new_patches: Vec<(PatchName, gix::ObjectId)> = Vec::with_capacity(tree_ids.len());
+    let mut parent_id = parent_commit.id;
+    for tree_id in tree_ids {
+        let commit_id = repo.commit_ex(&author, committer, &message, tree_id, [parent_id])?;
+        let new_patchname = {
+            let mut disallow: Vec<&PatchName> = stack.all_patches().collect();
+            disallow.extend(new_patches.iter().map(|(pn, _)| pn));
+            PatchName::make(&format!("{patchname}-1"), false, None).uniquify(&[], &disallow)
+        };
+        new_patches.push((new_patchname, commit_id));
+        parent_id = commit_id;
+    }
+    let remainder_id = repo.commit_ex(&author, committer, &message, patch_tree_id, [parent_id])?;
+
+    stack
+        .setup_transaction()
+        .with_output_stream(get_color_stdout(matches))
+        .transact(|trans| {
+            let is_applied = trans.applied().contains(&patchname);
+            let incidental = if is_applied {
+                trans.pop_patches(|pn| pn == &patchname)?
+            } else {
+                vec![]
+            };
+            trans.update_patch(&patchname, remainder_id)?;
+            let pos = trans
+                .unapplied()
+                .iter()
+                .position(|pn| pn == &patchname)
+                .expect("patch is unapplied");
+            for (i, (new_patchname, commit_id)) in new_patches.iter().enumerate() {
+                trans.new_unapplied(new_patchname, *commit_id, pos + i)?;
+            }
+            if is_applied {
+                let mut to_push: Vec<PatchName> =
+                    new_patches.iter().map(|(pn, _)| pn.clone()).collect();
+                to_push.push(patchname.clone());
+                to_push.extend(incidental);
+                trans.push_patches(&to_push, false)?;
+            }
+            Ok(())
+        })
+        .execute(&format!("split {patchname}"))?;
+
+    Ok(())
+}
@@ -2,9 +2,10 @@
 
 //! `stg squash` implementation.
 
-use std::fmt::Write;
+use std::{fmt::Write, str::FromStr};
 
 use anyhow::{anyhow, Result};
+use bstr::ByteSlice;
 use clap::{Arg, ArgMatches};
 
 use crate::{
@@ -47,7 +48,36 @@ fn make() -> clap::Command {
             \n\
             Conflicts can occur whenever a patch is pushed; this is, in steps (2) and \
             (5). If conflicts occur, the squash command will halt such that the \
-            conflicts may be resolved manually.",
+            conflicts may be resolved manually.\n\
+            \n\
+            With '--fixups', the patches to squash are determined automatically, \
+            similar to `git rebase --autosquash`. Each applied or unapplied patch \
+            whose subject is of the form \"fixup! <target>\" or \"squash! <target>\" \
+            is squashed into the earlier patch named <target> or whose subject \
+            matches <target>. The squashed patch keeps the target patch's name and author. The \
+            commit message of a \"fixup!\" patch is discarded, whereas the body of a \
+            \"squash!\" patch's message is appended to the target patch's message. \
+            All squashes are performed in a single transaction.\n\
+            \n\
+            With '--interactive', the given patches are listed in an editor before \
+            squashing. The lines may be reordered to change the order in which the \
+            patches are squashed. Marking a patch with \"fixup\" instead of \
+            \"squash\" discards its commit message from the squashed patch's \
+            message. Removing a patch's line excludes that patch from the squash.\n\
+            \n\
+            By default, the squashed patches' messages are concatenated as-is, \
+            including any trailers such as \"Signed-off-by:\" or \"Link:\". With \
+            '--preserve-trailers', the trailers are instead removed from each \
+            message and merged into a single trailer block at the end of the \
+            squashed patch's message according to the given policy:\n\
+            \n\
+            \"union\" keeps all trailers from all squashed patches.\n\
+            \n\
+            \"dedup\" keeps all trailers, but repeated trailers with the same key \
+            and value are kept only once.\n\
+            \n\
+            \"first-wins\" keeps, for each trailer key, only the trailers from the \
+            first squashed patch having that key.",
         )
         .arg(
             Arg::new("patchranges")
@@ -56,7 +86,7 @@ fn make() -> clap::Command {
                 .num_args(1..)
                 .allow_hyphen_values(true)
                 .value_parser(clap::value_parser!(PatchRange))
-                .required(true),
+                .required_unless_present("fixups"),
         )
         .arg(
             Arg::new("name")
@@ -66,6 +96,29 @@ fn make() -> clap::Command {
                 .value_name("name")
                 .allow_hyphen_values(true)
                 .value_parser(clap::value_parser!(PatchName)),
+        )
+        .arg(
+            Arg::new("fixups")
+                .long("fixups")
+                .help("Squash \"fixup!\" and \"squash!\" patches into their targets")
+                .action(clap::ArgAction::SetTrue)
+                .conflicts_with_all(["patchranges", "name", "save-template"]),
+        )
+        .arg(
+            Arg::new("interactive")
+                .long("interactive")
+                .short('i')
+                .help("Reorder patches and select messages in an editor")
+                .action(clap::ArgAction::SetTrue)
+                .conflicts_with("fixups"),
+        )
+        .arg(
+            Arg::new("preserve-trailers")
+                .long("preserve-trailers")
+                .help("Merge trailers of squashed patches using <policy>")
+                .value_name("policy")
+                .value_parser(["union", "dedup", "first-wins"])
+                .conflicts_with("fixups"),
         );
     patchedit::add_args(command, true, true)
 }
@@ -80,6 +133,10 @@ fn run(matches: &ArgMatches) -> Result<()> {
     statuses.check_conflicts()?;
     stack.check_head_top_mismatch()?;
 
+    if matches.get_flag("fixups") {
+        return squash_fixups(stack, matches);
+    }
+
     let squash_patchnames: Vec<PatchName> = patchrange::resolve_names(
         &stack,
         matches
@@ -88,6 +145,15 @@ fn run(matches: &ArgMatches) -> Result<()> {
         RangeConstraint::All,
     )?;
 
+    let (squash_patchnames, fixup_patchnames) = if matches.get_flag("interactive") {
+        if squash_patchnames.len() < 2 {
+            return Err(anyhow!("need at least two patches"));
+        }
+        edit_instructions(&stack, &squash_patchnames)?
+    } else {
+        (squash_patchnames, vec![])
+    };
+
     let patchname: Option<PatchName> = matches.get_one::<PatchName>("name").cloned();
 
     if let Some(patchname) = patchname.as_ref() {
@@ -111,7 +177,12 @@ fn run(matches: &ArgMatches) -> Result<()> {
                 .allow_template_save(true)
                 .template_patchname(patchname.as_ref())
                 .default_author(repo.get_author()?.override_author(matches))
-                .default_message(prepare_message(&stack, &squash_patchnames)?)
+                .default_message(prepare_message(
+                    &stack,
+                    &squash_patchnames,
+                    &fixup_patchnames,
+                    TrailerPolicy::from_matches(matches),
+                )?)
                 .edit(&stack, &repo, matches)?
         {
             let template_path = template_path.to_string_lossy();
@@ -129,6 +200,8 @@ fn run(matches: &ArgMatches) -> Result<()> {
             .iter()
             .any(|pn| squash_patchnames.contains(pn));
 
+        stack.backup("squash", &squash_patchnames)?;
+
         stack
             .setup_transaction()
             .allow_conflicts(true)
@@ -140,6 +213,7 @@ fn run(matches: &ArgMatches) -> Result<()> {
                     trans,
                     matches,
                     &squash_patchnames,
+                    &fixup_patchnames,
                     patchname.as_ref(),
                     should_push_squashed,
                 )?;
@@ -150,16 +224,360 @@ fn run(matches: &ArgMatches) -> Result<()> {
     }
 }
 
+/// Squash `fixup!` and `squash!` patches into their target patches.
+fn squash_fixups(stack: Stack, matches: &ArgMatches) -> Result<()> {
+    let groups = find_fixup_groups(&stack)?;
+
+    if groups.is_empty() {
+        print_info_message(matches, "no fixup or squash patches found");
+        return Ok(());
+    }
+
+    let mut group_matches = Vec::with_capacity(groups.len());
+    for group in &groups {
+        let message = prepare_fixup_message(&stack, group)?;
+        let target_author = stack.get_patch_commit(&group[0]).author_strict()?;
+        let author = format!("{} <{}>", target_author.name, target_author.email);
+        let authdate = target_author.time.format(gix::date::time::format::RAW);
+        let dummy_squash_command = clap::Command::new("dummy-squash");
+        let dummy_squash_command = patchedit::add_args(dummy_squash_command, true, false);
+        let squash_matches = dummy_squash_command
+            .try_get_matches_from([
+                "dummy-squash",
+                "--message",
+                message.as_str(),
+                "--author",
+                author.as_str(),
+                "--authdate",
+                authdate.as_str(),
+            ])
+            .expect("dummy command has valid arguments");
+        group_matches.push(squash_matches);
+    }
+
+    let squashed: Vec<PatchName> = groups.iter().flatten().cloned().collect();
+    stack.backup("squash", &squashed)?;
+
+    stack
+        .setup_transaction()
+        .allow_conflicts(true)
+        .use_index_and_worktree(true)
+        .committer_date_is_author_date(matches.get_flag("committer-date-is-author-date"))
+        .with_output_stream(get_color_stdout(matches))
+        .transact(|trans| {
+            for (group, squash_matches) in groups.iter().zip(group_matches.iter()) {
+                let should_push_squashed = trans.applied().iter().any(|pn| group.contains(pn));
+                squash(
+                    trans,
+                    squash_matches,
+                    group,
+                    &[],
+                    Some(&group[0]),
+                    should_push_squashed,
+                )?;
+            }
+            Ok(())
+        })
+        .execute("squash: fixups")?;
+
+    Ok(())
+}
+
+/// Kind of autosquash patch, determined by its subject prefix.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum FixupKind {
+    Fixup,
+    Squash,
+}
+
+/// Determine the autosquash kind and target of a patch's subject line.
+fn parse_fixup_subject(subject: &str) -> Option<(FixupKind, &str)> {
+    if let Some(target) = subject.strip_prefix("fixup! ") {
+        Some((FixupKind::Fixup, target.trim()))
+    } else {
+        subject
+            .strip_prefix("squash! ")
+            .map(|target| (FixupKind::Squash, target.trim()))
+    }
+}
+
+pub(super) fn patch_subject<'repo>(
+    stack: &impl StackStateAccess<'repo>,
+    patchname: &PatchName,
+) -> Result<String> {
+    let message = stack.get_patch_commit(patchname).message_ex();
+    let message = message.decode()?;
+    Ok(message
+        .lines()
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_string())
+}
+
+/// Find groups of patches to squash, each led by its target patch.
+///
+/// Only applied and unapplied patches are considered. A `fixup!` or `squash!` patch
+/// may only target a patch below it in the stack. Groups are returned in stack order
+/// of their target patches.
+fn find_fixup_groups(stack: &Stack) -> Result<Vec<Vec<PatchName>>> {
+    let patchnames: Vec<PatchName> = stack
+        .applied()
+        .iter()
+        .chain(stack.unapplied().iter())
+        .cloned()
+        .collect();
+    let subjects = patchnames
+        .iter()
+        .map(|pn| patch_subject(stack, pn))
+        .collect::<Result<Vec<String>>>()?;
+
+    // Index of the group leader for each patch that is squashed into another.
+    let mut leaders: Vec<Option<usize>> = vec![None; patchnames.len()];
+
+    for (i, subject) in subjects.iter().enumerate() {
+        let target = if let Some((_, target)) = parse_fixup_subject(subject) {
+            target
+        } else {
+            continue;
+        };
+        let target_index = (0..i)
+            .find(|&j| subjects[j] == target)
+            .or_else(|| (0..i).find(|&j| AsRef::<str>::as_ref(&patchnames[j]) == target))
+            .or_else(|| (0..i).find(|&j| subjects[j].starts_with(target)));
+        if let Some(j) = target_index {
+            leaders[i] = Some(leaders[j].unwrap_or(j));
+        }
+    }
+
+    let mut groups: Vec<(usize, Vec<PatchName>)> = Vec::new();
+    for (i, leader) in leaders.iter().enumerate() {
+        if let Some(leader) = leader {
+            if let Some((_, group)) = groups.iter_mut().find(|(j, _)| j == leader) {
+                group.push(patchnames[i].clone());
+            } else {
+                groups.push((
+                    *leader,
+                    vec![patchnames[*leader].clone(), patchnames[i].clone()],
+                ));
+            }
+        }
+    }
+    groups.sort_by_key(|(leader, _)| *leader);
+    Ok(groups.into_iter().map(|(_, group)| group).collect())
+}
+
+/// Prepare the message for a target patch squashed with its fixups.
+///
+/// The target patch's message is retained. The message bodies of `squash!` patches
+/// are appended while `fixup!` patch messages are discarded.
+fn prepare_fixup_message<'repo>(
+    stack_state: &impl StackStateAccess<'repo>,
+    group: &[PatchName],
+) -> Result<String> {
+    let target_message = stack_state.get_patch_commit(&group[0]).message_ex();
+    let mut message = target_message.decode()?.trim_end().to_string();
+    for patchname in &group[1..] {
+        let squash_message = stack_state.get_patch_commit(patchname).message_ex();
+        let squash_message = squash_message.decode()?;
+        let mut lines = squash_message.lines();
+        let subject = lines.next().unwrap_or_default();
+        if let Some((FixupKind::Squash, _)) = parse_fixup_subject(subject.trim()) {
+            let body = lines.collect::<Vec<_>>().join("\n");
+            let body = body.trim();
+            if !body.is_empty() {
+                message.push_str("\n\n");
+                message.push_str(body);
+            }
+        }
+    }
+    Ok(message)
+}
+
+const INTERACTIVE_HELP_LINES: &str = "\
+#
+# Commands:
+#
+#   s, squash <patch> = squash patch, keeping its commit message
+#   f, fixup <patch> = squash patch, discarding its commit message
+#
+# These lines can be reordered; the patches are squashed from top to bottom.
+#
+# Removing a patch's line excludes the patch from the squash.
+# If all lines are removed, the squash is aborted.
+";
+
+/// Let the user reorder the patches to squash and choose which messages to keep.
+///
+/// Returns the patches to squash, in order, along with the subset of those patches
+/// whose commit messages are to be discarded.
+fn edit_instructions(
+    stack: &Stack,
+    patchnames: &[PatchName],
+) -> Result<(Vec<PatchName>, Vec<PatchName>)> {
+    let name_width = patchnames.iter().map(PatchName::len).max().unwrap_or(0);
+    let mut template = String::with_capacity(4096);
+    for patchname in patchnames {
+        let subject = patch_subject(stack, patchname)?;
+        writeln!(template, "squash {patchname:name_width$} # {subject}")?;
+    }
+    template.push_str(INTERACTIVE_HELP_LINES);
+
+    let filename = ".stgit-squash-interactive.txt";
+    std::fs::write(filename, template)?;
+    let buf = patchedit::call_editor(filename, &stack.repo.config_snapshot())?;
+    let buf = buf
+        .to_str()
+        .map_err(|_| anyhow!("`{filename}` is not valid UTF-8"))?;
+
+    let mut squash_patchnames: Vec<PatchName> = Vec::new();
+    let mut fixup_patchnames: Vec<PatchName> = Vec::new();
+    for line in buf.lines() {
+        let instruction_str = line.split_once('#').map_or(line, |(s, _)| s).trim();
+        if instruction_str.is_empty() {
+            continue;
+        }
+        let (action_str, patchname_str) = instruction_str
+            .split_once(|c: char| c.is_ascii_whitespace())
+            .ok_or_else(|| anyhow!("bad instruction line: `{}`", line.trim()))?;
+        let patchname = PatchName::from_str(patchname_str.trim())?;
+        if !patchnames.contains(&patchname) {
+            return Err(anyhow!("patch `{patchname}` is not being squashed"));
+        } else if squash_patchnames.contains(&patchname) {
+            return Err(anyhow!("duplicated patch name `{patchname}`"));
+        }
+        match action_str {
+            "s" | "squash" => {}
+            "f" | "fix" | "fixup" => fixup_patchnames.push(patchname.clone()),
+            _ => return Err(anyhow!("unknown instruction action `{action_str}`")),
+        }
+        squash_patchnames.push(patchname);
+    }
+
+    if squash_patchnames.is_empty() {
+        Err(anyhow!("no patches listed; squash aborted"))
+    } else if squash_patchnames.len() < 2 {
+        Err(anyhow!("need at least two patches"))
+    } else if fixup_patchnames.len() == squash_patchnames.len() {
+        Err(anyhow!("at least one patch's message must be kept"))
+    } else {
+        Ok((squash_patchnames, fixup_patchnames))
+    }
+}
+
+/// Policy for merging the trailers of squashed patches' messages.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum TrailerPolicy {
+    /// Keep all trailers.
+    Union,
+    /// Keep all trailers, but only the first of any identical trailers.
+    Dedup,
+    /// For each trailer key, keep only trailers from the first patch having that key.
+    FirstWins,
+}
+
+impl TrailerPolicy {
+    /// Get the policy from the `--preserve-trailers` option, if any.
+    ///
+    /// The option may be absent from `matches`, e.g. when squashing from `stg rebase`.
+    fn from_matches(matches: &ArgMatches) -> Option<Self> {
+        match matches
+            .try_get_one::<String>("preserve-trailers")
+            .ok()
+            .flatten()
+            .map(String::as_str)
+        {
+            Some("union") => Some(Self::Union),
+            Some("dedup") => Some(Self::Dedup),
+            Some("first-wins") => Some(Self::FirstWins),
+            _ => None,
+        }
+    }
+}
+
+/// Split a commit message into its body and the lines of its trailer block.
+///
+/// The trailer block is the message's last paragraph, provided that it is not the
+/// only paragraph and that all of its lines are of the form `Key: value`.
+fn split_trailers(message: &str) -> (&str, Vec<&str>) {
+    let trimmed = message.trim_end();
+    if let Some((body, last)) = trimmed.rsplit_once("\n\n") {
+        let is_trailer_block = last.lines().all(|line| {
+            line.split_once(':').map_or(false, |(key, value)| {
+                !key.is_empty()
+                    && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+                    && !value.trim().is_empty()
+            })
+        });
+        if is_trailer_block {
+            return (body.trim_end(), last.lines().collect());
+        }
+    }
+    (trimmed, Vec::new())
+}
+
+/// Merge the trailers of several messages according to `policy`.
+///
+/// Each element of `trailer_blocks` holds the trailer lines of one message.
+fn merge_trailers<'a>(trailer_blocks: &[Vec<&'a str>], policy: TrailerPolicy) -> Vec<&'a str> {
+    let trailer_key = |line: &str| {
+        line.split_once(':')
+            .map_or("", |(key, _)| key)
+            .to_ascii_lowercase()
+    };
+    let mut merged: Vec<&str> = Vec::new();
+    let mut seen_keys: Vec<String> = Vec::new();
+    for block in trailer_blocks {
+        let mut block_keys: Vec<String> = Vec::new();
+        for &line in block {
+            let key = trailer_key(line);
+            let keep = match policy {
+                TrailerPolicy::Union => true,
+                TrailerPolicy::Dedup => !merged.contains(&line),
+                TrailerPolicy::FirstWins => !seen_keys.contains(&key),
+            };
+            if keep {
+                merged.push(line);
+            }
+            block_keys.push(key);
+        }
+        seen_keys.extend(block_keys);
+    }
+    merged
+}
+
+/// Prepare the message for the squashed patch.
+///
+/// The messages of `fixup_patchnames` are omitted. With a `trailer_policy`, the
+/// trailers of the messages are merged into a single trailer block at the end.
 fn prepare_message<'repo>(
     stack_state: &impl StackStateAccess<'repo>,
     patchnames: &[PatchName],
+    fixup_patchnames: &[PatchName],
+    trailer_policy: Option<TrailerPolicy>,
 ) -> Result<String> {
     let mut squash_message = String::new();
+    let mut messages: Vec<String> = Vec::with_capacity(patchnames.len());
+    for patchname in patchnames {
+        if !fixup_patchnames.contains(patchname) {
+            let commit = stack_state.get_patch_commit(patchname);
+            messages.push(commit.message_ex().decode()?.to_string());
+        }
+    }
+    let mut trailer_blocks: Vec<Vec<&str>> = Vec::new();
+    let mut messages = messages.iter();
     for (i, patchname) in patchnames.iter().enumerate() {
-        let commit = stack_state.get_patch_commit(patchname);
-        let message = commit.message_ex();
-        let message = message.decode()?;
-        let message = message.trim_end();
+        if fixup_patchnames.contains(patchname) {
+            continue;
+        }
+        let message = messages.next().expect("message for each non-fixup patch");
+        let message = if trailer_policy.is_some() {
+            let (body, trailers) = split_trailers(message);
+            trailer_blocks.push(trailers);
+            body
+        } else {
+            message.trim_end()
+        };
         let patch_number = i + 1;
         write!(
             squash_message,
@@ -168,6 +586,12 @@ fn prepare_message<'repo>(
              \n"
         )?;
     }
+    if let Some(policy) = trailer_policy {
+        for line in merge_trailers(&trailer_blocks, policy) {
+            squash_message.push_str(line);
+            squash_message.push('\n');
+        }
+    }
     Ok(squash_message)
 }
 
@@ -175,11 +599,12 @@ pub(super) fn squash(
     trans: &mut StackTransaction,
     matches: &ArgMatches,
     patchnames: &[PatchName],
+    fixup_patchnames: &[PatchName],
     patchname: Option<&PatchName>,
     should_push_squashed: bool,
 ) -> Result<PatchName> {
     let (new_patchname, commit_id, to_push) = if let Some((new_patchname, commit_id)) =
-        try_squash(trans, matches, patchnames, patchname)?
+        try_squash(trans, matches, patchnames, fixup_patchnames, patchname)?
     {
         // Squashed commit could be created with simple merges, so the
         // constituent patches can just be deleted.
@@ -189,7 +614,8 @@ pub(super) fn squash(
         // Simple approach failed, need to do pops and pushes...
         let to_push = trans.pop_patches(|pn| patchnames.contains(pn))?;
         trans.push_patches(patchnames, false)?;
-        if let Some((new_patchname, commit_id)) = try_squash(trans, matches, patchnames, patchname)?
+        if let Some((new_patchname, commit_id)) =
+            try_squash(trans, matches, patchnames, fixup_patchnames, patchname)?
         {
             let popped_extra = trans.delete_patches(|pn| patchnames.contains(pn))?;
             assert!(popped_extra.is_empty());
@@ -218,6 +644,7 @@ fn try_squash(
     trans: &StackTransaction,
     matches: &ArgMatches,
     patchnames: &[PatchName],
+    fixup_patchnames: &[PatchName],
     patchname: Option<&PatchName>,
 ) -> Result<Option<(PatchName, gix::ObjectId)>> {
     let repo = trans.repo();
@@ -260,7 +687,12 @@ fn try_squash(
             .template_patchname(patchname)
             .extra_allowed_patchnames(patchnames)
             .default_author(repo.get_author()?.override_author(matches))
-            .default_message(prepare_message(trans, patchnames)?)
+            .default_message(prepare_message(
+                trans,
+                patchnames,
+                fixup_patchnames,
+                TrailerPolicy::from_matches(matches),
+            )?)
             .edit(trans, repo, matches)?
         {
             Ok(Some((
@@ -0,0 +1,116 @@
+// SPDX-License-Identifier: GPL-2.0-only
+
+//! `stg stack export` implementation.
+
+use std::{ffi::OsString, io::Write, path::PathBuf};
+
+use anyhow::{Context, Result};
+use clap::Arg;
+
+use super::{Bundle, BundleDiff, BundlePatch, BundlePatchState, BundleSignature, BUNDLE_VERSION};
+use crate::{
+    argset,
+    branchloc::BranchLocator,
+    ext::{CommitExtended, RepositoryExtended},
+    stack::{InitializationPolicy, Stack, StackAccess, StackStateAccess},
+    stupid::Stupid,
+};
+
+pub(super) fn command() -> clap::Command {
+    clap::Command::new("export")
+        .about("Export the stack to a bundle")
+        .long_about(
+            "Export all patches of the stack, including unapplied and hidden patches, \
+             to a JSON stack bundle. The bundle may be recreated in another clone \
+             using `stg stack import`.\n\
+             \n\
+             The bundle is written to standard output unless '--output' is specified.",
+        )
+        .arg(argset::branch_arg())
+        .arg(
+            Arg::new("output")
+                .long("output")
+                .short('o')
+                .help("Write bundle to <file> instead of stdout")
+                .value_name("file")
+                .value_hint(clap::ValueHint::FilePath)
+                .value_parser(clap::value_parser!(PathBuf)),
+        )
+}
+
+pub(super) fn dispatch(matches: &clap::ArgMatches) -> Result<()> {
+    let repo = gix::Repository::open()?;
+    let stack = Stack::from_branch_locator(
+        &repo,
+        matches.get_one::<BranchLocator>("branch"),
+        InitializationPolicy::RequireInitialized,
+    )?;
+    let stupid = repo.stupid();
+
+    let mut patches = Vec::new();
+    for (patchname, state) in stack
+        .applied()
+        .iter()
+        .map(|pn| (pn, BundlePatchState::Applied))
+        .chain(
+            stack
+                .unapplied()
+                .iter()
+                .map(|pn| (pn, BundlePatchState::Unapplied)),
+        )
+        .chain(
+            stack
+                .hidden()
+                .iter()
+                .map(|pn| (pn, BundlePatchState::Hidden)),
+        )
+    {
+        let patch_commit = stack.get_patch_commit(patchname);
+        let parent_commit = patch_commit.get_parent_commit()?;
+        let author = patch_commit.author_strict()?;
+        let message = patch_commit
+            .message_ex()
+            .decode()
+            .with_context(|| format!("decoding message of patch `{patchname}`"))?
+            .to_string();
+        let diff = stupid.diff_tree_patch(
+            parent_commit.tree_id()?.detach(),
+            patch_commit.tree_id()?.detach(),
+            <Option<Vec<OsString>>>::None,
+            false,
+            ["--full-index", "--binary"],
+        )?;
+        let diff = BundleDiff::from_bytes(diff.into());
+
+        patches.push(BundlePatch {
+            name: patchname.clone(),
+            state,
+            author: BundleSignature {
+                name: author.name.to_string(),
+                email: author.email.to_string(),
+                date: author.time.format(gix::date::time::format::RAW),
+            },
+            message,
+            diff,
+        });
+    }
+
+    let bundle = Bundle {
+        version: BUNDLE_VERSION,
+        branch: stack.get_branch_name().to_string(),
+        base: stack.base().id.to_string(),
+        patches,
+    };
+
+    let mut output = serde_json::to_vec_pretty(&bundle)?;
+    output.push(b'\n');
+
+    if let Some(path) = matches.get_one::<PathBuf>("output") {
+        std::fs::write(path, output)
+            .with_context(|| format!("writing bundle to `{}`", path.display()))?;
+    } else {
+        std::io::stdout().write_all(&output)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,149 @@
+// SPDX-License-Identifier: GPL-2.0-only
+
+//! `stg stack import` implementation.
+
+use std::{io::Read, path::PathBuf};
+
+use anyhow::{anyhow, Context, Result};
+use bstr::{BStr, BString, ByteSlice};
+use clap::Arg;
+
+use super::{Bundle, BundlePatchState, BUNDLE_VERSION};
+use crate::{
+    color::get_color_stdout,
+    ext::{RepositoryExtended, TimeExtended},
+    patch::PatchName,
+    stack::{InitializationPolicy, Stack, StackAccess, StackStateAccess},
+    stupid::Stupid,
+    wrap::Message,
+};
+
+pub(super) fn command() -> clap::Command {
+    clap::Command::new("import")
+        .about("Recreate patches from a stack bundle")
+        .long_about(
+            "Recreate the patches from a stack bundle, as written by `stg stack \
+             export`, on top of the current stack.\n\
+             \n\
+             The bundled patches are added above the current top patch in the same \
+             order and with the same applied, unapplied, or hidden state that they \
+             had in the exported stack. None of the bundled patch names may already \
+             exist in the current stack.\n\
+             \n\
+             Use '-' as the bundle file to read the bundle from standard input.",
+        )
+        .arg(
+            Arg::new("file")
+                .help("Stack bundle file")
+                .value_name("file")
+                .required(true)
+                .value_hint(clap::ValueHint::FilePath)
+                .value_parser(clap::value_parser!(PathBuf)),
+        )
+}
+
+pub(super) fn dispatch(matches: &clap::ArgMatches) -> Result<()> {
+    let repo = gix::Repository::open()?;
+    let stack = Stack::current(&repo, InitializationPolicy::AutoInitialize)?;
+    let stupid = repo.stupid();
+
+    repo.check_repository_state()?;
+    stupid.statuses(None)?.check_index_and_worktree_clean()?;
+    stack.check_head_top_mismatch()?;
+
+    let path = matches
+        .get_one::<PathBuf>("file")
+        .expect("file is a required argument");
+    let data = if path.as_os_str() == "-" {
+        let mut data = Vec::new();
+        std::io::stdin().read_to_end(&mut data)?;
+        data
+    } else {
+        std::fs::read(path).with_context(|| format!("reading `{}`", path.display()))?
+    };
+    let bundle: Bundle = serde_json::from_slice(&data).context("parsing stack bundle")?;
+
+    if bundle.version > BUNDLE_VERSION {
+        return Err(anyhow!(
+            "unsupported stack bundle version {}; expected {BUNDLE_VERSION} or less",
+            bundle.version
+        ));
+    }
+
+    for (i, patch) in bundle.patches.iter().enumerate() {
+        if let Some(colliding) = stack.all_patches().find(|pn| patch.name.collides(pn)) {
+            return Err(anyhow!(
+                "patch `{}` from bundle collides with existing patch `{colliding}`",
+                patch.name
+            ));
+        }
+        if bundle.patches[..i]
+            .iter()
+            .any(|other| patch.name.collides(&other.name))
+        {
+            return Err(anyhow!("duplicate patch `{}` in bundle", patch.name));
+        }
+    }
+
+    let committer = repo.get_committer()?;
+    let mut parent_id = stack.get_branch_head().id;
+    let mut parent_tree_id = stack.get_branch_head().tree_id()?.detach();
+    let mut new_patches: Vec<(PatchName, BundlePatchState, gix::ObjectId)> =
+        Vec::with_capacity(bundle.patches.len());
+
+    for patch in bundle.patches {
+        let tree_id = if patch.diff.as_bytes().trim().is_empty() {
+            parent_tree_id
+        } else {
+            stupid
+                .with_temp_index(|stupid_temp| {
+                    stupid_temp.read_tree(parent_tree_id)?;
+                    stupid_temp.apply_to_index(BStr::new(patch.diff.as_bytes()))?;
+                    stupid_temp.write_tree()
+                })
+                .with_context(|| format!("applying diff of patch `{}`", patch.name))?
+        };
+        let author = gix::actor::Signature {
+            name: BString::from(patch.author.name),
+            email: BString::from(patch.author.email),
+            time: gix::date::Time::parse_time(&patch.author.date)?,
+        };
+        let commit_id = repo.commit_ex(
+            &author,
+            committer,
+            &Message::from(patch.message),
+            tree_id,
+            [parent_id],
+        )?;
+        parent_id = commit_id;
+        parent_tree_id = tree_id;
+        new_patches.push((patch.name, patch.state, commit_id));
+    }
+
+    stack
+        .setup_transaction()
+        .use_index_and_worktree(true)
+        .with_output_stream(get_color_stdout(matches))
+        .transact(|trans| {
+            let mut to_hide = Vec::new();
+            let mut insert_pos = 0;
+            for (patchname, state, commit_id) in &new_patches {
+                if *state == BundlePatchState::Applied && insert_pos == 0 {
+                    trans.new_applied(patchname, *commit_id)?;
+                } else {
+                    trans.new_unapplied(patchname, *commit_id, insert_pos)?;
+                    insert_pos += 1;
+                    if *state == BundlePatchState::Hidden {
+                        to_hide.push(patchname.clone());
+                    }
+                }
+            }
+            if !to_hide.is_empty() {
+                trans.hide_patches(&to_hide)?;
+            }
+            Ok(())
+        })
+        .execute(&format!("stack import: {}", bundle.branch))?;
+
+    Ok(())
+}
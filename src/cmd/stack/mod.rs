@@ -0,0 +1,109 @@
+// SPDX-License-Identifier: GPL-2.0-only
+
+//! `stg stack` implementation.
+
+mod export;
+mod import;
+
+use anyhow::Result;
+
+pub(super) const STGIT_COMMAND: super::StGitCommand = super::StGitCommand {
+    name: "stack",
+    category: super::CommandCategory::StackManipulation,
+    make,
+    run,
+};
+
+fn make() -> clap::Command {
+    clap::Command::new(STGIT_COMMAND.name)
+        .about("Export or import a whole stack as a bundle")
+        .long_about(
+            "Export or import a whole stack as a portable bundle.\n\
+             \n\
+             A stack bundle is a single JSON document containing each patch's name, \
+             author, message, and diff along with the order of the patches and \
+             whether each patch is applied, unapplied, or hidden. Bundles make it \
+             possible to move a stack between clones of a repository without pushing \
+             or fetching the stack's references.\n\
+             \n\
+             Use `stg stack export` to write a bundle and `stg stack import` to \
+             recreate the bundled patches on top of a branch in another clone.",
+        )
+        .subcommand_required(true)
+        .subcommand(export::command())
+        .subcommand(import::command())
+}
+
+fn run(matches: &clap::ArgMatches) -> Result<()> {
+    match matches.subcommand() {
+        Some(("export", sub_matches)) => export::dispatch(sub_matches),
+        Some(("import", sub_matches)) => import::dispatch(sub_matches),
+        _ => panic!("valid subcommand is expected"),
+    }
+}
+
+/// Version of the stack bundle format.
+const BUNDLE_VERSION: i64 = 1;
+
+/// Serialized representation of a whole stack.
+#[derive(serde::Serialize, serde::Deserialize)]
+struct Bundle {
+    version: i64,
+    branch: String,
+    base: String,
+    patches: Vec<BundlePatch>,
+}
+
+/// Serialized representation of a single patch in a [`Bundle`].
+#[derive(serde::Serialize, serde::Deserialize)]
+struct BundlePatch {
+    name: crate::patch::PatchName,
+    state: BundlePatchState,
+    author: BundleSignature,
+    message: String,
+    diff: BundleDiff,
+}
+
+/// Diff of a patch in a [`Bundle`].
+///
+/// Diffs are stored as JSON strings when they are valid UTF-8. Otherwise, e.g. when a
+/// patch changes a file using another text encoding, the diff is stored as an array
+/// of byte values such that it is carried through the bundle unchanged.
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(untagged)]
+enum BundleDiff {
+    Text(String),
+    Bytes(Vec<u8>),
+}
+
+impl BundleDiff {
+    fn from_bytes(diff: Vec<u8>) -> Self {
+        match String::from_utf8(diff) {
+            Ok(text) => Self::Text(text),
+            Err(e) => Self::Bytes(e.into_bytes()),
+        }
+    }
+
+    fn as_bytes(&self) -> &[u8] {
+        match self {
+            Self::Text(text) => text.as_bytes(),
+            Self::Bytes(bytes) => bytes,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum BundlePatchState {
+    Applied,
+    Unapplied,
+    Hidden,
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct BundleSignature {
+    name: String,
+    email: String,
+    /// Time in git's raw format, i.e. `<seconds> <offset>`.
+    date: String,
+}
@@ -0,0 +1,164 @@
+// SPDX-License-Identifier: GPL-2.0-only
+
+//! `stg stat` implementation.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use anyhow::Result;
+use bstr::BString;
+use clap::{Arg, ArgMatches};
+
+use crate::{
+    argset,
+    branchloc::BranchLocator,
+    ext::{CommitExtended, RepositoryExtended},
+    patch::PatchName,
+    stack::{InitializationPolicy, Stack, StackStateAccess},
+    stupid::Stupid,
+};
+
+pub(super) const STGIT_COMMAND: super::StGitCommand = super::StGitCommand {
+    name: "stat",
+    category: super::CommandCategory::StackInspection,
+    make,
+    run,
+};
+
+fn make() -> clap::Command {
+    clap::Command::new(STGIT_COMMAND.name)
+        .about("Show stack statistics")
+        .long_about(
+            "Show aggregate statistics about the patches in the stack.\n\
+             \n\
+             The number of patches in each state is always shown. The remaining \
+             statistics are computed over the applied and unapplied patches, and \
+             also the hidden patches when '--all' is specified:\n\
+             \n\
+             - The total number of distinct files changed and the total number of \
+             inserted and deleted lines, summed over each patch's diff.\n\
+             \n\
+             - The average number of changed lines per patch.\n\
+             \n\
+             - The oldest and newest patches by author date.\n\
+             \n\
+             - The number of patches by each author. Authors are mapped through \
+             the mailmap, as with git-log(1).",
+        )
+        .arg(argset::branch_arg())
+        .arg(
+            Arg::new("all")
+                .long("all")
+                .short('a')
+                .help("Include hidden patches in statistics")
+                .action(clap::ArgAction::SetTrue),
+        )
+}
+
+fn run(matches: &ArgMatches) -> Result<()> {
+    let repo = gix::Repository::open()?;
+    let stack = Stack::from_branch_locator(
+        &repo,
+        matches.get_one::<BranchLocator>("branch"),
+        InitializationPolicy::AllowUninitialized,
+    )?;
+    let stupid = repo.stupid();
+
+    let patchnames: Vec<&PatchName> = if matches.get_flag("all") {
+        stack.all_patches().collect()
+    } else {
+        stack
+            .applied()
+            .iter()
+            .chain(stack.unapplied().iter())
+            .collect()
+    };
+
+    println!(
+        "Patches:   {} ({} applied, {} unapplied, {} hidden)",
+        stack.applied().len() + stack.unapplied().len() + stack.hidden().len(),
+        stack.applied().len(),
+        stack.unapplied().len(),
+        stack.hidden().len(),
+    );
+
+    if patchnames.is_empty() {
+        return Ok(());
+    }
+
+    let mut files: BTreeSet<BString> = BTreeSet::new();
+    let mut insertions = 0;
+    let mut deletions = 0;
+    let mut oldest: Option<(gix::date::Time, &PatchName)> = None;
+    let mut newest: Option<(gix::date::Time, &PatchName)> = None;
+    let mut authors: BTreeMap<(BString, BString), usize> = BTreeMap::new();
+    let mailmap = repo.mailmap();
+
+    for patchname in &patchnames {
+        let patch_commit = stack.get_patch_commit(patchname);
+        let parent_commit = patch_commit.get_parent_commit()?;
+        for numstat in stupid.diff_tree_numstat(
+            parent_commit.tree_id()?.detach(),
+            patch_commit.tree_id()?.detach(),
+        )? {
+            insertions += numstat.added.unwrap_or_default();
+            deletions += numstat.deleted.unwrap_or_default();
+            files.insert(numstat.path);
+        }
+
+        let author = patch_commit.author_strict()?;
+        if oldest.map_or(true, |(time, _)| author.time.seconds < time.seconds) {
+            oldest = Some((author.time, patchname));
+        }
+        if newest.map_or(true, |(time, _)| author.time.seconds >= time.seconds) {
+            newest = Some((author.time, patchname));
+        }
+        let author = mailmap.resolve(author.to_ref());
+        *authors.entry((author.name, author.email)).or_default() += 1;
+    }
+
+    println!(
+        "Changes:   {} file{} changed, {insertions} insertion{}(+), {deletions} deletion{}(-)",
+        files.len(),
+        plural(files.len()),
+        plural(insertions),
+        plural(deletions),
+    );
+    println!(
+        "Average:   {:.1} changed lines per patch",
+        (insertions + deletions) as f64 / patchnames.len() as f64
+    );
+    if let Some((time, patchname)) = oldest {
+        println!(
+            "Oldest:    {} ({patchname})",
+            time.format(gix::date::time::format::ISO8601)
+        );
+    }
+    if let Some((time, patchname)) = newest {
+        println!(
+            "Newest:    {} ({patchname})",
+            time.format(gix::date::time::format::ISO8601)
+        );
+    }
+
+    let mut authors: Vec<_> = authors.into_iter().collect();
+    authors.sort_by(|(a_ident, a_count), (b_ident, b_count)| {
+        b_count.cmp(a_count).then_with(|| a_ident.cmp(b_ident))
+    });
+    let count_width = authors
+        .first()
+        .map_or(1, |(_, count)| count.to_string().len());
+    println!("Authors:");
+    for ((name, email), count) in authors {
+        println!("  {count:>count_width$}  {name} <{email}>");
+    }
+
+    Ok(())
+}
+
+fn plural(count: usize) -> &'static str {
+    if count == 1 {
+        ""
+    } else {
+        "s"
+    }
+}
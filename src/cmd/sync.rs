@@ -223,14 +223,11 @@ fn run(matches: &clap::ArgMatches) -> Result<()> {
 
                 if let Some(tree_id) = maybe_tree_id {
                     let author = commit.author_strict()?;
-                    let default_committer = trans.repo().get_committer()?;
-                    let committer = if matches.get_flag("committer-date-is-author-date") {
-                        let mut committer = default_committer.to_owned();
-                        committer.time = author.time;
-                        committer
-                    } else {
-                        default_committer.to_owned()
-                    };
+                    let committer = trans.repo().get_rewrite_committer(
+                        Some(commit),
+                        author.time,
+                        matches.get_flag("committer-date-is-author-date"),
+                    )?;
                     let commit_id = trans.repo().commit_ex(
                         &author,
                         &committer,
@@ -11,6 +11,7 @@ use crate::{
     argset,
     branchloc::BranchLocator,
     ext::RepositoryExtended,
+    patch::PatchName,
     stack::{InitializationPolicy, Stack, StackStateAccess},
 };
 
@@ -31,6 +32,36 @@ fn make() -> clap::Command {
              message will be printed if no patches are applied.",
         )
         .arg(argset::branch_arg())
+        .arg(oid_arg())
+}
+
+/// Argument for printing a patch's commit id instead of its name.
+pub(super) fn oid_arg() -> clap::Arg {
+    clap::Arg::new("oid")
+        .long("oid")
+        .help("Print the patch's commit id instead of its name")
+        .action(clap::ArgAction::SetTrue)
+}
+
+/// Print the name of the given patch, or its commit id with `--oid`.
+pub(super) fn print_patch<'repo>(
+    matches: &clap::ArgMatches,
+    stack: &impl StackStateAccess<'repo>,
+    patchname: &PatchName,
+) -> Result<()> {
+    let mut stdout = crate::color::get_color_stdout(matches);
+    if matches.get_flag("oid") {
+        writeln!(stdout, "{}", stack.get_patch_commit_id(patchname))?;
+        return Ok(());
+    }
+    let mut color_spec = termcolor::ColorSpec::new();
+    color_spec.set_bold(true);
+    stdout.set_color(&color_spec)?;
+    write!(stdout, "{patchname}")?;
+    color_spec.clear();
+    stdout.set_color(&color_spec)?;
+    writeln!(stdout)?;
+    Ok(())
 }
 
 fn run(matches: &clap::ArgMatches) -> Result<()> {
@@ -42,15 +73,7 @@ fn run(matches: &clap::ArgMatches) -> Result<()> {
     )?;
 
     if let Some(patchname) = stack.applied().last() {
-        let mut stdout = crate::color::get_color_stdout(matches);
-        let mut color_spec = termcolor::ColorSpec::new();
-        color_spec.set_bold(true);
-        stdout.set_color(&color_spec)?;
-        write!(stdout, "{patchname}")?;
-        color_spec.clear();
-        stdout.set_color(&color_spec)?;
-        writeln!(stdout)?;
-        Ok(())
+        print_patch(matches, &stack, patchname)
     } else {
         Err(super::Error::NoAppliedPatches.into())
     }
@@ -2,16 +2,17 @@
 
 //! `stg uncommit` implementation.
 
-use std::rc::Rc;
+use std::{fmt::Write, rc::Rc, str::FromStr};
 
 use anyhow::{anyhow, Result};
+use bstr::ByteSlice;
 use clap::{Arg, ArgMatches};
 
 use crate::{
     argset,
     color::get_color_stdout,
     ext::{CommitExtended, RepositoryExtended},
-    patch::PatchName,
+    patch::{patchedit, PatchName},
     stack::{InitializationPolicy, Stack, StackAccess, StackStateAccess},
     stupid::Stupid,
 };
@@ -46,6 +47,13 @@ fn make() -> clap::Command {
              given commit should be uncommitted. The -x/--exclusive option may be \
              used to exclude the \"to\" commit.\n\
              \n\
+             The -e/--edit-names option opens the patch names, whether generated or \
+             provided on the command line, in an editor before any patches are \
+             created. Each line holds the name of one patch, in series order, \
+             followed by the commit's subject as a comment. Changing a name on a line \
+             renames the corresponding patch. Removing all lines aborts the \
+             uncommit.\n\
+             \n\
              Only commits with exactly one parent can be uncommitted; in other words, \
              merge commits may not be uncommitted.",
         )
@@ -88,6 +96,13 @@ fn make() -> clap::Command {
                 .help("Exclude the commit specified by the '--to' option")
                 .action(clap::ArgAction::SetTrue),
         )
+        .arg(
+            Arg::new("edit-names")
+                .long("edit-names")
+                .short('e')
+                .help("Edit the patch names in an editor before uncommitting")
+                .action(clap::ArgAction::SetTrue),
+        )
 }
 
 fn run(matches: &ArgMatches) -> Result<()> {
@@ -190,6 +205,14 @@ fn run(matches: &ArgMatches) -> Result<()> {
 
     assert_eq!(commits.len(), patchnames.len());
 
+    let patchnames = if matches.get_flag("edit-names") {
+        let patchnames = edit_patchnames(&stack, &commits, &patchnames)?;
+        check_patchnames(&stack, &patchnames)?;
+        patchnames
+    } else {
+        patchnames
+    };
+
     stack
         .setup_transaction()
         .use_index_and_worktree(false)
@@ -259,3 +282,62 @@ fn check_patchnames(stack: &Stack, patchnames: &[PatchName]) -> Result<()> {
 
     Ok(())
 }
+
+const EDIT_NAMES_HELP_LINES: &str = "\
+#
+# Each line holds the name of the patch to create for the commit whose subject
+# follows the '#'. The patches are listed from bottom to top of the stack.
+#
+# Edit the names as desired, but do not add, remove, or reorder lines.
+# If all lines are removed, the uncommit is aborted.
+";
+
+/// Let the user edit the names of the patches to be uncommitted.
+///
+/// The `commits` and `patchnames` are ordered from newest to oldest, whereas the
+/// editor lists them in series order, i.e. from oldest to newest. The returned patch
+/// names are ordered from newest to oldest.
+fn edit_patchnames(
+    stack: &Stack,
+    commits: &[Rc<gix::Commit<'_>>],
+    patchnames: &[PatchName],
+) -> Result<Vec<PatchName>> {
+    let name_width = patchnames.iter().map(PatchName::len).max().unwrap_or(0);
+    let mut template = String::with_capacity(4096);
+    for (commit, patchname) in commits.iter().zip(patchnames).rev() {
+        let message = commit.message_ex();
+        let message = message.decode()?;
+        let subject = message.lines().next().unwrap_or_default();
+        writeln!(template, "{patchname:name_width$} # {subject}")?;
+    }
+    template.push_str(EDIT_NAMES_HELP_LINES);
+
+    let filename = ".stgit-uncommit-names.txt";
+    std::fs::write(filename, template)?;
+    let buf = patchedit::call_editor(filename, &stack.repo.config_snapshot())?;
+    let buf = buf
+        .to_str()
+        .map_err(|_| anyhow!("`{filename}` is not valid UTF-8"))?;
+
+    let mut edited_patchnames: Vec<PatchName> = Vec::with_capacity(patchnames.len());
+    for line in buf.lines() {
+        let patchname_str = line.split_once('#').map_or(line, |(s, _)| s).trim();
+        if patchname_str.is_empty() {
+            continue;
+        }
+        edited_patchnames.push(PatchName::from_str(patchname_str)?);
+    }
+
+    if edited_patchnames.is_empty() {
+        Err(anyhow!("no patch names listed; uncommit aborted"))
+    } else if edited_patchnames.len() != patchnames.len() {
+        Err(anyhow!(
+            "expected {} patch names, found {}",
+            patchnames.len(),
+            edited_patchnames.len()
+        ))
+    } else {
+        edited_patchnames.reverse();
+        Ok(edited_patchnames)
+    }
+}
@@ -4,14 +4,17 @@
 
 use std::rc::Rc;
 
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, Context, Result};
 use bstr::{BStr, ByteSlice};
 use clap::Arg;
 
 use crate::{
+    argset,
     color::get_color_stdout,
     ext::RepositoryExtended,
+    print_info_message,
     stack::{InitializationPolicy, Stack, StackAccess, StackState},
+    stupid::Stupid,
 };
 
 pub(super) const STGIT_COMMAND: super::StGitCommand = super::StGitCommand {
@@ -26,8 +29,17 @@ fn make() -> clap::Command {
         .about("Undo the last command")
         .long_about(
             "Reset the patch stack to the state before the last operation. \
-             Consecutive undos will go back to yet older stack states.",
+             Consecutive undos will go back to yet older stack states.\n\
+             \n\
+             The '--list' option lists the stack states that may be undone to, \
+             numbered by the number of operations to undo, along with the time and \
+             description of the operation that produced each state. The '--to' \
+             option resets the stack to the state with the given number.",
         )
+        .override_usage(super::make_usage(
+            "stg undo",
+            &["[OPTIONS]", "[OPTIONS] --to <n>", "--list"],
+        ))
         .arg(
             Arg::new("number")
                 .long("number")
@@ -46,18 +58,55 @@ fn make() -> clap::Command {
                         })
                 }),
         )
+        .arg(
+            Arg::new("to")
+                .long("to")
+                .help("Undo to the state numbered <n> by --list")
+                .value_name("n")
+                .value_parser(|s: &str| {
+                    argset::parse_usize(s).and_then(|n| {
+                        if n >= 1 {
+                            Ok(n as isize)
+                        } else {
+                            Err(anyhow!("Bad state number"))
+                        }
+                    })
+                })
+                .conflicts_with("number"),
+        )
+        .arg(
+            Arg::new("list")
+                .long("list")
+                .help("List the states that may be undone to")
+                .action(clap::ArgAction::SetTrue)
+                .conflicts_with_all(["number", "to", "hard"]),
+        )
         .arg(
             Arg::new("hard")
                 .long("hard")
                 .help("Discard changes in the index and worktree")
                 .action(clap::ArgAction::SetTrue),
         )
+        .arg(argset::stash_discarded_arg())
 }
 
 fn run(matches: &clap::ArgMatches) -> Result<()> {
     let repo = gix::Repository::open()?;
     let stack = Stack::current(&repo, InitializationPolicy::RequireInitialized)?;
-    let undo_steps = matches.get_one::<isize>("number").copied().unwrap_or(1);
+
+    if matches.get_flag("list") {
+        return print_undo_states(&stack, false);
+    }
+
+    let undo_steps = matches
+        .get_one::<isize>("number")
+        .or_else(|| matches.get_one::<isize>("to"))
+        .copied()
+        .unwrap_or(1);
+
+    if matches.get_flag("stash-discarded") {
+        stash_discarded_changes(&repo, matches, "undo --hard")?;
+    }
 
     stack
         .setup_transaction()
@@ -74,6 +123,24 @@ fn run(matches: &clap::ArgMatches) -> Result<()> {
     Ok(())
 }
 
+/// Save the index and worktree changes about to be discarded by `--hard` in the stash.
+pub(super) fn stash_discarded_changes(
+    repo: &gix::Repository,
+    matches: &clap::ArgMatches,
+    command: &str,
+) -> Result<()> {
+    let message = format!("stg {command}: discarded changes");
+    if repo
+        .stupid()
+        .stash_store_changes(&message)
+        .context("stashing discarded changes")?
+        .is_some()
+    {
+        print_info_message(matches, "Discarded changes saved in `stash@{0}`");
+    }
+    Ok(())
+}
+
 pub(super) fn find_undo_state<'repo>(
     stack: &Stack<'repo>,
     undo_steps: isize,
@@ -115,6 +182,64 @@ pub(super) fn find_undo_state<'repo>(
     }
 }
 
+/// Find the stack states that may be undone or redone to.
+///
+/// Each state is paired with the number of steps to undo, or redo if `redo` is true,
+/// to reach the state. This matches the states found by [`find_undo_state()`].
+fn list_undo_states(stack: &Stack, redo: bool) -> Result<Vec<(usize, gix::ObjectId)>> {
+    let mut states = Vec::new();
+    let mut steps: isize = 0;
+    let mut max_steps: isize = 0;
+    let mut state_commit = stack
+        .repo
+        .find_reference(stack.get_stack_refname())?
+        .into_fully_peeled_id()?
+        .object()?
+        .peel_tags_to_end()?
+        .try_into_commit()?;
+    loop {
+        if steps > max_steps {
+            max_steps = steps;
+            states.push((steps as usize, state_commit.id));
+        }
+        let urstate = parse_undo_redo_message(state_commit.message_raw()?);
+        if redo {
+            match urstate {
+                Some(URState::Undo(_)) => steps += 1,
+                Some(URState::Redo(n)) => steps -= n,
+                None => break,
+            }
+        } else if let Some(URState::Undo(n)) = urstate {
+            steps -= n;
+        } else {
+            steps += 1;
+        }
+        let state = StackState::from_commit(stack.repo, &state_commit)?;
+        if let Some(prev) = state.prev {
+            state_commit = stack.repo.find_commit(prev.id)?;
+        } else {
+            break;
+        }
+    }
+    Ok(states)
+}
+
+/// Print the stack states that may be undone or redone to.
+pub(super) fn print_undo_states(stack: &Stack, redo: bool) -> Result<()> {
+    for (number, state_commit_id) in list_undo_states(stack, redo)? {
+        let state_commit = stack.repo.find_commit(state_commit_id)?;
+        let message = state_commit.message_raw()?;
+        let summary = message.lines().next().unwrap_or_default().to_str_lossy();
+        println!(
+            "{number}  {}  {summary}",
+            state_commit
+                .time()?
+                .format(gix::date::time::format::ISO8601),
+        );
+    }
+    Ok(())
+}
+
 #[derive(Debug)]
 enum URState {
     Undo(isize),
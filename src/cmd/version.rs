@@ -32,7 +32,7 @@ fn run(matches: &ArgMatches) -> Result<()> {
         .and_then(|rev_hash| Some(format!(" ({rev_hash})")))
         .unwrap_or_default();
     if matches.get_flag("short") {
-        let bin_name = env!("CARGO_BIN_NAME");
+        let bin_name = "stg";
         println!("{bin_name} {pkg_version}{hash_suffix}");
     } else {
         let license_id = env!("CARGO_PKG_LICENSE");
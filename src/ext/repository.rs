@@ -90,6 +90,24 @@ pub(crate) trait RepositoryExtended {
 
     /// [`gix::Repository::rev_parse_single()`] with StGit-specific error mapping.
     fn rev_parse_single_ex(&self, spec: &str) -> Result<gix::Id<'_>>;
+
+    /// Get the committer signature for a commit that rewrites `original`.
+    ///
+    /// With `committer_date_is_author_date`, the committer date is `author_time`.
+    /// Otherwise, the committer date of the original commit is kept when
+    /// `stgit.keepcommitterdate` is true. The current time is used by default.
+    fn get_rewrite_committer(
+        &self,
+        original: Option<&gix::Commit<'_>>,
+        author_time: gix::date::Time,
+        committer_date_is_author_date: bool,
+    ) -> Result<gix::actor::Signature>;
+
+    /// Get the mailmap for mapping author identities to canonical identities.
+    ///
+    /// As with git-log(1), the mailmap is used unless `log.mailmap` is set to false,
+    /// in which case an empty mailmap is returned.
+    fn mailmap(&self) -> gix::mailmap::Snapshot;
 }
 
 /// Options for creating a git commit object.
@@ -350,4 +368,35 @@ impl RepositoryExtended for gix::Repository {
                 }
             })
     }
+
+    fn get_rewrite_committer(
+        &self,
+        original: Option<&gix::Commit<'_>>,
+        author_time: gix::date::Time,
+        committer_date_is_author_date: bool,
+    ) -> Result<gix::actor::Signature> {
+        let mut committer = self.get_committer()?.to_owned();
+        if committer_date_is_author_date {
+            committer.time = author_time;
+        } else if let Some(original) = original.filter(|_| {
+            self.config_snapshot()
+                .boolean("stgit.keepcommitterdate")
+                .unwrap_or(false)
+        }) {
+            committer.time = original.committer()?.time;
+        }
+        Ok(committer)
+    }
+
+    fn mailmap(&self) -> gix::mailmap::Snapshot {
+        if self
+            .config_snapshot()
+            .boolean("log.mailmap")
+            .unwrap_or(true)
+        {
+            self.open_mailmap()
+        } else {
+            gix::mailmap::Snapshot::default()
+        }
+    }
 }
@@ -0,0 +1,169 @@
+// SPDX-License-Identifier: GPL-2.0-only
+
+//! File operations that tolerate the quirks of network file systems.
+//!
+//! On NFS and SMB mounts, file operations may transiently fail, e.g. with a stale
+//! file handle after another process, such as an editor, replaced the file by
+//! renaming over it. Data written to a file may also not be visible to other clients
+//! until it is flushed to the server. The functions in this module write files
+//! atomically, syncing their content before renaming them into place, and retry
+//! operations that fail with such transient errors.
+
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use anyhow::{Context, Result};
+
+/// Number of attempts made for an operation failing with transient errors.
+const ATTEMPTS: u32 = 5;
+
+/// Delay before the first retry, doubled for each subsequent retry.
+const INITIAL_DELAY: Duration = Duration::from_millis(20);
+
+/// Atomically write `contents` to the file at `path`.
+///
+/// The contents are written and synced to a temporary file in the same directory,
+/// which then replaces any existing file at `path`.
+pub(crate) fn write_file(path: &Path, contents: impl AsRef<[u8]>) -> Result<()> {
+    let contents = contents.as_ref();
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+    retry(|| {
+        let mut temp_file = tempfile::Builder::new()
+            .prefix(".stg-tmp-")
+            .tempfile_in(&dir)?;
+        temp_file.write_all(contents)?;
+        temp_file.as_file().sync_all()?;
+        temp_file.persist(path).map_err(|e| e.error)?;
+        Ok(())
+    })
+    .with_context(|| format!("writing `{}`", path.display()))?;
+    sync_dir(&dir);
+    Ok(())
+}
+
+/// Read the contents of the file at `path`.
+pub(crate) fn read_file(path: &Path) -> Result<Vec<u8>> {
+    retry(|| std::fs::read(path)).with_context(|| format!("reading `{}`", path.display()))
+}
+
+/// Create a new, empty file at `path`, failing if the file already exists.
+pub(crate) fn create_new_file(path: &Path) -> Result<()> {
+    retry(|| {
+        std::fs::OpenOptions::new()
+            .create_new(true)
+            .write(true)
+            .open(path)
+            .map(drop)
+    })
+    .with_context(|| format!("creating `{}`", path.display()))
+}
+
+/// Remove the file at `path`.
+///
+/// It is not an error for the file to not exist.
+pub(crate) fn remove_file(path: &Path) -> Result<()> {
+    retry(|| match std::fs::remove_file(path) {
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        result => result,
+    })
+    .with_context(|| format!("removing `{}`", path.display()))
+}
+
+/// Run `op`, retrying it with increasing delays while it fails with transient errors.
+fn retry<T>(mut op: impl FnMut() -> std::io::Result<T>) -> std::io::Result<T> {
+    let mut delay = INITIAL_DELAY;
+    let mut attempt = 1;
+    loop {
+        match op() {
+            Err(e) if attempt < ATTEMPTS && is_transient(&e) => {
+                std::thread::sleep(delay);
+                delay *= 2;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Determine whether an error may be resolved by retrying the operation.
+fn is_transient(e: &std::io::Error) -> bool {
+    use std::io::ErrorKind;
+
+    if matches!(
+        e.kind(),
+        ErrorKind::Interrupted | ErrorKind::WouldBlock | ErrorKind::TimedOut
+    ) {
+        return true;
+    }
+
+    // Stale file handle (ESTALE) on unix and sharing or lock violations on Windows.
+    let transient_codes: &[i32] = if cfg!(target_os = "linux") {
+        &[116]
+    } else if cfg!(target_os = "windows") {
+        &[32, 33]
+    } else if cfg!(unix) {
+        &[70]
+    } else {
+        &[]
+    };
+    e.raw_os_error()
+        .map_or(false, |code| transient_codes.contains(&code))
+}
+
+/// Sync a directory such that a file renamed into it is durable.
+///
+/// Failures are ignored since not all platforms and file systems support syncing
+/// directories.
+fn sync_dir(dir: &Path) {
+    if cfg!(unix) {
+        if let Ok(dir) = std::fs::File::open(dir) {
+            dir.sync_all().ok();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn write_read_remove() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("file.txt");
+        write_file(&path, "first").unwrap();
+        write_file(&path, "second").unwrap();
+        assert_eq!(read_file(&path).unwrap(), b"second");
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+        remove_file(&path).unwrap();
+        remove_file(&path).unwrap();
+        assert!(read_file(&path).is_err());
+    }
+
+    #[test]
+    fn retry_transient() {
+        let mut failures = 2;
+        let result = retry(|| {
+            if failures > 0 {
+                failures -= 1;
+                Err(std::io::Error::from(std::io::ErrorKind::Interrupted))
+            } else {
+                Ok(42)
+            }
+        });
+        assert_eq!(result.unwrap(), 42);
+
+        let mut attempts = 0;
+        let result: std::io::Result<()> = retry(|| {
+            attempts += 1;
+            Err(std::io::Error::from(std::io::ErrorKind::NotFound))
+        });
+        assert!(result.is_err());
+        assert_eq!(attempts, 1);
+    }
+}
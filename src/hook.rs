@@ -11,11 +11,17 @@ use std::{
 use anyhow::{anyhow, Context, Result};
 use bstr::BString;
 
-use crate::wrap::Message;
+use crate::{timing, wrap::Message};
 
 /// Find path to hook script given a hook name.
 ///
-/// Returns None if the hook script is not found or is not executable.
+/// As with git, hooks are found in the directory given by `core.hooksPath`, which may
+/// be set per-worktree, or otherwise in the `hooks` directory of the repository's
+/// common git directory, which is shared by all worktrees.
+///
+/// Returns None if the hook script is not found or is not executable. A hint is
+/// printed for a hook that exists but is not executable, unless disabled with the
+/// `advice.ignoredHook` config variable.
 fn get_hook_path(repo: &gix::Repository, hook_name: &str) -> Result<Option<PathBuf>> {
     let config = repo.config_snapshot();
     let hooks_path =
@@ -42,6 +48,14 @@ fn get_hook_path(repo: &gix::Repository, hook_name: &str) -> Result<Option<PathB
     };
 
     if !is_executable(&hook_meta) {
+        if config.boolean("advice.ignoredHook").unwrap_or(true) {
+            eprintln!(
+                "hint: The '{}' hook was ignored because it's not set as executable.\n\
+                 hint: You can disable this warning with \
+                 `git config advice.ignoredHook false`.",
+                hook_path.display()
+            );
+        }
         return Ok(None);
     }
 
@@ -50,6 +64,23 @@ fn get_hook_path(repo: &gix::Repository, hook_name: &str) -> Result<Option<PathB
     Ok(Some(hook_path))
 }
 
+/// Run a hook command to completion, recording the time it takes.
+fn run_hook_command(
+    hook_name: &str,
+    hook_command: &mut std::process::Command,
+) -> Result<std::process::ExitStatus> {
+    let start = std::time::Instant::now();
+    let status = hook_command
+        .status()
+        .with_context(|| format!("`{hook_name}` hook"));
+    timing::record(
+        timing::Category::Hook,
+        format!("hook {hook_name}"),
+        start.elapsed(),
+    );
+    status
+}
+
 /// Run the git `pre-commit` hook script.
 ///
 /// The `use_editor` flag determines whether the hook should be allowed to invoke an
@@ -74,12 +105,10 @@ pub(crate) fn run_pre_commit_hook(repo: &gix::Repository, use_editor: bool) -> R
         hook_command.env("GIT_EDITOR", ":");
     }
 
-    let mut hook_command = make_sh_command_on_windows(hook_command);
+    let mut hook_command = make_sh_command_on_windows(hook_command)?;
 
-    let status = hook_command
-        .stdin(std::process::Stdio::null())
-        .status()
-        .with_context(|| format!("`{hook_name}` hook"))?;
+    hook_command.stdin(std::process::Stdio::null());
+    let status = run_hook_command(hook_name, &mut hook_command)?;
 
     if status.success() {
         Ok(true)
@@ -129,11 +158,9 @@ pub(crate) fn run_commit_msg_hook<'repo>(
 
     hook_command.arg(temp_msg.filename());
 
-    let mut hook_command = make_sh_command_on_windows(hook_command);
+    let mut hook_command = make_sh_command_on_windows(hook_command)?;
 
-    let status = hook_command
-        .status()
-        .with_context(|| format!("`{hook_name}` hook"))?;
+    let status = run_hook_command(hook_name, &mut hook_command)?;
 
     if status.success() {
         let message_bytes = temp_msg.read()?;
@@ -153,6 +180,66 @@ pub(crate) fn run_commit_msg_hook<'repo>(
     }
 }
 
+/// Kind of stack transaction hook.
+#[derive(Clone, Copy)]
+pub(crate) enum StackTransactionHook {
+    /// Hook run before the stack state reference is updated. The transaction is
+    /// aborted if the hook fails.
+    Pre,
+
+    /// Hook run after the stack state reference is updated. The hook's exit status is
+    /// ignored.
+    Post,
+}
+
+/// Run the `pre-stack-transaction` or `post-stack-transaction` hook script.
+///
+/// The hook is given the branch name, the operation name, and the old and new stack
+/// state commit ids via the `STGIT_BRANCH`, `STGIT_OPERATION`, `STGIT_OLD_STATE`, and
+/// `STGIT_NEW_STATE` environment variables.
+///
+/// Returns successfully if the hook script does not exist, is not a file, or is not
+/// executable.
+pub(crate) fn run_stack_transaction_hook(
+    repo: &gix::Repository,
+    kind: StackTransactionHook,
+    branch_name: &str,
+    operation: &str,
+    old_state_id: gix::ObjectId,
+    new_state_id: gix::ObjectId,
+) -> Result<()> {
+    let hook_name = match kind {
+        StackTransactionHook::Pre => "pre-stack-transaction",
+        StackTransactionHook::Post => "post-stack-transaction",
+    };
+    let hook_path = if let Some(hook_path) = get_hook_path(repo, hook_name)? {
+        hook_path
+    } else {
+        return Ok(());
+    };
+
+    let mut hook_command = std::process::Command::new(hook_path);
+    hook_command.current_dir(repo.work_dir().unwrap_or_else(|| repo.git_dir()));
+    hook_command.env("STGIT_BRANCH", branch_name);
+    hook_command.env("STGIT_OPERATION", operation);
+    hook_command.env("STGIT_OLD_STATE", old_state_id.to_string());
+    hook_command.env("STGIT_NEW_STATE", new_state_id.to_string());
+
+    let mut hook_command = make_sh_command_on_windows(hook_command)?;
+
+    hook_command.stdin(std::process::Stdio::null());
+    let status = run_hook_command(hook_name, &mut hook_command)?;
+
+    if status.success() || matches!(kind, StackTransactionHook::Post) {
+        Ok(())
+    } else {
+        Err(anyhow!(
+            "`{hook_name}` hook returned {}",
+            status.code().unwrap_or(-1)
+        ))
+    }
+}
+
 /// Temporary commit message file for commit-msg hook.
 ///
 /// The temporary file is created relative to the work dir using the StGit process id to
@@ -212,16 +299,18 @@ fn is_executable(_meta: &std::fs::Metadata) -> bool {
 }
 
 #[cfg(not(windows))]
-fn make_sh_command_on_windows(command: std::process::Command) -> std::process::Command {
-    command
+fn make_sh_command_on_windows(command: std::process::Command) -> Result<std::process::Command> {
+    Ok(command)
 }
 
 #[cfg(windows)]
-fn make_sh_command_on_windows(command: std::process::Command) -> std::process::Command {
-    let hook_path = command
-        .get_program()
-        .to_str()
-        .expect("path to hook is valid UTF-8");
+fn make_sh_command_on_windows(command: std::process::Command) -> Result<std::process::Command> {
+    let hook_path = command.get_program().to_str().ok_or_else(|| {
+        anyhow!(
+            "hook path `{}` is not valid Unicode",
+            Path::new(command.get_program()).display()
+        )
+    })?;
     assert!(!hook_path.contains('"'));
     assert!(!hook_path.contains('\''));
 
@@ -230,7 +319,12 @@ fn make_sh_command_on_windows(command: std::process::Command) -> std::process::C
     command_str.push_str(hook_path);
     command_str.push('"');
     for arg in command.get_args() {
-        let arg = arg.to_str().expect("hook args are valid UTF-8");
+        let arg = arg.to_str().ok_or_else(|| {
+            anyhow!(
+                "hook argument `{}` is not valid Unicode",
+                Path::new(arg).display()
+            )
+        })?;
         assert!(!arg.contains('"'));
         assert!(!arg.contains('\''));
         command_str.push(' ');
@@ -255,5 +349,5 @@ fn make_sh_command_on_windows(command: std::process::Command) -> std::process::C
         sh_command.current_dir(cur_dir);
     }
 
-    sh_command
+    Ok(sh_command)
 }
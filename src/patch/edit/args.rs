@@ -7,7 +7,7 @@ use clap::{
     Arg, ValueHint,
 };
 
-use super::parse::{parse_email, parse_name, parse_name_email2};
+use super::parse::{parse_email, parse_name, parse_name_email2, parse_trailer};
 use crate::{argset, ext::TimeExtended};
 
 /// Add patch editing options to a StGit command.
@@ -29,7 +29,14 @@ pub(crate) fn add_args(
             Arg::new("diff")
                 .long("diff")
                 .short('d')
+                .visible_alias("verbose")
+                .visible_short_alias('v')
                 .help("Show diff when editing patch description")
+                .long_help(
+                    "Show the patch's diff below a scissors line when editing the \
+                     patch description, similar to `git commit --verbose`. See also \
+                     the `stgit.edit.verbose` configuration variable.",
+                )
                 .action(clap::ArgAction::SetTrue),
         );
     let command = if add_message_opts {
@@ -110,6 +117,8 @@ pub(crate) fn add_args(
                     "Add \"Signed-off-by\" message trailer.\n\
                      \n\
                      The value is optional and defaults to the committer name and email. \
+                     An explicit identity may be given, e.g. \
+                     `--signoff=\"Jane Doe <jane@example.com>\"`. \
                      This option may be provided multiple times.",
                 )
                 .value_name("value")
@@ -126,6 +135,8 @@ pub(crate) fn add_args(
                     "Add \"Acked-by\" message trailer.\n\
                      \n\
                      The value is optional and defaults to the committer's name and email. \
+                     An explicit identity may be given, e.g. \
+                     `--ack=\"Jane Doe <jane@example.com>\"`. \
                      This option may be provided multiple times.",
                 )
                 .value_name("value")
@@ -142,6 +153,8 @@ pub(crate) fn add_args(
                     "Add \"Reviewed-by\" message trailer.\n\
                      \n\
                      The value is optional and defaults to the committer's name and email. \
+                     An explicit identity may be given, e.g. \
+                     `--review=\"Jane Doe <jane@example.com>\"`. \
                      This option may be provided multiple times.",
                 )
                 .value_name("value")
@@ -150,6 +163,27 @@ pub(crate) fn add_args(
                 .require_equals(true)
                 .action(clap::ArgAction::Append),
         )
+        .arg(
+            Arg::new("trailer")
+                .long("trailer")
+                .help("Add \"<key>: <value>\" message trailer")
+                .long_help(
+                    "Add an arbitrary message trailer, e.g. \"Fixes: <commit>\" or \
+                     \"Link: <url>\". The key and value may be separated by either ':' \
+                     or '='.\n\
+                     \n\
+                     The trailer is added with git-interpret-trailers(1), thus the \
+                     `trailer.*` configuration variables, e.g. `trailer.where` or \
+                     `trailer.ifExists`, determine the trailer's placement and how \
+                     duplicate trailers are handled. This option may be provided \
+                     multiple times.",
+                )
+                .value_name("key: value")
+                .num_args(1)
+                .action(clap::ArgAction::Append)
+                .value_parser(ValueParser::new(parse_trailer))
+                .value_hint(ValueHint::Other),
+        )
         .arg(
             Arg::new("sign-by")
                 .long("sign-by")
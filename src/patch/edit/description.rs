@@ -59,7 +59,7 @@ pub(super) struct EditablePatchDescription {
     pub diff: Option<DiffBuffer>,
 }
 
-const CUT_LINE: &str = "# ------------------------ >8 ------------------------\n";
+pub(super) const CUT_LINE: &str = "# ------------------------ >8 ------------------------\n";
 
 impl EditablePatchDescription {
     /// Write user-editable patch description to the provided stream.
@@ -152,6 +152,11 @@ impl TryFrom<&[u8]> for EditedPatchDescription {
     /// lines of the message. This rigidity is done to allow the message, which follows
     /// these headers, to potentially contain strings such as "Patch:".
     ///
+    /// Any further `Key: value` lines directly following the "Date" header, such as
+    /// "Message-Id" or "Change-Id", are not understood by StGit, but are preserved by
+    /// appending them to the message's trailers, unless the message already contains
+    /// the same line.
+    ///
     /// If all headers are absent and the trimmed message is empty, an error is
     /// returned. Blanking-out the headers and message is thus a mechanism for the user
     /// to abort the interactive edit.
@@ -163,6 +168,7 @@ impl TryFrom<&[u8]> for EditedPatchDescription {
         let mut consuming_message: bool = false;
         let mut consecutive_empty: usize = 0;
         let mut message = String::new();
+        let mut extra_headers: Vec<String> = Vec::new();
         let mut pos: usize = 0;
 
         for (line_num, line) in buf
@@ -219,6 +225,10 @@ impl TryFrom<&[u8]> for EditedPatchDescription {
                             Some(raw_value.to_string())
                         });
                         continue;
+                    } else if raw_authdate.is_some() && is_header_key(key) && !raw_value.is_empty()
+                    {
+                        extra_headers.push(format!("{key}: {raw_value}"));
+                        continue;
                     }
                 }
 
@@ -279,6 +289,8 @@ impl TryFrom<&[u8]> for EditedPatchDescription {
             message.clear();
         }
 
+        append_extra_headers(&mut message, &extra_headers);
+
         let diff = if consume_diff {
             // Skip any comment lines after the cut line.
             for line in buf[pos..].split_inclusive(|&b| b == b'\n') {
@@ -308,6 +320,40 @@ impl TryFrom<&[u8]> for EditedPatchDescription {
     }
 }
 
+/// Determine whether a header key is a plausible header or trailer token.
+fn is_header_key(key: &str) -> bool {
+    !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+}
+
+/// Append extra header lines from the description's header block to the message.
+///
+/// The headers are added to the message's trailer block, which is started if the
+/// message's last paragraph does not already consist of trailers.
+fn append_extra_headers(message: &mut String, headers: &[String]) {
+    let headers: Vec<&String> = headers
+        .iter()
+        .filter(|header| !message.lines().any(|line| line == header.as_str()))
+        .collect();
+    if headers.is_empty() {
+        return;
+    }
+    let trimmed = message.trim_end();
+    let has_trailer_block = trimmed.rsplit_once("\n\n").map_or(false, |(_, last)| {
+        last.lines().all(|line| {
+            line.split_once(':').map_or(false, |(key, value)| {
+                is_header_key(key) && !value.trim().is_empty()
+            })
+        })
+    });
+    if !message.is_empty() && !has_trailer_block {
+        message.push('\n');
+    }
+    for header in headers {
+        message.push_str(header);
+        message.push('\n');
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use bstr::ByteSlice;
@@ -716,6 +762,60 @@ mod tests {
         compare_edited_descs(&expected, &edited_desc);
     }
 
+    #[test]
+    fn extra_headers_after_date() {
+        let description = b"\
+        Patch:  patch\n\
+        Author: The Author <author@example.com>\n\
+        Date:   2001-04-19 10:25:21 +0600\n\
+        Message-Id: <1234@example.com>\n\
+        X-Custom:   value\n\
+        \n\
+        Subject\n\
+        \n\
+        Body\n\
+        \n\
+        # Instruction\n";
+
+        let edited_desc = EditedPatchDescription::try_from(description.as_slice()).unwrap();
+
+        assert_eq!(
+            edited_desc.message,
+            "Subject\n\
+             \n\
+             Body\n\
+             \n\
+             Message-Id: <1234@example.com>\n\
+             X-Custom: value\n"
+        );
+    }
+
+    #[test]
+    fn extra_headers_join_existing_trailers() {
+        let description = b"\
+        Patch:  patch\n\
+        Author: The Author <author@example.com>\n\
+        Date:   2001-04-19 10:25:21 +0600\n\
+        Change-Id: I1234\n\
+        X-Custom: value\n\
+        \n\
+        Subject\n\
+        \n\
+        Change-Id: I1234\n\
+        Signed-off-by: The Author <author@example.com>\n";
+
+        let edited_desc = EditedPatchDescription::try_from(description.as_slice()).unwrap();
+
+        assert_eq!(
+            edited_desc.message,
+            "Subject\n\
+             \n\
+             Change-Id: I1234\n\
+             Signed-off-by: The Author <author@example.com>\n\
+             X-Custom: value\n"
+        );
+    }
+
     #[test]
     fn invalid_date() {
         let description = b"\
@@ -2,17 +2,12 @@
 
 //! Functions for conducting interactive patch edit session.
 
-use std::{
-    ffi::OsString,
-    fs::File,
-    io::{BufWriter, Write},
-    path::Path,
-};
+use std::{ffi::OsString, io::Write, path::Path};
 
 use anyhow::{anyhow, Result};
 use bstr::BString;
 
-use super::description::{EditablePatchDescription, EditedPatchDescription};
+use super::description::{EditablePatchDescription, EditedPatchDescription, CUT_LINE};
 
 pub(crate) static EDIT_INSTRUCTION: &str = "\
     # Please enter the message for your patch. Lines starting with\n\
@@ -30,12 +25,62 @@ static EDIT_FILE_NAME: &str = ".stgit-edit.txt";
 /// Default file name for interactively editable patch description with diff.
 static EDIT_FILE_NAME_DIFF: &str = ".stgit-edit.patch";
 
+/// File name for the edit session information used by editor integrations.
+static EDIT_INFO_FILE_NAME: &str = ".stgit-edit.json";
+
+/// Version of the edit session information format.
+const EDIT_INFO_VERSION: u32 = 1;
+
+/// Information about an interactive patch edit session.
+///
+/// This information is written as JSON to a companion file alongside the edit buffer
+/// so that editor plugins may, for example, validate the diff or complete trailers.
+/// The path to the companion file is provided to the editor in the `STGIT_EDIT_INFO`
+/// environment variable.
+#[derive(serde::Serialize)]
+pub(super) struct EditSessionInfo {
+    /// Name of the branch being edited, if any.
+    pub branch: Option<String>,
+
+    /// Original name of the patch being edited, if any.
+    pub patch: Option<String>,
+
+    /// Commit id of the patch's parent.
+    pub base: String,
+
+    /// Paths modified by the patch.
+    pub paths: Vec<String>,
+
+    /// Trailer keys that may be offered for completion.
+    pub trailers: Vec<String>,
+}
+
+/// Complete edit session information file contents.
+#[derive(serde::Serialize)]
+struct EditInfoFile<'a> {
+    version: u32,
+    file: &'a str,
+    #[serde(flatten)]
+    session: &'a EditSessionInfo,
+    diff: Option<&'static str>,
+    markers: EditMarkers,
+}
+
+/// Markers used in the edit buffer.
+#[derive(serde::Serialize)]
+struct EditMarkers {
+    comment: &'static str,
+    headers: [&'static str; 3],
+    cut_line: &'static str,
+}
+
 /// Conduct interactive patch edit session.
 ///
 /// The patch description is written to a file, the user's editor of choice is invoked,
 /// and the modified description is read-back and parsed.
 pub(super) fn edit_interactive(
     patch_desc: &EditablePatchDescription,
+    session_info: &EditSessionInfo,
     config: &gix::config::Snapshot,
 ) -> Result<EditedPatchDescription> {
     let filename = if patch_desc.diff.is_some() {
@@ -45,12 +90,37 @@ pub(super) fn edit_interactive(
     };
 
     {
-        let file = File::create(filename)?;
-        let mut stream = BufWriter::new(file);
-        patch_desc.write(&mut stream)?;
+        let mut buf = Vec::new();
+        patch_desc.write(&mut buf)?;
+        crate::fsutil::write_file(Path::new(filename), buf)?;
     }
 
-    let buf = call_editor(filename, config)?;
+    let info_file = EditInfoFile {
+        version: EDIT_INFO_VERSION,
+        file: filename,
+        session: session_info,
+        diff: patch_desc.diff.as_ref().map(|_| {
+            if patch_desc.diff_instruction == Some(EDIT_INSTRUCTION_EDITABLE_DIFF) {
+                "editable"
+            } else {
+                "read-only"
+            }
+        }),
+        markers: EditMarkers {
+            comment: "#",
+            headers: ["Patch", "Author", "Date"],
+            cut_line: CUT_LINE.trim_end(),
+        },
+    };
+    let mut info = serde_json::to_vec_pretty(&info_file)?;
+    info.push(b'\n');
+    crate::fsutil::write_file(Path::new(EDIT_INFO_FILE_NAME), info)?;
+    let info_path = std::env::current_dir()?.join(EDIT_INFO_FILE_NAME);
+
+    let result = call_editor_with_info(filename, Some(&info_path), config);
+    // The companion file is of no use once the editor exits.
+    crate::fsutil::remove_file(Path::new(EDIT_INFO_FILE_NAME)).ok();
+    let buf = result?;
     let edited_desc = EditedPatchDescription::try_from(buf.as_slice())?;
     Ok(edited_desc)
 }
@@ -72,6 +142,18 @@ fn is_terminal_dumb() -> bool {
 pub(crate) fn call_editor<P: AsRef<Path>>(
     path: P,
     config: &gix::config::Snapshot,
+) -> Result<BString> {
+    call_editor_with_info(path, None, config)
+}
+
+/// Run the user's editor, optionally pointing it at an edit session information file.
+///
+/// When provided, the path to the information file is exported to the editor in the
+/// `STGIT_EDIT_INFO` environment variable.
+fn call_editor_with_info<P: AsRef<Path>>(
+    path: P,
+    info_path: Option<&Path>,
+    config: &gix::config::Snapshot,
 ) -> Result<BString> {
     let editor = get_editor(config)?;
 
@@ -99,10 +181,12 @@ pub(crate) fn call_editor<P: AsRef<Path>>(
         } else {
             "/bin/sh"
         };
-        let mut child = std::process::Command::new(shell)
-            .arg("-c")
-            .arg(subcommand)
-            .spawn()?;
+        let mut command = std::process::Command::new(shell);
+        command.arg("-c").arg(subcommand);
+        if let Some(info_path) = info_path {
+            command.env("STGIT_EDIT_INFO", info_path);
+        }
+        let mut child = command.spawn()?;
 
         let status = child.wait()?;
 
@@ -120,8 +204,8 @@ pub(crate) fn call_editor<P: AsRef<Path>>(
         }
     }
 
-    let buf = std::fs::read(&path)?.into();
-    std::fs::remove_file(&path)?;
+    let buf = crate::fsutil::read_file(path.as_ref())?.into();
+    crate::fsutil::remove_file(path.as_ref())?;
     Ok(buf)
 }
 
@@ -16,17 +16,19 @@ use std::{
     ffi::OsString,
     fs::File,
     io::{BufWriter, Read},
-    path::PathBuf,
+    path::{Path, PathBuf},
 };
 
 use anyhow::{anyhow, Result};
 use bstr::{BString, ByteSlice};
 use clap::ArgMatches;
 
-pub(crate) use self::{args::add_args, interactive::call_editor, parse::parse_name_email};
+pub(crate) use self::{
+    args::add_args, interactive::call_editor, parse::parse_name_email, trailers::add_trailers,
+};
 use self::{
     description::{DiffBuffer, EditablePatchDescription, EditedPatchDescription},
-    interactive::edit_interactive,
+    interactive::{edit_interactive, EditSessionInfo},
 };
 use super::PatchName;
 use crate::{
@@ -293,6 +295,7 @@ impl<'a, 'repo> EditBuilder<'a, 'repo> {
                     "sign-by",
                     "ack-by",
                     "review-by",
+                    "trailer",
                     "author",
                     "authname",
                     "authemail",
@@ -372,7 +375,12 @@ impl<'a, 'repo> EditBuilder<'a, 'repo> {
         let (diff, computed_diff) = if file_diff.is_some() {
             (file_diff, None)
         } else if need_interactive_edit
-            && (matches.get_flag("diff") || config.boolean("stgit.edit.verbose").unwrap_or(false))
+            && (matches.get_flag("diff")
+                || config
+                    .boolean("stgit.edit.verbose")
+                    .or_else(|| config.integer("commit.verbose").map(|n| n > 0))
+                    .or_else(|| config.boolean("commit.verbose"))
+                    .unwrap_or(false))
         {
             let old_tree = repo.find_commit(parent_id)?.tree()?;
             let new_tree = repo.find_tree(tree_id)?;
@@ -455,7 +463,11 @@ impl<'a, 'repo> EditBuilder<'a, 'repo> {
                 author: edited_author,
                 message: edited_message,
                 diff: edited_diff,
-            } = edit_interactive(&patch_description, &config)?;
+            } = edit_interactive(
+                &patch_description,
+                &edit_session_info(repo, original_patchname.as_ref(), parent_id, tree_id)?,
+                &config,
+            )?;
 
             let patchname = match edited_patchname {
                 Some(Some(patchname)) => Some(patchname),
@@ -503,7 +515,6 @@ impl<'a, 'repo> EditBuilder<'a, 'repo> {
                 Err(e) => {
                     let diff = Some(DiffBuffer(diff));
                     let failed_description_path = ".stgit-failed.patch";
-                    let mut stream = BufWriter::new(File::create(failed_description_path)?);
                     let failed_patch_description = EditablePatchDescription {
                         patchname,
                         author,
@@ -512,7 +523,9 @@ impl<'a, 'repo> EditBuilder<'a, 'repo> {
                         diff_instruction,
                         diff,
                     };
-                    failed_patch_description.write(&mut stream)?;
+                    let mut buf = Vec::new();
+                    failed_patch_description.write(&mut buf)?;
+                    crate::fsutil::write_file(Path::new(failed_description_path), buf)?;
                     return Err(anyhow!(
                         "edited patch did not apply due to:\n\
                          {e:#};\n\
@@ -542,13 +555,11 @@ impl<'a, 'repo> EditBuilder<'a, 'repo> {
                 .uniquify(&allowed_patchnames, &disallow_patchnames)
         };
 
-        let committer = if matches.get_flag("committer-date-is-author-date") {
-            let mut committer = default_committer.to_owned();
-            committer.time = author.time;
-            committer
-        } else {
-            default_committer.to_owned()
-        };
+        let committer = repo.get_rewrite_committer(
+            patch_commit,
+            author.time,
+            matches.get_flag("committer-date-is-author-date"),
+        )?;
 
         let new_commit_id = if patch_commit.and_then(|commit| commit.decode().ok()).map_or(
             false,
@@ -646,6 +657,66 @@ fn prettify(message: &str) -> String {
     pretty
 }
 
+/// Gather information about an interactive edit session for editor integrations.
+fn edit_session_info(
+    repo: &gix::Repository,
+    patchname: Option<&PatchName>,
+    parent_id: gix::ObjectId,
+    tree_id: gix::ObjectId,
+) -> Result<EditSessionInfo> {
+    let parent_tree_id = repo.find_commit(parent_id)?.tree_id()?.detach();
+    let paths = repo
+        .stupid()
+        .diff_tree_files(parent_tree_id, tree_id)?
+        .iter()
+        .map(|path| path.to_string_lossy().into_owned())
+        .collect();
+
+    let mut trailers: Vec<String> = ["Signed-off-by", "Acked-by", "Reviewed-by"]
+        .iter()
+        .map(|key| key.to_string())
+        .collect();
+    if let Some(sections) = repo
+        .config_snapshot()
+        .plumbing()
+        .sections_by_name("trailer")
+    {
+        for section in sections {
+            let key = section
+                .value("key")
+                .map(|key| {
+                    key.to_str_lossy()
+                        .trim_end()
+                        .trim_end_matches(':')
+                        .to_string()
+                })
+                .or_else(|| {
+                    section
+                        .header()
+                        .subsection_name()
+                        .map(|name| name.to_string())
+                });
+            if let Some(key) = key {
+                if !trailers.contains(&key) {
+                    trailers.push(key);
+                }
+            }
+        }
+    }
+
+    Ok(EditSessionInfo {
+        branch: repo
+            .get_current_branch()
+            .ok()
+            .and_then(|branch| branch.get_branch_partial_name().ok())
+            .map(|name| name.to_string()),
+        patch: patchname.map(ToString::to_string),
+        base: parent_id.to_string(),
+        paths,
+        trailers,
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::prettify;
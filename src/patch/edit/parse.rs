@@ -53,6 +53,23 @@ pub(crate) fn parse_email(email: &str) -> Result<String> {
     Ok(email.to_string())
 }
 
+/// Parse trailer key and value from string.
+///
+/// The incoming string is expected to be of the form `key: value` or `key=value`.
+/// Whitespace around the key and value is trimmed.
+pub(crate) fn parse_trailer(trailer: &str) -> Result<(String, String)> {
+    if let Some(sep_pos) = trailer.find([':', '=']) {
+        let key = trailer[..sep_pos].trim();
+        let value = trailer[sep_pos + 1..].trim();
+        if !key.is_empty() && !value.is_empty() && !key.contains(char::is_whitespace) {
+            return Ok((key.to_string(), value.to_string()));
+        }
+    }
+    Err(anyhow!(
+        "invalid trailer `{trailer}`; expected \"<key>: <value>\""
+    ))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -76,4 +93,20 @@ mod tests {
         assert!(parse_name_email("Hello World (<hello@example.com>)").is_err());
         assert!(parse_name_email("Hello World <<hello@example.com>>").is_err());
     }
+
+    #[test]
+    fn test_parse_trailer() {
+        assert_eq!(
+            parse_trailer("Fixes: abc123 (\"Subject\")").unwrap(),
+            ("Fixes".to_string(), "abc123 (\"Subject\")".to_string())
+        );
+        assert_eq!(
+            parse_trailer("Link=https://example.com/a:b").unwrap(),
+            ("Link".to_string(), "https://example.com/a:b".to_string())
+        );
+        assert!(parse_trailer("Fixes").is_err());
+        assert!(parse_trailer("Fixes: ").is_err());
+        assert!(parse_trailer(": value").is_err());
+        assert!(parse_trailer("Not a key: value").is_err());
+    }
 }
@@ -42,6 +42,15 @@ pub(crate) fn add_trailers<'a, 'b>(
         }
     }
 
+    if let (Some(indices), Some(values)) = (
+        matches.indices_of("trailer"),
+        matches.get_many::<(String, String)>("trailer"),
+    ) {
+        for (index, (key, value)) in indices.zip(values) {
+            trailers.push((index, key, value));
+        }
+    }
+
     if trailers.is_empty() && autosign.is_none() {
         Ok(message)
     } else {
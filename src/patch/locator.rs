@@ -24,6 +24,12 @@ pub(crate) enum Error {
     #[error("patch `{patchname}` does not exist")]
     PatchNotKnown { patchname: PatchName },
 
+    #[error("`{patchname}` is ambiguous: matches {candidates}")]
+    AmbiguousPatchName {
+        patchname: PatchName,
+        candidates: String,
+    },
+
     #[error("invalid patch index `{0}`")]
     InvalidPatchIndex(usize),
 
@@ -377,6 +383,42 @@ impl PatchLocator {
         }
     }
 
+    /// Expand an abbreviated patch name in this locator.
+    ///
+    /// When this locator names a patch that does not exist in the stack, the name is
+    /// matched against the names of the stack's patches, preferring substring matches
+    /// over case-insensitive subsequence matches. If several patches match equally
+    /// well, the user is prompted to choose one when running interactively.
+    ///
+    /// Returns `None` when there is nothing to expand or the abbreviation matches no
+    /// patches, in which case resolving the original locator reports the usual errors.
+    /// An error is returned if several patches match and the user cannot be prompted
+    /// or does not make a valid choice. Only commands that inspect or navigate to
+    /// patches should expand abbreviations; commands that modify patches require
+    /// exact names.
+    pub(crate) fn expand_abbreviation<'repo>(
+        &self,
+        stack: &impl StackStateAccess<'repo>,
+    ) -> Result<Option<PatchLocator>, Error> {
+        let abbreviation = match self.disambiguate(stack).id {
+            DisambiguatedId::Name(pn) if !stack.has_patch(pn) => pn,
+            _ => return Ok(None),
+        };
+        let candidates = abbreviation_matches(abbreviation, stack);
+        let patchname = match candidates.len() {
+            0 => return Ok(None),
+            1 => candidates[0],
+            _ => prompt_for_patchname(&candidates).ok_or_else(|| Error::AmbiguousPatchName {
+                patchname: abbreviation.clone(),
+                candidates: patchnames_string(&candidates).expect("several candidates"),
+            })?,
+        };
+        Ok(Some(PatchLocator {
+            id: PatchId::Name(patchname.clone()),
+            offsets: self.offsets.clone(),
+        }))
+    }
+
     fn disambiguate<'a, 'repo>(
         &'a self,
         stack: &'a impl StackStateAccess<'repo>,
@@ -523,6 +565,64 @@ impl PatchLocator {
     }
 }
 
+/// Get the patch names matching an abbreviated patch name.
+///
+/// Patches whose names contain `patchname` as a substring are preferred, followed by
+/// patches whose names contain the characters of `patchname` in order, ignoring case.
+fn abbreviation_matches<'a, 'repo>(
+    patchname: &PatchName,
+    stack: &'a impl StackStateAccess<'repo>,
+) -> Vec<&'a PatchName> {
+    let needle: &str = patchname.as_ref();
+    let substring_matches: Vec<&PatchName> = stack
+        .all_patches()
+        .filter(|pn| AsRef::<str>::as_ref(pn).contains(needle))
+        .collect();
+    if !substring_matches.is_empty() {
+        return substring_matches;
+    }
+
+    let is_subsequence = |name: &str| {
+        let mut name_chars = name.chars().flat_map(char::to_lowercase);
+        needle
+            .chars()
+            .flat_map(char::to_lowercase)
+            .all(|c| name_chars.any(|name_c| name_c == c))
+    };
+    stack
+        .all_patches()
+        .filter(|pn| is_subsequence(pn.as_ref()))
+        .collect()
+}
+
+/// Prompt the user to choose one of several candidate patch names.
+///
+/// Returns `None` without prompting when stdin or stderr is not a terminal, or if the
+/// user does not make a valid choice.
+fn prompt_for_patchname<'a>(candidates: &[&'a PatchName]) -> Option<&'a PatchName> {
+    use std::io::Write;
+
+    use is_terminal::IsTerminal;
+
+    if !std::io::stdin().is_terminal() || !std::io::stderr().is_terminal() {
+        return None;
+    }
+
+    let mut stderr = std::io::stderr();
+    for (i, pn) in candidates.iter().enumerate() {
+        writeln!(stderr, "{:>3}) {pn}", i + 1).ok()?;
+    }
+    write!(stderr, "Which patch? [1-{}] ", candidates.len()).ok()?;
+    stderr.flush().ok()?;
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer).ok()?;
+    let choice = answer.trim().parse::<usize>().ok()?;
+    choice
+        .checked_sub(1)
+        .and_then(|i| candidates.get(i))
+        .copied()
+}
+
 fn similar_patchnames<'a>(
     patchname: &PatchName,
     stack: &impl StackStateAccess<'a>,
@@ -34,12 +34,17 @@ use crate::branchloc::BranchLocator;
 /// The last patch in an open-ended range depends on command-specific policy which is
 /// determined by the [`RangeConstraint`] used with [`patchrange::resolve_names()`]
 /// or [`patchrange::resolve_names_contiguous()`].
+///
+/// A range may also be specified as `label:<label>`, selecting all patches with the
+/// given label in stack order.
 #[derive(Clone, Debug, PartialEq)]
 pub(crate) enum PatchRange {
     /// A range consisting of a single patch.
     Single(PatchLocator),
     /// A range bound by optional begin and end patches.
     Range(PatchRangeBounds),
+    /// The patches with the given label.
+    Label(String),
 }
 
 /// Patch locations bounding a range of patches.
@@ -122,12 +127,12 @@ pub(crate) enum PatchOffsetAtom {
 /// additional restriction of not containing '/' as well as not being one of the
 /// reserved strings "@" or "{base}".
 #[derive(Clone, Hash, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
-pub(crate) struct PatchName(pub(self) String);
+pub struct PatchName(pub(self) String);
 
 /// A patch specified by the user on the command line may be constrained to a subset of
 /// the stack locations.
 #[derive(Clone, Copy, Debug)]
-pub(crate) enum LocationConstraint {
+pub enum LocationConstraint {
     /// All patches within the stack are allowed.
     All,
     /// Only visible (non-hidden) patches are allowed.
@@ -171,7 +176,7 @@ pub(crate) enum RangeConstraint {
 /// The stack consists of all the applied patches, then unapplied, followed by any
 /// hidden patches.
 #[derive(Debug)]
-pub(crate) enum LocationGroup {
+pub enum LocationGroup {
     Applied,
     Unapplied,
     Hidden,
@@ -8,7 +8,7 @@ use super::{LocationConstraint, LocationGroup, PatchName};
 use crate::stack::StackStateAccess;
 
 #[derive(thiserror::Error, Debug)]
-pub(crate) enum Error {
+pub enum Error {
     #[error("invalid patch name `{name}`: {reason}")]
     InvalidPatchName { name: String, reason: String },
 
@@ -4,9 +4,9 @@
 
 use nom::{
     branch::alt,
-    bytes::complete::tag,
+    bytes::complete::{tag, take_while1},
     combinator::{map, opt},
-    sequence::separated_pair,
+    sequence::{preceded, separated_pair},
 };
 
 use super::patch_locator;
@@ -14,6 +14,7 @@ use crate::patch::{PatchRange, PatchRangeBounds};
 
 pub(in super::super) fn patch_range(input: &str) -> nom::IResult<&str, PatchRange> {
     alt((
+        map(patch_range_label, PatchRange::Label),
         map(patch_range_bounds, PatchRange::Range),
         map(patch_locator, PatchRange::Single),
     ))(input)
@@ -25,3 +26,13 @@ pub(super) fn patch_range_bounds(input: &str) -> nom::IResult<&str, PatchRangeBo
         |(begin, end)| PatchRangeBounds { begin, end },
     )(input)
 }
+
+pub(super) fn patch_range_label(input: &str) -> nom::IResult<&str, String> {
+    map(
+        preceded(
+            tag("label:"),
+            take_while1(|c: char| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '/')),
+        ),
+        |label: &str| label.to_string(),
+    )(input)
+}
@@ -96,4 +96,8 @@ fn range_parsing() {
             })
         )
     );
+    assert_eq!(
+        patch_range("label:fix-ups/v1.2").unwrap(),
+        ("", PatchRange::Label("fix-ups/v1.2".to_string()))
+    );
 }
@@ -32,6 +32,9 @@ pub(crate) enum Error {
         begin_patchname: PatchName,
         end_patchname: PatchName,
     },
+
+    #[error("no patches labeled `{0}`")]
+    NoLabeledPatches(String),
 }
 
 impl std::fmt::Display for PatchRange {
@@ -39,6 +42,7 @@ impl std::fmt::Display for PatchRange {
         match self {
             PatchRange::Single(patch_loc) => patch_loc.fmt(f),
             PatchRange::Range(bounds) => bounds.fmt(f),
+            PatchRange::Label(label) => write!(f, "label:{label}"),
         }
     }
 }
@@ -215,6 +219,15 @@ pub(crate) fn resolve_names<'a, 'repo>(
                 }
                 patches.push(patchname);
             }
+
+            PatchRange::Label(label) => {
+                for patchname in labeled_patches(stack, &allowed_patches, allow, label)? {
+                    if patches.contains(&patchname) {
+                        return Err(Error::Duplicate { patchname });
+                    }
+                    patches.push(patchname);
+                }
+            }
         }
     }
 
@@ -324,6 +337,25 @@ pub(crate) fn resolve_names_contiguous<'a>(
                     next_pos = Some(pos + 1);
                 }
             }
+            PatchRange::Label(label) => {
+                for patchname in labeled_patches(stack, &allowed_patches, allow, label)? {
+                    if patches.contains(&patchname) {
+                        return Err(Error::Duplicate { patchname });
+                    }
+                    let pos = allowed_patches
+                        .iter()
+                        .position(|&pn| pn == &patchname)
+                        .expect("labeled patch is one of the allowed patches");
+                    if next_pos.is_some() && Some(pos) != next_pos {
+                        return Err(Error::NotContiguous {
+                            range: range.to_string(),
+                            prev_range: prev_range.unwrap_or(range).to_string(),
+                        });
+                    }
+                    patches.push(patchname);
+                    next_pos = Some(pos + 1);
+                }
+            }
         }
 
         prev_range = Some(range);
@@ -331,3 +363,30 @@ pub(crate) fn resolve_names_contiguous<'a>(
 
     Ok(patches)
 }
+
+/// Get the allowed patches with the given label, in stack order.
+///
+/// It is an error if no patches have the label. When labeled patches exist, but none
+/// are allowed, the error for the first labeled patch's constraint violation is
+/// returned.
+fn labeled_patches<'repo>(
+    stack: &impl StackStateAccess<'repo>,
+    allowed_patches: &[&PatchName],
+    allow: RangeConstraint,
+    label: &str,
+) -> Result<Vec<PatchName>, Error> {
+    let has_label = |pn: &PatchName| stack.get_patch(pn).labels.contains(label);
+    let patchnames: Vec<PatchName> = allowed_patches
+        .iter()
+        .filter(|pn| has_label(pn))
+        .map(|&pn| pn.clone())
+        .collect();
+    if patchnames.is_empty() {
+        if let Some(patchname) = stack.all_patches().find(|pn| has_label(pn)) {
+            patchname.clone().constrain(stack, allow.into())?;
+        }
+        Err(Error::NoLabeledPatches(label.to_string()))
+    } else {
+        Ok(patchnames)
+    }
+}
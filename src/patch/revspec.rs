@@ -159,6 +159,30 @@ impl RangeRevisionSpec {
             }
         }
     }
+
+    /// Expand an abbreviated patch name in a single patch-like specification.
+    ///
+    /// See [`super::PatchLocator::expand_abbreviation()`].
+    pub(crate) fn expand_abbreviation<'repo>(
+        &self,
+        stack: &impl StackStateAccess<'repo>,
+    ) -> Result<Option<RangeRevisionSpec>, Error> {
+        match self {
+            RangeRevisionSpec::Single(
+                SingleRevisionSpec::PatchLike(patch_like)
+                | SingleRevisionSpec::PatchAndGitLike(patch_like, _),
+            ) => Ok(patch_like
+                .patch_loc
+                .expand_abbreviation(stack)?
+                .map(|patch_loc| {
+                    RangeRevisionSpec::Single(SingleRevisionSpec::PatchLike(PatchLikeSpec {
+                        patch_loc,
+                        suffix: patch_like.suffix.clone(),
+                    }))
+                })),
+            _ => Ok(None),
+        }
+    }
 }
 
 /// Resolve many ranged revision specifications.
@@ -189,3 +189,49 @@ fn should_resolve_ambiguity() {
     ));
     assert_eq!(name("patch"), resolve("beef3"));
 }
+
+#[test]
+fn should_resolve_abbreviated_name() {
+    let stack = DummyStack::from_series(&[
+        ('+', "fix-parser-error-handling", None),
+        ('+', "add-parser-tests", None),
+        ('>', "update-docs", None),
+        ('-', "Refactor-Config-Loading", None),
+        ('!', "old-experiment", None),
+    ]);
+
+    let resolve = |s| {
+        PatchLocator::from_str(s)
+            .expect("valid patch locator")
+            .expand_abbreviation(&stack)
+            .unwrap()
+            .expect("abbreviation matches one patch")
+            .resolve_name(&stack)
+            .unwrap()
+    };
+
+    assert_eq!(name("fix-parser-error-handling"), resolve("error"));
+    assert_eq!(name("add-parser-tests"), resolve("tests"));
+    assert_eq!(name("update-docs"), resolve("docs"));
+    assert_eq!(name("fix-parser-error-handling"), resolve("tests~"));
+    assert_eq!(name("Refactor-Config-Loading"), resolve("rcl"));
+    assert_eq!(name("old-experiment"), resolve("experiment"));
+    assert_eq!(name("fix-parser-error-handling"), resolve("fpeh"));
+
+    assert!(PatchLocator::from_str("update-docs")
+        .unwrap()
+        .expand_abbreviation(&stack)
+        .unwrap()
+        .is_none());
+    assert!(PatchLocator::from_str("xyz")
+        .unwrap()
+        .expand_abbreviation(&stack)
+        .unwrap()
+        .is_none());
+    assert!(matches!(
+        PatchLocator::from_str("error")
+            .unwrap()
+            .resolve_name(&stack),
+        Err(super::super::locator::Error::PatchNotKnown { .. })
+    ));
+}
@@ -14,7 +14,7 @@ use crate::patch::{LocationConstraint, LocationGroup, PatchName};
 /// Trait for accessing information about a stack, including its parent branch.
 ///
 /// This is a supertrait of [`StackStateAccess`].
-pub(crate) trait StackAccess<'repo>: StackStateAccess<'repo> {
+pub trait StackAccess<'repo>: StackStateAccess<'repo> {
     /// Get branch name.
     ///
     /// This is the branch's short name, e.g. "main".
@@ -43,7 +43,7 @@ pub(crate) trait StackAccess<'repo>: StackStateAccess<'repo> {
 /// Trait for accessing stack state.
 ///
 /// Both `Stack` and `StackTransaction` implement this interface.
-pub(crate) trait StackStateAccess<'repo> {
+pub trait StackStateAccess<'repo> {
     /// Get slice of applied patch names.
     fn applied(&self) -> &[PatchName];
 
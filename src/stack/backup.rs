@@ -0,0 +1,158 @@
+// SPDX-License-Identifier: GPL-2.0-only
+
+//! Backups of stack state made before destructive operations.
+//!
+//! Before patches are deleted or otherwise discarded, a backup commit is recorded in
+//! the `refs/stgit-backup/<branch>/<n>` namespace. The backup commit has the same
+//! tree as the stack state commit it backs up, thus it may be read as a
+//! [`StackState`]. The stack state commit is the backup commit's sole parent, which
+//! keeps all patch commits of the backed-up state reachable.
+//!
+//! The backup commit's message records the name of the operation on its first line
+//! followed by the names of the affected patches, one per line.
+
+use anyhow::{anyhow, Result};
+use bstr::ByteSlice;
+
+use super::state::StackState;
+use crate::{ext::RepositoryExtended, patch::PatchName, wrap::Message};
+
+/// Default number of days after which backups expire.
+const DEFAULT_EXPIRE_DAYS: i64 = 30;
+
+/// A backup of a stack state.
+pub(crate) struct Backup<'repo> {
+    /// Backup number, unique within the branch's backup namespace.
+    pub(crate) number: usize,
+
+    /// The backup commit.
+    pub(crate) commit: gix::Commit<'repo>,
+}
+
+impl<'repo> Backup<'repo> {
+    /// Name of the operation that caused the backup to be made.
+    pub(crate) fn operation(&self) -> Result<String> {
+        let message = self.commit.message_raw()?;
+        Ok(message
+            .lines()
+            .next()
+            .unwrap_or_default()
+            .to_str_lossy()
+            .to_string())
+    }
+
+    /// Names of the patches affected by the backed-up operation.
+    pub(crate) fn patchnames(&self) -> Result<Vec<PatchName>> {
+        let message = self.commit.message_raw()?;
+        message
+            .lines()
+            .skip(2)
+            .filter(|line| !line.is_empty())
+            .map(|line| {
+                line.to_str()
+                    .ok()
+                    .and_then(|name| name.parse::<PatchName>().ok())
+                    .ok_or_else(|| anyhow!("invalid patch name in backup {}", self.number))
+            })
+            .collect()
+    }
+
+    /// Time the backup was made.
+    pub(crate) fn time(&self) -> Result<gix::date::Time> {
+        Ok(self.commit.time()?)
+    }
+
+    /// The backed-up stack state.
+    pub(crate) fn state(&self, repo: &'repo gix::Repository) -> Result<StackState<'repo>> {
+        StackState::from_commit(repo, &self.commit)
+    }
+}
+
+/// Get the reference name prefix of the given branch's backups.
+pub(crate) fn backup_refname_prefix(branch_name: &str) -> String {
+    format!("refs/stgit-backup/{branch_name}/")
+}
+
+/// Get the backups of the given branch, ordered by backup number.
+pub(crate) fn list<'repo>(
+    repo: &'repo gix::Repository,
+    branch_name: &str,
+) -> Result<Vec<Backup<'repo>>> {
+    let prefix = backup_refname_prefix(branch_name);
+    let mut backups = Vec::new();
+    let references = repo.references()?;
+    for reference in references.prefixed(prefix.as_str())?.filter_map(Result::ok) {
+        let number = reference
+            .name()
+            .as_bstr()
+            .to_str()
+            .ok()
+            .and_then(|refname| refname.strip_prefix(prefix.as_str()))
+            .and_then(|suffix| suffix.parse::<usize>().ok());
+        if let Some(number) = number {
+            let commit_id = reference.into_fully_peeled_id()?.detach();
+            let commit = repo.find_object(commit_id)?.try_into_commit()?;
+            backups.push(Backup { number, commit });
+        }
+    }
+    backups.sort_by_key(|backup| backup.number);
+    Ok(backups)
+}
+
+/// Record a backup of the given stack state commit.
+///
+/// Expired backups are removed as a side effect. Backups expire after the number of
+/// days configured with `stgit.backup.expire`.
+pub(super) fn create(
+    repo: &gix::Repository,
+    branch_name: &str,
+    state_commit_id: gix::ObjectId,
+    operation: &str,
+    patchnames: &[PatchName],
+) -> Result<()> {
+    let backups = list(repo, branch_name)?;
+    expire(repo, branch_name, &backups)?;
+
+    let number = backups.last().map_or(1, |backup| backup.number + 1);
+    let state_commit = repo.find_commit(state_commit_id)?;
+    let mut message = format!("{operation}\n\n");
+    for patchname in patchnames {
+        message.push_str(patchname.as_ref());
+        message.push('\n');
+    }
+    let committer = repo.get_committer()?;
+    let backup_commit_id = repo.commit_ex(
+        committer,
+        committer,
+        &Message::from(message.as_str()),
+        state_commit.tree_id()?.detach(),
+        [state_commit_id],
+    )?;
+    repo.reference(
+        format!("{}{number}", backup_refname_prefix(branch_name)),
+        backup_commit_id,
+        gix::refs::transaction::PreviousValue::MustNotExist,
+        format!("backup: {operation}"),
+    )?;
+    Ok(())
+}
+
+/// Delete backups older than the configured expiry.
+fn expire(repo: &gix::Repository, branch_name: &str, backups: &[Backup]) -> Result<()> {
+    let expire_days = repo
+        .config_snapshot()
+        .integer("stgit.backup.expire")
+        .unwrap_or(DEFAULT_EXPIRE_DAYS);
+    if expire_days <= 0 {
+        return Ok(());
+    }
+    let now = gix::date::Time::now_local_or_utc();
+    for backup in backups {
+        let days = (now.seconds - backup.time()?.seconds) / (24 * 60 * 60);
+        if days >= expire_days {
+            let refname = format!("{}{}", backup_refname_prefix(branch_name), backup.number);
+            repo.find_reference(refname.as_str())?.delete()?;
+        }
+    }
+    Ok(())
+}
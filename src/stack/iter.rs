@@ -7,7 +7,7 @@ use std::{iter::Chain, slice::Iter};
 use crate::patch::PatchName;
 
 /// Iterator over all patches (applied, unapplied, and hidden).
-pub(crate) struct AllPatches<'s>(
+pub struct AllPatches<'s>(
     Chain<Chain<Iter<'s, PatchName>, Iter<'s, PatchName>>, Iter<'s, PatchName>>,
 );
 
@@ -31,7 +31,7 @@ impl<'s> Iterator for AllPatches<'s> {
 
 /// Chained iterator over two patch lists, applied and unapplied, or unapplied and
 /// hidden.
-pub(crate) struct BothPatches<'s>(Chain<Iter<'s, PatchName>, Iter<'s, PatchName>>);
+pub struct BothPatches<'s>(Chain<Iter<'s, PatchName>, Iter<'s, PatchName>>);
 
 impl<'s> BothPatches<'s> {
     pub(crate) fn new(a: &'s [PatchName], b: &'s [PatchName]) -> Self {
@@ -2,6 +2,7 @@
 
 //! The StGit stack data structure.
 mod access;
+pub(crate) mod backup;
 mod iter;
 mod serde;
 #[allow(clippy::module_inception)]
@@ -10,7 +11,12 @@ mod state;
 mod transaction;
 mod upgrade;
 
-pub(crate) use access::{StackAccess, StackStateAccess};
-pub(crate) use stack::{state_refname_from_branch_name, InitializationPolicy, Stack};
-pub(crate) use state::{PatchState, StackState};
-pub(crate) use transaction::{Error as TransactionError, StackTransaction};
+pub use access::{StackAccess, StackStateAccess};
+pub use iter::{AllPatches, BothPatches};
+pub(crate) use serde::RawStackState;
+pub(crate) use stack::state_refname_from_branch_name;
+pub use stack::{InitializationPolicy, Stack};
+pub use state::{PatchState, StackState};
+pub use transaction::{
+    Error as TransactionError, ExecuteContext, StackTransaction, TransactionBuilder,
+};
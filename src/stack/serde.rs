@@ -2,9 +2,9 @@
 
 //! Serialize and deserialize stack state to/from JSON representation.
 
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 
-use anyhow::{Context, Result};
+use anyhow::{anyhow, Context, Result};
 
 use crate::patch::PatchName;
 
@@ -24,6 +24,12 @@ pub(crate) struct RawStackState {
 pub(crate) struct RawPatchState {
     /// The commit id of the patch.
     pub oid: gix::ObjectId,
+
+    /// Arbitrary key/value metadata associated with the patch.
+    pub metadata: BTreeMap<String, String>,
+
+    /// Labels of the patch.
+    pub labels: BTreeSet<String>,
 }
 
 impl RawStackState {
@@ -31,6 +37,15 @@ impl RawStackState {
     pub(crate) fn from_stack_json(data: &[u8]) -> Result<Self> {
         serde_json::from_slice(data).context("derserializing stack state")
     }
+
+    /// Read and deserialize the stack state blob from the given stack state tree.
+    pub(crate) fn from_tree(tree: gix::Tree<'_>) -> Result<Self> {
+        let stack_json = tree
+            .lookup_entry_by_path("stack.json")?
+            .ok_or_else(|| anyhow!("stack metadata not found"))?;
+        let stack_json_blob = stack_json.object()?.peel_to_kind(gix::objs::Kind::Blob)?;
+        Self::from_stack_json(&stack_json_blob.data)
+    }
 }
 
 impl<'de> serde::Deserialize<'de> for RawStackState {
@@ -52,9 +67,15 @@ impl<'de> serde::Deserialize<'de> for RawStackState {
             pub patches: BTreeMap<PatchName, DeserPatchState>,
         }
 
+        // Fields added to version 5 after its introduction are optional. See the
+        // `upgrade` module for details.
         #[derive(serde::Deserialize)]
         struct DeserPatchState {
             pub oid: String,
+            #[serde(default)]
+            pub meta: BTreeMap<String, String>,
+            #[serde(default)]
+            pub labels: BTreeSet<String>,
         }
 
         let ds = DeserState::deserialize(deserializer)?;
@@ -90,7 +111,14 @@ impl<'de> serde::Deserialize<'de> for RawStackState {
                     patchname, &raw_patch.oid
                 ))
             })?;
-            patches.insert(patchname, RawPatchState { oid });
+            patches.insert(
+                patchname,
+                RawPatchState {
+                    oid,
+                    metadata: raw_patch.meta,
+                    labels: raw_patch.labels,
+                },
+            );
         }
 
         Ok(RawStackState {
@@ -117,12 +145,16 @@ impl serde::Serialize for super::state::StackState<'_> {
             pub applied: &'a Vec<PatchName>,
             pub unapplied: &'a Vec<PatchName>,
             pub hidden: &'a Vec<PatchName>,
-            pub patches: BTreeMap<&'a PatchName, SerializablePatchState>,
+            pub patches: BTreeMap<&'a PatchName, SerializablePatchState<'a>>,
         }
 
         #[derive(serde::Serialize)]
-        struct SerializablePatchState {
+        struct SerializablePatchState<'a> {
             pub oid: String,
+            #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+            pub meta: &'a BTreeMap<String, String>,
+            #[serde(skip_serializing_if = "BTreeSet::is_empty")]
+            pub labels: &'a BTreeSet<String>,
         }
 
         let prev: Option<String> = self.prev.as_ref().map(|commit| commit.id().to_string());
@@ -133,6 +165,8 @@ impl serde::Serialize for super::state::StackState<'_> {
                 patchname,
                 SerializablePatchState {
                     oid: patch_state.commit.id().to_string(),
+                    meta: &patch_state.metadata,
+                    labels: &patch_state.labels,
                 },
             );
         }
@@ -150,3 +184,36 @@ impl serde::Serialize for super::state::StackState<'_> {
         ss.serialize(serializer)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    #[test]
+    fn optional_patch_fields() {
+        let oid = "0123456789012345678901234567890123456789";
+        let json = format!(
+            r#"{{
+                "version": 5,
+                "prev": null,
+                "head": "{oid}",
+                "applied": ["p0", "p1"],
+                "unapplied": [],
+                "hidden": [],
+                "patches": {{
+                    "p0": {{"oid": "{oid}"}},
+                    "p1": {{"oid": "{oid}", "meta": {{"uuid": "abc"}}, "labels": ["wip"]}}
+                }}
+            }}"#
+        );
+        let state = RawStackState::from_stack_json(json.as_bytes()).unwrap();
+        let p0 = &state.patches[&PatchName::from_str("p0").unwrap()];
+        assert!(p0.metadata.is_empty());
+        assert!(p0.labels.is_empty());
+        let p1 = &state.patches[&PatchName::from_str("p1").unwrap()];
+        assert_eq!(p1.metadata.get("uuid").map(String::as_str), Some("abc"));
+        assert!(p1.labels.contains("wip"));
+    }
+}
@@ -2,14 +2,18 @@
 
 //! High-level StGit stack representation.
 
-use std::{collections::BTreeMap, rc::Rc, str::FromStr};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    rc::Rc,
+    str::FromStr,
+};
 
 use anyhow::{anyhow, Result};
 use bstr::ByteSlice;
 
 use super::{
-    state::StackState, transaction::TransactionBuilder, upgrade::stack_upgrade, PatchState,
-    StackAccess, StackStateAccess,
+    serde::RawStackState, state::StackState, transaction::TransactionBuilder,
+    upgrade::stack_upgrade, PatchState, StackAccess, StackStateAccess,
 };
 use crate::{
     branchloc::BranchLocator,
@@ -23,7 +27,7 @@ use crate::{
 ///
 /// This struct contains the underlying stack state as recorded in the git repo along
 /// with other relevant branch state.
-pub(crate) struct Stack<'repo> {
+pub struct Stack<'repo> {
     pub(crate) repo: &'repo gix::Repository,
     branch_name: String,
     branch: Branch<'repo>,
@@ -35,7 +39,7 @@ pub(crate) struct Stack<'repo> {
 }
 
 /// Policy for stack initialization when opening/discovering a stack for a branch.
-pub(crate) enum InitializationPolicy {
+pub enum InitializationPolicy {
     /// The stack will be initialized if it is not yet initialized.
     AutoInitialize,
 
@@ -59,13 +63,14 @@ pub(crate) enum InitializationPolicy {
 impl<'repo> Stack<'repo> {
     /// Remove StGit stack state from the repository.
     ///
-    /// This removes the reference to the stack state, i.e. `refs/stacks/<name>`, and
-    /// references to the stacks patches found in `refs/patches/<name>/`. StGit specific
+    /// This removes the reference to the stack state, i.e. `refs/stacks/<name>`,
+    /// references to the stacks patches found in `refs/patches/<name>/`, and the stack's
+    /// backups found in `refs/stgit-backup/<name>/`. StGit specific
     /// configuration associated with the stack is also removed from the config.
     ///
     /// N.B. stack and patch commits that become unreferenced are subject to git's
     /// normal periodic garbage collection.
-    pub(crate) fn deinitialize(self) -> Result<()> {
+    pub fn deinitialize(self) -> Result<()> {
         let Self {
             repo,
             branch_name,
@@ -74,15 +79,15 @@ impl<'repo> Stack<'repo> {
         } = self;
         let state_ref = repo.find_reference(&stack_refname)?;
         let patch_ref_prefix = get_patch_refname(&branch_name, "");
+        let backup_ref_prefix = super::backup::backup_refname_prefix(&branch_name);
         for patch_reference in
             repo.references()?
                 .all()?
                 .filter_map(Result::ok)
                 .filter(|reference| {
-                    reference
-                        .name()
-                        .as_bstr()
-                        .starts_with(patch_ref_prefix.as_bytes())
+                    let refname = reference.name().as_bstr();
+                    refname.starts_with(patch_ref_prefix.as_bytes())
+                        || refname.starts_with(backup_ref_prefix.as_bytes())
                 })
         {
             patch_reference.delete()?;
@@ -97,7 +102,76 @@ impl<'repo> Stack<'repo> {
         Ok(())
     }
 
-    pub(crate) fn current(
+    /// Remove StGit stack state from the repository, keeping a backup of it.
+    ///
+    /// The stack state is removed as with [`Stack::deinitialize()`], after which a
+    /// backup of the final stack state is recorded in the branch's (now empty) backup
+    /// namespace. The backup keeps the patch commits reachable such that the
+    /// `patchnames` may later be restored with `stg recover` once a stack is again
+    /// initialized for the branch. Returns the backup number.
+    pub(crate) fn deinitialize_archived(
+        self,
+        operation: &str,
+        patchnames: &[PatchName],
+    ) -> Result<usize> {
+        let repo = self.repo;
+        let branch_name = self.branch_name.clone();
+        let state_commit_id = repo
+            .find_reference(&self.stack_refname)?
+            .into_fully_peeled_id()?
+            .detach();
+        self.deinitialize()?;
+        super::backup::create(repo, &branch_name, state_commit_id, operation, patchnames)?;
+        Ok(super::backup::list(repo, &branch_name)?
+            .last()
+            .map_or(1, |backup| backup.number))
+    }
+
+    /// Find the patches that would become unreachable if the branch were deleted.
+    ///
+    /// A patch's commit remains reachable if it is reachable from any reference other
+    /// than the branch itself and the stack's state, patch, and backup references.
+    pub(crate) fn unreachable_patches(&self) -> Result<Vec<PatchName>> {
+        let branch_refname = self.get_branch_refname().as_bstr();
+        let patch_ref_prefix = get_patch_refname(&self.branch_name, "");
+        let backup_ref_prefix = super::backup::backup_refname_prefix(&self.branch_name);
+        let mut exclude_ids = BTreeSet::new();
+        for reference in self.repo.references()?.all()?.filter_map(Result::ok) {
+            let refname = reference.name().as_bstr();
+            if refname == branch_refname
+                || refname == self.stack_refname.as_bytes()
+                || refname.starts_with(patch_ref_prefix.as_bytes())
+                || refname.starts_with(backup_ref_prefix.as_bytes())
+            {
+                continue;
+            }
+            if let Ok(commit) = reference
+                .into_fully_peeled_id()
+                .map_err(anyhow::Error::from)
+                .and_then(|id| Ok(id.object()?.try_into_commit()?))
+            {
+                exclude_ids.insert(commit.id);
+            }
+        }
+
+        let unreachable_ids: BTreeSet<gix::ObjectId> = self
+            .repo
+            .stupid()
+            .rev_list_excluding(
+                self.all_patches()
+                    .map(|patchname| self.get_patch_commit_id(patchname)),
+                exclude_ids,
+            )?
+            .into_iter()
+            .collect();
+        Ok(self
+            .all_patches()
+            .filter(|patchname| unreachable_ids.contains(&self.get_patch_commit_id(patchname)))
+            .cloned()
+            .collect())
+    }
+
+    pub fn current(
         repo: &'repo gix::Repository,
         init_policy: InitializationPolicy,
     ) -> Result<Self> {
@@ -230,6 +304,14 @@ impl<'repo> Stack<'repo> {
         })
     }
 
+    /// Determine whether the stack is initialized.
+    ///
+    /// Only stacks obtained with [`InitializationPolicy::AllowUninitialized`] may be
+    /// uninitialized.
+    pub(crate) fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+
     /// Check whether the stack is marked as protected in the config.
     pub(crate) fn is_protected(&self, config: &gix::config::Snapshot) -> bool {
         config
@@ -273,12 +355,12 @@ impl<'repo> Stack<'repo> {
     }
 
     /// Check whether the stack's recorded head matches the branch's head.
-    pub(crate) fn is_head_top(&self) -> bool {
+    pub fn is_head_top(&self) -> bool {
         self.state.head.id() == self.branch_head.id()
     }
 
     /// Return an error if the stack's recorded head differs from the branch's head.
-    pub(crate) fn check_head_top_mismatch(&self) -> Result<()> {
+    pub fn check_head_top_mismatch(&self) -> Result<()> {
         if self.state.applied.is_empty() || self.is_head_top() {
             Ok(())
         } else {
@@ -290,8 +372,74 @@ impl<'repo> Stack<'repo> {
         }
     }
 
+    /// Determine when each hidden patch became hidden.
+    ///
+    /// The stack state log is walked backward from the current state until each
+    /// hidden patch is found to not be hidden. The time of the oldest state in which a
+    /// patch is continuously hidden is reported for that patch. If the log is
+    /// exhausted, e.g. due to `stg log --clear`, the time of the oldest available
+    /// state is used.
+    pub(crate) fn hidden_since(&self) -> Result<BTreeMap<PatchName, gix::date::Time>> {
+        let mut since = BTreeMap::new();
+        if !self.is_initialized || self.state.hidden.is_empty() {
+            return Ok(since);
+        }
+
+        let mut pending: BTreeSet<&PatchName> = self.state.hidden.iter().collect();
+        let mut maybe_state_id = Some(
+            self.repo
+                .find_reference(&self.stack_refname)?
+                .into_fully_peeled_id()?
+                .detach(),
+        );
+
+        while let Some(state_id) = maybe_state_id {
+            if pending.is_empty() {
+                break;
+            }
+            let state_commit = self.repo.find_commit(state_id)?;
+            let state_time = state_commit.time()?;
+            let stack_json = state_commit
+                .tree()?
+                .lookup_entry_by_path("stack.json")?
+                .ok_or_else(|| anyhow!("stack metadata not found in `{state_id}`"))?;
+            let stack_json_blob = stack_json.object()?.peel_to_kind(gix::objs::Kind::Blob)?;
+            let raw_state = RawStackState::from_stack_json(&stack_json_blob.data)?;
+            pending.retain(|&patchname| {
+                if raw_state.hidden.contains(patchname) {
+                    since.insert(patchname.clone(), state_time);
+                    true
+                } else {
+                    false
+                }
+            });
+            maybe_state_id = raw_state.prev;
+        }
+
+        Ok(since)
+    }
+
+    /// Record a backup of the current stack state before a destructive operation.
+    ///
+    /// The `patchnames` are the patches that the operation is about to delete or
+    /// otherwise discard. They are restorable with `stg recover`.
+    pub(crate) fn backup(&self, operation: &str, patchnames: &[PatchName]) -> Result<()> {
+        let state_commit_id = self
+            .repo
+            .find_reference(&self.stack_refname)?
+            .into_fully_peeled_id()?
+            .detach();
+        super::backup::create(
+            self.repo,
+            &self.branch_name,
+            state_commit_id,
+            operation,
+            patchnames,
+        )
+    }
+
     /// Re-commit stack state with updated branch head.
-    pub(crate) fn log_external_mods(self, message: Option<&str>) -> Result<Self> {
+    pub fn log_external_mods(self, message: Option<&str>) -> Result<Self> {
         assert!(
             self.is_initialized,
             "Attempt to log stack state when uninitialized"
@@ -337,7 +485,7 @@ impl<'repo> Stack<'repo> {
     }
 
     /// Start a transaction to modify the stack.
-    pub(crate) fn setup_transaction(self) -> TransactionBuilder<'repo> {
+    pub fn setup_transaction(self) -> TransactionBuilder<'repo> {
         assert!(
             self.is_initialized,
             "Attempt transaction with uninitialized stack state"
@@ -353,6 +501,70 @@ impl<'repo> Stack<'repo> {
         Ok(())
     }
 
+    /// Get the stack state log's state commits, newest first.
+    ///
+    /// The log ends at the first state without a previous state, i.e. where the log was
+    /// started or last cleared.
+    pub(crate) fn state_log(&self) -> Result<Vec<gix::Commit<'repo>>> {
+        let mut commit = self
+            .repo
+            .find_reference(&self.stack_refname)?
+            .into_fully_peeled_id()?
+            .object()?
+            .try_into_commit()?;
+        let mut log = Vec::new();
+        loop {
+            let prev_id = RawStackState::from_tree(commit.tree()?)?.prev;
+            log.push(commit);
+            if let Some(prev_id) = prev_id {
+                commit = self.repo.find_commit(prev_id)?;
+            } else {
+                break;
+            }
+        }
+        Ok(log)
+    }
+
+    /// Truncate the stack state log to its `keep` most recent entries.
+    ///
+    /// The `log` is the stack state log as returned by [`Stack::state_log()`]. The kept
+    /// entries are re-committed with their original messages, authors, and committers
+    /// such that the dropped entries are no longer reachable from the stack state
+    /// reference.
+    pub(crate) fn truncate_state_log(
+        &mut self,
+        log: &[gix::Commit<'repo>],
+        keep: usize,
+        reflog_msg: &str,
+    ) -> Result<()> {
+        assert!(keep > 0 && keep <= log.len());
+        let mut prev: Option<Rc<gix::Commit<'repo>>> = None;
+        for commit in log[..keep].iter().rev() {
+            let mut state = StackState::from_commit(self.repo, commit)?;
+            state.prev = prev;
+            let commit_ref = commit.decode()?;
+            let state_commit_id = state.commit_with_signatures(
+                self.repo,
+                None,
+                &commit_ref.message.to_str_lossy(),
+                commit_ref.author(),
+                commit_ref.committer(),
+            )?;
+            prev = Some(Rc::new(self.repo.find_commit(state_commit_id)?));
+        }
+        let state_commit = prev.expect("at least one log entry is kept");
+        self.repo.reference(
+            self.stack_refname.as_str(),
+            state_commit.id,
+            gix::refs::transaction::PreviousValue::MustExistAndMatch(gix::refs::Target::Peeled(
+                log[0].id,
+            )),
+            reflog_msg,
+        )?;
+        self.state = StackState::from_commit(self.repo, &state_commit)?;
+        Ok(())
+    }
+
     /// Update the branch and branch head commit.
     pub(super) fn update_head(&mut self, branch: Branch<'repo>, commit: Rc<gix::Commit<'repo>>) {
         self.branch = branch;
@@ -5,7 +5,11 @@
 //! This stack state representation is serialized to/from the `stack.json` blob in the
 //! stack state tree.
 
-use std::{collections::BTreeMap, rc::Rc, str};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    rc::Rc,
+    str,
+};
 
 use anyhow::{anyhow, Result};
 use bstr::{BString, ByteVec};
@@ -14,6 +18,7 @@ use super::{access::StackStateAccess, iter::AllPatches, serde::RawStackState};
 use crate::{
     ext::{CommitExtended, CommitOptions, RepositoryExtended},
     patch::PatchName,
+    stupid::Stupid,
     wrap::Message,
 };
 
@@ -21,12 +26,12 @@ use crate::{
 ///
 /// This is the core state recorded-to and read-from the git repository that describes
 /// the state of a StGit stack.
-pub(crate) struct StackState<'repo> {
+pub struct StackState<'repo> {
     /// Commit of the previous stack state.
     ///
     /// Will be None for a newly initialized stack or when the stack history is cleared
     /// (i.e. with `stg log --clear`).
-    pub(crate) prev: Option<Rc<gix::Commit<'repo>>>,
+    pub prev: Option<Rc<gix::Commit<'repo>>>,
 
     /// Head commit of the stack.
     ///
@@ -48,11 +53,64 @@ pub(crate) struct StackState<'repo> {
 }
 
 /// State associated with a patch.
-///
-/// Currently the only state is a commit object.
 #[derive(Clone, Debug)]
-pub(crate) struct PatchState<'repo> {
-    pub(crate) commit: Rc<gix::Commit<'repo>>,
+pub struct PatchState<'repo> {
+    /// The patch's commit object.
+    pub commit: Rc<gix::Commit<'repo>>,
+
+    /// Arbitrary key/value metadata associated with the patch.
+    ///
+    /// The metadata is carried along as the patch's commit is updated and when the
+    /// patch is renamed.
+    pub metadata: BTreeMap<String, String>,
+
+    /// Labels used to group and select patches.
+    ///
+    /// Like the metadata, labels are carried along as the patch is updated or renamed.
+    pub labels: BTreeSet<String>,
+}
+
+impl<'repo> PatchState<'repo> {
+    /// Metadata key of the patch's persistent UUID.
+    pub const UUID_KEY: &'static str = "uuid";
+
+    /// Make the state of a newly created patch.
+    ///
+    /// New patches are assigned a random UUID which, being recorded in the patch's
+    /// metadata, identifies the patch even when it is renamed.
+    pub(crate) fn new(commit: gix::Commit<'repo>) -> Result<Self> {
+        let mut metadata = BTreeMap::new();
+        metadata.insert(Self::UUID_KEY.to_string(), make_uuid()?);
+        Ok(Self {
+            commit: Rc::new(commit),
+            metadata,
+            labels: BTreeSet::new(),
+        })
+    }
+
+    /// Get the patch's persistent UUID.
+    ///
+    /// Patches created before UUIDs were assigned do not have one.
+    pub fn uuid(&self) -> Option<&str> {
+        self.metadata.get(Self::UUID_KEY).map(String::as_str)
+    }
+}
+
+/// Make a random (version 4) UUID string.
+fn make_uuid() -> Result<String> {
+    let mut bytes = [0u8; 16];
+    getrandom::getrandom(&mut bytes).map_err(|e| anyhow!("generating patch UUID: {e}"))?;
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    let hex: String = bytes.iter().map(|b| format!("{b:02x}")).collect();
+    Ok(format!(
+        "{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    ))
 }
 
 impl<'repo> StackStateAccess<'repo> for StackState<'repo> {
@@ -107,30 +165,21 @@ impl<'repo> StackState<'repo> {
     }
 
     /// Read and parse stack state from given state state commit.
-    pub(crate) fn from_commit(
-        repo: &'repo gix::Repository,
-        commit: &gix::Commit<'repo>,
-    ) -> Result<Self> {
+    pub fn from_commit(repo: &'repo gix::Repository, commit: &gix::Commit<'repo>) -> Result<Self> {
         Self::from_tree(repo, commit.tree()?)
     }
 
     /// Read and parse stack state from given stack state tree.
     pub(super) fn from_tree(repo: &'repo gix::Repository, tree: gix::Tree<'repo>) -> Result<Self> {
-        let stack_json = tree.lookup_entry_by_path("stack.json")?;
-        if let Some(stack_json) = stack_json {
-            let stack_json_blob = stack_json.object()?.peel_to_kind(gix::objs::Kind::Blob)?;
-            let raw_state = RawStackState::from_stack_json(&stack_json_blob.data)?;
-            Self::from_raw_state(repo, raw_state)
-        } else {
-            Err(anyhow!("stack metadata not found"))
-        }
+        let raw_state = RawStackState::from_tree(tree)?;
+        Self::from_raw_state(repo, raw_state)
     }
 
     /// Convert [`RawStackState`] to [`StackState`].
     ///
     /// Commit objects are looked-up from commit ids in the raw state. This may fail if
     /// the raw state references commit ids not present in the repository.
-    pub(super) fn from_raw_state(
+    pub(crate) fn from_raw_state(
         repo: &'repo gix::Repository,
         raw_state: RawStackState,
     ) -> Result<Self> {
@@ -141,6 +190,8 @@ impl<'repo> StackState<'repo> {
                 patchname,
                 PatchState {
                     commit: Rc::new(commit),
+                    metadata: raw_state.metadata,
+                    labels: raw_state.labels,
                 },
             );
         }
@@ -198,16 +249,39 @@ impl<'repo> StackState<'repo> {
         update_ref: Option<&str>,
         message: &str,
     ) -> Result<gix::ObjectId> {
+        self.commit_with_signatures(
+            repo,
+            update_ref,
+            message,
+            repo.get_author()?,
+            repo.get_committer()?,
+        )
+    }
+
+    /// Commit stack state to repository with the given author and committer.
+    ///
+    /// See [`StackState::commit()`].
+    pub(crate) fn commit_with_signatures(
+        &self,
+        repo: &'repo gix::Repository,
+        update_ref: Option<&str>,
+        message: &str,
+        author: gix::actor::SignatureRef<'_>,
+        committer: gix::actor::SignatureRef<'_>,
+    ) -> Result<gix::ObjectId> {
+        let config = repo.config_snapshot();
+        let record_increments = config.boolean("stgit.log.increments").unwrap_or(false);
         let (state_tree_id, prev_state) = if let Some(prev_commit) = self.prev.as_ref() {
             let prev_state = Self::from_tree(repo, prev_commit.tree()?)?;
-            let state_tree_id = self.make_tree(repo, Some((&prev_state, prev_commit.tree()?)))?;
+            let state_tree_id = self.make_tree(
+                repo,
+                Some((&prev_state, prev_commit.tree()?)),
+                record_increments,
+            )?;
             (state_tree_id, Some(prev_state))
         } else {
-            (self.make_tree(repo, None)?, None)
+            (self.make_tree(repo, None, false)?, None)
         };
-        let config = repo.config_snapshot();
-        let committer = repo.get_committer()?;
-        let author = repo.get_author()?;
 
         let simplified_parents: Vec<gix::ObjectId> = match &self.prev {
             Some(prev_commit) => {
@@ -224,7 +298,10 @@ impl<'repo> StackState<'repo> {
 
         let commit_opts = CommitOptions {
             commit_encoding: None,
-            gpgsign: config.boolean("stgit.gpgsign").unwrap_or(false),
+            gpgsign: config
+                .boolean("stgit.gpgsign-stack")
+                .or_else(|| config.boolean("stgit.gpgsign"))
+                .unwrap_or(false),
         };
 
         let simplified_parent_id = repo.commit_with_options(
@@ -301,17 +378,25 @@ impl<'repo> StackState<'repo> {
     /// treated as write-only by StGit and most useful when running `stg log
     /// <patchname>`.
     ///
-    /// ```
+    /// ```text
     /// stack.json
     /// patches/
     ///    <patchname1>
     ///    <patchname2>
     ///    ...
     /// ```
+    ///
+    /// When `record_increments` is true, the tree additionally contains an
+    /// `increments` sub-tree with a blob for each patch whose content was changed
+    /// without changing its parent, e.g. by a refresh, relative to the previous stack
+    /// state. Each such blob contains the diff of the patch's content before and after
+    /// the change. Unlike the patch metadata blobs, increments are not carried over to
+    /// subsequent stack states.
     fn make_tree(
         &self,
         repo: &'repo gix::Repository,
         prev_state_and_tree: Option<(&Self, gix::Tree)>,
+        record_increments: bool,
     ) -> Result<gix::ObjectId> {
         let stack_json_id = repo.write_blob(serde_json::to_string_pretty(self)?.as_bytes())?;
         let stack_json_entry = gix::objs::tree::Entry {
@@ -339,9 +424,19 @@ impl<'repo> StackState<'repo> {
             oid: patches_tree_id,
         };
 
-        let state_tree = gix::objs::Tree {
-            entries: vec![patches_entry, stack_json_entry],
-        };
+        let mut entries = Vec::with_capacity(3);
+        if let Some(prev_state) = prev_state.filter(|_| record_increments) {
+            if let Some(increments_tree_id) = self.make_increments_tree(repo, prev_state)? {
+                entries.push(gix::objs::tree::Entry {
+                    mode: gix::objs::tree::EntryMode::Tree,
+                    filename: "increments".into(),
+                    oid: increments_tree_id,
+                });
+            }
+        }
+        entries.push(patches_entry);
+        entries.push(stack_json_entry);
+        let state_tree = gix::objs::Tree { entries };
 
         let state_tree_id = repo.write_object(state_tree)?;
         Ok(state_tree_id.detach())
@@ -371,6 +466,50 @@ impl<'repo> StackState<'repo> {
         Ok(patches_tree_id)
     }
 
+    /// Make the `increments` subtree, if any patches' content changed in place.
+    fn make_increments_tree(
+        &self,
+        repo: &gix::Repository,
+        prev_state: &StackState,
+    ) -> Result<Option<gix::ObjectId>> {
+        let mut increments_tree = gix::objs::Tree::empty();
+        for patchname in self.all_patches() {
+            let commit = &self.patches[patchname].commit;
+            let prev_commit = if let Some(prev_patch) = prev_state.patches.get(patchname) {
+                &prev_patch.commit
+            } else {
+                continue;
+            };
+            if prev_commit.id == commit.id
+                || prev_commit.parent_ids().next() != commit.parent_ids().next()
+            {
+                continue;
+            }
+            let diff = repo.stupid().diff_tree_patch(
+                prev_commit.tree_id()?.detach(),
+                commit.tree_id()?.detach(),
+                None::<Vec<&str>>,
+                false,
+                ["--no-ext-diff", "--binary"],
+            )?;
+            if !diff.is_empty() {
+                increments_tree.entries.push(gix::objs::tree::Entry {
+                    mode: gix::objs::tree::EntryMode::Blob,
+                    filename: patchname.to_string().into(),
+                    oid: repo.write_blob(diff)?.detach(),
+                });
+            }
+        }
+        if increments_tree.entries.is_empty() {
+            Ok(None)
+        } else {
+            increments_tree
+                .entries
+                .sort_by_key(|entry| entry.filename.clone());
+            Ok(Some(repo.write_object(increments_tree)?.detach()))
+        }
+    }
+
     /// Make patch metadata blob.
     ///
     /// The patch metadata blobs are for human consumption. The per-patch log, viewed
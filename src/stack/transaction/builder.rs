@@ -12,7 +12,7 @@ use super::{
 use crate::stack::{Stack, StackAccess, StackStateAccess};
 
 /// Builder used to setup a stack transaction.
-pub(crate) struct TransactionBuilder<'repo> {
+pub struct TransactionBuilder<'repo> {
     stack: Stack<'repo>,
     output: Option<termcolor::StandardStream>,
     options: TransactionOptions,
@@ -34,7 +34,7 @@ impl<'repo> TransactionBuilder<'repo> {
     /// does not match the head recorded in stack state. By default, the transaction
     /// will not execute if the stack head does not match the branch head.
     #[must_use]
-    pub(crate) fn allow_bad_head(mut self, allow: bool) -> Self {
+    pub fn allow_bad_head(mut self, allow: bool) -> Self {
         self.options.allow_bad_head = allow;
         self
     }
@@ -43,7 +43,7 @@ impl<'repo> TransactionBuilder<'repo> {
     /// outstanding merge conflicts. By default, the transaction will not execute if
     /// there are outstanding conflicts.
     #[must_use]
-    pub(crate) fn allow_conflicts(mut self, allow: bool) -> Self {
+    pub fn allow_conflicts(mut self, allow: bool) -> Self {
         self.options.conflict_mode = if allow {
             ConflictMode::Allow
         } else {
@@ -69,16 +69,25 @@ impl<'repo> TransactionBuilder<'repo> {
     /// Allow pushing of a patch that results in conflicts. Will use the value of
     /// "stgit.push.allow-conflicts" if not set explicitly.
     #[must_use]
-    pub(crate) fn allow_push_conflicts(mut self, allow: bool) -> Self {
+    pub fn allow_push_conflicts(mut self, allow: bool) -> Self {
         self.options.allow_push_conflicts = Some(allow);
         self
     }
 
+    /// Instead of leaving conflict markers when a pushed patch conflicts, apply the
+    /// patch's hunks that apply cleanly and write the remaining hunks to `*.rej`
+    /// files, as with `git apply --reject`.
+    #[must_use]
+    pub fn reject_push_conflicts(mut self, reject: bool) -> Self {
+        self.options.reject_push_conflicts = reject;
+        self
+    }
+
     /// Discard any modifications to files in the working tree when the transaction
     /// executes. By default, the transaction will not execute if there are any
     /// modified files in the working tree.
     #[must_use]
-    pub(crate) fn discard_changes(mut self, discard: bool) -> Self {
+    pub fn discard_changes(mut self, discard: bool) -> Self {
         self.options.discard_changes = discard;
         self
     }
@@ -88,14 +97,14 @@ impl<'repo> TransactionBuilder<'repo> {
     /// index and worktree is disallowed (the default), all pushes must apply cleanly
     /// because any conflicts cannot be written the index and worktree.
     #[must_use]
-    pub(crate) fn use_index_and_worktree(mut self, allow: bool) -> Self {
+    pub fn use_index_and_worktree(mut self, allow: bool) -> Self {
         self.options.use_index_and_worktree = allow;
         self
     }
 
     /// Set the output stream for the transaction. This method must be called.
     #[must_use]
-    pub(crate) fn with_output_stream(mut self, output: termcolor::StandardStream) -> Self {
+    pub fn with_output_stream(mut self, output: termcolor::StandardStream) -> Self {
         self.output = Some(output);
         self
     }
@@ -112,7 +121,7 @@ impl<'repo> TransactionBuilder<'repo> {
     /// Determines whether the committer date will be set to be the same as the author
     /// date when pushing patches.
     #[must_use]
-    pub(crate) fn committer_date_is_author_date(mut self, yes: bool) -> Self {
+    pub fn committer_date_is_author_date(mut self, yes: bool) -> Self {
         self.options.committer_date_is_author_date = yes;
         self
     }
@@ -128,7 +137,7 @@ impl<'repo> TransactionBuilder<'repo> {
     /// This method must be called. It returns an [`ExecuteContext`] which must then be used
     /// to execute the transaction by calling [`ExecuteContext::execute()`].
     #[must_use]
-    pub(crate) fn transact<F>(self, f: F) -> ExecuteContext<'repo>
+    pub fn transact<F>(self, f: F) -> ExecuteContext<'repo>
     where
         F: FnOnce(&mut StackTransaction) -> Result<()>,
     {
@@ -0,0 +1,171 @@
+// SPDX-License-Identifier: GPL-2.0-only
+
+//! Attribution of push conflicts to the applied patches that caused them.
+
+use std::path::Path;
+
+use anyhow::Result;
+use bstr::ByteSlice;
+
+use crate::{patch::PatchName, stupid::Stupid};
+
+/// Find the applied patches that last modified the conflicting regions of each
+/// conflicted file in the work tree.
+///
+/// The stack's side of each conflict region is blamed, from `top_id` back to, but
+/// excluding, `base_id`. The stack's side is the "ours" side of the conflict markers
+/// when `stack_is_ours` is true and the "theirs" side otherwise. Lines blamed on
+/// commits that are not among the given applied patch commits are not attributed.
+///
+/// Returns each conflicted path, relative to the work tree root, along with the
+/// patches it conflicts with, in stack order. Paths that cannot be attributed to any
+/// patch are omitted.
+pub(super) fn attribute_conflicts(
+    repo: &gix::Repository,
+    base_id: gix::ObjectId,
+    top_id: gix::ObjectId,
+    applied: &[(PatchName, gix::ObjectId)],
+    stack_is_ours: bool,
+) -> Result<Vec<(String, Vec<PatchName>)>> {
+    let mut attributions = Vec::new();
+    if applied.is_empty() {
+        return Ok(attributions);
+    }
+    let work_dir = if let Some(work_dir) = repo.work_dir() {
+        work_dir
+    } else {
+        return Ok(attributions);
+    };
+    let stupid = repo.stupid();
+
+    for path in stupid.diff_unmerged_names()? {
+        let path = work_dir.join(path);
+        let content = if let Ok(content) = std::fs::read(&path) {
+            content
+        } else {
+            continue;
+        };
+        let ranges = conflict_line_ranges(&content, stack_is_ours);
+        if ranges.is_empty() {
+            continue;
+        }
+        let mut opts = Vec::with_capacity(ranges.len() * 2);
+        for (start, end) in ranges {
+            opts.push("-L".to_string());
+            opts.push(format!("{start},{end}"));
+        }
+        let blamed = if let Ok(blamed) = stupid.blame(base_id, top_id, &path, &opts) {
+            blamed
+        } else {
+            continue;
+        };
+        let patchnames: Vec<PatchName> = applied
+            .iter()
+            .filter(|(_, commit_id)| blamed.iter().any(|(id, _)| id == commit_id))
+            .map(|(patchname, _)| patchname.clone())
+            .collect();
+        if !patchnames.is_empty() {
+            let display_path = path
+                .strip_prefix(work_dir)
+                .unwrap_or_else(|_| Path::new(&path))
+                .to_string_lossy()
+                .into_owned();
+            attributions.push((display_path, patchnames));
+        }
+    }
+
+    Ok(attributions)
+}
+
+/// Find the line ranges of the conflict regions on one side of a conflicted file.
+///
+/// The conflict regions are delimited by conflict markers as written by `git
+/// merge-recursive`, optionally including the merge base section of the `diff3` and
+/// `zdiff3` conflict styles. The returned ranges are 1-based and inclusive line
+/// numbers in that side's version of the file. When a side of a conflict region is
+/// empty, the line preceding the region is used instead.
+fn conflict_line_ranges(content: &[u8], ours: bool) -> Vec<(usize, usize)> {
+    #[derive(PartialEq, Eq)]
+    enum Section {
+        Outside,
+        Ours,
+        Base,
+        Theirs,
+    }
+
+    let is_marker = |line: &[u8], marker: &[u8]| {
+        line.starts_with(marker)
+            && matches!(line.get(marker.len()), None | Some(b' ' | b'\n' | b'\r'))
+    };
+
+    let mut ranges = Vec::new();
+    let mut section = Section::Outside;
+    let mut line_number = 0;
+    let mut region_start = 0;
+
+    for line in content.lines_with_terminator() {
+        if section == Section::Outside && is_marker(line, b"<<<<<<<") {
+            section = Section::Ours;
+            region_start = line_number + 1;
+        } else if section == Section::Ours && is_marker(line, b"|||||||") {
+            section = Section::Base;
+        } else if matches!(section, Section::Ours | Section::Base) && is_marker(line, b"=======") {
+            section = Section::Theirs;
+        } else if section == Section::Theirs && is_marker(line, b">>>>>>>") {
+            section = Section::Outside;
+            if line_number >= region_start {
+                ranges.push((region_start, line_number));
+            } else if line_number > 0 {
+                ranges.push((line_number, line_number));
+            }
+        } else if section == Section::Outside
+            || (ours && section == Section::Ours)
+            || (!ours && section == Section::Theirs)
+        {
+            line_number += 1;
+        }
+    }
+
+    ranges
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFLICTED: &[u8] = b"\
+a
+<<<<<<< ours
+b1
+b2
+=======
+B
+>>>>>>> theirs
+c
+<<<<<<< ours
+||||||| base
+d
+=======
+D
+>>>>>>> theirs
+e
+";
+
+    #[test]
+    fn ours_ranges() {
+        assert_eq!(conflict_line_ranges(CONFLICTED, true), vec![(2, 3), (4, 4)]);
+    }
+
+    #[test]
+    fn theirs_ranges() {
+        assert_eq!(
+            conflict_line_ranges(CONFLICTED, false),
+            vec![(2, 2), (4, 4)]
+        );
+    }
+
+    #[test]
+    fn no_conflicts() {
+        assert!(conflict_line_ranges(b"a\n<<<<<<<< not a marker\n", true).is_empty());
+    }
+}
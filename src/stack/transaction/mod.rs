@@ -14,7 +14,7 @@
 //!
 //! # Example
 //!
-//! ```no_run
+//! ```ignore
 //! let new_stack = stack
 //!     .setup_transaction()
 //!     .with_output_stream(...)
@@ -27,15 +27,20 @@
 //! ```
 
 mod builder;
+mod conflicts;
 mod options;
+mod parallel;
 mod ui;
 
-use std::{collections::BTreeMap, rc::Rc};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    rc::Rc,
+};
 
 use anyhow::{anyhow, Result};
 use indexmap::IndexSet;
 
-pub(crate) use self::builder::TransactionBuilder;
+pub use self::builder::TransactionBuilder;
 use self::{
     options::{ConflictMode, TransactionOptions},
     ui::TransactionUserInterface,
@@ -43,6 +48,7 @@ use self::{
 use super::{state::StackState, StackAccess};
 use crate::{
     ext::{CommitExtended, RepositoryExtended},
+    hook::{self, StackTransactionHook},
     patch::PatchName,
     stack::{PatchState, Stack, StackStateAccess},
     stupid::{Stupid, StupidContext},
@@ -50,7 +56,7 @@ use crate::{
 };
 
 #[derive(thiserror::Error, Debug)]
-pub(crate) enum Error {
+pub enum Error {
     #[error("{0}")]
     CheckoutConflicts(String),
 
@@ -59,7 +65,7 @@ pub(crate) enum Error {
 }
 
 /// Stack transaction state.
-pub(crate) struct StackTransaction<'repo> {
+pub struct StackTransaction<'repo> {
     stack: Stack<'repo>,
     ui: TransactionUserInterface,
     options: TransactionOptions,
@@ -106,7 +112,7 @@ enum PushStatus {
 ///
 /// Wraps [`StackTransaction`] to ensure [`ExecuteContext::execute()`] is called after
 /// [`TransactionBuilder::transact()`].
-pub(crate) struct ExecuteContext<'repo>(StackTransaction<'repo>);
+pub struct ExecuteContext<'repo>(StackTransaction<'repo>);
 
 impl<'repo> ExecuteContext<'repo> {
     /// Execute the transaction.
@@ -115,7 +121,7 @@ impl<'repo> ExecuteContext<'repo> {
     /// stack, index, and worktree state will be rolled back.
     ///
     /// A new `Stack` instance is returned.
-    pub(crate) fn execute(self, reflog_msg: &str) -> Result<Stack<'repo>> {
+    pub fn execute(self, reflog_msg: &str) -> Result<Stack<'repo>> {
         let transaction = self.0;
 
         // Check consistency
@@ -189,10 +195,62 @@ impl<'repo> ExecuteContext<'repo> {
             )
         };
 
-        if options.set_head && options.use_index_and_worktree {
-            if !options.allow_bad_head {
-                stack.check_head_top_mismatch()?;
+        if options.set_head && options.use_index_and_worktree && !options.allow_bad_head {
+            stack.check_head_top_mismatch()?;
+        }
+
+        // Commit updated stack state
+        let conflict_msg;
+        let state_reflog_msg = if has_conflicts {
+            conflict_msg = format!("{reflog_msg} (CONFLICT)");
+            &conflict_msg
+        } else {
+            reflog_msg
+        };
+        let stack_ref = repo.find_reference(stack.get_stack_refname())?;
+        let branch_ref_name = stack.get_branch_refname().to_owned();
+        let prev_state_commit = stack_ref
+            .into_fully_peeled_id()?
+            .object()?
+            .try_into_commit()?;
+        let old_state_id = prev_state_commit.id;
+        let state = stack.state_mut();
+        for (patchname, maybe_patch) in &updated_patches {
+            if let Some(patch) = maybe_patch {
+                state.patches.insert(patchname.clone(), patch.clone());
+            } else {
+                state.patches.remove(patchname);
             }
+        }
+        state.prev = Some(Rc::new(prev_state_commit));
+        state.head = trans_head.clone();
+        state.applied = applied;
+        state.unapplied = unapplied;
+        state.hidden = hidden;
+        let state_commit_id = state.commit(repo, None, state_reflog_msg)?;
+
+        // The pre hook may reject the transaction. It is run before the worktree is
+        // changed such that nothing needs to be rolled back, and outside of the
+        // critical section such that a hook that hangs may be interrupted.
+        hook::run_stack_transaction_hook(
+            repo,
+            StackTransactionHook::Pre,
+            stack.get_branch_name(),
+            reflog_msg,
+            old_state_id,
+            state_commit_id,
+        )
+        .map_err(|err| {
+            if let Err(print_err) = ui.print_rolled_back(stack_top_patchname.as_ref()) {
+                return print_err;
+            }
+            anyhow!(
+                "{err:#};\n\
+                 command aborted (all changes rolled back)"
+            )
+        })?;
+
+        if options.set_head && options.use_index_and_worktree {
             checkout(
                 repo,
                 &options,
@@ -205,35 +263,6 @@ impl<'repo> ExecuteContext<'repo> {
         }
 
         crate::signal::critical(|| {
-            // Commit updated stack state
-            let conflict_msg;
-            let state_reflog_msg = if has_conflicts {
-                conflict_msg = format!("{reflog_msg} (CONFLICT)");
-                &conflict_msg
-            } else {
-                reflog_msg
-            };
-            let stack_ref = repo.find_reference(stack.get_stack_refname())?;
-            let branch_ref_name = stack.get_branch_refname().to_owned();
-            let prev_state_commit = stack_ref
-                .into_fully_peeled_id()?
-                .object()?
-                .try_into_commit()?;
-            let state = stack.state_mut();
-            for (patchname, maybe_patch) in &updated_patches {
-                if let Some(patch) = maybe_patch {
-                    state.patches.insert(patchname.clone(), patch.clone());
-                } else {
-                    state.patches.remove(patchname);
-                }
-            }
-            state.prev = Some(Rc::new(prev_state_commit));
-            state.head = trans_head.clone();
-            state.applied = applied;
-            state.unapplied = unapplied;
-            state.hidden = hidden;
-            let state_commit_id = state.commit(repo, None, state_reflog_msg)?;
-
             // Update various refs as a single transaction. This reference transaction is
             // not quite atomic--it is possible for some, but not all references to be
             // updated--but atomic enough in practice for our purposes.
@@ -306,6 +335,15 @@ impl<'repo> ExecuteContext<'repo> {
         })
         .map_err(|e| rollback(trans_head_tree_id, e))?;
 
+        hook::run_stack_transaction_hook(
+            repo,
+            StackTransactionHook::Post,
+            stack.get_branch_name(),
+            reflog_msg,
+            old_state_id,
+            state_commit_id,
+        )?;
+
         if let Some(err) = error {
             Err(err)
         } else {
@@ -354,17 +392,17 @@ fn checkout(
 
 impl<'repo> StackTransaction<'repo> {
     /// Get an immutable reference to the original stack.
-    pub(crate) fn stack(&self) -> &Stack<'repo> {
+    pub fn stack(&self) -> &Stack<'repo> {
         &self.stack
     }
 
     /// Get a reference to the repo.
-    pub(crate) fn repo(&self) -> &'repo gix::Repository {
+    pub fn repo(&self) -> &'repo gix::Repository {
         self.stack.repo
     }
 
     /// Reset stack to a previous stack state.
-    pub(crate) fn reset_to_state(&mut self, state: StackState<'repo>) -> Result<()> {
+    pub fn reset_to_state(&mut self, state: StackState<'repo>) -> Result<()> {
         for pn in self.all_patches().cloned().collect::<Vec<_>>() {
             self.updated_patches.insert(pn, None);
         }
@@ -468,11 +506,7 @@ impl<'repo> StackTransaction<'repo> {
     ///
     /// Any notes associated with the patch's previous commit are copied to the new
     /// commit.
-    pub(crate) fn update_patch(
-        &mut self,
-        patchname: &PatchName,
-        commit_id: gix::ObjectId,
-    ) -> Result<()> {
+    pub fn update_patch(&mut self, patchname: &PatchName, commit_id: gix::ObjectId) -> Result<()> {
         let commit = self.stack.repo.find_commit(commit_id)?;
         let old_commit = self.get_patch_commit(patchname);
         // Failure to copy is okay. The old commit may not have a note to copy.
@@ -481,30 +515,56 @@ impl<'repo> StackTransaction<'repo> {
             .stupid()
             .notes_copy(old_commit.id, commit_id)
             .ok();
-        self.updated_patches.insert(
-            patchname.clone(),
-            Some(PatchState {
-                commit: Rc::new(commit),
-            }),
-        );
+        let patch = PatchState {
+            commit: Rc::new(commit),
+            ..self.get_patch(patchname).clone()
+        };
+        self.updated_patches.insert(patchname.clone(), Some(patch));
         self.ui.print_updated(patchname, self.applied())?;
         Ok(())
     }
 
+    /// Replace the set of labels of a patch.
+    pub fn set_patch_labels(
+        &mut self,
+        patchname: &PatchName,
+        labels: BTreeSet<String>,
+    ) -> Result<()> {
+        let patch = PatchState {
+            labels,
+            ..self.get_patch(patchname).clone()
+        };
+        self.updated_patches.insert(patchname.clone(), Some(patch));
+        Ok(())
+    }
+
+    /// Set or, when `value` is `None`, remove a metadata value of a patch.
+    pub fn set_patch_metadata(
+        &mut self,
+        patchname: &PatchName,
+        key: &str,
+        value: Option<&str>,
+    ) -> Result<()> {
+        let mut patch = self.get_patch(patchname).clone();
+        if let Some(value) = value {
+            patch.metadata.insert(key.to_string(), value.to_string());
+        } else {
+            patch.metadata.remove(key);
+        }
+        self.updated_patches.insert(patchname.clone(), Some(patch));
+        Ok(())
+    }
+
     /// Add new patch to the top of the stack.
     ///
     /// The commit for the new patch must be parented by the former top commit of the
     /// stack.
-    pub(crate) fn new_applied(&mut self, patchname: &PatchName, oid: gix::ObjectId) -> Result<()> {
+    pub fn new_applied(&mut self, patchname: &PatchName, oid: gix::ObjectId) -> Result<()> {
         let commit = self.stack.repo.find_commit(oid)?;
         assert_eq!(commit.parent_ids().next().unwrap().detach(), self.top().id);
         self.applied.push(patchname.clone());
-        self.updated_patches.insert(
-            patchname.clone(),
-            Some(PatchState {
-                commit: Rc::new(commit),
-            }),
-        );
+        self.updated_patches
+            .insert(patchname.clone(), Some(PatchState::new(commit)?));
         self.ui.print_pushed(patchname, PushStatus::New, true)?;
         Ok(())
     }
@@ -512,7 +572,7 @@ impl<'repo> StackTransaction<'repo> {
     /// Add new unapplied patch to the stack.
     ///
     /// The new patch may be pushed to any position in the unapplied list.
-    pub(crate) fn new_unapplied(
+    pub fn new_unapplied(
         &mut self,
         patchname: &PatchName,
         commit_id: gix::ObjectId,
@@ -520,12 +580,8 @@ impl<'repo> StackTransaction<'repo> {
     ) -> Result<()> {
         let commit = self.stack.repo.find_commit(commit_id)?;
         self.unapplied.insert(insert_pos, patchname.clone());
-        self.updated_patches.insert(
-            patchname.clone(),
-            Some(PatchState {
-                commit: Rc::new(commit),
-            }),
-        );
+        self.updated_patches
+            .insert(patchname.clone(), Some(PatchState::new(commit)?));
         self.ui.print_popped(&[patchname.clone()])?;
         Ok(())
     }
@@ -558,14 +614,11 @@ impl<'repo> StackTransaction<'repo> {
             PushStatus::Unmodified
         } else {
             let author = patch_commit.author_strict()?;
-            let default_committer = repo.get_committer()?;
-            let committer = if self.options.committer_date_is_author_date {
-                let mut committer = default_committer.to_owned();
-                committer.time = author.time;
-                committer
-            } else {
-                default_committer.to_owned()
-            };
+            let committer = repo.get_rewrite_committer(
+                Some(patch_commit),
+                author.time,
+                self.options.committer_date_is_author_date,
+            )?;
             let message = patch_commit.message_ex();
             let parent_ids = [self.top().id];
             let new_commit_id = repo.commit_ex(
@@ -580,12 +633,11 @@ impl<'repo> StackTransaction<'repo> {
             repo.stupid()
                 .notes_copy(patch_commit.id, new_commit_id)
                 .ok();
-            self.updated_patches.insert(
-                patchname.clone(),
-                Some(PatchState {
-                    commit: Rc::new(commit),
-                }),
-            );
+            let patch = PatchState {
+                commit: Rc::new(commit),
+                ..self.get_patch(patchname).clone()
+            };
+            self.updated_patches.insert(patchname.clone(), Some(patch));
 
             PushStatus::Modified
         };
@@ -643,7 +695,7 @@ impl<'repo> StackTransaction<'repo> {
     /// Perform push and pop operations to achieve a new stack ordering.
     ///
     /// The current ordering is maintained for any patch list that is not provided.
-    pub(crate) fn reorder_patches(
+    pub fn reorder_patches(
         &mut self,
         applied: Option<&[PatchName]>,
         unapplied: Option<&[PatchName]>,
@@ -692,7 +744,7 @@ impl<'repo> StackTransaction<'repo> {
     // If the chosen `to_commit` patches are not currently the bottommost patches in the
     // stack, pops and pushes will be performed to move them to the bottom of the stack.
     // This may result in merge conflicts.
-    pub(crate) fn commit_patches(&mut self, to_commit: &[PatchName]) -> Result<()> {
+    pub fn commit_patches(&mut self, to_commit: &[PatchName]) -> Result<()> {
         let num_common = self
             .applied()
             .iter()
@@ -735,12 +787,8 @@ impl<'repo> StackTransaction<'repo> {
         let mut new_applied: Vec<_> = Vec::with_capacity(self.applied.len());
         for (patchname, commit_id) in patches {
             let commit = self.stack.repo.find_commit(commit_id)?;
-            self.updated_patches.insert(
-                patchname.clone(),
-                Some(PatchState {
-                    commit: Rc::new(commit),
-                }),
-            );
+            self.updated_patches
+                .insert(patchname.clone(), Some(PatchState::new(commit)?));
             new_applied.push(patchname.clone());
         }
         new_applied.append(&mut self.applied);
@@ -752,7 +800,7 @@ impl<'repo> StackTransaction<'repo> {
     ///
     /// Hidden patches are not shown by default by `stg series` and are excluded from
     /// several other operations.
-    pub(crate) fn hide_patches(&mut self, to_hide: &[PatchName]) -> Result<()> {
+    pub fn hide_patches(&mut self, to_hide: &[PatchName]) -> Result<()> {
         let applied: Vec<PatchName> = self
             .applied
             .iter()
@@ -775,7 +823,7 @@ impl<'repo> StackTransaction<'repo> {
     }
 
     /// Move hidden patches to the unapplied list.
-    pub(crate) fn unhide_patches(&mut self, to_unhide: &[PatchName]) -> Result<()> {
+    pub fn unhide_patches(&mut self, to_unhide: &[PatchName]) -> Result<()> {
         let unapplied: Vec<PatchName> = self
             .unapplied
             .iter()
@@ -799,7 +847,7 @@ impl<'repo> StackTransaction<'repo> {
     ///
     /// An error will be returned if either the old patchname does not exist or if the
     /// new patchname conflicts with an existing patch.
-    pub(crate) fn rename_patch(
+    pub fn rename_patch(
         &mut self,
         old_patchname: &PatchName,
         new_patchname: &PatchName,
@@ -841,7 +889,7 @@ impl<'repo> StackTransaction<'repo> {
     ///
     /// Deleted patches' commits become disconnected from the regular git history and
     /// are thus subject to eventual garbage collection.
-    pub(crate) fn delete_patches<F>(&mut self, should_delete: F) -> Result<Vec<PatchName>>
+    pub fn delete_patches<F>(&mut self, should_delete: F) -> Result<Vec<PatchName>>
     where
         F: Fn(&PatchName) -> bool,
     {
@@ -911,7 +959,7 @@ impl<'repo> StackTransaction<'repo> {
     ///
     /// The `should_pop` closure should return true for each patch name to be popped and
     /// false for patches that are to remain applied.
-    pub(crate) fn pop_patches<F>(&mut self, should_pop: F) -> Result<Vec<PatchName>>
+    pub fn pop_patches<F>(&mut self, should_pop: F) -> Result<Vec<PatchName>>
     where
         F: Fn(&PatchName) -> bool,
     {
@@ -958,11 +1006,13 @@ impl<'repo> StackTransaction<'repo> {
     /// whether the patches' changes have already been merged into the stack's base
     /// tree. Patches that are determined to have already been merged will still be
     /// pushed successfully, but their diff will be empty.
-    pub(crate) fn push_patches<P>(&mut self, patchnames: &[P], check_merged: bool) -> Result<()>
+    pub fn push_patches<P>(&mut self, patchnames: &[P], check_merged: bool) -> Result<()>
     where
         P: AsRef<PatchName>,
     {
         let stupid = self.stack.repo.stupid();
+        let base_tree_id = self.top().tree_id()?.detach();
+        let precomputed = self.apply_independent_patches(patchnames, &stupid, base_tree_id)?;
         stupid.with_temp_index(|stupid_temp| {
             let mut temp_index_tree_id: Option<gix::ObjectId> = None;
 
@@ -982,6 +1032,7 @@ impl<'repo> StackTransaction<'repo> {
                     patchname,
                     already_merged,
                     is_last,
+                    precomputed[i].map(|tree_id| (base_tree_id, tree_id)),
                     stupid_temp,
                     &mut temp_index_tree_id,
                 )?;
@@ -991,18 +1042,55 @@ impl<'repo> StackTransaction<'repo> {
         })
     }
 
+    /// Apply the diffs of patches to be pushed to the current top tree in parallel.
+    ///
+    /// Only the diffs of patches that do not change files changed by the patches
+    /// pushed before them are applied. The resulting trees are later merged with the
+    /// patches' actual new parents' trees when the patches are pushed. The number of
+    /// worker threads is determined by `stgit.push.jobs`, defaulting to a single
+    /// worker. Nothing is done when there is only a single worker or patch.
+    fn apply_independent_patches<P>(
+        &self,
+        patchnames: &[P],
+        stupid: &StupidContext,
+        base_tree_id: gix::ObjectId,
+    ) -> Result<Vec<Option<gix::ObjectId>>>
+    where
+        P: AsRef<PatchName>,
+    {
+        let jobs = self
+            .stack
+            .repo
+            .config_snapshot()
+            .integer("stgit.push.jobs")
+            .map_or(1, |jobs| usize::try_from(jobs).unwrap_or(1));
+        if jobs <= 1 || patchnames.len() <= 1 {
+            return Ok(vec![None; patchnames.len()]);
+        }
+
+        let mut diffs = Vec::with_capacity(patchnames.len());
+        for patchname in patchnames {
+            let patch_commit = self.get_patch_commit(patchname.as_ref());
+            diffs.push((
+                patch_commit.get_parent_commit()?.tree_id()?.detach(),
+                patch_commit.tree_id()?.detach(),
+            ));
+        }
+        parallel::apply_independent_diffs(stupid, base_tree_id, &diffs, jobs)
+    }
+
     fn push_patch(
         &mut self,
         patchname: &PatchName,
         already_merged: bool,
         is_last: bool,
+        precomputed: Option<(gix::ObjectId, gix::ObjectId)>,
         stupid_temp: &StupidContext,
         temp_index_tree_id: &mut Option<gix::ObjectId>,
     ) -> Result<()> {
         let repo = self.stack.repo;
         let config = repo.config_snapshot();
         let stupid = repo.stupid();
-        let default_committer = repo.get_committer()?;
         let patch_commit = self.get_patch_commit(patchname).clone();
         let old_parent = patch_commit.get_parent_commit()?;
         let new_parent = self.top().clone();
@@ -1011,6 +1099,8 @@ impl<'repo> StackTransaction<'repo> {
         let new_parent_ref = new_parent.decode()?;
 
         let mut push_status = PushStatus::Unmodified;
+        let mut rejected = false;
+        let mut conflict_attributions = Vec::new();
 
         let new_tree_id = if already_merged {
             push_status = PushStatus::AlreadyMerged;
@@ -1021,6 +1111,14 @@ impl<'repo> StackTransaction<'repo> {
             new_parent_ref.tree()
         } else if new_parent_ref.tree() == patch_commit_ref.tree() {
             patch_commit_ref.tree()
+        } else if let Some(tree_id) = precomputed
+            .map(|(base_tree_id, tree_id)| {
+                parallel::merge_disjoint_trees(repo, base_tree_id, new_parent_ref.tree(), tree_id)
+            })
+            .transpose()?
+            .flatten()
+        {
+            tree_id
         } else {
             let (ours, theirs) = if temp_index_tree_id == &Some(patch_commit_ref.tree()) {
                 (patch_commit_ref.tree(), new_parent_ref.tree())
@@ -1061,6 +1159,32 @@ impl<'repo> StackTransaction<'repo> {
                     conflicts: false,
                 }
                 .into());
+            } else if self.options.reject_push_conflicts {
+                let new_parent_tree_id = new_parent_ref.tree();
+                if stupid
+                    .read_tree_checkout(self.current_tree_id, new_parent_tree_id)
+                    .is_err()
+                {
+                    return Err(Error::TransactionHalt {
+                        msg: "index/worktree dirty".to_string(),
+                        conflicts: false,
+                    }
+                    .into());
+                }
+                self.current_tree_id = new_parent_tree_id;
+
+                if stupid.apply_treediff_with_reject(base, patch_commit_ref.tree())? {
+                    let tree_id = stupid.write_tree()?;
+                    self.current_tree_id = tree_id;
+                    push_status = PushStatus::Modified;
+                    tree_id
+                } else {
+                    // The hunks that did apply are left in the worktree, to be
+                    // refreshed into the patch along with the resolved rejects.
+                    rejected = true;
+                    push_status = PushStatus::Conflict;
+                    new_parent_tree_id
+                }
             } else {
                 if stupid
                     .read_tree_checkout(self.current_tree_id, ours)
@@ -1088,6 +1212,19 @@ impl<'repo> StackTransaction<'repo> {
                     }
                     Ok(false) => {
                         push_status = PushStatus::Conflict;
+                        let applied: Vec<(PatchName, gix::ObjectId)> = self
+                            .applied
+                            .iter()
+                            .map(|pn| (pn.clone(), self.get_patch_commit(pn).id))
+                            .collect();
+                        conflict_attributions = conflicts::attribute_conflicts(
+                            repo,
+                            self.stack.base().id,
+                            new_parent.id,
+                            &applied,
+                            ours == new_parent_ref.tree(),
+                        )
+                        .unwrap_or_default();
                         ours
                     }
                     Err(e) => {
@@ -1103,13 +1240,11 @@ impl<'repo> StackTransaction<'repo> {
 
         if new_tree_id != patch_commit_ref.tree() || new_parent.id != old_parent.id {
             let author = patch_commit.author_strict()?;
-            let committer = if self.options.committer_date_is_author_date {
-                let mut committer = default_committer.to_owned();
-                committer.time = author.time;
-                committer
-            } else {
-                default_committer.to_owned()
-            };
+            let committer = repo.get_rewrite_committer(
+                Some(&patch_commit),
+                author.time,
+                self.options.committer_date_is_author_date,
+            )?;
             let commit_id = repo.commit_ex(
                 &author,
                 &committer,
@@ -1130,8 +1265,11 @@ impl<'repo> StackTransaction<'repo> {
                 push_status = PushStatus::Empty;
             }
 
-            self.updated_patches
-                .insert(patchname.clone(), Some(PatchState { commit }));
+            let patch = PatchState {
+                commit,
+                ..self.get_patch(patchname).clone()
+            };
+            self.updated_patches.insert(patchname.clone(), Some(patch));
         }
 
         if push_status == PushStatus::Conflict {
@@ -1147,8 +1285,21 @@ impl<'repo> StackTransaction<'repo> {
         self.applied.push(patchname.clone());
 
         self.ui.print_pushed(patchname, push_status, is_last)?;
+        self.ui
+            .print_conflict_attributions(&conflict_attributions)?;
 
-        if push_status == PushStatus::Conflict {
+        if rejected {
+            Err(Error::TransactionHalt {
+                msg: format!(
+                    "patch `{patchname}` does not apply cleanly; \
+                     rejected hunks were written to `*.rej` files. \
+                     Apply them manually then refresh or \
+                     undo the operation with `stg undo --hard`."
+                ),
+                conflicts: true,
+            }
+            .into())
+        } else if push_status == PushStatus::Conflict {
             Err(Error::TransactionHalt {
                 msg: "merge conflicts; \
                       resolve conflicts manually then refresh or \
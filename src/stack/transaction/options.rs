@@ -4,6 +4,7 @@
 pub(super) struct TransactionOptions {
     pub(super) conflict_mode: ConflictMode,
     pub(super) allow_push_conflicts: Option<bool>,
+    pub(super) reject_push_conflicts: bool,
     pub(super) discard_changes: bool,
     pub(super) use_index_and_worktree: bool,
     pub(super) set_head: bool,
@@ -16,6 +17,7 @@ impl Default for TransactionOptions {
         Self {
             conflict_mode: ConflictMode::Disallow,
             allow_push_conflicts: None,
+            reject_push_conflicts: false,
             discard_changes: false,
             use_index_and_worktree: false,
             set_head: true,
@@ -0,0 +1,199 @@
+// SPDX-License-Identifier: GPL-2.0-only
+
+//! Parallel computation of the trees of independent patches being pushed.
+//!
+//! Each pushed patch's new tree normally depends on the new tree of the patch pushed
+//! before it, such that patches' diffs must be applied one after another. However,
+//! when a patch does not change any of the files changed by the patches pushed before
+//! it, applying its diff to the starting top tree yields the same changes as applying
+//! it to its actual new parent's tree. The former may therefore be done up front, for
+//! many patches at once, in worker threads. The resulting trees are then combined with
+//! the actual new parents' trees with [`merge_disjoint_trees()`], which is cheap since
+//! it does not involve running git.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::{Path, PathBuf},
+};
+
+use anyhow::Result;
+use bstr::BString;
+use gix::objs::tree::{Entry, EntryMode};
+
+use crate::stupid::StupidContext;
+
+/// The files changed by a patch and its tree as applied to the base tree, if any.
+type DiffOutcome = (Vec<PathBuf>, Option<gix::ObjectId>);
+
+type TreeEntries = BTreeMap<BString, (EntryMode, gix::ObjectId)>;
+
+/// Apply the diffs of independent patches to a common base tree in parallel.
+///
+/// Each element of `diffs` is a patch's old parent tree and the patch's tree, in push
+/// order. A patch is independent if it does not change any of the files changed by the
+/// patches preceding it. The returned vector has an element for each patch, which is
+/// the tree resulting from applying the patch's diff to `base_tree_id` if the patch is
+/// independent and its diff applies cleanly, or `None` otherwise.
+pub(super) fn apply_independent_diffs(
+    stupid: &StupidContext,
+    base_tree_id: gix::ObjectId,
+    diffs: &[(gix::ObjectId, gix::ObjectId)],
+    jobs: usize,
+) -> Result<Vec<Option<gix::ObjectId>>> {
+    let jobs = jobs.clamp(1, diffs.len().max(1));
+
+    let mut outcomes: Vec<(usize, Result<DiffOutcome>)> = std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..jobs)
+            .map(|job| {
+                let stupid = stupid.for_thread();
+                scope.spawn(move || {
+                    (job..diffs.len())
+                        .step_by(jobs)
+                        .map(|i| (i, apply_diff(&stupid, base_tree_id, diffs[i])))
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().expect("push worker thread panicked"))
+            .collect()
+    });
+    outcomes.sort_by_key(|(i, _)| *i);
+
+    let mut changed_files: BTreeSet<PathBuf> = BTreeSet::new();
+    let mut trees = Vec::with_capacity(diffs.len());
+    for (_, outcome) in outcomes {
+        let (files, maybe_tree_id) = outcome?;
+        let is_independent = files.iter().all(|path| !changed_files.contains(path));
+        trees.push(maybe_tree_id.filter(|_| is_independent));
+        changed_files.extend(files);
+    }
+    Ok(trees)
+}
+
+/// Apply the diff between two trees to a base tree.
+///
+/// Returns the files changed by the diff along with the resulting tree, if the diff
+/// applies cleanly.
+fn apply_diff(
+    stupid: &StupidContext,
+    base_tree_id: gix::ObjectId,
+    (old_tree_id, new_tree_id): (gix::ObjectId, gix::ObjectId),
+) -> Result<DiffOutcome> {
+    let files: Vec<PathBuf> = stupid
+        .diff_tree_files(old_tree_id, new_tree_id)?
+        .iter()
+        .map(Path::to_path_buf)
+        .collect();
+    let maybe_tree_id = if files.is_empty() {
+        None
+    } else if old_tree_id == base_tree_id {
+        Some(new_tree_id)
+    } else {
+        stupid.with_temp_index(|stupid_temp| {
+            stupid_temp.read_tree(base_tree_id)?;
+            if stupid_temp.apply_treediff_to_index(old_tree_id, new_tree_id, true)? {
+                Ok(stupid_temp.write_tree().ok())
+            } else {
+                Ok(None)
+            }
+        })?
+    };
+    Ok((files, maybe_tree_id))
+}
+
+/// Merge two trees whose changes relative to a base tree do not overlap.
+///
+/// Returns `None` if both `ours_tree_id` and `theirs_tree_id` change the same path
+/// relative to `base_tree_id`, in which case a real merge is needed.
+pub(super) fn merge_disjoint_trees(
+    repo: &gix::Repository,
+    base_tree_id: gix::ObjectId,
+    ours_tree_id: gix::ObjectId,
+    theirs_tree_id: gix::ObjectId,
+) -> Result<Option<gix::ObjectId>> {
+    merge_trees(repo, Some(base_tree_id), ours_tree_id, theirs_tree_id)
+}
+
+fn merge_trees(
+    repo: &gix::Repository,
+    base_tree_id: Option<gix::ObjectId>,
+    ours_tree_id: gix::ObjectId,
+    theirs_tree_id: gix::ObjectId,
+) -> Result<Option<gix::ObjectId>> {
+    if Some(theirs_tree_id) == base_tree_id || ours_tree_id == theirs_tree_id {
+        return Ok(Some(ours_tree_id));
+    } else if Some(ours_tree_id) == base_tree_id {
+        return Ok(Some(theirs_tree_id));
+    }
+
+    let empty_tree_id = gix::ObjectId::empty_tree(repo.object_hash());
+    let base = read_tree_entries(repo, base_tree_id)?;
+    let ours = read_tree_entries(repo, Some(ours_tree_id))?;
+    let theirs = read_tree_entries(repo, Some(theirs_tree_id))?;
+    let names: BTreeSet<&BString> = base
+        .keys()
+        .chain(ours.keys())
+        .chain(theirs.keys())
+        .collect();
+
+    let mut entries = Vec::with_capacity(names.len());
+    for name in names {
+        let (base_entry, ours_entry, theirs_entry) =
+            (base.get(name), ours.get(name), theirs.get(name));
+        let merged_entry = if theirs_entry == base_entry || ours_entry == theirs_entry {
+            ours_entry.copied()
+        } else if ours_entry == base_entry {
+            theirs_entry.copied()
+        } else if let (Some((EntryMode::Tree, ours_id)), Some((EntryMode::Tree, theirs_id))) =
+            (ours_entry, theirs_entry)
+        {
+            let base_id = base_entry
+                .filter(|(mode, _)| *mode == EntryMode::Tree)
+                .map(|(_, id)| *id);
+            if let Some(tree_id) = merge_trees(repo, base_id, *ours_id, *theirs_id)? {
+                Some((EntryMode::Tree, tree_id)).filter(|_| tree_id != empty_tree_id)
+            } else {
+                return Ok(None);
+            }
+        } else {
+            return Ok(None);
+        };
+        if let Some((mode, oid)) = merged_entry {
+            entries.push(Entry {
+                mode,
+                filename: name.clone(),
+                oid,
+            });
+        }
+    }
+
+    // Git sorts tree entries as if trees' names had a trailing slash.
+    entries.sort_by_cached_key(|entry| {
+        let mut key = entry.filename.clone();
+        if entry.mode == EntryMode::Tree {
+            key.push(b'/');
+        }
+        key
+    });
+    let tree_id = repo.write_object(gix::objs::Tree { entries })?.detach();
+    Ok(Some(tree_id))
+}
+
+fn read_tree_entries(
+    repo: &gix::Repository,
+    tree_id: Option<gix::ObjectId>,
+) -> Result<TreeEntries> {
+    let mut entries = TreeEntries::new();
+    if let Some(tree_id) = tree_id {
+        let tree = repo.find_object(tree_id)?.try_into_tree()?;
+        for entry in tree.decode()?.entries {
+            entries.insert(
+                entry.filename.to_owned(),
+                (entry.mode, entry.oid.to_owned()),
+            );
+        }
+    }
+    Ok(entries)
+}
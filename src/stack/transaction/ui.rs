@@ -189,6 +189,23 @@ impl TransactionUserInterface {
         Ok(())
     }
 
+    pub(super) fn print_conflict_attributions(
+        &self,
+        attributions: &[(String, Vec<PatchName>)],
+    ) -> Result<()> {
+        let mut output = self.output.borrow_mut();
+        for (path, patchnames) in attributions {
+            let plural = if patchnames.len() == 1 { "" } else { "es" };
+            let patchnames: Vec<String> = patchnames.iter().map(|pn| format!("`{pn}`")).collect();
+            writeln!(
+                output,
+                "hint: `{path}` conflicts with patch{plural} {}",
+                patchnames.join(", ")
+            )?;
+        }
+        Ok(())
+    }
+
     pub(super) fn print_top(&self, patchname: &PatchName) -> Result<()> {
         let mut output = self.output.borrow_mut();
         let mut color_spec = termcolor::ColorSpec::new();
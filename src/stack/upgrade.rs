@@ -9,9 +9,18 @@
 //! Stack state version 4 was introduced in StGit v1.0.
 //! Stack state version 3 was introduced in StGit v0.20.
 //! Stack state version 2 was introduced in StGit v0.13.
+//!
+//! Version 5 stack state has since been extended with optional per-patch fields.
+//! These additions do not change the format version: the fields are omitted when
+//! empty, state without them remains valid, and StGit versions predating them ignore
+//! the unknown fields when reading the state. Those older versions do not preserve
+//! the fields when they rewrite the state, though.
+//!
+//! - `meta`: key/value metadata of the patch, including its UUID.
+//! - `labels`: labels used to group and select the patch.
 
 use std::{
-    collections::BTreeMap,
+    collections::{BTreeMap, BTreeSet},
     fs::{remove_dir, remove_dir_all, remove_file, File, OpenOptions},
     io::{BufRead, BufReader},
     str::FromStr,
@@ -150,7 +159,14 @@ fn stack_upgrade_from_4(repo: &gix::Repository, branch_name: &str) -> Result<()>
                                     format!("converting `{oid_str}` for `{patchname}`")
                                 })?;
                             patch_list.push(patchname.clone());
-                            patches.insert(patchname, RawPatchState { oid: commit_id });
+                            patches.insert(
+                                patchname,
+                                RawPatchState {
+                                    oid: commit_id,
+                                    metadata: BTreeMap::new(),
+                                    labels: BTreeSet::new(),
+                                },
+                            );
                         }
                     } else {
                         return Err(anyhow!("malformed metadata"));
@@ -266,7 +282,14 @@ fn stack_upgrade_from_3(repo: &gix::Repository, branch_name: &str) -> Result<()>
                     .with_context(|| format!("converting `{}` to patchname", &pn))?;
                 patch_list.push(patchname.clone());
                 cleanup.push(format!("refs/patches/{branch_name}/{pn}.log"));
-                patches.insert(patchname, RawPatchState { oid: commit_id });
+                patches.insert(
+                    patchname,
+                    RawPatchState {
+                        oid: commit_id,
+                        metadata: BTreeMap::new(),
+                        labels: BTreeSet::new(),
+                    },
+                );
             }
         }
     }
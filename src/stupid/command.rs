@@ -5,11 +5,14 @@
 use std::{
     io::Write,
     process::{Child, Command, ExitStatus, Output, Stdio},
+    time::Instant,
 };
 
 use anyhow::{anyhow, Context, Result};
 use bstr::ByteSlice;
 
+use crate::timing;
+
 const GIT_EXEC_FAIL: &str = "could not execute `git`";
 
 pub(super) trait StupidCommand {
@@ -39,10 +42,14 @@ impl StupidCommand for Command {
     }
 
     fn output_git(&mut self) -> Result<Output> {
-        self.output().context(GIT_EXEC_FAIL)
+        let start = Instant::now();
+        let output = self.output().context(GIT_EXEC_FAIL);
+        timing::record_git(self, start.elapsed());
+        output
     }
 
     fn in_and_out(&mut self, input: &[u8]) -> Result<Output> {
+        let start = Instant::now();
         let mut child = self.stdin(Stdio::piped()).spawn_git()?;
         let mut stdin = child.stdin.take().unwrap();
         let output_result: Result<Output> = std::thread::scope(|scope| {
@@ -53,6 +60,7 @@ impl StupidCommand for Command {
                 .map_err(|_| anyhow!("panic while writing to stdin"))??;
             Ok(output_result?)
         });
+        timing::record_git(self, start.elapsed());
         output_result
     }
 }
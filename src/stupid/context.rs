@@ -6,10 +6,12 @@
 
 use std::{
     cell::RefCell,
+    collections::HashMap,
     ffi::{OsStr, OsString},
     io::Write,
-    path::Path,
+    path::{Path, PathBuf},
     process::{Command, Stdio},
+    time::Instant,
 };
 
 use anyhow::{anyhow, Context, Result};
@@ -17,20 +19,49 @@ use bstr::{BStr, BString, ByteSlice, ByteVec};
 
 use super::{
     command::{git_command_error, StupidCommand, StupidExitStatus, StupidOutput},
-    diff::DiffFiles,
+    diff::{DiffFiles, DiffNumStat, DiffRawEntry, DiffSummary},
     oid::parse_oid,
+    path::path_to_git_path,
     status::{StatusOptions, Statuses},
     tempindex::TempIndex,
     version::StupidVersion,
 };
+use crate::timing;
 
 /// Context for running stupid commands.
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug)]
 pub(crate) struct StupidContext<'repo, 'index> {
     pub(super) git_dir: Option<&'repo Path>,
     pub(super) work_dir: Option<&'repo Path>,
     pub(super) index_filename: Option<&'index Path>,
     pub(super) git_version: RefCell<Option<StupidVersion>>,
+    pub(super) longpaths: bool,
+}
+
+impl Default for StupidContext<'_, '_> {
+    /// Make a context for running commands outside of any repository.
+    ///
+    /// Only the system and global configuration are consulted to determine whether
+    /// long path support needs to be enabled.
+    fn default() -> Self {
+        Self {
+            git_dir: None,
+            work_dir: None,
+            index_filename: None,
+            git_version: RefCell::new(None),
+            longpaths: cfg!(windows)
+                && gix::config::File::from_globals().map_or(true, |config| use_longpaths(&config)),
+        }
+    }
+}
+
+/// Determine whether git's long path support needs to be enabled.
+///
+/// On Windows, long path support is enabled such that files in deeply nested
+/// directories may be checked out and updated. A `core.longpaths` value configured by
+/// the user is respected.
+pub(super) fn use_longpaths(config: &gix::config::File<'_>) -> bool {
+    cfg!(windows) && config.boolean_by_key("core.longpaths").is_none()
 }
 
 impl<'repo, 'index> StupidContext<'repo, 'index> {
@@ -50,28 +81,66 @@ impl<'repo, 'index> StupidContext<'repo, 'index> {
             work_dir: self.work_dir,
             index_filename: Some(temp_index.filename()),
             git_version: RefCell::new(None),
+            longpaths: self.longpaths,
         };
 
         f(&stupid_temp)
     }
+
+    /// Make a copy of this context for use by another thread.
+    ///
+    /// A [`StupidContext`] may not be shared between threads, but a copy may be sent
+    /// to another thread.
+    pub(crate) fn for_thread(&self) -> StupidContext<'repo, 'index> {
+        StupidContext {
+            git_dir: self.git_dir,
+            work_dir: self.work_dir,
+            index_filename: self.index_filename,
+            git_version: RefCell::new(None),
+            longpaths: self.longpaths,
+        }
+    }
+}
+
+/// Make a new command for running `git`.
+///
+/// Long path support is enabled when `longpaths` is true. See [`use_longpaths()`].
+fn new_git_command(longpaths: bool) -> Command {
+    let mut command = Command::new("git");
+    if longpaths {
+        command.args(["-c", "core.longpaths=true"]);
+    }
+    command
 }
 
 impl<'repo, 'index> StupidContext<'repo, 'index> {
     fn git(&self) -> Command {
-        let mut command = Command::new("git");
+        let mut command = new_git_command(self.longpaths);
         self.setup_git_env(&mut command);
         command
     }
 
     fn git_in_work_root(&self) -> Result<Command> {
-        let mut command = Command::new("git");
+        let mut command = new_git_command(self.longpaths);
         let work_dir = self
             .work_dir
             .expect("work_dir is required for this command");
         command.current_dir(work_dir);
         let cwd = std::env::current_dir()?;
-        let realpath =
-            |path| gix::path::realpath_opts(path, cwd.as_path(), gix::path::realpath::MAX_SYMLINKS);
+        let realpath = |path: &Path| -> Result<PathBuf> {
+            match gix::path::realpath_opts(path, cwd.as_path(), gix::path::realpath::MAX_SYMLINKS) {
+                Ok(path) => Ok(path),
+                // A symlink whose target cannot be found, e.g. on Windows when lacking
+                // the privilege to follow it, leaves the unresolved absolute path to be
+                // used instead.
+                Err(gix::path::realpath::Error::ReadLink(e))
+                    if e.kind() == std::io::ErrorKind::NotFound =>
+                {
+                    Ok(cwd.join(path))
+                }
+                Err(e) => Err(e.into()),
+            }
+        };
         if let Some(git_dir) = self.git_dir {
             let git_dir = realpath(git_dir)?;
             if let Some(index_filename) = self.index_filename {
@@ -111,6 +180,29 @@ impl<'repo, 'index> StupidContext<'repo, 'index> {
 }
 
 impl<'repo, 'index> StupidContext<'repo, 'index> {
+    /// Interactively select work tree hunks to add to the index with `git add --patch`.
+    ///
+    /// Path limits are relative to the current directory.
+    pub(crate) fn add_patch<SpecIter, SpecArg>(&self, pathspecs: Option<SpecIter>) -> Result<()>
+    where
+        SpecIter: IntoIterator<Item = SpecArg>,
+        SpecArg: AsRef<OsStr>,
+    {
+        let mut command = self.git();
+        command.args(["add", "--patch", "--"]);
+        if let Some(pathspecs) = pathspecs {
+            command.args(pathspecs);
+        }
+        let status = command
+            .status()
+            .context("could not execute `git add --patch`")?;
+        if status.success() {
+            Ok(())
+        } else {
+            Err(anyhow!("`git add --patch` failed"))
+        }
+    }
+
     /// Apply a patch (diff) to the specified index using `git apply --cached`.
     pub(crate) fn apply_to_index(&self, diff: &BStr) -> Result<()> {
         self.git_in_work_root()?
@@ -121,6 +213,31 @@ impl<'repo, 'index> StupidContext<'repo, 'index> {
         Ok(())
     }
 
+    /// Apply a user-edited patch (diff) to the specified index.
+    ///
+    /// Uses `git apply --cached --recount` such that the hunk headers need not be
+    /// adjusted after editing the hunks' lines.
+    pub(crate) fn apply_edited_to_index(&self, diff: &BStr) -> Result<()> {
+        self.git_in_work_root()?
+            .args(["apply", "--cached", "--recount"])
+            .stdout(Stdio::null())
+            .in_and_out(diff)?
+            .require_success("apply")?;
+        Ok(())
+    }
+
+    /// Apply a patch (diff) without context lines to the specified index.
+    ///
+    /// Uses `git apply --cached --unidiff-zero`.
+    pub(crate) fn apply_zero_context_to_index(&self, diff: &BStr) -> Result<()> {
+        self.git_in_work_root()?
+            .args(["apply", "--cached", "--unidiff-zero"])
+            .stdout(Stdio::null())
+            .in_and_out(diff)?
+            .require_success("apply")?;
+        Ok(())
+    }
+
     pub(crate) fn apply_to_worktree_and_index(
         &self,
         diff: &BStr,
@@ -194,6 +311,42 @@ impl<'repo, 'index> StupidContext<'repo, 'index> {
         Ok(apply_output.status.success())
     }
 
+    /// Apply diff between two trees to the index and worktree, rejecting failed hunks.
+    ///
+    /// Pipes `git diff-tree | git apply --index --reject`. Hunks that do not apply are
+    /// written to `*.rej` files next to the affected files in the worktree.
+    ///
+    /// Returns `true` if all hunks applied, `false` otherwise.
+    pub(crate) fn apply_treediff_with_reject(
+        &self,
+        tree1: gix::ObjectId,
+        tree2: gix::ObjectId,
+    ) -> Result<bool> {
+        if tree1 == tree2 {
+            return Ok(true);
+        }
+        let mut diff_tree_child = self
+            .git()
+            .args(["diff-tree", "--full-index", "--binary", "--patch"])
+            .arg(tree1.to_string())
+            .arg(tree2.to_string())
+            .arg("--")
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .spawn_git()?;
+
+        let apply_output = self
+            .git_in_work_root()?
+            .args(["apply", "--index", "--reject"])
+            .stdin(diff_tree_child.stdout.take().unwrap())
+            .stdout(Stdio::null())
+            .output_git()?
+            .require_code_less_than("apply", 128)?;
+
+        diff_tree_child.require_success("diff-tree")?;
+        Ok(apply_output.status.success())
+    }
+
     /// Apply path limited diff between to trees to specified index.
     pub(crate) fn apply_pathlimited_treediff_to_index<SpecIter, SpecArg>(
         &self,
@@ -306,6 +459,51 @@ impl<'repo, 'index> StupidContext<'repo, 'index> {
         }
     }
 
+    /// Attribute each line of a file to the commit that last modified it.
+    ///
+    /// Only commits reachable from `head` but not from `boundary` are considered.
+    /// Lines not modified in that range are attributed to the boundary commit.
+    /// The returned tuples contain the commit id and the line's content, without
+    /// its line terminator.
+    pub(crate) fn blame<OptIter, OptArg>(
+        &self,
+        boundary: gix::ObjectId,
+        head: gix::ObjectId,
+        path: &Path,
+        opts: OptIter,
+    ) -> Result<Vec<(gix::ObjectId, BString)>>
+    where
+        OptIter: IntoIterator<Item = OptArg>,
+        OptArg: AsRef<OsStr>,
+    {
+        let output = self
+            .git()
+            .args(["blame", "--line-porcelain"])
+            .args(opts)
+            .arg(format!("^{boundary}"))
+            .arg(head.to_string())
+            .arg("--")
+            .arg(path)
+            .stdin(Stdio::null())
+            .output_git()?
+            .require_success("blame")?;
+
+        let mut lines = Vec::new();
+        let mut commit_id: Option<gix::ObjectId> = None;
+        for line in output.stdout.lines() {
+            if let Some(content) = line.strip_prefix(b"\t") {
+                let commit_id = commit_id.take().ok_or_else(|| {
+                    anyhow!("unexpected blame output: content without commit header")
+                })?;
+                lines.push((commit_id, BString::from(content)));
+            } else if commit_id.is_none() {
+                let header_oid = line.split_str(" ").next().unwrap_or_default();
+                commit_id = Some(parse_oid(header_oid)?);
+            }
+        }
+        Ok(lines)
+    }
+
     /// Copy branch
     ///
     /// Copies branch ref, reflog, and `branch.<name>` config sections.
@@ -361,6 +559,48 @@ impl<'repo, 'index> StupidContext<'repo, 'index> {
         Ok(())
     }
 
+    /// Checkout a commit with a detached `HEAD`.
+    pub(crate) fn checkout_detached(&self, commit_id: gix::ObjectId) -> Result<()> {
+        self.git()
+            .args(["checkout", "--quiet", "--detach"])
+            .arg(commit_id.to_string())
+            .arg("--")
+            .stdout(Stdio::null())
+            .output_git()?
+            .require_success("checkout --detach")?;
+        Ok(())
+    }
+
+    /// Clone a repository into a new directory using `git clone`.
+    ///
+    /// The progress output of `git clone` is passed through to the user.
+    pub(crate) fn clone_repository(
+        &self,
+        repository: &OsStr,
+        directory: &Path,
+        branch_name: Option<&str>,
+    ) -> Result<()> {
+        let mut command = self.git();
+        command.arg("clone");
+        if let Some(branch_name) = branch_name {
+            command.args(["--branch", branch_name]);
+        }
+        let status = command
+            .arg("--")
+            .arg(repository)
+            .arg(directory)
+            .stdin(Stdio::null())
+            .stdout(Stdio::inherit())
+            .stderr(Stdio::inherit())
+            .status()
+            .context("could not execute `git clone`")?;
+        if status.success() {
+            Ok(())
+        } else {
+            Err(anyhow!("`git clone` failed"))
+        }
+    }
+
     /// Create a commit for the specified tree id using `git commit-tree`.
     ///
     /// The newly created commit id is returned.
@@ -423,6 +663,16 @@ impl<'repo, 'index> StupidContext<'repo, 'index> {
         parse_oid(&output.stdout)
     }
 
+    /// Add a value to a, possibly multi-valued, local config variable.
+    pub(crate) fn config_add(&self, name: &str, value: &str) -> Result<()> {
+        self.git()
+            .args(["config", "--local", "--add", name, value])
+            .stdout(Stdio::null())
+            .output_git()?
+            .require_success("config --add")?;
+        Ok(())
+    }
+
     pub(crate) fn config_remove_section(&self, section_name: &str) -> Result<()> {
         self.git()
             .args(["config", "--local", "--remove-section"])
@@ -448,7 +698,7 @@ impl<'repo, 'index> StupidContext<'repo, 'index> {
         &self,
         revspec: &str,
         pathspecs: Option<SpecIter>,
-        stat: bool,
+        summary: Option<DiffSummary>,
         use_color: bool,
         diff_opts: OptIter,
     ) -> Result<()>
@@ -460,8 +710,8 @@ impl<'repo, 'index> StupidContext<'repo, 'index> {
     {
         let mut command = self.git();
         command.arg("diff");
-        if stat {
-            command.args(["--stat", "--summary"]);
+        if let Some(summary) = summary {
+            command.args(summary.args());
         }
 
         command.arg(if use_color {
@@ -495,6 +745,31 @@ impl<'repo, 'index> StupidContext<'repo, 'index> {
         Ok(BString::from(output.stdout))
     }
 
+    /// Generate diff between the index and work tree using `git diff-files -p`.
+    ///
+    /// The diff is generated with the given number of context lines. Path limits are
+    /// relative to the current directory.
+    pub(crate) fn diff_files_patch<SpecIter, SpecArg>(
+        &self,
+        pathspecs: Option<SpecIter>,
+        context_lines: usize,
+    ) -> Result<BString>
+    where
+        SpecIter: IntoIterator<Item = SpecArg>,
+        SpecArg: AsRef<OsStr>,
+    {
+        let mut command = self.git();
+        command
+            .args(["diff-files", "-p", "--no-ext-diff"])
+            .arg(format!("-U{context_lines}"))
+            .arg("--");
+        if let Some(pathspecs) = pathspecs {
+            command.args(pathspecs);
+        }
+        let output = command.output_git()?.require_success("diff-files")?;
+        Ok(BString::from(output.stdout))
+    }
+
     /// Generate diff between specified tree and the working tree or index with `git diff-index`.
     pub(crate) fn diff_index(&self, tree_id: gix::ObjectId) -> Result<BString> {
         let output = self
@@ -517,9 +792,7 @@ impl<'repo, 'index> StupidContext<'repo, 'index> {
 
         if let Some(relative) = relative {
             let mut arg = BString::from("--relative=");
-            let relative = <[u8]>::from_os_str(relative.as_os_str())
-                .expect("relative path is valid UTF-8 on Windows");
-            arg.push_str(relative);
+            arg.push_str(path_to_git_path(relative)?);
             let arg = arg
                 .to_os_str()
                 .context("building --relative arg for `git diff-index`")?;
@@ -559,7 +832,7 @@ impl<'repo, 'index> StupidContext<'repo, 'index> {
             .args([tree1.to_string(), tree2.to_string()])
             .output_git()?
             .require_success("diff-tree")
-            .map(|output| DiffFiles::new(output.stdout))
+            .and_then(|output| DiffFiles::new(output.stdout))
     }
 
     /// Interactive diff-tree (for 'stg files').
@@ -569,10 +842,14 @@ impl<'repo, 'index> StupidContext<'repo, 'index> {
         tree2: gix::ObjectId,
         stat: bool,
         name_only: bool,
+        find_renames: bool,
         use_color: bool,
     ) -> Result<BString> {
         let mut command = self.git();
         command.args(["diff-tree", "-r"]);
+        if find_renames {
+            command.arg("-M");
+        }
         if stat {
             command.args(["--stat", "--summary"]);
         } else if name_only {
@@ -590,6 +867,147 @@ impl<'repo, 'index> StupidContext<'repo, 'index> {
         Ok(BString::from(output.stdout))
     }
 
+    /// Get files renamed between two trees using `git diff-tree -M`.
+    ///
+    /// Returns `(old_path, new_path)` pairs, relative to the root of the worktree.
+    pub(crate) fn diff_tree_renames(
+        &self,
+        tree1: gix::ObjectId,
+        tree2: gix::ObjectId,
+    ) -> Result<Vec<(BString, BString)>> {
+        let output = self
+            .git()
+            .args([
+                "diff-tree",
+                "-r",
+                "-M",
+                "--name-status",
+                "--diff-filter=R",
+                "-z",
+            ])
+            .args([tree1.to_string(), tree2.to_string()])
+            .output_git()?
+            .require_success("diff-tree")?;
+        let mut fields = output.stdout.split_str(b"\0");
+        let mut renames = Vec::new();
+        while let (Some(_status), Some(old_path), Some(new_path)) =
+            (fields.next(), fields.next(), fields.next())
+        {
+            renames.push((BString::from(old_path), BString::from(new_path)));
+        }
+        Ok(renames)
+    }
+
+    /// Get per-file changes between two trees using `git diff-tree --raw`.
+    ///
+    /// Renamed files are detected when `find_renames` is true.
+    pub(crate) fn diff_tree_raw(
+        &self,
+        tree1: gix::ObjectId,
+        tree2: gix::ObjectId,
+        find_renames: bool,
+    ) -> Result<Vec<DiffRawEntry>> {
+        let mut command = self.git();
+        command.args(["diff-tree", "-r", "--raw", "-z"]);
+        if find_renames {
+            command.arg("-M");
+        }
+        let output = command
+            .args([tree1.to_string(), tree2.to_string()])
+            .output_git()?
+            .require_success("diff-tree --raw")?;
+        let bad_output = || anyhow!("unexpected diff-tree --raw output");
+        let mut fields = output.stdout.split_str(b"\0").filter(|f| !f.is_empty());
+        let mut entries = Vec::new();
+        while let Some(info) = fields.next() {
+            let info = info.strip_prefix(b":").ok_or_else(bad_output)?;
+            let mut info = info.fields();
+            let (old_mode, new_mode, status) = match (
+                info.next(),
+                info.next(),
+                info.next(),
+                info.next(),
+                info.next(),
+            ) {
+                (Some(old_mode), Some(new_mode), Some(_), Some(_), Some(status)) => {
+                    (old_mode, new_mode, status)
+                }
+                _ => return Err(bad_output()),
+            };
+            let mode = |mode: &[u8]| {
+                if mode.iter().all(|b| *b == b'0') {
+                    None
+                } else {
+                    Some(mode.to_str_lossy().into_owned())
+                }
+            };
+            let status_char = char::from(*status.first().ok_or_else(bad_output)?);
+            let similarity = status[1..].to_str().ok().and_then(|s| s.parse().ok());
+            let first_path = BString::from(fields.next().ok_or_else(bad_output)?);
+            let (old_path, path) = if matches!(status_char, 'R' | 'C') {
+                let path = BString::from(fields.next().ok_or_else(bad_output)?);
+                (Some(first_path), path)
+            } else {
+                (None, first_path)
+            };
+            entries.push(DiffRawEntry {
+                old_mode: mode(old_mode),
+                new_mode: mode(new_mode),
+                status: status_char,
+                similarity,
+                old_path,
+                path,
+            });
+        }
+        Ok(entries)
+    }
+
+    /// Get per-file numbers of added and deleted lines between two trees.
+    pub(crate) fn diff_tree_numstat(
+        &self,
+        tree1: gix::ObjectId,
+        tree2: gix::ObjectId,
+    ) -> Result<Vec<DiffNumStat>> {
+        let output = self
+            .git()
+            .args(["diff-tree", "-r", "--numstat", "-z"])
+            .args([tree1.to_string(), tree2.to_string()])
+            .output_git()?
+            .require_success("diff-tree --numstat")?;
+        let mut stats = Vec::new();
+        for record in output.stdout.split_str(b"\0").filter(|r| !r.is_empty()) {
+            let mut fields = record.splitn_str(3, b"\t");
+            let (added, deleted, path) = match (fields.next(), fields.next(), fields.next()) {
+                (Some(added), Some(deleted), Some(path)) => (added, deleted, path),
+                _ => return Err(anyhow!("unexpected diff-tree --numstat output")),
+            };
+            let parse_count = |count: &[u8]| count.to_str().ok().and_then(|s| s.parse().ok());
+            stats.push(DiffNumStat {
+                added: parse_count(added),
+                deleted: parse_count(deleted),
+                path: BString::from(path),
+            });
+        }
+        Ok(stats)
+    }
+
+    /// Get one-line summary of changes between two trees using `git diff-tree --shortstat`.
+    ///
+    /// The summary is empty if the trees do not differ.
+    pub(crate) fn diff_tree_shortstat(
+        &self,
+        tree1: gix::ObjectId,
+        tree2: gix::ObjectId,
+    ) -> Result<String> {
+        let output = self
+            .git()
+            .args(["diff-tree", "-r", "--shortstat"])
+            .args([tree1.to_string(), tree2.to_string()])
+            .output_git()?
+            .require_success("diff-tree --shortstat")?;
+        Ok(output.stdout.to_str_lossy().trim().to_string())
+    }
+
     /// Generate diff between two trees using `git diff-tree -p`.
     pub(crate) fn diff_tree_patch<SpecIter, SpecArg, OptIter, OptArg>(
         &self,
@@ -642,6 +1060,53 @@ impl<'repo, 'index> StupidContext<'repo, 'index> {
         Ok(paths)
     }
 
+    /// Fetch the given refspecs from a remote.
+    pub(crate) fn fetch_refspecs<SpecIter, SpecArg>(
+        &self,
+        remote_name: &str,
+        refspecs: SpecIter,
+    ) -> Result<()>
+    where
+        SpecIter: IntoIterator<Item = SpecArg>,
+        SpecArg: AsRef<OsStr>,
+    {
+        self.git()
+            .args(["fetch", "--quiet"])
+            .arg(remote_name)
+            .args(refspecs)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .output_git()?
+            .require_success("fetch")?;
+        Ok(())
+    }
+
+    /// Fetch a single revision from another repository given by URL or path.
+    ///
+    /// No refs are updated. The id of the fetched object is determined from
+    /// `FETCH_HEAD`.
+    pub(crate) fn fetch_revision(
+        &self,
+        repository: &OsStr,
+        revision: &str,
+    ) -> Result<gix::ObjectId> {
+        self.git()
+            .args(["fetch", "--quiet", "--no-tags"])
+            .arg(repository)
+            .arg(revision)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .output_git()?
+            .require_success("fetch")?;
+        let output = self
+            .git()
+            .args(["rev-parse", "--verify", "--quiet", "FETCH_HEAD"])
+            .stdin(Stdio::null())
+            .output_git()?
+            .require_success("rev-parse")?;
+        parse_oid(&output.stdout)
+    }
+
     /// Run `git format-patch` with arbitrary arguments.
     pub(crate) fn format_patch<OptIter, OptArg>(&self, args: OptIter) -> Result<()>
     where
@@ -660,22 +1125,13 @@ impl<'repo, 'index> StupidContext<'repo, 'index> {
     }
 
     /// Show log in gitk
-    pub(crate) fn gitk<SpecIter, SpecArg>(
-        &self,
-        commit_id: gix::ObjectId,
-        pathspecs: Option<SpecIter>,
-    ) -> Result<()>
-    where
-        SpecIter: IntoIterator<Item = SpecArg>,
-        SpecArg: AsRef<OsStr>,
-    {
+    pub(crate) fn gitk(&self, commit_ids: &[gix::ObjectId], no_walk: bool) -> Result<()> {
         let mut command = Command::new("gitk");
         self.setup_git_env(&mut command);
-        command.arg(commit_id.to_string());
-        if let Some(pathspecs) = pathspecs {
-            command.arg("--");
-            command.args(pathspecs);
+        if no_walk {
+            command.arg("--no-walk=unsorted");
         }
+        command.args(commit_ids.iter().map(ToString::to_string));
 
         let output = command
             .stdout(Stdio::inherit())
@@ -710,19 +1166,17 @@ impl<'repo, 'index> StupidContext<'repo, 'index> {
     }
 
     /// Interactively show log
-    pub(crate) fn log<SpecIter, SpecArg>(
+    ///
+    /// When `no_walk` is true, only the given commits are shown, in the given order.
+    pub(crate) fn log(
         &self,
-        commit_id: gix::ObjectId,
-        pathspecs: Option<SpecIter>,
+        commit_ids: &[gix::ObjectId],
+        no_walk: bool,
         num_commits: Option<usize>,
         use_color: bool,
         full_index: bool,
         show_diff: bool,
-    ) -> Result<()>
-    where
-        SpecIter: IntoIterator<Item = SpecArg>,
-        SpecArg: AsRef<OsStr>,
-    {
+    ) -> Result<()> {
         let mut command = self.git_in_work_root()?;
         command.arg("log");
         if let Some(n) = num_commits {
@@ -738,10 +1192,13 @@ impl<'repo, 'index> StupidContext<'repo, 'index> {
         } else if !full_index {
             command.arg("--pretty=tformat:%C(auto)%h   %C(auto,blue)%aD   %C(auto)%s");
         }
-        command.arg(commit_id.to_string());
-        if let Some(pathspecs) = pathspecs {
-            command.arg("--");
-            command.args(pathspecs);
+        if no_walk {
+            command.arg("--no-walk=unsorted");
+        }
+        command.args(commit_ids.iter().map(ToString::to_string));
+        if show_diff {
+            // Patch increments recorded in stack states are not part of the state diff.
+            command.args(["--", ":(exclude)increments"]);
         }
         let output = command.stdout(Stdio::inherit()).output_git()?;
         if output.status.is_signal(13) {
@@ -817,6 +1274,29 @@ impl<'repo, 'index> StupidContext<'repo, 'index> {
         Ok(num_patches)
     }
 
+    /// Determine whether a commit is an ancestor of another with `git merge-base`.
+    pub(crate) fn is_ancestor(
+        &self,
+        ancestor_id: gix::ObjectId,
+        descendant_id: gix::ObjectId,
+    ) -> Result<bool> {
+        let output = self
+            .git()
+            .args(["merge-base", "--is-ancestor"])
+            .args([ancestor_id.to_string(), descendant_id.to_string()])
+            .output_git()?;
+        if output.status.success() {
+            Ok(true)
+        } else if output.status.code() == Some(1) {
+            Ok(false)
+        } else {
+            Err(git_command_error(
+                "merge-base --is-ancestor",
+                &output.stderr,
+            ))
+        }
+    }
+
     pub(crate) fn merge_bases(
         &self,
         id0: gix::ObjectId,
@@ -910,6 +1390,49 @@ impl<'repo, 'index> StupidContext<'repo, 'index> {
         Ok(())
     }
 
+    /// Compute stable patch ids for the given commits.
+    ///
+    /// The returned map is keyed by commit id. Commits that introduce no changes
+    /// relative to their parent have no patch id and are absent from the map.
+    pub(crate) fn patch_ids(
+        &self,
+        commit_ids: impl IntoIterator<Item = gix::ObjectId>,
+    ) -> Result<HashMap<gix::ObjectId, gix::ObjectId>> {
+        let mut input = Vec::new();
+        for commit_id in commit_ids {
+            writeln!(input, "{commit_id}")?;
+        }
+        if input.is_empty() {
+            return Ok(HashMap::new());
+        }
+        let diffs = self
+            .git()
+            .args([
+                "diff-tree",
+                "--patch",
+                "--no-color",
+                "--no-ext-diff",
+                "--stdin",
+            ])
+            .stdout(Stdio::piped())
+            .in_and_out(&input)?
+            .require_success("diff-tree")?
+            .stdout;
+        let output = self
+            .git()
+            .args(["patch-id", "--stable"])
+            .stdout(Stdio::piped())
+            .in_and_out(&diffs)?
+            .require_success("patch-id")?;
+        let mut patch_ids = HashMap::new();
+        for line in output.stdout.lines() {
+            if let Some((patch_id, commit_id)) = line.split_once_str(" ") {
+                patch_ids.insert(parse_oid(commit_id)?, parse_oid(patch_id)?);
+            }
+        }
+        Ok(patch_ids)
+    }
+
     /// Read content of a tree into specified index using `git read-tree`.
     pub(crate) fn read_tree(&self, tree_id: gix::ObjectId) -> Result<()> {
         self.git_in_work_root()?
@@ -953,6 +1476,24 @@ impl<'repo, 'index> StupidContext<'repo, 'index> {
         Ok(())
     }
 
+    /// Compare two commit ranges using `git range-diff`.
+    ///
+    /// The ranges are given as pairs of base and top commit ids.
+    pub(crate) fn range_diff(
+        &self,
+        old_range: (gix::ObjectId, gix::ObjectId),
+        new_range: (gix::ObjectId, gix::ObjectId),
+    ) -> Result<BString> {
+        let output = self
+            .git()
+            .args(["range-diff", "--no-color"])
+            .arg(format!("{}..{}", old_range.0, old_range.1))
+            .arg(format!("{}..{}", new_range.0, new_range.1))
+            .output_git()?
+            .require_success("range-diff")?;
+        Ok(BString::from(output.stdout))
+    }
+
     /// Pack unpacked objects
     pub(crate) fn repack(&self) -> Result<()> {
         self.git()
@@ -994,6 +1535,39 @@ impl<'repo, 'index> StupidContext<'repo, 'index> {
         Ok(oids)
     }
 
+    /// Get commits reachable from any of `include_ids`, but not from any of `exclude_ids`.
+    ///
+    /// The commit ids are passed to `git rev-list --stdin` to avoid overlong command
+    /// lines.
+    pub(crate) fn rev_list_excluding(
+        &self,
+        include_ids: impl IntoIterator<Item = gix::ObjectId>,
+        exclude_ids: impl IntoIterator<Item = gix::ObjectId>,
+    ) -> Result<Vec<gix::ObjectId>> {
+        let mut input = BString::from(Vec::new());
+        for id in include_ids {
+            input.push_str(format!("{id}\n"));
+        }
+        for id in exclude_ids {
+            input.push_str(format!("^{id}\n"));
+        }
+        let output = self
+            .git()
+            .args(["rev-list", "--stdin"])
+            .stdout(Stdio::piped())
+            .in_and_out(&input)?
+            .require_success("rev-list --stdin")?;
+        let mut oids: Vec<gix::ObjectId> = Vec::new();
+        for line in output
+            .stdout
+            .split_str("\n")
+            .filter(|line| !line.is_empty())
+        {
+            oids.push(parse_oid(line)?);
+        }
+        Ok(oids)
+    }
+
     /// Get cdup for current directory from `git rev-parse --show-cdup`.
     pub(crate) fn rev_parse_cdup(&self) -> Result<OsString> {
         let output = self
@@ -1010,6 +1584,28 @@ impl<'repo, 'index> StupidContext<'repo, 'index> {
         }
     }
 
+    /// Expire a reference's reflog entries unreachable from the reference.
+    ///
+    /// Uses `git reflog expire --expire-unreachable=now`. Nothing is done if the
+    /// reference does not have a reflog.
+    pub(crate) fn reflog_expire_unreachable(&self, refname: &str) -> Result<()> {
+        let exists = self
+            .git()
+            .args(["reflog", "exists", refname])
+            .output_git()?
+            .status
+            .success();
+        if !exists {
+            return Ok(());
+        }
+        self.git()
+            .args(["reflog", "expire", "--expire-unreachable=now", refname])
+            .stdout(Stdio::null())
+            .output_git()?
+            .require_success("reflog expire")?;
+        Ok(())
+    }
+
     pub(crate) fn rev_parse_symbolic_full_name(&self, name: &str) -> Result<Option<String>> {
         let output = self
             .git()
@@ -1061,7 +1657,7 @@ impl<'repo, 'index> StupidContext<'repo, 'index> {
         &self,
         oids: impl IntoIterator<Item = gix::ObjectId>,
         pathspecs: Option<SpecIter>,
-        stat: bool,
+        summary: Option<DiffSummary>,
         use_color: bool,
         diff_opts: OptIter,
     ) -> Result<()>
@@ -1073,8 +1669,8 @@ impl<'repo, 'index> StupidContext<'repo, 'index> {
     {
         let mut command = self.git();
         command.arg("show");
-        if stat {
-            command.args(["--stat", "--summary"]);
+        if let Some(summary) = summary {
+            command.args(summary.args());
         } else {
             command.arg("--patch");
         }
@@ -1142,6 +1738,56 @@ impl<'repo, 'index> StupidContext<'repo, 'index> {
         Ok(())
     }
 
+    /// Save index and worktree changes as a stash entry without altering them.
+    ///
+    /// The changes are recorded with `git stash create` and added to the stash reflog
+    /// with the given message. Returns the stash commit id, or `None` if there were no
+    /// changes to stash.
+    pub(crate) fn stash_store_changes(&self, message: &str) -> Result<Option<gix::ObjectId>> {
+        let output = self
+            .git()
+            .args(["stash", "create", message])
+            .output_git()?
+            .require_success("stash create")?;
+        if output.stdout.trim().is_empty() {
+            return Ok(None);
+        }
+        let stash_id = parse_oid(&output.stdout)?;
+        self.git()
+            .args(["stash", "store", "--quiet", "--message", message])
+            .arg(stash_id.to_string())
+            .output_git()?
+            .require_success("stash store")?;
+        Ok(Some(stash_id))
+    }
+
+    /// Get the statuses of a submodule's index and work tree relative to its HEAD.
+    ///
+    /// The submodule path is relative to the root of the work tree. Nested submodules
+    /// and untracked files are included in the statuses.
+    pub(crate) fn submodule_statuses(&self, path: &Path) -> Result<Statuses> {
+        let work_dir = self
+            .work_dir
+            .expect("work_dir is required for this command");
+        let status_data = new_git_command(self.longpaths)
+            .current_dir(work_dir.join(path))
+            .env_remove("GIT_DIR")
+            .env_remove("GIT_WORK_TREE")
+            .env_remove("GIT_INDEX_FILE")
+            .args([
+                "status",
+                "--porcelain=v2",
+                "--null",
+                "--ignore-submodules=none",
+                "--untracked-files=normal",
+            ])
+            .stdin(Stdio::null())
+            .output_git()?
+            .require_success("status --porcelain=v2")?
+            .stdout;
+        Statuses::from_data(status_data)
+    }
+
     /// Get index and worktree change statuses relative to HEAD.
     pub(crate) fn statuses(&self, options: Option<&StatusOptions>) -> Result<Statuses> {
         let default_options;
@@ -1189,7 +1835,7 @@ impl<'repo, 'index> StupidContext<'repo, 'index> {
             .require_success("status --porcelain=v2")?
             .stdout;
 
-        Ok(Statuses::from_data(status_data))
+        Statuses::from_data(status_data)
     }
 
     /// Show short status using `git status`.
@@ -1218,8 +1864,9 @@ impl<'repo, 'index> StupidContext<'repo, 'index> {
         SpecIter: IntoIterator<Item = SpecArg> + Send,
         SpecArg: AsRef<OsStr> + Send,
     {
-        let mut child = self
-            .git_in_work_root()?
+        let start = Instant::now();
+        let mut command = self.git_in_work_root()?;
+        command
             .args([
                 "update-index",
                 "--remove",
@@ -1229,8 +1876,8 @@ impl<'repo, 'index> StupidContext<'repo, 'index> {
                 "--stdin",
             ])
             .stdin(Stdio::piped())
-            .stdout(Stdio::null())
-            .spawn_git()?;
+            .stdout(Stdio::null());
+        let mut child = command.spawn_git()?;
 
         {
             let mut stdin = child.stdin.take().unwrap();
@@ -1239,10 +1886,8 @@ impl<'repo, 'index> StupidContext<'repo, 'index> {
                 let write_result: Result<()> = std::thread::scope(|scope| {
                     let handle = scope.spawn(|| {
                         for spec in pathspecs {
-                            if let Some(spec_bytes) = <[u8]>::from_os_str(spec.as_ref()) {
-                                stdin.write_all(spec_bytes)?;
-                                stdin.write_all(&[0])?;
-                            }
+                            stdin.write_all(path_to_git_path(Path::new(spec.as_ref()))?)?;
+                            stdin.write_all(&[0])?;
                         }
                         Ok(())
                     });
@@ -1253,7 +1898,9 @@ impl<'repo, 'index> StupidContext<'repo, 'index> {
                 write_result?;
             }
         }
-        child.wait_with_output()?.require_success("update-index")?;
+        let output = child.wait_with_output();
+        timing::record_git(&command, start.elapsed());
+        output?.require_success("update-index")?;
         Ok(())
     }
 
@@ -4,8 +4,11 @@
 
 use std::path::Path;
 
+use anyhow::Result;
 use bstr::ByteSlice;
 
+use super::path::{check_git_paths, git_path_to_path};
+
 /// Diff output containing only names of differing files.
 ///
 /// E.g. from `git diff-tree --name-only -z`
@@ -14,8 +17,9 @@ pub(crate) struct DiffFiles {
 }
 
 impl DiffFiles {
-    pub(super) fn new(data: Vec<u8>) -> Self {
-        DiffFiles { data }
+    pub(super) fn new(data: Vec<u8>) -> Result<Self> {
+        check_git_paths(&data)?;
+        Ok(DiffFiles { data })
     }
 
     pub(crate) fn iter(&self) -> DiffFilesIter<'_> {
@@ -41,9 +45,7 @@ impl<'a> Iterator for DiffFilesIter<'a> {
             let null_offset = remaining
                 .find_byte(0)
                 .expect("all paths are nul terminated");
-            let path = remaining[..null_offset]
-                .to_path()
-                .expect("paths on Windows must be utf8");
+            let path = git_path_to_path(&remaining[..null_offset]);
             self.index += null_offset + 1;
             Some(path)
         } else {
@@ -52,13 +54,73 @@ impl<'a> Iterator for DiffFilesIter<'a> {
     }
 }
 
+/// Summary output mode for `git diff` and `git show`.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum DiffSummary {
+    /// Diffstat with summary of created, deleted, and renamed files.
+    ///
+    /// I.e. `--stat --summary`
+    Stat,
+
+    /// Machine-friendly numbers of added and deleted lines per file.
+    ///
+    /// I.e. `--numstat`
+    NumStat,
+}
+
+impl DiffSummary {
+    pub(super) fn args(self) -> &'static [&'static str] {
+        match self {
+            DiffSummary::Stat => &["--stat", "--summary"],
+            DiffSummary::NumStat => &["--numstat"],
+        }
+    }
+}
+
+/// Numbers of added and deleted lines of a file.
+///
+/// E.g. from `git diff-tree --numstat -z`
+pub(crate) struct DiffNumStat {
+    /// Number of added lines, or `None` for binary files.
+    pub(crate) added: Option<usize>,
+
+    /// Number of deleted lines, or `None` for binary files.
+    pub(crate) deleted: Option<usize>,
+
+    /// Path of the file.
+    pub(crate) path: bstr::BString,
+}
+
+/// Change to a single file between two trees.
+///
+/// E.g. from `git diff-tree --raw -z`
+pub(crate) struct DiffRawEntry {
+    /// File mode before the change, or `None` if the file was added.
+    pub(crate) old_mode: Option<String>,
+
+    /// File mode after the change, or `None` if the file was deleted.
+    pub(crate) new_mode: Option<String>,
+
+    /// Status letter, e.g. `M` for modified or `R` for renamed.
+    pub(crate) status: char,
+
+    /// Similarity percentage of renamed and copied files.
+    pub(crate) similarity: Option<u32>,
+
+    /// Source path of renamed and copied files.
+    pub(crate) old_path: Option<bstr::BString>,
+
+    /// Path of the file.
+    pub(crate) path: bstr::BString,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
     fn diff_iteration() {
-        let diff_files = DiffFiles::new(b"abc\0def ghi\0jkl\0".to_vec());
+        let diff_files = DiffFiles::new(b"abc\0def ghi\0jkl\0".to_vec()).unwrap();
         let mut it = diff_files.iter();
         assert_eq!(it.next(), Some(Path::new("abc")));
         assert_eq!(it.next(), Some(Path::new("def ghi")));
@@ -10,6 +10,7 @@ mod command;
 mod context;
 mod diff;
 mod oid;
+mod path;
 mod status;
 mod tempindex;
 mod version;
@@ -18,7 +19,8 @@ use std::cell::RefCell;
 
 pub(crate) use self::{
     context::StupidContext,
-    status::{Status, StatusOptions, Statuses},
+    diff::DiffSummary,
+    status::{Status, StatusEntryKind, StatusOptions, Statuses},
 };
 
 pub(crate) trait Stupid<'repo, 'index> {
@@ -33,6 +35,7 @@ impl<'repo, 'index> Stupid<'repo, 'index> for gix::Repository {
             work_dir: self.work_dir(),
             index_filename: None,
             git_version: RefCell::new(None::<self::version::StupidVersion>),
+            longpaths: self::context::use_longpaths(&self.config_snapshot()),
         }
     }
 }
@@ -0,0 +1,72 @@
+// SPDX-License-Identifier: GPL-2.0-only
+
+//! Conversion of paths exchanged with git.
+//!
+//! Git reads and writes paths as raw bytes. On unix, these bytes map directly to
+//! native paths. On Windows, git encodes paths as UTF-8 whereas native paths are
+//! UTF-16, so paths must be converted between the two encodings. Paths that cannot be
+//! represented in the other encoding, e.g. those containing unpaired surrogates, are
+//! reported as errors instead of being silently dropped or mangled.
+
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+use bstr::ByteSlice;
+
+/// Check that the paths in output from git may be converted to native paths.
+///
+/// This is always the case on unix. On Windows, the output must be valid UTF-8.
+/// Checking the output up front allows the paths within it to be borrowed as native
+/// paths without any further fallible conversions.
+pub(super) fn check_git_paths(data: &[u8]) -> Result<()> {
+    if cfg!(windows) {
+        if let Err(e) = data.to_str() {
+            let start = data[..e.valid_up_to()]
+                .rfind_byte(0)
+                .map_or(0, |offset| offset + 1);
+            let end = data[start..]
+                .find_byte(0)
+                .map_or(data.len(), |offset| start + offset);
+            return Err(anyhow!(
+                "git produced path `{}` that is not valid UTF-8",
+                data[start..end].as_bstr()
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Convert bytes from previously checked git output to a native path.
+///
+/// See [`check_git_paths()`].
+pub(super) fn git_path_to_path(path_bytes: &[u8]) -> &Path {
+    path_bytes
+        .to_path()
+        .expect("git output paths are checked before conversion")
+}
+
+/// Convert a native path to bytes suitable for passing to git.
+pub(super) fn path_to_git_path(path: &Path) -> Result<&[u8]> {
+    <[u8]>::from_path(path).ok_or_else(|| anyhow!("path `{}` is not valid Unicode", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let path = Path::new("dir/caf\u{e9} \u{1f600}.txt");
+        let bytes = path_to_git_path(path).unwrap();
+        assert_eq!(bytes, "dir/caf\u{e9} \u{1f600}.txt".as_bytes());
+        check_git_paths(bytes).unwrap();
+        assert_eq!(git_path_to_path(bytes), path);
+    }
+
+    #[test]
+    #[cfg(windows)]
+    fn invalid_utf8() {
+        let err = check_git_paths(b"good\0bad\xff\0").unwrap_err();
+        assert!(err.to_string().contains("bad"));
+    }
+}
@@ -11,7 +11,10 @@ use std::{
 use anyhow::{anyhow, Result};
 use bstr::ByteSlice;
 
-use super::oid::parse_oid;
+use super::{
+    oid::parse_oid,
+    path::{check_git_paths, git_path_to_path},
+};
 
 #[derive(Default)]
 pub(crate) struct StatusOptions {
@@ -69,7 +72,6 @@ impl StatusOptions {
     /// Capture supplemental branch header information.
     ///
     /// Use [`Statuses::headers()`] to inspect these headers.
-    #[allow(unused)]
     pub(crate) fn include_branch_headers(&mut self, include: bool) -> &mut Self {
         self.include_branch_headers = include;
         self
@@ -141,6 +143,18 @@ impl Status {
     }
 }
 
+/// State of a submodule, as reported by `git status --porcelain=v2`.
+pub(crate) struct SubmoduleState {
+    /// The submodule's checked-out commit differs from the recorded commit.
+    pub(crate) commit_changed: bool,
+
+    /// The submodule has modified tracked content, including nested submodules.
+    pub(crate) has_tracked_changes: bool,
+
+    /// The submodule has untracked files.
+    pub(crate) has_untracked_changes: bool,
+}
+
 /// A snapshot of status information.
 pub(crate) struct Statuses {
     data: Vec<u8>,
@@ -149,7 +163,8 @@ pub(crate) struct Statuses {
 }
 
 impl Statuses {
-    pub(super) fn from_data(data: Vec<u8>) -> Statuses {
+    pub(super) fn from_data(data: Vec<u8>) -> Result<Statuses> {
+        check_git_paths(&data)?;
         let mut header_ranges = Vec::new();
         let mut entry_ranges = Vec::new();
         let mut offset = 0;
@@ -184,11 +199,11 @@ impl Statuses {
             }
         }
 
-        Statuses {
+        Ok(Statuses {
             data,
             header_ranges,
             entry_ranges,
-        }
+        })
     }
 
     fn get(&self, index: usize) -> Option<StatusEntry<'_>> {
@@ -276,6 +291,29 @@ impl Statuses {
         }
     }
 
+    /// Determine whether the given paths are clean in both the index and work tree.
+    ///
+    /// The error lists the paths with local changes.
+    pub(crate) fn check_paths_clean<'a>(
+        &self,
+        paths: impl IntoIterator<Item = &'a Path>,
+    ) -> Result<()> {
+        let paths: std::collections::BTreeSet<&Path> = paths.into_iter().collect();
+        let dirty: Vec<String> = self
+            .iter()
+            .filter(|entry| paths.contains(entry.path()))
+            .map(|entry| format!("`{}`", entry.path().display()))
+            .collect();
+        if dirty.is_empty() {
+            Ok(())
+        } else {
+            Err(anyhow!(
+                "local changes to {} would be affected; use `refresh` or `reset --hard`",
+                dirty.join(", ")
+            ))
+        }
+    }
+
     /// Determine whether the index is clean.
     ///
     /// A clean index is one that does not record and differences from the `HEAD` tree.
@@ -345,6 +383,32 @@ impl<'s> StatusEntry<'s> {
         Status::from_char(self.data[self.range.start + 3])
     }
 
+    /// Get the submodule state of the entry.
+    ///
+    /// Returns `None` if the entry is not a submodule.
+    pub(crate) fn submodule_state(&self) -> Option<SubmoduleState> {
+        match self.kind() {
+            StatusEntryKind::Untracked | StatusEntryKind::Ignored => None,
+            _ => {
+                // b"1 XY Scmu ..."
+                //       ^^^^
+                let sub = self.data[self.range.clone()]
+                    .splitn_str(4, b" ")
+                    .nth(2)
+                    .expect("submodule state is 3rd space separated field");
+                if let [b'S', c, m, u] = sub {
+                    Some(SubmoduleState {
+                        commit_changed: *c == b'C',
+                        has_tracked_changes: *m == b'M',
+                        has_untracked_changes: *u == b'U',
+                    })
+                } else {
+                    None
+                }
+            }
+        }
+    }
+
     pub(crate) fn path_bytes(&self) -> &'s [u8] {
         let slice = &self.data[self.range.clone()];
         match self.kind() {
@@ -371,9 +435,21 @@ impl<'s> StatusEntry<'s> {
     }
 
     pub(crate) fn path(&self) -> &'s Path {
-        self.path_bytes()
-            .to_path()
-            .expect("paths on Windows must be utf8")
+        git_path_to_path(self.path_bytes())
+    }
+
+    /// Original path of a renamed or copied entry.
+    pub(crate) fn orig_path(&self) -> Option<&'s Path> {
+        if let StatusEntryKind::Renamed = self.kind() {
+            let slice = &self.data[self.range.clone()];
+            let orig_path = slice
+                .splitn_str(2, b"\0")
+                .nth(1)
+                .expect("rename entry has original path after inner null terminator");
+            Some(git_path_to_path(orig_path))
+        } else {
+            None
+        }
     }
 }
 
@@ -441,7 +517,6 @@ impl<'s> StatusHeaders<'s> {
     }
 
     /// Get current branch's upstream branch name.
-    #[allow(unused)]
     pub(crate) fn branch_upstream(&self) -> Option<String> {
         for entry in self.iter() {
             if let (HeaderKind::BranchUpstream, name_bytes) = entry.kind_value() {
@@ -643,7 +718,7 @@ mod tests {
 
     #[test]
     fn parse_example_status() {
-        let statuses = Statuses::from_data(EXAMPLE.to_vec());
+        let statuses = Statuses::from_data(EXAMPLE.to_vec()).unwrap();
         assert_eq!(statuses.len(), 11);
         assert!(!statuses.is_empty());
         let mut iter = statuses.iter();
@@ -688,4 +763,15 @@ mod tests {
         assert_eq!(ignored_iter.next().unwrap().value(), b"# also ignored");
         assert!(ignored_iter.next().is_none());
     }
+
+    #[test]
+    fn rename_orig_path() {
+        let statuses = Statuses::from_data(EXAMPLE.to_vec()).unwrap();
+        let orig_paths: Vec<Option<&Path>> = statuses
+            .iter()
+            .map(|entry| entry.orig_path())
+            .take(4)
+            .collect();
+        assert_eq!(orig_paths, vec![None, None, Some(Path::new("file2")), None]);
+    }
 }
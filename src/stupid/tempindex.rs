@@ -5,13 +5,19 @@
 //! The temporary index file is created relative to `git_dir`.
 //!
 //! The file name is based on the PID of the StGit process to mitigate the most
-//! egregious name collision scenarios.
+//! egregious name collision scenarios, along with a per-process sequence number such
+//! that multiple temporary index files may be in use at the same time, e.g. from
+//! separate threads.
 //!
 //! When [`TempIndex`] instance is dropped, it will attempt to delete the temporary
 //! index file. It is okay if the file no longer exists at drop-time, but a panic will
-//! happen if the file exists but removal fails.
+//! happen if the file exists but removal fails. Creation and removal are retried when
+//! they fail transiently, as may happen when the git dir is on a network file system.
 
-use std::path::{Path, PathBuf};
+use std::{
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+};
 
 use anyhow::Result;
 
@@ -25,13 +31,11 @@ impl<'repo> TempIndex<'repo> {
     ///
     /// The temporary index file will be auto-deleted when this value is dropped.
     pub(crate) fn new(git_dir: &'repo Path) -> Result<Self> {
+        static SEQUENCE: AtomicUsize = AtomicUsize::new(0);
         let pid = std::process::id();
-        let filename = PathBuf::from(format!("index-temp-stg-{pid}"));
-        let index_path = git_dir.join(&filename);
-        std::fs::OpenOptions::new()
-            .create_new(true)
-            .write(true)
-            .open(index_path)?;
+        let seq = SEQUENCE.fetch_add(1, Ordering::Relaxed);
+        let filename = PathBuf::from(format!("index-temp-stg-{pid}-{seq}"));
+        crate::fsutil::create_new_file(&git_dir.join(&filename))?;
 
         Ok(Self { git_dir, filename })
     }
@@ -48,9 +52,8 @@ impl<'repo> TempIndex<'repo> {
 impl<'repo> Drop for TempIndex<'repo> {
     fn drop(&mut self) {
         let index_path = self.git_dir.join(self.filename());
-        assert!(index_path.is_file());
-        if let Err(e) = std::fs::remove_file(&index_path) {
-            panic!("failed to remove temp index {index_path:?}: {e}");
+        if let Err(e) = crate::fsutil::remove_file(&index_path) {
+            panic!("failed to remove temp index {index_path:?}: {e:#}");
         }
     }
 }
@@ -139,7 +139,7 @@ pub(crate) fn specialize_template(
 
 /// Default patch export template.
 pub(crate) const PATCHEXPORT_TMPL: &str = "\
-%(shortdescr)s
+%(subjectprefix)s%(shortdescr)s
 
 From: %(authname)s <%(authemail)s>
 
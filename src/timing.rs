@@ -0,0 +1,216 @@
+// SPDX-License-Identifier: GPL-2.0-only
+
+//! Collection and reporting of where time is spent by a command.
+//!
+//! The time taken by each git subprocess and hook is recorded while a command runs.
+//! When timing is enabled with `--timing` or the `stgit.showtiming` configuration
+//! variable, a summary of the recorded times is printed once the command finishes.
+
+use std::{
+    ffi::OsStr,
+    process::Command,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+    time::Duration,
+};
+
+use crate::ext::RepositoryExtended;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+static RECORDS: Mutex<Vec<Record>> = Mutex::new(Vec::new());
+
+/// Broad category of work a recorded subprocess performed.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum Category {
+    Status,
+    Merge,
+    Index,
+    Hook,
+    Other,
+}
+
+impl Category {
+    fn description(self) -> &'static str {
+        match self {
+            Category::Status => "status scan",
+            Category::Merge => "merges",
+            Category::Index => "index writes",
+            Category::Hook => "hooks",
+            Category::Other => "other subprocesses",
+        }
+    }
+
+    /// Categorize a git subprocess by its subcommand name.
+    fn from_git_subcommand(subcommand: &str) -> Self {
+        match subcommand {
+            "status" | "diff-files" | "diff-index" | "ls-files" => Category::Status,
+            "apply" | "merge-file" | "merge-recursive" | "merge-tree" => Category::Merge,
+            "checkout-index" | "read-tree" | "update-index" | "write-tree" => Category::Index,
+            _ => Category::Other,
+        }
+    }
+}
+
+struct Record {
+    category: Category,
+    label: String,
+    elapsed: Duration,
+}
+
+/// Resolve whether timing output is enabled.
+///
+/// Timing output is enabled by the `--timing` option, given by `option`, or the
+/// `stgit.showtiming` configuration variable of the repository, if any. This is
+/// resolved once at startup such that recording times is cheap when disabled.
+pub(crate) fn init(option: bool) {
+    let enabled = option
+        || gix::Repository::open().ok().map_or(false, |repo| {
+            repo.config_snapshot()
+                .boolean("stgit.showtiming")
+                .unwrap_or(false)
+        });
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+/// Determine whether timing output is enabled.
+pub(crate) fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Record the time taken by a subprocess.
+pub(crate) fn record(category: Category, label: impl Into<String>, elapsed: Duration) {
+    if !is_enabled() {
+        return;
+    }
+    if let Ok(mut records) = RECORDS.lock() {
+        records.push(Record {
+            category,
+            label: label.into(),
+            elapsed,
+        });
+    }
+}
+
+/// Record the time taken by a git subprocess.
+///
+/// The subprocess is labeled and categorized by its git subcommand name.
+pub(crate) fn record_git(command: &Command, elapsed: Duration) {
+    if !is_enabled() {
+        return;
+    }
+    let subcommand = git_subcommand(command.get_args()).unwrap_or_default();
+    let category = Category::from_git_subcommand(&subcommand);
+    record(category, format!("git {subcommand}"), elapsed);
+}
+
+/// Find the subcommand in the arguments of a `git` command.
+///
+/// Options preceding the subcommand are skipped, including the values of `-c` and `-C`
+/// options, e.g. `core.longpaths=true`.
+fn git_subcommand<'a>(mut args: impl Iterator<Item = &'a OsStr>) -> Option<String> {
+    while let Some(arg) = args.next() {
+        if arg == "-c" || arg == "-C" {
+            args.next();
+        } else if !arg.to_string_lossy().starts_with('-') {
+            return Some(arg.to_string_lossy().into_owned());
+        }
+    }
+    None
+}
+
+/// Print the breakdown of recorded times to stderr.
+///
+/// The recorded times are grouped by category and, within each category, by label.
+/// Time not spent in subprocesses is reported as in-process time.
+pub(crate) fn print_summary(total: Duration) {
+    use std::io::Write;
+
+    let records = if let Ok(records) = RECORDS.lock() {
+        records
+    } else {
+        return;
+    };
+
+    /// Accumulated time and number of calls for one label.
+    struct LabelTotal<'a> {
+        label: &'a str,
+        elapsed: Duration,
+        count: usize,
+    }
+
+    let mut groups: Vec<(Category, Vec<LabelTotal>)> = Vec::new();
+    for record in records.iter() {
+        let index = groups
+            .iter()
+            .position(|(category, _)| *category == record.category)
+            .unwrap_or_else(|| {
+                groups.push((record.category, Vec::new()));
+                groups.len() - 1
+            });
+        let labels = &mut groups[index].1;
+        if let Some(entry) = labels.iter_mut().find(|entry| entry.label == record.label) {
+            entry.elapsed += record.elapsed;
+            entry.count += 1;
+        } else {
+            labels.push(LabelTotal {
+                label: &record.label,
+                elapsed: record.elapsed,
+                count: 1,
+            });
+        }
+    }
+    groups.sort_by_key(|(category, _)| *category);
+
+    let seconds = |d: Duration| format!("{:.3}s", d.as_secs_f64());
+    let mut subprocess_total = Duration::ZERO;
+    let mut lines: Vec<String> = Vec::new();
+    for (category, labels) in &mut groups {
+        let category_total: Duration = labels.iter().map(|entry| entry.elapsed).sum();
+        subprocess_total += category_total;
+        lines.push(format!(
+            "  {:<24}{:>10}",
+            category.description(),
+            seconds(category_total)
+        ));
+        labels.sort_by_key(|entry| std::cmp::Reverse(entry.elapsed));
+        for entry in labels.iter() {
+            let calls = if entry.count == 1 { "call" } else { "calls" };
+            lines.push(format!(
+                "    {:<22}{:>10}  {} {calls}",
+                entry.label,
+                seconds(entry.elapsed),
+                entry.count,
+            ));
+        }
+    }
+    lines.push(format!(
+        "  {:<24}{:>10}",
+        "in-process",
+        seconds(total.saturating_sub(subprocess_total))
+    ));
+
+    let mut stderr = std::io::stderr().lock();
+    let _ = writeln!(stderr, "timing: {:<26}{:>10}", "total", seconds(total));
+    for line in lines {
+        let _ = writeln!(stderr, "timing: {line}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn git_subcommand_skips_options() {
+        let mut command = Command::new("git");
+        command.args(["-c", "core.longpaths=true", "--no-pager", "diff-tree", "-r"]);
+        assert_eq!(
+            git_subcommand(command.get_args()).as_deref(),
+            Some("diff-tree")
+        );
+        assert_eq!(git_subcommand(Command::new("git").get_args()), None);
+    }
+}
@@ -11,6 +11,9 @@
 GNUPGHOME="$PWD/gpghome"
 export GNUPGHOME
 
+GPGSSH_KEY_PRIMARY="$PWD/gpghome/ed25519_ssh_signing_key"
+GPGSSH_ALLOWED_SIGNERS="$PWD/gpghome/ssh.all_valid.allowedSignersFile"
+
 test_lazy_prereq GPG '
 	gpg_version=$(gpg --version 2>&1)
 	test $? != 127 || exit 1
@@ -53,6 +56,20 @@ test_lazy_prereq GPG '
 	esac
 '
 
+test_lazy_prereq GPGSSH '
+	ssh_version=$(ssh-keygen -Y find-principals -n "git" 2>&1)
+	test $? != 127 || exit 1
+	echo $ssh_version | grep -q "find-principals:missing signature file"
+	test $? = 0 || exit 1;
+
+	mkdir -p "${GNUPGHOME}" &&
+	chmod 0700 "${GNUPGHOME}" &&
+	ssh-keygen -t ed25519 -N "" -C "git ed25519 key" \
+		-f "${GPGSSH_KEY_PRIMARY}" >/dev/null &&
+	echo "\"principal with number 1\" $(cat "${GPGSSH_KEY_PRIMARY}.pub")" \
+		>>"${GPGSSH_ALLOWED_SIGNERS}"
+'
+
 sanitize_pgp() {
 	perl -ne '
 		/^-----END PGP/ and $in_pgp = 0;
@@ -22,4 +22,70 @@ test_expect_success 'check stgit duplicated initialization' '
     command_error stg init
 '
 
+test_expect_success 'Setup stack template' '
+    mkdir -p template/patches &&
+    cat >template/series <<-\EOF &&
+	# Standard series
+	prep
+	debug # temporary
+	EOF
+    printf "Prepare the thing\n\nWith a body.\n" >template/patches/prep &&
+    echo "Reviewed-by: Jane Doe <jane@example.com>" >template/trailers &&
+    printf "[stgit]\n\tautosign = Signed-off-by\n\tpull-policy = rebase\n" >template/config
+'
+
+test_expect_success 'Initialize stack from template' '
+    git checkout -b tmpl master &&
+    stg init --template template &&
+    test "$(echo $(stg series --noprefix))" = "prep debug" &&
+    test "$(git config stgit.autosign)" = "Signed-off-by" &&
+    test "$(git config branch.tmpl.stgit.pull-policy)" = "rebase" &&
+    test_must_fail git config branch.tmpl.stgit.autosign &&
+    stg show prep >show.txt &&
+    grep "Prepare the thing" show.txt &&
+    grep "With a body." show.txt &&
+    grep "Reviewed-by: Jane Doe <jane@example.com>" show.txt &&
+    test "$(git log -1 --format=%s $(stg id debug))" = "debug" &&
+    git log -1 --format=%B $(stg id debug) >debug-msg.txt &&
+    grep "Reviewed-by: Jane Doe <jane@example.com>" debug-msg.txt &&
+    test "$(git rev-parse master^{tree})" = "$(git rev-parse $(stg id debug)^{tree})"
+'
+
+test_expect_success 'Template config takes effect' '
+    stg new -m "after template" &&
+    git log -1 --format=%B >msg.txt &&
+    grep "Signed-off-by: " msg.txt &&
+    git config --unset stgit.autosign
+'
+
+test_expect_success 'Initialize stack from configured template' '
+    test_config stgit.init.template template &&
+    git checkout -b tmpl2 master &&
+    stg init &&
+    test "$(echo $(stg series --noprefix))" = "prep debug"
+'
+
+test_expect_success 'Initialize without configured template' '
+    test_config stgit.init.template template &&
+    git checkout -b tmpl3 master &&
+    stg init --no-template &&
+    stg series >series.txt &&
+    test_must_be_empty series.txt
+'
+
+test_expect_success 'Missing template directory' '
+    git checkout -b tmpl4 master &&
+    command_error stg init --template does-not-exist 2>err &&
+    grep "template directory does not exist" err &&
+    test_must_fail git rev-parse --verify -q refs/stacks/tmpl4
+'
+
+test_expect_success 'Invalid patch name in template series' '
+    mkdir bad-template &&
+    echo "bad..name" >bad-template/series &&
+    git checkout -b tmpl5 master &&
+    command_error stg init --template bad-template &&
+    test_must_fail git rev-parse --verify -q refs/stacks/tmpl5
+'
+
 test_done
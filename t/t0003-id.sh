@@ -19,13 +19,44 @@ test_expect_success 'Init repo' '
     done
 '
 
-test_expect_success 'Too many arguments' '
-    general_error stg id patch-1 patch-2 2>err &&
-    grep -e "unexpected argument .patch-2." err
+test_expect_success 'Multiple arguments' '
+    stg id patch-1 patch-2 >actual &&
+    git rev-parse refs/patches/master/patch-1 refs/patches/master/patch-2 >expected &&
+    test_cmp expected actual
+'
+
+test_expect_success 'Multiple arguments with bad revision' '
+    command_error stg id patch-1 bogus patch-2 >actual 2>err &&
+    test_must_be_empty actual &&
+    grep -e "bogus" err
 '
 
 test_expect_success 'Provide patch argument' '
     test "$(echo $(stg id))" = "$(echo $(stg id $(stg top)))"
 '
 
+test_expect_success 'Batch conflicts with arguments' '
+    general_error stg id --batch patch-1
+'
+
+test_expect_success 'Batch resolution from stdin' '
+    printf "patch-1\nbogus\n\n{base}\npatch-2^\n" | stg id --batch >actual &&
+    cat >expected <<-EOF &&
+	$(stg id patch-1)
+	bogus missing
+	$(stg id {base})
+	$(stg id patch-1)
+	EOF
+    test_cmp expected actual
+'
+
+test_expect_success 'Batch resolution with NUL terminators' '
+    printf "patch-2\0bogus\0" | stg id --batch -z | tr "\0" "\n" >actual &&
+    cat >expected <<-EOF &&
+	$(stg id patch-2)
+	bogus missing
+	EOF
+    test_cmp expected actual
+'
+
 test_done
@@ -144,4 +144,52 @@ test_expect_success 'Multiple path limits' '
     grep -e "ccc\.txt" out
 '
 
+test_expect_success 'Show renames with --find-renames' '
+    test_seq 1 20 >rename-src.txt &&
+    stg add rename-src.txt &&
+    stg new -rm add-rename-src &&
+    git mv rename-src.txt rename-dst.txt &&
+    stg new -rm rename &&
+    test_config diff.renames false &&
+    stg show rename >out &&
+    grep -e "^deleted file mode" out &&
+    ! grep -e "^rename from" out &&
+    stg show -M rename >out &&
+    grep -e "^rename from rename-src.txt" out &&
+    grep -e "^rename to rename-dst.txt" out &&
+    stg show --find-renames=100% rename >out &&
+    grep -e "^rename from rename-src.txt" out &&
+    stg diff -r add-rename-src..rename >out &&
+    ! grep -e "^rename from" out &&
+    stg diff -M -r add-rename-src..rename >out &&
+    grep -e "^rename from rename-src.txt" out
+'
+
+test_expect_success 'Show numstat' '
+    stg show --numstat add-rename-src >out &&
+    grep -e "^20	0	rename-src.txt\$" out &&
+    ! grep -e "^diff --git" out &&
+    general_error stg show --stat --numstat
+'
+
+test_expect_success 'Show patch in email format' '
+    stg show --patch-format email patch-bbb >out &&
+    grep -e "^From [0-9a-f]\{40\} " out &&
+    grep -e "^Subject: \[PATCH\] patch-bbb\$" out &&
+    grep -e "^ foo.txt | 1 +\$" out &&
+    grep -e "^+bbb\$" out &&
+    stg email format -o email-out patch-bbb &&
+    test_cmp email-out/0001-patch-bbb.patch out
+'
+
+test_expect_success 'Show series in email format' '
+    test_config stgit.mail.prefix "PATCH v2" &&
+    stg show --patch-format email patch-aaa..patch-ccc >out &&
+    grep -e "^Subject: \[PATCH v2 1/3\] patch-aaa\$" out &&
+    grep -e "^Subject: \[PATCH v2 3/3\] patch-ccc\$" out &&
+    stg show --patch-format email patch-aaa patch-ccc >out &&
+    test "$(grep -c -e "^Subject: \[PATCH v2\] " out)" = "2" &&
+    general_error stg show --patch-format email --stat
+'
+
 test_done
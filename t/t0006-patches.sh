@@ -87,4 +87,27 @@ test_expect_success 'With diff output and diff-opt' '
     test $(cat even-diff2.log | grep -c -E "p(0|1|3) message") = "3"
 '
 
+test_expect_success 'Renamed file' '
+    stg new -m "p5 message" p5 &&
+    git mv even.txt dir0/renamed.txt &&
+    stg refresh &&
+    echo "eight" >>dir0/renamed.txt &&
+    stg new -m "p6 message" p6 &&
+    stg refresh &&
+    stg patches dir0/renamed.txt >renamed.log &&
+    printf "p5\np6\n" >expected-renamed.log &&
+    test_cmp expected-renamed.log renamed.log &&
+    stg patches --find-renames dir0/renamed.txt >renamed-found.log &&
+    printf "p0\np1\np3\np5\np6\n" >expected-renamed-found.log &&
+    test_cmp expected-renamed-found.log renamed-found.log &&
+    (
+        cd dir0 &&
+        stg patches -M renamed.txt >../renamed-relative.log
+    ) &&
+    test_cmp expected-renamed-found.log renamed-relative.log &&
+    stg patches -M dir0 >renamed-dir.log &&
+    printf "p0\np1\np2\np3\np4\np5\np6\n" >expected-renamed-dir.log &&
+    test_cmp expected-renamed-dir.log renamed-dir.log
+'
+
 test_done
@@ -88,4 +88,91 @@ test_expect_success 'Moved file bare' '
     test_cmp a-d-bare.log expected-a-d-bare.log
 '
 
+test_expect_success 'Moved file with rename detection' '
+    stg files --find-renames patch-a-d >a-d-renames.log &&
+    cat >expected-a-d-renames.log <<-\EOF &&
+	R a.txt -> d.txt
+	EOF
+    test_cmp a-d-renames.log expected-a-d-renames.log &&
+    stg files -M patch-b-c >b-c-renames.log &&
+    test_cmp b-c-renames.log expected-b-c.log
+'
+
+test_expect_success 'Moved file bare with rename detection' '
+    stg files -M --bare patch-a-d >a-d-renames-bare.log &&
+    echo d.txt >expected-a-d-renames-bare.log &&
+    test_cmp a-d-renames-bare.log expected-a-d-renames-bare.log
+'
+
+test_expect_success 'JSON format conflicts with stat and bare' '
+    general_error stg files --format=json --stat &&
+    general_error stg files --format=json --bare
+'
+
+test_expect_success 'JSON format' '
+    stg files --format=json patch-b-c >b-c.json &&
+    cat >expected-b-c.json <<-\EOF &&
+	[
+	  {
+	    "status": "M",
+	    "path": "b.txt",
+	    "old_mode": "100644",
+	    "new_mode": "100644"
+	  },
+	  {
+	    "status": "A",
+	    "path": "c.txt",
+	    "old_mode": null,
+	    "new_mode": "100644"
+	  }
+	]
+	EOF
+    test_cmp expected-b-c.json b-c.json
+'
+
+test_expect_success 'JSON format with rename detection' '
+    stg files --format=json -M patch-a-d >a-d.json &&
+    cat >expected-a-d.json <<-\EOF &&
+	[
+	  {
+	    "status": "R",
+	    "path": "d.txt",
+	    "old_path": "a.txt",
+	    "similarity": 100,
+	    "old_mode": "100644",
+	    "new_mode": "100644"
+	  }
+	]
+	EOF
+    test_cmp expected-a-d.json a-d.json
+'
+
+test_expect_success 'JSON format with mode change' '
+    stg new -m patch-mode &&
+    test_chmod +x d.txt &&
+    stg refresh &&
+    stg files --format=json >mode.json &&
+    cat >expected-mode.json <<-\EOF &&
+	[
+	  {
+	    "status": "M",
+	    "path": "d.txt",
+	    "old_mode": "100644",
+	    "new_mode": "100755"
+	  }
+	]
+	EOF
+    test_cmp expected-mode.json mode.json
+'
+
+test_expect_success 'Print JSON output schema' '
+    stg schema >schemas &&
+    grep -e "^files 1\$" schemas &&
+    stg schema files >schema.json &&
+    grep -e "\"\$schema\": \"https://json-schema.org/draft/2020-12/schema\"" schema.json &&
+    grep -e "\"version\": 1" schema.json &&
+    grep -e "\"old_path\": {" schema.json &&
+    general_error stg schema bogus
+'
+
 test_done
@@ -154,12 +154,103 @@ test_expect_success 'Test short argument' '
     test_cmp expected.txt series.txt
 '
 
+test_expect_success 'Test stat' '
+    stg series --stat >series.txt &&
+    cat >expected.txt <<-\EOF &&
+	+ p0 | 1 file changed, 1 insertion(+)
+	+ p1 | 1 file changed, 1 insertion(+)
+	> p2 | 1 file changed, 1 insertion(+)
+	- p3 | no changes
+	EOF
+    test_cmp expected.txt series.txt
+'
+
 test_expect_success 'Test effects' '
     test_config stgit.color.applied none &&
     stg series --applied >series.txt &&
     test_line_count = 3 series.txt
 '
 
+test_expect_success 'Test separators' '
+    stg hide p3 &&
+    stg new -m p4 &&
+    stg pop p4 &&
+    stg series --all --separators >series.txt &&
+    cat >expected.txt <<-\EOF &&
+	+ p0
+	+ p1
+	> p2
+	---
+	- p4
+	---
+	! p3
+	EOF
+    test_cmp expected.txt series.txt &&
+    test_config stgit.series.separator "~~~~" &&
+    stg series --all --separators --reverse >series.txt &&
+    cat >expected.txt <<-\EOF &&
+	! p3
+	~~~~
+	- p4
+	~~~~
+	> p2
+	+ p1
+	+ p0
+	EOF
+    test_cmp expected.txt series.txt &&
+    test_config stgit.series.separators true &&
+    stg series --all >series.txt &&
+    test_line_count = 5 series.txt &&
+    stg series --applied --separators >series.txt &&
+    test_line_count = 3 series.txt
+'
+
+test_expect_success 'Test combined state filters and exit code' '
+    test "$(stg series --applied-only --hidden-only --count)" = "4" &&
+    stg series --unapplied-only --hidden-only --noprefix >series.txt &&
+    test_line_count = 2 series.txt &&
+    stg series --unapplied-only --count --exit-code >count.txt &&
+    echo 1 >expected.txt &&
+    test_cmp expected.txt count.txt &&
+    stg series -A -U --exit-code >/dev/null &&
+    test_expect_code 4 stg series --label no-such-label --count --exit-code >count.txt &&
+    echo 0 >expected.txt &&
+    test_cmp expected.txt count.txt &&
+    test_expect_code 4 stg series --label no-such-label --exit-code >series.txt &&
+    test_must_be_empty series.txt
+'
+
+test_expect_success 'Test JSON output' '
+    stg series --all --json >series.json &&
+    test "$(grep -c -e "^    \"name\":" series.json)" = "5" &&
+    grep -e "\"commit\": \"$(stg id p1)\"" series.json &&
+    grep -e "\"state\": \"hidden\"" series.json &&
+    grep -e "\"state\": \"unapplied\"" series.json &&
+    test "$(grep -c -e "\"top\": true" series.json)" = "1" &&
+    grep -e "\"email\": \"$GIT_AUTHOR_EMAIL\"" series.json &&
+    stg series --unapplied-only --json >series.json &&
+    test "$(grep -c -e "^    \"name\":" series.json)" = "1" &&
+    test_expect_code 4 stg series --label no-such-label --json --exit-code >series.json &&
+    echo "[]" >expected.json &&
+    test_cmp expected.json series.json &&
+    general_error stg series --json --count &&
+    stg schema series >schema.json &&
+    grep -e "\"enum\": \[" schema.json
+'
+
+test_expect_success 'Test count by state' '
+    stg series --all --count-by-state >series.txt &&
+    tail -n 1 series.txt >summary.txt &&
+    echo "applied: 3, unapplied: 1, hidden: 1" >expected.txt &&
+    test_cmp expected.txt summary.txt &&
+    stg series --unapplied --noprefix --count-by-state >series.txt &&
+    printf "p4\napplied: 0, unapplied: 1, hidden: 0\n" >expected.txt &&
+    test_cmp expected.txt series.txt &&
+    general_error stg series --count --count-by-state &&
+    stg delete p4 &&
+    stg unhide p3
+'
+
 test_expect_success 'Test missing' '
     stg branch --clone -- other &&
     test "$(stg branch)" = "other" &&
@@ -173,4 +264,122 @@ test_expect_success 'Test missing' '
     test_cmp expected.txt series.txt
 '
 
+test_expect_success 'Test missing by patch-id' '
+    stg branch master &&
+    stg series --noprefix --missing=other >series.txt &&
+    test_line_count = 0 series.txt &&
+    stg branch other &&
+    stg pick --ref-branch=master p1 &&
+    stg rename p1 p1-renamed &&
+    stg series --noprefix --missing=master >series.txt &&
+    echo "p2" >expected.txt &&
+    test_cmp expected.txt series.txt &&
+    stg edit -m "reworded" p1-renamed &&
+    stg series --noprefix --missing=master >series.txt &&
+    test_cmp expected.txt series.txt
+'
+
+test_expect_success 'Test prompt on uninitialized branch' '
+    git checkout -b prompt-test &&
+    stg series --prompt >out &&
+    test_must_be_empty out &&
+    general_error stg series --prompt --all
+'
+
+test_expect_success 'Test prompt' '
+    stg init &&
+    stg series --prompt >out &&
+    test_must_be_empty out &&
+    stg new -m "add prompt.txt" pa &&
+    echo "a" >prompt.txt &&
+    stg add prompt.txt &&
+    stg refresh &&
+    stg new -m "change prompt.txt" pb &&
+    echo "b" >prompt.txt &&
+    stg refresh &&
+    stg new -m "empty" pc &&
+    stg pop &&
+    echo "pb 2/3" >expected &&
+    stg series --prompt >out &&
+    test_cmp expected out &&
+    stg pop -a &&
+    echo "0/3" >expected &&
+    stg series --prompt >out &&
+    test_cmp expected out &&
+    stg series --prompt --branch=master >out &&
+    test "$(cat out)" = "$(stg top -b master) $(stg series -b master -A -c)/$(stg series -b master -A -U -c)"
+'
+
+test_expect_success 'Test prompt with conflicts' '
+    conflict stg push pb &&
+    echo "pb 1/3|CONFLICT" >expected &&
+    stg series --prompt >out &&
+    test_cmp expected out
+'
+
+test_expect_success 'Test prompt snippet' '
+    stg completion prompt >snippet &&
+    grep -e "__stg_ps1 ()" snippet &&
+    grep -e "stg series --prompt" snippet
+'
+
+test_expect_success 'Test author with mailmap' '
+    test_when_finished "rm -f .mailmap" &&
+    echo "Mapped Author <$GIT_AUTHOR_EMAIL>" >.mailmap &&
+    stg series -b master --author --noprefix >series.txt &&
+    test_line_count = $(stg series -b master -c) series.txt &&
+    ! grep -v -e " # Mapped Author\$" series.txt &&
+    test_config log.mailmap false &&
+    stg series -b master --author --noprefix >series.txt &&
+    ! grep -e "Mapped Author" series.txt
+'
+
+test_expect_success 'Test authored-by' '
+    test_when_finished "rm -f .mailmap" &&
+    stg series -b master --authored-by="<$GIT_AUTHOR_EMAIL>\$" -c >count.txt &&
+    stg series -b master -c >expected.txt &&
+    test_cmp expected.txt count.txt &&
+    stg series -b master --authored-by="^Mapped" -c >count.txt &&
+    echo 0 >expected.txt &&
+    test_cmp expected.txt count.txt &&
+    echo "Mapped Author <$GIT_AUTHOR_EMAIL>" >.mailmap &&
+    stg series -b master --authored-by="^Mapped" -c >count.txt &&
+    stg series -b master -c >expected.txt &&
+    test_cmp expected.txt count.txt &&
+    general_error stg series --authored-by="("
+'
+
+test_expect_success 'Test graph' '
+    stg undo --hard &&
+    stg branch --create graph-test &&
+    for i in 0 1 2 3; do
+        echo "$i" >graph$i.txt &&
+        stg add graph$i.txt &&
+        stg new -m "g$i" "g$i" &&
+        stg refresh || return 1
+    done &&
+    echo more >>graph0.txt &&
+    stg new -m g4 g4 &&
+    stg refresh &&
+    echo other >graph4.txt &&
+    stg add graph4.txt &&
+    stg new -m g5 g5 &&
+    stg refresh &&
+    stg meta set g5 depends "g1, g2" &&
+    stg series --graph=ascii --noprefix >series.txt &&
+    cat >expected.txt <<-\EOF &&
+	*     g0
+	| *   g1
+	| | * g2
+	| | | g3
+	+ | | g4
+	  + + g5
+	EOF
+    test_cmp expected.txt series.txt &&
+    stg series --graph --noprefix >series.txt &&
+    grep -e "^●     g0\$" series.txt &&
+    grep -e "^└ │ │ g4\$" series.txt &&
+    grep -e "^  └ └ g5\$" series.txt
+'
+
 test_done
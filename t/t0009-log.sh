@@ -128,6 +128,16 @@ test_expect_success 'Verify log for p2 and p3' '
     head -n 5 log.txt | tail -n 1 | grep -e "uncommit"
 '
 
+test_expect_success 'Graphical log shows the same entries' '
+    write_script gitk <<-\EOF &&
+	git log --format=%h "$@" >gitk.txt
+	EOF
+    test_when_finished "rm -f gitk gitk.txt" &&
+    PATH=.:$PATH stg log -g p2 p3 &&
+    stg log p2 p3 | cut -d" " -f1 >expected.txt &&
+    test_cmp expected.txt gitk.txt
+'
+
 test_expect_success 'Log with number' '
     stg log -n3 p2 p3 >log.txt &&
     test_line_count = 3 log.txt &&
@@ -136,6 +146,27 @@ test_expect_success 'Log with number' '
     head -n 3 log.txt | tail -n 1 | grep -e "refresh"
 '
 
+test_expect_success 'Log follows renamed patch' '
+    stg rename p3 renamed &&
+    stg log renamed >log.txt &&
+    test_line_count = 4 log.txt &&
+    head -n 1 log.txt | grep -e "rename p3 renamed" &&
+    head -n 2 log.txt | tail -n 1 | grep -e "edit" &&
+    stg log p3 >log-p3.txt &&
+    test_line_count = 4 log-p3.txt &&
+    stg undo
+'
+
+test_expect_success 'Log for deleted patch' '
+    stg delete p3 &&
+    stg log p3 >log.txt &&
+    test_line_count = 6 log.txt &&
+    head -n 1 log.txt | grep -e "delete" &&
+    command_error stg log nonexistent 2>err &&
+    grep -e "patch \`nonexistent\` not found in the stack log" err &&
+    stg undo
+'
+
 test_expect_success 'Clear the log' '
     stg log --clear &&
     test "$(echo $(stg series --noprefix))" = "p0 p1 p2 p3" &&
@@ -144,4 +175,30 @@ test_expect_success 'Clear the log' '
     head -n 1 log.txt | grep -e "clear log"
 '
 
+test_expect_success 'Record refresh increments' '
+    test_config stgit.log.increments true &&
+    echo one >incr.txt &&
+    stg add incr.txt &&
+    stg refresh &&
+    echo two >>incr.txt &&
+    stg refresh &&
+    stg log -n1 --diff p3 >log.txt &&
+    grep -e "^+two\$" log.txt &&
+    grep -e "^ one\$" log.txt &&
+    ! grep -e "stack.json" log.txt &&
+    stg log -n2 --diff p3 >log.txt &&
+    grep -e "^+one\$" log.txt &&
+    stg log -n1 --diff >log.txt &&
+    grep -e "stack.json" log.txt &&
+    ! grep -e "increments/" log.txt
+'
+
+test_expect_success 'Increments are not recorded by default' '
+    echo three >>incr.txt &&
+    stg refresh &&
+    stg log -n1 --diff p3 >log.txt &&
+    grep -e "stack.json" log.txt &&
+    ! grep -e "^+three\$" log.txt
+'
+
 test_done
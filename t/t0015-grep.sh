@@ -0,0 +1,82 @@
+#!/bin/sh
+
+test_description='Test stg grep'
+
+. ./test-lib.sh
+
+test_expect_success 'Initialize stack' '
+    for i in $(test_seq 1 20); do echo "line $i" || return 1; done >a.txt &&
+    git add a.txt &&
+    git commit -m "Add a.txt" &&
+    stg new -m "first patch" p0 &&
+    sed -i "s/^line 2$/line two/" a.txt &&
+    sed -i "s/^line 19$/line NINETEEN/" a.txt &&
+    stg refresh &&
+    stg new -m "Second patch
+
+Mention FooBar in the body." p1 &&
+    echo "some other text" >b.txt &&
+    stg add b.txt &&
+    stg refresh &&
+    stg new -m "third patch" p2 &&
+    echo "line ten" >>a.txt &&
+    stg refresh &&
+    stg pop &&
+    stg new -m "hidden patch" p3 &&
+    echo "hidden line ten" >>a.txt &&
+    stg refresh &&
+    stg pop &&
+    stg hide p3
+'
+
+test_expect_success 'Pattern is required' '
+    general_error stg grep
+'
+
+test_expect_success 'Invalid pattern' '
+    command_error stg grep -e "(" 2>err &&
+    grep "invalid pattern" err
+'
+
+test_expect_success 'Search patch names only' '
+    stg grep -l -e "line t" >out &&
+    printf "p0\np2\n" >expected &&
+    test_cmp expected out &&
+    stg grep -l --all -e "line t" >out &&
+    printf "p0\np2\np3\n" >expected &&
+    test_cmp expected out
+'
+
+test_expect_success 'Search diff hunks' '
+    stg grep -e "line two" >out &&
+    head -1 out >out-head &&
+    echo p0 >expected-head &&
+    test_cmp expected-head out-head &&
+    test "$(grep -c "^@@" out)" = "1" &&
+    grep "^+line two" out &&
+    ! grep "NINETEEN" out
+'
+
+test_expect_success 'Context lines are not searched' '
+    stg grep -l -e "line 3" >out &&
+    test_must_be_empty out
+'
+
+test_expect_success 'Search messages' '
+    stg grep -e foobar >out &&
+    test_must_be_empty out &&
+    stg grep -i -e foobar >out &&
+    printf "p1\n    Mention FooBar in the body.\n" >expected &&
+    test_cmp expected out
+'
+
+test_expect_success 'Multiple patterns and patch ranges' '
+    stg grep -l -e NINETEEN -e "other text" >out &&
+    printf "p0\np1\n" >expected &&
+    test_cmp expected out &&
+    stg grep -l -e NINETEEN -e "other text" p1.. >out &&
+    echo p1 >expected &&
+    test_cmp expected out
+'
+
+test_done
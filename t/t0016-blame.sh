@@ -0,0 +1,76 @@
+#!/bin/sh
+
+test_description='Test stg blame'
+
+. ./test-lib.sh
+
+test_expect_success 'Initialize repo with patches' '
+    test_seq 1 6 >file.txt &&
+    git add file.txt &&
+    git commit -m "add file" &&
+    stg init &&
+    sed "s/^2\$/two/" file.txt >file.tmp && mv file.tmp file.txt &&
+    stg new -m first-patch &&
+    stg refresh &&
+    sed "s/^5\$/five/" file.txt >file.tmp && mv file.tmp file.txt &&
+    echo 7 >>file.txt &&
+    stg new -m p2 &&
+    stg refresh
+'
+
+test_expect_success 'Blame file' '
+    stg blame file.txt >out &&
+    cat >expected <<-\EOF &&
+	{base}      1
+	first-patch two
+	{base}      3
+	{base}      4
+	p2          five
+	{base}      6
+	p2          7
+	EOF
+    test_cmp expected out
+'
+
+test_expect_success 'Blame line range' '
+    stg blame -L 2,3 file.txt >out &&
+    cat >expected <<-\EOF &&
+	first-patch two
+	{base}      3
+	EOF
+    test_cmp expected out
+'
+
+test_expect_success 'Blame ignores unapplied patches and worktree' '
+    stg pop &&
+    echo 8 >>file.txt &&
+    stg blame file.txt >out &&
+    cat >expected <<-\EOF &&
+	{base}      1
+	first-patch two
+	{base}      3
+	{base}      4
+	{base}      5
+	{base}      6
+	EOF
+    test_cmp expected out &&
+    git checkout file.txt &&
+    stg push
+'
+
+test_expect_success 'Blame from subdirectory' '
+    mkdir dir &&
+    (
+        cd dir &&
+        stg blame -L 1,2 ../file.txt >../out
+    ) &&
+    printf "{base}      1\nfirst-patch two\n" >expected &&
+    test_cmp expected out
+'
+
+test_expect_success 'Blame nonexistent file' '
+    command_error stg blame nonexistent.txt 2>err &&
+    grep "nonexistent.txt" err
+'
+
+test_done
@@ -0,0 +1,83 @@
+#!/bin/sh
+
+test_description='Test stg stat'
+
+. ./test-lib.sh
+
+test_expect_success 'Stat of empty stack' '
+    test_commit_bulk --message="base %s" 1 &&
+    stg init &&
+    stg stat >out &&
+    echo "Patches:   0 (0 applied, 0 unapplied, 0 hidden)" >expected &&
+    test_cmp expected out
+'
+
+test_expect_success 'Initialize patches' '
+    test_seq 1 4 >a.txt &&
+    stg add a.txt &&
+    stg new -m p1 &&
+    stg refresh &&
+    sed "s/^2\$/two/" a.txt >a.tmp && mv a.tmp a.txt &&
+    echo b >b.txt &&
+    git add a.txt b.txt &&
+    stg new -m p2 --author "Other Author <other@example.com>" --authdate "2000-01-01 00:00:00 +0000" &&
+    stg refresh --index &&
+    echo c >c.txt &&
+    stg add c.txt &&
+    stg new -m p3 &&
+    stg refresh &&
+    echo d >d.txt &&
+    stg add d.txt &&
+    stg new -m p4 &&
+    stg refresh &&
+    stg pop -n 2 &&
+    stg hide p4
+'
+
+test_expect_success 'Stat of stack' '
+    stg stat >out &&
+    grep "^Patches:   4 (2 applied, 1 unapplied, 1 hidden)\$" out &&
+    grep "^Changes:   3 files changed, 7 insertions(+), 1 deletion(-)\$" out &&
+    grep "^Average:   2.7 changed lines per patch\$" out &&
+    grep "^Oldest:    2000-01-01 00:00:00 +0000 (p2)\$" out &&
+    grep "^Newest:    .* (p3)\$" out &&
+    sed -n "/^Authors:/,\$p" out >authors &&
+    cat >expected <<-EOF &&
+	Authors:
+	  2  $GIT_AUTHOR_NAME <$GIT_AUTHOR_EMAIL>
+	  1  Other Author <other@example.com>
+	EOF
+    test_cmp expected authors
+'
+
+test_expect_success 'Stat including hidden patches' '
+    stg stat --all >out &&
+    grep "^Changes:   4 files changed, 8 insertions(+), 1 deletion(-)\$" out &&
+    grep "^Average:   2.2 changed lines per patch\$" out &&
+    grep "^  3  $GIT_AUTHOR_NAME <$GIT_AUTHOR_EMAIL>\$" out
+'
+
+test_expect_success 'Stat of other branch' '
+    stg branch --create other &&
+    stg stat >out &&
+    grep "^Patches:   0 " out &&
+    stg stat -b master >out &&
+    grep "^Patches:   4 " out
+'
+
+test_expect_success 'Stat maps authors through mailmap' '
+    test_when_finished "rm -f .mailmap" &&
+    echo "$GIT_AUTHOR_NAME <$GIT_AUTHOR_EMAIL> <other@example.com>" >.mailmap &&
+    stg stat -b master >out &&
+    sed -n "/^Authors:/,\$p" out >authors &&
+    cat >expected <<-EOF &&
+	Authors:
+	  3  $GIT_AUTHOR_NAME <$GIT_AUTHOR_EMAIL>
+	EOF
+    test_cmp expected authors &&
+    test_config log.mailmap false &&
+    stg stat -b master >out &&
+    grep "^  1  Other Author <other@example.com>\$" out
+'
+
+test_done
@@ -0,0 +1,68 @@
+#!/bin/sh
+
+test_description='Test stg interdiff'
+
+. ./test-lib.sh
+
+test_expect_success 'Initialize patches' '
+    test_commit_bulk --message="base %s" 1 &&
+    stg init &&
+    for i in 1 2 3; do
+        echo "$i" >"file$i.txt" &&
+        stg add "file$i.txt" &&
+        stg new -m "p$i" &&
+        stg refresh || return 1
+    done &&
+    git tag v1-state $(git rev-parse refs/stacks/master)
+'
+
+test_expect_success 'Interdiff against unchanged state' '
+    stg interdiff v1-state >out &&
+    echo "Interdiff for the stack:" >expected &&
+    test_cmp expected out
+'
+
+test_expect_success 'Modify stack' '
+    stg goto p2 &&
+    echo "2 more" >>file2.txt &&
+    stg refresh &&
+    stg push &&
+    stg delete p3 &&
+    echo 4 >file4.txt &&
+    stg add file4.txt &&
+    stg new -m p4 &&
+    stg refresh
+'
+
+test_expect_success 'Interdiff per patch and overall' '
+    stg interdiff v1-state >out &&
+    grep "^Interdiff for p2:\$" out &&
+    ! grep "^Interdiff for p1:\$" out &&
+    grep "^New patch p4\$" out &&
+    grep "^Removed patch p3\$" out &&
+    sed -n "/^Interdiff for the stack:\$/,\$p" out >overall &&
+    grep "^+2 more\$" overall &&
+    grep "^-3\$" overall &&
+    grep "^+4\$" overall
+'
+
+test_expect_success 'Interdiff for selected patches' '
+    stg interdiff v1-state p1..p2 >out &&
+    grep "^Interdiff for p2:\$" out &&
+    ! grep "New patch" out &&
+    ! grep "Removed patch" out
+'
+
+test_expect_success 'Interdiff against backup' '
+    stg interdiff refs/stgit-backup/master/1 >out &&
+    ! grep "^Interdiff for p2:\$" out &&
+    grep "^New patch p4\$" out &&
+    grep "^Removed patch p3\$" out
+'
+
+test_expect_success 'Interdiff against non-state' '
+    command_error stg interdiff HEAD 2>err &&
+    grep "\`HEAD\` is not a stack state" err
+'
+
+test_done
@@ -0,0 +1,51 @@
+#!/bin/sh
+
+test_description='Test timing output'
+
+. ./test-lib.sh
+
+test_expect_success 'Initialize StGit stack' '
+    stg init &&
+    stg new -m p0 &&
+    stg new -m p1
+'
+
+test_expect_success 'No timing output by default' '
+    stg pop 2>err &&
+    ! grep -e "^timing:" err
+'
+
+test_expect_success 'Timing output with --timing' '
+    stg --timing push 2>err &&
+    grep -e "^timing: total  *[0-9.]*s$" err &&
+    grep -e "^timing:   in-process  *[0-9.]*s$" err
+'
+
+test_expect_success 'Timing output breaks down git subprocesses' '
+    echo hello >foo.txt &&
+    stg add foo.txt &&
+    stg --timing refresh 2>err &&
+    grep -e "^timing:   status scan  *[0-9.]*s$" err &&
+    grep -e "^timing:     git status  *[0-9.]*s  [0-9]* calls*$" err &&
+    grep -e "^timing:   index writes  *[0-9.]*s$" err
+'
+
+test_expect_success 'Timing output is printed for failed commands' '
+    command_error stg --timing push 2>err &&
+    grep -e "no unapplied patches" err &&
+    grep -e "^timing: total" err
+'
+
+test_expect_success 'Timing output enabled with stgit.showtiming' '
+    test_config stgit.showtiming true &&
+    stg pop 2>err &&
+    grep -e "^timing: total" err
+'
+
+test_expect_success 'Timing output for aliases' '
+    test_config stgit.alias.pp "pop" &&
+    stg --timing pp 2>err &&
+    grep -e "^timing: total" err
+'
+
+test_done
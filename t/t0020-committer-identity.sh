@@ -0,0 +1,41 @@
+#!/bin/sh
+
+test_description='Test forcing the committer identity'
+
+. ./test-lib.sh
+
+test_expect_success 'Initialize StGit stack' '
+    test_commit_bulk --message="p%s" 2 &&
+    stg uncommit -n 2
+'
+
+test_expect_success 'Committer identity from option' '
+    stg --committer-identity="Bot User <bot@example.com>" new -m "new patch" p3 &&
+    test "$(git log -1 --format="%cn <%ce>" $(stg id p3))" = "Bot User <bot@example.com>" &&
+    test "$(git log -1 --format="%an <%ae>" $(stg id p3))" = "$GIT_AUTHOR_NAME <$GIT_AUTHOR_EMAIL>" &&
+    test "$(git log -1 --format="%cn <%ce>" refs/stacks/master)" = "Bot User <bot@example.com>"
+'
+
+test_expect_success 'Committer identity from config' '
+    test_config stgit.committer "Config Bot <config-bot@example.com>" &&
+    test_config committer.name "Configured Committer" &&
+    stg edit -m "p1 reworded" p1 &&
+    test "$(git log -1 --format="%cn <%ce>" $(stg id p1))" = "Config Bot <config-bot@example.com>" &&
+    test "$(git log -1 --format="%cn <%ce>" $(stg id p3))" = "Config Bot <config-bot@example.com>"
+'
+
+test_expect_success 'Option takes precedence over config' '
+    test_config stgit.committer "Config Bot <config-bot@example.com>" &&
+    stg --committer-identity="Bot User <bot@example.com>" edit -m "p2 reworded" p2 &&
+    test "$(git log -1 --format="%cn <%ce>" $(stg id p2))" = "Bot User <bot@example.com>"
+'
+
+test_expect_success 'Invalid committer identity' '
+    command_error stg --committer-identity="Bot User" new -m "bad" 2>err &&
+    grep -e "invalid committer identity" err &&
+    test_config stgit.committer "nobody" &&
+    command_error stg new -m "bad" 2>err &&
+    grep -e "invalid committer identity" err
+'
+
+test_done
@@ -0,0 +1,84 @@
+#!/bin/sh
+
+test_description='Test stg checkout'
+
+. ./test-lib.sh
+
+test_expect_success 'Initialize StGit stack' '
+    test_commit_bulk --start=0 --filename=file%s.txt --contents="content %s" --message="p%s" 4 &&
+    stg uncommit -n 4 &&
+    stg pop p3
+'
+
+test_expect_success 'Return without a checked out patch' '
+    command_error stg checkout - 2>err &&
+    grep -e "no patch is checked out; nothing to return from" err
+'
+
+test_expect_success 'Check out an applied patch' '
+    stg checkout p1 &&
+    test "$(git rev-parse HEAD)" = "$(stg id --branch=master p1)" &&
+    test_must_fail git symbolic-ref -q HEAD &&
+    test_path_is_file file1.txt &&
+    test_path_is_missing file2.txt &&
+    test "$(echo $(stg series --branch=master --applied --noprefix))" = "p0 p1 p2"
+'
+
+test_expect_success 'Stack commands are unavailable while detached' '
+    command_error stg top 2>err &&
+    grep -e "HEAD is detached" err
+'
+
+test_expect_success 'Check out another patch while detached' '
+    stg checkout p3 &&
+    test "$(git rev-parse HEAD)" = "$(stg id --branch=master p3)" &&
+    test_path_is_file file3.txt
+'
+
+test_expect_success 'Refuse to return with local changes' '
+    echo change >>file0.txt &&
+    command_error stg checkout - 2>err &&
+    grep -e "worktree not clean" err &&
+    git checkout file0.txt
+'
+
+test_expect_success 'Return to the branch' '
+    stg checkout - &&
+    test "$(git symbolic-ref HEAD)" = "refs/heads/master" &&
+    test "$(stg top)" = "p2" &&
+    test_path_is_missing file3.txt &&
+    command_error stg checkout -
+'
+
+test_expect_success 'Check out a patch with local changes' '
+    echo change >>file0.txt &&
+    command_error stg checkout p0 2>err &&
+    grep -e "worktree not clean" err &&
+    git checkout file0.txt &&
+    test "$(git symbolic-ref HEAD)" = "refs/heads/master"
+'
+
+test_expect_success 'Check out a nonexistent patch' '
+    command_error stg checkout bogus 2>err &&
+    grep -e "patch \`bogus\` does not exist" err &&
+    test "$(git symbolic-ref HEAD)" = "refs/heads/master"
+'
+
+test_expect_success 'Stale return branch is discarded after returning with git' '
+    stg checkout p1 &&
+    git checkout master &&
+    command_error stg checkout - 2>err &&
+    grep -e "no patch is checked out; nothing to return from" err &&
+    test_path_is_missing .git/STGIT_CHECKOUT_BRANCH
+'
+
+test_expect_success 'Stale return branch is discarded after detaching with git' '
+    stg checkout p1 &&
+    git checkout --detach "$(stg id --branch=master {base})" &&
+    command_error stg checkout p0 2>err &&
+    grep -e "HEAD is detached" err &&
+    test_path_is_missing .git/STGIT_CHECKOUT_BRANCH &&
+    git checkout master
+'
+
+test_done
@@ -116,6 +116,21 @@ test_expect_success 'New with verbose config boolean option' '
     grep "something else" raw_commit_message.txt
 '
 
+test_expect_success 'New with verbose flag or commit.verbose config' '
+    test_set_editor "$(pwd)/fake-editor" &&
+    test_when_finished test_set_editor false &&
+    stg new --edit --verbose verbose-long-patch &&
+    grep "something else" raw_commit_message.txt &&
+    stg new -e -v verbose-short-patch &&
+    grep "something else" raw_commit_message.txt &&
+    test_config commit.verbose true &&
+    stg new commit-verbose-patch &&
+    grep "something else" raw_commit_message.txt &&
+    test_config stgit.edit.verbose false &&
+    stg new stgit-verbose-false-patch &&
+    ! grep "something else" raw_commit_message.txt
+'
+
 test_expect_success 'Use stgit.autosign' '
     test_config stgit.autosign "Signed-off-by" &&
     stg new -m autosigned-patch &&
@@ -140,4 +155,58 @@ test_expect_success 'Patch with escaped leading hyphen' '
     [ "$(echo $(stg top))" = "-patch-" ]
 '
 
+test_expect_success 'Setup branch for positioned new patches' '
+    stg branch --create positions &&
+    for i in 1 2 3 4; do
+        stg new -m "p$i" "p$i" &&
+        echo "p$i" >"p$i.txt" &&
+        stg add "p$i.txt" &&
+        stg refresh || return 1
+    done &&
+    stg pop p4
+'
+
+test_expect_success 'New patch --before applied patch' '
+    top_tree=$(git rev-parse HEAD^{tree}) &&
+    p1_id=$(stg id p1) &&
+    stg new --before p2 -m "before p2" b2 &&
+    test "$(echo $(stg series --applied --noprefix))" = "p1 b2 p2 p3" &&
+    test "$(stg top)" = "p3" &&
+    test "$(stg id p1)" = "$p1_id" &&
+    test "$(stg id b2^)" = "$p1_id" &&
+    test "$(git rev-parse HEAD^{tree})" = "$top_tree" &&
+    test -z "$(stg files b2)"
+'
+
+test_expect_success 'New patch --after applied patch' '
+    stg new --after p2 -m "after p2" a2 &&
+    test "$(echo $(stg series --applied --noprefix))" = "p1 b2 p2 a2 p3" &&
+    test "$(stg id a2^)" = "$(stg id p2)" &&
+    stg new --after p3 -m "after p3" a3 &&
+    test "$(echo $(stg series --applied --noprefix))" = "p1 b2 p2 a2 p3 a3" &&
+    test "$(stg top)" = "a3"
+'
+
+test_expect_success 'New patch next to unapplied patch' '
+    stg new --before p4 -m "before p4" b4 &&
+    stg new --after p4 -m "after p4" a4 &&
+    test "$(echo $(stg series --unapplied --noprefix))" = "b4 p4 a4" &&
+    test "$(stg id b4^)" = "$(stg id p4^)" &&
+    test "$(stg id a4^)" = "$(stg id p4)" &&
+    test "$(stg top)" = "a3"
+'
+
+test_expect_success 'New patch next to hidden patch' '
+    stg hide a4 &&
+    command_error stg new --after a4 -m "after a4" 2>err &&
+    grep "cannot position new patch next to hidden patch .a4." err
+'
+
+test_expect_success 'New --before conflicts with --after and --refresh' '
+    general_error stg new --before p1 --after p2 -m bad 2>err &&
+    grep "cannot be used with" err &&
+    general_error stg new --before p1 --refresh -m bad 2>err &&
+    grep "cannot be used with" err
+'
+
 test_done
@@ -61,4 +61,48 @@ test_expect_success 'Delete a nonexistent branch' '
    grep -e "branch \`bar\` not found" err
 '
 
+test_expect_success 'Refused delete lists unreachable patches' '
+    stg branch --create baz &&
+    test_commit --no-tag p2 &&
+    test_commit --no-tag p3 &&
+    test_commit --no-tag p4 &&
+    stg uncommit -n 3 &&
+    stg pop p4 &&
+    stg hide p4 &&
+    stg branch foo &&
+    command_error stg branch --delete baz 2>err &&
+    grep -e "delete not permitted: the series still contains patches" err &&
+    grep -e "The following 3 patches would become unreachable:" err &&
+    grep -e "^  + p2  [0-9a-f]\{7\}  p2\$" err &&
+    grep -e "^  + p3 " err &&
+    grep -e "^  ! p4 " err &&
+    test "$(git rev-parse --verify -q refs/heads/baz)" != ""
+'
+
+test_expect_success 'Reachable patches are not listed' '
+    git tag keep-p2 $(stg id --branch=baz p2) &&
+    command_error stg branch --delete baz 2>err &&
+    grep -e "The following 2 patches would become unreachable:" err &&
+    ! grep -e " p2 " err &&
+    git tag -d keep-p2
+'
+
+test_expect_success 'Delete branch and archive patches' '
+    stg branch --delete --archive baz 2>err &&
+    grep -e "patches archived in backup .1." err &&
+    test_must_fail git rev-parse --verify -q refs/heads/baz &&
+    test_must_fail git rev-parse --verify -q refs/stacks/baz &&
+    git rev-parse --verify -q refs/stgit-backup/baz/1
+'
+
+test_expect_success 'Recover archived patches' '
+    stg branch --create baz &&
+    stg recover >out &&
+    grep -e "^1 .* branch --delete: p2 p3 p4\$" out &&
+    stg recover 1 &&
+    test "$(echo $(stg series --unapplied --noprefix))" = "p2 p3 p4" &&
+    stg push -a &&
+    test "$(cat p3.t)" = "p3"
+'
+
 test_done
@@ -30,6 +30,18 @@ test_expect_success GPG 'Stack metadata signing disabled' '
     stg delete p0
 '
 
+test_expect_success GPG 'Stack metadata signing with stgit.gpgsign-stack' '
+    test_config stgit.gpgsign false &&
+    test_config stgit.gpgsign-stack true &&
+    stg new -m p0 &&
+    git verify-commit refs/stacks/master &&
+    git verify-commit refs/stacks/master^ &&
+    test_config stgit.gpgsign true &&
+    test_config stgit.gpgsign-stack false &&
+    stg delete p0 &&
+    test_must_fail git verify-commit refs/stacks/master
+'
+
 test_expect_success GPG 'stg new creates a signed patch' '
     stg new -m p0 &&
     git verify-commit HEAD
@@ -145,4 +157,28 @@ test_expect_success GPG 'Using invalid user.signingkey causes failure' '
     test "$(stg top)" = "p1"
 '
 
+test_expect_success GPG 'Signing disabled with --no-gpg-sign' '
+    git config commit.gpgsign true &&
+    git config user.signingkey ${GIT_COMMITTER_EMAIL} &&
+    test_config stgit.gpgsign-stack true &&
+    stg --no-gpg-sign new -m unsigned-p3 &&
+    test_must_fail git verify-commit HEAD &&
+    test_must_fail git verify-commit refs/stacks/master &&
+    stg new -m signed-p4 &&
+    git verify-commit HEAD &&
+    git verify-commit refs/stacks/master &&
+    stg --no-gpg-sign delete signed-p4 unsigned-p3
+'
+
+test_expect_success GPGSSH 'Stack metadata signed with SSH key' '
+    test_config gpg.format ssh &&
+    test_config user.signingkey "${GPGSSH_KEY_PRIMARY}" &&
+    test_config gpg.ssh.allowedSignersFile "${GPGSSH_ALLOWED_SIGNERS}" &&
+    test_config stgit.gpgsign-stack true &&
+    stg new -m ssh-signed &&
+    git verify-commit refs/stacks/master 2>err &&
+    grep "Good \"git\" signature" err &&
+    stg delete ssh-signed
+'
+
 test_done
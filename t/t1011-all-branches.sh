@@ -0,0 +1,82 @@
+#!/bin/sh
+
+test_description='Test stg all-branches'
+
+. ./test-lib.sh
+
+test_expect_success 'Setup branches with stacks' '
+    test_commit_bulk --message="initial %s" 2 &&
+    stg init &&
+    stg new -m p1 && stg new -m p2 &&
+    stg branch --create other &&
+    stg new -m q1 &&
+    git branch plain &&
+    stg branch --create third &&
+    stg branch master
+'
+
+test_expect_success 'Require a command' '
+    general_error stg all-branches 2>err &&
+    grep "required arguments were not provided" err
+'
+
+test_expect_success 'Run series on all branches' '
+    stg all-branches series --count >out 2>err &&
+    cat >expected <<-\EOF &&
+	2
+	1
+	0
+	master  ok
+	other   ok
+	third   ok
+	EOF
+    test_cmp expected out &&
+    grep "Running on \`master\`" err &&
+    ! grep "plain" err &&
+    test "$(stg branch)" = "master"
+'
+
+test_expect_success 'Report failures on some branches' '
+    command_error stg all-branches delete p2 >out 2>err &&
+    tail -n 3 out >summary &&
+    cat >expected <<-\EOF &&
+	master  ok
+	other   failed (2)
+	third   failed (2)
+	EOF
+    test_cmp expected summary &&
+    grep "command did not succeed on 2 of 3 branches" err &&
+    test "$(echo $(stg series --noprefix))" = "p1" &&
+    test "$(stg branch)" = "master"
+'
+
+test_expect_success 'Require clean work tree' '
+    echo dirty >>1.t &&
+    command_error stg all-branches series 2>err &&
+    grep "worktree not clean" err &&
+    git checkout 1.t
+'
+
+test_expect_success 'Stop when a branch is left unclean' '
+    stg branch --create alpha &&
+    echo one >x.txt && stg add x.txt && stg new -m r1 && stg refresh &&
+    stg pop &&
+    echo two >x.txt && stg add x.txt && stg new -m r0 && stg refresh &&
+    stg commit r0 &&
+    stg branch master &&
+    command_error stg all-branches push r1 >out 2>err &&
+    tail -n 4 out >summary &&
+    cat >expected <<-\EOF &&
+	alpha   failed (3)
+	master  not run
+	other   not run
+	third   not run
+	EOF
+    test_cmp expected summary &&
+    grep "Index or work tree not clean on \`alpha\`; stopping" err &&
+    test "$(stg branch)" = "alpha" &&
+    stg undo --hard &&
+    stg branch master
+'
+
+test_done
@@ -0,0 +1,131 @@
+#!/bin/sh
+
+test_description='Test stg serve'
+
+. ./test-lib.sh
+
+if ! curl --version >/dev/null 2>&1
+then
+    skip_all='skipping stg serve tests, curl not available'
+    test_done
+fi
+
+test_expect_success 'Initialize branches' '
+    test_commit_bulk --message="p%s" 3 &&
+    stg uncommit -n 3 &&
+    stg pop p3 &&
+    stg hide p3 &&
+    stg branch --create other &&
+    stg new -m "other patch" &&
+    stg branch master &&
+    git branch plain
+'
+
+stg serve --http 127.0.0.1:0 >serve.out 2>serve.err &
+echo $! >serve.pid
+
+test_expect_success 'Start server' '
+    test_atexit "kill $(cat serve.pid)" &&
+    for i in $(test_seq 1 50)
+    do
+        if grep -q "^Serving on " serve.out
+        then
+            break
+        fi &&
+        sleep 0.1 || return 1
+    done &&
+    sed -n -e "s/^Serving on \(http:.*\)\/\$/\1/p" serve.out >url &&
+    test -s url
+'
+
+get () {
+    curl --silent --show-error --output out --write-out "%{http_code}\n" "$(cat url)$1" >status
+}
+
+test_expect_success 'List branches' '
+    get /branches &&
+    echo 200 >expected && test_cmp expected status &&
+    cat >expected <<-\EOF &&
+	[
+	  {
+	    "name": "master",
+	    "applied": 2,
+	    "unapplied": 0,
+	    "hidden": 1
+	  },
+	  {
+	    "name": "other",
+	    "applied": 1,
+	    "unapplied": 0,
+	    "hidden": 0
+	  }
+	]
+	EOF
+    test_cmp expected out
+'
+
+test_expect_success 'List series' '
+    get /series &&
+    echo 200 >expected && test_cmp expected status &&
+    grep "\"branch\": \"master\"" out &&
+    grep "\"name\": \"p1\"" out &&
+    grep "\"state\": \"hidden\"" out &&
+    grep "\"commit\": \"$(stg id p2)\"" out &&
+    grep "\"subject\": \"p2\"" out &&
+    get "/series?branch=other" &&
+    grep "\"subject\": \"other patch\"" out &&
+    grep "\"empty\": true" out
+'
+
+test_expect_success 'Show patch diff' '
+    get "/diff?patch=p2" &&
+    echo 200 >expected && test_cmp expected status &&
+    grep "\"patch\": \"p2\"" out &&
+    grep "\"diff\": \"diff --git a/2.t b/2.t" out
+'
+
+test_expect_success 'Show stack log' '
+    get "/log?limit=2" &&
+    echo 200 >expected && test_cmp expected status &&
+    grep "\"message\":" out >messages &&
+    cat >expected <<-\EOF &&
+	    "message": "hide",
+	    "message": "pop",
+	EOF
+    test_cmp expected messages
+'
+
+test_expect_success 'Report errors' '
+    get "/diff?patch=p9" &&
+    echo 404 >expected && test_cmp expected status &&
+    grep "patch \`p9\` does not exist" out &&
+    get "/diff" &&
+    echo 400 >expected && test_cmp expected status &&
+    grep "\`patch\` parameter is required" out &&
+    get "/series?branch=plain" &&
+    echo 404 >expected && test_cmp expected status &&
+    grep "not initialized" out &&
+    get "/series?branch=nobranch" &&
+    echo 404 >expected && test_cmp expected status &&
+    grep "branch \`nobranch\` not found" out &&
+    get "/diff?branch=other&patch=p2" &&
+    echo 404 >expected && test_cmp expected status &&
+    grep "patch \`p2\` does not exist" out &&
+    get "/log?limit=many" &&
+    echo 400 >expected && test_cmp expected status &&
+    get /nowhere &&
+    echo 404 >expected && test_cmp expected status &&
+    curl --silent --request POST --output out --write-out "%{http_code}\n" \
+        "$(cat url)/branches" >status &&
+    echo 405 >expected && test_cmp expected status
+'
+
+test_expect_success 'Reject oversized request headers' '
+    curl --silent --header "X-Big: $(printf "%070000d" 0)" --output out \
+        --write-out "%{http_code}\n" "$(cat url)/branches" >status &&
+    echo 431 >expected && test_cmp expected status &&
+    get /branches &&
+    echo 200 >expected && test_cmp expected status
+'
+
+test_done
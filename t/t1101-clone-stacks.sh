@@ -0,0 +1,85 @@
+#!/bin/sh
+
+test_description='Test stg clone carrying stack refs'
+
+. ./test-lib.sh
+
+test_expect_success 'Setup upstream repository with stacks' '
+    test_create_repo upstream &&
+    (
+        cd upstream &&
+        test_commit_bulk --message="base %s" 1 &&
+        stg init &&
+        stg new -m p0 &&
+        stg new -m p1 &&
+        stg pop &&
+        git branch other &&
+        stg branch other &&
+        stg init &&
+        stg new -m q0 &&
+        stg branch master
+    )
+'
+
+test_expect_success 'Clone with stacks' '
+    stg clone upstream downstream &&
+    (
+        cd downstream &&
+        test "$(stg series --applied --noprefix)" = "p0" &&
+        test "$(stg series --unapplied --noprefix)" = "p1"
+    )
+'
+
+test_expect_success 'Fetch refspecs are configured' '
+    git -C downstream config --get-all remote.origin.fetch >fetch &&
+    cat >expected <<-\EOF &&
+	+refs/heads/*:refs/remotes/origin/*
+	+refs/stacks/*:refs/stacks/*
+	+refs/patches/*:refs/patches/*
+	EOF
+    test_cmp expected fetch
+'
+
+test_expect_success 'Branches of other stacks are created' '
+    (
+        cd downstream &&
+        test "$(git config branch.other.remote)" = "origin" &&
+        test "$(git config branch.other.merge)" = "refs/heads/other" &&
+        stg branch other &&
+        test "$(stg series --noprefix)" = "q0"
+    )
+'
+
+test_expect_success 'Stack state follows upstream on fetch' '
+    (
+        cd upstream &&
+        stg branch other &&
+        stg new -m q1 &&
+        stg branch master
+    ) &&
+    (
+        cd downstream &&
+        git fetch origin &&
+        stg branch master &&
+        git branch -f other origin/other &&
+        stg branch other &&
+        stg series --noprefix >series &&
+        printf "q0\nq1\n" >expected &&
+        test_cmp expected series
+    )
+'
+
+test_expect_success 'Clone with default directory name' '
+    git clone --quiet --mirror upstream mirror.git &&
+    stg clone mirror.git &&
+    (
+        cd mirror &&
+        test "$(stg series --noprefix)" = "$(printf "p0\np1")"
+    )
+'
+
+test_expect_success 'Clone into existing non-empty directory fails' '
+    command_error stg clone upstream downstream
+'
+
+test_done
@@ -25,7 +25,8 @@ test_expect_success 'Create the second patch' '
 '
 
 test_expect_success 'Push the first patch with conflict' '
-    conflict stg push foo
+    conflict stg push foo >out &&
+    grep -e "^hint: \`test\` conflicts with patch \`bar\`\$" out
 '
 
 test_expect_success 'Show the, now empty, first patch' '
@@ -46,4 +47,26 @@ test_expect_success 'Resolve the conflict' '
     stg refresh
 '
 
+test_expect_success 'Attribute conflicts to the patches that caused them' '
+    test_seq 1 10 >lines.txt &&
+    stg add lines.txt &&
+    stg new -m lines lines &&
+    stg refresh &&
+    sed -e "s/^2\$/two/" lines.txt >lines.tmp && mv lines.tmp lines.txt &&
+    stg new -m p-a p-a &&
+    stg refresh &&
+    sed -e "s/^9\$/nine/" lines.txt >lines.tmp && mv lines.tmp lines.txt &&
+    stg new -m p-b p-b &&
+    stg refresh &&
+    stg pop p-a p-b &&
+    sed -e "s/^9\$/NINE/" lines.txt >lines.tmp && mv lines.tmp lines.txt &&
+    stg new -m p-c p-c &&
+    stg refresh &&
+    stg pop p-c &&
+    stg push p-a p-b &&
+    conflict stg push p-c >out &&
+    grep -e "^hint: \`lines.txt\` conflicts with patch \`p-b\`\$" out &&
+    ! grep -e "p-a" out
+'
+
 test_done
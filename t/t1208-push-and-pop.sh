@@ -52,6 +52,22 @@ test_expect_success 'Check prev, next, and top with some applied' '
     [ "$(echo $(stg prev))" = "p5" ]
 '
 
+test_expect_success 'Check prev and next with offsets' '
+    [ "$(stg prev -n 3)" = "p3" ] &&
+    [ "$(stg prev --number=6)" = "p0" ] &&
+    command_error stg prev -n 7 2>err && grep -e "not enough patches applied" err &&
+    [ "$(stg next -n 2)" = "p8" ] &&
+    [ "$(stg next -n 3)" = "p9" ] &&
+    command_error stg next -n 4 2>err && grep -e "not enough unapplied patches" err &&
+    general_error stg next -n 0 2>err && grep -e "offset must be at least 1" err
+'
+
+test_expect_success 'Check prev, next, and top with --oid' '
+    [ "$(stg top --oid)" = "$(git rev-parse HEAD)" ] &&
+    [ "$(stg prev --oid -n 2)" = "$(stg id p4)" ] &&
+    [ "$(stg next --oid)" = "$(stg id p7)" ]
+'
+
 test_expect_success 'Check prev, next, and top with invalid arguments' '
     general_error stg prev bogus_arg 2>err && grep -e "error: unexpected argument .bogus_arg." err &&
     general_error stg next bogus_arg 2>err && grep -e "error: unexpected argument .bogus_arg." err &&
@@ -133,4 +149,29 @@ test_expect_success 'Attempt to push already applied patches' '
     grep -e "patch \`p99999\` does not exist" err
 '
 
+test_expect_success 'Push until a patch, excluding it' '
+    stg pop -a &&
+    stg push --until p3 &&
+    [ "$(echo $(stg series --applied --noprefix))" = "p0 p1 p2" ] &&
+    [ -z "$(stg push --until p3 2>&1)" ] &&
+    [ "$(echo $(stg series --applied --noprefix))" = "p0 p1 p2" ]
+'
+
+test_expect_success 'Push through a patch, including it' '
+    stg push --through p5 &&
+    [ "$(echo $(stg series --applied --noprefix))" = "p0 p1 p2 p3 p4 p5" ] &&
+    stg push --count 2 &&
+    [ "$(echo $(stg series --applied --noprefix))" = "p0 p1 p2 p3 p4 p5 p6 p8" ]
+'
+
+test_expect_success 'Push until or through invalid patches' '
+    command_error stg push --until p0 2>err &&
+    grep -e "patch \`p0\` is already applied" err &&
+    command_error stg push --through p99999 2>err &&
+    grep -e "patch \`p99999\` does not exist" err &&
+    general_error stg push --until p7 --through p9 &&
+    general_error stg push --until p7 -n 1 &&
+    general_error stg push --through p7 p9
+'
+
 test_done
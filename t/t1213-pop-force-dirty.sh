@@ -0,0 +1,75 @@
+#!/bin/sh
+
+test_description='Test pop and goto with --force-dirty'
+
+. ./test-lib.sh
+
+test_expect_success 'Initialize patches' '
+    echo base >unrelated.txt &&
+    git add unrelated.txt &&
+    git commit -m "add unrelated" &&
+    stg init &&
+    for i in 1 2 3; do
+        echo "$i" >"file$i.txt" &&
+        stg add "file$i.txt" &&
+        stg new -m "p$i" &&
+        stg refresh || return 1
+    done
+'
+
+test_expect_success 'Force dirty conflicts with keep' '
+    general_error stg pop --force-dirty --keep &&
+    general_error stg pop --force-dirty --spill &&
+    general_error stg goto --force-dirty --keep p1
+'
+
+test_expect_success 'Pop with unrelated local changes' '
+    echo local >>unrelated.txt &&
+    command_error stg pop 2>err &&
+    grep "worktree not clean" err &&
+    stg pop --force-dirty &&
+    test "$(echo $(stg series --applied --noprefix))" = "p1 p2" &&
+    test "$(tail -n 1 unrelated.txt)" = "local"
+'
+
+test_expect_success 'Pop with local changes to touched file' '
+    echo local >>file2.txt &&
+    command_error stg pop --force-dirty 2>err &&
+    grep "local changes to \`file2.txt\` would be affected" err &&
+    test "$(echo $(stg series --applied --noprefix))" = "p1 p2" &&
+    git checkout file2.txt
+'
+
+test_expect_success 'Pop patch below changed file touched by re-pushed patch' '
+    stg push --keep p3 &&
+    echo local >>file3.txt &&
+    command_error stg pop --force-dirty p2 2>err &&
+    grep "local changes to \`file3.txt\` would be affected" err &&
+    git checkout file3.txt
+'
+
+test_expect_success 'Goto with unrelated local changes' '
+    git add unrelated.txt &&
+    stg goto --force-dirty p1 &&
+    test "$(echo $(stg series --applied --noprefix))" = "p1" &&
+    stg goto --force-dirty p3 &&
+    test "$(echo $(stg series --applied --noprefix))" = "p1 p2 p3" &&
+    git diff --cached --name-only >staged &&
+    echo unrelated.txt >expected &&
+    test_cmp expected staged
+'
+
+test_expect_success 'Goto with local changes to touched file' '
+    stg goto p1 --force-dirty &&
+    echo 2 >file2.txt &&
+    command_error stg goto --force-dirty p2 2>err &&
+    grep "local changes to \`file2.txt\` would be affected" err &&
+    echo local >>file1.txt &&
+    command_error stg goto --force-dirty p3 2>err &&
+    grep "local changes to \`file2.txt\` would be affected" err &&
+    rm file2.txt &&
+    git checkout file1.txt &&
+    stg goto --force-dirty p3
+'
+
+test_done
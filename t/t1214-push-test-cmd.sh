@@ -0,0 +1,84 @@
+#!/bin/sh
+
+test_description='Test stg push --test-cmd'
+
+. ./test-lib.sh
+
+test_expect_success 'Initialize patches' '
+    test_commit_bulk --message="base %s" 1 &&
+    stg init &&
+    for i in 1 2 3 4; do
+        echo "$i" >"file$i.txt" &&
+        stg add "file$i.txt" &&
+        stg new -m "p$i" &&
+        stg refresh || return 1
+    done &&
+    echo bad >bad.txt &&
+    stg add bad.txt &&
+    stg new -m "p5" &&
+    stg refresh &&
+    stg pop -a
+'
+
+test_expect_success 'Test command requires argument' '
+    general_error stg push --skip-failing
+'
+
+test_expect_success 'Push with passing test command' '
+    stg push -n 2 --test-cmd "test -f file1.txt" >out &&
+    grep "^passed: p1\$" out &&
+    grep "^passed: p2\$" out &&
+    test "$(echo $(stg series --applied --noprefix))" = "p1 p2"
+'
+
+test_expect_success 'Test command runs after each patch in work tree root' '
+    mkdir -p subdir &&
+    (
+        cd subdir &&
+        stg push --test-cmd "ls file*.txt | wc -l >>counts.txt" p3 p4
+    ) &&
+    printf "3\n4\n" >expected &&
+    test_cmp expected counts.txt &&
+    rm counts.txt &&
+    stg pop -a
+'
+
+test_expect_success 'Push stops at failing patch' '
+    command_error stg push -a --test-cmd "! test -f bad.txt" >out 2>err &&
+    grep "^failed: p5\$" out &&
+    grep "test command failed for patch: p5" err &&
+    test "$(echo $(stg series --applied --noprefix))" = "p1 p2 p3 p4 p5" &&
+    stg pop -a
+'
+
+test_expect_success 'Push skipping failing patches' '
+    stg push -a &&
+    stg float p5 p2 &&
+    stg pop -a &&
+    command_error stg push -a --skip-failing \
+        --test-cmd "! test -f bad.txt && ! test -f file2.txt" >out 2>err &&
+    cat >expected <<-\EOF &&
+	passed: p1
+	passed: p3
+	passed: p4
+	failed: p5
+	failed: p2
+	EOF
+    grep -E "^(passed|failed):" out >report &&
+    test_cmp expected report &&
+    grep "test command failed for patches: p5 p2" err &&
+    test "$(echo $(stg series --applied --noprefix))" = "p1 p3 p4" &&
+    test "$(echo $(stg series --unapplied --noprefix))" = "p2 p5"
+'
+
+test_expect_success 'Skipped patch may be undone' '
+    stg undo &&
+    test "$(echo $(stg series --applied --noprefix))" = "p1 p3 p4 p2"
+'
+
+test_expect_success 'Test command conflicts with --noapply' '
+    stg pop -a &&
+    general_error stg push --noapply --test-cmd true p1
+'
+
+test_done
@@ -0,0 +1,98 @@
+#!/bin/sh
+
+test_description='Test "stg push --interactive"'
+
+. ./test-lib.sh
+
+test_expect_success 'Initialize StGit stack' '
+    test_commit_bulk --start=0 --filename=file%s.txt --contents="base %s" --message="p%s" 5 &&
+    stg uncommit -n 5 &&
+    stg pop -a
+'
+
+test_expect_success 'Interactive conflicts with --all' '
+    general_error stg push --interactive --all
+'
+
+test_expect_success 'Instructions list all unapplied patches' '
+    write_script fake-editor <<-\EOF &&
+	case "$1" in
+	*push-interactive*)
+	    grep -v "^#" "$1" >instructions
+	    printf "" >"$1"
+	    ;;
+	esac
+	EOF
+    test_set_editor "$(pwd)/fake-editor" &&
+    test_when_finished test_set_editor : &&
+    command_error stg push --interactive 2>err &&
+    grep -e "no patches listed; push aborted" err &&
+    cat >expected <<-\EOF &&
+	push p0 # p0
+	push p1 # p1
+	push p2 # p2
+	push p3 # p3
+	push p4 # p4
+	EOF
+    test_cmp expected instructions &&
+    test -z "$(stg series --applied --noprefix)"
+'
+
+test_expect_success 'Instructions list given patches' '
+    write_script fake-editor <<-\EOF &&
+	case "$1" in
+	*push-interactive*)
+	    grep -v "^#" "$1" >instructions
+	    printf "" >"$1"
+	    ;;
+	esac
+	EOF
+    test_set_editor "$(pwd)/fake-editor" &&
+    test_when_finished test_set_editor : &&
+    command_error stg push -i p3 p1 2>err &&
+    cat >expected <<-\EOF &&
+	push p3 # p3
+	push p1 # p1
+	EOF
+    test_cmp expected instructions
+'
+
+test_expect_success 'Select and reorder patches to push' '
+    write_script fake-editor <<-\EOF &&
+	case "$1" in
+	*push-interactive*)
+	    printf "push p2\np p0 # first\n\n# comment\n" >"$1"
+	    ;;
+	esac
+	EOF
+    test_set_editor "$(pwd)/fake-editor" &&
+    test_when_finished test_set_editor : &&
+    stg push --interactive &&
+    test "$(echo $(stg series --applied --noprefix))" = "p2 p0" &&
+    test "$(echo $(stg series --unapplied --noprefix))" = "p1 p3 p4" &&
+    test "$(stg log -n 1 | sed -e "s/.*   //")" = "push"
+'
+
+test_expect_success 'Bad instructions' '
+    write_script fake-editor <<-\EOF &&
+	case "$1" in
+	*push-interactive*)
+	    cat instructions >"$1"
+	    ;;
+	esac
+	EOF
+    test_set_editor "$(pwd)/fake-editor" &&
+    test_when_finished test_set_editor : &&
+    printf "push p1\nbogus p3\n" >instructions &&
+    command_error stg push -i 2>err &&
+    grep -e "unknown instruction action .bogus." err &&
+    printf "push p1\npush p0\n" >instructions &&
+    command_error stg push -i 2>err &&
+    grep -e "patch .p0. is not being pushed" err &&
+    printf "push p1\npush p1\n" >instructions &&
+    command_error stg push -i 2>err &&
+    grep -e "duplicated patch name .p1." err &&
+    test "$(echo $(stg series --applied --noprefix))" = "p2 p0"
+'
+
+test_done
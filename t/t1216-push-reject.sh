@@ -0,0 +1,56 @@
+#!/bin/sh
+
+test_description='Test "stg push --reject"'
+
+. ./test-lib.sh
+
+test_expect_success 'Initialize StGit stack' '
+    test_seq 1 20 >a.txt &&
+    echo "*.rej" >>.git/info/exclude &&
+    git add a.txt &&
+    git commit -m "initial" &&
+    stg init &&
+    sed -e "s/^2\$/two/" a.txt >a.tmp && mv a.tmp a.txt &&
+    stg new -m p0 p0 &&
+    stg refresh &&
+    sed -e "s/^two\$/two again/" -e "s/^19\$/nineteen/" a.txt >a.tmp && mv a.tmp a.txt &&
+    stg new -m p1 p1 &&
+    stg refresh &&
+    stg pop -a
+'
+
+test_expect_success 'Reject conflicts with set-tree and noapply' '
+    general_error stg push --reject --set-tree p1 &&
+    general_error stg push --reject --noapply p1
+'
+
+test_expect_success 'Push without conflicts is unaffected by reject' '
+    stg push --reject p0 &&
+    test "$(echo $(stg series --applied --noprefix))" = "p0" &&
+    test_path_is_missing a.txt.rej &&
+    stg pop p0
+'
+
+test_expect_success 'Push with rejected hunks' '
+    conflict stg push --reject p1 2>err &&
+    grep -e "patch \`p1\` does not apply cleanly; rejected hunks were written to \`\*.rej\` files" err &&
+    test "$(echo $(stg series --applied --noprefix))" = "p1" &&
+    test_path_is_file a.txt.rej &&
+    grep -e "^+two again\$" a.txt.rej &&
+    ! grep -e "^[<>=]\{7\}" a.txt &&
+    test "$(sed -n -e 2p a.txt)" = "2" &&
+    test "$(sed -n -e 19p a.txt)" = "nineteen" &&
+    test -z "$(stg files --bare p1)" &&
+    test "$(git diff --name-only)" = "a.txt"
+'
+
+test_expect_success 'Refresh after applying rejected hunks' '
+    rm a.txt.rej &&
+    sed -e "s/^2\$/two again/" a.txt >a.tmp && mv a.tmp a.txt &&
+    stg refresh &&
+    test "$(echo $(stg files --bare p1))" = "a.txt" &&
+    test "$(git diff --stat HEAD^ HEAD | tail -n 1)" = \
+         " 1 file changed, 2 insertions(+), 2 deletions(-)"
+'
+
+test_done
@@ -0,0 +1,84 @@
+#!/bin/sh
+
+test_description='Test pushing independent patches in parallel'
+
+. ./test-lib.sh
+
+test_expect_success 'Initialize StGit stack' '
+    mkdir dir &&
+    for i in 0 1 2 3 4 5; do
+        test_seq 1 10 >file$i.txt &&
+        test_seq 1 10 >dir/file$i.txt || return 1
+    done &&
+    test_seq 1 10 >shared.txt &&
+    echo extra >dir/extra.txt &&
+    git add . &&
+    git commit -m "initial" &&
+    stg init &&
+    for i in 0 1 2 3 4 5; do
+        sed -e "s/^1\$/one $i/" file$i.txt >tmp && mv tmp file$i.txt &&
+        sed -e "s/^10\$/ten $i/" dir/file$i.txt >tmp && mv tmp dir/file$i.txt &&
+        stg new -m "p$i" &&
+        stg refresh || return 1
+    done &&
+    sed -e "s/^5\$/five/" shared.txt >tmp && mv tmp shared.txt &&
+    rm file0.txt &&
+    stg new -m p6 &&
+    stg refresh &&
+    sed -e "s/^5\$/five again/" -e "s/^6\$/six/" shared.txt >tmp && mv tmp shared.txt &&
+    echo new >dir/new.txt &&
+    rm dir/file1.txt &&
+    git add -A &&
+    stg new -m p7 &&
+    stg refresh --index &&
+    stg pop -a
+'
+
+test_expect_success 'Change the stack base' '
+    git rm -q dir/extra.txt &&
+    sed -e "s/^5\$/five/" file2.txt >tmp && mv tmp file2.txt &&
+    sed -e "s/^9\$/nine/" shared.txt >tmp && mv tmp shared.txt &&
+    git add file2.txt shared.txt &&
+    git commit -m "new base" &&
+    stg repair
+'
+
+test_expect_success 'Push patches serially' '
+    test_config stgit.push.jobs 1 &&
+    stg push -a &&
+    for p in $(stg series --noprefix); do
+        echo "$p $(git rev-parse $(stg id $p)^{tree})" || return 1
+    done >serial-trees &&
+    test_line_count = 8 serial-trees &&
+    stg undo --hard &&
+    test -z "$(stg series --applied --noprefix)"
+'
+
+test_expect_success 'Push patches in parallel' '
+    test_config stgit.push.jobs 4 &&
+    stg push -a &&
+    for p in $(stg series --noprefix); do
+        echo "$p $(git rev-parse $(stg id $p)^{tree})" || return 1
+    done >parallel-trees &&
+    test_cmp serial-trees parallel-trees &&
+    git diff --quiet HEAD &&
+    test_path_is_missing dir/file1.txt &&
+    test_path_is_missing dir/extra.txt &&
+    test_path_is_file dir/new.txt
+'
+
+test_expect_success 'Parallel push stops at conflicts' '
+    stg pop -a &&
+    sed -e "s/^1\$/base one/" file3.txt >tmp && mv tmp file3.txt &&
+    git add file3.txt &&
+    git commit -m "conflicting base" &&
+    stg repair &&
+    test_config stgit.push.jobs 4 &&
+    conflict stg push -a &&
+    test "$(echo $(stg series --applied --noprefix))" = "p0 p1 p2 p3" &&
+    test "$(git diff --name-only --diff-filter=U)" = "file3.txt" &&
+    stg undo --hard &&
+    test -z "$(stg series --applied --noprefix)"
+'
+
+test_done
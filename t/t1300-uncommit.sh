@@ -104,6 +104,49 @@ test_expect_success 'Attempt to use invalid patch name' '
     grep -e "error: invalid value .bad\.\.patchname. for .\[patchname\]\.\.\.." err
 '
 
+test_expect_success 'Edit generated patch names' '
+    write_script fake-editor <<-\EOF &&
+	case "$1" in
+	*uncommit-names*)
+	    grep -v "^#" "$1" >names &&
+	    sed -i -e "s/^bar-patch /renamed-bar /" "$1"
+	    ;;
+	esac
+	EOF
+    test_set_editor "$(pwd)/fake-editor" &&
+    test_when_finished test_set_editor : &&
+    stg uncommit --edit-names -n 2 &&
+    cat >expected <<-\EOF &&
+	foo-patch # Foo Patch
+	bar-patch # Bar Patch
+	EOF
+    test_cmp expected names &&
+    test "$(echo $(stg series --noprefix))" = "foo-patch renamed-bar" &&
+    stg commit --all
+'
+
+test_expect_success 'Edited patch names are checked' '
+    write_script fake-editor <<-\EOF &&
+	case "$1" in
+	*uncommit-names*)
+	    cat names >"$1"
+	    ;;
+	esac
+	EOF
+    test_set_editor "$(pwd)/fake-editor" &&
+    test_when_finished test_set_editor : &&
+    printf "p0\n" >names &&
+    command_error stg uncommit -e -n 2 2>err &&
+    grep -e "expected 2 patch names, found 1" err &&
+    printf "p0\np0\n" >names &&
+    command_error stg uncommit -e -n 2 2>err &&
+    grep -e "patch name .p0. collides with .p0." err &&
+    printf "# nothing\n" >names &&
+    command_error stg uncommit -e -n 2 2>err &&
+    grep -e "no patch names listed; uncommit aborted" err &&
+    test -z "$(stg series)"
+'
+
 test_expect_success 'Uncommit a commit with not precisely one parent' '
     command_error stg uncommit -n 5  &&
     [ "$(echo $(stg series))" = "" ]
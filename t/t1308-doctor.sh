@@ -0,0 +1,101 @@
+#!/bin/sh
+
+test_description='Test stg doctor'
+
+. ./test-lib.sh
+
+# Rewrite the stack.json of the stack state with the given sed script.
+edit_stack_json () {
+    git cat-file blob refs/stacks/master:stack.json | sed -e "$1" >stack.json &&
+    blob=$(git hash-object -w stack.json) &&
+    tree=$(printf "100644 blob %s\tstack.json\n" "$blob" | git mktree) &&
+    commit=$(git commit-tree -p refs/stacks/master -m "edit stack.json" "$tree") &&
+    git update-ref refs/stacks/master "$commit" &&
+    rm stack.json
+}
+
+test_expect_success 'Initialize patches' '
+    test_commit_bulk --message="base %s" 1 &&
+    stg init &&
+    for i in 1 2 3; do
+        echo "$i" >"file$i.txt" &&
+        stg add "file$i.txt" &&
+        stg new -m "p$i" &&
+        stg refresh || return 1
+    done
+'
+
+test_expect_success 'Doctor on healthy stack' '
+    stg doctor 2>err &&
+    grep "no problems found" err
+'
+
+test_expect_success 'Duplicate patch entry' '
+    edit_stack_json "s/\"unapplied\": \[\]/\"unapplied\": [\"p1\"]/" &&
+    command_error stg doctor >out 2>err &&
+    grep "^problem: patch \`p1\` is listed more than once\$" out &&
+    grep "found 1 problem; use \`stg doctor --fix\`" err &&
+    stg doctor --fix &&
+    stg doctor &&
+    test "$(echo $(stg series --noprefix))" = "p1 p2 p3"
+'
+
+test_expect_success 'Undo doctor fix' '
+    stg undo &&
+    command_error stg doctor &&
+    stg doctor --fix
+'
+
+test_expect_success 'Missing patch commit' '
+    edit_stack_json "/\"p3\": {/,/}/s/\"oid\": \"[0-9a-f]*\"/\"oid\": \"1111111111111111111111111111111111111111\"/" &&
+    command_error stg doctor >out &&
+    grep "^problem: commit \`1111111111111111111111111111111111111111\` of patch \`p3\` is missing\$" out &&
+    grep "^problem: stack head does not match topmost patch \`p2\`\$" out &&
+    grep "^problem: branch head does not match stack head\$" out &&
+    stg doctor --fix &&
+    stg doctor &&
+    test "$(echo $(stg series --noprefix))" = "p1 p2 p3" &&
+    test "$(cat file3.txt)" = "3"
+'
+
+test_expect_success 'Broken applied chain' '
+    edit_stack_json "/\"applied\": \[/,/\]/{
+s/\"p2\"/\"PX\"/
+s/\"p3\"/\"p2\"/
+s/\"PX\"/\"p3\"/
+}" &&
+    command_error stg doctor >out &&
+    grep "^problem: applied patch \`p3\` is not a child of \`p1\`\$" out &&
+    grep "^problem: stack head does not match topmost patch \`p1\`\$" out &&
+    stg doctor --fix &&
+    stg doctor &&
+    test "$(echo $(stg series --applied --noprefix))" = "p1 p2 p3"
+'
+
+test_expect_success 'Unreadable stack state' '
+    edit_stack_json "s/\"version\": 5/\"version\": \"bad\"/" &&
+    command_error stg series &&
+    command_error stg doctor >out &&
+    grep "^problem: stack state \`refs/stacks/master\` is unreadable" out &&
+    stg doctor --fix &&
+    stg doctor &&
+    test "$(echo $(stg series --noprefix))" = "p1 p2 p3"
+'
+
+test_expect_success 'Branch head mismatch on other branch' '
+    git checkout -b other &&
+    stg init &&
+    git checkout master &&
+    git commit --allow-empty -m "plain commit" &&
+    git checkout other &&
+    command_error stg doctor -b master >out 2>err &&
+    grep "^problem: branch head does not match stack head\$" out &&
+    grep "fix: none; check out \`master\` and run \`stg repair\`" out &&
+    command_error stg doctor -b master --fix 2>err &&
+    grep "1 problem could not be fixed" err &&
+    git checkout master &&
+    stg doctor --fix &&
+    stg doctor
+'
+
+test_done
@@ -0,0 +1,113 @@
+#!/bin/sh
+
+test_description='Test "stg repair --interactive"'
+
+. ./test-lib.sh
+
+test_expect_success 'Initialize StGit stack and commit with git' '
+    stg init &&
+    stg new -m p0 &&
+    echo p0 >p0.txt &&
+    stg add p0.txt &&
+    stg refresh &&
+    stg new -m p1 &&
+    echo p1 >p1.txt &&
+    stg add p1.txt &&
+    stg refresh &&
+    test_commit c1 c1.txt &&
+    test_commit c2 c2.txt &&
+    echo "more p1" >>p1.txt &&
+    git commit -a -m c3 &&
+    echo "more c1" >>c1.txt &&
+    git commit -a -m c4
+'
+
+test_expect_success 'Removing all instructions aborts repair' '
+    write_script fake-editor <<-\EOF &&
+	case "$1" in
+	*repair-interactive*)
+	    grep -v "^#" "$1" | sed -e "s/ [0-9a-f]* # / /" >instructions
+	    printf "" >"$1"
+	    ;;
+	esac
+	EOF
+    test_set_editor "$(pwd)/fake-editor" &&
+    test_when_finished test_set_editor : &&
+    head=$(git rev-parse HEAD) &&
+    command_error stg repair --interactive 2>err &&
+    grep -e "no commits listed; repair aborted" err &&
+    cat >expected <<-\EOF &&
+	patch c1
+	patch c2
+	patch c3
+	patch c4
+	EOF
+    test_cmp expected instructions &&
+    test "$(git rev-parse HEAD)" = "$head"
+'
+
+test_expect_success 'Bad instructions' '
+    write_script fake-editor <<-\EOF &&
+	case "$1" in
+	*repair-interactive*)
+	    cat instructions >"$1"
+	    ;;
+	esac
+	EOF
+    test_set_editor "$(pwd)/fake-editor" &&
+    test_when_finished test_set_editor : &&
+    head=$(git rev-parse HEAD) &&
+    echo "bogus $(git rev-parse HEAD)" >instructions &&
+    command_error stg repair -i 2>err &&
+    grep -e "unknown instruction action .bogus." err &&
+    echo "patch $(stg id p1)" >instructions &&
+    command_error stg repair -i 2>err &&
+    grep -e "commit .$(stg id p1). is not being repaired" err &&
+    printf "patch %s\nabsorb %s\n" $(git rev-parse HEAD HEAD) >instructions &&
+    command_error stg repair -i 2>err &&
+    grep -e "duplicated commit" err &&
+    test "$(git rev-parse HEAD)" = "$head"
+'
+
+test_expect_success 'Patch, absorb, and discard commits' '
+    write_script fake-editor <<-\EOF &&
+	case "$1" in
+	*repair-interactive*)
+	    sed -i -e "s/^patch \([0-9a-f]*\) # c2$/discard \1/" \
+	           -e "s/^patch \([0-9a-f]*\) # c3$/a \1/" \
+	           -e "/ # c4$/d" "$1"
+	    ;;
+	esac
+	EOF
+    test_set_editor "$(pwd)/fake-editor" &&
+    test_when_finished test_set_editor : &&
+    stg repair --interactive &&
+    test "$(echo $(stg series --noprefix --applied))" = "p0 p1 c1" &&
+    test "$(git rev-parse HEAD)" = "$(stg id c1)" &&
+    test "$(echo $(stg files --bare c1))" = "c1.txt p1.txt" &&
+    test "$(cat c1.txt)" = "c1" &&
+    test "$(echo $(cat p1.txt))" = "p1 more p1" &&
+    test_path_is_missing c2.txt &&
+    test -z "$(git status --porcelain --untracked-files=no)"
+'
+
+test_expect_success 'Absorb without any patch' '
+    stg pop -a &&
+    stg push p0 &&
+    echo amended >>p0.txt &&
+    git commit -a --amend -m "p0 amended" &&
+    echo "absorb $(git rev-parse HEAD)" >instructions &&
+    write_script fake-editor <<-\EOF &&
+	case "$1" in
+	*repair-interactive*)
+	    cat instructions >"$1"
+	    ;;
+	esac
+	EOF
+    test_set_editor "$(pwd)/fake-editor" &&
+    test_when_finished test_set_editor : &&
+    command_error stg repair -i 2>err &&
+    grep -e "no patch to absorb commit" err
+'
+
+test_done
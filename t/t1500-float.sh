@@ -100,4 +100,37 @@ test_expect_success 'Series with bogus patch name' '
     grep -e "error: <stdin>: patch \`BOGUS\` does not exist"
 '
 
+test_expect_success 'Float patches after a target' '
+    stg float --after p5 p1 p2 &&
+    test "$(echo $(stg series --applied --noprefix))" = "p7 p6 p5 p1 p2 p4 p3"
+'
+
+test_expect_success 'Float patches before a target' '
+    stg float --before p6 p3 p4 &&
+    test "$(echo $(stg series --applied --noprefix))" = "p7 p3 p4 p6 p5 p1 p2"
+'
+
+test_expect_success 'Float unapplied patch after a target' '
+    stg pop p2 &&
+    stg float --after p7 p2 &&
+    test "$(echo $(stg series --applied --noprefix))" = "p7 p2 p3 p4 p6 p5 p1" &&
+    test -z "$(stg series --unapplied --noprefix)"
+'
+
+test_expect_success 'Attempt float after unapplied target' '
+    stg pop p1 &&
+    command_error stg float --after p1 p7 2>err &&
+    grep -e "cannot float above \`p1\` since it is not applied" err &&
+    stg push p1
+'
+
+test_expect_success 'Attempt float before a patch being floated' '
+    command_error stg float --before p3 p3 p4 2>err &&
+    grep -e "target patch \`p3\` may not also be a patch to float" err
+'
+
+test_expect_success 'Attempt float after with --noapply' '
+    general_error stg float --noapply --after p3 p4
+'
+
 test_done
@@ -92,4 +92,27 @@ test_expect_success 'sink with conflict' '
     test "$(echo $(stg status))" = "DU f2"
 '
 
+test_expect_success 'sink patches after a target' '
+    stg undo --hard &&
+    test "$(echo $(stg series --applied --noprefix))" = "p1 p2 p3 p4" &&
+    stg sink --after=p1 p3 p4 &&
+    test "$(echo $(stg series --applied --noprefix))" = "p1 p3 p4 p2"
+'
+
+test_expect_success 'sink patches before a target' '
+    stg sink --before=p3 p2 &&
+    test "$(echo $(stg series --applied --noprefix))" = "p1 p2 p3 p4"
+'
+
+test_expect_success 'sink --nopush after a target' '
+    stg sink --nopush --after=p1 p3 &&
+    test "$(echo $(stg series --applied --noprefix))" = "p1 p3" &&
+    test "$(echo $(stg series --unapplied --noprefix))" = "p2 p4 p22" &&
+    stg goto p4
+'
+
+test_expect_success 'attempt sink with both --to and --after' '
+    general_error stg sink --to=p1 --after=p1 p4
+'
+
 test_done
@@ -0,0 +1,99 @@
+#!/bin/sh
+
+test_description='Test automatic backups and stg recover'
+
+. ./test-lib.sh
+
+test_expect_success 'Initialize repo with patches' '
+    test_commit_bulk --message="base %s" 1 &&
+    git config stgit.backup.expire 0 &&
+    stg init &&
+    for i in 1 2 3 4; do
+        echo "$i" >"file$i.txt" &&
+        stg add "file$i.txt" &&
+        stg new -m "p$i" &&
+        stg refresh || return 1
+    done &&
+    stg new -m empty
+'
+
+test_expect_success 'No backups initially' '
+    stg recover >out &&
+    test_must_be_empty out
+'
+
+test_expect_success 'Delete makes backup' '
+    stg label add keep p2 &&
+    stg meta get p2 uuid >uuid-p2 &&
+    stg delete p2 p3 &&
+    stg recover >out &&
+    test_line_count = 1 out &&
+    grep "^1  .*  delete: p2 p3\$" out &&
+    git rev-parse --verify refs/stgit-backup/master/1
+'
+
+test_expect_success 'Recover deleted patches' '
+    stg recover 1 &&
+    test "$(echo $(stg series --unapplied --noprefix))" = "p2 p3" &&
+    test "$(stg label list p2)" = "keep" &&
+    stg meta get p2 uuid >uuid-recovered &&
+    test_cmp uuid-p2 uuid-recovered &&
+    stg recover 1 &&
+    test "$(echo $(stg series --unapplied --noprefix))" = "p2 p3"
+'
+
+test_expect_success 'Recover single patch with name collision' '
+    stg edit -m "changed" p3 &&
+    stg recover 1 p3 &&
+    test "$(echo $(stg series --unapplied --noprefix))" = "p2 p3 p5" &&
+    test "$(stg id p5)" != "$(stg id p3)" &&
+    test "$(stg meta get p5 uuid)" != "$(stg meta get p3 uuid)"
+'
+
+test_expect_success 'Recover errors' '
+    command_error stg recover 9 2>err &&
+    grep "backup \`9\` not found" err &&
+    command_error stg recover 1 nonexistent 2>err &&
+    grep "patch \`nonexistent\` not found in backup \`1\`" err &&
+    general_error stg recover 1 "bad name"
+'
+
+test_expect_success 'Clean makes backup' '
+    stg clean &&
+    stg recover >out &&
+    grep "^2  .*  clean: empty\$" out
+'
+
+test_expect_success 'Squash makes backup' '
+    stg delete p5 &&
+    stg push p2 p3 &&
+    stg squash -m squashed p2 p3 &&
+    stg recover >out &&
+    grep "^4  .*  squash: p2 p3\$" out
+'
+
+test_expect_success 'Reset --hard makes backup' '
+    state=$(git rev-parse refs/stacks/master) &&
+    stg new -m extra &&
+    stg reset --hard $state &&
+    command_error stg id extra &&
+    stg recover >out &&
+    grep "^5  .*  reset --hard: extra\$" out
+'
+
+test_expect_success 'Expired backups are removed' '
+    test_config stgit.backup.expire 1 &&
+    stg delete p1 &&
+    stg recover >out &&
+    test_line_count = 1 out &&
+    grep "^6  .*  delete: p1\$" out &&
+    test_must_fail git rev-parse --verify refs/stgit-backup/master/5
+'
+
+test_expect_success 'Backups removed with branch' '
+    stg branch --create other &&
+    stg branch --delete --force master &&
+    test -z "$(git for-each-ref refs/stgit-backup/master)"
+'
+
+test_done
@@ -75,7 +75,30 @@ test_expect_success 'Goto with ambiguous patch substring' '
     command_error stg goto q1 2>err &&
     grep "error: patch \`q1\` does not exist" err &&
     command_error stg goto p 2>err &&
+    grep "error: \`p\` is ambiguous: matches \`p1\`, \`p2\`, \`p3\`, \`p4\`, and \`p5\`" err &&
+    command_error stg show p 2>err &&
+    grep "error: \`p\` is ambiguous: matches \`p1\`, \`p2\`" err &&
+    command_error stg goto p </dev/null 2>err &&
+    grep "error: \`p\` is ambiguous" err &&
+    command_error stg delete p 2>err &&
     grep "patch \`p\` does not exist, but is similar to \`p1\`, \`p2\`" err
 '
 
+test_expect_success 'Goto with unambiguous patch substring' '
+    stg new -m long-descriptive-name &&
+    stg goto p3 &&
+    stg goto descriptive &&
+    test "$(stg top)" = "long-descriptive-name" &&
+    stg goto ldn &&
+    test "$(stg top)" = "long-descriptive-name" &&
+    stg goto p3 &&
+    test "$(stg top)" = "p3" &&
+    stg show descr >show-out &&
+    grep -e "long-descriptive-name" show-out &&
+    command_error stg delete descriptive 2>err &&
+    grep "error: patch \`descriptive\` does not exist" err &&
+    stg series --noprefix >series-out &&
+    grep -e "long-descriptive-name" series-out
+'
+
 test_done
@@ -351,6 +351,31 @@ test_expect_success 'Import with root directory' '
     stg delete dirs from-relative
 '
 
+test_expect_success 'Round-trip binary patch through export and import' '
+    printf "\000\001\002\003" >bin.dat &&
+    cp bin.dat bin.dat.orig &&
+    stg add bin.dat &&
+    stg new -rm "binary" &&
+    stg export -d binary-export binary &&
+    grep -e "GIT binary patch" binary-export/binary &&
+    stg delete binary &&
+    test_path_is_missing bin.dat &&
+    stg import -n binary-imported binary-export/binary &&
+    test_cmp_bin bin.dat.orig bin.dat
+'
+
+test_expect_success 'Import binary diff without binary data' '
+    printf "\004\005\006" >>bin.dat &&
+    cp bin.dat bin.dat.new &&
+    git diff >binary-short.diff &&
+    grep -e "Binary files a/bin.dat and b/bin.dat differ" binary-short.diff &&
+    git hash-object -w bin.dat >/dev/null &&
+    git checkout bin.dat &&
+    stg import -n binary-short binary-short.diff &&
+    test_cmp_bin bin.dat.new bin.dat &&
+    stg delete binary-imported binary-short
+'
+
 test_expect_success 'Import series from stdin' '
     echo "some.patch" |
     stg import --series &&
@@ -0,0 +1,57 @@
+#!/bin/sh
+
+test_description='Test importing git format-patch directories'
+
+. ./test-lib.sh
+
+test_expect_success 'Create format-patch output' '
+    test_commit_bulk --message="base" 1 &&
+    git tag base &&
+    git checkout -b work &&
+    echo a >a.txt &&
+    git add a.txt &&
+    git commit -m "add a" &&
+    echo b >b.txt &&
+    git add b.txt &&
+    git commit -m "add b" &&
+    git format-patch --cover-letter -o skeleton master &&
+    git format-patch --cover-letter -o outgoing master &&
+    sed -i -e "s/\*\*\* SUBJECT HERE \*\*\*/Add a and b/" \
+        -e "s/\*\*\* BLURB HERE \*\*\*/Adds two files./" \
+        outgoing/0000-cover-letter.patch &&
+    git format-patch -v2 -o reroll master &&
+    git checkout master &&
+    stg init
+'
+
+test_expect_success 'Import directory with cover letter' '
+    stg import outgoing &&
+    test "$(echo $(stg series --noprefix))" = "add-a add-b" &&
+    test "$(git log -1 --format=%s)" = "add b" &&
+    stg cover >out &&
+    head -n 3 out >head &&
+    printf "Add a and b\n\nAdds two files.\n" >expected &&
+    test_cmp expected head
+'
+
+test_expect_success 'Skeleton cover letter is ignored' '
+    stg branch --create skel base &&
+    stg import skeleton &&
+    test "$(echo $(stg series --noprefix))" = "add-a add-b" &&
+    test_must_fail git config branch.skel.stgit.cover
+'
+
+test_expect_success 'Import rerolled directory without cover letter' '
+    stg branch --create reroll base &&
+    stg import reroll &&
+    test "$(echo $(stg series --noprefix))" = "add-a add-b"
+'
+
+test_expect_success 'Import directory without format-patch files' '
+    mkdir empty &&
+    echo foo >empty/notes.txt &&
+    command_error stg import empty 2>err &&
+    grep "no format-patch files found" err
+'
+
+test_done
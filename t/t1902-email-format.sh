@@ -80,4 +80,33 @@ test_expect_success 'With cover letter' '
     rmdir out
 '
 
+test_expect_success 'Format with rename detection' '
+    test_seq 1 20 >rename-src.txt &&
+    stg add rename-src.txt &&
+    stg new -rm add-rename-src &&
+    git mv rename-src.txt rename-dst.txt &&
+    stg new -rm rename &&
+    test_config diff.renames false &&
+    stg email format -G--stdout rename >out &&
+    ! grep -e "^rename from" out &&
+    stg email format -G--stdout --find-renames rename >out &&
+    grep -e "^rename from rename-src.txt" out &&
+    stg email format -G--stdout --find-copies=50 rename >out &&
+    grep -e "^rename from rename-src.txt" out
+'
+
+test_expect_success 'Subject prefix composition' '
+    stg email format -G--stdout rename >out &&
+    grep -e "^Subject: \[PATCH\] rename" out &&
+    test_config stgit.mail.prefix "PATCH net-next" &&
+    stg email format -G--stdout rename >out &&
+    grep -e "^Subject: \[PATCH net-next\] rename" out &&
+    stg email format -G--stdout --rfc --resend -v2 rename >out &&
+    grep -e "^Subject: \[RESEND RFC PATCH net-next v2\] rename" out &&
+    stg email format -G--stdout --subject-prefix=FOO --rfc rename >out &&
+    grep -e "^Subject: \[RFC FOO\] rename" out &&
+    stg email format -G--stdout -k rename >out &&
+    grep -e "^Subject: rename" out
+'
+
 test_done
@@ -0,0 +1,114 @@
+#!/bin/sh
+
+test_description='Test stg cover'
+
+. ./test-lib.sh
+
+test_expect_success 'Cover with no applied patches' '
+    test_commit_bulk --message="base %s" 1 &&
+    stg init &&
+    command_error stg cover 2>err &&
+    grep "no patches applied" err
+'
+
+test_expect_success 'Initialize patches' '
+    echo a >a.txt &&
+    stg add a.txt &&
+    stg new -m "add a" &&
+    stg refresh &&
+    echo b >b.txt &&
+    stg add b.txt &&
+    stg new -m "add b" --author "Other Author <other@example.com>" &&
+    stg refresh &&
+    echo aa >>a.txt &&
+    stg new -m "extend a" &&
+    stg refresh
+'
+
+test_expect_success 'Generate cover letter skeleton' '
+    stg cover >out &&
+    cat >expected <<-EOF &&
+	*** SUBJECT HERE ***
+
+	*** BLURB HERE ***
+
+	$GIT_AUTHOR_NAME (2):
+	  add a
+	  extend a
+
+	Other Author (1):
+	  add b
+
+	 a.txt |    2 ++
+	 b.txt |    1 +
+	 2 files changed, 3 insertions(+)
+	 create mode 100644 a.txt
+	 create mode 100644 b.txt
+	EOF
+    test_cmp expected out
+'
+
+test_expect_success 'Write cover letter to file' '
+    stg cover -o cover.txt &&
+    stg cover >out &&
+    test_cmp out cover.txt
+'
+
+test_expect_success 'Edit and store subject and blurb' '
+    write_script editor <<-\EOF &&
+	printf "The subject\n\nThe blurb\nspans lines.\n" >"$1"
+	EOF
+    test_set_editor "$(pwd)/editor" &&
+    stg cover --edit >out &&
+    head -n 5 out >head &&
+    printf "The subject\n\nThe blurb\nspans lines.\n\n" >expected &&
+    test_cmp expected head &&
+    test_set_editor : &&
+    stg cover >out2 &&
+    test_cmp out out2
+'
+
+test_expect_success 'Edit template has stored description' '
+    write_script editor <<-\EOF &&
+	cp "$1" edit-template.txt
+	EOF
+    test_set_editor "$(pwd)/editor" &&
+    stg cover --edit >/dev/null &&
+    head -n 4 edit-template.txt >head &&
+    printf "The subject\n\nThe blurb\nspans lines.\n" >expected &&
+    test_cmp expected head &&
+    grep "^#   add a\$" edit-template.txt &&
+    test_set_editor :
+'
+
+test_expect_success 'Cover letter with interdiff' '
+    git tag v1 &&
+    echo aaa >>a.txt &&
+    stg refresh &&
+    stg cover --interdiff v1 >out &&
+    sed -n "/^Interdiff against v1:\$/,\$p" out >interdiff &&
+    grep "^+aaa\$" interdiff &&
+    test_line_count = 9 interdiff
+'
+
+test_expect_success 'Cover letter with interdiff against stack state' '
+    state=$(git rev-parse refs/stacks/master) &&
+    echo bbb >>a.txt &&
+    stg refresh &&
+    stg cover --interdiff $state >out &&
+    sed -n "/^Interdiff against $state:\$/,\$p" out >interdiff &&
+    grep "^+bbb\$" interdiff &&
+    ! grep "^+aaa\$" interdiff &&
+    ! grep "stack.json" interdiff
+'
+
+test_expect_success 'Cover letter for other branch' '
+    stg branch --create other &&
+    command_error stg cover &&
+    stg cover -b master >out &&
+    head -n 1 out >head &&
+    echo "The subject" >expected &&
+    test_cmp expected head
+'
+
+test_done
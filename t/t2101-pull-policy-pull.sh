@@ -76,7 +76,8 @@ test_expect_success 'Rewind/rewrite upstream commit and pull it from clone, with
     ) &&
     (
         cd clone &&
-        conflict stg pull origin
+        conflict stg pull origin 2>err &&
+        grep "upstream .origin/master. no longer contains" err
     )
 '
 
@@ -100,4 +101,23 @@ test_expect_success 'Exercise stgit.keepoptimized' '
     )
 '
 
+test_expect_success 'Refuse to pull force-pushed upstream' '
+    (
+        cd upstream &&
+        stg commit -a &&
+        git commit --amend -m "u1 rewritten"
+    ) &&
+    (
+        cd clone &&
+        git fetch origin &&
+        command_error stg pull 2>err &&
+        grep "upstream .origin/master. no longer contains" err &&
+        grep "use .stg rebase origin/master." err &&
+        test "$(echo $(stg series --applied --noprefix))" = "c1" &&
+        stg rebase origin/master &&
+        test "$(git log -1 --format=%s $(stg id {base}))" = "u1 rewritten" &&
+        stg pull
+    )
+'
+
 test_done
@@ -0,0 +1,100 @@
+#!/bin/sh
+
+test_description='Test stg pull --reapply-policy'
+
+. ./test-lib.sh
+
+test_expect_success 'Setup upstream repo, clone it, and add patches to the clone' '
+    test_seq 1 10 >file &&
+    git add file &&
+    git commit -m "initial" &&
+    test_create_repo upstream &&
+    (
+        cd upstream &&
+        git pull .. master
+    ) &&
+    git clone upstream clone &&
+    (
+        cd clone &&
+        stg init &&
+        sed -e "s/^2\$/two/" file >file.tmp && mv file.tmp file &&
+        stg new -m p1 && stg refresh &&
+        sed -e "s/^5\$/five/" file >file.tmp && mv file.tmp file &&
+        stg new -m p2 && stg refresh &&
+        sed -e "s/^9\$/nine/" file >file.tmp && mv file.tmp file &&
+        stg new -m p3 && stg refresh &&
+        stg branch --create stop-branch origin/master &&
+        git config branch.stop-branch.remote origin &&
+        git config branch.stop-branch.merge refs/heads/master &&
+        stg branch master
+    )
+'
+
+test_expect_success 'Make upstream change conflicting with p2' '
+    (
+        cd upstream &&
+        sed -e "s/^5\$/FIVE/" file >file.tmp && mv file.tmp file &&
+        git commit -a -m "upstream five"
+    )
+'
+
+test_expect_success 'Invalid reapply policy' '
+    (
+        cd clone &&
+        general_error stg pull --reapply-policy=bogus 2>err &&
+        grep "invalid value .bogus." err &&
+        general_error stg pull --nopush --reapply-policy=skip 2>err &&
+        grep "cannot be used with" err
+    )
+'
+
+test_expect_success 'Pull skipping conflicting patches' '
+    (
+        cd clone &&
+        stg pull --reapply-policy=skip 2>err &&
+        grep "\`p2\` conflicts with the pulled changes; left unapplied" err &&
+        test "$(echo $(stg series --applied --noprefix))" = "p1 p3" &&
+        test "$(echo $(stg series --unapplied --noprefix))" = "p2" &&
+        grep "^FIVE\$" file &&
+        grep "^two\$" file &&
+        grep "^nine\$" file &&
+        git diff --quiet &&
+        git diff --cached --quiet
+    )
+'
+
+test_expect_success 'Make upstream change conflicting with p3' '
+    (
+        cd upstream &&
+        sed -e "s/^9\$/NINE/" file >file.tmp && mv file.tmp file &&
+        git commit -a -m "upstream nine"
+    )
+'
+
+test_expect_success 'Pull hiding conflicting patches with config' '
+    (
+        cd clone &&
+        git config stgit.pull.reapply-policy hide &&
+        stg pull 2>err &&
+        grep "\`p3\` conflicts with the pulled changes; hidden" err &&
+        test "$(echo $(stg series --applied --noprefix))" = "p1" &&
+        test "$(echo $(stg series --unapplied --noprefix))" = "p2" &&
+        test "$(echo $(stg series --hidden --noprefix))" = "p3" &&
+        git diff --quiet
+    )
+'
+
+test_expect_success 'Command line overrides config' '
+    (
+        cd clone &&
+        stg branch stop-branch &&
+        sed -e "s/^5\$/cinq/" file >file.tmp && mv file.tmp file &&
+        stg new -m q1 && stg refresh &&
+        conflict stg pull --reapply-policy=stop &&
+        test "$(echo $(stg series --applied --noprefix))" = "q1" &&
+        test "$(stg status file)" = "UU file" &&
+        stg undo --hard
+    )
+'
+
+test_done
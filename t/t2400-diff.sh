@@ -86,6 +86,13 @@ test_expect_success 'Diff revs parent-child' '
     test_cmp foo-bar-stat.diff bar-stat.diff
 '
 
+test_expect_success 'Diff numstat' '
+    stg diff -r foo..baz --numstat >out &&
+    printf "2\t0\tbar.txt\n1\t0\tdir0/dir1/baz.txt\n" >expected &&
+    test_cmp expected out &&
+    general_error stg diff --stat --numstat
+'
+
 test_expect_success 'Diff invalid rev patch name' '
     command_error stg diff -r foo..bad-name 2>err &&
     grep -e "patch \`bad-name\` does not exist" err
@@ -185,4 +192,37 @@ test_expect_success 'Binary diff range with --binary' '
     test_cmp num-binary.diff num-binary2.diff
 '
 
+test_expect_success 'Word diff' '
+    echo "one two three" >words.txt &&
+    stg add words.txt &&
+    stg new -rm words &&
+    echo "one 2 three" >words.txt &&
+    stg diff --word-diff >out &&
+    grep -e "^one \[-two-\]{+2+} three\$" out &&
+    stg diff --word-diff=porcelain >out &&
+    grep -e "^-two\$" out &&
+    test_config diff.wordRegex "[a-z]" &&
+    stg diff --word-diff >out &&
+    ! grep -e "^one \[-two-\]{+2+} three\$" out &&
+    general_error stg diff --word-diff=bogus &&
+    git checkout words.txt
+'
+
+test_expect_success 'Color moved lines' '
+    test_seq 1 10 | sed -e "s/^/moved line number /" >moved.txt &&
+    stg add moved.txt &&
+    stg refresh &&
+    (test_seq 6 10 && test_seq 1 5) | sed -e "s/^/moved line number /" >moved.txt &&
+    stg diff --color=always >out &&
+    ! grep -e "$(printf "\033\\[1;35m")" out &&
+    stg diff --color=always --color-moved=plain >out &&
+    grep -e "$(printf "\033\\[1;35m")" out &&
+    test_config diff.colorMoved plain &&
+    stg diff --color=always >out &&
+    grep -e "$(printf "\033\\[1;35m")" out &&
+    stg diff --color=always --color-moved=no >out &&
+    ! grep -e "$(printf "\033\\[1;35m")" out &&
+    git checkout moved.txt
+'
+
 test_done
@@ -0,0 +1,83 @@
+#!/bin/sh
+
+test_description='Run "stg clean --orphans"'
+
+. ./test-lib.sh
+
+test_expect_success 'Orphans require initialized stack' '
+    command_error stg clean --orphans 2>err &&
+    grep -e "StGit stack not initialized" err
+'
+
+test_expect_success 'Dry run requires --orphans' '
+    general_error stg clean --dry-run
+'
+
+test_expect_success 'Create old stack log entries' '
+    test_tick &&
+    stg new p0 -m p0 &&
+    echo old >foo.txt &&
+    stg add foo.txt &&
+    stg refresh &&
+    test_tick &&
+    echo older >foo.txt &&
+    stg refresh &&
+    test_tick &&
+    stg new p1 -m p1 &&
+    echo bar >bar.txt &&
+    stg add bar.txt &&
+    stg refresh &&
+    echo baz >bar.txt &&
+    stg refresh &&
+    stg pop
+'
+
+test_expect_success 'Nothing expires when expiry is disabled' '
+    test_config stgit.log.expire 0 &&
+    stg clean --orphans 2>err &&
+    grep -e "stack log entries never expire" err
+'
+
+test_expect_success 'Create recent stack log entries' '
+    unset GIT_COMMITTER_DATE GIT_AUTHOR_DATE &&
+    echo new >foo.txt &&
+    stg refresh &&
+    stg new p2 -m p2 &&
+    test "$(stg log | wc -l)" -gt 3
+'
+
+test_expect_success 'List orphaned commits' '
+    stg log >log-before &&
+    stg clean --orphans --dry-run >orphans &&
+    test_line_count = 8 orphans &&
+    test "$(grep -c -e "^[0-9a-f]* p0$" orphans)" = "2" &&
+    test "$(grep -c -e "^[0-9a-f]* p1$" orphans)" = "2" &&
+    test "$(grep -c -e " Refresh of p0$" orphans)" = "2" &&
+    test "$(grep -c -e " Refresh of p1$" orphans)" = "2" &&
+    stg log >log-after &&
+    test_cmp log-before log-after
+'
+
+test_expect_success 'Drop expired stack log entries' '
+    orphan_id=$(head -n 1 orphans | cut -d" " -f1) &&
+    git cat-file -e $orphan_id &&
+    stg clean --orphans 2>err &&
+    grep -e "Dropped 12 stack log entries older than 90 days, orphaning 8 commits" err &&
+    stg log >log-after &&
+    head -n 3 log-before | sed -e "s/^[0-9a-f]* //" >log-expected &&
+    sed -e "s/^[0-9a-f]* //" log-after >log-actual &&
+    test_cmp log-expected log-actual &&
+    test "$(echo $(stg series --noprefix))" = "p0 p2 p1" &&
+    test "$(cat foo.txt)" = "new" &&
+    git -c gc.reflogExpire=now -c gc.reflogExpireUnreachable=now gc --prune=now -q &&
+    test_must_fail git cat-file -e $orphan_id &&
+    stg log >log-after-gc &&
+    test_cmp log-after log-after-gc
+'
+
+test_expect_success 'Nothing left to expire' '
+    stg clean --orphans 2>err &&
+    grep -e "no stack log entries older than 90 days" err
+'
+
+test_done
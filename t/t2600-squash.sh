@@ -82,6 +82,77 @@ test_expect_success 'Empty commit message aborts the squash' '
     test "$(echo $(stg series))" = "+ p0 > q1"
 '
 
+test_expect_success 'Setup patches with trailers' '
+    printf "t0\n\nSigned-off-by: A U Thor <author@example.com>\nLink: https://example.com/0\n" >msg0 &&
+    printf "t1\n\nbody 1\n\nSigned-off-by: A U Thor <author@example.com>\nLink: https://example.com/1\n" >msg1 &&
+    printf "t2\n\nAcked-by: Other <other@example.com>\n" >msg2 &&
+    stg new -f msg0 t0 &&
+    stg new -f msg1 t1 &&
+    stg new -f msg2 t2
+'
+
+test_expect_success 'Squash with --preserve-trailers' '
+    test_set_editor : &&
+    test_when_finished test_set_editor false &&
+    stg squash --preserve-trailers=union -n u0 t0 t1 t2 &&
+    cat >expected <<-\EOF &&
+	t0
+
+	t1
+
+	body 1
+
+	t2
+
+	Signed-off-by: A U Thor <author@example.com>
+	Link: https://example.com/0
+	Signed-off-by: A U Thor <author@example.com>
+	Link: https://example.com/1
+	Acked-by: Other <other@example.com>
+	EOF
+    git cat-file commit $(stg id u0) | sed -e "1,/^\$/d" >actual &&
+    test_cmp expected actual &&
+    stg undo &&
+    stg squash --preserve-trailers=dedup -n d0 t0 t1 t2 &&
+    cat >expected <<-\EOF &&
+	t0
+
+	t1
+
+	body 1
+
+	t2
+
+	Signed-off-by: A U Thor <author@example.com>
+	Link: https://example.com/0
+	Link: https://example.com/1
+	Acked-by: Other <other@example.com>
+	EOF
+    git cat-file commit $(stg id d0) | sed -e "1,/^\$/d" >actual &&
+    test_cmp expected actual &&
+    stg undo &&
+    stg squash --preserve-trailers=first-wins -n f0 t0 t1 t2 &&
+    cat >expected <<-\EOF &&
+	t0
+
+	t1
+
+	body 1
+
+	t2
+
+	Signed-off-by: A U Thor <author@example.com>
+	Link: https://example.com/0
+	Acked-by: Other <other@example.com>
+	EOF
+    git cat-file commit $(stg id f0) | sed -e "1,/^\$/d" >actual &&
+    test_cmp expected actual &&
+    stg undo &&
+    general_error stg squash --preserve-trailers=bogus t0 t1 &&
+    test "$(echo $(stg series --applied --noprefix | tail -n 3))" = "t0 t1 t2" &&
+    stg delete t0 t1 t2
+'
+
 test_expect_success 'Squash with top != head' '
     write_script fake-editor <<-\EOF &&
 	#!/bin/sh
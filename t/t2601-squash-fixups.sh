@@ -0,0 +1,80 @@
+#!/bin/sh
+
+test_description='Test "stg squash --fixups"'
+
+. ./test-lib.sh
+
+test_expect_success 'Initialize StGit stack' '
+    test_commit_bulk --start=0 --filename=file%s.txt --contents="base %s" --message="p%s" 3 &&
+    stg uncommit -n 3 &&
+    echo fix0 >>file0.txt &&
+    stg new -m "fixup! p0" &&
+    stg refresh &&
+    echo fix2 >>file2.txt &&
+    stg new -m "squash! p2
+
+Extra details for p2." &&
+    stg refresh &&
+    echo fix0-again >>file0.txt &&
+    stg new -m "fixup! fixup-p0" fix-p0-by-name &&
+    stg refresh &&
+    echo unrelated >file3.txt &&
+    stg add file3.txt &&
+    stg new -m "unrelated" &&
+    stg refresh
+'
+
+test_expect_success 'Arguments conflict with --fixups' '
+    general_error stg squash --fixups p0 p1 &&
+    general_error stg squash --fixups --name=foo
+'
+
+test_expect_success 'Squash fixups into targets' '
+    git log -1 --format=%an%ae%ad $(stg id p0) >expected-author &&
+    stg squash --fixups &&
+    test "$(echo $(stg series --applied --noprefix))" = "p0 p1 p2 unrelated" &&
+    test "$(echo $(stg series --unapplied --noprefix))" = "" &&
+    test "$(git log -1 --format=%B $(stg id p0))" = "p0" &&
+    git log -1 --format=%an%ae%ad $(stg id p0) >actual-author &&
+    test_cmp expected-author actual-author &&
+    git log -1 --format=%B $(stg id p2) >p2-message &&
+    cat >expected-p2-message <<-\EOF &&
+	p2
+
+	Extra details for p2.
+
+	EOF
+    test_cmp expected-p2-message p2-message &&
+    git show $(stg id p0):file0.txt >p0-file0 &&
+    printf "base 0\nfix0\nfix0-again\n" >expected-p0-file0 &&
+    test_cmp expected-p0-file0 p0-file0 &&
+    test "$(git show $(stg id p2):file2.txt | tail -1)" = "fix2"
+'
+
+test_expect_success 'No fixups to squash' '
+    stg squash --fixups 2>err &&
+    grep "no fixup or squash patches found" err &&
+    test "$(echo $(stg series --applied --noprefix))" = "p0 p1 p2 unrelated"
+'
+
+test_expect_success 'Squash unapplied fixup' '
+    echo fix1 >>file1.txt &&
+    stg new -m "fixup! p1" &&
+    stg refresh &&
+    stg pop fixup-p1 &&
+    stg squash --fixups &&
+    test "$(echo $(stg series --applied --noprefix))" = "p0 p1 p2 unrelated" &&
+    test "$(echo $(stg series --unapplied --noprefix))" = "" &&
+    test "$(git show $(stg id p1):file1.txt | tail -1)" = "fix1"
+'
+
+test_expect_success 'Fixup without target is left alone' '
+    echo orphan >>file3.txt &&
+    stg new -m "fixup! no such patch" &&
+    stg refresh &&
+    stg squash --fixups 2>err &&
+    grep "no fixup or squash patches found" err &&
+    test "$(echo $(stg series --applied --noprefix))" = "p0 p1 p2 unrelated fixup-no-such-patch"
+'
+
+test_done
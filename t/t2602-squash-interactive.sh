@@ -0,0 +1,97 @@
+#!/bin/sh
+
+test_description='Test "stg squash --interactive"'
+
+. ./test-lib.sh
+
+test_expect_success 'Initialize StGit stack' '
+    test_commit_bulk --start=0 --filename=file%s.txt --contents="base %s" --message="p%s" 5 &&
+    stg uncommit -n 5
+'
+
+test_expect_success 'Interactive conflicts with --fixups' '
+    general_error stg squash --interactive --fixups
+'
+
+test_expect_success 'Instructions list the patches to squash' '
+    write_script fake-editor <<-\EOF &&
+	case "$1" in
+	*squash-interactive*)
+	    grep -v "^#" "$1" >instructions
+	    printf "" >"$1"
+	    ;;
+	esac
+	EOF
+    test_set_editor "$(pwd)/fake-editor" &&
+    test_when_finished test_set_editor : &&
+    command_error stg squash --interactive p1 p0 2>err &&
+    grep -e "no patches listed; squash aborted" err &&
+    cat >expected <<-\EOF &&
+	squash p1 # p1
+	squash p0 # p0
+	EOF
+    test_cmp expected instructions &&
+    test "$(echo $(stg series --noprefix))" = "p0 p1 p2 p3 p4"
+'
+
+test_expect_success 'Reorder and fixup patches' '
+    write_script fake-editor <<-\EOF &&
+	case "$1" in
+	*squash-interactive*)
+	    printf "squash p1\nfixup p0 # discard p0 message\n" >"$1"
+	    ;;
+	esac
+	EOF
+    test_set_editor "$(pwd)/fake-editor" &&
+    test_when_finished test_set_editor : &&
+    stg squash --interactive --name=q0 p0 p1 &&
+    test "$(echo $(stg series --noprefix))" = "q0 p2 p3 p4" &&
+    test "$(git log -1 --format=%B $(stg id q0))" = "p1" &&
+    test "$(cat file0.txt)" = "base 0" &&
+    test "$(cat file1.txt)" = "base 1"
+'
+
+test_expect_success 'Removed line excludes patch from squash' '
+    write_script fake-editor <<-\EOF &&
+	case "$1" in
+	*squash-interactive*)
+	    sed -i -e "/^squash p4 /d" "$1"
+	    ;;
+	esac
+	EOF
+    test_set_editor "$(pwd)/fake-editor" &&
+    test_when_finished test_set_editor : &&
+    stg squash --interactive --name=q2 p2 p3 p4 &&
+    test "$(echo $(stg series --noprefix))" = "q0 q2 p4" &&
+    test "$(git log -1 --format=%B $(stg id q2))" = "$(printf "p2\n\np3")"
+'
+
+test_expect_success 'Bad instructions' '
+    write_script fake-editor <<-\EOF &&
+	case "$1" in
+	*squash-interactive*)
+	    cat instructions >"$1"
+	    ;;
+	esac
+	EOF
+    test_set_editor "$(pwd)/fake-editor" &&
+    test_when_finished test_set_editor : &&
+    printf "squash q0\nbogus q2\n" >instructions &&
+    command_error stg squash -i q0 q2 2>err &&
+    grep -e "unknown instruction action .bogus." err &&
+    printf "squash q0\nsquash p4\n" >instructions &&
+    command_error stg squash -i q0 q2 2>err &&
+    grep -e "patch .p4. is not being squashed" err &&
+    printf "squash q0\nsquash q0\n" >instructions &&
+    command_error stg squash -i q0 q2 2>err &&
+    grep -e "duplicated patch name .q0." err &&
+    printf "squash q0\n" >instructions &&
+    command_error stg squash -i q0 q2 2>err &&
+    grep -e "need at least two patches" err &&
+    printf "fixup q0\nfixup q2\n" >instructions &&
+    command_error stg squash -i q0 q2 2>err &&
+    grep -e "at least one patch.s message must be kept" err &&
+    test "$(echo $(stg series --noprefix))" = "q0 q2 p4"
+'
+
+test_done
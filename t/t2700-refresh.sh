@@ -186,6 +186,130 @@ test_expect_success 'Test annotate' '
     stg log -f | grep -e "My Annotation"
 '
 
+test_expect_success 'Annotate with placeholders' '
+    echo anno >anno.txt &&
+    stg add anno.txt &&
+    stg refresh --annotate "%(patchname)s on %(branch)s, stg %(version)s %(bogus)s" &&
+    stg log -f -n1 >log &&
+    grep -e "^ *$(stg top) on master, stg [0-9][^ ]* %(bogus)s\$" log
+'
+
+test_expect_success 'Annotate with configured default' '
+    test_config stgit.refresh.annotate "default for %(patchname)s" &&
+    echo more >>anno.txt &&
+    stg refresh &&
+    stg log -f -n1 >log &&
+    grep -e "default for $(stg top)" log &&
+    echo more >>anno.txt &&
+    stg refresh --annotate override &&
+    stg log -f -n1 >log &&
+    grep -e "override" log &&
+    ! grep -e "default for" log
+'
+
+test_expect_success 'Refresh with exclude pathspec magic' '
+    stg new -m p-exclude &&
+    echo "gen" >generated.txt &&
+    echo "src" >source.txt &&
+    stg add generated.txt source.txt &&
+    stg refresh ":!generated.txt" &&
+    stg files --bare >files &&
+    echo source.txt >expected &&
+    test_cmp expected files &&
+    git diff --cached --name-only >staged &&
+    echo generated.txt >expected &&
+    test_cmp expected staged
+'
+
+test_expect_success 'Refresh with --exclude' '
+    echo "more src" >>source.txt &&
+    echo "other" >other.txt &&
+    stg add other.txt &&
+    stg refresh --exclude generated.txt -x other.txt &&
+    stg files --bare >files &&
+    echo source.txt >expected &&
+    test_cmp expected files &&
+    git status --porcelain generated.txt other.txt source.txt >status &&
+    cat >expected <<-\EOF &&
+	A  generated.txt
+	A  other.txt
+	EOF
+    test_cmp expected status &&
+    stg refresh --exclude generated.txt other.txt &&
+    stg files --bare >files &&
+    printf "other.txt\nsource.txt\n" >expected &&
+    test_cmp expected files &&
+    general_error stg refresh --index --exclude generated.txt 2>err &&
+    grep -e "cannot be used with" err &&
+    git rm -f generated.txt
+'
+
+test_expect_success 'Refresh with verbose message editing' '
+    write_script verbose-editor <<-\EOF &&
+	cp "$1" buffer.txt &&
+	sed -i -e "s/^source\$/verbose message/" "$1"
+	EOF
+    test_set_editor "$(pwd)/verbose-editor" &&
+    test_when_finished test_set_editor : &&
+    echo "more" >>source.txt &&
+    stg refresh --edit --verbose &&
+    grep "^# ------------------------ >8 ------------------------\$" buffer.txt &&
+    grep "^+more\$" buffer.txt &&
+    git log -1 --format=%B >msg &&
+    ! grep "^+more\$" msg &&
+    ! grep ">8" msg
+'
+
+test_expect_success 'Refresh rename with pathspec matching either name' '
+    stg new -m p-rename &&
+    echo "rename me" >rename-src.txt &&
+    echo "other" >rename-other.txt &&
+    stg add rename-src.txt rename-other.txt &&
+    stg refresh &&
+    stg new -m p-rename-2 &&
+    stg mv rename-src.txt rename-dst.txt &&
+    stg refresh rename-src.txt &&
+    test "$(echo $(stg files --bare))" = "rename-dst.txt rename-src.txt" &&
+    test -z "$(git status --porcelain --untracked-files=no)" &&
+    stg mv rename-dst.txt rename-src.txt &&
+    stg refresh rename-src.txt &&
+    test -z "$(stg files --bare)" &&
+    stg mv rename-other.txt rename-other-dst.txt &&
+    stg refresh rename-other-dst.txt &&
+    test "$(echo $(stg files --bare))" = "rename-other-dst.txt rename-other.txt"
+'
+
+test_expect_success 'Refresh rename without rename detection' '
+    test_config stgit.refresh.renames false &&
+    stg new -m p-rename-3 &&
+    stg mv rename-src.txt rename-dst.txt &&
+    stg refresh rename-src.txt &&
+    test "$(echo $(stg files --bare))" = "rename-src.txt" &&
+    test "$(git status --porcelain --untracked-files=no)" = "A  rename-dst.txt" &&
+    stg refresh &&
+    stg delete p-rename p-rename-2 p-rename-3
+'
+
+test_expect_success 'Refresh non-ASCII path' '
+    fname=$(printf "caf\303\251.txt") &&
+    stg new -m p-unicode &&
+    echo "hello" >"$fname" &&
+    stg add "$fname" &&
+    stg refresh "$fname" &&
+    test "$(git -c core.quotepath=false diff-tree --name-only -r HEAD^ HEAD)" = "$fname" &&
+    stg delete p-unicode
+'
+
+test_expect_success SYMLINKS 'Refresh symlink' '
+    stg new -m p-symlink &&
+    ln -s rename-dst.txt link.txt &&
+    stg add link.txt &&
+    stg refresh &&
+    test "$(stg files --bare)" = "link.txt" &&
+    test "$(git cat-file -p HEAD:link.txt)" = "rename-dst.txt" &&
+    stg delete p-symlink
+'
+
 test_expect_success 'Attempt refresh with open conflict' '
     stg new -m p6 &&
     echo "foo" >conflicting.txt &&
@@ -40,4 +40,37 @@ test_expect_success 'Add new file to non-top patch' '
     test_cmp expected.txt files2.txt
 '
 
+test_expect_success 'Conflicting refresh of unapplied patch leaves temp patch' '
+    echo z >1.txt &&
+    stg new p3 -m "Patch 3" &&
+    stg refresh &&
+    stg pop &&
+    echo w >1.txt &&
+    stg refresh -p p3 >out.txt &&
+    grep "The new changes did not apply cleanly to p3" out.txt &&
+    stg series --unapplied --noprefix >series.txt &&
+    grep "^refresh-temp\$" series.txt &&
+    stg delete refresh-temp
+'
+
+test_expect_success 'Push unapplied patch on refresh conflict' '
+    echo w >1.txt &&
+    conflict stg refresh -p p3 --push-on-conflict 2>err.txt &&
+    grep "the new changes conflict with .p3." err.txt &&
+    test "$(stg top)" = "p3" &&
+    ! stg series --noprefix | grep "refresh-temp" &&
+    grep "^<<<<<<<" 1.txt &&
+    echo resolved >1.txt &&
+    git add 1.txt &&
+    stg refresh &&
+    stg show p3 >show.txt &&
+    grep "^+resolved\$" show.txt &&
+    test "$(echo $(stg series --noprefix))" = "p1 p2 p3"
+'
+
+test_expect_success 'Push on conflict requires patch option' '
+    general_error stg refresh --push-on-conflict 2>err.txt &&
+    grep "required arguments were not provided" err.txt
+'
+
 test_done
@@ -79,6 +79,13 @@ test_expect_success 'Spill with annotation' '
     stg log -f -n1 | grep -e "banana"
 '
 
+test_expect_success 'Spill with configured default annotation' '
+    stg refresh &&
+    test_config stgit.spill.annotate "spilled %(patchname)s on %(branch)s" &&
+    stg spill &&
+    stg log -f -n1 | grep -e "spilled $(stg top) on master"
+'
+
 test_expect_success 'Spill with --reset' '
     stg refresh &&
     stg spill --reset &&
@@ -181,6 +188,14 @@ test_expect_success 'Spill uptree pathspec' '
     stg undo --hard
 '
 
+test_expect_success 'Spill pathspec not matching patch' '
+    command_error stg spill dir0/dir1/no-such-file.txt 2>err &&
+    grep "no changes in .upper-vowels. match the given paths" err &&
+    rm err &&
+    stg files >files.txt &&
+    test_line_count = 3 files.txt
+'
+
 test_expect_success 'Spill with modified worktree' '
     echo "modification" >>dir0/a.txt &&
     stg spill dir0/dir1 &&
@@ -345,4 +360,36 @@ test_expect_success 'Spill removed file' '
     stg delete --top
 '
 
+test_expect_success 'Spill to new patch' '
+    echo "modification" >>dir0/a.txt &&
+    echo "modification" >>dir0/dir1/e.txt &&
+    stg add dir0 &&
+    stg new -rm "spill to patch" &&
+    stg spill --to-patch=spilled dir0/dir1 &&
+    test "$(stg top)" = "spill-to-patch" &&
+    test "$(stg series --unapplied --noprefix | head -n 1)" = "spilled" &&
+    test -z "$(git status --porcelain --untracked-files=no)" &&
+    ! grep "modification" dir0/dir1/e.txt &&
+    stg files spilled >files.txt &&
+    cat >expected-files.txt <<-\EOF &&
+	M dir0/dir1/e.txt
+	EOF
+    test_cmp expected-files.txt files.txt &&
+    test "$(git log -1 --format=%B $(stg id spilled))" = "spill to patch" &&
+    stg push spilled &&
+    grep "modification" dir0/dir1/e.txt &&
+    stg delete --top &&
+    stg delete --top
+'
+
+test_expect_success 'Spill to existing patch name' '
+    echo "modification" >>dir0/a.txt &&
+    stg add dir0 &&
+    stg new -rm "spill to patch" &&
+    command_error stg spill --to-patch=spill-to-patch 2>err &&
+    grep "patch .spill-to-patch. already exists" err &&
+    general_error stg spill --to-patch=other --reset &&
+    stg delete --top
+'
+
 test_done
@@ -0,0 +1,118 @@
+#!/bin/sh
+
+test_description='Test stg absorb'
+
+. ./test-lib.sh
+
+test_expect_success 'Absorb without applied patches' '
+    test_seq 1 30 >a.txt &&
+    git add a.txt &&
+    git commit -m "initial" &&
+    stg init &&
+    command_error stg absorb 2>err &&
+    grep "no patches applied" err
+'
+
+test_expect_success 'Initialize patches' '
+    sed -e "s/^5\$/five/" a.txt >a.tmp && mv a.tmp a.txt &&
+    stg new -m p1 && stg refresh &&
+    sed -e "s/^15\$/fifteen/" a.txt >a.tmp && mv a.tmp a.txt &&
+    echo b >b.txt && stg add b.txt &&
+    stg new -m p2 && stg refresh --force &&
+    sed -e "s/^25\$/twenty-five/" a.txt >a.tmp && mv a.tmp a.txt &&
+    stg new -m p3 && stg refresh
+'
+
+test_expect_success 'Dry run shows hunk assignments' '
+    sed -e "s/^five\$/FIVE/" -e "s/^twenty-five\$/TWENTY-FIVE/" \
+        -e "s/^10\$/TEN/" a.txt >a.tmp && mv a.tmp a.txt &&
+    stg absorb --dry-run >out &&
+    cat >expected <<-\EOF &&
+	a.txt:5: p1
+	a.txt:10: (not absorbed)
+	a.txt:25: p3
+	EOF
+    test_cmp expected out &&
+    git diff --name-only >diff &&
+    echo a.txt >expected &&
+    test_cmp expected diff
+'
+
+test_expect_success 'Absorb hunks into their patches' '
+    stg absorb 2>err &&
+    grep "absorbed 2 hunks into 2 patches; 1 hunk left in the work tree" err &&
+    stg show p1 >out &&
+    grep "^+FIVE\$" out &&
+    ! grep "^+five\$" out &&
+    stg show p3 >out &&
+    grep "^+TWENTY-FIVE\$" out &&
+    stg show p2 >out &&
+    grep "^+fifteen\$" out &&
+    git diff >diff &&
+    grep "^+TEN\$" diff &&
+    ! grep "^[-+].*FIVE" diff &&
+    git diff --cached --quiet &&
+    test "$(stg series --noprefix)" = "$(printf "p1\np2\np3")"
+'
+
+test_expect_success 'Absorb added lines next to patch lines' '
+    git checkout a.txt &&
+    sed -e "/^fifteen\$/a sixteen-ish" a.txt >a.tmp && mv a.tmp a.txt &&
+    echo bb >>b.txt &&
+    stg absorb &&
+    stg show p2 >out &&
+    grep "^+sixteen-ish\$" out &&
+    grep "^+bb\$" out &&
+    git diff --quiet
+'
+
+test_expect_success 'Absorb is undoable' '
+    stg undo &&
+    stg show p2 >out &&
+    ! grep "sixteen-ish" out &&
+    stg redo &&
+    stg show p2 >out &&
+    grep "^+sixteen-ish\$" out
+'
+
+test_expect_success 'Absorb with nothing to absorb' '
+    sed -e "s/^20\$/TWENTY/" a.txt >a.tmp && mv a.tmp a.txt &&
+    command_error stg absorb 2>err &&
+    grep "no hunks could be absorbed" err &&
+    git checkout a.txt
+'
+
+test_expect_success 'Absorb limited to pathspec' '
+    sed -e "s/^FIVE\$/Five/" a.txt >a.tmp && mv a.tmp a.txt &&
+    echo bbb >>b.txt &&
+    stg absorb b.txt &&
+    stg show p2 >out &&
+    grep "^+bbb\$" out &&
+    git diff --name-only >diff &&
+    echo a.txt >expected &&
+    test_cmp expected diff &&
+    git checkout a.txt
+'
+
+test_expect_success 'Absorb with dirty index' '
+    echo bbbb >>b.txt &&
+    git add b.txt &&
+    command_error stg absorb 2>err &&
+    grep "index not clean" err &&
+    git reset b.txt &&
+    git checkout b.txt
+'
+
+test_expect_success 'Absorb keeps committer date with stgit.keepcommitterdate' '
+    test_config stgit.keepcommitterdate true &&
+    git log -1 --format=%ct $(stg id p1) >expected &&
+    test_tick &&
+    sed -e "s/^FIVE\$/Five/" a.txt >a.tmp && mv a.tmp a.txt &&
+    stg absorb &&
+    stg show p1 >out &&
+    grep "^+Five\$" out &&
+    git log -1 --format=%ct $(stg id p1) >actual &&
+    test_cmp expected actual
+'
+
+test_done
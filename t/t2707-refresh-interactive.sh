@@ -0,0 +1,64 @@
+#!/bin/sh
+
+test_description='Test stg refresh --interactive'
+
+. ./test-lib.sh
+
+test_expect_success 'Initialize repo and patch' '
+    test_seq 1 20 >a.txt &&
+    test_seq 1 20 >b.txt &&
+    git add a.txt b.txt &&
+    git commit -m "initial" &&
+    stg init &&
+    stg new -m p1
+'
+
+test_expect_success 'Refresh selected hunk' '
+    sed -e "s/^1\$/one/" -e "s/^20\$/twenty/" a.txt >a.tmp &&
+    mv a.tmp a.txt &&
+    printf "y\nn\n" | stg refresh --interactive &&
+    stg show p1 >out &&
+    grep "^+one\$" out &&
+    ! grep "^+twenty\$" out &&
+    git diff >diff &&
+    grep "^+twenty\$" diff &&
+    ! grep "^+one\$" diff &&
+    git diff --cached --quiet
+'
+
+test_expect_success 'Refresh remaining hunk' '
+    printf "y\n" | stg refresh --interactive &&
+    stg show p1 >out &&
+    grep "^+one\$" out &&
+    grep "^+twenty\$" out &&
+    git diff --quiet
+'
+
+test_expect_success 'Refresh interactively with pathspec' '
+    sed -e "s/^5\$/five/" a.txt >a.tmp &&
+    mv a.tmp a.txt &&
+    sed -e "s/^5\$/five/" b.txt >b.tmp &&
+    mv b.tmp b.txt &&
+    printf "y\n" | stg refresh --interactive b.txt &&
+    stg show p1 >out &&
+    grep "^+++ b/b.txt" out &&
+    git diff --name-only >diff &&
+    echo a.txt >expected &&
+    test_cmp expected diff
+'
+
+test_expect_success 'Refresh with no hunks selected' '
+    stg show p1 >before &&
+    printf "n\n" | stg refresh --interactive &&
+    git diff --name-only >diff &&
+    test_cmp expected diff &&
+    stg show p1 >after &&
+    test_cmp before after
+'
+
+test_expect_success 'Interactive refresh from index not allowed' '
+    general_error stg refresh --interactive --index 2>err &&
+    grep "cannot be used with" err
+'
+
+test_done
@@ -0,0 +1,85 @@
+#!/bin/sh
+
+test_description='Test stg refresh --route'
+
+. ./test-lib.sh
+
+test_expect_success 'Initialize repo and patches' '
+    mkdir docs src &&
+    echo doc >docs/a.txt &&
+    echo src >src/a.c &&
+    echo other >other.txt &&
+    git add docs src other.txt &&
+    git commit -m "initial" &&
+    stg init &&
+    echo doc2 >>docs/a.txt && stg new -m doc-patch && stg refresh &&
+    echo src2 >>src/a.c && stg new -m src-patch && stg refresh &&
+    stg new -m top-patch
+'
+
+test_expect_success 'Route without routes configured' '
+    echo doc3 >>docs/a.txt &&
+    command_error stg refresh --route 2>err &&
+    grep "no routes configured" err
+'
+
+test_expect_success 'Route to nonexistent patch' '
+    git config stgit.route.docs no-such-patch &&
+    command_error stg refresh --route 2>err &&
+    grep "patch .no-such-patch. routed by .stgit.route.docs. does not exist" err
+'
+
+test_expect_success 'Refresh files into routed patches' '
+    git config stgit.route.docs doc-patch &&
+    git config stgit.route.src src-patch &&
+    echo src3 >>src/a.c &&
+    echo other2 >>other.txt &&
+    stg refresh --route 2>err &&
+    grep "refreshed 2 files into 2 patches; 1 file left in the work tree" err &&
+    stg show doc-patch >out &&
+    grep "^+doc3\$" out &&
+    stg show src-patch >out &&
+    grep "^+src3\$" out &&
+    stg show top-patch >out &&
+    ! grep "^+" out &&
+    git diff --name-only >diff &&
+    echo other.txt >expected &&
+    test_cmp expected diff &&
+    git diff --cached --quiet &&
+    test "$(echo $(stg series --applied --noprefix))" = "doc-patch src-patch top-patch"
+'
+
+test_expect_success 'Routed refresh is undoable' '
+    stg undo --hard &&
+    stg show doc-patch >out &&
+    ! grep "^+doc3\$" out &&
+    stg redo --hard &&
+    stg show doc-patch >out &&
+    grep "^+doc3\$" out
+'
+
+test_expect_success 'Route with no matching files' '
+    git checkout other.txt &&
+    echo other3 >>other.txt &&
+    command_error stg refresh --route 2>err &&
+    grep "no modified files match the configured routes" err &&
+    git checkout other.txt
+'
+
+test_expect_success 'Route with pathspec limit' '
+    echo doc4 >>docs/a.txt &&
+    echo src4 >>src/a.c &&
+    stg refresh --route src &&
+    stg show src-patch >out &&
+    grep "^+src4\$" out &&
+    git diff --name-only >diff &&
+    echo docs/a.txt >expected &&
+    test_cmp expected diff
+'
+
+test_expect_success 'Route conflicts with --patch' '
+    general_error stg refresh --route -p doc-patch 2>err &&
+    grep "cannot be used with" err
+'
+
+test_done
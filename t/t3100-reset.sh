@@ -155,4 +155,59 @@ test_expect_success '... and undo the refresh' '
     test_cmp expected.txt a
 '
 
+test_expect_success 'Interactive reset lists states with their series' '
+    write_script fake-editor <<-\EOF &&
+	cp "$1" template.txt &&
+	sed -i -e "/^[0-9a-f]*  .*  refresh p2\$/!d" "$1"
+	EOF
+    test_set_editor "$(pwd)/fake-editor" &&
+    test_when_finished test_set_editor false &&
+    stg reset --interactive &&
+    head -n 3 template.txt >head &&
+    sed -i -e "s/^[0-9a-f]*  .*  /<id>  /" head &&
+    cat >expected <<-\EOF &&
+	<id>  refresh p2
+	#   + p1
+	#   > p2
+	EOF
+    test_cmp expected head &&
+    test "$(echo $(stg series --all))" = "+ p1 > p2" &&
+    cat >expected.txt <<-\EOF &&
+	000
+	111
+	222
+	ggg
+	EOF
+    test_cmp expected.txt a
+'
+
+test_expect_success 'Interactive reset filtered by patch' '
+    write_script fake-editor <<-\EOF &&
+	sed -i -e "/^[0-9a-f]*  .*  uncommit\$/!d" "$1"
+	EOF
+    test_set_editor "$(pwd)/fake-editor" &&
+    test_when_finished test_set_editor false &&
+    stg reset --hard --interactive --patch p1 &&
+    test "$(echo $(stg series --all))" = "+ p1 + p2 > p3" &&
+    general_error stg reset --patch p1 &&
+    command_error stg reset --interactive --patch p9 2>err &&
+    grep "patch \`p9\` not found in the stack log" err
+'
+
+test_expect_success 'Interactive reset with no or several states selected' '
+    write_script fake-editor <<-\EOF &&
+	sed -i -e "/^[0-9a-f]/d" "$1"
+	EOF
+    test_set_editor "$(pwd)/fake-editor" &&
+    test_when_finished test_set_editor false &&
+    command_error stg reset --interactive 2>err &&
+    grep "reset aborted" err &&
+    write_script fake-editor <<-\EOF &&
+	true
+	EOF
+    command_error stg reset --interactive 2>err &&
+    grep "more than one stack state selected" err &&
+    test "$(echo $(stg series --all))" = "+ p1 + p2 > p3"
+'
+
 test_done
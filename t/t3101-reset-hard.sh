@@ -52,4 +52,47 @@ test_expect_success 'Try to reset with --hard' '
     test "$(echo $(stg series))" = "+ p1 + p2 > p3"
 '
 
+test_expect_success 'Stash discarded changes requires --hard' '
+    general_error stg reset --stash-discarded 2>err &&
+    grep -e "--hard" err
+'
+
+test_expect_success 'Reset with --hard and --stash-discarded' '
+    echo 444 >>a &&
+    echo bbb >b &&
+    stg add b &&
+    stg reset --hard --stash-discarded 2>err &&
+    grep -e "Discarded changes saved in .stash@{0}." err &&
+    rm err &&
+    stg status >actual.txt &&
+    test_must_be_empty actual.txt &&
+    git stash list >actual.txt &&
+    test_line_count = 1 actual.txt &&
+    grep -e "stg reset --hard: discarded changes" actual.txt &&
+    git stash pop --index &&
+    stg status >actual.txt &&
+    cat >expected.txt <<-\EOF &&
+	 M a
+	A  b
+	EOF
+    test_cmp expected.txt actual.txt &&
+    stg reset --hard
+'
+
+test_expect_success 'Stash nothing when there are no changes' '
+    stg reset --hard --stash-discarded 2>err &&
+    test_must_be_empty err &&
+    rm err &&
+    test_must_fail git rev-parse --verify -q refs/stash
+'
+
+test_expect_success 'Reset to state with --stash-discarded' '
+    echo 555 >>a &&
+    stg reset --hard --stash-discarded refs/stacks/master^ &&
+    test "$(echo $(stg series))" = "+ p1 + p2 > p3" &&
+    test "$(git stash list | wc -l)" = 1 &&
+    git diff stash@{0}^ stash@{0} -- a >actual.txt &&
+    grep -e "^+555" actual.txt
+'
+
 test_done
@@ -76,4 +76,24 @@ test_expect_success 'Undo past end of history' '
     test_cmp expected.txt a
 '
 
+test_expect_success 'List undo states' '
+    stg undo --list >list &&
+    sed -e "s/  .*  /  /" list >actual &&
+    cat >expected <<-\EOF &&
+	1  uncommit
+	2  initialize
+	EOF
+    test_cmp expected actual &&
+    grep -E "^[0-9]+  [0-9]{4}-[0-9]{2}-[0-9]{2} " list
+'
+
+test_expect_success 'Undo to listed state' '
+    number=$(sed -n -e "s/^\([0-9]*\)  .*  uncommit\$/\1/p" list) &&
+    stg undo --to $number &&
+    test "$(echo $(stg series))" = "+ p1 + p2 > p3" &&
+    general_error stg undo --to 0 &&
+    command_error stg undo --to 100 &&
+    general_error stg undo --to 1 -n 1
+'
+
 test_done
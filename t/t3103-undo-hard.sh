@@ -50,4 +50,15 @@ test_expect_success 'Try to undo with --hard' '
     test "$(stg id)" = "$(stg id $(stg top))"
 '
 
+test_expect_success 'Undo with --hard and --stash-discarded' '
+    stg pop &&
+    echo 444 >>a &&
+    stg undo --hard --stash-discarded &&
+    test "$(echo $(stg series))" = "+ p1 + p2 > p3" &&
+    stg status a >actual.txt &&
+    test_must_be_empty actual.txt &&
+    git stash list >actual.txt &&
+    grep -e "stg undo --hard: discarded changes" actual.txt
+'
+
 test_done
@@ -114,4 +114,19 @@ test_expect_success 'Redo past end of history' '
     test_cmp expected.txt a
 '
 
+test_expect_success 'List redo states' '
+    stg undo -n 2 &&
+    stg undo &&
+    stg redo --list >list &&
+    sed -e "s/  .*  /  /" list >actual &&
+    cat >expected <<-\EOF &&
+	1  undo 2
+	2  redo 1
+	EOF
+    test_cmp expected actual &&
+    stg redo -n 2 &&
+    stg redo --list >list &&
+    test_line_count = 0 list
+'
+
 test_done
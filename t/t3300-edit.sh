@@ -350,4 +350,45 @@ test_expect_success 'Set patch tree' '
     test "$(git notes show $(stg id p4))" = "note4"
 '
 
+test_expect_success 'Preserve unknown headers from description file' '
+    cat >desc <<-\EOF &&
+	Patch:  p2
+	Author: A Ú Thor <author@example.com>
+	Date:   2005-04-07 15:13:13 -0700
+	Message-Id: <1234@example.com>
+	X-Custom: some value
+
+	p2 subject
+	EOF
+    stg edit -f desc p2 &&
+    git log -1 --format=%B $(stg id p2) >actual &&
+    cat >expected <<-\EOF &&
+	p2 subject
+
+	Message-Id: <1234@example.com>
+	X-Custom: some value
+
+	EOF
+    test_cmp expected actual
+'
+
+test_expect_success 'Preserve unknown headers through interactive edit' '
+    write_script fake-editor <<-\EOF &&
+	sed -i -e "/^Date:/a Change-Id: I1234" -e "/^Date:/a Message-Id: <1234@example.com>" "$1"
+	EOF
+    test_set_editor "$(pwd)/fake-editor" &&
+    test_when_finished test_set_editor : &&
+    stg edit -e p2 &&
+    git log -1 --format=%B $(stg id p2) >actual &&
+    cat >expected <<-\EOF &&
+	p2 subject
+
+	Message-Id: <1234@example.com>
+	X-Custom: some value
+	Change-Id: I1234
+
+	EOF
+    test_cmp expected actual
+'
+
 test_done
@@ -20,9 +20,9 @@ test_expect_success 'Attempt to edit non-existant patch name' '
     grep "patch \`not-a-patch\` does not exist" err
 '
 
-test_expect_success 'Attempt to edit multiple patches' '
-    general_error stg edit p1 p2 2>err &&
-    grep "unexpected argument .p2." err
+test_expect_success 'Attempt to edit multiple patches with single patch options' '
+    command_error stg edit -m foo p1 p2 2>err &&
+    grep "\`--message\` cannot be used when editing multiple patches" err
 '
 
 test_done
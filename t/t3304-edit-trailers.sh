@@ -49,4 +49,52 @@ test_expect_success 'Sign patch and tack on a custom ack and review' '
     test "$(msg refs/patches/master/p5)" = "$m//Signed-off-by: Someone <someone@example.com>/Acked-by: ACKKER/Reviewed-by: best friend"
 '
 
+test_expect_success 'Add generic trailers' '
+    m=$(msg refs/patches/master/p6) &&
+    stg edit --trailer "Fixes: abc123" --trailer Link=https://example.com/1 p6 &&
+    test "$(msg refs/patches/master/p6)" = "$m/Fixes: abc123/Link: https://example.com/1"
+'
+
+test_expect_success 'Generic trailers honor trailer config' '
+    m=$(msg refs/patches/master/p6) &&
+    test_config trailer.ifExists addIfDifferent &&
+    stg edit --trailer "Fixes: abc123" --trailer "Fixes: def456" p6 &&
+    test "$(msg refs/patches/master/p6)" = "$m/Fixes: def456"
+'
+
+test_expect_success 'Generic trailers mixed with sign-off keep order' '
+    stg new -m "p7" &&
+    stg refresh --trailer "Cc: Someone <someone@example.com>" \
+        --sign-by "Sign Er <signer@example.com>" &&
+    test "$(msg HEAD)" = "p7//Cc: Someone <someone@example.com>/Signed-off-by: Sign Er <signer@example.com>"
+'
+
+test_expect_success 'New patch with generic trailer' '
+    stg new -m "p8" --trailer "Link: https://example.com/8" &&
+    test "$(msg HEAD)" = "p8//Link: https://example.com/8"
+'
+
+test_expect_success 'Invalid generic trailer' '
+    general_error stg edit --trailer "no trailer here" p6 2>err &&
+    grep -e "invalid trailer" err &&
+    general_error stg edit --trailer "Key:" p6 2>err &&
+    grep -e "invalid trailer" err
+'
+
+test_expect_success 'Acknowledge a patch with explicit identity' '
+    m=$(msg refs/patches/master/p3) &&
+    stg edit --ack="Jane Doe <jane@example.com>" p3 &&
+    test "$(msg refs/patches/master/p3)" = "$m/Acked-by: Jane Doe <jane@example.com>"
+'
+
+test_expect_success 'Acknowledge a range of patches' '
+    m4=$(msg refs/patches/master/p4) &&
+    m5=$(msg refs/patches/master/p5) &&
+    stg edit --ack="Jane Doe <jane@example.com>" --review p4..p5 &&
+    test "$(msg refs/patches/master/p4)" = "$m4/Acked-by: Jane Doe <jane@example.com>/Reviewed-by: C Ó Mitter <committer@example.com>" &&
+    test "$(msg refs/patches/master/p5)" = "$m5/Acked-by: Jane Doe <jane@example.com>/Reviewed-by: C Ó Mitter <committer@example.com>" &&
+    command_error stg edit --ack --authname=Someone p4 p5 2>err &&
+    grep -e "cannot be used when editing multiple patches" err
+'
+
 test_done
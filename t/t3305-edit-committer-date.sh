@@ -97,4 +97,31 @@ test_expect_success 'Sink without --committer-date-is-author-date' '
     test "$test_tick" -eq "$(committer_time foo)"
 '
 
+test_expect_success 'Keep committer time for edit with stgit.keepcommitterdate' '
+    test_config stgit.keepcommitterdate true &&
+    committer_time >ctime &&
+    test_tick &&
+    stg edit -m "foo message 4" &&
+    test "$(cat ctime)" -eq "$(committer_time)"
+'
+
+test_expect_success 'Keep committer time for merge push with stgit.keepcommitterdate' '
+    test_config stgit.keepcommitterdate true &&
+    committer_time foo >ctime &&
+    stg pop foo &&
+    stg goto bar &&
+    echo bar >bar.txt &&
+    stg refresh &&
+    test_tick &&
+    stg push foo &&
+    test "$(cat ctime)" -eq "$(committer_time foo)"
+'
+
+test_expect_success 'Command line option overrides stgit.keepcommitterdate' '
+    test_config stgit.keepcommitterdate true &&
+    test_tick &&
+    stg float --committer-date-is-author-date bar &&
+    test "$(author_time bar)" -eq "$(committer_time bar)"
+'
+
 test_done
@@ -0,0 +1,73 @@
+#!/bin/sh
+
+test_description='Test "stg edit --exec"'
+
+. ./test-lib.sh
+
+test_expect_success 'Initialize stack' '
+    test_commit_bulk --message="p%s" 4 &&
+    stg uncommit -n 4 &&
+    stg pop p4 &&
+    printf "p1\n\nSee https://old.example.com/1\n" >msg &&
+    stg edit p1 -f msg &&
+    printf "p4\n\nSee https://old.example.com/4\n" >msg &&
+    stg edit p4 -f msg
+'
+
+test_expect_success 'Edit options not allowed with --exec' '
+    general_error stg edit --exec cat -m foo &&
+    general_error stg edit --exec cat --all p1
+'
+
+test_expect_success 'Rewrite top patch message' '
+    stg edit --exec "sed -e s/^p3/P3/" &&
+    test "$(stg id p3)" = "$(git rev-parse HEAD)" &&
+    test "$(git log -1 --format=%s)" = "P3" &&
+    test "$(echo $(stg series --noprefix --applied))" = "p1 p2 p3"
+'
+
+test_expect_success 'Rewrite messages of a range' '
+    tree=$(git rev-parse HEAD^{tree}) &&
+    stg edit --exec "sed -e s/old.example.com/new.example.com/" p1..p4 &&
+    git log -1 --format=%b $(stg id p1) >out &&
+    grep "https://new.example.com/1" out &&
+    git log -1 --format=%b $(stg id p4) >out &&
+    grep "https://new.example.com/4" out &&
+    test "$(git rev-parse HEAD^{tree})" = "$tree" &&
+    test "$(stg id p2^)" = "$(stg id p1)" &&
+    test "$(stg id p3)" = "$(git rev-parse HEAD)" &&
+    test "$(git log -1 --format=%s $(stg id p3))" = "P3" &&
+    test "$(echo $(stg series --noprefix --unapplied))" = "p4"
+'
+
+test_expect_success 'Patch name is available to message filter' '
+    stg edit --exec "sed -e \"1s/.*/\$STGIT_PATCH: &/\"" p1 p2 &&
+    test "$(git log -1 --format=%s $(stg id p1))" = "p1: p1" &&
+    test "$(git log -1 --format=%s $(stg id p2))" = "p2: p2"
+'
+
+test_expect_success 'Failing message filter leaves patches unchanged' '
+    before=$(stg id p1) &&
+    command_error stg edit --exec false p1 2>err &&
+    grep "message filter \`false\` failed for patch \`p1\`" err &&
+    command_error stg edit --exec "cat >/dev/null" p1 2>err &&
+    grep "empty message" err &&
+    test "$(stg id p1)" = "$before"
+'
+
+test_expect_success 'Undo message rewrite' '
+    stg undo &&
+    test "$(git log -1 --format=%s $(stg id p1))" = "p1"
+'
+
+test_expect_success 'Inject ticket number into all patch messages' '
+    stg edit --exec "sed -e \"1s/^/[TICKET-42] /\"" --all &&
+    for p in p1 p2 p3 p4
+    do
+        git log -1 --format=%s $(stg id $p) >subject &&
+        grep -e "^\[TICKET-42\] " subject || return 1
+    done &&
+    test "$(echo $(stg series --noprefix --unapplied))" = "p4"
+'
+
+test_done
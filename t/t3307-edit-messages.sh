@@ -0,0 +1,112 @@
+#!/bin/sh
+
+test_description='Test editing multiple patch messages with "stg edit"'
+
+. ./test-lib.sh
+
+test_expect_success 'Initialize stack' '
+    test_commit_bulk --message="p%s" 4 &&
+    stg uncommit -n 4 &&
+    for p in p1 p2 p3 p4
+    do
+        printf "$p\n\nAcked-by: Some Oen <some@example.com>\n" >msg &&
+        stg edit -f msg $p || return 1
+    done &&
+    stg pop p4
+'
+
+test_expect_success 'Template lists messages of selected patches' '
+    write_script fake-editor <<-\EOF &&
+	cp "$1" template.txt
+	EOF
+    test_set_editor "$(pwd)/fake-editor" &&
+    test_when_finished test_set_editor : &&
+    before=$(stg id p1) &&
+    stg edit p1..p2 &&
+    grep -v "^#" template.txt >actual &&
+    cat >expected <<-\EOF &&
+	=== patch: p1 ===
+	p1
+
+	Acked-by: Some Oen <some@example.com>
+
+	=== patch: p2 ===
+	p2
+
+	Acked-by: Some Oen <some@example.com>
+
+	EOF
+    test_cmp expected actual &&
+    test "$(stg id p1)" = "$before"
+'
+
+test_expect_success 'Fix trailer in all patches' '
+    write_script fake-editor <<-\EOF &&
+	sed -i -e "s/Some Oen/Some One/" "$1"
+	EOF
+    test_set_editor "$(pwd)/fake-editor" &&
+    test_when_finished test_set_editor : &&
+    tree=$(git rev-parse HEAD^{tree}) &&
+    stg edit --all &&
+    for p in p1 p2 p3 p4
+    do
+        git log -1 --format=%b $(stg id $p) >out &&
+        grep "^Acked-by: Some One <some@example.com>\$" out || return 1
+    done &&
+    test "$(git log -1 --format=%s $(stg id p3))" = "p3" &&
+    test "$(stg id p3)" = "$(git rev-parse HEAD)" &&
+    test "$(git rev-parse HEAD^{tree})" = "$tree" &&
+    test "$(echo $(stg series --noprefix --unapplied))" = "p4"
+'
+
+test_expect_success 'Only changed messages are rewritten' '
+    write_script fake-editor <<-\EOF &&
+	sed -i -e "s/^p3\$/Patch three/" "$1"
+	EOF
+    test_set_editor "$(pwd)/fake-editor" &&
+    test_when_finished test_set_editor : &&
+    p1=$(stg id p1) &&
+    p2=$(stg id p2) &&
+    stg edit p1 p2 p3 &&
+    test "$(stg id p1)" = "$p1" &&
+    test "$(stg id p2)" = "$p2" &&
+    test "$(git log -1 --format=%s $(stg id p3))" = "Patch three" &&
+    stg undo &&
+    test "$(git log -1 --format=%s $(stg id p3))" = "p3"
+'
+
+test_expect_success 'Invalid edits leave patches unchanged' '
+    before=$(stg id p2) &&
+    write_script fake-editor <<-\EOF &&
+	sed -i -e "/^=== patch: p2 ===\$/d" "$1"
+	EOF
+    test_set_editor "$(pwd)/fake-editor" &&
+    test_when_finished test_set_editor : &&
+    command_error stg edit p2..p3 2>err &&
+    grep "text found before the first patch delimiter line" err &&
+    write_script fake-editor <<-\EOF &&
+	sed -i -e "s/^=== patch: p3 ===\$/=== patch: p9 ===/" "$1"
+	EOF
+    command_error stg edit p2..p3 2>err &&
+    grep "unexpected patch \`p9\`" err &&
+    write_script fake-editor <<-\EOF &&
+	sed -i -e "/^=== patch: p3 ===\$/,\$d" "$1"
+	EOF
+    command_error stg edit p2..p3 2>err &&
+    grep "message for patch \`p3\` is missing" err &&
+    write_script fake-editor <<-\EOF &&
+	sed -i -e "/^p3\$/d" -e "/Acked-by/d" "$1"
+	EOF
+    command_error stg edit p2..p3 2>err &&
+    grep "empty message for patch \`p3\`" err &&
+    test "$(stg id p2)" = "$before"
+'
+
+test_expect_success 'Single patch options are rejected' '
+    command_error stg edit --all --authname=Someone 2>err &&
+    grep "\`--authname\` cannot be used when editing multiple patches" err &&
+    command_error stg edit --all --sign --authname=Someone 2>err &&
+    grep "\`--authname\` cannot be used when editing multiple patches" err
+'
+
+test_done
@@ -0,0 +1,74 @@
+#!/bin/sh
+
+test_description='Test edit session information for editor integrations'
+
+. ./test-lib.sh
+
+test_expect_success 'Initialize repo' '
+    test_commit base &&
+    stg init &&
+    echo foo >foo.txt &&
+    echo bar >bar.txt &&
+    stg add foo.txt bar.txt &&
+    stg new -m p1 &&
+    stg refresh
+'
+
+test_expect_success 'Edit session information is provided to editor' '
+    write_script info-editor <<-\EOF &&
+	test -f .stgit-edit.json &&
+	cp "$STGIT_EDIT_INFO" info.json
+	EOF
+    test_set_editor "$(pwd)/info-editor" &&
+    test_when_finished test_set_editor : &&
+    test_config trailer.fix.key "Fixes: " &&
+    stg edit --edit p1 &&
+    test_path_is_missing .stgit-edit.json &&
+    cat >expected <<-EOF &&
+	{
+	  "version": 1,
+	  "file": ".stgit-edit.txt",
+	  "branch": "master",
+	  "patch": "p1",
+	  "base": "$(git rev-parse base)",
+	  "paths": [
+	    "bar.txt",
+	    "foo.txt"
+	  ],
+	  "trailers": [
+	    "Signed-off-by",
+	    "Acked-by",
+	    "Reviewed-by",
+	    "Fixes"
+	  ],
+	  "diff": null,
+	  "markers": {
+	    "comment": "#",
+	    "headers": [
+	      "Patch",
+	      "Author",
+	      "Date"
+	    ],
+	    "cut_line": "# ------------------------ >8 ------------------------"
+	  }
+	}
+	EOF
+    test_cmp expected info.json
+'
+
+test_expect_success 'Edit session information with diff' '
+    test_set_editor "$(pwd)/info-editor" &&
+    test_when_finished test_set_editor : &&
+    stg edit --diff p1 &&
+    grep "\"file\": \".stgit-edit.patch\"" info.json &&
+    grep "\"diff\": \"editable\"" info.json &&
+    rm info.json &&
+    echo baz >baz.txt &&
+    stg add baz.txt &&
+    stg new --edit --diff p2 &&
+    grep "\"diff\": \"read-only\"" info.json &&
+    grep "\"paths\": \[\]" info.json &&
+    grep "\"patch\": \"p2\"" info.json
+'
+
+test_done
@@ -215,6 +215,44 @@ test_expect_success 'Pick --update with conflict' '
     stg reset --hard
 '
 
+test_expect_success 'Setup other repository' '
+    git init other &&
+    (
+        cd other &&
+        test_commit_bulk --filename=other%s.txt --contents="other %s" --message="other-%s" 5 &&
+        git tag other-tag HEAD~2
+    )
+'
+
+test_expect_success 'Pick --repo conflicts with --ref-branch' '
+    general_error stg pick --repo other -B foo master 2>err &&
+    grep "cannot be used with" err
+'
+
+test_expect_success 'Pick single commit --repo' '
+    stg pick --repo other "$(git -C other rev-parse HEAD~3)" &&
+    test "$(stg top)" = "other-2" &&
+    test "$(cat other2.txt)" = "other 2" &&
+    stg delete --top
+'
+
+test_expect_success 'Pick range with --repo' '
+    stg pick --repo "$(pwd)/other" --noapply other-tag..master~1 &&
+    test "$(stg series --unapplied --noprefix | head -n 1)" = "other-4" &&
+    stg delete other-4 &&
+    stg pick --repo other master~3..master &&
+    test "$(echo $(stg series --applied --noprefix | tail -n 3))" = "other-3 other-4 other-5" &&
+    test "$(cat other5.txt)" = "other 5" &&
+    stg delete other-3..other-5
+'
+
+test_expect_success 'Pick unknown revision with --repo' '
+    command_error stg pick --repo other no-such-ref 2>err &&
+    grep "fetching .no-such-ref. from .other." err &&
+    command_error stg pick --repo other other-tag..other-tag 2>err &&
+    grep "no commits in range .other-tag..other-tag." err
+'
+
 test_expect_success 'Attempt pick with auto-initialized stack' '
 git checkout -b bar &&
 stg pick -B master A &&
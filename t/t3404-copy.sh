@@ -0,0 +1,63 @@
+#!/bin/sh
+
+test_description='Test stg copy'
+
+. ./test-lib.sh
+
+test_expect_success 'Initialize repo and patches' '
+    test_commit_bulk --message="initial %s" 1 &&
+    stg init &&
+    echo a >a.txt && stg add a.txt && stg new -m "add a" p1 && stg refresh &&
+    echo b >b.txt && stg add b.txt && stg new -m "add b" p2 && stg refresh &&
+    stg pop
+'
+
+test_expect_success 'Copy applied patch with default name' '
+    stg copy p1 &&
+    test "$(echo $(stg series --applied --noprefix))" = "p1" &&
+    test "$(echo $(stg series --unapplied --noprefix))" = "p1-copy p2" &&
+    stg show p1 >expected-show &&
+    stg show p1-copy >copy-show &&
+    grep "^+a\$" copy-show &&
+    test "$(stg id p1-copy^)" = "$(stg id p1^)" &&
+    test "$(git log -1 --format=%B $(stg id p1-copy))" = "add a"
+'
+
+test_expect_success 'Copy gets a distinct uuid' '
+    stg series --uuid --noprefix >out &&
+    uuid_p1=$(grep " p1\$" out | cut -d" " -f1) &&
+    uuid_copy=$(grep " p1-copy\$" out | cut -d" " -f1) &&
+    test -n "$uuid_copy" &&
+    test "$uuid_p1" != "$uuid_copy"
+'
+
+test_expect_success 'Copy unapplied patch with explicit name' '
+    stg copy p2 p2-backport &&
+    test "$(echo $(stg series --unapplied --noprefix))" = "p2-backport p1-copy p2" &&
+    stg push p2-backport &&
+    test_path_is_file b.txt &&
+    stg pop
+'
+
+test_expect_success 'Default name is uniquified' '
+    stg copy p1 &&
+    stg series --unapplied --noprefix >out &&
+    grep "^p1-copy-1\$" out
+'
+
+test_expect_success 'Copy to existing name' '
+    command_error stg copy p1 p2 2>err &&
+    grep "patch .p2. already exists" err
+'
+
+test_expect_success 'Copy nonexistent patch' '
+    command_error stg copy no-such-patch 2>err &&
+    grep "patch .no-such-patch. does not exist" err
+'
+
+test_expect_success 'Copy is undoable' '
+    stg undo &&
+    ! stg series --unapplied --noprefix | grep "^p1-copy-1\$"
+'
+
+test_done
@@ -0,0 +1,72 @@
+#!/bin/sh
+
+test_description='Test stg backport'
+
+. ./test-lib.sh
+
+test_expect_success 'Initialize branches' '
+    test_seq 1 20 >file &&
+    git add file &&
+    git commit -m "initial" &&
+    git branch release &&
+    stg init &&
+    sed -e "s/^2\$/two/" file >file.tmp && mv file.tmp file &&
+    stg new -m "change two" fix-two && stg refresh &&
+    echo twenty-one >>file &&
+    stg new -m "add twenty-one" feature && stg refresh &&
+    sed -e "s/^9\$/nine/" file >file.tmp && mv file.tmp file &&
+    stg new -m "change nine" fix-nine && stg refresh &&
+    stg branch release &&
+    sed -e "s/^20\$/twenty/" file >file.tmp && mv file.tmp file &&
+    git commit -a -m "release change" &&
+    stg init &&
+    git branch plain &&
+    stg branch master
+'
+
+test_expect_success 'Backport requires an initialized target' '
+    command_error stg backport --to plain fix-two 2>err &&
+    grep "StGit stack not initialized for branch .plain." err
+'
+
+test_expect_success 'Backport to the current branch' '
+    command_error stg backport --to master fix-two 2>err &&
+    grep "cannot backport to the current branch" err
+'
+
+test_expect_success 'Backport patches to another branch' '
+    stg backport --to release fix-two fix-nine &&
+    test "$(stg branch)" = "master" &&
+    test "$(echo $(stg series -b release --noprefix))" = "fix-two fix-nine" &&
+    git show release:file >release-file &&
+    grep "^two\$" release-file &&
+    grep "^nine\$" release-file &&
+    grep "^twenty\$" release-file &&
+    ! grep "^twenty-one\$" release-file &&
+    git log -1 --format=%B release >msg &&
+    grep "^Backported-from: $(stg id fix-nine) (fix-nine from master)\$" msg &&
+    git diff --quiet &&
+    git diff --cached --quiet
+'
+
+test_expect_success 'Backport uniquifies patch names' '
+    stg delete -b release fix-two fix-nine &&
+    stg backport --to release fix-two &&
+    stg backport --to release fix-two &&
+    test "$(echo $(stg series -b release --noprefix))" = "fix-two fix-two-1"
+'
+
+test_expect_success 'Backport is atomic when a patch does not apply' '
+    stg delete -b release fix-two-1 &&
+    command_error stg backport --to release fix-nine feature 2>err &&
+    grep "patch .feature. does not apply cleanly to .release." err &&
+    test "$(echo $(stg series -b release --noprefix))" = "fix-two"
+'
+
+test_expect_success 'Backport to protected branch' '
+    stg branch --protect release &&
+    command_error stg backport --to release fix-nine 2>err &&
+    grep "backport not permitted: branch .release. is protected" err
+'
+
+test_done
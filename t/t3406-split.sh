@@ -0,0 +1,108 @@
+#!/bin/sh
+
+test_description='Test stg split'
+
+. ./test-lib.sh
+
+test_expect_success 'Initialize repo and patches' '
+    test_seq 1 20 >a.txt &&
+    echo b >b.txt &&
+    echo c >c.txt &&
+    git add a.txt b.txt c.txt &&
+    git commit -m "initial" &&
+    stg init &&
+    sed -e "s/^1\$/one/" -e "s/^20\$/twenty/" a.txt >a.tmp && mv a.tmp a.txt &&
+    echo bb >>b.txt &&
+    echo cc >>c.txt &&
+    stg new -m "$(printf "Change files\n\nSigned-off-by: A Tester <a@example.com>")" p1 &&
+    stg refresh &&
+    echo d >d.txt &&
+    stg add d.txt &&
+    stg new -m p2 p2 &&
+    stg refresh
+'
+
+test_expect_success 'Split by path' '
+    stg split --path b.txt --path c.txt p1 &&
+    test "$(echo $(stg series --noprefix))" = "p1-1 p1-2 p1 p2" &&
+    test "$(echo $(stg files --bare p1-1))" = "b.txt" &&
+    test "$(echo $(stg files --bare p1-2))" = "c.txt" &&
+    test "$(echo $(stg files --bare p1))" = "a.txt" &&
+    test "$(echo $(stg files --bare p2))" = "d.txt" &&
+    git diff --quiet HEAD &&
+    git cat-file -p $(stg id p1-1) >commit &&
+    grep "^Signed-off-by: A Tester <a@example.com>\$" commit &&
+    test "$(git log -1 --format=%an%ae $(stg id p1-2))" = \
+         "$(git log -1 --format=%an%ae $(stg id p1))" &&
+    stg undo
+'
+
+test_expect_success 'Split unapplied patch' '
+    stg pop -a &&
+    stg split --path c.txt p1 &&
+    test "$(echo $(stg series))" = "- p1-1 - p1 - p2" &&
+    stg push -a &&
+    test "$(echo $(stg files --bare p1))" = "a.txt b.txt" &&
+    stg undo && stg undo &&
+    stg push -a
+'
+
+test_expect_success 'Split with nothing left or no matching changes' '
+    command_error stg split --path "*.txt" p1 2>err &&
+    grep "all changes selected; nothing would remain in \`p1\`" err &&
+    command_error stg split --path d.txt p1 2>err &&
+    grep "no changes to \`d.txt\` in patch \`p1\`" err &&
+    test "$(echo $(stg series --noprefix))" = "p1 p2"
+'
+
+test_expect_success 'Split by editing the diff' '
+    write_script fake-editor <<-\EOF &&
+	sed -i -e "/^diff --git a\/b.txt/,\$d" "$1"
+	EOF
+    test_set_editor "$(pwd)/fake-editor" &&
+    test_when_finished test_set_editor false &&
+    stg split p1 &&
+    test "$(echo $(stg series --noprefix))" = "p1-1 p1 p2" &&
+    test "$(echo $(stg files --bare p1-1))" = "a.txt" &&
+    test "$(echo $(stg files --bare p1))" = "b.txt c.txt" &&
+    git diff --quiet HEAD &&
+    stg undo
+'
+
+test_expect_success 'Split aborted when all changes are removed' '
+    write_script fake-editor <<-\EOF &&
+	sed -i -e "/^diff --git/,\$d" "$1"
+	EOF
+    test_set_editor "$(pwd)/fake-editor" &&
+    test_when_finished test_set_editor false &&
+    command_error stg split --edit p1 2>err &&
+    grep "no changes selected; split aborted" err &&
+    test "$(echo $(stg series --noprefix))" = "p1 p2"
+'
+
+test_expect_success 'Split hunks interactively' '
+    command_error stg split --interactive p1 2>err &&
+    grep "requires \`p1\` to be the topmost applied patch" err &&
+    stg pop p2 &&
+    printf "y\nn\nn\nn\n" | stg split --interactive &&
+    test "$(echo $(stg series --noprefix))" = "p1-1 p1 p2" &&
+    stg show p1-1 >out &&
+    grep "^+one\$" out &&
+    ! grep "^+twenty\$" out &&
+    stg show p1 >out &&
+    grep "^+twenty\$" out &&
+    ! grep "^+one\$" out &&
+    git diff --quiet HEAD
+'
+
+test_expect_success 'Split hidden or empty patch' '
+    stg new -m empty &&
+    command_error stg split 2>err &&
+    grep "patch \`empty\` is empty; nothing to split" err &&
+    stg pop &&
+    stg hide empty &&
+    command_error stg split empty 2>err &&
+    grep "cannot split hidden patch \`empty\`" err
+'
+
+test_done
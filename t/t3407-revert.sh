@@ -0,0 +1,66 @@
+#!/bin/sh
+
+test_description='Test stg revert'
+
+. ./test-lib.sh
+
+test_expect_success 'Initialize repo and patches' '
+    echo a >a.txt &&
+    echo b >b.txt &&
+    echo c >c.txt &&
+    git add a.txt b.txt c.txt &&
+    git commit -m "initial" &&
+    stg init &&
+    echo aa >>a.txt &&
+    echo bb >>b.txt &&
+    stg new -m p0 p0 &&
+    stg refresh &&
+    echo cc >>c.txt &&
+    stg new -m p1 p1 &&
+    stg refresh &&
+    echo bbb >>b.txt &&
+    stg new -m p2 p2 &&
+    stg refresh &&
+    stg pop p2
+'
+
+test_expect_success 'Paths are required' '
+    general_error stg revert p0 &&
+    general_error stg revert p0 a.txt
+'
+
+test_expect_success 'No changes to given paths' '
+    command_error stg revert p0 -- c.txt 2>err &&
+    grep -e "no changes in \`p0\` match the given paths" err
+'
+
+test_expect_success 'Revert file in patch below the top' '
+    stg revert p0 -- a.txt &&
+    test "$(echo $(stg series --noprefix --applied))" = "p0 p1" &&
+    test "$(echo $(stg files --bare p0))" = "b.txt" &&
+    test "$(echo $(stg files --bare p1))" = "c.txt" &&
+    test "$(cat a.txt)" = "a" &&
+    test "$(echo $(cat b.txt))" = "b bb" &&
+    test "$(echo $(cat c.txt))" = "c cc" &&
+    test "$(git log -1 --format=%s $(stg id p0))" = "p0" &&
+    git diff --quiet HEAD
+'
+
+test_expect_success 'Revert file in unapplied patch' '
+    stg revert p2 -- b.txt &&
+    test "$(echo $(stg series --noprefix --unapplied))" = "p2" &&
+    test "$(git rev-parse $(stg id p2)^{tree})" = \
+         "$(git rev-parse $(stg id p2)^^{tree})" &&
+    stg undo
+'
+
+test_expect_success 'Revert changes that later patches depend on' '
+    stg push p2 &&
+    conflict stg revert p0 -- b.txt &&
+    test "$(echo $(stg series --noprefix --applied))" = "p0 p1 p2" &&
+    test "$(echo $(stg files --bare p0))" = "" &&
+    stg undo --hard &&
+    test "$(echo $(stg series --noprefix --applied))" = "p0 p1 p2"
+'
+
+test_done
@@ -38,4 +38,50 @@ test_expect_success 'Unhide hidden patch' '
     stg unhide p2
 '
 
+test_expect_success 'No expiry warning by default' '
+    stg hide p2 &&
+    stg series --all 2>err &&
+    test_must_be_empty err &&
+    stg clean --expired-hidden 2>err &&
+    grep -e "hidden patches never expire" err
+'
+
+test_expect_success 'Series does not check expiry' '
+    test_config stgit.hidden.expire 30 &&
+    stg series --all 2>err &&
+    test_must_be_empty err
+'
+
+test_expect_success 'Warn about expired hidden patch' '
+    test_config stgit.hidden.expire 30 &&
+    stg clean --expired-hidden 2>err &&
+    grep -e "hidden patch \`p2\` expired" err &&
+    test "$(echo $(stg series --hidden --noprefix))" = "p2"
+'
+
+test_expect_success 'Recently hidden patch is not expired' '
+    test_config stgit.hidden.expire 30 &&
+    stg unhide p2 &&
+    GIT_COMMITTER_DATE="$(date +%s) +0000" stg hide p2 &&
+    stg clean --expired-hidden 2>err &&
+    test_must_be_empty err
+'
+
+test_expect_success 'Invalid expire action' '
+    test_config stgit.hidden.expire 1 &&
+    test_config stgit.hidden.expire-action bogus &&
+    command_error stg clean --expired-hidden 2>err &&
+    grep -e "invalid \`stgit.hidden.expire-action\` value \`bogus\`" err
+'
+
+test_expect_success 'Prompt action falls back to warning without terminal' '
+    test_config stgit.hidden.expire 1 &&
+    test_config stgit.hidden.expire-action prompt &&
+    stg unhide p2 &&
+    stg hide p2 &&
+    stg clean --expired-hidden </dev/null 2>err &&
+    grep -e "hidden patch \`p2\` expired" err &&
+    test "$(echo $(stg series --hidden --noprefix))" = "p2"
+'
+
 test_done
@@ -112,4 +112,65 @@ test_expect_success 'Fold with base' '
     stg status --porcelain foo.txt | grep -e "M  foo.txt"
 '
 
+test_expect_success 'Attempt to fold with --apply-3way and incompatible options' '
+    general_error stg fold --apply-3way --reject fold1.diff &&
+    general_error stg fold --apply-3way --threeway fold1.diff &&
+    general_error stg fold --apply-3way --base p1 fold1.diff &&
+    general_error stg fold -t -3 fold1.diff &&
+    general_error stg fold -3 -b p1 fold1.diff
+'
+
+test_expect_success 'Setup for 3-way fold' '
+    stg reset --hard &&
+    test_seq 1 10 >seq.txt &&
+    stg add seq.txt &&
+    stg new -m p3 &&
+    stg refresh &&
+    sed -e "s/^5\$/five/" seq.txt >seq.tmp &&
+    mv seq.tmp seq.txt &&
+    git diff --full-index seq.txt >seq.diff &&
+    git checkout seq.txt &&
+    sed -e "s/^3\$/three/" -e "s/^7\$/seven/" seq.txt >seq.tmp &&
+    mv seq.tmp seq.txt &&
+    stg refresh
+'
+
+test_expect_success 'Attempt to fold without --apply-3way' '
+    command_error stg fold seq.diff 2>err &&
+    grep "patch does not apply" err
+'
+
+test_expect_success 'Fold with --apply-3way' '
+    stg fold --apply-3way seq.diff &&
+    test "$(echo $(sed -n -e "3,7p" seq.txt))" = "three 4 five 6 seven" &&
+    stg status --porcelain seq.txt | grep -e "M  seq.txt" &&
+    stg reset --hard
+'
+
+test_expect_success 'Fold with --apply-3way and conflicts' '
+    sed -e "s/^5\$/FIVE/" seq.txt >seq.tmp &&
+    mv seq.tmp seq.txt &&
+    stg refresh &&
+    conflict stg fold --apply-3way seq.diff 2>err &&
+    grep "merge conflicts" err &&
+    stg status --porcelain seq.txt | grep -e "UU seq.txt" &&
+    grep -e "^<<<<<<<" seq.txt &&
+    stg reset --hard
+'
+
+test_expect_success 'Fold binary diff without binary data' '
+    printf "\000\001\002" >foo.bin &&
+    stg add foo.bin &&
+    stg refresh &&
+    printf "\003\004" >>foo.bin &&
+    cp foo.bin foo.bin.new &&
+    git diff >bin.diff &&
+    grep -e "Binary files a/foo.bin and b/foo.bin differ" bin.diff &&
+    git hash-object -w foo.bin >/dev/null &&
+    git checkout foo.bin &&
+    stg fold bin.diff &&
+    test_cmp_bin foo.bin.new foo.bin &&
+    stg reset --hard
+'
+
 test_done
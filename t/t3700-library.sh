@@ -0,0 +1,81 @@
+#!/bin/sh
+
+test_description='Test the shared patch library'
+
+. ./test-lib.sh
+
+test_expect_success 'Initialize repo with patches' '
+    test_commit_bulk --start=0 --filename=file%s.txt --contents="base %s" 2 &&
+    git branch other &&
+    stg init &&
+    echo "debug = true" >debug.cfg &&
+    git add debug.cfg &&
+    stg new -m "Enable debugging" debug-toggle &&
+    stg refresh --index &&
+    echo tweak >>file0.txt &&
+    stg new -m "Tweak build" build-tweak &&
+    stg refresh
+'
+
+test_expect_success 'Empty library' '
+    stg library list >list.txt &&
+    test_must_be_empty list.txt
+'
+
+test_expect_success 'Add patches to library' '
+    stg library add debug-toggle &&
+    stg library add --name tweak build-tweak &&
+    test "$(git rev-parse refs/library/debug-toggle)" = "$(stg id debug-toggle)" &&
+    test "$(git rev-parse refs/library/tweak)" = "$(stg id build-tweak)" &&
+    stg library list >list.txt &&
+    cat >expected.txt <<-\EOF &&
+	debug-toggle # Enable debugging
+	tweak        # Tweak build
+	EOF
+    test_cmp expected.txt list.txt &&
+    stg library list --bare >list-bare.txt &&
+    printf "debug-toggle\ntweak\n" >expected-bare.txt &&
+    test_cmp expected-bare.txt list-bare.txt
+'
+
+test_expect_success 'Refuse to replace existing entry' '
+    command_error stg library add --name tweak debug-toggle 2>err &&
+    grep "library entry \`tweak\` already exists" err &&
+    stg library add --force --name tweak debug-toggle &&
+    test "$(git rev-parse refs/library/tweak)" = "$(stg id debug-toggle)" &&
+    stg library add --force --name tweak build-tweak
+'
+
+test_expect_success 'Apply library entries on another stack' '
+    stg branch other &&
+    stg library apply debug-toggle &&
+    stg library apply --name my-tweak --noapply tweak &&
+    test "$(echo $(stg series --applied --noprefix))" = "debug-toggle" &&
+    test "$(echo $(stg series --unapplied --noprefix))" = "my-tweak" &&
+    test "$(cat debug.cfg)" = "debug = true" &&
+    stg push my-tweak &&
+    test "$(tail -1 file0.txt)" = "tweak"
+'
+
+test_expect_success 'Apply uniquifies patch name' '
+    stg library apply --noapply debug-toggle &&
+    test "$(echo $(stg series --unapplied --noprefix))" = "debug-toggle-1" &&
+    command_error stg library apply --name my-tweak tweak 2>err &&
+    grep "patch name \`my-tweak\` already taken" err
+'
+
+test_expect_success 'Apply nonexistent entry' '
+    command_error stg library apply no-such-entry 2>err &&
+    grep "library entry \`no-such-entry\` does not exist" err
+'
+
+test_expect_success 'Delete library entries' '
+    command_error stg library delete tweak no-such-entry &&
+    test "$(stg library list --bare | wc -l)" = "2" &&
+    stg library delete tweak debug-toggle &&
+    stg library list >list.txt &&
+    test_must_be_empty list.txt &&
+    test "$(echo $(stg series --noprefix))" = "debug-toggle my-tweak debug-toggle-1"
+'
+
+test_done
@@ -0,0 +1,115 @@
+#!/bin/sh
+
+test_description='Test stg meta patch metadata'
+
+. ./test-lib.sh
+
+test_expect_success 'Initialize repo with patches' '
+    test_commit_bulk --message="base %s" 1 &&
+    stg init &&
+    for i in 1 2 3; do
+        echo "$i" >"file$i.txt" &&
+        stg add "file$i.txt" &&
+        stg new -m "p$i" &&
+        stg refresh || return 1
+    done
+'
+
+test_expect_success 'Set and get metadata' '
+    stg meta set p1 issue ABC-123 &&
+    stg meta set p1 review.url https://example.com/r/1 &&
+    test "$(stg meta get p1 issue)" = "ABC-123" &&
+    test "$(stg meta get p1 review.url)" = "https://example.com/r/1"
+'
+
+test_expect_success 'New patches have a UUID' '
+    stg meta get p1 uuid >uuid &&
+    grep -E "^[0-9a-f]{8}-[0-9a-f]{4}-4[0-9a-f]{3}-[89ab][0-9a-f]{3}-[0-9a-f]{12}\$" uuid &&
+    test "$(stg meta get p2 uuid)" != "$(cat uuid)"
+'
+
+test_expect_success 'List metadata' '
+    stg meta list p1 >out &&
+    cat >expected <<-EOF &&
+	issue=ABC-123
+	review.url=https://example.com/r/1
+	uuid=$(cat uuid)
+	EOF
+    test_cmp expected out &&
+    stg meta list --keys p1 >out &&
+    printf "issue\nreview.url\nuuid\n" >expected &&
+    test_cmp expected out &&
+    stg meta list p2 >out &&
+    echo "uuid=$(stg meta get p2 uuid)" >expected &&
+    test_cmp expected out
+'
+
+test_expect_success 'Replace metadata value' '
+    stg meta set p1 issue ABC-456 &&
+    test "$(stg meta get p1 issue)" = "ABC-456"
+'
+
+test_expect_success 'Get missing metadata' '
+    command_error stg meta get p2 issue 2>err &&
+    grep "patch \`p2\` does not have metadata key \`issue\`" err
+'
+
+test_expect_success 'Invalid metadata key' '
+    general_error stg meta set p1 "bad key" value 2>err &&
+    grep "invalid character" err
+'
+
+test_expect_success 'Metadata persists across refresh' '
+    stg goto p1 &&
+    echo more >>file1.txt &&
+    stg refresh &&
+    test "$(stg meta get p1 issue)" = "ABC-456"
+'
+
+test_expect_success 'Metadata persists across push and pop' '
+    stg goto p3 &&
+    stg float p1 &&
+    test "$(stg top)" = "p1" &&
+    test "$(stg meta get p1 issue)" = "ABC-456" &&
+    stg pop -a &&
+    test "$(stg meta get p1 issue)" = "ABC-456" &&
+    stg push -a
+'
+
+test_expect_success 'Metadata persists across rename' '
+    stg rename p1 renamed &&
+    test "$(stg meta get renamed issue)" = "ABC-456" &&
+    test "$(stg meta get renamed uuid)" = "$(cat uuid)" &&
+    command_error stg meta get p1 issue
+'
+
+test_expect_success 'Series shows UUIDs' '
+    stg series --uuid --noprefix renamed >out &&
+    echo "$(cat uuid) renamed" >expected &&
+    test_cmp expected out
+'
+
+test_expect_success 'Metadata persists across edit' '
+    stg edit -m "new message" renamed &&
+    test "$(stg meta get renamed issue)" = "ABC-456"
+'
+
+test_expect_success 'Unset metadata' '
+    stg meta unset renamed issue &&
+    command_error stg meta get renamed issue &&
+    command_error stg meta unset renamed issue &&
+    test "$(stg meta list --keys renamed)" = "$(printf "review.url\nuuid")"
+'
+
+test_expect_success 'Metadata restored by undo' '
+    stg undo &&
+    test "$(stg meta get renamed issue)" = "ABC-456"
+'
+
+test_expect_success 'Metadata for other branch' '
+    stg branch --create other &&
+    test "$(stg meta get -b master renamed issue)" = "ABC-456" &&
+    stg branch master
+'
+
+test_done
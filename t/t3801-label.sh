@@ -0,0 +1,122 @@
+#!/bin/sh
+
+test_description='Test stg label and label-based selection'
+
+. ./test-lib.sh
+
+test_expect_success 'Initialize repo with patches' '
+    test_commit_bulk --message="base %s" 1 &&
+    stg init &&
+    for i in 1 2 3 4; do
+        echo "$i" >"file$i.txt" &&
+        stg add "file$i.txt" &&
+        stg new -m "p$i" &&
+        stg refresh || return 1
+    done
+'
+
+test_expect_success 'Add labels' '
+    stg label add fixup p1 p3 &&
+    stg label add experimental p2..p3 &&
+    stg label add fixup p1 &&
+    stg label list p3 >out &&
+    printf "experimental\nfixup\n" >expected &&
+    test_cmp expected out &&
+    stg label list p4 >out &&
+    test_must_be_empty out
+'
+
+test_expect_success 'List all labels' '
+    stg label list >out &&
+    cat >expected <<-\EOF &&
+	experimental: p2 p3
+	fixup: p1 p3
+	EOF
+    test_cmp expected out
+'
+
+test_expect_success 'Invalid label' '
+    general_error stg label add "bad label" p1 2>err &&
+    grep "invalid character" err
+'
+
+test_expect_success 'Series by label' '
+    stg series --label fixup >out &&
+    cat >expected <<-\EOF &&
+	+ p1
+	+ p3
+	EOF
+    test_cmp expected out &&
+    stg series --noprefix --label nonexistent >out &&
+    test_must_be_empty out
+'
+
+test_expect_success 'Pop by label' '
+    stg pop --label experimental &&
+    test "$(echo $(stg series --applied --noprefix))" = "p1 p4" &&
+    command_error stg pop --label experimental 2>err &&
+    grep "no applied patches labeled \`experimental\`" err
+'
+
+test_expect_success 'Push by label' '
+    stg push --label experimental &&
+    test "$(echo $(stg series --noprefix))" = "p1 p4 p2 p3" &&
+    stg pop p4 &&
+    command_error stg push --label experimental 2>err &&
+    grep "no unapplied patches labeled \`experimental\`" err &&
+    general_error stg push --label experimental p4 &&
+    stg push p4
+'
+
+test_expect_success 'Export by label' '
+    stg pop p3 &&
+    stg export --label fixup --dir export &&
+    printf "p1\np3 # unapplied\n" >expected &&
+    grep -v "^#" export/series >out &&
+    test_cmp expected out &&
+    stg push p3
+'
+
+test_expect_success 'Labels persist across refresh and rename' '
+    stg goto p1 &&
+    echo more >>file1.txt &&
+    stg refresh &&
+    stg rename p1 renamed &&
+    test "$(stg label list renamed)" = "fixup" &&
+    stg goto p3
+'
+
+test_expect_success 'Remove labels' '
+    stg label remove fixup renamed p4 &&
+    stg label list >out &&
+    cat >expected <<-\EOF &&
+	experimental: p2 p3
+	fixup: p3
+	EOF
+    test_cmp expected out
+'
+
+test_expect_success 'Labels restored by undo' '
+    stg undo &&
+    test "$(stg label list renamed)" = "fixup"
+'
+
+test_expect_success 'Label patch range' '
+    command_error stg series label:experimental 2>err &&
+    grep "not contiguous" err &&
+    stg label add experimental p4 &&
+    stg series --noprefix label:experimental >out &&
+    printf "p2\np4\np3\n" >expected &&
+    test_cmp expected out &&
+    stg label remove experimental p4 &&
+    stg pop label:fixup &&
+    test "$(echo $(stg series --applied --noprefix))" = "p2 p4" &&
+    stg push label:fixup &&
+    test "$(echo $(stg series --applied --noprefix))" = "p2 p4 renamed p3" &&
+    command_error stg push label:nonexistent 2>err &&
+    grep "no patches labeled \`nonexistent\`" err &&
+    command_error stg pop label:fixup p3 2>err &&
+    grep "patch \`p3\` is used more than once" err
+'
+
+test_done
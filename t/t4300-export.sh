@@ -87,4 +87,95 @@ test_expect_success 'Export series with empty patch' '
     stg import -S export6/series
 '
 
+test_expect_success 'Export with range-diff against tag' '
+    git tag v1 &&
+    git tag v1-state $(git rev-parse refs/stacks/master) &&
+    stg edit -m "patch-2 reworded" patch-2 &&
+    stg export -d export7 --range-diff v1 &&
+    test_path_is_file export7/range-diff.txt &&
+    test_line_count = 7 export7/series &&
+    grep -e "^2: .* ! 2: .* patch-2\$" export7/range-diff.txt &&
+    grep -e "^ *+ *patch-2 reworded\$" export7/range-diff.txt &&
+    grep -c -e "^[0-9]: .* = [0-9]: " export7/range-diff.txt >count &&
+    echo 5 >expected &&
+    test_cmp expected count
+'
+
+test_expect_success 'Export with range-diff against stack state' '
+    stg export -d export8 --range-diff v1-state patch-1..patch-3 &&
+    grep -e "^2: .* ! 2: .* patch-2\$" export8/range-diff.txt &&
+    grep -e "^4: .* < -: " export8/range-diff.txt
+'
+
+test_expect_success 'Export with range-diff of non-consecutive patches' '
+    command_error stg export -d export9 --range-diff v1 patch-1 patch-3 2>err &&
+    grep -e "consecutive applied patches" err &&
+    test_path_is_missing export9
+'
+
+test_expect_success 'Export with range-diff to stdout' '
+    general_error stg export --stdout --range-diff v1 2>err &&
+    grep -e "cannot be used with" err
+'
+
+test_expect_success 'Export all patches including unapplied and hidden' '
+    stg pop patch-5 patch-6 &&
+    stg hide patch-6 &&
+    stg export -d export10 --all &&
+    cat >expected <<-\EOF &&
+	patch-1
+	patch-2
+	patch-3
+	patch-4
+	patch-5 # unapplied
+	patch-6 # hidden
+	EOF
+    tail -n +2 export10/series >series &&
+    test_cmp expected series &&
+    general_error stg export --all patch-1 &&
+    stg export -d export11 patch-5 &&
+    grep -e "^patch-5 # unapplied\$" export11/series
+'
+
+test_expect_success 'Reimport export with unapplied and hidden patches' '
+    stg delete $(stg series --all --noprefix) &&
+    stg import -S export10/series &&
+    test "$(echo $(stg series -A --noprefix))" = "patch-1 patch-2 patch-3 patch-4" &&
+    test "$(echo $(stg series -U --noprefix))" = "patch-5" &&
+    test "$(echo $(stg series -H --noprefix))" = "patch-6"
+'
+
+test_expect_success 'Export with rename detection' '
+    test_seq 1 20 >rename-src.txt &&
+    stg add rename-src.txt &&
+    stg new -rm add-rename-src &&
+    git mv rename-src.txt rename-dst.txt &&
+    stg new -rm rename &&
+    stg export --stdout rename >out &&
+    ! grep -e "^rename from" out &&
+    stg export --stdout -M rename >out &&
+    grep -e "^rename from rename-src.txt" out &&
+    test_config diff.renames true &&
+    stg export --stdout rename >out &&
+    grep -e "^rename from rename-src.txt" out &&
+    test_config diff.renames copies &&
+    stg export --stdout rename >out &&
+    grep -e "^rename from rename-src.txt" out &&
+    stg export --stdout --diff-opt=--no-renames rename >out &&
+    ! grep -e "^rename from" out
+'
+
+test_expect_success 'Export with subject prefix' '
+    stg export --stdout rename >out &&
+    head -n 1 out >subject &&
+    ! grep -e "^\[" subject &&
+    stg export --stdout --rfc --reroll-count=3 rename >out &&
+    head -n 1 out >subject &&
+    grep -e "^\[RFC PATCH v3\] rename\$" subject &&
+    test_config stgit.mail.prefix "PATCH net-next" &&
+    stg export --stdout --resend rename >out &&
+    head -n 1 out >subject &&
+    grep -e "^\[RESEND PATCH net-next\] rename\$" subject
+'
+
 test_done
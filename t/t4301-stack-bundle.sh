@@ -0,0 +1,110 @@
+#!/bin/sh
+
+test_description='Test stack bundle export and import'
+
+. ./test-lib.sh
+
+test_expect_success 'Initialize repo with patches' '
+    echo base >file0.t &&
+    git add file0.t &&
+    git commit -m base &&
+    git clone . clone &&
+    echo "*.json" >>.git/info/exclude &&
+    echo "*.diff" >>.git/info/exclude &&
+    echo clone >>.git/info/exclude &&
+    stg init &&
+    stg new -m "p0 subject" p0 &&
+    echo p0 >>file0.t &&
+    printf "\000\001\002" >bin0 &&
+    git add bin0 file0.t &&
+    stg refresh --index &&
+    stg new -m p1 p1 &&
+    echo p1 >>file0.t &&
+    stg refresh &&
+    stg new -m p2 p2 &&
+    echo p2 >p2.txt &&
+    git add p2.txt &&
+    stg refresh --index &&
+    stg new -m p3 p3 &&
+    echo p3 >>file0.t &&
+    stg refresh &&
+    stg pop p2 p3 &&
+    stg hide p3 &&
+    stg new -m "empty patch" p4 &&
+    stg pop p4
+'
+
+test_expect_success 'Export stack bundle' '
+    stg stack export >bundle.json &&
+    stg stack export -o bundle2.json &&
+    test_cmp bundle.json bundle2.json &&
+    grep "\"version\": 1" bundle.json &&
+    grep "\"name\": \"p0\"" bundle.json &&
+    grep "\"state\": \"hidden\"" bundle.json
+'
+
+test_expect_success 'Import stack bundle into clone' '
+    (
+        cd clone &&
+        stg stack import ../bundle.json &&
+        test "$(echo $(stg series --applied --noprefix))" = "p0 p1" &&
+        test "$(echo $(stg series --unapplied --noprefix))" = "p4 p2" &&
+        test "$(echo $(stg series --hidden --noprefix))" = "p3" &&
+        test "$(git log -1 --format=%s)" = "p1" &&
+        test "$(git log -1 --format=%an%ae%ad)" = "$(cd .. && git log -1 --format=%an%ae%ad $(stg id p1))" &&
+        test_cmp bin0 ../bin0 &&
+        test_cmp file0.t ../file0.t &&
+        stg show p0 >../p0-clone.diff
+    ) &&
+    stg show p0 >p0.diff &&
+    test_cmp p0.diff p0-clone.diff
+'
+
+test_expect_success 'Export from clone matches original bundle' '
+    (cd clone && stg stack export) >clone-bundle.json &&
+    test_cmp bundle.json clone-bundle.json
+'
+
+test_expect_success 'Import refuses colliding patch names' '
+    (
+        cd clone &&
+        command_error stg stack import ../bundle.json 2>err &&
+        grep "collides with existing patch \`p0\`" err
+    )
+'
+
+test_expect_success 'Import from stdin' '
+    (
+        cd clone &&
+        stg branch --create from-stdin origin/master &&
+        stg stack import - <../bundle.json &&
+        test "$(echo $(stg series --noprefix --all))" = "p0 p1 p4 p2 p3"
+    )
+'
+
+test_expect_success 'Import refuses newer bundle versions' '
+    sed -e "s/\"version\": 1/\"version\": 99/" bundle.json >bundle99.json &&
+    (
+        cd clone &&
+        stg branch --create newer origin/master &&
+        command_error stg stack import ../bundle99.json 2>err &&
+        grep "unsupported stack bundle version 99" err
+    )
+'
+
+test_expect_success 'Export and import diff that is not valid UTF-8' '
+    stg branch --create latin1 master &&
+    stg new -m latin1 latin1 &&
+    printf "caf\351\n" >latin1.t &&
+    git add latin1.t &&
+    stg refresh --index &&
+    stg stack export >latin1.json &&
+    (
+        cd clone &&
+        stg branch --create latin1 origin/master &&
+        stg stack import ../latin1.json &&
+        test_cmp latin1.t ../latin1.t
+    )
+'
+
+test_done
@@ -0,0 +1,117 @@
+#!/bin/sh
+
+test_description='Test stg publish'
+
+. ./test-lib.sh
+
+test_expect_success 'Initialize repo with patches' '
+    test_commit_bulk --message="base %s" 2 &&
+    stg init &&
+    echo p1 >p1.txt &&
+    stg add p1.txt &&
+    stg new -m p1 &&
+    stg refresh &&
+    echo p2 >p2.txt &&
+    stg add p2.txt &&
+    stg new -m p2 &&
+    stg refresh
+'
+
+test_expect_success 'Publish creates public branch' '
+    stg publish 2>err &&
+    grep "Created \`master.public\`" err &&
+    test "$(git rev-parse master.public)" = "$(git rev-parse HEAD)"
+'
+
+test_expect_success 'Publish when up to date' '
+    stg publish 2>err &&
+    grep "already up to date" err
+'
+
+test_expect_success 'Show last published patch' '
+    test "$(stg publish --last)" = "p2" &&
+    stg publish --unpublished >unpublished &&
+    test_must_be_empty unpublished
+'
+
+test_expect_success 'Publish new patch' '
+    old_public=$(git rev-parse master.public) &&
+    echo p3 >p3.txt &&
+    stg add p3.txt &&
+    stg new -m "p3 subject" &&
+    stg refresh &&
+    test "$(stg publish --unpublished)" = "p3-subject" &&
+    stg publish 2>err &&
+    grep "Published new patch \`p3-subject\`" err &&
+    test "$(git rev-parse master.public^)" = "$old_public" &&
+    test "$(git log -1 --format=%s master.public)" = "p3 subject" &&
+    test "$(git rev-parse master.public^{tree})" = "$(git rev-parse HEAD^{tree})"
+'
+
+test_expect_success 'Publish modified patch as update commit' '
+    old_public=$(git rev-parse master.public) &&
+    stg goto p1 &&
+    echo more >>p1.txt &&
+    stg refresh &&
+    stg goto p3-subject &&
+    test "$(stg publish --unpublished | tr "\n" " ")" = "p1 p2 p3-subject " &&
+    command_error stg publish --last &&
+    stg publish -m "update public" &&
+    test "$(git rev-parse master.public^)" = "$old_public" &&
+    test "$(git log -1 --format=%s master.public)" = "update public" &&
+    test "$(git rev-parse master.public^{tree})" = "$(git rev-parse HEAD^{tree})"
+'
+
+test_expect_success 'Update commit message from editor' '
+    stg goto p2 &&
+    echo more >>p2.txt &&
+    stg refresh &&
+    stg goto p3-subject &&
+    test_set_editor "$(pwd)/edit-message" &&
+    cat >edit-message <<-\EOF &&
+	#!/bin/sh
+	printf "edited message\n" >"$1"
+	EOF
+    chmod +x edit-message &&
+    stg publish &&
+    test "$(git log -1 --format=%s master.public)" = "edited message"
+'
+
+test_expect_success 'Publish rebased stack as merge' '
+    old_public=$(git rev-parse master.public) &&
+    stg pop -a &&
+    echo base >base.txt &&
+    git add base.txt &&
+    git commit -m "new base" &&
+    stg push -a &&
+    stg publish -m "merge base" &&
+    test "$(git rev-parse master.public^1)" = "$old_public" &&
+    test "$(git rev-parse master.public^2)" = "$(git rev-parse $(stg id {base}))" &&
+    test "$(git rev-parse master.public^{tree})" = "$(git rev-parse HEAD^{tree})"
+'
+
+test_expect_success 'Overwrite public branch' '
+    stg publish --overwrite 2>err &&
+    grep "Overwrote \`master.public\`" err &&
+    test "$(git rev-parse master.public)" = "$(git rev-parse HEAD)"
+'
+
+test_expect_success 'Public branch name from config' '
+    test_config branch.master.stgit.public pub &&
+    stg publish &&
+    test "$(git rev-parse pub)" = "$(git rev-parse HEAD)"
+'
+
+test_expect_success 'Fast-forward public branch' '
+    stg publish other-public &&
+    stg commit -a &&
+    echo p4 >p4.txt &&
+    stg add p4.txt &&
+    stg new -m p4 &&
+    stg refresh &&
+    stg publish other-public 2>err &&
+    grep "Fast-forwarded \`other-public\`" err &&
+    test "$(git rev-parse other-public)" = "$(git rev-parse HEAD)"
+'
+
+test_done
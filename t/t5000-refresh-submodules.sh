@@ -57,4 +57,57 @@ test_expect_success 'refresh with config' '
     [ "$(stg status)" = "" ]
 '
 
+test_expect_success 'setup nested submodule' '
+    test_create_repo nested &&
+    (
+        cd nested &&
+        test_commit n1
+    ) &&
+    (
+        cd submodules/foo &&
+        git -c protocol.file.allow=always submodule add "$(pwd)/../../nested" bar &&
+        git commit -m "nested submodule"
+    ) &&
+    stg refresh --submodules 2>err &&
+    test_must_be_empty err &&
+    test -z "$(git status --porcelain --untracked-files=no)"
+'
+
+test_expect_success 'refresh warns about new commit in nested submodule' '
+    (
+        cd submodules/foo/bar &&
+        test_commit n2
+    ) &&
+    stg refresh --submodules 2>err &&
+    grep "nested submodule .submodules/foo/bar. has a new commit that is not committed in .submodules/foo." err &&
+    [ "$(git status --porcelain --untracked-files=no)" = " M submodules/foo" ]
+'
+
+test_expect_success 'refresh records nested gitlink once committed in submodule' '
+    (
+        cd submodules/foo &&
+        git commit -a -m "update nested submodule"
+    ) &&
+    stg refresh --submodules 2>err &&
+    test_must_be_empty err &&
+    test -z "$(git status --porcelain --untracked-files=no)"
+'
+
+test_expect_success 'refresh warns about uncommitted submodule content' '
+    (
+        cd submodules/foo &&
+        echo change >>file1 &&
+        echo untracked >untracked
+    ) &&
+    (
+        cd submodules/foo/bar &&
+        echo untracked >untracked
+    ) &&
+    stg refresh --submodules 2>err &&
+    grep "submodule .submodules/foo. has uncommitted changes that cannot be recorded" err &&
+    grep "submodule .submodules/foo. has untracked files that cannot be recorded" err &&
+    grep "submodule .submodules/foo/bar. has untracked files that cannot be recorded" err &&
+    ! grep "nested submodule" err
+'
+
 test_done
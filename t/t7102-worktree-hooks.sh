@@ -51,4 +51,67 @@ test_expect_success 'Check that hook runs from subdir' '
     )
 '
 
+test_expect_success 'Check per-worktree core.hooksPath' '
+    (
+        cd linked &&
+        mkdir -p .wt-hooks &&
+        echo ".wt-hooks" >>"$(git rev-parse --git-path info/exclude)" &&
+        write_script .wt-hooks/pre-commit <<-\EOF &&
+	touch wt-hook-out
+	exit 0
+	EOF
+        git config --worktree core.hooksPath .wt-hooks &&
+        (
+            cd dir0 &&
+            echo "more" >>dir1/file.txt &&
+            stg new -rm p3
+        ) &&
+        test_path_is_file wt-hook-out &&
+        test_path_is_missing hook-out &&
+        rm wt-hook-out
+    ) &&
+    (
+        cd main &&
+        echo "update" >>2.t &&
+        stg new -rm p1 &&
+        test_path_is_file hook-out &&
+        test_path_is_missing wt-hook-out &&
+        rm hook-out
+    )
+'
+
+test_expect_success 'Check core.hooksPath from environment' '
+    (
+        cd main &&
+        mkdir -p ../env-hooks &&
+        write_script ../env-hooks/pre-commit <<-\EOF &&
+	touch env-hook-out
+	exit 0
+	EOF
+        echo "update" >>3.t &&
+        GIT_CONFIG_COUNT=1 \
+        GIT_CONFIG_KEY_0=core.hooksPath \
+        GIT_CONFIG_VALUE_0="$(cd .. && pwd)/env-hooks" \
+        stg new -rm p2 &&
+        test_path_is_file env-hook-out &&
+        test_path_is_missing hook-out &&
+        rm env-hook-out
+    )
+'
+
+test_expect_success 'Hint for non-executable hook' '
+    (
+        cd main &&
+        chmod -x "$(git rev-parse --git-path hooks/pre-commit)" &&
+        echo "update" >>1.t &&
+        stg new -rm p3 2>err &&
+        test_path_is_missing hook-out &&
+        grep -e "hint: The .* hook was ignored because it.s not set as executable" err &&
+        echo "update" >>1.t &&
+        git config advice.ignoredHook false &&
+        stg new -rm p4 2>err &&
+        test_must_be_empty err
+    )
+'
+
 test_done
@@ -0,0 +1,85 @@
+#!/bin/sh
+
+test_description='Test pre-stack-transaction and post-stack-transaction hooks'
+
+. ./test-lib.sh
+
+test_expect_success 'Initialize stack' '
+    test_commit_bulk --message="base %s" 1 &&
+    stg init &&
+    echo "hook-*.log" >>.git/info/exclude &&
+    stg new -m p1
+'
+
+test_expect_success 'Setup hooks' '
+    write_script "$(git rev-parse --git-path hooks/pre-stack-transaction)" <<-\EOF &&
+	echo "$STGIT_BRANCH $STGIT_OPERATION $STGIT_OLD_STATE $STGIT_NEW_STATE" >>hook-pre.log
+	if test -e p3.t; then echo "p3.t exists" >>hook-pre.log; fi
+	test ! -e reject-transaction
+	EOF
+    write_script "$(git rev-parse --git-path hooks/post-stack-transaction)" <<-\EOF
+	echo "$STGIT_BRANCH $STGIT_OPERATION $STGIT_OLD_STATE $STGIT_NEW_STATE" >>hook-post.log
+	echo "$(git rev-parse refs/stacks/$STGIT_BRANCH)" >>hook-post.log
+	exit 1
+	EOF
+'
+
+test_expect_success 'Hooks run with state ids and operation' '
+    old_state=$(git rev-parse refs/stacks/master) &&
+    stg new -m p2 &&
+    new_state=$(git rev-parse refs/stacks/master) &&
+    echo "master new: p2 $old_state $new_state" >expected &&
+    test_cmp expected hook-pre.log &&
+    echo "$new_state" >>expected &&
+    test_cmp expected hook-post.log &&
+    rm hook-pre.log hook-post.log
+'
+
+test_expect_success 'Hooks run from subdirectory' '
+    mkdir -p sub &&
+    (
+        cd sub &&
+        stg pop
+    ) &&
+    test_path_is_file hook-pre.log &&
+    test_path_is_file hook-post.log &&
+    grep "^master pop " hook-pre.log &&
+    rm hook-pre.log hook-post.log
+'
+
+test_expect_success 'Failing pre-stack-transaction hook aborts transaction' '
+    touch reject-transaction &&
+    old_state=$(git rev-parse refs/stacks/master) &&
+    command_error stg push 2>err &&
+    grep "\`pre-stack-transaction\` hook returned 1" err &&
+    grep "command aborted" err &&
+    test "$(git rev-parse refs/stacks/master)" = "$old_state" &&
+    test "$(echo $(stg series --applied --noprefix))" = "p1" &&
+    test_path_is_missing hook-post.log &&
+    rm reject-transaction hook-pre.log
+'
+
+test_expect_success 'Pre-stack-transaction hook runs before work tree is updated' '
+    echo p3 >p3.t &&
+    stg add p3.t &&
+    stg new -m p3 &&
+    stg refresh &&
+    stg pop p3 &&
+    rm hook-pre.log &&
+    touch reject-transaction &&
+    command_error stg push p3 &&
+    grep "^master push " hook-pre.log &&
+    ! grep "p3.t exists" hook-pre.log &&
+    test_path_is_missing p3.t &&
+    rm reject-transaction &&
+    stg delete p3 &&
+    rm hook-pre.log hook-post.log
+'
+
+test_expect_success 'Failing post-stack-transaction hook is ignored' '
+    stg push &&
+    test "$(echo $(stg series --applied --noprefix))" = "p1 p2" &&
+    test_path_is_file hook-post.log
+'
+
+test_done
@@ -1325,6 +1325,11 @@ esac
 
 test -z "$NO_PERL" && test_set_prereq PERL
 
+test_lazy_prereq SYMLINKS '
+	# test whether the filesystem supports symbolic links
+	ln -s x y && test -h y
+'
+
 test_lazy_prereq QUILT '
 	# test whether quilt is installed
 	quilt --version 2>/dev/null >/dev/null